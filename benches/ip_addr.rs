@@ -0,0 +1,50 @@
+#![feature(test)]
+
+extern crate test;
+
+use binator_network::concrete::{
+  ip_address,
+  ipv6_address,
+};
+use test::{
+  black_box,
+  Bencher,
+};
+
+const IPV6: &[&str] = &[
+  "::",
+  "::1",
+  "fe80::1",
+  "2001:db8::8a2e:370:7334",
+  "2001:db8:85a3:0:0:8a2e:370:7334",
+  "::ffff:192.0.2.128",
+  "1:2:3:4:5:6::",
+  "1:2:3:4:5:6:7:8",
+];
+
+#[bench]
+fn bench_ipv6_address(b: &mut Bencher) {
+  b.iter(|| {
+    for ipv6 in IPV6 {
+      let _ = black_box(ipv6_address(black_box(ipv6.as_bytes())));
+    }
+  });
+}
+
+#[bench]
+fn bench_ipv6_address_invalid(b: &mut Bencher) {
+  b.iter(|| {
+    for ipv6 in ["1:2:3:4:5:6:7", "1::2::3", "2001:db8:::1", "192.0.2.1"] {
+      let _ = black_box(ipv6_address(black_box(ipv6.as_bytes())));
+    }
+  });
+}
+
+#[bench]
+fn bench_ip_address_v4(b: &mut Bencher) {
+  b.iter(|| {
+    for ip in ["127.0.0.1", "192.0.2.128", "10.255.255.254"] {
+      let _ = black_box(ip_address(black_box(ip.as_bytes())));
+    }
+  });
+}
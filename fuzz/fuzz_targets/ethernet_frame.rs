@@ -0,0 +1,19 @@
+#![no_main]
+
+use binator::Parsed;
+use binator_network::concrete::{
+  ethernet_frame,
+  ethernet_frame_with_fcs,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  if let Parsed::Success { token, stream } = ethernet_frame(data) {
+    // an 802.3 length bound the stream to the start of the payload
+    assert_eq!(&data[token.len()..][..stream.len()], stream);
+    if token.payload_len().is_none() {
+      assert_eq!(token.len(), data.len() - stream.len());
+    }
+  }
+  let _ = ethernet_frame_with_fcs(data);
+});
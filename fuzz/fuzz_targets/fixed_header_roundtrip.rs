@@ -0,0 +1,32 @@
+#![no_main]
+
+use binator::Parsed;
+use binator_network::{
+  concrete::{
+    ipv6_header,
+    udp_header,
+  },
+  IPv6Header,
+  UdpHeader,
+};
+use libfuzzer_sys::fuzz_target;
+
+// from_bytes must agree with the combinator parsers and to_bytes must give
+// back the input
+fuzz_target!(|data: &[u8]| {
+  if let Some(bytes) = data.first_chunk::<{ UdpHeader::LEN }>() {
+    let header = UdpHeader::from_bytes(bytes);
+    assert_eq!(&header.to_bytes(), bytes);
+    if let Parsed::Success { token, .. } = udp_header(data) {
+      assert_eq!(token, header);
+    }
+  }
+
+  if let Some(bytes) = data.first_chunk::<{ IPv6Header::LEN }>() {
+    let header = IPv6Header::from_bytes(bytes);
+    if let Some(header) = header {
+      assert_eq!(&header.to_bytes(), bytes);
+    }
+    assert_eq!(ipv6_header(data).is_success(), header.is_some());
+  }
+});
@@ -0,0 +1,18 @@
+#![no_main]
+
+use binator_network::concrete::{
+  ipv4_address,
+  ipv6_address,
+  ipv6_address_zoned,
+  ipv6_reference,
+  ipv6_reference_zoned,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let _ = ipv4_address(data);
+  let _ = ipv6_address(data);
+  let _ = ipv6_reference(data);
+  let _ = ipv6_address_zoned(data);
+  let _ = ipv6_reference_zoned(data);
+});
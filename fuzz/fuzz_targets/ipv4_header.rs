@@ -0,0 +1,11 @@
+#![no_main]
+
+use binator::Parsed;
+use binator_network::concrete::ipv4_header;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  if let Parsed::Success { token, stream } = ipv4_header(data) {
+    assert_eq!(token.header_len(), data.len() - stream.len());
+  }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use binator_network::concrete::ipv6_header;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let _ = ipv6_header(data);
+});
@@ -0,0 +1,22 @@
+#![no_main]
+
+use std::{
+  net::Ipv6Addr,
+  str::FromStr,
+};
+
+use binator::Parsed;
+use binator_network::concrete::ipv6_address_strict;
+use libfuzzer_sys::fuzz_target;
+
+// strict parser must accept exactly the inputs std accept
+fuzz_target!(|data: &[u8]| {
+  let ours = match ipv6_address_strict(data) {
+    Parsed::Success { token, stream } if stream.is_empty() => Some(token),
+    _ => None,
+  };
+  let std = std::str::from_utf8(data)
+    .ok()
+    .and_then(|data| Ipv6Addr::from_str(data).ok());
+  assert_eq!(ours, std);
+});
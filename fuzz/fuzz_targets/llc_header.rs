@@ -0,0 +1,11 @@
+#![no_main]
+
+use binator::Parsed;
+use binator_network::concrete::llc_header;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  if let Parsed::Success { token, stream } = llc_header(data) {
+    assert_eq!(token.len(), data.len() - stream.len());
+  }
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use binator_network::concrete::quic_long_packet;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let _ = quic_long_packet(data);
+});
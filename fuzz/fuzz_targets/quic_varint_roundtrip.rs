@@ -0,0 +1,21 @@
+#![no_main]
+
+use binator::Parsed;
+use binator_network::{
+  concrete::quic_varint,
+  emit_quic_varint,
+};
+use libfuzzer_sys::fuzz_target;
+
+// parse -> emit -> parse must give back the same value
+fuzz_target!(|data: &[u8]| {
+  if let Parsed::Success { token, stream } = quic_varint(data) {
+    let mut out = Vec::new();
+    let len = emit_quic_varint(token, &mut out).unwrap();
+    assert!(len <= data.len() - stream.len());
+
+    let again = quic_varint(&out).unwrap();
+    assert_eq!(again.token, token);
+    assert!(again.stream.is_empty());
+  }
+});
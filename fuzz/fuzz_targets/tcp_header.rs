@@ -0,0 +1,15 @@
+#![no_main]
+
+use binator::Parsed;
+use binator_network::concrete::{
+  tcp_header,
+  tcp_options,
+};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  if let Parsed::Success { token, stream } = tcp_header(data) {
+    assert_eq!(token.header_len(), data.len() - stream.len());
+    let _ = tcp_options(token.options);
+  }
+});
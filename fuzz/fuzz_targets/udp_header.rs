@@ -0,0 +1,8 @@
+#![no_main]
+
+use binator_network::concrete::udp_header;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+  let _ = udp_header(data);
+});
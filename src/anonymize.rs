@@ -0,0 +1,288 @@
+//! Anonymize captured frames so they can be shared
+
+use std::net::{
+  Ipv4Addr,
+  Ipv6Addr,
+};
+
+use binator::{
+  context::Ignore,
+  Parse,
+  Parsed,
+};
+
+use crate::{
+  checksum_update,
+  ethernet_frame,
+  ipv4_header,
+  ipv6_header,
+  tcp_header,
+  udp_header_unchecked,
+  EtherType,
+  IPProtocol,
+};
+
+/// Keyed anonymizer, the same key always produce the same mapping so flows
+/// stay consistent across a capture.
+///
+/// IP addresses are anonymized with the prefix-preserving construction of
+/// Crypto-PAn: two addresses sharing a n bits prefix are mapped to two
+/// addresses sharing a n bits prefix. The pseudo random function is
+/// SipHash-2-4 instead of AES so the output differ from the reference
+/// Crypto-PAn implementation.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Anonymizer {
+  k0: u64,
+  k1: u64,
+}
+
+macro_rules! sip_round {
+  ($v0:ident, $v1:ident, $v2:ident, $v3:ident) => {
+    $v0 = $v0.wrapping_add($v1);
+    $v1 = $v1.rotate_left(13);
+    $v1 ^= $v0;
+    $v0 = $v0.rotate_left(32);
+    $v2 = $v2.wrapping_add($v3);
+    $v3 = $v3.rotate_left(16);
+    $v3 ^= $v2;
+    $v0 = $v0.wrapping_add($v3);
+    $v3 = $v3.rotate_left(21);
+    $v3 ^= $v0;
+    $v2 = $v2.wrapping_add($v1);
+    $v1 = $v1.rotate_left(17);
+    $v1 ^= $v2;
+    $v2 = $v2.rotate_left(32);
+  };
+}
+
+impl Anonymizer {
+  /// Return an Anonymizer using key as secret
+  pub const fn new(key: [u8; 16]) -> Self {
+    let [a, b, c, d, e, f, g, h, i, j, k, l, m, n, o, p] = key;
+    Self {
+      k0: u64::from_le_bytes([a, b, c, d, e, f, g, h]),
+      k1: u64::from_le_bytes([i, j, k, l, m, n, o, p]),
+    }
+  }
+
+  // SipHash-2-4
+  fn prf(&self, data: &[u8]) -> u64 {
+    let mut v0 = self.k0 ^ 0x736F_6D65_7073_6575;
+    let mut v1 = self.k1 ^ 0x646F_7261_6E64_6F6D;
+    let mut v2 = self.k0 ^ 0x6C79_6765_6E65_7261;
+    let mut v3 = self.k1 ^ 0x7465_6462_7974_6573;
+
+    let mut chunks = data.chunks_exact(8);
+    for chunk in &mut chunks {
+      let m = u64::from_le_bytes(chunk.try_into().unwrap());
+      v3 ^= m;
+      sip_round!(v0, v1, v2, v3);
+      sip_round!(v0, v1, v2, v3);
+      v0 ^= m;
+    }
+
+    let mut last = [0; 8];
+    last[..chunks.remainder().len()].copy_from_slice(chunks.remainder());
+    last[7] = data.len() as u8;
+    let m = u64::from_le_bytes(last);
+    v3 ^= m;
+    sip_round!(v0, v1, v2, v3);
+    sip_round!(v0, v1, v2, v3);
+    v0 ^= m;
+
+    v2 ^= 0xFF;
+    for _ in 0..4u8 {
+      sip_round!(v0, v1, v2, v3);
+    }
+
+    v0 ^ v1 ^ v2 ^ v3
+  }
+
+  fn prefix_preserving(&self, addr: u128, bits: u8) -> u128 {
+    let mut flip = 0;
+    for i in 0..bits {
+      let prefix = if i == 0 {
+        0
+      } else {
+        addr >> (bits - i) << (bits - i)
+      };
+      let mut input = [0; 18];
+      input[0] = bits;
+      input[1] = i;
+      input[2..].copy_from_slice(&prefix.to_be_bytes());
+      flip |= u128::from(self.prf(&input) & 1) << (bits - 1 - i);
+    }
+    addr ^ flip
+  }
+
+  /// Return the anonymized version of addr, prefix preserving
+  pub fn ipv4(&self, addr: Ipv4Addr) -> Ipv4Addr {
+    Ipv4Addr::from(self.prefix_preserving(u128::from(u32::from(addr)), 32) as u32)
+  }
+
+  /// Return the anonymized version of addr, prefix preserving
+  pub fn ipv6(&self, addr: Ipv6Addr) -> Ipv6Addr {
+    Ipv6Addr::from(self.prefix_preserving(u128::from(addr), 128))
+  }
+
+  /// Return a random looking MAC address derived from mac, the group bit is
+  /// kept and the locally administered bit is set.
+  pub fn mac(&self, mac: [u8; 6]) -> [u8; 6] {
+    let mut input = [0; 7];
+    input[0] = 6;
+    input[1..].copy_from_slice(&mac);
+    let [a, b, c, d, e, f, ..] = self.prf(&input).to_be_bytes();
+    [(a & 0xFC) | 0x02 | (mac[0] & 0x01), b, c, d, e, f]
+  }
+
+  /// Anonymize in place an ethernet frame: MAC addresses, IPv4 or IPv6
+  /// addresses, the IPv4 header checksum and the TCP, UDP or ICMPv6 checksum
+  /// are fixed with incremental updates. Length fields are left untouched.
+  ///
+  /// Return the length the frame should be truncated to in order to only keep
+  /// keep_payload bytes after the last decoded header.
+  pub fn frame(&self, frame: &mut [u8], keep_payload: usize) -> usize {
+    let Parsed::Success {
+      token: ethernet, ..
+    } = ethernet_frame::<_, Ignore>(&*frame)
+    else {
+      return frame.len();
+    };
+    // the stream of an 802.3 frame is bounded by its length
+    let l3 = ethernet.len();
+
+    let destination = self.mac(ethernet.destination);
+    frame[0..6].copy_from_slice(&destination);
+    let source = self.mac(ethernet.source);
+    frame[6..12].copy_from_slice(&source);
+
+    let (l4, protocol, addrs) = match ethernet.payload_ether_type() {
+      Some(EtherType::IPV4) => {
+        let Parsed::Success { token, stream } = ipv4_header::<_, Ignore>.parse(&frame[l3..]) else {
+          return (l3 + keep_payload).min(frame.len());
+        };
+        let l4 = frame.len() - stream.len();
+        let protocol = (token.fragment_offset == 0).then_some(token.protocol);
+        let (source_addr, dest_addr, chksum) = (token.source_addr, token.dest_addr, token.chksum);
+
+        let addrs = l3 + 12..l3 + 20;
+        let old = frame[addrs.clone()].to_vec();
+        frame[l3 + 12..l3 + 16].copy_from_slice(&self.ipv4(source_addr).octets());
+        frame[l3 + 16..l3 + 20].copy_from_slice(&self.ipv4(dest_addr).octets());
+        let chksum = checksum_update(chksum, &old, &frame[addrs.clone()]);
+        frame[l3 + 10..l3 + 12].copy_from_slice(&chksum.to_be_bytes());
+
+        (l4, protocol, (old, addrs))
+      }
+      Some(EtherType::IPV6) => {
+        let Parsed::Success { token, stream } = ipv6_header::<_, Ignore>.parse(&frame[l3..]) else {
+          return (l3 + keep_payload).min(frame.len());
+        };
+        let l4 = frame.len() - stream.len();
+
+        let addrs = l3 + 8..l3 + 40;
+        let old = frame[addrs.clone()].to_vec();
+        frame[l3 + 8..l3 + 24].copy_from_slice(&self.ipv6(token.source_addr).octets());
+        frame[l3 + 24..l3 + 40].copy_from_slice(&self.ipv6(token.dest_addr).octets());
+
+        (l4, Some(token.next_header), (old, addrs))
+      }
+      _ => return (l3 + keep_payload).min(frame.len()),
+    };
+
+    let (checksum_offset, payload) = match protocol {
+      Some(IPProtocol::TCP) => (
+        16,
+        match tcp_header::<_, Ignore>.parse(&frame[l4..]) {
+          Parsed::Success { stream, .. } => frame.len() - stream.len(),
+          _ => l4,
+        },
+      ),
+      Some(IPProtocol::UDP) => (
+        6,
+        // the datagram can be truncated by the snaplen or fragmented
+        match udp_header_unchecked::<_, Ignore>.parse(&frame[l4..]) {
+          Parsed::Success { stream, .. } => frame.len() - stream.len(),
+          _ => l4,
+        },
+      ),
+      Some(IPProtocol::ICMP_6) => (2, l4),
+      _ => return (l4 + keep_payload).min(frame.len()),
+    };
+
+    let at = l4 + checksum_offset;
+    if let Some(field) = frame.get(at..at + 2) {
+      let checksum = u16::from_be_bytes([field[0], field[1]]);
+      // A zero UDP checksum means no checksum
+      if !(protocol == Some(IPProtocol::UDP) && checksum == 0) {
+        let (old, addrs) = addrs;
+        let checksum = match checksum_update(checksum, &old, &frame[addrs]) {
+          0 if protocol == Some(IPProtocol::UDP) => 0xFFFF,
+          checksum => checksum,
+        };
+        frame[at..at + 2].copy_from_slice(&checksum.to_be_bytes());
+      }
+    }
+
+    (payload + keep_payload).min(frame.len())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use super::Anonymizer;
+  use crate::{
+    internet_checksum,
+    EthernetFrame,
+    IPv4Header,
+    TcpHeader,
+  };
+
+  #[test]
+  fn anonymize() {
+    let key = core::array::from_fn(|i| i as u8);
+    let anonymizer = Anonymizer::new(key);
+
+    // https://github.com/veorq/SipHash/blob/master/vectors.h
+    assert_eq!(anonymizer.prf(&[]), 0x726F_DB47_DD0E_0E31);
+
+    let a = anonymizer.ipv4(Ipv4Addr::new(192, 168, 1, 1));
+    let b = anonymizer.ipv4(Ipv4Addr::new(192, 168, 1, 200));
+    let c = anonymizer.ipv4(Ipv4Addr::new(10, 0, 0, 1));
+    assert_eq!(a.octets()[..3], b.octets()[..3]);
+    assert_ne!(a, Ipv4Addr::new(192, 168, 1, 1));
+    assert_ne!(a.octets()[0] & 0x80, c.octets()[0] & 0x80);
+
+    let mut frame = [
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00, 0x6C,
+      0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39, 0xAE,
+      0xE6, 0x50, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00, 0x47, 0x45, 0x54, 0x20, 0x2F, 0x69,
+      0x6E, 0x64, 0x65, 0x78, 0x2E, 0x68, 0x74, 0x6D, 0x6C, 0x0A,
+    ];
+    let tcp_checksum = |frame: &[u8]| {
+      let mut pseudo = Vec::new();
+      pseudo.extend_from_slice(&frame[26..34]);
+      pseudo.extend_from_slice(&[0, 6, 0, 36]);
+      pseudo.extend_from_slice(&frame[34..]);
+      internet_checksum(&pseudo)
+    };
+    assert_eq!(tcp_checksum(&frame), 0);
+
+    let len = anonymizer.frame(&mut frame, 4);
+
+    assert_eq!(
+      len,
+      EthernetFrame::MIN_LEN + IPv4Header::<()>::MIN_LEN + TcpHeader::<()>::MIN_LEN + 4
+    );
+    assert_eq!(frame[0] & 0x03, 0x02);
+    assert_ne!(
+      frame[26..34],
+      [0xC0, 0xA8, 0x00, 0x6C, 0xD0, 0x61, 0xB1, 0x7C]
+    );
+    assert_eq!(internet_checksum(&frame[14..34]), 0);
+    assert_eq!(tcp_checksum(&frame), 0);
+  }
+}
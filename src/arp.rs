@@ -0,0 +1,209 @@
+//! Handles parsing of ARP packets and classify the ones used for address
+//! conflict detection
+//! <https://www.rfc-editor.org/rfc/rfc826>
+//! <https://www.rfc-editor.org/rfc/rfc5227>
+
+use std::net::Ipv4Addr;
+
+use binator::{
+  base::{
+    octet,
+    primitive::u16_be,
+    take,
+  },
+  utils::UtilsAtom,
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  EtherType,
+};
+
+/// Meta trait for arp combinator
+pub trait ArpParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>;
+
+struct_variants! {
+  ArpOperation, operation, u16:
+    /// Request
+    REQUEST => 1,
+    /// Reply
+    REPLY => 2,
+    /// Reverse ARP request
+    RARP_REQUEST => 3,
+    /// Reverse ARP reply
+    RARP_REPLY => 4,
+}
+
+/// Meaning of an ARP packet for monitoring, see [ArpPacket::kind]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ArpKind {
+  /// Request resolving the target address
+  Request,
+  /// Reply to a request
+  Reply,
+  /// RFC 5227 probe, a request with an unspecified sender address checking
+  /// the target address is free
+  Probe,
+  /// RFC 5227 announcement, a request for the sender own address claiming it
+  Announcement,
+  /// Reply for the sender own address, sent unsolicited to update caches
+  Gratuitous,
+  /// Any other operation
+  Other,
+}
+
+/// ARP packet, addresses are kept as spans as their lengths depend on the
+/// hardware and protocol types
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArpPacket<Span> {
+  /// Hardware type, 1 for Ethernet
+  pub hardware_type: u16,
+  /// Protocol type
+  pub protocol_type: EtherType,
+  /// Operation
+  pub operation: ArpOperation,
+  /// Sender hardware address
+  pub sender_hardware_addr: Span,
+  /// Sender protocol address
+  pub sender_protocol_addr: Span,
+  /// Target hardware address
+  pub target_hardware_addr: Span,
+  /// Target protocol address
+  pub target_protocol_addr: Span,
+}
+
+impl<Span> ArpPacket<Span>
+where
+  Span: AsRef<[u8]>,
+{
+  /// Hardware type of Ethernet
+  pub const ETHERNET: u16 = 1;
+
+  /// Return the sender IPv4 address, None if the protocol is not IPv4
+  pub fn sender_ipv4(&self) -> Option<Ipv4Addr> {
+    self.ipv4(&self.sender_protocol_addr)
+  }
+
+  /// Return the target IPv4 address, None if the protocol is not IPv4
+  pub fn target_ipv4(&self) -> Option<Ipv4Addr> {
+    self.ipv4(&self.target_protocol_addr)
+  }
+
+  fn ipv4(&self, addr: &Span) -> Option<Ipv4Addr> {
+    if self.protocol_type != EtherType::IPV4 {
+      return None;
+    }
+    <[u8; 4]>::try_from(addr.as_ref()).ok().map(Ipv4Addr::from)
+  }
+
+  /// Classify the packet, a request for the sender own address is an
+  /// announcement even if it's often called gratuitous ARP too
+  pub fn kind(&self) -> ArpKind {
+    let sender = self.sender_protocol_addr.as_ref();
+    let own_addr = sender == self.target_protocol_addr.as_ref();
+    match self.operation {
+      ArpOperation::REQUEST if sender.iter().all(|octet| *octet == 0) => ArpKind::Probe,
+      ArpOperation::REQUEST if own_addr => ArpKind::Announcement,
+      ArpOperation::REQUEST => ArpKind::Request,
+      ArpOperation::REPLY if own_addr => ArpKind::Gratuitous,
+      ArpOperation::REPLY => ArpKind::Reply,
+      _ => ArpKind::Other,
+    }
+  }
+}
+
+/// Parse an ARP packet, Ethernet padding after it is left in the stream
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn arp_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<ArpPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): ArpParse<Stream, Context>,
+{
+  let Success {
+    token: (hardware_type, protocol_type, hardware_len, protocol_len, operation),
+    stream,
+  } = (u16_be, u16_be, octet, octet, u16_be).parse(stream)?;
+  let hardware_len = usize::from(hardware_len);
+  let protocol_len = usize::from(protocol_len);
+
+  let Success {
+    token: (sender_hardware_addr, sender_protocol_addr, target_hardware_addr, target_protocol_addr),
+    stream,
+  } = (
+    take(hardware_len),
+    take(protocol_len),
+    take(hardware_len),
+    take(protocol_len),
+  )
+    .parse(stream)?;
+
+  Parsed::Success {
+    token: ArpPacket {
+      hardware_type,
+      protocol_type: EtherType::new(protocol_type),
+      operation: ArpOperation::new(operation),
+      sender_hardware_addr,
+      sender_protocol_addr,
+      target_hardware_addr,
+      target_protocol_addr,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    ArpKind,
+    ArpOperation,
+  };
+
+  #[test]
+  fn arp_packet() {
+    let mut packet = [
+      0x00, 0x01, 0x08, 0x00, 0x06, 0x04, 0x00, 0x01, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0xC0,
+      0xA8, 0x00, 0x6C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0xA8, 0x00, 0x01, 0x00,
+    ];
+    let Success { token, stream } = super::arp_packet::<_, Ignore>(&packet[..]).unwrap();
+    assert_eq!(token.operation, ArpOperation::REQUEST);
+    assert_eq!(token.sender_ipv4(), Some(Ipv4Addr::new(192, 168, 0, 108)));
+    assert_eq!(token.target_ipv4(), Some(Ipv4Addr::new(192, 168, 0, 1)));
+    assert_eq!(token.kind(), ArpKind::Request);
+    assert_eq!(stream, [0x00]);
+
+    packet[14..18].fill(0);
+    let token = super::arp_packet::<_, Ignore>(&packet[..]).unwrap().token;
+    assert_eq!(token.kind(), ArpKind::Probe);
+
+    packet[14..18].copy_from_slice(&[0xC0, 0xA8, 0x00, 0x01]);
+    let token = super::arp_packet::<_, Ignore>(&packet[..]).unwrap().token;
+    assert_eq!(token.kind(), ArpKind::Announcement);
+
+    packet[7] = 0x02;
+    let token = super::arp_packet::<_, Ignore>(&packet[..]).unwrap().token;
+    assert_eq!(token.kind(), ArpKind::Gratuitous);
+  }
+}
@@ -0,0 +1,237 @@
+//! Handles parsing of IEEE 1722 Audio Video Transport Protocol stream headers
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+      u64_be,
+    },
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+struct_variants! {
+  AvtpSubtype, subtype, u8:
+    /// IEC 61883/IIDC Format
+    IEC_61883_IIDC => 0x00,
+    /// MMA Streams
+    MMA_STREAM => 0x01,
+    /// AVTP Audio Format
+    AAF => 0x02,
+    /// Compressed Video Format
+    CVF => 0x03,
+    /// Clock Reference Format
+    CRF => 0x04,
+    /// Time-Synchronous Control Format
+    TSCF => 0x05,
+    /// SDI Video Format
+    SVF => 0x06,
+    /// Raw Video Format
+    RVF => 0x07,
+    /// AES Encrypted Format Continuous
+    AEF_CONTINUOUS => 0x6E,
+    /// Vendor Specific Format Stream
+    VSF_STREAM => 0x6F,
+    /// Experimental Format Stream
+    EF_STREAM => 0x7F,
+    /// Non-Time-Synchronous Control Format
+    NTSCF => 0x82,
+    /// ECC Signed Control Format
+    ESCF => 0xEC,
+    /// ECC Encrypted Control Format
+    EECF => 0xED,
+    /// AES Encrypted Format Discrete
+    AEF_DISCRETE => 0xEE,
+    /// AVDECC Discovery Protocol
+    ADP => 0xFA,
+    /// AVDECC Enumeration and Control Protocol
+    AECP => 0xFB,
+    /// AVDECC Connection Management Protocol
+    ACMP => 0xFC,
+    /// MAAP Protocol
+    MAAP => 0xFE,
+    /// Experimental Format Control
+    EF_CONTROL => 0xFF,
+}
+
+/// Atom produced by avtp_stream parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AvtpAtom {
+  /// When version is not 0
+  Version(u8),
+}
+
+impl Display for AvtpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      AvtpAtom::Version(version) => write!(f, "Version: expected 0 found {}", version),
+    }
+  }
+}
+
+/// AVTP stream data unit, the header shared by the stream subtypes
+/// <https://standards.ieee.org/ieee/1722/6609/>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AvtpStream<Span> {
+  /// Format of the stream
+  pub subtype: AvtpSubtype,
+  /// AVTP version, always 0
+  pub version: u8,
+  /// Media clock restart
+  pub media_clock_restart: bool,
+  /// gateway_info is valid
+  pub gateway_valid: bool,
+  /// timestamp is valid
+  pub timestamp_valid: bool,
+  /// Incremented for each AVTPDU of the stream
+  pub sequence_num: u8,
+  /// The timestamp may be wrong
+  pub timestamp_uncertain: bool,
+  /// Identify the stream, None when the stream_id valid bit is not set
+  pub stream_id: Option<u64>,
+  /// Presentation time, in gPTP time nanoseconds modulo 2^32
+  pub timestamp: u32,
+  /// Gateway information or format specific data depending of subtype
+  pub format_specific: u32,
+  /// Length of stream_data, in bytes
+  pub stream_data_length: u16,
+  /// Format specific data following the length
+  pub packet_info: u16,
+  /// Payload of the AVTPDU
+  pub stream_data: Span,
+}
+
+/// Parse an AVTP stream data unit, the payload of an ethernet frame with
+/// [crate::EtherType::AVTP]
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn avtp_stream<Stream, Context>(
+  stream: Stream,
+) -> Parsed<AvtpStream<<Stream as Streaming>::Span>, Stream, Context>
+where
+  Stream: Streaming,
+  Stream: Eq,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<AvtpAtom>,
+{
+  let Success {
+    token: (subtype, flags, sequence_num, timestamp_uncertain),
+    stream,
+  } = (
+    octet.map(AvtpSubtype::new),
+    octet.try_map(|flags| {
+      let version = flags >> 4u8 & 0b111;
+      if version == 0 {
+        Ok(flags)
+      } else {
+        Err(Context::new(AvtpAtom::Version(version)))
+      }
+    }),
+    octet,
+    octet.map(|byte| byte & 0x01 != 0),
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: (stream_id, timestamp, format_specific, stream_data_length, packet_info),
+    stream,
+  } = (
+    u64_be.map(|stream_id| (flags & 0x80 != 0).then_some(stream_id)),
+    u32_be,
+    u32_be,
+    u16_be,
+    u16_be,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: stream_data,
+    stream,
+  } = take(usize::from(stream_data_length)).parse(stream)?;
+
+  Parsed::Success {
+    token: AvtpStream {
+      subtype,
+      version: flags >> 4u8 & 0b111,
+      media_clock_restart: flags & 0x08 != 0,
+      gateway_valid: flags & 0x02 != 0,
+      timestamp_valid: flags & 0x01 != 0,
+      sequence_num,
+      timestamp_uncertain,
+      stream_id,
+      timestamp,
+      format_specific,
+      stream_data_length,
+      packet_info,
+      stream_data,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::{
+    AvtpStream,
+    AvtpSubtype,
+  };
+
+  #[test]
+  fn avtp_stream() {
+    let bytes = [
+      0x02, 0x81, 0x2A, 0x00, 0x00, 0x1B, 0x21, 0xFF, 0xFE, 0x00, 0x00, 0x01, 0x12, 0x34, 0x56,
+      0x78, 0x02, 0x10, 0x02, 0x18, 0x00, 0x04, 0x00, 0x00, 0xDE, 0xAD, 0xBE, 0xEF, 0x00,
+    ];
+
+    assert_eq!(
+      super::avtp_stream::<_, Ignore>(&bytes[..]),
+      Parsed::Success {
+        token: AvtpStream {
+          subtype: AvtpSubtype::AAF,
+          version: 0,
+          media_clock_restart: false,
+          gateway_valid: false,
+          timestamp_valid: true,
+          sequence_num: 42,
+          timestamp_uncertain: false,
+          stream_id: Some(0x001B_21FF_FE00_0001),
+          timestamp: 0x1234_5678,
+          format_specific: 0x0210_0218,
+          stream_data_length: 4,
+          packet_info: 0,
+          stream_data: &[0xDE, 0xAD, 0xBE, 0xEF][..],
+        },
+        stream: &[0x00][..],
+      }
+    );
+  }
+}
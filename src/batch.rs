@@ -0,0 +1,272 @@
+//! Parse many ethernet frames at once into a column oriented layout
+
+use std::net::IpAddr;
+
+use binator::{
+  context::Ignore,
+  Parse,
+  Parsed,
+};
+
+use crate::{
+  ethernet_frame,
+  ipv4_header,
+  ipv6_header,
+  tcp_header,
+  udp_header_unchecked,
+  EtherType,
+  IPProtocol,
+  Port,
+  TcpFlags,
+};
+
+/// Struct of arrays summary of a batch of ethernet frames, every column have
+/// one entry per frame, None when the layer was absent or failed to parse.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PacketColumns {
+  /// Length of the whole frame
+  pub frame_len: Vec<usize>,
+  /// EtherType of the frame
+  pub ether_type: Vec<Option<EtherType>>,
+  /// IP source address
+  pub source_addr: Vec<Option<IpAddr>>,
+  /// IP destination address
+  pub dest_addr: Vec<Option<IpAddr>>,
+  /// IPv4 protocol or IPv6 next header, None for IPv4 fragments other than
+  /// the first
+  pub protocol: Vec<Option<IPProtocol>>,
+  /// TCP or UDP source port
+  pub source_port: Vec<Option<Port>>,
+  /// TCP or UDP destination port
+  pub dest_port: Vec<Option<Port>>,
+  /// TCP flags
+  pub tcp_flags: Vec<Option<TcpFlags>>,
+  /// Number of bytes following the transport header, computed from the IP
+  /// length so ethernet padding and trailer are not counted
+  pub payload_len: Vec<Option<usize>>,
+}
+
+#[derive(Default)]
+pub(crate) struct Row {
+  pub(crate) ether_type: Option<EtherType>,
+  pub(crate) source_addr: Option<IpAddr>,
+  pub(crate) dest_addr: Option<IpAddr>,
+  pub(crate) protocol: Option<IPProtocol>,
+  pub(crate) source_port: Option<Port>,
+  pub(crate) dest_port: Option<Port>,
+  pub(crate) tcp_flags: Option<TcpFlags>,
+  pub(crate) payload_len: Option<usize>,
+}
+
+impl Row {
+  pub(crate) fn new(frame: &[u8]) -> Self {
+    let mut row = Self::default();
+
+    let Parsed::Success {
+      token: ethernet,
+      stream,
+    } = ethernet_frame::<_, Ignore>(frame)
+    else {
+      return row;
+    };
+    row.ether_type = ethernet.payload_ether_type();
+
+    // the IP payload length exclude the ethernet padding and trailer
+    let (len, stream) = match row.ether_type {
+      Some(EtherType::IPV4) => match ipv4_header::<_, Ignore>.parse(stream) {
+        Parsed::Success { token, stream } => {
+          row.source_addr = Some(token.source_addr.into());
+          row.dest_addr = Some(token.dest_addr.into());
+          row.protocol = (token.fragment_offset == 0).then_some(token.protocol);
+          (token.payload_len(), stream)
+        }
+        _ => return row,
+      },
+      Some(EtherType::IPV6) => match ipv6_header::<_, Ignore>.parse(stream) {
+        Parsed::Success { token, stream } => {
+          row.source_addr = Some(token.source_addr.into());
+          row.dest_addr = Some(token.dest_addr.into());
+          row.protocol = Some(token.next_header);
+          (Some(usize::from(token.length)), stream)
+        }
+        _ => return row,
+      },
+      _ => return row,
+    };
+    let payload_len = |header: &[u8], payload: &[u8]| {
+      len.and_then(|len| len.checked_sub(header.len() - payload.len()))
+    };
+
+    match row.protocol {
+      Some(IPProtocol::TCP) => {
+        if let Parsed::Success {
+          token: tcp,
+          stream: payload,
+        } = tcp_header::<_, Ignore>.parse(stream)
+        {
+          row.source_port = Some(tcp.source_port);
+          row.dest_port = Some(tcp.dest_port);
+          row.tcp_flags = Some(tcp.flags);
+          row.payload_len = payload_len(stream, payload);
+        }
+      }
+      Some(IPProtocol::UDP) => {
+        if let Parsed::Success {
+          token: udp,
+          stream: payload,
+        } = udp_header_unchecked::<_, Ignore>.parse(stream)
+        {
+          row.source_port = Some(udp.source_port);
+          row.dest_port = Some(udp.dest_port);
+          row.payload_len = payload_len(stream, payload);
+        }
+      }
+      _ => {}
+    }
+
+    row
+  }
+}
+
+impl PacketColumns {
+  /// Return empty columns with room for capacity frames
+  pub fn with_capacity(capacity: usize) -> Self {
+    Self {
+      frame_len: Vec::with_capacity(capacity),
+      ether_type: Vec::with_capacity(capacity),
+      source_addr: Vec::with_capacity(capacity),
+      dest_addr: Vec::with_capacity(capacity),
+      protocol: Vec::with_capacity(capacity),
+      source_port: Vec::with_capacity(capacity),
+      dest_port: Vec::with_capacity(capacity),
+      tcp_flags: Vec::with_capacity(capacity),
+      payload_len: Vec::with_capacity(capacity),
+    }
+  }
+
+  /// Return the number of frames
+  pub fn len(&self) -> usize {
+    self.frame_len.len()
+  }
+
+  /// Return true if there is no frame
+  pub fn is_empty(&self) -> bool {
+    self.frame_len.is_empty()
+  }
+
+  /// Parse frame and append one entry to every column
+  pub fn push_frame(&mut self, frame: &[u8]) {
+    let Row {
+      ether_type,
+      source_addr,
+      dest_addr,
+      protocol,
+      source_port,
+      dest_port,
+      tcp_flags,
+      payload_len,
+    } = Row::new(frame);
+
+    self.frame_len.push(frame.len());
+    self.ether_type.push(ether_type);
+    self.source_addr.push(source_addr);
+    self.dest_addr.push(dest_addr);
+    self.protocol.push(protocol);
+    self.source_port.push(source_port);
+    self.dest_port.push(dest_port);
+    self.tcp_flags.push(tcp_flags);
+    self.payload_len.push(payload_len);
+  }
+}
+
+impl<'a> Extend<&'a [u8]> for PacketColumns {
+  fn extend<Frames: IntoIterator<Item = &'a [u8]>>(&mut self, frames: Frames) {
+    for frame in frames {
+      self.push_frame(frame);
+    }
+  }
+}
+
+/// Parse every ethernet frame of frames into column oriented arrays
+pub fn parse_batch<'a, Frames>(frames: Frames) -> PacketColumns
+where
+  Frames: IntoIterator<Item = &'a [u8]>,
+{
+  let frames = frames.into_iter();
+  let mut columns = PacketColumns::with_capacity(frames.size_hint().0);
+  columns.extend(frames);
+  columns
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    IpAddr,
+    Ipv4Addr,
+  };
+
+  use super::parse_batch;
+  use crate::{
+    EtherType,
+    IPProtocol,
+    Port,
+  };
+
+  #[test]
+  fn parse_batch_columns() {
+    let tcp = [
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00, 0x6C,
+      0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39, 0xAE,
+      0xE6, 0x50, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00, 0x47, 0x45, 0x54, 0x20, 0x2F, 0x69,
+      0x6E, 0x64, 0x65, 0x78, 0x2E, 0x68, 0x74, 0x6D, 0x6C, 0x0A,
+    ];
+    let arp = [
+      0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x06,
+    ];
+    // UDP with a 2 bytes payload and ethernet padding
+    let mut udp = vec![
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x1E, 0x12, 0x34, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 0xC0, 0xA8, 0x00, 0x6C,
+      0xC0, 0xA8, 0x00, 0x01, 0xD4, 0x31, 0x00, 0x35, 0x00, 0x0A, 0x00, 0x00, 0xAB, 0xCD,
+    ];
+    udp.resize(60, 0);
+    // second fragment of the same datagram, offset 8 bytes
+    let mut fragment = udp.clone();
+    fragment[20] = 0x00;
+    fragment[21] = 0x01;
+
+    let columns = parse_batch([&tcp[..], &arp[..], &udp[..], &fragment[..]]);
+
+    assert_eq!(columns.len(), 4);
+    assert_eq!(columns.frame_len, [tcp.len(), arp.len(), 60, 60]);
+    assert_eq!(
+      columns.ether_type,
+      [
+        Some(EtherType::IPV4),
+        Some(EtherType::ARP),
+        Some(EtherType::IPV4),
+        Some(EtherType::IPV4)
+      ]
+    );
+    assert_eq!(
+      columns.source_addr[..2],
+      [Some(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 108))), None]
+    );
+    assert_eq!(
+      columns.protocol,
+      [Some(IPProtocol::TCP), None, Some(IPProtocol::UDP), None]
+    );
+    assert_eq!(
+      columns.source_port,
+      [Some(Port(45250)), None, Some(Port(54321)), None]
+    );
+    assert_eq!(
+      columns.dest_port,
+      [Some(Port(80)), None, Some(Port(53)), None]
+    );
+    assert_eq!(columns.payload_len, [Some(16), None, Some(2), None]);
+    assert!(columns.tcp_flags[0].as_ref().unwrap().get_ack());
+  }
+}
@@ -0,0 +1,507 @@
+//! Handles decoding of ASN.1 BER and DER encoded values, as used by SNMP and
+//! Kerberos. Only the definite length form is supported.
+//! <https://www.itu.int/rec/T-REC-X.690>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+/// Meta trait for ber combinator
+pub trait BerParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<BerAtom>;
+
+/// Atom produced by ber parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BerAtom {
+  /// When the indefinite length form is used
+  IndefiniteLength,
+  /// When a length or a tag number doesn't fit in 32 bits
+  TooLong,
+  /// When a value is too long for its type
+  Length(usize),
+  /// When the tag is not the one expected
+  Tag {
+    /// Expected tag
+    expected: BerTag,
+    /// Found tag
+    found: BerTag,
+  },
+}
+
+impl Display for BerAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BerAtom::IndefiniteLength => write!(f, "IndefiniteLength: only definite length is supported"),
+      BerAtom::TooLong => write!(f, "TooLong: length or tag number overflow 32 bits"),
+      BerAtom::Length(length) => write!(f, "Length: length {} is too long for its type", length),
+      BerAtom::Tag { expected, found } => {
+        write!(f, "Tag: expected {} found {}", expected, found)
+      }
+    }
+  }
+}
+
+/// Identifier of a value, its class, form and number
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BerTag {
+  /// Class, one of [BerTag::UNIVERSAL], [BerTag::APPLICATION],
+  /// [BerTag::CONTEXT] or [BerTag::PRIVATE]
+  pub class: u8,
+  /// The value is a sequence of values
+  pub constructed: bool,
+  /// Tag number
+  pub number: u32,
+}
+
+impl BerTag {
+  /// Application class
+  pub const APPLICATION: u8 = 1;
+  /// BIT STRING
+  pub const BIT_STRING: Self = Self::universal(3, false);
+  /// Context-specific class
+  pub const CONTEXT: u8 = 2;
+  /// GeneralizedTime
+  pub const GENERALIZED_TIME: Self = Self::universal(24, false);
+  /// GeneralString
+  pub const GENERAL_STRING: Self = Self::universal(27, false);
+  /// INTEGER
+  pub const INTEGER: Self = Self::universal(2, false);
+  /// NULL
+  pub const NULL: Self = Self::universal(5, false);
+  /// OBJECT IDENTIFIER
+  pub const OBJECT_IDENTIFIER: Self = Self::universal(6, false);
+  /// OCTET STRING
+  pub const OCTET_STRING: Self = Self::universal(4, false);
+  /// Private class
+  pub const PRIVATE: u8 = 3;
+  /// SEQUENCE and SEQUENCE OF
+  pub const SEQUENCE: Self = Self::universal(16, true);
+  /// Universal class
+  pub const UNIVERSAL: u8 = 0;
+
+  /// Return a universal tag
+  pub const fn universal(number: u32, constructed: bool) -> Self {
+    Self {
+      class: Self::UNIVERSAL,
+      constructed,
+      number,
+    }
+  }
+
+  /// Return a constructed application tag
+  pub const fn application(number: u32) -> Self {
+    Self {
+      class: Self::APPLICATION,
+      constructed: true,
+      number,
+    }
+  }
+
+  /// Return a constructed context-specific tag, as used by explicit tagging
+  pub const fn context(number: u32) -> Self {
+    Self {
+      class: Self::CONTEXT,
+      constructed: true,
+      number,
+    }
+  }
+}
+
+impl Display for BerTag {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let class = match self.class {
+      Self::UNIVERSAL => "UNIVERSAL",
+      Self::APPLICATION => "APPLICATION",
+      Self::CONTEXT => "CONTEXT",
+      _ => "PRIVATE",
+    };
+    write!(f, "[{} {}]", class, self.number)
+  }
+}
+
+/// Tag and length of a value
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BerHeader {
+  /// Tag
+  pub tag: BerTag,
+  /// Length of the content
+  pub length: usize,
+  /// Length of the tag and length fields
+  pub header_len: usize,
+}
+
+impl BerHeader {
+  /// Return the length of the whole value, header included
+  pub const fn total_len(&self) -> usize {
+    self.header_len + self.length
+  }
+}
+
+/// Value with its content left undecoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BerTlv<Span> {
+  /// Tag
+  pub tag: BerTag,
+  /// Content
+  pub value: Span,
+}
+
+/// OBJECT IDENTIFIER, as its list of arcs
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BerOid(pub Vec<u32>);
+
+impl BerOid {
+  /// Return true if self is prefix or one of its descendants
+  pub fn starts_with(&self, prefix: &[u32]) -> bool {
+    self.0.starts_with(prefix)
+  }
+}
+
+impl Display for BerOid {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let mut arcs = self.0.iter();
+    if let Some(arc) = arcs.next() {
+      write!(f, "{}", arc)?;
+    }
+    for arc in arcs {
+      write!(f, ".{}", arc)?;
+    }
+    Ok(())
+  }
+}
+
+// accumulate base 128 digits, the high bit tell another digit follow
+fn base128<Stream, Context>(stream: Stream, first: u32) -> Parsed<(u32, usize), Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  let mut stream = stream;
+  let mut value = first;
+  let mut len = 0;
+  loop {
+    let Success {
+      token: digit,
+      stream: next,
+    } = octet.parse(stream)?;
+    if value > u32::MAX >> 7u8 {
+      return Parsed::Failure(Context::new(BerAtom::TooLong));
+    }
+    value = value << 7u8 | u32::from(digit & 0x7F);
+    len += 1;
+    stream = next;
+    if digit & 0x80 == 0 {
+      return Parsed::Success {
+        token: (value, len),
+        stream,
+      };
+    }
+  }
+}
+
+/// Parse the tag and length of a value, the stream is left at its content
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ber_header<Stream, Context>(stream: Stream) -> Parsed<BerHeader, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  let Success {
+    token: identifier,
+    stream,
+  } = octet.parse(stream)?;
+  let Success {
+    token: (number, number_len),
+    stream,
+  } = if identifier & 0x1F == 0x1F {
+    base128(stream, 0)?
+  } else {
+    Success {
+      token: (u32::from(identifier & 0x1F), 0),
+      stream,
+    }
+  };
+  let tag = BerTag {
+    class: identifier >> 6u8,
+    constructed: identifier & 0x20 != 0,
+    number,
+  };
+
+  let Success {
+    token: first,
+    stream,
+  } = octet.parse(stream)?;
+  let Success {
+    token: (length, length_len),
+    stream,
+  } = match first {
+    0x00..=0x7F => Success {
+      token: (usize::from(first), 0),
+      stream,
+    },
+    0x80 => return Parsed::Failure(Context::new(BerAtom::IndefiniteLength)),
+    0x81..=0x84 => {
+      let len = usize::from(first & 0x7F);
+      octet
+        .fold_bounds(
+          len,
+          || 0,
+          |length, octet| length << 8u8 | usize::from(octet),
+        )
+        .map(|length| (length, len))
+        .parse(stream)?
+    }
+    _ => return Parsed::Failure(Context::new(BerAtom::TooLong)),
+  };
+
+  Parsed::Success {
+    token: BerHeader {
+      tag,
+      length,
+      header_len: 2 + number_len + length_len,
+    },
+    stream,
+  }
+}
+
+/// Return a parser of the header of a value that must have tag
+pub fn ber_expect<Stream, Context>(tag: BerTag) -> impl Parse<Stream, Context, Token = BerHeader>
+where
+  (): BerParse<Stream, Context>,
+{
+  move |stream: Stream| -> Parsed<BerHeader, Stream, Context> {
+    ber_header
+      .try_map(|header| {
+        if header.tag == tag {
+          Ok(header)
+        } else {
+          Err(Context::new(BerAtom::Tag {
+            expected: tag,
+            found: header.tag,
+          }))
+        }
+      })
+      .parse(stream)
+  }
+}
+
+/// Parse any value without decoding its content
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ber_tlv<Stream, Context>(
+  stream: Stream,
+) -> Parsed<BerTlv<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_header.parse(stream)?;
+  take(header.length)
+    .map(|value| BerTlv {
+      tag: header.tag,
+      value,
+    })
+    .parse(stream)
+}
+
+/// Parse the content of a two's complement integer of len bytes
+pub fn ber_integer_content<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<i64, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  if len > 8 {
+    return Parsed::Failure(Context::new(BerAtom::Length(len)));
+  }
+  octet
+    .fold_bounds(
+      len,
+      || None,
+      |value: Option<i64>, octet| {
+        Some(match value {
+          // sign extend the first byte
+          None => i64::from(octet as i8),
+          Some(value) => value << 8u8 | i64::from(octet),
+        })
+      },
+    )
+    .map(Option::unwrap_or_default)
+    .parse(stream)
+}
+
+/// Parse the content of an unsigned integer of len bytes, it can have a
+/// leading zero byte
+pub fn ber_unsigned_content<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<u64, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  if len > 9 {
+    return Parsed::Failure(Context::new(BerAtom::Length(len)));
+  }
+  octet
+    .fold_bounds(
+      len,
+      || 0,
+      |value: u64, octet| value << 8u8 | u64::from(octet),
+    )
+    .parse(stream)
+}
+
+/// Parse the content of an OBJECT IDENTIFIER of len bytes
+pub fn ber_oid_content<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<BerOid, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  let mut stream = stream;
+  let mut len = len;
+  let mut arcs = Vec::new();
+  while len > 0 {
+    let Success {
+      token: (arc, arc_len),
+      stream: next,
+    } = base128(stream, 0)?;
+    let Some(rest) = len.checked_sub(arc_len) else {
+      return Parsed::Failure(Context::new(BerAtom::Length(len)));
+    };
+    // the first sub identifier encode the two first arcs
+    if arcs.is_empty() {
+      let first = (arc / 40).min(2);
+      arcs.push(first);
+      arcs.push(arc - first * 40);
+    } else {
+      arcs.push(arc);
+    }
+    len = rest;
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: BerOid(arcs),
+    stream,
+  }
+}
+
+/// Parse an INTEGER
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ber_integer<Stream, Context>(stream: Stream) -> Parsed<i64, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_expect(BerTag::INTEGER).parse(stream)?;
+  ber_integer_content(stream, header.length)
+}
+
+/// Parse an OCTET STRING
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ber_octet_string<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_expect(BerTag::OCTET_STRING).parse(stream)?;
+  take(header.length).parse(stream)
+}
+
+/// Parse an OBJECT IDENTIFIER
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ber_oid<Stream, Context>(stream: Stream) -> Parsed<BerOid, Stream, Context>
+where
+  (): BerParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_expect(BerTag::OBJECT_IDENTIFIER).parse(stream)?;
+  ber_oid_content(stream, header.length)
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    BerOid,
+    BerTag,
+  };
+
+  #[test]
+  fn ber() {
+    let oid = [0x06, 0x08, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x05, 0x00];
+    let Success { token, stream } = super::ber_oid::<_, Ignore>(&oid[..]).unwrap();
+    assert_eq!(token, BerOid(vec![1, 3, 6, 1, 2, 1, 1, 5, 0]));
+    assert_eq!(token.to_string(), "1.3.6.1.2.1.1.5.0");
+    assert_eq!(stream, b"");
+
+    let integer = [0x02, 0x02, 0xFF, 0x7F];
+    let Success { token, .. } = super::ber_integer::<_, Ignore>(&integer[..]).unwrap();
+    assert_eq!(token, -129);
+
+    let long = [0x7F, 0x81, 0x00, 0x81, 0x02, 0xAA, 0xBB];
+    let Success { token, stream } = super::ber_tlv::<_, Ignore>(&long[..]).unwrap();
+    assert_eq!(token.tag.number, 128);
+    assert_eq!(token.tag.class, BerTag::APPLICATION);
+    assert_eq!(token.value, [0xAA, 0xBB]);
+    assert_eq!(stream, b"");
+
+    assert!(matches!(
+      super::ber_oid::<_, Ignore>(&integer[..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
@@ -0,0 +1,828 @@
+//! Handles parsing of BGP-4 messages, they span TCP segments so the stream
+//! should be the reassembled byte stream of a session
+//! <https://www.rfc-editor.org/rfc/rfc4271>
+//! <https://www.rfc-editor.org/rfc/rfc4760>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::Ipv4Addr,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// TCP port of BGP
+pub const BGP_PORT: Port = Port(179);
+
+/// Meta trait for bgp combinator
+pub trait BgpParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<BgpAtom>;
+
+/// Atom produced by bgp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BgpAtom {
+  /// When the marker is not all ones
+  Marker,
+  /// When a length field doesn't match the fields it cover
+  Length(u16),
+  /// When a prefix is longer than its address family allow
+  PrefixLength(u8),
+}
+
+impl Display for BgpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BgpAtom::Marker => write!(f, "Marker: expected all ones"),
+      BgpAtom::Length(length) => {
+        write!(f, "Length: length {} doesn't match its content", length)
+      }
+      BgpAtom::PrefixLength(length) => {
+        write!(f, "PrefixLength: prefix length {} is too long", length)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  BgpMessageType, message_type, u8:
+    /// OPEN
+    OPEN => 1,
+    /// UPDATE
+    UPDATE => 2,
+    /// NOTIFICATION
+    NOTIFICATION => 3,
+    /// KEEPALIVE
+    KEEPALIVE => 4,
+    /// ROUTE-REFRESH
+    ROUTE_REFRESH => 5,
+}
+
+struct_variants! {
+  BgpAttributeType, attribute_type, u8:
+    /// ORIGIN
+    ORIGIN => 1,
+    /// AS_PATH
+    AS_PATH => 2,
+    /// NEXT_HOP
+    NEXT_HOP => 3,
+    /// MULTI_EXIT_DISC
+    MULTI_EXIT_DISC => 4,
+    /// LOCAL_PREF
+    LOCAL_PREF => 5,
+    /// ATOMIC_AGGREGATE
+    ATOMIC_AGGREGATE => 6,
+    /// AGGREGATOR
+    AGGREGATOR => 7,
+    /// COMMUNITIES
+    COMMUNITIES => 8,
+    /// MP_REACH_NLRI
+    MP_REACH_NLRI => 14,
+    /// MP_UNREACH_NLRI
+    MP_UNREACH_NLRI => 15,
+    /// LARGE_COMMUNITY
+    LARGE_COMMUNITY => 32,
+}
+
+/// Capability advertised in an OPEN message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpCapability<Span> {
+  /// Capability code
+  pub code: u8,
+  /// Capability value
+  pub value: Span,
+}
+
+impl<Span> BgpCapability<Span> {
+  /// ADD-PATH
+  pub const ADD_PATH: u8 = 69;
+  /// Support for 4-octet AS numbers, the value is the AS number
+  pub const FOUR_OCTET_AS: u8 = 65;
+  /// Multiprotocol extensions, the value is an AFI and a SAFI
+  pub const MULTIPROTOCOL: u8 = 1;
+  /// Route refresh
+  pub const ROUTE_REFRESH: u8 = 2;
+}
+
+/// Optional parameter of an OPEN message other than capabilities
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpParameter<Span> {
+  /// Parameter type
+  pub parameter_type: u8,
+  /// Parameter value
+  pub value: Span,
+}
+
+/// OPEN message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpOpen<Span> {
+  /// Protocol version, 4
+  pub version: u8,
+  /// AS number of the sender, AS_TRANS when it doesn't fit
+  pub my_as: u16,
+  /// Proposed hold time in seconds
+  pub hold_time: u16,
+  /// BGP identifier of the sender
+  pub identifier: Ipv4Addr,
+  /// Capabilities, of all capabilities parameters
+  pub capabilities: Vec<BgpCapability<Span>>,
+  /// Other optional parameters
+  pub parameters: Vec<BgpParameter<Span>>,
+}
+
+impl<Span> BgpOpen<Span> {
+  /// Optional parameter type of capabilities
+  pub const CAPABILITIES: u8 = 2;
+
+  /// Return the 4-octet AS number of the sender when it's advertised
+  pub fn four_octet_as(&self) -> Option<u32>
+  where
+    Span: AsRef<[u8]>,
+  {
+    self
+      .capabilities
+      .iter()
+      .find(|capability| capability.code == BgpCapability::<()>::FOUR_OCTET_AS)
+      .and_then(|capability| capability.value.as_ref().try_into().ok())
+      .map(u32::from_be_bytes)
+  }
+}
+
+/// IP prefix of withdrawn routes and NLRI, only the significant bytes are
+/// present
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpPrefix<Span> {
+  /// Length of the prefix in bits
+  pub length: u8,
+  /// Significant bytes of the prefix
+  pub prefix: Span,
+}
+
+impl<Span> BgpPrefix<Span> {
+  /// Return the prefix as an IPv4 address, trailing bytes are zero
+  pub fn ipv4(&self) -> Option<Ipv4Addr>
+  where
+    Span: AsRef<[u8]>,
+  {
+    let prefix = self.prefix.as_ref();
+    let mut octets = [0; 4];
+    octets.get_mut(..prefix.len())?.copy_from_slice(prefix);
+    Some(Ipv4Addr::from(octets))
+  }
+}
+
+/// Segment of an AS_PATH attribute
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpAsPathSegment {
+  /// Segment type
+  pub segment_type: u8,
+  /// AS numbers
+  pub asns: Vec<u32>,
+}
+
+impl BgpAsPathSegment {
+  /// Ordered AS numbers
+  pub const AS_SEQUENCE: u8 = 2;
+  /// Unordered AS numbers of an aggregate
+  pub const AS_SET: u8 = 1;
+}
+
+/// Content of a MP_REACH_NLRI attribute
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpMpReach<Span> {
+  /// Address Family Identifier
+  pub afi: u16,
+  /// Subsequent Address Family Identifier
+  pub safi: u8,
+  /// Next hop, its format depend on the address family
+  pub next_hop: Span,
+  /// Reachable prefixes
+  pub nlri: Vec<BgpPrefix<Span>>,
+}
+
+/// Content of a MP_UNREACH_NLRI attribute
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpMpUnreach<Span> {
+  /// Address Family Identifier
+  pub afi: u16,
+  /// Subsequent Address Family Identifier
+  pub safi: u8,
+  /// Withdrawn prefixes
+  pub withdrawn: Vec<BgpPrefix<Span>>,
+}
+
+/// Value of a path attribute, the ones needed to follow routes are decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BgpAttributeValue<Span> {
+  /// ORIGIN, 0 IGP, 1 EGP, 2 INCOMPLETE
+  Origin(u8),
+  /// AS_PATH
+  AsPath(Vec<BgpAsPathSegment>),
+  /// NEXT_HOP
+  NextHop(Ipv4Addr),
+  /// MULTI_EXIT_DISC
+  MultiExitDisc(u32),
+  /// LOCAL_PREF
+  LocalPref(u32),
+  /// MP_REACH_NLRI
+  MpReach(BgpMpReach<Span>),
+  /// MP_UNREACH_NLRI
+  MpUnreach(BgpMpUnreach<Span>),
+  /// Any other attribute
+  Other(Span),
+}
+
+/// Path attribute of an UPDATE message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpPathAttribute<Span> {
+  /// Optional, transitive, partial and extended length flags
+  pub flags: u8,
+  /// Attribute type
+  pub attribute_type: BgpAttributeType,
+  /// Attribute value
+  pub value: BgpAttributeValue<Span>,
+}
+
+impl<Span> BgpPathAttribute<Span> {
+  /// Length field is 2 bytes
+  pub const EXTENDED_LENGTH: u8 = 0x10;
+  /// Optional attribute
+  pub const OPTIONAL: u8 = 0x80;
+  /// Optional transitive attribute not recognized by a router of the path
+  pub const PARTIAL: u8 = 0x20;
+  /// Transitive attribute
+  pub const TRANSITIVE: u8 = 0x40;
+}
+
+/// UPDATE message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpUpdate<Span> {
+  /// Withdrawn IPv4 routes
+  pub withdrawn: Vec<BgpPrefix<Span>>,
+  /// Path attributes
+  pub attributes: Vec<BgpPathAttribute<Span>>,
+  /// Reachable IPv4 prefixes
+  pub nlri: Vec<BgpPrefix<Span>>,
+}
+
+impl<Span> BgpUpdate<Span> {
+  /// Return the value of the first attribute of attribute_type
+  pub fn attribute(&self, attribute_type: BgpAttributeType) -> Option<&BgpAttributeValue<Span>> {
+    self
+      .attributes
+      .iter()
+      .find(|attribute| attribute.attribute_type == attribute_type)
+      .map(|attribute| &attribute.value)
+  }
+}
+
+/// NOTIFICATION message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BgpNotification<Span> {
+  /// Error code
+  pub code: u8,
+  /// Error subcode
+  pub subcode: u8,
+  /// Data, its meaning depend on the code
+  pub data: Span,
+}
+
+/// BGP message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BgpMessage<Span> {
+  /// OPEN
+  Open(BgpOpen<Span>),
+  /// UPDATE
+  Update(BgpUpdate<Span>),
+  /// NOTIFICATION
+  Notification(BgpNotification<Span>),
+  /// KEEPALIVE
+  Keepalive,
+  /// Any other message, type and body
+  Other(BgpMessageType, Span),
+}
+
+impl<Span> BgpMessage<Span> {
+  /// Length of the header of a message
+  pub const HEADER_LEN: usize = 19;
+}
+
+fn length_failure<Token, Stream, Context>(length: usize) -> Parsed<Token, Stream, Context>
+where
+  Context: Contexting<BgpAtom>,
+{
+  Parsed::Failure(Context::new(BgpAtom::Length(length as u16)))
+}
+
+// Parse items until exactly len bytes are consumed, the parser return the
+// number of bytes it consumed with its token
+fn bgp_list<Stream, Context, Parser, Token>(
+  mut stream: Stream, mut len: usize, mut parser: Parser,
+) -> Parsed<Vec<Token>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+  Parser: Parse<Stream, Context, Token = (Token, usize)>,
+{
+  let total = len;
+  let mut list = Vec::new();
+  while len > 0 {
+    let Success {
+      token: (token, consumed),
+      stream: next,
+    } = parser.parse(stream)?;
+    let Some(rest) = len.checked_sub(consumed) else {
+      return length_failure(total);
+    };
+    len = rest;
+    list.push(token);
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: list,
+    stream,
+  }
+}
+
+fn bgp_prefix<Stream, Context>(
+  stream: Stream, max: u8,
+) -> Parsed<(BgpPrefix<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: length,
+    stream,
+  } = octet
+    .try_map(|length| {
+      if length <= max {
+        Ok(length)
+      } else {
+        Err(Context::new(BgpAtom::PrefixLength(length)))
+      }
+    })
+    .parse(stream)?;
+  let len = usize::from(length).div_ceil(8);
+  take(len)
+    .map(|prefix| (BgpPrefix { length, prefix }, 1 + len))
+    .parse(stream)
+}
+
+// prefixes of address families other than IPv4 and IPv6 are not bounded
+fn max_prefix_len(afi: u16) -> u8 {
+  match afi {
+    1 => 32,
+    2 => 128,
+    _ => u8::MAX,
+  }
+}
+
+fn bgp_capability<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(BgpCapability<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: (code, len),
+    stream,
+  } = (octet, octet).parse(stream)?;
+  let len = usize::from(len);
+  take(len)
+    .map(|value| (BgpCapability { code, value }, 2 + len))
+    .parse(stream)
+}
+
+fn bgp_open<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<BgpOpen<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: (version, my_as, hold_time, identifier, parameters_len),
+    mut stream,
+  } = (octet, u16_be, u16_be, u32_be.map(Ipv4Addr::from), octet).parse(stream)?;
+  let mut parameters_len = usize::from(parameters_len);
+  if 10 + parameters_len != len {
+    return length_failure(len);
+  }
+
+  let mut capabilities = Vec::new();
+  let mut parameters = Vec::new();
+  while parameters_len > 0 {
+    let Success {
+      token: (parameter_type, parameter_len),
+      stream: next,
+    } = (octet, octet).parse(stream)?;
+    let parameter_len = usize::from(parameter_len);
+    let Some(rest) = parameters_len.checked_sub(2 + parameter_len) else {
+      return length_failure(len);
+    };
+    parameters_len = rest;
+
+    stream = if parameter_type == BgpOpen::<()>::CAPABILITIES {
+      let Success {
+        token,
+        stream: next,
+      } = bgp_list(next, parameter_len, bgp_capability)?;
+      capabilities.extend(token);
+      next
+    } else {
+      let Success {
+        token: value,
+        stream: next,
+      } = take(parameter_len).parse(next)?;
+      parameters.push(BgpParameter {
+        parameter_type,
+        value,
+      });
+      next
+    };
+  }
+
+  Parsed::Success {
+    token: BgpOpen {
+      version,
+      my_as,
+      hold_time,
+      identifier,
+      capabilities,
+      parameters,
+    },
+    stream,
+  }
+}
+
+fn bgp_as_path_segment<Stream, Context>(
+  stream: Stream, four_octet_as: bool,
+) -> Parsed<(BgpAsPathSegment, usize), Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: (segment_type, count),
+    stream,
+  } = (octet, octet).parse(stream)?;
+  let count = usize::from(count);
+
+  if four_octet_as {
+    u32_be
+      .fold_bounds(count, Vec::new, Acc::acc)
+      .map(|asns| (BgpAsPathSegment { segment_type, asns }, 2 + count * 4))
+      .parse(stream)
+  } else {
+    u16_be
+      .map(u32::from)
+      .fold_bounds(count, Vec::new, Acc::acc)
+      .map(|asns| (BgpAsPathSegment { segment_type, asns }, 2 + count * 2))
+      .parse(stream)
+  }
+}
+
+fn bgp_mp_reach<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<BgpMpReach<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: (afi, safi, next_hop_len),
+    stream,
+  } = (u16_be, octet, octet).parse(stream)?;
+  let next_hop_len = usize::from(next_hop_len);
+  let Some(nlri_len) = len.checked_sub(5 + next_hop_len) else {
+    return length_failure(len);
+  };
+  // next hop is followed by a reserved byte
+  let Success {
+    token: (next_hop, _reserved),
+    stream,
+  } = (take(next_hop_len), octet).parse(stream)?;
+
+  let max = max_prefix_len(afi);
+  bgp_list(stream, nlri_len, |stream| bgp_prefix(stream, max)).map_token(|nlri| BgpMpReach {
+    afi,
+    safi,
+    next_hop,
+    nlri,
+  })
+}
+
+fn bgp_mp_unreach<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<BgpMpUnreach<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: (afi, safi),
+    stream,
+  } = (u16_be, octet).parse(stream)?;
+  let Some(withdrawn_len) = len.checked_sub(3) else {
+    return length_failure(len);
+  };
+
+  let max = max_prefix_len(afi);
+  bgp_list(stream, withdrawn_len, |stream| bgp_prefix(stream, max)).map_token(|withdrawn| {
+    BgpMpUnreach {
+      afi,
+      safi,
+      withdrawn,
+    }
+  })
+}
+
+fn bgp_attribute<Stream, Context>(
+  stream: Stream, four_octet_as: bool,
+) -> Parsed<(BgpPathAttribute<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: (flags, attribute_type),
+    stream,
+  } = (octet, octet.map(BgpAttributeType::new)).parse(stream)?;
+  let extended = flags & BgpPathAttribute::<()>::EXTENDED_LENGTH != 0;
+  let Success { token: len, stream } = if extended {
+    u16_be.parse(stream)?
+  } else {
+    octet.map(u16::from).parse(stream)?
+  };
+  let len = usize::from(len);
+
+  let Success {
+    token: value,
+    stream,
+  } = match (attribute_type, len) {
+    (BgpAttributeType::ORIGIN, 1) => octet.map(BgpAttributeValue::Origin).parse(stream),
+    (BgpAttributeType::AS_PATH, _) => bgp_list(stream, len, |stream| {
+      bgp_as_path_segment(stream, four_octet_as)
+    })
+    .map_token(BgpAttributeValue::AsPath),
+    (BgpAttributeType::NEXT_HOP, 4) => u32_be
+      .map(|next_hop| BgpAttributeValue::NextHop(Ipv4Addr::from(next_hop)))
+      .parse(stream),
+    (BgpAttributeType::MULTI_EXIT_DISC, 4) => {
+      u32_be.map(BgpAttributeValue::MultiExitDisc).parse(stream)
+    }
+    (BgpAttributeType::LOCAL_PREF, 4) => u32_be.map(BgpAttributeValue::LocalPref).parse(stream),
+    (BgpAttributeType::MP_REACH_NLRI, _) => {
+      bgp_mp_reach(stream, len).map_token(BgpAttributeValue::MpReach)
+    }
+    (BgpAttributeType::MP_UNREACH_NLRI, _) => {
+      bgp_mp_unreach(stream, len).map_token(BgpAttributeValue::MpUnreach)
+    }
+    _ => take(len).map(BgpAttributeValue::Other).parse(stream),
+  }?;
+
+  let header_len = if extended { 4 } else { 3 };
+  Parsed::Success {
+    token: (
+      BgpPathAttribute {
+        flags,
+        attribute_type,
+        value,
+      },
+      header_len + len,
+    ),
+    stream,
+  }
+}
+
+fn bgp_update<Stream, Context>(
+  stream: Stream, len: usize, four_octet_as: bool,
+) -> Parsed<BgpUpdate<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: withdrawn_len,
+    stream,
+  } = u16_be.parse(stream)?;
+  let withdrawn_len = usize::from(withdrawn_len);
+  let Success {
+    token: withdrawn,
+    stream,
+  } = bgp_list(stream, withdrawn_len, |stream| bgp_prefix(stream, 32))?;
+
+  let Success {
+    token: attributes_len,
+    stream,
+  } = u16_be.parse(stream)?;
+  let attributes_len = usize::from(attributes_len);
+  let Some(nlri_len) = len.checked_sub(4 + withdrawn_len + attributes_len) else {
+    return length_failure(len);
+  };
+  let Success {
+    token: attributes,
+    stream,
+  } = bgp_list(stream, attributes_len, |stream| {
+    bgp_attribute(stream, four_octet_as)
+  })?;
+
+  bgp_list(stream, nlri_len, |stream| bgp_prefix(stream, 32)).map_token(|nlri| BgpUpdate {
+    withdrawn,
+    attributes,
+    nlri,
+  })
+}
+
+fn bgp_message_as<Stream, Context>(
+  stream: Stream, four_octet_as: bool,
+) -> Parsed<BgpMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  let Success {
+    token: (_marker, length, message_type),
+    stream,
+  } = (
+    octet.fill::<16>().try_map(|marker| {
+      if marker == [0xFF; 16] {
+        Ok(marker)
+      } else {
+        Err(Context::new(BgpAtom::Marker))
+      }
+    }),
+    u16_be,
+    octet.map(BgpMessageType::new),
+  )
+    .parse(stream)?;
+  let Some(len) = usize::from(length).checked_sub(BgpMessage::<()>::HEADER_LEN) else {
+    return length_failure(usize::from(length));
+  };
+
+  match message_type {
+    BgpMessageType::OPEN => bgp_open(stream, len).map_token(BgpMessage::Open),
+    BgpMessageType::UPDATE => bgp_update(stream, len, four_octet_as).map_token(BgpMessage::Update),
+    BgpMessageType::NOTIFICATION => {
+      let Some(data_len) = len.checked_sub(2) else {
+        return length_failure(usize::from(length));
+      };
+      (octet, octet, take(data_len))
+        .map(|(code, subcode, data)| {
+          BgpMessage::Notification(BgpNotification {
+            code,
+            subcode,
+            data,
+          })
+        })
+        .parse(stream)
+    }
+    BgpMessageType::KEEPALIVE if len == 0 => Parsed::Success {
+      token: BgpMessage::Keepalive,
+      stream,
+    },
+    BgpMessageType::KEEPALIVE => length_failure(usize::from(length)),
+    _ => take(len)
+      .map(|body| BgpMessage::Other(message_type, body))
+      .parse(stream),
+  }
+}
+
+/// Parse a BGP message of a session that negotiated 4-octet AS numbers, see
+/// [BgpOpen::four_octet_as]
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn bgp_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<BgpMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  bgp_message_as(stream, true)
+}
+
+/// Parse a BGP message of a session using 2-octet AS numbers in AS_PATH
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn bgp_message_as2<Stream, Context>(
+  stream: Stream,
+) -> Parsed<BgpMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BgpParse<Stream, Context>,
+{
+  bgp_message_as(stream, false)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    BgpAttributeType,
+    BgpAttributeValue,
+    BgpMessage,
+  };
+
+  fn message(message_type: u8, body: &[u8]) -> Vec<u8> {
+    let mut message = vec![0xFF; 16];
+    message.extend_from_slice(&(19 + body.len() as u16).to_be_bytes());
+    message.push(message_type);
+    message.extend_from_slice(body);
+    message
+  }
+
+  #[test]
+  fn bgp_session() {
+    let open = [
+      0x04, 0x5B, 0xA0, 0x00, 0xB4, 0xC0, 0x00, 0x02, 0x01, 0x08, 0x02, 0x06, 0x41, 0x04, 0x00,
+      0x01, 0x00, 0x00,
+    ];
+    let update = [
+      0x00, 0x00, 0x00, 0x19, 0x40, 0x01, 0x01, 0x00, 0x50, 0x02, 0x00, 0x0A, 0x02, 0x02, 0x00,
+      0x01, 0x00, 0x00, 0x00, 0x00, 0xFD, 0xE8, 0x40, 0x03, 0x04, 0xC0, 0x00, 0x02, 0x01, 0x18,
+      0xC6, 0x33, 0x64,
+    ];
+    let mut stream = message(1, &open);
+    stream.extend(message(2, &update));
+    stream.extend(message(4, &[]));
+
+    let Success { token, stream } = super::bgp_message::<_, Ignore>(stream.as_slice()).unwrap();
+    let BgpMessage::Open(open) = token else {
+      panic!("expected an OPEN");
+    };
+    assert_eq!(open.hold_time, 180);
+    assert_eq!(open.identifier, Ipv4Addr::new(192, 0, 2, 1));
+    assert_eq!(open.four_octet_as(), Some(65536));
+
+    let Success { token, stream } = super::bgp_message::<_, Ignore>(stream).unwrap();
+    let BgpMessage::Update(update) = token else {
+      panic!("expected an UPDATE");
+    };
+    let Some(BgpAttributeValue::AsPath(segments)) = update.attribute(BgpAttributeType::AS_PATH)
+    else {
+      panic!("expected an AS_PATH");
+    };
+    assert_eq!(segments[0].asns, [65536, 65000]);
+    assert_eq!(
+      update.attribute(BgpAttributeType::NEXT_HOP),
+      Some(&BgpAttributeValue::NextHop(Ipv4Addr::new(192, 0, 2, 1)))
+    );
+    assert_eq!(update.nlri[0].length, 24);
+    assert_eq!(update.nlri[0].ipv4(), Some(Ipv4Addr::new(198, 51, 100, 0)));
+
+    let Success { token, stream } = super::bgp_message::<_, Ignore>(stream).unwrap();
+    assert_eq!(token, BgpMessage::Keepalive);
+    assert_eq!(stream, b"");
+
+    let keepalive = message(4, &[0x00]);
+    assert!(matches!(
+      super::bgp_message::<_, Ignore>(keepalive.as_slice()),
+      Parsed::Failure(_)
+    ));
+  }
+}
@@ -0,0 +1,663 @@
+//! Handles parsing of the BitTorrent peer wire protocol and of the UDP
+//! tracker protocol
+//! <https://www.bittorrent.org/beps/bep_0003.html>
+//! <https://www.bittorrent.org/beps/bep_0010.html>
+//! <https://www.bittorrent.org/beps/bep_0015.html>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    Ipv4Addr,
+    SocketAddrV4,
+  },
+};
+
+use binator::{
+  base::{
+    all,
+    octet,
+    primitive::{
+      i32_be,
+      u16_be,
+      u32_be,
+      u64_be,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// Protocol string of the handshake
+pub const BITTORRENT_PROTOCOL: &[u8; 19] = b"BitTorrent protocol";
+/// Connection id of the connect request of the UDP tracker protocol
+pub const BITTORRENT_TRACKER_PROTOCOL_ID: u64 = 0x0417_2710_1980;
+
+/// Meta trait for bittorrent combinator
+pub trait BittorrentParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<BittorrentAtom>;
+
+/// Atom produced by bittorrent parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BittorrentAtom {
+  /// When the handshake doesn't start with the protocol string
+  Protocol,
+  /// When the length of a message doesn't match its id
+  Length {
+    /// Message id
+    id: u8,
+    /// Length, id included
+    length: u32,
+  },
+}
+
+impl Display for BittorrentAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BittorrentAtom::Protocol => write!(f, "Protocol: expected BitTorrent protocol"),
+      BittorrentAtom::Length { id, length } => {
+        write!(f, "Length: length {} is invalid for message {}", length, id)
+      }
+    }
+  }
+}
+
+/// Handshake, the first message sent by each peer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BittorrentHandshake {
+  /// Reserved bits, announcing extensions
+  pub reserved: [u8; 8],
+  /// SHA-1 of the info dictionary of the torrent
+  pub info_hash: [u8; 20],
+  /// Peer id, its prefix often identify the client
+  pub peer_id: [u8; 20],
+}
+
+impl BittorrentHandshake {
+  /// Return true if the peer support the DHT
+  pub const fn dht(&self) -> bool {
+    self.reserved[7] & 0x01 != 0
+  }
+
+  /// Return true if the peer support the extension protocol
+  pub const fn extension_protocol(&self) -> bool {
+    self.reserved[5] & 0x10 != 0
+  }
+
+  /// Return true if the peer support the fast extension
+  pub const fn fast(&self) -> bool {
+    self.reserved[7] & 0x04 != 0
+  }
+}
+
+/// Block of a piece, for request and cancel
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BittorrentBlock {
+  /// Piece index
+  pub index: u32,
+  /// Offset in the piece
+  pub begin: u32,
+  /// Length
+  pub length: u32,
+}
+
+/// Peer wire message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BittorrentMessage<Span> {
+  /// Keep-alive, a message of length zero
+  KeepAlive,
+  /// choke
+  Choke,
+  /// unchoke
+  Unchoke,
+  /// interested
+  Interested,
+  /// not interested
+  NotInterested,
+  /// have, index of a piece the peer has
+  Have(u32),
+  /// bitfield, pieces the peer has
+  Bitfield(Span),
+  /// request
+  Request(BittorrentBlock),
+  /// piece
+  Piece {
+    /// Piece index
+    index: u32,
+    /// Offset in the piece
+    begin: u32,
+    /// Data
+    block: Span,
+  },
+  /// cancel
+  Cancel(BittorrentBlock),
+  /// port, the DHT port of the peer
+  Port(Port),
+  /// Message of the extension protocol, a bencoded dictionary follow the id
+  Extended {
+    /// Extended message id, 0 for the extension handshake
+    id: u8,
+    /// Payload
+    payload: Span,
+  },
+  /// Any other message
+  Other {
+    /// Message id
+    id: u8,
+    /// Payload
+    payload: Span,
+  },
+}
+
+struct_variants! {
+  BittorrentTrackerAction, action, u32:
+    /// connect
+    CONNECT => 0,
+    /// announce
+    ANNOUNCE => 1,
+    /// scrape
+    SCRAPE => 2,
+    /// error, only in responses
+    ERROR => 3,
+}
+
+struct_variants! {
+  BittorrentTrackerEvent, event, u32:
+    /// No event, regular announce
+    NONE => 0,
+    /// Download completed
+    COMPLETED => 1,
+    /// Download started
+    STARTED => 2,
+    /// Download stopped
+    STOPPED => 3,
+}
+
+/// Announce request
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BittorrentTrackerAnnounce {
+  /// Info hash of the torrent
+  pub info_hash: [u8; 20],
+  /// Peer id of the client
+  pub peer_id: [u8; 20],
+  /// Bytes downloaded
+  pub downloaded: u64,
+  /// Bytes left
+  pub left: u64,
+  /// Bytes uploaded
+  pub uploaded: u64,
+  /// Event
+  pub event: BittorrentTrackerEvent,
+  /// Address of the client, unspecified for the source address
+  pub ip: Ipv4Addr,
+  /// Key
+  pub key: u32,
+  /// Number of peers wanted, -1 for the default
+  pub num_want: i32,
+  /// Port of the client
+  pub port: Port,
+}
+
+/// Body of a tracker request
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BittorrentTrackerRequestBody<Span> {
+  /// connect
+  Connect,
+  /// announce
+  Announce(BittorrentTrackerAnnounce),
+  /// scrape, info hashes of the torrents
+  Scrape(Vec<[u8; 20]>),
+  /// Any other action
+  Other(BittorrentTrackerAction, Span),
+}
+
+/// UDP tracker request
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BittorrentTrackerRequest<Span> {
+  /// Connection id, [BITTORRENT_TRACKER_PROTOCOL_ID] for connect
+  pub connection_id: u64,
+  /// Transaction id, copied in the response
+  pub transaction_id: u32,
+  /// Body
+  pub body: BittorrentTrackerRequestBody<Span>,
+}
+
+/// Statistics of a torrent in a scrape response
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BittorrentTrackerScrape {
+  /// Number of seeders
+  pub seeders: u32,
+  /// Number of completed downloads
+  pub completed: u32,
+  /// Number of leechers
+  pub leechers: u32,
+}
+
+/// Body of a tracker response
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BittorrentTrackerResponseBody<Span> {
+  /// connect
+  Connect {
+    /// Connection id to use in the following requests
+    connection_id: u64,
+  },
+  /// announce
+  Announce {
+    /// Seconds to wait before the next announce
+    interval: u32,
+    /// Number of leechers
+    leechers: u32,
+    /// Number of seeders
+    seeders: u32,
+    /// Peers
+    peers: Vec<SocketAddrV4>,
+  },
+  /// scrape, in the order of the request
+  Scrape(Vec<BittorrentTrackerScrape>),
+  /// error, a message
+  Error(Span),
+  /// Any other action
+  Other(BittorrentTrackerAction, Span),
+}
+
+/// UDP tracker response
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BittorrentTrackerResponse<Span> {
+  /// Transaction id of the request
+  pub transaction_id: u32,
+  /// Body
+  pub body: BittorrentTrackerResponseBody<Span>,
+}
+
+/// Parse the handshake
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn bittorrent_handshake<Stream, Context>(
+  stream: Stream,
+) -> Parsed<BittorrentHandshake, Stream, Context>
+where
+  (): BittorrentParse<Stream, Context>,
+{
+  (
+    octet.fill::<20>().try_map(|protocol| {
+      if protocol[0] == 19 && protocol[1..] == BITTORRENT_PROTOCOL[..] {
+        Ok(())
+      } else {
+        Err(Context::new(BittorrentAtom::Protocol))
+      }
+    }),
+    octet.fill(),
+    octet.fill(),
+    octet.fill(),
+  )
+    .map(|(_, reserved, info_hash, peer_id)| BittorrentHandshake {
+      reserved,
+      info_hash,
+      peer_id,
+    })
+    .parse(stream)
+}
+
+fn bittorrent_block<Stream, Context>(stream: Stream) -> Parsed<BittorrentBlock, Stream, Context>
+where
+  (): BittorrentParse<Stream, Context>,
+{
+  (u32_be, u32_be, u32_be)
+    .map(|(index, begin, length)| BittorrentBlock {
+      index,
+      begin,
+      length,
+    })
+    .parse(stream)
+}
+
+/// Parse a length prefixed peer wire message
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn bittorrent_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<BittorrentMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BittorrentParse<Stream, Context>,
+{
+  let Success {
+    token: length,
+    stream,
+  } = u32_be.parse(stream)?;
+  if length == 0 {
+    return Parsed::Success {
+      token: BittorrentMessage::KeepAlive,
+      stream,
+    };
+  }
+
+  let Success { token: id, stream } = octet.parse(stream)?;
+  let payload = (length - 1) as usize;
+  let expected = match id {
+    0..=3 => Some(0),
+    4 => Some(4),
+    6 | 8 => Some(12),
+    9 => Some(2),
+    _ => None,
+  };
+  if let Some(expected) = expected {
+    if payload != expected {
+      return Parsed::Failure(Context::new(BittorrentAtom::Length { id, length }));
+    }
+  }
+  if id == 7 && payload < 8 {
+    return Parsed::Failure(Context::new(BittorrentAtom::Length { id, length }));
+  }
+
+  match id {
+    0 => Parsed::Success {
+      token: BittorrentMessage::Choke,
+      stream,
+    },
+    1 => Parsed::Success {
+      token: BittorrentMessage::Unchoke,
+      stream,
+    },
+    2 => Parsed::Success {
+      token: BittorrentMessage::Interested,
+      stream,
+    },
+    3 => Parsed::Success {
+      token: BittorrentMessage::NotInterested,
+      stream,
+    },
+    4 => u32_be.map(BittorrentMessage::Have).parse(stream),
+    5 => take(payload).map(BittorrentMessage::Bitfield).parse(stream),
+    6 => bittorrent_block
+      .map(BittorrentMessage::Request)
+      .parse(stream),
+    7 => (u32_be, u32_be, take(payload - 8))
+      .map(|(index, begin, block)| BittorrentMessage::Piece {
+        index,
+        begin,
+        block,
+      })
+      .parse(stream),
+    8 => bittorrent_block
+      .map(BittorrentMessage::Cancel)
+      .parse(stream),
+    9 => u16_be
+      .map(|port| BittorrentMessage::Port(Port(port)))
+      .parse(stream),
+    20 if payload > 0 => (octet, take(payload - 1))
+      .map(|(id, payload)| BittorrentMessage::Extended { id, payload })
+      .parse(stream),
+    _ => take(payload)
+      .map(|payload| BittorrentMessage::Other { id, payload })
+      .parse(stream),
+  }
+}
+
+/// Parse a UDP tracker request, every action but scrape have a fixed length,
+/// scrape consume the whole stream
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn bittorrent_tracker_request<Stream, Context>(
+  stream: Stream,
+) -> Parsed<BittorrentTrackerRequest<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BittorrentParse<Stream, Context>,
+{
+  let Success {
+    token: (connection_id, action, transaction_id),
+    stream,
+  } = (u64_be, u32_be.map(BittorrentTrackerAction::new), u32_be).parse(stream)?;
+
+  let Success {
+    token: body,
+    stream,
+  } = match action {
+    BittorrentTrackerAction::CONNECT => Success {
+      token: BittorrentTrackerRequestBody::Connect,
+      stream,
+    },
+    BittorrentTrackerAction::ANNOUNCE => (
+      (octet.fill(), octet.fill(), u64_be, u64_be, u64_be),
+      (
+        u32_be.map(BittorrentTrackerEvent::new),
+        u32_be.map(Ipv4Addr::from),
+        u32_be,
+        i32_be,
+        u16_be.map(Port),
+      ),
+    )
+      .map(
+        |((info_hash, peer_id, downloaded, left, uploaded), (event, ip, key, num_want, port))| {
+          BittorrentTrackerRequestBody::Announce(BittorrentTrackerAnnounce {
+            info_hash,
+            peer_id,
+            downloaded,
+            left,
+            uploaded,
+            event,
+            ip,
+            key,
+            num_want,
+            port,
+          })
+        },
+      )
+      .parse(stream)?,
+    BittorrentTrackerAction::SCRAPE => octet
+      .fill()
+      .fold_bounds(.., Vec::new, Acc::acc)
+      .map(BittorrentTrackerRequestBody::Scrape)
+      .parse(stream)?,
+    _ => all
+      .map(|payload| BittorrentTrackerRequestBody::Other(action, payload))
+      .parse(stream)?,
+  };
+
+  Parsed::Success {
+    token: BittorrentTrackerRequest {
+      connection_id,
+      transaction_id,
+      body,
+    },
+    stream,
+  }
+}
+
+/// Parse a UDP tracker response, the lists of announce and scrape consume the
+/// whole stream
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn bittorrent_tracker_response<Stream, Context>(
+  stream: Stream,
+) -> Parsed<BittorrentTrackerResponse<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): BittorrentParse<Stream, Context>,
+{
+  let Success {
+    token: (action, transaction_id),
+    stream,
+  } = (u32_be.map(BittorrentTrackerAction::new), u32_be).parse(stream)?;
+
+  let Success {
+    token: body,
+    stream,
+  } = match action {
+    BittorrentTrackerAction::CONNECT => u64_be
+      .map(|connection_id| BittorrentTrackerResponseBody::Connect { connection_id })
+      .parse(stream)?,
+    BittorrentTrackerAction::ANNOUNCE => (
+      u32_be,
+      u32_be,
+      u32_be,
+      (u32_be, u16_be)
+        .map(|(addr, port)| SocketAddrV4::new(Ipv4Addr::from(addr), port))
+        .fold_bounds(.., Vec::new, Acc::acc),
+    )
+      .map(
+        |(interval, leechers, seeders, peers)| BittorrentTrackerResponseBody::Announce {
+          interval,
+          leechers,
+          seeders,
+          peers,
+        },
+      )
+      .parse(stream)?,
+    BittorrentTrackerAction::SCRAPE => (u32_be, u32_be, u32_be)
+      .map(|(seeders, completed, leechers)| BittorrentTrackerScrape {
+        seeders,
+        completed,
+        leechers,
+      })
+      .fold_bounds(.., Vec::new, Acc::acc)
+      .map(BittorrentTrackerResponseBody::Scrape)
+      .parse(stream)?,
+    BittorrentTrackerAction::ERROR => all
+      .map(BittorrentTrackerResponseBody::Error)
+      .parse(stream)?,
+    _ => all
+      .map(|payload| BittorrentTrackerResponseBody::Other(action, payload))
+      .parse(stream)?,
+  };
+
+  Parsed::Success {
+    token: BittorrentTrackerResponse {
+      transaction_id,
+      body,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    Ipv4Addr,
+    SocketAddrV4,
+  };
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    BittorrentBlock,
+    BittorrentMessage,
+    BittorrentTrackerRequestBody,
+    BittorrentTrackerResponseBody,
+    BITTORRENT_PROTOCOL,
+    BITTORRENT_TRACKER_PROTOCOL_ID,
+  };
+
+  #[test]
+  fn bittorrent() {
+    let mut handshake = vec![19];
+    handshake.extend_from_slice(BITTORRENT_PROTOCOL);
+    handshake.extend_from_slice(&[0, 0, 0, 0, 0, 0x10, 0, 0x05]);
+    handshake.extend_from_slice(&[0xAA; 20]);
+    handshake.extend_from_slice(b"-qB4500-123456789012");
+    let Success { token, stream } =
+      super::bittorrent_handshake::<_, Ignore>(&handshake[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert!(token.extension_protocol() && token.dht() && token.fast());
+    assert_eq!(&token.peer_id[..8], b"-qB4500-");
+
+    let messages = [
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0D, 0x06, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+      0x40, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x0B, 0x07, 0x00, 0x00, 0x00, 0x01,
+      0x00, 0x00, 0x40, 0x00, 0xDE, 0xAD,
+    ];
+    let Success { token, stream } = super::bittorrent_message::<_, Ignore>(&messages[..]).unwrap();
+    assert_eq!(token, BittorrentMessage::KeepAlive);
+    let Success { token, stream } = super::bittorrent_message::<_, Ignore>(stream).unwrap();
+    assert_eq!(
+      token,
+      BittorrentMessage::Request(BittorrentBlock {
+        index: 1,
+        begin: 0x4000,
+        length: 0x4000,
+      })
+    );
+    let Success { token, stream } = super::bittorrent_message::<_, Ignore>(stream).unwrap();
+    assert_eq!(
+      token,
+      BittorrentMessage::Piece {
+        index: 1,
+        begin: 0x4000,
+        block: &[0xDE, 0xAD][..],
+      }
+    );
+    assert_eq!(stream, b"");
+
+    assert!(matches!(
+      super::bittorrent_message::<_, Ignore>(&[0x00, 0x00, 0x00, 0x02, 0x04, 0x00][..]),
+      Parsed::Failure(_)
+    ));
+
+    let connect = [
+      0x00, 0x00, 0x04, 0x17, 0x27, 0x10, 0x19, 0x80, 0x00, 0x00, 0x00, 0x00, 0x12, 0x34, 0x56,
+      0x78,
+    ];
+    let Success { token, .. } =
+      super::bittorrent_tracker_request::<_, Ignore>(&connect[..]).unwrap();
+    assert_eq!(token.connection_id, BITTORRENT_TRACKER_PROTOCOL_ID);
+    assert_eq!(token.transaction_id, 0x12345678);
+    assert_eq!(token.body, BittorrentTrackerRequestBody::Connect);
+
+    let announce = [
+      0x00, 0x00, 0x00, 0x01, 0x12, 0x34, 0x56, 0x78, 0x00, 0x00, 0x07, 0x08, 0x00, 0x00, 0x00,
+      0x01, 0x00, 0x00, 0x00, 0x02, 0xC0, 0x00, 0x02, 0x01, 0x1A, 0xE1,
+    ];
+    let Success { token, stream } =
+      super::bittorrent_tracker_response::<_, Ignore>(&announce[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(
+      token.body,
+      BittorrentTrackerResponseBody::Announce {
+        interval: 1800,
+        leechers: 1,
+        seeders: 2,
+        peers: vec![SocketAddrV4::new(Ipv4Addr::new(192, 0, 2, 1), 6881)],
+      }
+    );
+  }
+}
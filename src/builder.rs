@@ -0,0 +1,657 @@
+//! Write headers to their wire format and build packets
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
+use crate::{
+  transport_checksum,
+  EtherType,
+  EtherTypeOrLength,
+  EthernetFrame,
+  IPProtocol,
+  IPv4Header,
+  IPv6Header,
+  LlcControl,
+  Port,
+  SoftwareChecksum,
+  TcpFlags,
+  TcpHeader,
+  UdpHeader,
+  VlanTag,
+};
+
+/// Header that can be written in its wire format
+pub trait WriteHeader {
+  /// Return the number of bytes write_header append
+  fn header_len(&self) -> usize;
+
+  /// Append the wire representation of the header to buf, fields are written
+  /// as they are, nothing is computed
+  fn write_header(&self, buf: &mut Vec<u8>);
+}
+
+impl WriteHeader for EthernetFrame {
+  fn header_len(&self) -> usize {
+    self.len()
+  }
+
+  fn write_header(&self, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&self.destination);
+    buf.extend_from_slice(&self.source);
+    for vlan in &self.vlans {
+      buf.extend_from_slice(&vlan.tpid.ether_type().to_be_bytes());
+      buf.extend_from_slice(&vlan.tci().to_be_bytes());
+    }
+    let ether_type = match self.ether_type {
+      EtherTypeOrLength::EtherType(ether_type) => ether_type.ether_type(),
+      EtherTypeOrLength::Length(length) => length,
+    };
+    buf.extend_from_slice(&ether_type.to_be_bytes());
+    if let Some(llc) = self.llc {
+      buf.extend_from_slice(&[llc.dsap, llc.ssap]);
+      match llc.control {
+        LlcControl::Information(control) | LlcControl::Supervisory(control) => {
+          buf.extend_from_slice(&control.to_be_bytes())
+        }
+        LlcControl::Unnumbered(control) => buf.push(control),
+      }
+      if let Some(snap) = llc.snap {
+        buf.extend_from_slice(&snap.oui);
+        buf.extend_from_slice(&snap.protocol_id.to_be_bytes());
+      }
+    }
+  }
+}
+
+impl<Span> WriteHeader for IPv4Header<Span>
+where
+  Span: AsRef<[u8]>,
+{
+  fn header_len(&self) -> usize {
+    Self::MIN_LEN + self.options.as_ref().len()
+  }
+
+  fn write_header(&self, buf: &mut Vec<u8>) {
+    let [offset_0, offset_1] = self.fragment_offset.to_be_bytes();
+    buf.extend_from_slice(&[self.version << 4u8 | self.ihl, self.tos]);
+    buf.extend_from_slice(&self.length.to_be_bytes());
+    buf.extend_from_slice(&self.id.to_be_bytes());
+    buf.extend_from_slice(&[
+      self.flags << 5u8 | offset_0,
+      offset_1,
+      self.ttl,
+      self.protocol.protocol(),
+    ]);
+    buf.extend_from_slice(&self.chksum.to_be_bytes());
+    buf.extend_from_slice(&self.source_addr.octets());
+    buf.extend_from_slice(&self.dest_addr.octets());
+    buf.extend_from_slice(self.options.as_ref());
+  }
+}
+
+impl WriteHeader for IPv6Header {
+  fn header_len(&self) -> usize {
+    Self::LEN
+  }
+
+  fn write_header(&self, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&self.to_bytes());
+  }
+}
+
+impl<Span> WriteHeader for TcpHeader<Span>
+where
+  Span: AsRef<[u8]>,
+{
+  fn header_len(&self) -> usize {
+    Self::MIN_LEN + self.options.as_ref().len()
+  }
+
+  fn write_header(&self, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&self.source_port.get().to_be_bytes());
+    buf.extend_from_slice(&self.dest_port.get().to_be_bytes());
+    buf.extend_from_slice(&self.sequence_no.to_be_bytes());
+    buf.extend_from_slice(&self.ack_no.to_be_bytes());
+    buf.extend_from_slice(&self.flags.to_wire(self.data_offset).to_be_bytes());
+    buf.extend_from_slice(&self.window.to_be_bytes());
+    buf.extend_from_slice(&self.checksum.to_be_bytes());
+    buf.extend_from_slice(&self.urgent_pointer.to_be_bytes());
+    buf.extend_from_slice(self.options.as_ref());
+  }
+}
+
+impl WriteHeader for UdpHeader {
+  fn header_len(&self) -> usize {
+    Self::LEN
+  }
+
+  fn write_header(&self, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&self.to_bytes());
+  }
+}
+
+/// Reason a builder refused to build a packet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+  /// The packet would be longer than its length field can tell
+  Length(usize),
+  /// Options are longer than the header allow, padding included
+  Options(usize),
+}
+
+impl Display for BuildError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      BuildError::Length(len) => write!(f, "Length: packet length {} is too long", len),
+      BuildError::Options(len) => write!(f, "Options: options length {} is too long", len),
+    }
+  }
+}
+
+fn padded(options: &[u8], max: usize) -> Result<Vec<u8>, BuildError> {
+  let len = options.len().next_multiple_of(4);
+  if len > max {
+    return Err(BuildError::Options(len));
+  }
+  let mut options = options.to_vec();
+  options.resize(len, 0);
+  Ok(options)
+}
+
+/// Build Ethernet II frames
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EthernetFrameBuilder {
+  destination: [u8; 6],
+  source: [u8; 6],
+  ether_type: EtherType,
+  vlans: Vec<VlanTag>,
+}
+
+impl EthernetFrameBuilder {
+  /// Minimum length of a frame without the frame check sequence, shorter
+  /// frames are padded with zeros
+  pub const MIN_FRAME_LEN: usize = 60;
+
+  /// Return a builder of untagged frames
+  pub const fn new(destination: [u8; 6], source: [u8; 6], ether_type: EtherType) -> Self {
+    Self {
+      destination,
+      source,
+      ether_type,
+      vlans: Vec::new(),
+    }
+  }
+
+  /// Tag frames with an 802.1Q or 802.1ad tag, each call add a tag inside
+  /// the previous ones
+  pub fn vlan(mut self, vlan: VlanTag) -> Self {
+    self.vlans.push(vlan);
+    self
+  }
+
+  /// Return the header of a frame
+  pub fn header(&self) -> EthernetFrame {
+    EthernetFrame {
+      destination: self.destination,
+      source: self.source,
+      ether_type: EtherTypeOrLength::EtherType(self.ether_type),
+      vlans: self.vlans.clone(),
+      llc: None,
+    }
+  }
+
+  /// Return a frame carrying payload, without frame check sequence, see
+  /// [crate::append_fcs]
+  pub fn build(&self, payload: &[u8]) -> Vec<u8> {
+    let header = self.header();
+    let mut frame = Vec::with_capacity(Self::MIN_FRAME_LEN.max(header.len() + payload.len()));
+    header.write_header(&mut frame);
+    frame.extend_from_slice(payload);
+    if frame.len() < Self::MIN_FRAME_LEN {
+      frame.resize(Self::MIN_FRAME_LEN, 0);
+    }
+    frame
+  }
+}
+
+/// Build IPv4 packets, length, IHL and checksum are computed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ipv4HeaderBuilder {
+  tos: u8,
+  id: u16,
+  flags: u8,
+  fragment_offset: u16,
+  ttl: u8,
+  protocol: IPProtocol,
+  source_addr: Ipv4Addr,
+  dest_addr: Ipv4Addr,
+  options: Vec<u8>,
+}
+
+impl Ipv4HeaderBuilder {
+  /// Return a builder of unfragmented packets with a TTL of 64
+  pub const fn new(source_addr: Ipv4Addr, dest_addr: Ipv4Addr, protocol: IPProtocol) -> Self {
+    Self {
+      tos: 0,
+      id: 0,
+      flags: 0b010,
+      fragment_offset: 0,
+      ttl: 64,
+      protocol,
+      source_addr,
+      dest_addr,
+      options: Vec::new(),
+    }
+  }
+
+  /// Set the type of service field
+  pub const fn tos(mut self, tos: u8) -> Self {
+    self.tos = tos;
+    self
+  }
+
+  /// Set the identification field
+  pub const fn id(mut self, id: u16) -> Self {
+    self.id = id;
+    self
+  }
+
+  /// Set the 3 bits flags field and the fragment offset
+  pub const fn fragment(mut self, flags: u8, fragment_offset: u16) -> Self {
+    self.flags = flags & 0b111;
+    self.fragment_offset = fragment_offset & 0x1FFF;
+    self
+  }
+
+  /// Set the time to live field
+  pub const fn ttl(mut self, ttl: u8) -> Self {
+    self.ttl = ttl;
+    self
+  }
+
+  /// Set the raw options, they are padded with End of Options
+  pub fn options(mut self, options: &[u8]) -> Self {
+    self.options = options.to_vec();
+    self
+  }
+
+  /// Return the header of a packet carrying payload_len bytes
+  pub fn header(&self, payload_len: usize) -> Result<IPv4Header<Vec<u8>>, BuildError> {
+    let options = padded(
+      &self.options,
+      IPv4Header::<()>::MAX_LEN - IPv4Header::<()>::MIN_LEN,
+    )?;
+    let len = IPv4Header::<()>::MIN_LEN + options.len() + payload_len;
+    let length = u16::try_from(len).map_err(|_| BuildError::Length(len))?;
+
+    let mut header = IPv4Header {
+      version: 4,
+      ihl: ((IPv4Header::<()>::MIN_LEN + options.len()) / 4) as u8,
+      tos: self.tos,
+      length,
+      id: self.id,
+      flags: self.flags,
+      fragment_offset: self.fragment_offset,
+      ttl: self.ttl,
+      protocol: self.protocol,
+      chksum: 0,
+      source_addr: self.source_addr,
+      dest_addr: self.dest_addr,
+      options,
+    };
+    header.chksum = header.compute_checksum();
+    Ok(header)
+  }
+
+  /// Return a packet carrying payload
+  pub fn build(&self, payload: &[u8]) -> Result<Vec<u8>, BuildError> {
+    let header = self.header(payload.len())?;
+    let mut packet = Vec::with_capacity(usize::from(header.length));
+    header.write_header(&mut packet);
+    packet.extend_from_slice(payload);
+    Ok(packet)
+  }
+}
+
+/// Build IPv6 packets, payload length is computed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ipv6HeaderBuilder {
+  ds: u8,
+  ecn: u8,
+  flow_label: u32,
+  next_header: IPProtocol,
+  hop_limit: u8,
+  source_addr: Ipv6Addr,
+  dest_addr: Ipv6Addr,
+}
+
+impl Ipv6HeaderBuilder {
+  /// Return a builder of packets with a hop limit of 64
+  pub const fn new(source_addr: Ipv6Addr, dest_addr: Ipv6Addr, next_header: IPProtocol) -> Self {
+    Self {
+      ds: 0,
+      ecn: 0,
+      flow_label: 0,
+      next_header,
+      hop_limit: 64,
+      source_addr,
+      dest_addr,
+    }
+  }
+
+  /// Set the differentiated services and ECN fields
+  pub const fn traffic_class(mut self, ds: u8, ecn: u8) -> Self {
+    self.ds = ds & 0x3F;
+    self.ecn = ecn & 0b11;
+    self
+  }
+
+  /// Set the 20 bits flow label
+  pub const fn flow_label(mut self, flow_label: u32) -> Self {
+    self.flow_label = flow_label & 0xF_FFFF;
+    self
+  }
+
+  /// Set the hop limit field
+  pub const fn hop_limit(mut self, hop_limit: u8) -> Self {
+    self.hop_limit = hop_limit;
+    self
+  }
+
+  /// Return the header of a packet carrying payload_len bytes
+  pub fn header(&self, payload_len: usize) -> Result<IPv6Header, BuildError> {
+    let length = u16::try_from(payload_len).map_err(|_| BuildError::Length(payload_len))?;
+
+    Ok(IPv6Header {
+      version: 6,
+      ds: self.ds,
+      ecn: self.ecn,
+      flow_label: self.flow_label,
+      length,
+      next_header: self.next_header,
+      hop_limit: self.hop_limit,
+      source_addr: self.source_addr,
+      dest_addr: self.dest_addr,
+    })
+  }
+
+  /// Return a packet carrying payload
+  pub fn build(&self, payload: &[u8]) -> Result<Vec<u8>, BuildError> {
+    let header = self.header(payload.len())?;
+    let mut packet = Vec::with_capacity(IPv6Header::LEN + payload.len());
+    header.write_header(&mut packet);
+    packet.extend_from_slice(payload);
+    Ok(packet)
+  }
+}
+
+/// Build TCP segments, data offset and checksum are computed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TcpHeaderBuilder {
+  source_port: Port,
+  dest_port: Port,
+  sequence_no: u32,
+  ack_no: u32,
+  flags: TcpFlags,
+  window: u16,
+  urgent_pointer: u16,
+  options: Vec<u8>,
+}
+
+impl TcpHeaderBuilder {
+  /// Return a builder of segments without flags and a window of 65535
+  pub fn new(source_port: Port, dest_port: Port) -> Self {
+    Self {
+      source_port,
+      dest_port,
+      sequence_no: 0,
+      ack_no: 0,
+      flags: TcpFlags::default(),
+      window: u16::MAX,
+      urgent_pointer: 0,
+      options: Vec::new(),
+    }
+  }
+
+  /// Set the sequence number
+  pub const fn sequence_no(mut self, sequence_no: u32) -> Self {
+    self.sequence_no = sequence_no;
+    self
+  }
+
+  /// Set the acknowledgment number
+  pub const fn ack_no(mut self, ack_no: u32) -> Self {
+    self.ack_no = ack_no;
+    self
+  }
+
+  /// Set the flags
+  pub fn flags(mut self, flags: TcpFlags) -> Self {
+    self.flags = flags;
+    self
+  }
+
+  /// Set the window field
+  pub const fn window(mut self, window: u16) -> Self {
+    self.window = window;
+    self
+  }
+
+  /// Set the urgent pointer field
+  pub const fn urgent_pointer(mut self, urgent_pointer: u16) -> Self {
+    self.urgent_pointer = urgent_pointer;
+    self
+  }
+
+  /// Set the raw options, they are padded with End of Option
+  pub fn options(mut self, options: &[u8]) -> Self {
+    self.options = options.to_vec();
+    self
+  }
+
+  /// Return a segment carrying payload, the checksum include the pseudo
+  /// header of source and dest that must be of the same family
+  pub fn build(&self, source: IpAddr, dest: IpAddr, payload: &[u8]) -> Result<Vec<u8>, BuildError> {
+    let options = padded(
+      &self.options,
+      TcpHeader::<()>::MAX_LEN - TcpHeader::<()>::MIN_LEN,
+    )?;
+    let header_len = TcpHeader::<()>::MIN_LEN + options.len();
+    let len = header_len + payload.len();
+    if source.is_ipv4() && len > usize::from(u16::MAX) {
+      return Err(BuildError::Length(len));
+    }
+
+    let mut header = TcpHeader {
+      source_port: self.source_port,
+      dest_port: self.dest_port,
+      sequence_no: self.sequence_no,
+      ack_no: self.ack_no,
+      // options are at most 40 bytes so this fit in 4 bits
+      data_offset: (header_len / 4) as u8,
+      flags: self.flags.clone(),
+      window: self.window,
+      checksum: 0,
+      urgent_pointer: self.urgent_pointer,
+      options,
+    };
+
+    let mut segment = Vec::with_capacity(len);
+    header.write_header(&mut segment);
+    segment.extend_from_slice(payload);
+    header.checksum =
+      transport_checksum(&SoftwareChecksum, source, dest, IPProtocol::TCP, &segment);
+    segment[16..18].copy_from_slice(&header.checksum.to_be_bytes());
+    Ok(segment)
+  }
+}
+
+/// Build UDP datagrams, length and checksum are computed
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UdpHeaderBuilder {
+  source_port: Port,
+  dest_port: Port,
+}
+
+impl UdpHeaderBuilder {
+  /// Return a builder of datagrams
+  pub const fn new(source_port: Port, dest_port: Port) -> Self {
+    Self {
+      source_port,
+      dest_port,
+    }
+  }
+
+  /// Return a datagram carrying payload, the checksum include the pseudo
+  /// header of source and dest that must be of the same family
+  pub fn build(&self, source: IpAddr, dest: IpAddr, payload: &[u8]) -> Result<Vec<u8>, BuildError> {
+    let len = UdpHeader::LEN + payload.len();
+    let length = u16::try_from(len).map_err(|_| BuildError::Length(len))?;
+
+    let mut header = UdpHeader {
+      source_port: self.source_port,
+      dest_port: self.dest_port,
+      length,
+      checksum: 0,
+    };
+
+    let mut datagram = Vec::with_capacity(len);
+    header.write_header(&mut datagram);
+    datagram.extend_from_slice(payload);
+    header.checksum =
+      match transport_checksum(&SoftwareChecksum, source, dest, IPProtocol::UDP, &datagram) {
+        // zero means no checksum, it's transmitted as all ones
+        0 => 0xFFFF,
+        checksum => checksum,
+      };
+    datagram[6..8].copy_from_slice(&header.checksum.to_be_bytes());
+    Ok(datagram)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+  };
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::{
+    EthernetFrameBuilder,
+    Ipv4HeaderBuilder,
+    Ipv6HeaderBuilder,
+    TcpHeaderBuilder,
+    UdpHeaderBuilder,
+    WriteHeader,
+  };
+  use crate::{
+    ethernet_frame,
+    ipv4_header_checked,
+    verify_tcp_checksum,
+    verify_udp_checksum,
+    EtherType,
+    IPProtocol,
+    IPv6Header,
+    Port,
+    SoftwareChecksum,
+    TcpFlags,
+  };
+
+  #[test]
+  fn build() {
+    let source = Ipv4Addr::new(192, 168, 0, 108);
+    let dest = Ipv4Addr::new(208, 97, 177, 124);
+    let mut flags = TcpFlags::default();
+    flags.set_syn(true);
+
+    let tcp = TcpHeaderBuilder::new(Port(45250), Port(80))
+      .sequence_no(42)
+      .flags(flags)
+      .options(&[0x02, 0x04, 0x05, 0xB4, 0x01])
+      .build(source.into(), dest.into(), b"GET")
+      .unwrap();
+    let ip = Ipv4HeaderBuilder::new(source, dest, IPProtocol::TCP)
+      .id(7)
+      .build(&tcp)
+      .unwrap();
+    let frame = EthernetFrameBuilder::new([0xFF; 6], [0x02; 6], EtherType::IPV4).build(&ip);
+    assert_eq!(frame.len(), 14 + 20 + 28 + 3);
+
+    let Parsed::Success {
+      token: ethernet,
+      stream,
+    } = ethernet_frame::<_, Ignore>(frame.as_slice())
+    else {
+      panic!("ethernet frame");
+    };
+    assert_eq!(ethernet.header_len(), 14);
+    let Parsed::Success {
+      token: ipv4,
+      stream,
+    } = ipv4_header_checked::<_, Ignore>(stream)
+    else {
+      panic!("ipv4 header");
+    };
+    assert_eq!(usize::from(ipv4.length), ip.len());
+    let Parsed::Success {
+      token: header,
+      stream: payload,
+    } = crate::tcp_header::<_, Ignore>(stream)
+    else {
+      panic!("tcp header");
+    };
+    assert!(header.is_syn() && !header.is_ack());
+    assert_eq!(header.data_offset, 7);
+    assert_eq!(header.header_len(), 28);
+    assert_eq!(header.payload_len(&ipv4), Some(3));
+    assert_eq!(
+      header.options,
+      &[0x02, 0x04, 0x05, 0xB4, 0x01, 0x00, 0x00, 0x00]
+    );
+    assert_eq!(payload, b"GET");
+    assert!(verify_tcp_checksum(
+      &SoftwareChecksum,
+      &ipv4,
+      &header,
+      payload
+    ));
+
+    let source = Ipv6Addr::LOCALHOST;
+    let udp = UdpHeaderBuilder::new(Port(53), Port(53))
+      .build(IpAddr::V6(source), IpAddr::V6(source), &[42])
+      .unwrap();
+    let ip = Ipv6HeaderBuilder::new(source, source, IPProtocol::UDP)
+      .build(&udp)
+      .unwrap();
+    let ipv6 = IPv6Header::from_bytes(ip[..IPv6Header::LEN].try_into().unwrap()).unwrap();
+    assert_eq!(usize::from(ipv6.length), udp.len());
+    let Parsed::Success {
+      token: header,
+      stream: payload,
+    } = crate::udp_header::<_, Ignore>(&ip[IPv6Header::LEN..])
+    else {
+      panic!("udp header");
+    };
+    assert!(verify_udp_checksum(
+      &SoftwareChecksum,
+      &ipv6,
+      &header,
+      payload
+    ));
+  }
+}
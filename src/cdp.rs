@@ -0,0 +1,421 @@
+//! Handles parsing of Cisco Discovery Protocol packets, carried by SNAP with
+//! [crate::SnapHeader::OUI_CISCO] and [CDP_PROTOCOL_ID]
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+/// Protocol id of CDP in the SNAP header
+pub const CDP_PROTOCOL_ID: u16 = 0x2000;
+/// Destination MAC of CDP packets
+pub const CDP_MULTICAST: [u8; 6] = [0x01, 0x00, 0x0C, 0xCC, 0xCC, 0xCC];
+
+/// Meta trait for cdp combinator
+pub trait CdpParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  <Stream as Streaming>::Span: AsRef<[u8]>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<CdpAtom>;
+
+/// Atom produced by cdp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CdpAtom {
+  /// When a TLV length is too short for its header or its content
+  Length(u16),
+}
+
+impl Display for CdpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      CdpAtom::Length(length) => write!(f, "Length: TLV length {} is too short", length),
+    }
+  }
+}
+
+struct_variants! {
+  CdpTlvType, tlv_type, u16:
+    /// Device-ID, host name of the device
+    DEVICE_ID => 0x0001,
+    /// Addresses of the interface
+    ADDRESSES => 0x0002,
+    /// Port-ID, name of the interface
+    PORT_ID => 0x0003,
+    /// Capabilities
+    CAPABILITIES => 0x0004,
+    /// Software Version
+    SOFTWARE_VERSION => 0x0005,
+    /// Platform, model of the device
+    PLATFORM => 0x0006,
+    /// VTP Management Domain
+    VTP_DOMAIN => 0x0009,
+    /// Native VLAN
+    NATIVE_VLAN => 0x000A,
+    /// Duplex
+    DUPLEX => 0x000B,
+    /// Management Addresses
+    MANAGEMENT_ADDRESSES => 0x0016,
+}
+
+/// Address of an Addresses TLV
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CdpAddress<Span> {
+  /// IPv4 address
+  Ipv4(Ipv4Addr),
+  /// IPv6 address
+  Ipv6(Ipv6Addr),
+  /// Any other protocol
+  Other {
+    /// Protocol type, 1 for NLPID and 2 for 802.2
+    protocol_type: u8,
+    /// Protocol
+    protocol: Span,
+    /// Address
+    address: Span,
+  },
+}
+
+/// Value of a TLV
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CdpValue<Span> {
+  /// Device-ID, Port-ID, Software Version, Platform and VTP Management Domain
+  Text(Span),
+  /// Addresses and Management Addresses
+  Addresses(Vec<CdpAddress<Span>>),
+  /// Capabilities, see the CAPABILITY constants of [CdpPacket]
+  Capabilities(u32),
+  /// Native VLAN
+  NativeVlan(u16),
+  /// Duplex, true for full duplex
+  Duplex(bool),
+  /// Any other TLV or a TLV with an unexpected length
+  Other(Span),
+}
+
+/// TLV
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CdpTlv<Span> {
+  /// Type
+  pub tlv_type: CdpTlvType,
+  /// Value
+  pub value: CdpValue<Span>,
+}
+
+/// CDP packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CdpPacket<Span> {
+  /// Version
+  pub version: u8,
+  /// Seconds the information must be kept
+  pub ttl: u8,
+  /// Checksum
+  pub checksum: u16,
+  /// TLVs in order
+  pub tlvs: Vec<CdpTlv<Span>>,
+}
+
+impl<Span> CdpPacket<Span> {
+  /// The device is a host
+  pub const CAPABILITY_HOST: u32 = 0x0010;
+  /// The device is IGMP capable
+  pub const CAPABILITY_IGMP: u32 = 0x0020;
+  /// The device is a phone
+  pub const CAPABILITY_PHONE: u32 = 0x0080;
+  /// The device is a repeater
+  pub const CAPABILITY_REPEATER: u32 = 0x0040;
+  /// The device is a router
+  pub const CAPABILITY_ROUTER: u32 = 0x0001;
+  /// The device is a source route bridge
+  pub const CAPABILITY_SOURCE_ROUTE_BRIDGE: u32 = 0x0004;
+  /// The device is a switch
+  pub const CAPABILITY_SWITCH: u32 = 0x0008;
+  /// The device is a transparent bridge
+  pub const CAPABILITY_TRANSPARENT_BRIDGE: u32 = 0x0002;
+
+  /// Return the value of the first TLV of type tlv_type
+  pub fn tlv(&self, tlv_type: CdpTlvType) -> Option<&CdpValue<Span>> {
+    self
+      .tlvs
+      .iter()
+      .find(|tlv| tlv.tlv_type == tlv_type)
+      .map(|tlv| &tlv.value)
+  }
+
+  fn text(&self, tlv_type: CdpTlvType) -> Option<&Span> {
+    match self.tlv(tlv_type)? {
+      CdpValue::Text(text) => Some(text),
+      _ => None,
+    }
+  }
+
+  /// Return the Device-ID
+  pub fn device_id(&self) -> Option<&Span> {
+    self.text(CdpTlvType::DEVICE_ID)
+  }
+
+  /// Return the Port-ID
+  pub fn port_id(&self) -> Option<&Span> {
+    self.text(CdpTlvType::PORT_ID)
+  }
+
+  /// Return the Software Version
+  pub fn software_version(&self) -> Option<&Span> {
+    self.text(CdpTlvType::SOFTWARE_VERSION)
+  }
+
+  /// Return the Platform
+  pub fn platform(&self) -> Option<&Span> {
+    self.text(CdpTlvType::PLATFORM)
+  }
+
+  /// Return the addresses of the interface
+  pub fn addresses(&self) -> Option<&[CdpAddress<Span>]> {
+    match self.tlv(CdpTlvType::ADDRESSES)? {
+      CdpValue::Addresses(addresses) => Some(addresses),
+      _ => None,
+    }
+  }
+
+  /// Return the Capabilities
+  pub fn capabilities(&self) -> Option<u32> {
+    match self.tlv(CdpTlvType::CAPABILITIES)? {
+      CdpValue::Capabilities(capabilities) => Some(*capabilities),
+      _ => None,
+    }
+  }
+
+  /// Return the Native VLAN
+  pub fn native_vlan(&self) -> Option<u16> {
+    match self.tlv(CdpTlvType::NATIVE_VLAN)? {
+      CdpValue::NativeVlan(vlan) => Some(*vlan),
+      _ => None,
+    }
+  }
+}
+
+// NLPID of IP
+const NLPID_IP: u8 = 0xCC;
+// 802.2 header of IPv6
+const LLC_IPV6: &[u8] = &[0xAA, 0xAA, 0x03, 0x00, 0x00, 0x00, 0x86, 0xDD];
+
+// address and its length
+fn cdp_address<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(CdpAddress<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  (): CdpParse<Stream, Context>,
+{
+  let Success {
+    token: (protocol_type, protocol),
+    stream,
+  } = (octet, octet.and_then(|len| take(usize::from(len)))).parse(stream)?;
+  let Success {
+    token: address,
+    stream,
+  } = u16_be
+    .and_then(|len| take(usize::from(len)))
+    .parse(stream)?;
+  let consumed = 4 + protocol.as_ref().len() + address.as_ref().len();
+
+  let address = match (protocol_type, protocol.as_ref(), address.as_ref()) {
+    (1, [NLPID_IP], &[a, b, c, d]) => CdpAddress::Ipv4(Ipv4Addr::new(a, b, c, d)),
+    (2, LLC_IPV6, addr) if addr.len() == 16 => {
+      let mut octets = [0; 16];
+      octets.copy_from_slice(addr);
+      CdpAddress::Ipv6(Ipv6Addr::from(octets))
+    }
+    _ => CdpAddress::Other {
+      protocol_type,
+      protocol,
+      address,
+    },
+  };
+
+  Parsed::Success {
+    token: (address, consumed),
+    stream,
+  }
+}
+
+fn cdp_addresses<Stream, Context>(
+  stream: Stream, length: u16,
+) -> Parsed<CdpValue<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): CdpParse<Stream, Context>,
+{
+  let Success {
+    token: count,
+    mut stream,
+  } = u32_be.parse(stream)?;
+
+  // length includes the TLV header and the count
+  let mut rest = usize::from(length).saturating_sub(8);
+  let mut addresses = Vec::new();
+  for _ in 0..count {
+    let Success {
+      token: (address, consumed),
+      stream: next,
+    } = cdp_address(stream)?;
+    let Some(next_rest) = rest.checked_sub(consumed) else {
+      return Parsed::Failure(Context::new(CdpAtom::Length(length)));
+    };
+    addresses.push(address);
+    rest = next_rest;
+    stream = next;
+  }
+
+  take(rest)
+    .parse(stream)
+    .map_token(|_| CdpValue::Addresses(addresses))
+}
+
+fn cdp_tlv<Stream, Context>(
+  stream: Stream,
+) -> Parsed<CdpTlv<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): CdpParse<Stream, Context>,
+{
+  let Success {
+    token: (tlv_type, length),
+    stream,
+  } = (u16_be.map(CdpTlvType::new), u16_be).parse(stream)?;
+  let Some(len) = length.checked_sub(4) else {
+    return Parsed::Failure(Context::new(CdpAtom::Length(length)));
+  };
+  let len = usize::from(len);
+
+  let Success {
+    token: value,
+    stream,
+  } = match (tlv_type, len) {
+    (
+      CdpTlvType::DEVICE_ID
+      | CdpTlvType::PORT_ID
+      | CdpTlvType::SOFTWARE_VERSION
+      | CdpTlvType::PLATFORM
+      | CdpTlvType::VTP_DOMAIN,
+      _,
+    ) => take(len).map(CdpValue::Text).parse(stream)?,
+    (CdpTlvType::ADDRESSES | CdpTlvType::MANAGEMENT_ADDRESSES, 4..) => {
+      cdp_addresses(stream, length)?
+    }
+    (CdpTlvType::CAPABILITIES, 4) => u32_be.map(CdpValue::Capabilities).parse(stream)?,
+    (CdpTlvType::NATIVE_VLAN, 2) => u16_be.map(CdpValue::NativeVlan).parse(stream)?,
+    (CdpTlvType::DUPLEX, 1) => octet
+      .map(|duplex| CdpValue::Duplex(duplex != 0))
+      .parse(stream)?,
+    _ => take(len).map(CdpValue::Other).parse(stream)?,
+  };
+
+  Parsed::Success {
+    token: CdpTlv { tlv_type, value },
+    stream,
+  }
+}
+
+/// Parse a CDP packet, TLVs are parsed until the end of the stream or a
+/// malformed TLV
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn cdp_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<CdpPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): CdpParse<Stream, Context>,
+{
+  (
+    octet,
+    octet,
+    u16_be,
+    cdp_tlv.fold_bounds(.., Vec::new, Acc::acc),
+  )
+    .map(|(version, ttl, checksum, tlvs)| CdpPacket {
+      version,
+      ttl,
+      checksum,
+      tlvs,
+    })
+    .parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    CdpAddress,
+    CdpPacket,
+  };
+
+  #[test]
+  fn cdp() {
+    let packet = [
+      0x02, 0xB4, 0x12, 0x34, 0x00, 0x01, 0x00, 0x06, b's', b'w', 0x00, 0x02, 0x00, 0x11, 0x00,
+      0x00, 0x00, 0x01, 0x01, 0x01, 0xCC, 0x00, 0x04, 0xC0, 0x00, 0x02, 0x01, 0x00, 0x03, 0x00,
+      0x08, b'G', b'i', b'0', b'/', 0x00, 0x04, 0x00, 0x08, 0x00, 0x00, 0x00, 0x28, 0x00, 0x0A,
+      0x00, 0x06, 0x00, 0x0A,
+    ];
+    let Success { token, stream } = super::cdp_packet::<_, Ignore>(&packet[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.version, 2);
+    assert_eq!(token.ttl, 180);
+    assert_eq!(token.device_id(), Some(&&b"sw"[..]));
+    assert_eq!(token.port_id(), Some(&&b"Gi0/"[..]));
+    assert_eq!(
+      token.addresses(),
+      Some(&[CdpAddress::Ipv4(Ipv4Addr::new(192, 0, 2, 1))][..])
+    );
+    let capabilities = token.capabilities().unwrap();
+    assert_ne!(capabilities & CdpPacket::<&[u8]>::CAPABILITY_SWITCH, 0);
+    assert_ne!(capabilities & CdpPacket::<&[u8]>::CAPABILITY_IGMP, 0);
+    assert_eq!(token.native_vlan(), Some(10));
+
+    let short = [0x02, 0xB4, 0x00, 0x00, 0x00, 0x01, 0x00, 0x02];
+    let Success { token, stream } = super::cdp_packet::<_, Ignore>(&short[..]).unwrap();
+    assert!(token.tlvs.is_empty());
+    assert_eq!(stream, &short[4..]);
+  }
+}
@@ -0,0 +1,413 @@
+//! Internet checksum and CRC-32 helpers
+//! <https://www.rfc-editor.org/rfc/rfc1071>
+
+use std::net::IpAddr;
+
+use crate::IPProtocol;
+#[cfg(feature = "ipv4")]
+use crate::IPv4Header;
+#[cfg(feature = "ipv6")]
+use crate::IPv6Header;
+#[cfg(feature = "tcp")]
+use crate::TcpHeader;
+#[cfg(feature = "udp")]
+use crate::UdpHeader;
+
+// u64 can't overflow before 2^48 words, far beyond any packet
+fn sum(mut acc: u64, data: &[u8]) -> u64 {
+  let mut chunks = data.chunks_exact(2);
+  for word in &mut chunks {
+    acc += u64::from(u16::from_be_bytes([word[0], word[1]]));
+  }
+  if let [last] = chunks.remainder() {
+    acc += u64::from(u16::from_be_bytes([*last, 0]));
+  }
+  acc
+}
+
+fn fold(mut acc: u64) -> u16 {
+  while acc > 0xFFFF {
+    acc = (acc & 0xFFFF) + (acc >> 16u8);
+  }
+  acc as u16
+}
+
+/// Return the one's complement of the one's complement sum of all 16 bits
+/// words of data, an odd trailing byte is padded with zero. If data contain a
+/// valid checksum field the result is 0.
+pub fn internet_checksum(data: &[u8]) -> u16 {
+  !fold(sum(0, data))
+}
+
+/// Return checksum updated after the bytes old have been replaced by new,
+/// both must have the same even length and be 16 bits aligned in the
+/// checksummed data.
+/// <https://www.rfc-editor.org/rfc/rfc1624#section-3>
+pub fn checksum_update(checksum: u16, old: &[u8], new: &[u8]) -> u16 {
+  debug_assert_eq!(old.len(), new.len());
+  debug_assert_eq!(old.len() % 2, 0);
+
+  let acc = old.chunks_exact(2).fold(u64::from(!checksum), |acc, word| {
+    acc + u64::from(!u16::from_be_bytes([word[0], word[1]]))
+  });
+  !fold(sum(acc, new))
+}
+
+/// Compute internet checksums, implement it to plug hardware offload or
+/// precomputed results into the checksum functions of this crate
+pub trait ChecksumBackend {
+  /// Return the internet checksum of the concatenation of parts, every part
+  /// but the last have an even length
+  fn checksum(&self, parts: &[&[u8]]) -> u16;
+
+  /// Return checksum updated after the bytes old have been replaced by new,
+  /// see [checksum_update]
+  fn update(&self, checksum: u16, old: &[u8], new: &[u8]) -> u16 {
+    checksum_update(checksum, old, new)
+  }
+}
+
+/// Software implementation of [ChecksumBackend]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SoftwareChecksum;
+
+impl ChecksumBackend for SoftwareChecksum {
+  fn checksum(&self, parts: &[&[u8]]) -> u16 {
+    !fold(parts.iter().fold(0, |acc, part| sum(acc, part)))
+  }
+}
+
+/// Return true if the IPv4 header, options included, have a valid checksum
+pub fn verify_ipv4_header<Backend>(backend: &Backend, header: &[u8]) -> bool
+where
+  Backend: ChecksumBackend,
+{
+  backend.checksum(&[header]) == 0
+}
+
+/// Return the checksum of a TCP, UDP or ICMPv6 segment including the IP
+/// pseudo header, 0 if the checksum field of segment is valid. Use
+/// [verify_ipv4_header] semantic for ICMP that doesn't have a pseudo header.
+/// source and dest must be of the same family.
+pub fn transport_checksum<Backend>(
+  backend: &Backend, source: IpAddr, dest: IpAddr, protocol: IPProtocol, segment: &[u8],
+) -> u16
+where
+  Backend: ChecksumBackend,
+{
+  pseudo_checksum(
+    backend,
+    source,
+    dest,
+    protocol,
+    segment.len() as u32,
+    &[segment],
+  )
+}
+
+fn pseudo_checksum<Backend>(
+  backend: &Backend, source: IpAddr, dest: IpAddr, protocol: IPProtocol, len: u32,
+  segment: &[&[u8]],
+) -> u16
+where
+  Backend: ChecksumBackend,
+{
+  let protocol = [0, protocol.protocol()];
+  let len_v4 = (len as u16).to_be_bytes();
+  let len_v6 = len.to_be_bytes();
+  let (source_v4, dest_v4, source_v6, dest_v6);
+  let mut parts: Vec<&[u8]> = match (source, dest) {
+    (IpAddr::V4(source), IpAddr::V4(dest)) => {
+      (source_v4, dest_v4) = (source.octets(), dest.octets());
+      vec![&source_v4, &dest_v4, &protocol, &len_v4]
+    }
+    (source, dest) => {
+      (source_v6, dest_v6) = (to_ipv6(source).octets(), to_ipv6(dest).octets());
+      vec![&source_v6, &dest_v6, &len_v6, &[0, 0], &protocol]
+    }
+  };
+  parts.extend_from_slice(segment);
+  backend.checksum(&parts)
+}
+
+fn to_ipv6(addr: IpAddr) -> std::net::Ipv6Addr {
+  match addr {
+    IpAddr::V4(addr) => addr.to_ipv6_mapped(),
+    IpAddr::V6(addr) => addr,
+  }
+}
+
+/// Return true if the checksum field of a TCP, UDP or ICMPv6 segment is
+/// valid, a zero UDP checksum over IPv4 means no checksum and is valid
+pub fn verify_transport<Backend>(
+  backend: &Backend, source: IpAddr, dest: IpAddr, protocol: IPProtocol, segment: &[u8],
+) -> bool
+where
+  Backend: ChecksumBackend,
+{
+  if protocol == IPProtocol::UDP && source.is_ipv4() && segment.get(6..8) == Some(&[0, 0]) {
+    return true;
+  }
+  transport_checksum(backend, source, dest, protocol, segment) == 0
+}
+
+/// IP header that provide the addresses of a transport pseudo header
+pub trait PseudoHeader {
+  /// Return source and destination addresses
+  fn pseudo_addrs(&self) -> (IpAddr, IpAddr);
+}
+
+#[cfg(feature = "ipv4")]
+impl<Span> PseudoHeader for IPv4Header<Span> {
+  fn pseudo_addrs(&self) -> (IpAddr, IpAddr) {
+    (self.source_addr.into(), self.dest_addr.into())
+  }
+}
+
+#[cfg(feature = "ipv6")]
+impl PseudoHeader for IPv6Header {
+  fn pseudo_addrs(&self) -> (IpAddr, IpAddr) {
+    (self.source_addr.into(), self.dest_addr.into())
+  }
+}
+
+/// Return true if the checksum field of a parsed TCP header is valid for
+/// payload, ip is the IPv4 or IPv6 header that carried the segment.
+#[cfg(feature = "tcp")]
+pub fn verify_tcp_checksum<Backend, Ip, Options>(
+  backend: &Backend, ip: &Ip, tcp: &TcpHeader<Options>, payload: &[u8],
+) -> bool
+where
+  Backend: ChecksumBackend,
+  Ip: PseudoHeader,
+  Options: AsRef<[u8]>,
+{
+  let mut header = [0u8; TcpHeader::<()>::MIN_LEN];
+  header[0..2].copy_from_slice(&tcp.source_port.get().to_be_bytes());
+  header[2..4].copy_from_slice(&tcp.dest_port.get().to_be_bytes());
+  header[4..8].copy_from_slice(&tcp.sequence_no.to_be_bytes());
+  header[8..12].copy_from_slice(&tcp.ack_no.to_be_bytes());
+  header[12..14].copy_from_slice(&tcp.flags.to_wire(tcp.data_offset).to_be_bytes());
+  header[14..16].copy_from_slice(&tcp.window.to_be_bytes());
+  header[16..18].copy_from_slice(&tcp.checksum.to_be_bytes());
+  header[18..20].copy_from_slice(&tcp.urgent_pointer.to_be_bytes());
+  let options = tcp.options.as_ref();
+
+  let (source, dest) = ip.pseudo_addrs();
+  let len = header.len() + options.len() + payload.len();
+  pseudo_checksum(
+    backend,
+    source,
+    dest,
+    IPProtocol::TCP,
+    len as u32,
+    &[&header, options, payload],
+  ) == 0
+}
+
+/// Return true if the checksum field of a parsed UDP header is valid for
+/// payload, ip is the IPv4 or IPv6 header that carried the datagram. A zero
+/// checksum over IPv4 means no checksum and is valid.
+#[cfg(feature = "udp")]
+pub fn verify_udp_checksum<Backend, Ip>(
+  backend: &Backend, ip: &Ip, udp: &UdpHeader, payload: &[u8],
+) -> bool
+where
+  Backend: ChecksumBackend,
+  Ip: PseudoHeader,
+{
+  let (source, dest) = ip.pseudo_addrs();
+  if udp.checksum == 0 && source.is_ipv4() {
+    return true;
+  }
+
+  let mut header = [0u8; 8];
+  header[0..2].copy_from_slice(&udp.source_port.get().to_be_bytes());
+  header[2..4].copy_from_slice(&udp.dest_port.get().to_be_bytes());
+  header[4..6].copy_from_slice(&udp.length.to_be_bytes());
+  header[6..8].copy_from_slice(&udp.checksum.to_be_bytes());
+
+  pseudo_checksum(
+    backend,
+    source,
+    dest,
+    IPProtocol::UDP,
+    u32::from(udp.length),
+    &[&header, payload],
+  ) == 0
+}
+
+const CRC32_TABLE: [u32; 256] = {
+  let mut table = [0; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u32;
+    let mut bit = 0u8;
+    while bit < 8 {
+      crc = if crc & 1 != 0 {
+        crc >> 1u8 ^ 0xEDB8_8320
+      } else {
+        crc >> 1u8
+      };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+};
+
+/// Initial state of [crc32_update]
+pub(crate) const CRC32_INIT: u32 = !0;
+
+/// Feed one byte to a CRC-32 state, the final CRC is the complement of the
+/// state
+pub(crate) const fn crc32_update(state: u32, byte: u8) -> u32 {
+  CRC32_TABLE[((state ^ byte as u32) & 0xFF) as usize] ^ state >> 8u8
+}
+
+/// Return the CRC-32 (IEEE 802.3) of data, as used by the ethernet frame
+/// check sequence
+pub fn crc32(data: &[u8]) -> u32 {
+  !data
+    .iter()
+    .fold(CRC32_INIT, |state, &byte| crc32_update(state, byte))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    IpAddr,
+    Ipv4Addr,
+  };
+
+  use super::{
+    checksum_update,
+    crc32,
+    internet_checksum,
+    verify_ipv4_header,
+    verify_transport,
+    ChecksumBackend,
+    SoftwareChecksum,
+  };
+  use crate::IPProtocol;
+
+  #[test]
+  fn checksum() {
+    let mut header = [
+      0x45, 0x00, 0x05, 0xDC, 0x1A, 0xE6, 0x20, 0x00, 0x40, 0x01, 0x00, 0x00, 0x0A, 0x0A, 0x01,
+      0x87, 0x0A, 0x0A, 0x01, 0xB4,
+    ];
+    assert_eq!(internet_checksum(&header), 0x22ED);
+
+    header[10..12].copy_from_slice(&0x22EDu16.to_be_bytes());
+    assert_eq!(internet_checksum(&header), 0);
+
+    let updated = checksum_update(0x22ED, &header[12..16], &[192, 168, 1, 1]);
+    header[12..16].copy_from_slice(&[192, 168, 1, 1]);
+    header[10..12].copy_from_slice(&updated.to_be_bytes());
+    assert_eq!(internet_checksum(&header), 0);
+
+    // bigger than what a u32 accumulator can hold
+    assert_eq!(internet_checksum(&[0xFF; 1 << 18]), 0);
+    assert_eq!(internet_checksum(&[0x01; 1 << 18]), 0xFDFD);
+
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+    assert!(verify_ipv4_header(&SoftwareChecksum, &header));
+    let tcp = [
+      0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39, 0xAE, 0xE6, 0x50, 0x18, 0x00,
+      0xE5, 0x76, 0x92, 0x00, 0x00, 0x47, 0x45, 0x54, 0x20, 0x2F, 0x69, 0x6E, 0x64, 0x65, 0x78,
+      0x2E, 0x68, 0x74, 0x6D, 0x6C, 0x0A,
+    ];
+    let source = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 108));
+    let dest = IpAddr::V4(Ipv4Addr::new(208, 97, 177, 124));
+    assert!(verify_transport(
+      &SoftwareChecksum,
+      source,
+      dest,
+      IPProtocol::TCP,
+      &tcp
+    ));
+    assert!(!verify_transport(
+      &SoftwareChecksum,
+      dest,
+      source,
+      IPProtocol::UDP,
+      &tcp
+    ));
+
+    // offloaded checksum already verified by the NIC
+    struct Offloaded;
+    impl ChecksumBackend for Offloaded {
+      fn checksum(&self, _parts: &[&[u8]]) -> u16 {
+        0
+      }
+    }
+    assert!(verify_transport(
+      &Offloaded,
+      dest,
+      source,
+      IPProtocol::UDP,
+      &tcp
+    ));
+  }
+  #[cfg(all(feature = "ipv4", feature = "tcp", feature = "udp"))]
+  #[test]
+  fn verify_headers() {
+    use binator::{
+      context::Ignore,
+      Parsed,
+    };
+
+    use super::{
+      verify_tcp_checksum,
+      verify_udp_checksum,
+    };
+    use crate::{
+      IPv4Header,
+      UdpHeader,
+    };
+
+    let ip = [
+      0x45, 0x00, 0x00, 0x38, 0x00, 0x00, 0x40, 0x00, 0x40, 0x06, 0x00, 0x00, 0xC0, 0xA8, 0x00,
+      0x6C, 0xD0, 0x61, 0xB1, 0x7C,
+    ];
+    let ip: Parsed<IPv4Header<&[u8]>, _, Ignore> = crate::ipv4_header(&ip[..]);
+    let ip = ip.unwrap().token;
+    let tcp = [
+      0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39, 0xAE, 0xE6, 0x50, 0x18, 0x00,
+      0xE5, 0x76, 0x92, 0x00, 0x00, 0x47, 0x45, 0x54, 0x20, 0x2F, 0x69, 0x6E, 0x64, 0x65, 0x78,
+      0x2E, 0x68, 0x74, 0x6D, 0x6C, 0x0A,
+    ];
+    let Parsed::Success {
+      token: header,
+      stream: payload,
+    } = crate::tcp_header::<_, Ignore>(&tcp[..])
+    else {
+      panic!("tcp header");
+    };
+    assert!(verify_tcp_checksum(
+      &SoftwareChecksum,
+      &ip,
+      &header,
+      payload
+    ));
+    assert!(!verify_tcp_checksum(
+      &SoftwareChecksum,
+      &ip,
+      &header,
+      &payload[1..]
+    ));
+
+    let mut udp = UdpHeader {
+      source_port: 53.into(),
+      dest_port: 53.into(),
+      length: 9,
+      checksum: 0,
+    };
+    assert!(verify_udp_checksum(&SoftwareChecksum, &ip, &udp, &[42]));
+    udp.checksum = 1;
+    assert!(!verify_udp_checksum(&SoftwareChecksum, &ip, &udp, &[42]));
+  }
+}
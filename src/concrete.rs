@@ -0,0 +1,813 @@
+//! Non generic version of every parser of this crate for `&[u8]` stream.
+//!
+//! The generic parsers are monomorphized in every crate that use them, the
+//! functions of this module are compiled once here with a fixed stream and
+//! context type.
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+#[cfg(feature = "ip_addr")]
+use std::net::{
+  IpAddr,
+  Ipv4Addr,
+  Ipv6Addr,
+  SocketAddr,
+  SocketAddrV4,
+  SocketAddrV6,
+};
+
+use binator::{
+  base::{
+    BaseAtom,
+    EndOfStreamAtom,
+    IntRadixAtom,
+  },
+  context::Tree,
+  utils::UtilsAtom,
+  CoreAtom,
+  Parsed,
+};
+
+#[cfg(feature = "arp")]
+use crate::ArpPacket;
+#[cfg(feature = "sll")]
+use crate::SllHeader;
+use crate::TruncatedAtom;
+#[cfg(feature = "ipsec")]
+use crate::{
+  AhHeader,
+  EspPacket,
+  IpsecAtom,
+};
+#[cfg(feature = "avtp")]
+use crate::{
+  AvtpAtom,
+  AvtpStream,
+};
+#[cfg(feature = "ber")]
+use crate::{
+  BerAtom,
+  BerHeader,
+  BerOid,
+  BerTlv,
+};
+#[cfg(feature = "bgp")]
+use crate::{
+  BgpAtom,
+  BgpMessage,
+};
+#[cfg(feature = "bittorrent")]
+use crate::{
+  BittorrentAtom,
+  BittorrentHandshake,
+  BittorrentMessage,
+  BittorrentTrackerRequest,
+  BittorrentTrackerResponse,
+};
+#[cfg(feature = "cdp")]
+use crate::{
+  CdpAtom,
+  CdpPacket,
+};
+#[cfg(feature = "dhcp")]
+use crate::{
+  DhcpAtom,
+  DhcpMessage,
+};
+#[cfg(feature = "dnp3")]
+use crate::{
+  Dnp3ApplicationHeader,
+  Dnp3Atom,
+  Dnp3Frame,
+  Dnp3ObjectHeader,
+};
+#[cfg(feature = "eapol")]
+use crate::{
+  EapPacket,
+  Eapol,
+  EapolAtom,
+};
+#[cfg(feature = "ethernet")]
+use crate::{
+  EthernetAtom,
+  EthernetFrame,
+  LlcHeader,
+};
+#[cfg(feature = "gtp")]
+use crate::{
+  GtpAtom,
+  GtpUserPacket,
+  GtpV1Header,
+  GtpV2Header,
+};
+#[cfg(feature = "http")]
+use crate::{
+  HttpAtom,
+  HttpChunkedBody,
+  HttpRequest,
+  HttpResponse,
+};
+#[cfg(feature = "ipv4")]
+use crate::{
+  IPv4Header,
+  Ipv4Atom,
+  Ipv4Option,
+  Ipv4OptionAtom,
+};
+#[cfg(feature = "ipv6")]
+use crate::{
+  IPv6Header,
+  Ipv6Atom,
+  Ipv6FragmentHeader,
+  Ipv6HopByHopHeader,
+  Ipv6RoutingHeader,
+};
+#[cfg(feature = "icmp")]
+use crate::{
+  IcmpAtom,
+  IcmpError,
+  IcmpHeader,
+  Icmpv6Header,
+  MldMessage,
+};
+#[cfg(feature = "ip_addr")]
+use crate::{
+  IpAddrAtom,
+  IpNet,
+  Ipv4Net,
+  Ipv6Net,
+  Ipv6Reference,
+  Ipv6Zoned,
+};
+#[cfg(feature = "ipfix")]
+use crate::{
+  IpfixAtom,
+  IpfixHeader,
+  IpfixMessage,
+};
+#[cfg(feature = "kerberos")]
+use crate::{
+  KerberosAtom,
+  KerberosMessage,
+};
+#[cfg(feature = "lacp")]
+use crate::{
+  LacpAtom,
+  Lacpdu,
+  SlowProtocol,
+};
+#[cfg(feature = "dissect")]
+use crate::{
+  LenientPacket,
+  Packet,
+};
+#[cfg(feature = "link_type")]
+use crate::{
+  LinkAtom,
+  LoopbackHeader,
+};
+#[cfg(feature = "lisp")]
+use crate::{
+  LispAtom,
+  LispControl,
+  LispDataHeader,
+};
+#[cfg(feature = "smb")]
+use crate::{
+  NbnsPacket,
+  NetbiosSession,
+  Smb2Header,
+  SmbAtom,
+};
+#[cfg(feature = "ppp")]
+use crate::{
+  PppControl,
+  PppFrame,
+  PppProtocol,
+};
+#[cfg(feature = "pppoe")]
+use crate::{
+  PppoeAtom,
+  PppoeDiscovery,
+  PppoeHeader,
+};
+#[cfg(feature = "quic")]
+use crate::{
+  QuicAtom,
+  QuicLongHeader,
+  QuicLongPacket,
+};
+#[cfg(feature = "wifi")]
+use crate::{
+  RadiotapHeader,
+  WifiAtom,
+  WifiFrame,
+  WifiHeader,
+};
+#[cfg(feature = "rtp")]
+use crate::{
+  RtcpPacket,
+  RtpAtom,
+  RtpHeader,
+};
+#[cfg(feature = "rtsp")]
+use crate::{
+  RtspInterleaved,
+  RtspMessage,
+  RtspRequest,
+  RtspResponse,
+};
+#[cfg(feature = "tunnel")]
+use crate::{
+  SixInFourPacket,
+  TeredoPacket,
+  TunnelAtom,
+};
+#[cfg(feature = "sixlowpan")]
+use crate::{
+  SixLowpanAtom,
+  SixLowpanFragment,
+};
+#[cfg(feature = "snmp")]
+use crate::{
+  SnmpAtom,
+  SnmpMessage,
+};
+#[cfg(feature = "socks")]
+use crate::{
+  Socks4Reply,
+  Socks4Request,
+  Socks5Auth,
+  Socks5Method,
+  Socks5Reply,
+  Socks5Request,
+  Socks5UdpHeader,
+  SocksAtom,
+};
+#[cfg(feature = "ssdp")]
+use crate::{
+  SsdpAtom,
+  SsdpMessage,
+};
+#[cfg(feature = "tcp")]
+use crate::{
+  TcpAtom,
+  TcpHeader,
+  TcpHeaderFull,
+  TcpOption,
+  TcpOptionsPadding,
+};
+#[cfg(feature = "textproto")]
+use crate::{
+  TextCommand,
+  TextReply,
+};
+#[cfg(feature = "tls")]
+use crate::{
+  TlsAtom,
+  TlsHandshake,
+  TlsRecord,
+};
+#[cfg(feature = "udp")]
+use crate::{
+  UdpAtom,
+  UdpHeader,
+  UdpOptions,
+};
+#[cfg(feature = "vxlan")]
+use crate::{
+  VxlanAtom,
+  VxlanHeader,
+};
+
+/// Every atom the parsers of this crate can produce on a `&[u8]` stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetworkAtom<'a> {
+  /// binator core atom
+  Core(CoreAtom<&'a [u8]>),
+  /// binator utils atom
+  Utils(UtilsAtom<&'a [u8]>),
+  /// binator base atom
+  Base(BaseAtom<u8>),
+  /// binator end of stream atom
+  EndOfStream(EndOfStreamAtom<&'a [u8]>),
+  /// binator radix atom for u8
+  U8Radix(IntRadixAtom<u8>),
+  /// binator radix atom for u16
+  U16Radix(IntRadixAtom<u16>),
+  /// avtp atom
+  #[cfg(feature = "avtp")]
+  Avtp(AvtpAtom),
+  /// ber atom
+  #[cfg(feature = "ber")]
+  Ber(BerAtom),
+  /// bgp atom
+  #[cfg(feature = "bgp")]
+  Bgp(BgpAtom),
+  /// bittorrent atom
+  #[cfg(feature = "bittorrent")]
+  Bittorrent(BittorrentAtom),
+  /// cdp atom
+  #[cfg(feature = "cdp")]
+  Cdp(CdpAtom),
+  /// dhcp atom
+  #[cfg(feature = "dhcp")]
+  Dhcp(DhcpAtom),
+  /// dnp3 atom
+  #[cfg(feature = "dnp3")]
+  Dnp3(Dnp3Atom),
+  /// eapol atom
+  #[cfg(feature = "eapol")]
+  Eapol(EapolAtom),
+  /// ethernet atom
+  #[cfg(feature = "ethernet")]
+  Ethernet(EthernetAtom),
+  /// ip_addr atom
+  #[cfg(feature = "ip_addr")]
+  IpAddr(IpAddrAtom),
+  /// gtp atom
+  #[cfg(feature = "gtp")]
+  Gtp(GtpAtom),
+  /// http atom
+  #[cfg(feature = "http")]
+  Http(HttpAtom),
+  /// icmp atom
+  #[cfg(feature = "icmp")]
+  Icmp(IcmpAtom),
+  /// ipfix atom
+  #[cfg(feature = "ipfix")]
+  Ipfix(IpfixAtom),
+  /// ipsec atom
+  #[cfg(feature = "ipsec")]
+  Ipsec(IpsecAtom),
+  /// ipv4 atom
+  #[cfg(feature = "ipv4")]
+  Ipv4(Ipv4Atom),
+  /// ipv4 option atom
+  #[cfg(feature = "ipv4")]
+  Ipv4Option(Ipv4OptionAtom),
+  /// ipv6 atom
+  #[cfg(feature = "ipv6")]
+  Ipv6(Ipv6Atom),
+  /// kerberos atom
+  #[cfg(feature = "kerberos")]
+  Kerberos(KerberosAtom),
+  /// lacp atom
+  #[cfg(feature = "lacp")]
+  Lacp(LacpAtom),
+  /// link type atom
+  #[cfg(feature = "link_type")]
+  Link(LinkAtom),
+  /// lisp atom
+  #[cfg(feature = "lisp")]
+  Lisp(LispAtom),
+  /// pppoe atom
+  #[cfg(feature = "pppoe")]
+  Pppoe(PppoeAtom),
+  /// quic atom
+  #[cfg(feature = "quic")]
+  Quic(QuicAtom),
+  /// rtp atom
+  #[cfg(feature = "rtp")]
+  Rtp(RtpAtom),
+  /// smb atom
+  #[cfg(feature = "smb")]
+  Smb(SmbAtom),
+  /// snmp atom
+  #[cfg(feature = "snmp")]
+  Snmp(SnmpAtom),
+  /// socks atom
+  #[cfg(feature = "socks")]
+  Socks(SocksAtom),
+  /// ssdp atom
+  #[cfg(feature = "ssdp")]
+  Ssdp(SsdpAtom),
+  /// sixlowpan atom
+  #[cfg(feature = "sixlowpan")]
+  SixLowpan(SixLowpanAtom),
+  /// tcp atom
+  #[cfg(feature = "tcp")]
+  Tcp(TcpAtom),
+  /// tls atom
+  #[cfg(feature = "tls")]
+  Tls(TlsAtom),
+  /// tunnel atom
+  #[cfg(feature = "tunnel")]
+  Tunnel(TunnelAtom),
+  /// udp atom
+  #[cfg(feature = "udp")]
+  Udp(UdpAtom),
+  /// vxlan atom
+  #[cfg(feature = "vxlan")]
+  Vxlan(VxlanAtom),
+  /// wifi atom
+  #[cfg(feature = "wifi")]
+  Wifi(WifiAtom),
+  /// snaplen atom
+  Truncated(TruncatedAtom),
+}
+
+macro_rules! network_atom_from {
+  ($($(#[$meta:meta])* $variant:ident($atom:ty),)*) => {
+    $($(#[$meta])* impl<'a> From<$atom> for NetworkAtom<'a> {
+      fn from(atom: $atom) -> Self {
+        Self::$variant(atom)
+      }
+    })*
+
+    impl<'a> Display for NetworkAtom<'a> {
+      fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+          $($(#[$meta])* Self::$variant(atom) => network_atom_from!(@display f, $variant, atom),)*
+        }
+      }
+    }
+  };
+  (@display $f:ident, EndOfStream, $atom:ident) => {
+    write!($f, "EndOfStream: expected end of stream found {:?}", $atom.item)
+  };
+  (@display $f:ident, $variant:ident, $atom:ident) => {
+    write!($f, "{}", $atom)
+  };
+}
+
+network_atom_from! {
+  Core(CoreAtom<&'a [u8]>),
+  Utils(UtilsAtom<&'a [u8]>),
+  Base(BaseAtom<u8>),
+  EndOfStream(EndOfStreamAtom<&'a [u8]>),
+  U8Radix(IntRadixAtom<u8>),
+  U16Radix(IntRadixAtom<u16>),
+  #[cfg(feature = "avtp")]
+  Avtp(AvtpAtom),
+  #[cfg(feature = "ber")]
+  Ber(BerAtom),
+  #[cfg(feature = "bgp")]
+  Bgp(BgpAtom),
+  #[cfg(feature = "bittorrent")]
+  Bittorrent(BittorrentAtom),
+  #[cfg(feature = "cdp")]
+  Cdp(CdpAtom),
+  #[cfg(feature = "dhcp")]
+  Dhcp(DhcpAtom),
+  #[cfg(feature = "dnp3")]
+  Dnp3(Dnp3Atom),
+  #[cfg(feature = "eapol")]
+  Eapol(EapolAtom),
+  #[cfg(feature = "ethernet")]
+  Ethernet(EthernetAtom),
+  #[cfg(feature = "ip_addr")]
+  IpAddr(IpAddrAtom),
+  #[cfg(feature = "gtp")]
+  Gtp(GtpAtom),
+  #[cfg(feature = "http")]
+  Http(HttpAtom),
+  #[cfg(feature = "icmp")]
+  Icmp(IcmpAtom),
+  #[cfg(feature = "ipfix")]
+  Ipfix(IpfixAtom),
+  #[cfg(feature = "ipsec")]
+  Ipsec(IpsecAtom),
+  #[cfg(feature = "ipv4")]
+  Ipv4(Ipv4Atom),
+  #[cfg(feature = "ipv4")]
+  Ipv4Option(Ipv4OptionAtom),
+  #[cfg(feature = "ipv6")]
+  Ipv6(Ipv6Atom),
+  #[cfg(feature = "kerberos")]
+  Kerberos(KerberosAtom),
+  #[cfg(feature = "lacp")]
+  Lacp(LacpAtom),
+  #[cfg(feature = "link_type")]
+  Link(LinkAtom),
+  #[cfg(feature = "lisp")]
+  Lisp(LispAtom),
+  #[cfg(feature = "pppoe")]
+  Pppoe(PppoeAtom),
+  #[cfg(feature = "quic")]
+  Quic(QuicAtom),
+  #[cfg(feature = "rtp")]
+  Rtp(RtpAtom),
+  #[cfg(feature = "smb")]
+  Smb(SmbAtom),
+  #[cfg(feature = "snmp")]
+  Snmp(SnmpAtom),
+  #[cfg(feature = "socks")]
+  Socks(SocksAtom),
+  #[cfg(feature = "ssdp")]
+  Ssdp(SsdpAtom),
+  #[cfg(feature = "sixlowpan")]
+  SixLowpan(SixLowpanAtom),
+  #[cfg(feature = "tcp")]
+  Tcp(TcpAtom),
+  #[cfg(feature = "tls")]
+  Tls(TlsAtom),
+  #[cfg(feature = "tunnel")]
+  Tunnel(TunnelAtom),
+  #[cfg(feature = "udp")]
+  Udp(UdpAtom),
+  #[cfg(feature = "vxlan")]
+  Vxlan(VxlanAtom),
+  #[cfg(feature = "wifi")]
+  Wifi(WifiAtom),
+  Truncated(TruncatedAtom),
+}
+
+/// Context used by every parser of this module
+pub type NetworkContext<'a> = Tree<NetworkAtom<'a>>;
+
+/// Result of every parser of this module
+pub type ParsedSlice<'a, Token> = Parsed<Token, &'a [u8], NetworkContext<'a>>;
+
+macro_rules! concrete {
+  ($($(#[$meta:meta])* $name:ident -> $token:ty,)*) => {
+    $(
+      $(#[$meta])*
+      #[doc = concat!("Non generic version of [`crate::", stringify!($name), "`]")]
+      pub fn $name(stream: &[u8]) -> ParsedSlice<'_, $token> {
+        crate::$name(stream)
+      }
+    )*
+  };
+}
+
+concrete! {
+  #[cfg(feature = "arp")]
+  arp_packet -> ArpPacket<&[u8]>,
+  #[cfg(feature = "avtp")]
+  avtp_stream -> AvtpStream<&[u8]>,
+  #[cfg(feature = "ber")]
+  ber_header -> BerHeader,
+  #[cfg(feature = "ber")]
+  ber_tlv -> BerTlv<&[u8]>,
+  #[cfg(feature = "ber")]
+  ber_integer -> i64,
+  #[cfg(feature = "ber")]
+  ber_octet_string -> &[u8],
+  #[cfg(feature = "ber")]
+  ber_oid -> BerOid,
+  #[cfg(feature = "bgp")]
+  bgp_message -> BgpMessage<&[u8]>,
+  #[cfg(feature = "bgp")]
+  bgp_message_as2 -> BgpMessage<&[u8]>,
+  #[cfg(feature = "bittorrent")]
+  bittorrent_handshake -> BittorrentHandshake,
+  #[cfg(feature = "bittorrent")]
+  bittorrent_message -> BittorrentMessage<&[u8]>,
+  #[cfg(feature = "bittorrent")]
+  bittorrent_tracker_request -> BittorrentTrackerRequest<&[u8]>,
+  #[cfg(feature = "bittorrent")]
+  bittorrent_tracker_response -> BittorrentTrackerResponse<&[u8]>,
+  #[cfg(feature = "cdp")]
+  cdp_packet -> CdpPacket<&[u8]>,
+  #[cfg(feature = "dhcp")]
+  dhcp_message -> DhcpMessage<&[u8]>,
+  #[cfg(feature = "dissect")]
+  dissect_ethernet -> Packet<&[u8]>,
+  #[cfg(feature = "dissect")]
+  dissect_ethernet_lenient -> LenientPacket<&[u8], NetworkContext<'_>>,
+  #[cfg(feature = "dnp3")]
+  dnp3_frame -> Dnp3Frame,
+  #[cfg(feature = "dnp3")]
+  dnp3_application_header -> Dnp3ApplicationHeader,
+  #[cfg(feature = "dnp3")]
+  dnp3_object_header -> Dnp3ObjectHeader,
+  #[cfg(feature = "eapol")]
+  eapol -> Eapol<&[u8]>,
+  #[cfg(feature = "eapol")]
+  eap_packet -> EapPacket<&[u8]>,
+  #[cfg(feature = "ethernet")]
+  ethernet_frame -> EthernetFrame,
+  #[cfg(feature = "ethernet")]
+  ethernet_frame_with_fcs -> (EthernetFrame, &[u8]),
+  #[cfg(feature = "gtp")]
+  gtpv1_header -> GtpV1Header<&[u8]>,
+  #[cfg(feature = "gtp")]
+  gtpu_decap -> GtpUserPacket<&[u8]>,
+  #[cfg(feature = "gtp")]
+  gtpv2_header -> GtpV2Header,
+  #[cfg(feature = "http")]
+  http_request -> HttpRequest<&[u8]>,
+  #[cfg(feature = "http")]
+  http_response -> HttpResponse<&[u8]>,
+  #[cfg(feature = "http")]
+  http_chunk -> &[u8],
+  #[cfg(feature = "http")]
+  http_chunked_body -> HttpChunkedBody<&[u8]>,
+  #[cfg(feature = "icmp")]
+  icmp_header -> IcmpHeader,
+  #[cfg(feature = "icmp")]
+  icmpv6_header -> Icmpv6Header,
+  #[cfg(feature = "icmp")]
+  icmp_error -> IcmpError<IcmpHeader, &[u8]>,
+  #[cfg(feature = "icmp")]
+  icmpv6_error -> IcmpError<Icmpv6Header, &[u8]>,
+  #[cfg(feature = "icmp")]
+  mld_message -> MldMessage<&[u8]>,
+  #[cfg(feature = "ipfix")]
+  ipfix_header -> IpfixHeader,
+  #[cfg(feature = "ipfix")]
+  ipfix_message -> IpfixMessage<&[u8]>,
+  #[cfg(feature = "ipsec")]
+  ah_header -> AhHeader<&[u8]>,
+  #[cfg(feature = "ipsec")]
+  esp_packet -> EspPacket<&[u8]>,
+  #[cfg(feature = "ipv4")]
+  ipv4_header -> IPv4Header<&[u8]>,
+  #[cfg(feature = "ipv4")]
+  ipv4_header_checked -> IPv4Header<&[u8]>,
+  #[cfg(feature = "ipv4")]
+  ipv4_header_strict -> IPv4Header<&[u8]>,
+  #[cfg(feature = "ipv4")]
+  ipv4_options -> Vec<Ipv4Option<&[u8]>>,
+  #[cfg(feature = "ipv6")]
+  ipv6_header -> IPv6Header,
+  #[cfg(feature = "ipv6")]
+  ipv6_fragment_header -> Ipv6FragmentHeader,
+  #[cfg(feature = "ipv6")]
+  ipv6_hop_by_hop_header -> Ipv6HopByHopHeader<&[u8]>,
+  #[cfg(feature = "ipv6")]
+  ipv6_routing_header -> Ipv6RoutingHeader<&[u8]>,
+  #[cfg(feature = "kerberos")]
+  kerberos_message -> KerberosMessage<&[u8]>,
+  #[cfg(feature = "lacp")]
+  lacpdu -> Lacpdu,
+  #[cfg(feature = "lacp")]
+  slow_protocol -> SlowProtocol<&[u8]>,
+  #[cfg(feature = "ppp")]
+  ppp_protocol -> PppProtocol,
+  #[cfg(feature = "ppp")]
+  ppp_control -> PppControl<&[u8]>,
+  #[cfg(feature = "ppp")]
+  ppp_frame -> PppFrame<&[u8]>,
+  #[cfg(feature = "pppoe")]
+  pppoe_header -> PppoeHeader,
+  #[cfg(feature = "pppoe")]
+  pppoe_session -> PppoeHeader,
+  #[cfg(feature = "pppoe")]
+  pppoe_discovery -> PppoeDiscovery<&[u8]>,
+  #[cfg(feature = "rtp")]
+  rtp_header -> RtpHeader<&[u8]>,
+  #[cfg(feature = "rtp")]
+  rtcp_packet -> RtcpPacket<&[u8]>,
+  #[cfg(feature = "rtp")]
+  rtcp_compound -> Vec<RtcpPacket<&[u8]>>,
+  #[cfg(feature = "rtsp")]
+  rtsp_request -> RtspRequest<&[u8]>,
+  #[cfg(feature = "rtsp")]
+  rtsp_response -> RtspResponse<&[u8]>,
+  #[cfg(feature = "rtsp")]
+  rtsp_interleaved -> RtspInterleaved<&[u8]>,
+  #[cfg(feature = "rtsp")]
+  rtsp_message -> RtspMessage<&[u8]>,
+  #[cfg(feature = "sll")]
+  sll_header -> SllHeader,
+  #[cfg(feature = "smb")]
+  netbios_session -> NetbiosSession<&[u8]>,
+  #[cfg(feature = "smb")]
+  nbns_packet -> NbnsPacket<&[u8]>,
+  #[cfg(feature = "smb")]
+  smb2_header -> Smb2Header,
+  #[cfg(feature = "snmp")]
+  snmp_message -> SnmpMessage<&[u8]>,
+  #[cfg(feature = "socks")]
+  socks5_greeting -> Vec<Socks5Method>,
+  #[cfg(feature = "socks")]
+  socks5_method_selection -> Socks5Method,
+  #[cfg(feature = "socks")]
+  socks5_auth -> Socks5Auth<&[u8]>,
+  #[cfg(feature = "socks")]
+  socks5_request -> Socks5Request<&[u8]>,
+  #[cfg(feature = "socks")]
+  socks5_reply -> Socks5Reply<&[u8]>,
+  #[cfg(feature = "socks")]
+  socks5_udp_header -> Socks5UdpHeader<&[u8]>,
+  #[cfg(feature = "socks")]
+  socks4_request -> Socks4Request<&[u8]>,
+  #[cfg(feature = "socks")]
+  socks4_reply -> Socks4Reply,
+  #[cfg(feature = "ssdp")]
+  ssdp_message -> SsdpMessage<&[u8]>,
+  #[cfg(feature = "sll")]
+  sll2_header -> SllHeader,
+  #[cfg(feature = "tcp")]
+  tcp_header -> TcpHeader<&[u8]>,
+  #[cfg(feature = "tcp")]
+  tcp_options -> Vec<TcpOption<&[u8]>>,
+  #[cfg(feature = "tcp")]
+  tcp_options_padding -> TcpOptionsPadding<&[u8]>,
+  #[cfg(feature = "tcp")]
+  tcp_options_lenient -> Vec<TcpOption<&[u8]>>,
+  #[cfg(feature = "tcp")]
+  tcp_header_full -> TcpHeaderFull<&[u8]>,
+  #[cfg(feature = "textproto")]
+  text_command -> TextCommand<&[u8]>,
+  #[cfg(feature = "textproto")]
+  text_reply -> TextReply<&[u8]>,
+  #[cfg(feature = "tls")]
+  tls_record -> TlsRecord<&[u8]>,
+  #[cfg(feature = "tls")]
+  tls_handshake -> TlsHandshake<&[u8]>,
+  #[cfg(feature = "tunnel")]
+  six_in_four -> SixInFourPacket<&[u8]>,
+  #[cfg(feature = "tunnel")]
+  teredo_packet -> TeredoPacket<&[u8]>,
+  #[cfg(feature = "udp")]
+  udp_header -> UdpHeader,
+  #[cfg(feature = "udp")]
+  udp_header_unchecked -> UdpHeader,
+  #[cfg(feature = "udp")]
+  udp_options -> UdpOptions<&[u8]>,
+  #[cfg(feature = "vxlan")]
+  vxlan_header -> VxlanHeader,
+  #[cfg(feature = "vxlan")]
+  vxlan_decap -> (VxlanHeader, EthernetFrame),
+  #[cfg(feature = "wifi")]
+  radiotap_header -> RadiotapHeader,
+  #[cfg(feature = "wifi")]
+  wifi_header -> WifiHeader,
+  #[cfg(feature = "wifi")]
+  wifi_frame -> WifiFrame<&[u8]>,
+  #[cfg(feature = "ip_addr")]
+  ipv4_address -> Ipv4Addr,
+  #[cfg(feature = "ip_addr")]
+  ipv6_address -> Ipv6Addr,
+  #[cfg(feature = "ip_addr")]
+  ipv6_address_strict -> Ipv6Addr,
+  #[cfg(feature = "ip_addr")]
+  ipv6_reference -> Ipv6Reference,
+  #[cfg(feature = "ip_addr")]
+  ipv6_address_zoned -> Ipv6Zoned<&[u8]>,
+  #[cfg(feature = "ip_addr")]
+  ipv6_reference_zoned -> Ipv6Zoned<&[u8]>,
+  #[cfg(feature = "ip_addr")]
+  ip_address -> IpAddr,
+  #[cfg(feature = "ip_addr")]
+  ip_address_or_reference -> IpAddr,
+  #[cfg(feature = "ip_addr")]
+  ipv4_cidr -> Ipv4Net,
+  #[cfg(feature = "ip_addr")]
+  ipv6_cidr -> Ipv6Net,
+  #[cfg(feature = "ip_addr")]
+  ip_cidr -> IpNet,
+  #[cfg(feature = "ip_addr")]
+  socket_addr_v4 -> SocketAddrV4,
+  #[cfg(feature = "ip_addr")]
+  socket_addr_v6 -> SocketAddrV6,
+  #[cfg(feature = "ip_addr")]
+  socket_addr -> SocketAddr,
+  #[cfg(feature = "ethernet")]
+  llc_header -> LlcHeader,
+  #[cfg(feature = "quic")]
+  quic_varint -> u64,
+  #[cfg(feature = "quic")]
+  quic_long_header -> QuicLongHeader<&[u8]>,
+  #[cfg(feature = "quic")]
+  quic_long_packet -> QuicLongPacket<&[u8]>,
+  #[cfg(feature = "sixlowpan")]
+  sixlowpan_fragment -> SixLowpanFragment,
+  #[cfg(feature = "framing")]
+  length_prefixed -> &[u8],
+  #[cfg(feature = "link_type")]
+  loopback_header -> LoopbackHeader,
+  #[cfg(feature = "lisp")]
+  lisp_data -> LispDataHeader<&[u8]>,
+  #[cfg(feature = "lisp")]
+  lisp_control -> LispControl<&[u8]>,
+}
+
+#[cfg(all(test, feature = "ipv4", feature = "tcp", feature = "ip_addr"))]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::Success;
+
+  use crate::Port;
+
+  #[test]
+  fn concrete_parsers() {
+    let bytes = [
+      0x45, 0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00,
+      0x6C, 0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39,
+      0xAE, 0xE6, 0x50, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00,
+    ];
+
+    let Success {
+      token: ipv4_header,
+      stream,
+    } = super::ipv4_header(&bytes).unwrap();
+    let Success {
+      token: tcp_header,
+      stream,
+    } = super::tcp_header(stream).unwrap();
+
+    assert_eq!(ipv4_header.source_addr, Ipv4Addr::new(192, 168, 0, 108));
+    assert_eq!(tcp_header.dest_port, Port(80));
+    assert_eq!(stream, b"");
+
+    let context = super::ipv4_address(b"256.0.0.1").unwrap_context();
+    assert!(!context.to_string().is_empty());
+  }
+}
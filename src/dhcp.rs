@@ -0,0 +1,406 @@
+//! Handles parsing of DHCPv4 messages and their options
+//! <https://www.rfc-editor.org/rfc/rfc2131>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::Ipv4Addr,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// UDP port of DHCP servers
+pub const DHCP_SERVER_PORT: Port = Port(67);
+/// UDP port of DHCP clients
+pub const DHCP_CLIENT_PORT: Port = Port(68);
+/// Value of the magic cookie that start the options
+pub const DHCP_MAGIC_COOKIE: u32 = 0x6382_5363;
+
+/// Meta trait for dhcp combinator
+pub trait DhcpParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<DhcpAtom>;
+
+/// Atom produced by dhcp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DhcpAtom {
+  /// When the magic cookie is not [DHCP_MAGIC_COOKIE]
+  MagicCookie(u32),
+  /// When the length of an option doesn't match its code
+  OptionLength {
+    /// Option code
+    code: u8,
+    /// Option length
+    length: u8,
+  },
+}
+
+impl Display for DhcpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DhcpAtom::MagicCookie(cookie) => {
+        write!(f, "MagicCookie: expected 0x63825363 found {:#010X}", cookie)
+      }
+      DhcpAtom::OptionLength { code, length } => {
+        write!(
+          f,
+          "OptionLength: option {} can't have a length of {}",
+          code, length
+        )
+      }
+    }
+  }
+}
+
+struct_variants! {
+  DhcpOp, op, u8:
+    /// Message sent by a client
+    BOOTREQUEST => 1,
+    /// Message sent by a server
+    BOOTREPLY => 2,
+}
+
+struct_variants! {
+  DhcpMessageType, message_type, u8:
+    /// DHCPDISCOVER
+    DISCOVER => 1,
+    /// DHCPOFFER
+    OFFER => 2,
+    /// DHCPREQUEST
+    REQUEST => 3,
+    /// DHCPDECLINE
+    DECLINE => 4,
+    /// DHCPACK
+    ACK => 5,
+    /// DHCPNAK
+    NAK => 6,
+    /// DHCPRELEASE
+    RELEASE => 7,
+    /// DHCPINFORM
+    INFORM => 8,
+}
+
+/// DHCP option, Pad and End are not reported
+/// <https://www.iana.org/assignments/bootp-dhcp-parameters/bootp-dhcp-parameters.xhtml>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DhcpOption<Span> {
+  /// Subnet Mask
+  SubnetMask(Ipv4Addr),
+  /// Routers, in order of preference
+  Router(Vec<Ipv4Addr>),
+  /// Domain Name Servers, in order of preference
+  DnsServers(Vec<Ipv4Addr>),
+  /// Vendor Specific Information
+  VendorSpecific(Span),
+  /// Requested IP Address
+  RequestedIp(Ipv4Addr),
+  /// IP Address Lease Time, in seconds
+  LeaseTime(u32),
+  /// DHCP Message Type
+  MessageType(DhcpMessageType),
+  /// Server Identifier
+  ServerIdentifier(Ipv4Addr),
+  /// Parameter Request List, codes of the options requested
+  ParameterRequestList(Span),
+  /// Any other option, code and data
+  Unknown(u8, Span),
+}
+
+/// DHCP message, the BOOTP fixed fields followed by options
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DhcpMessage<Span> {
+  /// Message op code
+  pub op: DhcpOp,
+  /// Hardware address type, 1 for ethernet
+  pub htype: u8,
+  /// Hardware address length
+  pub hlen: u8,
+  /// Number of relay agents the message went through
+  pub hops: u8,
+  /// Transaction ID chosen by the client
+  pub xid: u32,
+  /// Seconds elapsed since the client began address acquisition
+  pub secs: u16,
+  /// Flags, the most significant bit is BROADCAST
+  pub flags: u16,
+  /// Client IP address, when the client can respond to ARP requests
+  pub ciaddr: Ipv4Addr,
+  /// Your (client) IP address
+  pub yiaddr: Ipv4Addr,
+  /// IP address of next server to use in bootstrap
+  pub siaddr: Ipv4Addr,
+  /// Relay agent IP address
+  pub giaddr: Ipv4Addr,
+  /// Client hardware address, hlen bytes are used
+  pub chaddr: [u8; 16],
+  /// Optional server host name, null terminated
+  pub sname: Span,
+  /// Boot file name, null terminated
+  pub file: Span,
+  /// Options up to the End option
+  pub options: Vec<DhcpOption<Span>>,
+}
+
+impl<Span> DhcpMessage<Span> {
+  /// Return the message type option, every DHCP message have one
+  pub fn message_type(&self) -> Option<DhcpMessageType> {
+    self.options.iter().find_map(|option| match option {
+      DhcpOption::MessageType(message_type) => Some(*message_type),
+      _ => None,
+    })
+  }
+}
+
+fn dhcp_addr<Stream, Context>(stream: Stream) -> Parsed<Ipv4Addr, Stream, Context>
+where
+  (): DhcpParse<Stream, Context>,
+{
+  u32_be.map(Ipv4Addr::from).parse(stream)
+}
+
+fn dhcp_addrs<Stream, Context>(
+  stream: Stream, code: u8, length: u8,
+) -> Parsed<Vec<Ipv4Addr>, Stream, Context>
+where
+  (): DhcpParse<Stream, Context>,
+{
+  if length == 0 || !length.is_multiple_of(4) {
+    return Parsed::Failure(Context::new(DhcpAtom::OptionLength { code, length }));
+  }
+
+  dhcp_addr
+    .fold_bounds(usize::from(length / 4), Vec::new, Acc::acc)
+    .parse(stream)
+}
+
+// None for End
+fn dhcp_option<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Option<Option<DhcpOption<<Stream as Streaming>::Span>>>, Stream, Context>
+where
+  (): DhcpParse<Stream, Context>,
+{
+  let Success {
+    token: code,
+    stream,
+  } = octet.parse(stream)?;
+  match code {
+    0 => {
+      return Parsed::Success {
+        token: Some(None),
+        stream,
+      }
+    }
+    255 => {
+      return Parsed::Success {
+        token: None,
+        stream,
+      }
+    }
+    _ => {}
+  }
+
+  let Success {
+    token: length,
+    stream,
+  } = octet.parse(stream)?;
+  match (code, length) {
+    (1, 4) => dhcp_addr.map(DhcpOption::SubnetMask).parse(stream),
+    (3, _) => dhcp_addrs(stream, code, length).map_token(DhcpOption::Router),
+    (6, _) => dhcp_addrs(stream, code, length).map_token(DhcpOption::DnsServers),
+    (43, _) => take(usize::from(length))
+      .map(DhcpOption::VendorSpecific)
+      .parse(stream),
+    (50, 4) => dhcp_addr.map(DhcpOption::RequestedIp).parse(stream),
+    (51, 4) => u32_be.map(DhcpOption::LeaseTime).parse(stream),
+    (53, 1) => octet
+      .map(DhcpMessageType::new)
+      .map(DhcpOption::MessageType)
+      .parse(stream),
+    (54, 4) => dhcp_addr.map(DhcpOption::ServerIdentifier).parse(stream),
+    (55, _) => take(usize::from(length))
+      .map(DhcpOption::ParameterRequestList)
+      .parse(stream),
+    (1 | 50 | 51 | 53 | 54, _) => {
+      Parsed::Failure(Context::new(DhcpAtom::OptionLength { code, length }))
+    }
+    _ => take(usize::from(length))
+      .map(|data| DhcpOption::Unknown(code, data))
+      .parse(stream),
+  }
+  .map_token(|option| Some(Some(option)))
+}
+
+/// Parse a DHCP message, the payload of a UDP datagram on
+/// [DHCP_SERVER_PORT] or [DHCP_CLIENT_PORT]. Options are parsed up to the End
+/// option, the bytes following it are left in the stream.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn dhcp_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<DhcpMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): DhcpParse<Stream, Context>,
+{
+  let Success {
+    token: (op, htype, hlen, hops, xid, secs, flags),
+    stream,
+  } = (
+    octet.map(DhcpOp::new),
+    octet,
+    octet,
+    octet,
+    u32_be,
+    u16_be,
+    u16_be,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: (ciaddr, yiaddr, siaddr, giaddr),
+    stream,
+  } = (dhcp_addr, dhcp_addr, dhcp_addr, dhcp_addr).parse(stream)?;
+
+  let Success {
+    token: (chaddr, sname, file),
+    stream,
+  } = (octet.fill(), take(64), take(128)).parse(stream)?;
+
+  let Success { stream, .. } = u32_be
+    .try_map(|cookie| {
+      if cookie == DHCP_MAGIC_COOKIE {
+        Ok(cookie)
+      } else {
+        Err(Context::new(DhcpAtom::MagicCookie(cookie)))
+      }
+    })
+    .parse(stream)?;
+
+  let mut options = Vec::new();
+  let mut stream = stream;
+  loop {
+    let Success {
+      token: option,
+      stream: next,
+    } = dhcp_option.parse(stream)?;
+    stream = next;
+    match option {
+      Some(Some(option)) => options.push(option),
+      Some(None) => {}
+      None => break,
+    }
+  }
+
+  Parsed::Success {
+    token: DhcpMessage {
+      op,
+      htype,
+      hlen,
+      hops,
+      xid,
+      secs,
+      flags,
+      ciaddr,
+      yiaddr,
+      siaddr,
+      giaddr,
+      chaddr,
+      sname,
+      file,
+      options,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::{
+    DhcpMessageType,
+    DhcpOp,
+    DhcpOption,
+  };
+
+  #[test]
+  fn dhcp_message() {
+    let mut message = vec![
+      0x01, 0x01, 0x06, 0x00, 0x39, 0x03, 0xF3, 0x26, 0x00, 0x00, 0x80, 0x00,
+    ];
+    message.extend_from_slice(&[0; 16]);
+    message.extend_from_slice(&[0x00, 0x0B, 0x82, 0x01, 0xFC, 0x42]);
+    message.extend_from_slice(&[0; 10 + 64 + 128]);
+    message.extend_from_slice(&[
+      0x63, 0x82, 0x53, 0x63, 0x35, 0x01, 0x01, 0x00, 0x32, 0x04, 0xC0, 0xA8, 0x00, 0x0A, 0x37,
+      0x03, 0x01, 0x03, 0x06, 0x06, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x08, 0x04, 0x04, 0xFF,
+      0x00,
+    ]);
+
+    let Parsed::Success { token, stream } = super::dhcp_message::<_, Ignore>(message.as_slice())
+    else {
+      panic!("dhcp message");
+    };
+    assert_eq!(token.op, DhcpOp::BOOTREQUEST);
+    assert_eq!(token.xid, 0x3903_F326);
+    assert_eq!(token.flags, 0x8000);
+    assert_eq!(token.chaddr[..6], [0x00, 0x0B, 0x82, 0x01, 0xFC, 0x42]);
+    assert_eq!(token.message_type(), Some(DhcpMessageType::DISCOVER));
+    assert_eq!(
+      token.options,
+      [
+        DhcpOption::MessageType(DhcpMessageType::DISCOVER),
+        DhcpOption::RequestedIp(Ipv4Addr::new(192, 168, 0, 10)),
+        DhcpOption::ParameterRequestList(&[0x01, 0x03, 0x06][..]),
+        DhcpOption::DnsServers(vec![Ipv4Addr::new(8, 8, 8, 8), Ipv4Addr::new(8, 8, 4, 4)]),
+      ]
+    );
+    assert_eq!(stream, [0x00]);
+
+    // message type option length
+    message[236 + 5] = 0x02;
+    assert!(matches!(
+      super::dhcp_message::<_, Ignore>(message.as_slice()),
+      Parsed::Failure(_)
+    ));
+  }
+}
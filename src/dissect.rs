@@ -0,0 +1,576 @@
+//! Dissect a whole ethernet frame, from link to transport layer
+
+use binator::{
+  base::{
+    all,
+    take,
+  },
+  Contexting,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  arp_packet,
+  ethernet_frame,
+  guess_payload,
+  icmp_header,
+  icmpv6_header,
+  ipv4_header,
+  ipv6_header,
+  tcp_header,
+  udp_header,
+  udp_header_unchecked,
+  Application,
+  ArpPacket,
+  EtherType,
+  EthernetAtom,
+  EthernetFrame,
+  IPProtocol,
+  IPv4Header,
+  IPv6Header,
+  IcmpAtom,
+  IcmpHeader,
+  Icmpv6Header,
+  Ipv4Atom,
+  Ipv6Atom,
+  TcpHeader,
+  TcpParse,
+  UdpAtom,
+  UdpHeader,
+};
+
+/// Meta trait for dissect combinator
+pub trait DissectParse<Stream, Context> = where
+  (): TcpParse<Stream, Context>,
+  Stream: Streaming<Span = Stream>,
+  Context: Contexting<EthernetAtom>,
+  Context: Contexting<Ipv4Atom>,
+  Context: Contexting<Ipv6Atom>,
+  Context: Contexting<UdpAtom>,
+  Context: Contexting<IcmpAtom>;
+
+/// Network layer of a [Packet]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Network<Span> {
+  /// IPv4 header
+  Ipv4(IPv4Header<Span>),
+  /// IPv6 header
+  Ipv6(IPv6Header),
+  /// ARP packet
+  Arp(ArpPacket<Span>),
+}
+
+/// Transport layer of a [Packet]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Transport<Span> {
+  /// TCP header
+  Tcp(TcpHeader<Span>),
+  /// UDP header
+  Udp(UdpHeader),
+  /// ICMP header
+  Icmp(IcmpHeader),
+  /// ICMPv6 header
+  Icmpv6(Icmpv6Header),
+}
+
+impl<Span> Transport<Span> {
+  /// Return the application the payload likely carry, None for ICMP, see
+  /// [guess_payload]
+  pub fn guess_payload(&self) -> Option<Application> {
+    match self {
+      Self::Tcp(tcp) => guess_payload(IPProtocol::TCP, tcp.source_port, tcp.dest_port),
+      Self::Udp(udp) => guess_payload(IPProtocol::UDP, udp.source_port, udp.dest_port),
+      Self::Icmp(_) | Self::Icmpv6(_) => None,
+    }
+  }
+}
+
+/// Layers decoded by [dissect_ethernet], dissection stop at the first layer
+/// this crate doesn't know, its bytes are the payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Packet<Span> {
+  /// Ethernet header, VLAN tag and LLC header included
+  pub ethernet: EthernetFrame,
+  /// IPv4 or IPv6 header or ARP packet
+  pub network: Option<Network<Span>>,
+  /// TCP, UDP, ICMP or ICMPv6 header, only for unfragmented or first fragment
+  /// packets, the UDP length of a first fragment is not checked
+  pub transport: Option<Transport<Span>>,
+  /// Bytes following the last decoded header, bounded by the length fields
+  /// when they are known so ethernet padding is left in the stream
+  pub payload: Span,
+}
+
+impl<Span> Packet<Span> {
+  /// Return the application the payload likely carry, None without transport
+  /// layer, see [guess_payload]
+  pub fn guess_payload(&self) -> Option<Application> {
+    self.transport.as_ref().and_then(Transport::guess_payload)
+  }
+}
+
+/// Layer of a [Packet] that [dissect_ethernet_lenient] failed to parse
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DissectLayer {
+  /// IPv4 or IPv6 header or ARP packet
+  Network,
+  /// TCP, UDP, ICMP or ICMPv6 header
+  Transport,
+}
+
+/// Layers decoded by [dissect_ethernet_lenient] and the context of every
+/// layer that failed, a failed layer is None and its bytes are the payload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LenientPacket<Span, Context> {
+  /// Layers parsed before the first failure
+  pub packet: Packet<Span>,
+  /// Failures in layer order
+  pub errors: Vec<(DissectLayer, Context)>,
+}
+
+type NetworkLayer<Span> = (
+  Option<Network<Span>>,
+  Option<IPProtocol>,
+  bool,
+  Option<usize>,
+);
+
+type TransportLayer<Span> = (Option<Transport<Span>>, Option<usize>);
+
+fn network_layer<Stream, Context>(
+  ethernet: &EthernetFrame, stream: Stream,
+) -> Parsed<NetworkLayer<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): DissectParse<Stream, Context>,
+{
+  // the first fragment only hold the start of the transport payload
+  match ethernet.payload_ether_type() {
+    Some(EtherType::IPV4) => {
+      let Success { token, stream } = ipv4_header.parse(stream)?;
+      let protocol = (token.fragment_offset == 0).then_some(token.protocol);
+      let fragment = token.is_fragment();
+      let len = token.payload_len();
+      Parsed::Success {
+        token: (Some(Network::Ipv4(token)), protocol, fragment, len),
+        stream,
+      }
+    }
+    Some(EtherType::IPV6) => {
+      let Success { token, stream } = ipv6_header.parse(stream)?;
+      let protocol = Some(token.next_header);
+      let len = Some(usize::from(token.length));
+      Parsed::Success {
+        token: (Some(Network::Ipv6(token)), protocol, false, len),
+        stream,
+      }
+    }
+    // ARP carry nothing, what follow is ethernet padding
+    Some(EtherType::ARP) => {
+      let Success { token, stream } = arp_packet.parse(stream)?;
+      Parsed::Success {
+        token: (Some(Network::Arp(token)), None, false, Some(0)),
+        stream,
+      }
+    }
+    _ => Parsed::Success {
+      token: (None, None, false, ethernet.payload_len()),
+      stream,
+    },
+  }
+}
+
+fn transport_layer<Stream, Context>(
+  protocol: Option<IPProtocol>, fragment: bool, len: Option<usize>, stream: Stream,
+) -> Parsed<TransportLayer<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): DissectParse<Stream, Context>,
+{
+  match protocol {
+    Some(IPProtocol::TCP) => {
+      let Success { token, stream } = tcp_header.parse(stream)?;
+      let len = len.and_then(|len| len.checked_sub(token.header_len()));
+      Parsed::Success {
+        token: (Some(Transport::Tcp(token)), len),
+        stream,
+      }
+    }
+    Some(IPProtocol::UDP) if fragment => {
+      let Success { token, stream } = udp_header_unchecked.parse(stream)?;
+      let len = len.and_then(|len| len.checked_sub(UdpHeader::LEN));
+      Parsed::Success {
+        token: (Some(Transport::Udp(token)), len),
+        stream,
+      }
+    }
+    Some(IPProtocol::UDP) => {
+      let Success { token, stream } = udp_header.parse(stream)?;
+      let len = usize::from(token.length).checked_sub(UdpHeader::LEN);
+      Parsed::Success {
+        token: (Some(Transport::Udp(token)), len),
+        stream,
+      }
+    }
+    Some(IPProtocol::ICMP) => {
+      let Success { token, stream } = icmp_header.parse(stream)?;
+      let len = len.and_then(|len| len.checked_sub(IcmpHeader::LEN));
+      Parsed::Success {
+        token: (Some(Transport::Icmp(token)), len),
+        stream,
+      }
+    }
+    Some(IPProtocol::ICMP_6) => {
+      let Success { token, stream } = icmpv6_header.parse(stream)?;
+      let len = len.and_then(|len| len.checked_sub(Icmpv6Header::LEN));
+      Parsed::Success {
+        token: (Some(Transport::Icmpv6(token)), len),
+        stream,
+      }
+    }
+    _ => Parsed::Success {
+      token: (None, len),
+      stream,
+    },
+  }
+}
+
+fn payload<Stream, Context>(
+  len: Option<usize>, stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): DissectParse<Stream, Context>,
+{
+  // length fields can lie, the payload is then everything left
+  match len.map(|len| take(len).parse(stream.clone())) {
+    Some(Parsed::Success { token, stream }) => Parsed::Success { token, stream },
+    Some(Parsed::Error(context)) => Parsed::Error(context),
+    Some(Parsed::Failure(_)) | None => all.parse(stream),
+  }
+}
+
+/// Parse an ethernet frame then the IPv4 or IPv6 header or the ARP packet, and
+/// the TCP, UDP, ICMP or ICMPv6 header the IP header carry, switching on
+/// EtherType and IP protocol. A malformed header of a known protocol is a
+/// failure.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn dissect_ethernet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Packet<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): DissectParse<Stream, Context>,
+{
+  let Success {
+    token: ethernet,
+    stream,
+  } = ethernet_frame.parse(stream)?;
+  let Success {
+    token: (network, protocol, fragment, len),
+    stream,
+  } = network_layer(&ethernet, stream)?;
+  let Success {
+    token: (transport, len),
+    stream,
+  } = transport_layer(protocol, fragment, len, stream)?;
+  let Success {
+    token: payload,
+    stream,
+  } = payload(len, stream)?;
+
+  Parsed::Success {
+    token: Packet {
+      ethernet,
+      network,
+      transport,
+      payload,
+    },
+    stream,
+  }
+}
+
+/// Like [dissect_ethernet] but a malformed IP or transport header doesn't fail
+/// the frame, dissection stop at this layer and its failure is recorded, for
+/// forensic analysis of malformed or attack traffic. Only a malformed ethernet
+/// header is a failure.
+// no ret(Display), the recorded contexts are not required to be Debug
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all))]
+pub fn dissect_ethernet_lenient<Stream, Context>(
+  stream: Stream,
+) -> Parsed<LenientPacket<<Stream as Streaming>::Span, Context>, Stream, Context>
+where
+  (): DissectParse<Stream, Context>,
+{
+  let Success {
+    token: ethernet,
+    stream,
+  } = ethernet_frame.parse(stream)?;
+  let mut errors = Vec::new();
+
+  let (network, protocol, fragment, len, stream) = match network_layer(&ethernet, stream.clone()) {
+    Parsed::Success {
+      token: (network, protocol, fragment, len),
+      stream,
+    } => (network, protocol, fragment, len, stream),
+    Parsed::Failure(context) => {
+      errors.push((DissectLayer::Network, context));
+      (None, None, false, None, stream)
+    }
+    Parsed::Error(context) => return Parsed::Error(context),
+  };
+
+  let (transport, len, stream) = match transport_layer(protocol, fragment, len, stream.clone()) {
+    Parsed::Success {
+      token: (transport, len),
+      stream,
+    } => (transport, len, stream),
+    Parsed::Failure(context) => {
+      errors.push((DissectLayer::Transport, context));
+      (None, len, stream)
+    }
+    Parsed::Error(context) => return Parsed::Error(context),
+  };
+
+  let Success {
+    token: payload,
+    stream,
+  } = payload(len, stream)?;
+
+  Parsed::Success {
+    token: LenientPacket {
+      packet: Packet {
+        ethernet,
+        network,
+        transport,
+        payload,
+      },
+      errors,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    DissectLayer,
+    LenientPacket,
+    Network,
+    Packet,
+    Transport,
+  };
+  use crate::{
+    Application,
+    ArpOperation,
+    EtherType,
+    IcmpType,
+    Icmpv6Type,
+    Port,
+  };
+
+  #[test]
+  fn dissect_ethernet() {
+    let mut frame = vec![
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00, 0x6C,
+      0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39, 0xAE,
+      0xE6, 0x50, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00, 0x47, 0x45, 0x54, 0x20, 0x2F, 0x69,
+      0x6E, 0x64, 0x65, 0x78, 0x2E, 0x68, 0x74, 0x6D, 0x6C, 0x0A,
+    ];
+    // ethernet padding
+    frame.extend_from_slice(&[0, 0]);
+
+    let Success {
+      token: Packet {
+        ethernet,
+        network,
+        transport,
+        payload,
+      },
+      stream,
+    } = super::dissect_ethernet::<_, Ignore>(frame.as_slice()).unwrap();
+    assert_eq!(
+      transport.as_ref().and_then(Transport::guess_payload),
+      Some(Application::Http)
+    );
+    assert_eq!(ethernet.ether_type.ether_type(), Some(EtherType::IPV4));
+    let Some(Network::Ipv4(ipv4)) = network else {
+      panic!("ipv4 header");
+    };
+    assert_eq!(ipv4.source_addr, Ipv4Addr::new(192, 168, 0, 108));
+    let Some(Transport::Tcp(tcp)) = transport else {
+      panic!("tcp header");
+    };
+    assert_eq!(tcp.dest_port, Port(80));
+    assert_eq!(payload, b"GET /index.html\x0a");
+    assert_eq!(stream, [0, 0]);
+
+    let lldp = [
+      0x01, 0x80, 0xC2, 0x00, 0x00, 0x0E, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x88, 0xCC, 0x02,
+      0x07,
+    ];
+    let packet = super::dissect_ethernet::<_, Ignore>(&lldp[..])
+      .unwrap()
+      .token;
+    assert_eq!(packet.network, None);
+    assert_eq!(packet.guess_payload(), None);
+    assert_eq!(packet.payload, [0x02, 0x07]);
+
+    assert!(matches!(
+      super::dissect_ethernet::<_, Ignore>(&frame[..20]),
+      Parsed::Failure(_)
+    ));
+
+    // first fragment, MF set and offset 0, of a 2000 bytes UDP datagram
+    let mut fragment = vec![
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x24, 0x12, 0x34, 0x20, 0x00, 0x40, 0x11, 0x00, 0x00, 0xC0, 0xA8, 0x00, 0x6C,
+      0xC0, 0xA8, 0x00, 0x01, 0xD4, 0x31, 0x00, 0x35, 0x07, 0xD0, 0x00, 0x00, 0xDE, 0xAD, 0xBE,
+      0xEF, 0xCA, 0xFE, 0xBA, 0xBE,
+    ];
+    fragment.extend_from_slice(&[0; 10]);
+    let Success { token, stream } =
+      super::dissect_ethernet::<_, Ignore>(fragment.as_slice()).unwrap();
+    let Some(Transport::Udp(udp)) = token.transport else {
+      panic!("udp header");
+    };
+    assert_eq!(udp.length, 2000);
+    assert_eq!(udp.dest_port, Port(53));
+    assert_eq!(
+      token.payload,
+      [0xDE, 0xAD, 0xBE, 0xEF, 0xCA, 0xFE, 0xBA, 0xBE]
+    );
+    assert_eq!(stream, [0; 10]);
+  }
+
+  #[test]
+  fn dissect_arp() {
+    let mut frame = vec![
+      0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x06, 0x00,
+      0x01, 0x08, 0x00, 0x06, 0x04, 0x00, 0x01, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0xC0, 0xA8,
+      0x00, 0x6C, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xC0, 0xA8, 0x00, 0x01,
+    ];
+    // ethernet padding
+    frame.extend_from_slice(&[0; 18]);
+
+    let Success { token, stream } = super::dissect_ethernet::<_, Ignore>(frame.as_slice()).unwrap();
+    let Some(Network::Arp(arp)) = &token.network else {
+      panic!("arp packet");
+    };
+    assert_eq!(arp.operation, ArpOperation::REQUEST);
+    assert_eq!(arp.target_ipv4(), Some(Ipv4Addr::new(192, 168, 0, 1)));
+    assert_eq!(token.transport, None);
+    assert_eq!(token.payload, []);
+    assert_eq!(stream, [0; 18]);
+
+    assert!(matches!(
+      super::dissect_ethernet::<_, Ignore>(&frame[..30]),
+      Parsed::Failure(_)
+    ));
+  }
+
+  #[test]
+  fn dissect_icmp() {
+    // echo request
+    let frame = [
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x20, 0x12, 0x34, 0x00, 0x00, 0x40, 0x01, 0x00, 0x00, 0xC0, 0xA8, 0x00, 0x6C,
+      0xC0, 0xA8, 0x00, 0x01, 0x08, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x2A, 0xDE, 0xAD, 0xBE,
+      0xEF,
+    ];
+    let Success { token, stream } = super::dissect_ethernet::<_, Ignore>(&frame[..]).unwrap();
+    let Some(Transport::Icmp(icmp)) = token.transport else {
+      panic!("icmp header");
+    };
+    assert_eq!(icmp.icmp_type, IcmpType::ECHO);
+    assert_eq!(icmp.rest, 0x0001_002A);
+    assert_eq!(token.guess_payload(), None);
+    assert_eq!(token.payload, [0xDE, 0xAD, 0xBE, 0xEF]);
+    assert_eq!(stream, []);
+
+    // neighbor solicitation, payload truncated to the target address
+    let frame = [
+      0x33, 0x33, 0xFF, 0x00, 0x00, 0x01, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x86, 0xDD, 0x60,
+      0x00, 0x00, 0x00, 0x00, 0x18, 0x3A, 0xFF, 0xFE, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0xFF, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x01, 0x87, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0xFE, 0x80, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x01,
+    ];
+    let Success { token, stream } = super::dissect_ethernet::<_, Ignore>(&frame[..]).unwrap();
+    let Some(Transport::Icmpv6(icmpv6)) = token.transport else {
+      panic!("icmpv6 header");
+    };
+    assert_eq!(icmpv6.icmpv6_type, Icmpv6Type::NEIGHBOR_SOLICITATION);
+    assert_eq!(token.payload.len(), 16);
+    assert_eq!(stream, []);
+
+    assert!(matches!(
+      super::dissect_ethernet::<_, Ignore>(&frame[..58]),
+      Parsed::Failure(_)
+    ));
+  }
+
+  #[test]
+  fn dissect_ethernet_lenient() {
+    // TCP data offset of 8 words but the options are truncated
+    let frame = [
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x30, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00, 0x6C,
+      0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39, 0xAE,
+      0xE6, 0x80, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00, 0x01, 0x01, 0x08, 0x0A,
+    ];
+    assert!(matches!(
+      super::dissect_ethernet::<_, Ignore>(&frame[..]),
+      Parsed::Failure(_)
+    ));
+
+    let Success {
+      token: LenientPacket { packet, errors },
+      stream,
+    } = super::dissect_ethernet_lenient::<_, Ignore>(&frame[..]).unwrap();
+    assert!(matches!(packet.network, Some(Network::Ipv4(_))));
+    assert_eq!(packet.transport, None);
+    assert_eq!(packet.payload, &frame[34..]);
+    assert_eq!(stream, []);
+    assert_eq!(
+      errors.iter().map(|(layer, _)| *layer).collect::<Vec<_>>(),
+      [DissectLayer::Transport]
+    );
+
+    // truncated IPv4 header
+    let Success {
+      token: LenientPacket { packet, errors },
+      ..
+    } = super::dissect_ethernet_lenient::<_, Ignore>(&frame[..24]).unwrap();
+    assert_eq!(packet.network, None);
+    assert_eq!(packet.payload, &frame[14..24]);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, DissectLayer::Network);
+
+    // without options the frame is well formed
+    let mut frame = frame;
+    frame[46] = 0x50;
+    let Success {
+      token: LenientPacket { packet, errors },
+      ..
+    } = super::dissect_ethernet_lenient::<_, Ignore>(&frame[..]).unwrap();
+    assert!(matches!(packet.transport, Some(Transport::Tcp(_))));
+    assert!(errors.is_empty());
+  }
+}
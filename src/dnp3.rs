@@ -0,0 +1,671 @@
+//! Handles parsing of DNP3 link frames, transport segments and application
+//! fragment headers
+//! <https://www.dnp.org/About/Overview-of-DNP3-Protocol>
+
+use std::{
+  collections::HashMap,
+  fmt::{
+    Display,
+    Formatter,
+  },
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_le,
+      u32_le,
+    },
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// TCP and UDP port of DNP3
+pub const DNP3_PORT: Port = Port(20000);
+
+/// Meta trait for dnp3 combinator
+pub trait Dnp3Parse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Dnp3Atom>;
+
+/// Atom produced by dnp3 parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Dnp3Atom {
+  /// When the start bytes are not 0x05 0x64
+  Start(u16),
+  /// When the length is shorter than the header
+  Length(u8),
+  /// When a CRC doesn't match the block it cover
+  Crc {
+    /// CRC computed from the block
+    expected: u16,
+    /// CRC found after the block
+    found: u16,
+  },
+  /// When an object header qualifier has an unknown range specifier
+  Qualifier(u8),
+}
+
+impl Display for Dnp3Atom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Dnp3Atom::Start(start) => write!(f, "Start: expected 0x0564 found {:#06X}", start),
+      Dnp3Atom::Length(length) => write!(f, "Length: length {} is shorter than 5", length),
+      Dnp3Atom::Crc { expected, found } => {
+        write!(f, "Crc: expected {:#06X} found {:#06X}", expected, found)
+      }
+      Dnp3Atom::Qualifier(qualifier) => {
+        write!(
+          f,
+          "Qualifier: unknown range specifier in {:#04X}",
+          qualifier
+        )
+      }
+    }
+  }
+}
+
+const DNP3_CRC_TABLE: [u16; 256] = {
+  let mut table = [0; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut crc = i as u16;
+    let mut bit = 0u8;
+    while bit < 8 {
+      crc = if crc & 1 != 0 {
+        crc >> 1u8 ^ 0xA6BC
+      } else {
+        crc >> 1u8
+      };
+      bit += 1;
+    }
+    table[i] = crc;
+    i += 1;
+  }
+  table
+};
+
+const fn dnp3_crc_update(state: u16, byte: u8) -> u16 {
+  DNP3_CRC_TABLE[((state ^ byte as u16) & 0xFF) as usize] ^ state >> 8u8
+}
+
+/// Return the CRC-16 of data as used by DNP3 link frames
+pub fn dnp3_crc(data: &[u8]) -> u16 {
+  !data
+    .iter()
+    .fold(0, |state, &byte| dnp3_crc_update(state, byte))
+}
+
+/// Header of a link frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dnp3LinkHeader {
+  /// Length of control, addresses and user data, CRCs excluded
+  pub length: u8,
+  /// Direction, primary, frame count bits and function code
+  pub control: u8,
+  /// Destination address
+  pub destination: u16,
+  /// Source address
+  pub source: u16,
+}
+
+impl Dnp3LinkHeader {
+  /// Direction bit, set from master to outstation
+  pub const DIR: u8 = 0x80;
+  /// Length of a link header, its CRC included
+  pub const LEN: usize = 10;
+  /// Primary bit, set when the frame initiate a transaction
+  pub const PRM: u8 = 0x40;
+  /// Start bytes
+  pub const START: u16 = 0x0564;
+
+  /// Return the link function code
+  pub const fn function(&self) -> u8 {
+    self.control & 0x0F
+  }
+
+  /// Return the length of the user data
+  pub const fn data_len(&self) -> usize {
+    self.length as usize - 5
+  }
+}
+
+/// Link frame with the CRCs of its user data removed
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dnp3Frame {
+  /// Header
+  pub header: Dnp3LinkHeader,
+  /// User data, a transport segment
+  pub data: Vec<u8>,
+}
+
+fn dnp3_crc_check<Stream, Context>(stream: Stream, state: u16) -> Parsed<(), Stream, Context>
+where
+  (): Dnp3Parse<Stream, Context>,
+{
+  u16_le
+    .try_map(|found| {
+      let expected = !state;
+      if expected == found {
+        Ok(())
+      } else {
+        Err(Context::new(Dnp3Atom::Crc { expected, found }))
+      }
+    })
+    .parse(stream)
+}
+
+/// Parse a link frame, every CRC is verified
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn dnp3_frame<Stream, Context>(stream: Stream) -> Parsed<Dnp3Frame, Stream, Context>
+where
+  (): Dnp3Parse<Stream, Context>,
+{
+  let Success {
+    token: bytes,
+    stream,
+  } = octet.fill::<8>().parse(stream)?;
+  let [start_0, start_1, length, control, destination_0, destination_1, source_0, source_1] = bytes;
+  let start = u16::from_be_bytes([start_0, start_1]);
+  if start != Dnp3LinkHeader::START {
+    return Parsed::Failure(Context::new(Dnp3Atom::Start(start)));
+  }
+  if length < 5 {
+    return Parsed::Failure(Context::new(Dnp3Atom::Length(length)));
+  }
+  let state = bytes
+    .iter()
+    .fold(0, |state, &byte| dnp3_crc_update(state, byte));
+  let Success { mut stream, .. } = dnp3_crc_check(stream, state)?;
+  let header = Dnp3LinkHeader {
+    length,
+    control,
+    destination: u16::from_le_bytes([destination_0, destination_1]),
+    source: u16::from_le_bytes([source_0, source_1]),
+  };
+
+  // user data is split in blocks of 16 bytes, each followed by its CRC
+  let mut len = header.data_len();
+  let mut data = Vec::with_capacity(len);
+  while len > 0 {
+    let block = len.min(16);
+    let Success {
+      token: state,
+      stream: next,
+    } = octet
+      .fold_bounds(
+        block,
+        || 0,
+        |state, byte| {
+          data.push(byte);
+          dnp3_crc_update(state, byte)
+        },
+      )
+      .parse(stream)?;
+    let Success { stream: next, .. } = dnp3_crc_check(next, state)?;
+    len -= block;
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: Dnp3Frame { header, data },
+    stream,
+  }
+}
+
+/// Transport header, the first byte of the user data of a link frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dnp3TransportHeader {
+  /// FIN, FIR and sequence number
+  pub raw: u8,
+}
+
+impl Dnp3TransportHeader {
+  /// Return true if the segment is the last of a fragment
+  pub const fn fin(&self) -> bool {
+    self.raw & 0x80 != 0
+  }
+
+  /// Return true if the segment is the first of a fragment
+  pub const fn fir(&self) -> bool {
+    self.raw & 0x40 != 0
+  }
+
+  /// Return the 6 bits sequence number
+  pub const fn sequence(&self) -> u8 {
+    self.raw & 0x3F
+  }
+}
+
+#[derive(Clone, Debug)]
+struct Dnp3Fragment {
+  sequence: u8,
+  data: Vec<u8>,
+}
+
+/// Rebuild application fragments from the transport segments of link frames.
+/// A segment with FIR start a new fragment, a segment out of sequence discard
+/// the fragment being rebuilt.
+///
+/// A fragment growing beyond the maximum length is discarded, a new fragment
+/// is ignored while the maximum number of address pairs are rebuilding one.
+#[derive(Clone, Debug)]
+pub struct Dnp3Reassembler {
+  fragments: HashMap<(u16, u16), Dnp3Fragment>,
+  max_fragments: usize,
+  max_len: usize,
+}
+
+impl Default for Dnp3Reassembler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl Dnp3Reassembler {
+  /// Default number of fragments rebuilt at the same time
+  pub const MAX_FRAGMENTS: usize = 1024;
+  /// Default maximum length of a fragment, the maximum of IEEE 1815
+  pub const MAX_LEN: usize = 2048;
+
+  /// Return an empty Dnp3Reassembler with the default limits
+  pub fn new() -> Self {
+    Self::with_limits(Self::MAX_FRAGMENTS, Self::MAX_LEN)
+  }
+
+  /// Return an empty Dnp3Reassembler with custom limits
+  pub fn with_limits(max_fragments: usize, max_len: usize) -> Self {
+    Self {
+      fragments: HashMap::new(),
+      max_fragments,
+      max_len,
+    }
+  }
+
+  /// Add the user data of a frame, return the application fragment once its
+  /// last segment is received
+  pub fn push(&mut self, frame: &Dnp3Frame) -> Option<Vec<u8>> {
+    let (&raw, segment) = frame.data.split_first()?;
+    let transport = Dnp3TransportHeader { raw };
+    let key = (frame.header.source, frame.header.destination);
+
+    if transport.fir() {
+      self.fragments.remove(&key);
+      if segment.len() > self.max_len {
+        return None;
+      }
+      // a single segment fragment doesn't need to be tracked
+      if transport.fin() {
+        return Some(segment.to_vec());
+      }
+      if self.fragments.len() >= self.max_fragments {
+        return None;
+      }
+      self.fragments.insert(
+        key,
+        Dnp3Fragment {
+          sequence: transport.sequence(),
+          data: segment.to_vec(),
+        },
+      );
+    } else {
+      let fragment = self.fragments.get_mut(&key)?;
+      if transport.sequence() != (fragment.sequence + 1) & 0x3F
+        || fragment.data.len() + segment.len() > self.max_len
+      {
+        self.fragments.remove(&key);
+        return None;
+      }
+      fragment.sequence = transport.sequence();
+      fragment.data.extend_from_slice(segment);
+    }
+
+    if transport.fin() {
+      self.fragments.remove(&key).map(|fragment| fragment.data)
+    } else {
+      None
+    }
+  }
+
+  /// Return the number of fragments being rebuilt
+  pub fn len(&self) -> usize {
+    self.fragments.len()
+  }
+
+  /// Return true if no fragment is being rebuilt
+  pub fn is_empty(&self) -> bool {
+    self.fragments.is_empty()
+  }
+}
+
+struct_variants! {
+  Dnp3FunctionCode, function, u8:
+    /// Confirm
+    CONFIRM => 0x00,
+    /// Read
+    READ => 0x01,
+    /// Write
+    WRITE => 0x02,
+    /// Select
+    SELECT => 0x03,
+    /// Operate
+    OPERATE => 0x04,
+    /// Direct Operate
+    DIRECT_OPERATE => 0x05,
+    /// Cold Restart
+    COLD_RESTART => 0x0D,
+    /// Warm Restart
+    WARM_RESTART => 0x0E,
+    /// Enable Unsolicited
+    ENABLE_UNSOLICITED => 0x14,
+    /// Disable Unsolicited
+    DISABLE_UNSOLICITED => 0x15,
+    /// Response
+    RESPONSE => 0x81,
+    /// Unsolicited Response
+    UNSOLICITED_RESPONSE => 0x82,
+    /// Authentication Response
+    AUTHENTICATION_RESPONSE => 0x83,
+}
+
+impl Dnp3FunctionCode {
+  /// Return true if the function is sent by an outstation, its header then
+  /// carry internal indications
+  pub const fn is_response(&self) -> bool {
+    self.function >= 0x81
+  }
+}
+
+/// Header of an application fragment
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dnp3ApplicationHeader {
+  /// FIR, FIN, CON, UNS bits and sequence number
+  pub control: u8,
+  /// Function code
+  pub function: Dnp3FunctionCode,
+  /// Internal indications, only in responses
+  pub iin: Option<u16>,
+}
+
+impl Dnp3ApplicationHeader {
+  /// Confirmation requested
+  pub const CON: u8 = 0x20;
+  /// Unsolicited response
+  pub const UNS: u8 = 0x10;
+
+  /// Return the 4 bits sequence number
+  pub const fn sequence(&self) -> u8 {
+    self.control & 0x0F
+  }
+}
+
+/// Parse the header of an application fragment, the stream is left at the
+/// first object header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn dnp3_application_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Dnp3ApplicationHeader, Stream, Context>
+where
+  (): Dnp3Parse<Stream, Context>,
+{
+  let Success {
+    token: (control, function),
+    stream,
+  } = (octet, octet.map(Dnp3FunctionCode::new)).parse(stream)?;
+
+  let Success { token: iin, stream } = if function.is_response() {
+    octet
+      .fill::<2>()
+      .map(|iin| Some(u16::from_be_bytes(iin)))
+      .parse(stream)?
+  } else {
+    Success {
+      token: None,
+      stream,
+    }
+  };
+
+  Parsed::Success {
+    token: Dnp3ApplicationHeader {
+      control,
+      function,
+      iin,
+    },
+    stream,
+  }
+}
+
+/// Range of an object header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dnp3Range {
+  /// Start and stop indexes, inclusive
+  StartStop(u32, u32),
+  /// Number of objects
+  Count(u32),
+  /// Every object of the group and variation
+  All,
+}
+
+/// Object header, the size of the objects following it depend on the group
+/// and variation so they are left to the caller
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Dnp3ObjectHeader {
+  /// Object group
+  pub group: u8,
+  /// Object variation
+  pub variation: u8,
+  /// Prefix and range specifier codes
+  pub qualifier: u8,
+  /// Range
+  pub range: Dnp3Range,
+}
+
+impl Dnp3ObjectHeader {
+  /// Return the object prefix code
+  pub const fn prefix_code(&self) -> u8 {
+    self.qualifier >> 4u8 & 0x07
+  }
+
+  /// Return the range specifier code
+  pub const fn range_code(&self) -> u8 {
+    self.qualifier & 0x0F
+  }
+
+  /// Return the number of objects, None for [Dnp3Range::All]
+  pub const fn count(&self) -> Option<u32> {
+    match self.range {
+      Dnp3Range::StartStop(start, stop) => Some(stop.wrapping_sub(start).wrapping_add(1)),
+      Dnp3Range::Count(count) => Some(count),
+      Dnp3Range::All => None,
+    }
+  }
+}
+
+/// Parse an object header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn dnp3_object_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Dnp3ObjectHeader, Stream, Context>
+where
+  (): Dnp3Parse<Stream, Context>,
+{
+  let Success {
+    token: (group, variation, qualifier),
+    stream,
+  } = (octet, octet, octet).parse(stream)?;
+
+  let Success {
+    token: range,
+    stream,
+  } = match qualifier & 0x0F {
+    0x0 | 0x3 => (octet, octet)
+      .map(|(start, stop)| Dnp3Range::StartStop(u32::from(start), u32::from(stop)))
+      .parse(stream)?,
+    0x1 | 0x4 => (u16_le, u16_le)
+      .map(|(start, stop)| Dnp3Range::StartStop(u32::from(start), u32::from(stop)))
+      .parse(stream)?,
+    0x2 | 0x5 => (u32_le, u32_le)
+      .map(|(start, stop)| Dnp3Range::StartStop(start, stop))
+      .parse(stream)?,
+    0x6 => Success {
+      token: Dnp3Range::All,
+      stream,
+    },
+    0x7 | 0xB => octet
+      .map(|count| Dnp3Range::Count(u32::from(count)))
+      .parse(stream)?,
+    0x8 => u16_le
+      .map(|count| Dnp3Range::Count(u32::from(count)))
+      .parse(stream)?,
+    0x9 => u32_le.map(Dnp3Range::Count).parse(stream)?,
+    _ => return Parsed::Failure(Context::new(Dnp3Atom::Qualifier(qualifier))),
+  };
+
+  Parsed::Success {
+    token: Dnp3ObjectHeader {
+      group,
+      variation,
+      qualifier,
+      range,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    Dnp3Frame,
+    Dnp3FunctionCode,
+    Dnp3LinkHeader,
+    Dnp3Range,
+    Dnp3Reassembler,
+  };
+
+  fn frame(data: &[u8]) -> Vec<u8> {
+    let mut frame = vec![
+      0x05,
+      0x64,
+      5 + data.len() as u8,
+      0xC4,
+      0x01,
+      0x00,
+      0x00,
+      0x04,
+    ];
+    frame.extend_from_slice(&super::dnp3_crc(&frame).to_le_bytes());
+    for block in data.chunks(16) {
+      frame.extend_from_slice(block);
+      frame.extend_from_slice(&super::dnp3_crc(block).to_le_bytes());
+    }
+    frame
+  }
+
+  #[test]
+  fn dnp3_read() {
+    let status = [0x05, 0x64, 0x05, 0xC0, 0x01, 0x00, 0x00, 0x04, 0xE9, 0x21];
+    let Success { token, stream } = super::dnp3_frame::<_, Ignore>(&status[..]).unwrap();
+    assert_eq!(token.header.destination, 1);
+    assert_eq!(token.header.source, 1024);
+    assert!(token.data.is_empty());
+    assert_eq!(stream, b"");
+
+    // class 0 read split over two segments
+    let mut reassembler = Dnp3Reassembler::new();
+    let first = frame(&[0x41, 0xC3, 0x01, 0x3C, 0x01]);
+    let second = frame(&[0x82, 0x06]);
+    let Success { token, .. } = super::dnp3_frame::<_, Ignore>(first.as_slice()).unwrap();
+    assert_eq!(reassembler.push(&token), None);
+    let Success { token, .. } = super::dnp3_frame::<_, Ignore>(second.as_slice()).unwrap();
+    let fragment = reassembler.push(&token).unwrap();
+    assert!(reassembler.is_empty());
+
+    let Success { token, stream } =
+      super::dnp3_application_header::<_, Ignore>(fragment.as_slice()).unwrap();
+    assert_eq!(token.function, Dnp3FunctionCode::READ);
+    assert_eq!(token.sequence(), 3);
+    assert_eq!(token.iin, None);
+    let Success { token, stream } = super::dnp3_object_header::<_, Ignore>(stream).unwrap();
+    assert_eq!((token.group, token.variation), (60, 1));
+    assert_eq!(token.range, Dnp3Range::All);
+    assert_eq!(stream, b"");
+
+    let mut corrupted = first;
+    corrupted[10] ^= 0xFF;
+    assert!(matches!(
+      super::dnp3_frame::<_, Ignore>(corrupted.as_slice()),
+      Parsed::Failure(_)
+    ));
+  }
+
+  #[test]
+  fn dnp3_reassembler_limits() {
+    let segment = |source: u16, raw: u8, len: usize| Dnp3Frame {
+      header: Dnp3LinkHeader {
+        length: 6,
+        control: 0xC4,
+        destination: 1,
+        source,
+      },
+      data: [&[raw][..], &vec![0xAA; len]].concat(),
+    };
+
+    // in sequence segments without FIN, the sequence wrap
+    let mut reassembler = Dnp3Reassembler::with_limits(2, 600);
+    assert_eq!(reassembler.push(&segment(1, 0x40 | 0x3F, 249)), None);
+    assert_eq!(reassembler.push(&segment(1, 0x00, 249)), None);
+    assert_eq!(reassembler.len(), 1);
+    assert_eq!(reassembler.push(&segment(1, 0x01, 249)), None);
+    assert!(reassembler.is_empty());
+    assert_eq!(reassembler.push(&segment(1, 0x82, 1)), None);
+
+    assert_eq!(reassembler.push(&segment(1, 0x40, 1)), None);
+    assert_eq!(reassembler.push(&segment(2, 0x40, 1)), None);
+    assert_eq!(reassembler.push(&segment(3, 0x40, 1)), None);
+    assert_eq!(reassembler.len(), 2);
+    assert_eq!(reassembler.push(&segment(3, 0x81, 1)), None);
+    // a single segment fragment is never dropped
+    assert_eq!(reassembler.push(&segment(3, 0xC0, 2)), Some(vec![0xAA; 2]));
+    assert_eq!(reassembler.push(&segment(2, 0x81, 1)), Some(vec![0xAA; 2]));
+    assert_eq!(reassembler.len(), 1);
+  }
+}
@@ -0,0 +1,414 @@
+//! Handles parsing of EAPOL frames and the EAP packets they carry
+//! <https://www.rfc-editor.org/rfc/rfc3748>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+/// Meta trait for eapol combinator
+pub trait EapolParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<EapolAtom>;
+
+/// Atom produced by eapol parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EapolAtom {
+  /// When an EAP length is too short for the fields it must hold
+  Length(u16),
+}
+
+impl Display for EapolAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      EapolAtom::Length(length) => write!(f, "Length: EAP length {} is too short", length),
+    }
+  }
+}
+
+struct_variants! {
+  EapolPacketType, packet_type, u8:
+    /// EAP packet
+    EAP_PACKET => 0,
+    /// EAPOL-Start
+    START => 1,
+    /// EAPOL-Logoff
+    LOGOFF => 2,
+    /// EAPOL-Key
+    KEY => 3,
+    /// EAPOL-Encapsulated-ASF-Alert
+    ASF_ALERT => 4,
+    /// EAPOL-MKA
+    MKA => 5,
+    /// EAPOL-Announcement (Generic)
+    ANNOUNCEMENT_GENERIC => 6,
+    /// EAPOL-Announcement (Specific)
+    ANNOUNCEMENT_SPECIFIC => 7,
+    /// EAPOL-Announcement-Req
+    ANNOUNCEMENT_REQ => 8,
+}
+
+/// IEEE 802.1X EAPOL frame, the payload of an ethernet frame with
+/// [crate::EtherType::EAPOL]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Eapol<Span> {
+  /// Protocol version
+  pub version: u8,
+  /// Type of body
+  pub packet_type: EapolPacketType,
+  /// Length of body
+  pub length: u16,
+  /// Body, an EAP packet when packet_type is [EapolPacketType::EAP_PACKET]
+  pub body: Span,
+}
+
+/// Parse an EAPOL frame, the body can be given to [eap_packet]
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn eapol<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Eapol<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): EapolParse<Stream, Context>,
+{
+  let Success {
+    token: (version, packet_type, length),
+    stream,
+  } = (octet, octet.map(EapolPacketType::new), u16_be).parse(stream)?;
+
+  take(usize::from(length))
+    .map(|body| Eapol {
+      version,
+      packet_type,
+      length,
+      body,
+    })
+    .parse(stream)
+}
+
+struct_variants! {
+  EapCode, code, u8:
+    /// Request
+    REQUEST => 1,
+    /// Response
+    RESPONSE => 2,
+    /// Success
+    SUCCESS => 3,
+    /// Failure
+    FAILURE => 4,
+}
+
+struct_variants! {
+  EapType, eap_type, u8:
+    /// Identity
+    IDENTITY => 1,
+    /// Notification
+    NOTIFICATION => 2,
+    /// Legacy Nak, response only
+    NAK => 3,
+    /// MD5-Challenge
+    MD5_CHALLENGE => 4,
+    /// One-Time Password
+    OTP => 5,
+    /// Generic Token Card
+    GTC => 6,
+    /// EAP-TLS
+    TLS => 13,
+    /// EAP-TTLS
+    TTLS => 21,
+    /// PEAP
+    PEAP => 25,
+    /// EAP-MSCHAPv2
+    MSCHAP_V2 => 26,
+    /// EAP-FAST
+    FAST => 43,
+    /// Expanded Types
+    EXPANDED => 254,
+}
+
+/// Fragment of a TLS based method, EAP-TLS, EAP-TTLS or PEAP
+/// <https://www.rfc-editor.org/rfc/rfc5216#section-3.1>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EapTls<Span> {
+  /// Flags, the 3 low bits are the version for TTLS and PEAP
+  pub flags: u8,
+  /// Total length of the TLS message, present when the L flag is set
+  pub message_length: Option<u32>,
+  /// TLS data of this fragment
+  pub data: Span,
+}
+
+impl<Span> EapTls<Span> {
+  /// Return true if the message length is present
+  pub const fn get_length_included(&self) -> bool {
+    self.flags & 0x80 != 0
+  }
+
+  /// Return true if more fragments follow
+  pub const fn get_more_fragments(&self) -> bool {
+    self.flags & 0x40 != 0
+  }
+
+  /// Return true if this is the start of the exchange
+  pub const fn get_start(&self) -> bool {
+    self.flags & 0x20 != 0
+  }
+}
+
+/// Type specific data of an EAP request or response
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EapData<Span> {
+  /// Identity, usually an UTF-8 user name
+  Identity(Span),
+  /// MD5-Challenge
+  Md5Challenge {
+    /// Challenge or response value
+    value: Span,
+    /// Name of the sender
+    name: Span,
+  },
+  /// EAP-TLS fragment
+  Tls(EapTls<Span>),
+  /// PEAP fragment
+  Peap(EapTls<Span>),
+  /// Any other type, data is left undecoded
+  Other(EapType, Span),
+}
+
+/// EAP packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EapPacket<Span> {
+  /// Code of the packet
+  pub code: EapCode,
+  /// Match responses with requests
+  pub identifier: u8,
+  /// Length of the packet including this header
+  pub length: u16,
+  /// Type and data, present for requests and responses
+  pub data: Option<EapData<Span>>,
+}
+
+fn eap_tls<Stream, Context>(
+  stream: Stream, length: u16, len: usize,
+) -> Parsed<EapTls<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): EapolParse<Stream, Context>,
+{
+  let Success {
+    token: flags,
+    stream,
+  } = octet.parse(stream)?;
+  let Success {
+    token: message_length,
+    stream,
+  } = if flags & 0x80 != 0 {
+    u32_be.map(Some).parse(stream)
+  } else {
+    Parsed::Success {
+      token: None,
+      stream,
+    }
+  }?;
+
+  let used = if message_length.is_some() { 5 } else { 1 };
+  match len.checked_sub(used) {
+    Some(len) => take(len)
+      .map(|data| EapTls {
+        flags,
+        message_length,
+        data,
+      })
+      .parse(stream),
+    None => Parsed::Failure(Context::new(EapolAtom::Length(length))),
+  }
+}
+
+/// Parse an EAP packet
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn eap_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<EapPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): EapolParse<Stream, Context>,
+{
+  let Success {
+    token: (code, identifier, length),
+    stream,
+  } = (octet.map(EapCode::new), octet, u16_be).parse(stream)?;
+
+  if code != EapCode::REQUEST && code != EapCode::RESPONSE {
+    return Parsed::Success {
+      token: EapPacket {
+        code,
+        identifier,
+        length,
+        data: None,
+      },
+      stream,
+    };
+  }
+
+  let Some(len) = usize::from(length).checked_sub(5) else {
+    return Parsed::Failure(Context::new(EapolAtom::Length(length)));
+  };
+  let Success {
+    token: eap_type,
+    stream,
+  } = octet.map(EapType::new).parse(stream)?;
+
+  let Success {
+    token: data,
+    stream,
+  } = match eap_type {
+    EapType::IDENTITY => take(len).map(EapData::Identity).parse(stream),
+    EapType::MD5_CHALLENGE => {
+      let Success {
+        token: (size, value),
+        stream,
+      } = octet
+        .and_then(|size| take(usize::from(size)).map(move |value| (size, value)))
+        .parse(stream)?;
+      match len.checked_sub(1 + usize::from(size)) {
+        Some(len) => take(len)
+          .map(move |name| EapData::Md5Challenge {
+            value: value.clone(),
+            name,
+          })
+          .parse(stream),
+        None => Parsed::Failure(Context::new(EapolAtom::Length(length))),
+      }
+    }
+    EapType::TLS => eap_tls(stream, length, len).map_token(EapData::Tls),
+    EapType::PEAP => eap_tls(stream, length, len).map_token(EapData::Peap),
+    eap_type => take(len)
+      .map(|data| EapData::Other(eap_type, data))
+      .parse(stream),
+  }?;
+
+  Parsed::Success {
+    token: EapPacket {
+      code,
+      identifier,
+      length,
+      data: Some(data),
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parse,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    EapCode,
+    EapData,
+    EapPacket,
+    EapTls,
+    EapolPacketType,
+  };
+
+  #[test]
+  fn eap_identity_and_tls() {
+    let frame = [
+      0x01, 0x00, 0x00, 0x09, 0x02, 0x01, 0x00, 0x09, 0x01, 0x62, 0x6F, 0x62, 0x21,
+    ];
+    let Success { token, stream } = super::eapol::<_, Ignore>.parse(&frame[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.packet_type, EapolPacketType::EAP_PACKET);
+    assert_eq!(
+      super::eap_packet::<_, Ignore>(token.body),
+      Parsed::Success {
+        token: EapPacket {
+          code: EapCode::RESPONSE,
+          identifier: 1,
+          length: 9,
+          data: Some(EapData::Identity(&b"bob!"[..])),
+        },
+        stream: &[][..],
+      }
+    );
+
+    let tls = [
+      0x01, 0x02, 0x00, 0x0C, 0x0D, 0xC0, 0x00, 0x00, 0x01, 0x00, 0x16, 0x03,
+    ];
+    assert_eq!(
+      super::eap_packet::<_, Ignore>(&tls[..]),
+      Parsed::Success {
+        token: EapPacket {
+          code: EapCode::REQUEST,
+          identifier: 2,
+          length: 12,
+          data: Some(EapData::Tls(EapTls {
+            flags: 0xC0,
+            message_length: Some(256),
+            data: &[0x16, 0x03][..],
+          })),
+        },
+        stream: &[][..],
+      }
+    );
+
+    let md5 = [0x01, 0x03, 0x00, 0x0A, 0x04, 0x02, 0xAA, 0xBB, 0x6E, 0x73];
+    assert_eq!(
+      super::eap_packet::<_, Ignore>(&md5[..]),
+      Parsed::Success {
+        token: EapPacket {
+          code: EapCode::REQUEST,
+          identifier: 3,
+          length: 10,
+          data: Some(EapData::Md5Challenge {
+            value: &[0xAA, 0xBB][..],
+            name: &b"ns"[..],
+          }),
+        },
+        stream: &[][..],
+      }
+    );
+
+    assert!(matches!(
+      super::eap_packet::<_, Ignore>(&[0x01, 0x04, 0x00, 0x04, 0x01][..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
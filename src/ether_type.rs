@@ -11,7 +11,10 @@ use binator::{
   Streaming,
 };
 
-use crate::struct_variants;
+use crate::{
+  struct_variants,
+  EthernetAtom,
+};
 
 struct_variants! {
   EtherType, ether_type, u16:
@@ -25,6 +28,8 @@ struct_variants! {
     ARP => 0x0806,
     /// Wake-on-LAN
     WOL => 0x0842,
+    /// Audio Video Transport Protocol (IEEE 1722)
+    AVTP => 0x22F0,
     /// IETF TRILL Protocol
     TRILL => 0x22F3,
     /// DECnet Phase IV
@@ -45,6 +50,8 @@ struct_variants! {
     IPV6 => 0x86DD,
     /// Ethernet flow control
     FLOW_CONTROL => 0x8808,
+    /// Slow Protocols, LACP and OAM
+    SLOW_PROTOCOLS => 0x8809,
     /// CobraNet
     COBRA_NET => 0x8819,
     /// MPLS unicast
@@ -111,18 +118,71 @@ struct_variants! {
     LLT => 0xCAFE,
 }
 
-pub(crate) fn ether_type<Stream, Context>(stream: Stream) -> Parsed<EtherType, Stream, Context>
+/// Value of the EtherType field of an ethernet frame, 802.3 frames use it as
+/// the length of the payload
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum EtherTypeOrLength {
+  /// 802.3 length of the payload, LLC header included
+  Length(u16),
+  /// Ethernet II EtherType
+  EtherType(EtherType),
+}
+
+impl EtherTypeOrLength {
+  /// Return EtherTypeOrLength from the raw field, None if value is between
+  /// [EtherType::LANMAX] and 0x0600
+  pub const fn new(value: u16) -> Option<Self> {
+    if value <= EtherType::LANMAX.ether_type() {
+      Some(Self::Length(value))
+    } else if value >= 0x0600 {
+      Some(Self::EtherType(EtherType::new(value)))
+    } else {
+      None
+    }
+  }
+
+  /// Return the EtherType, None for 802.3 frames
+  pub const fn ether_type(&self) -> Option<EtherType> {
+    match *self {
+      Self::EtherType(ether_type) => Some(ether_type),
+      Self::Length(_) => None,
+    }
+  }
+
+  /// Return the 802.3 length, None for Ethernet II frames
+  pub const fn length(&self) -> Option<u16> {
+    match *self {
+      Self::Length(length) => Some(length),
+      Self::EtherType(_) => None,
+    }
+  }
+}
+
+impl From<EtherType> for EtherTypeOrLength {
+  fn from(ether_type: EtherType) -> Self {
+    Self::EtherType(ether_type)
+  }
+}
+
+pub(crate) fn ether_type_or_length<Stream, Context>(
+  stream: Stream,
+) -> Parsed<EtherTypeOrLength, Stream, Context>
 where
   Stream: Clone + Eq,
   Stream: Streaming,
   Context: Contexting<CoreAtom<Stream>>,
   Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<EthernetAtom>,
   Stream::Item: Into<u8>,
 {
   octet
     .fill()
     .map(u16::from_be_bytes)
-    .map(EtherType::new)
+    .try_map(|value| {
+      EtherTypeOrLength::new(value)
+        .ok_or_else(|| Context::new(EthernetAtom::EtherTypeOrLength(value)))
+    })
     .parse(stream)
 }
 
@@ -133,20 +193,30 @@ mod tests {
     Parsed,
   };
 
-  use super::EtherType;
+  use super::{
+    EtherType,
+    EtherTypeOrLength,
+  };
 
   #[test]
   fn ether_type() {
     let tests = [
-      ([0x08, 0x00], EtherType::IPV4),
-      ([0x08, 0x06], EtherType::ARP),
-      ([0x86, 0xDD], EtherType::IPV6),
-      ([0x81, 0x00], EtherType::VLAN),
+      ([0x08, 0x00], EtherType::IPV4.into()),
+      ([0x08, 0x06], EtherType::ARP.into()),
+      ([0x86, 0xDD], EtherType::IPV6.into()),
+      ([0x81, 0x00], EtherType::VLAN.into()),
+      ([0x05, 0xDC], EtherTypeOrLength::Length(1500)),
+      ([0x00, 0x26], EtherTypeOrLength::Length(38)),
     ];
 
+    assert!(matches!(
+      super::ether_type_or_length::<_, Ignore>(&[0x05, 0xFF][..]),
+      Parsed::Failure(_)
+    ));
+
     for (stream, expected) in tests {
       assert_eq!(
-        super::ether_type::<_, Ignore>(&stream[..]),
+        super::ether_type_or_length::<_, Ignore>(&stream[..]),
         Parsed::Success {
           token: expected,
           stream: &[][..],
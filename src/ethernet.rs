@@ -1,8 +1,26 @@
 //! Handles parsing of Ethernet headers
 
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
 use binator::{
-  base::octet,
+  base::{
+    any,
+    end_of_stream,
+    octet,
+    take,
+    EndOfStreamAtom,
+  },
   utils::{
+    Acc,
     Utils,
     UtilsAtom,
   },
@@ -14,28 +32,419 @@ use binator::{
   Success,
 };
 
-use crate::ether_type::{
-  ether_type,
-  EtherType,
+use crate::{
+  checksum::{
+    crc32,
+    crc32_update,
+    CRC32_INIT,
+  },
+  ether_type::{
+    ether_type_or_length,
+    EtherType,
+    EtherTypeOrLength,
+  },
+  llc::{
+    llc_header,
+    LlcHeader,
+  },
 };
 
+/// MAC address, EUI-48
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MacAddr(pub [u8; 6]);
+
+impl MacAddr {
+  /// ff:ff:ff:ff:ff:ff
+  pub const BROADCAST: Self = Self([0xFF; 6]);
+  /// Individual/Group bit of the first octet
+  pub const GROUP_BIT: u8 = 0x01;
+  /// Universal/Local bit of the first octet
+  pub const LOCAL_BIT: u8 = 0x02;
+
+  /// Return MacAddr from its six octets
+  pub const fn new(a: u8, b: u8, c: u8, d: u8, e: u8, f: u8) -> Self {
+    Self([a, b, c, d, e, f])
+  }
+
+  /// Return the six octets
+  pub const fn octets(&self) -> [u8; 6] {
+    self.0
+  }
+
+  /// Return the Organizationally Unique Identifier, the first three octets,
+  /// only meaningful for universally administered addresses
+  pub const fn oui(&self) -> [u8; 3] {
+    let [a, b, c, ..] = self.0;
+    [a, b, c]
+  }
+
+  /// Return true for ff:ff:ff:ff:ff:ff
+  pub const fn is_broadcast(&self) -> bool {
+    u64::from_be_bytes([
+      0, 0, self.0[0], self.0[1], self.0[2], self.0[3], self.0[4], self.0[5],
+    ]) == 0xFFFF_FFFF_FFFF
+  }
+
+  /// Return true if the group bit is set, broadcast included
+  pub const fn is_multicast(&self) -> bool {
+    self.0[0] & Self::GROUP_BIT != 0
+  }
+
+  /// Return true if the locally administered bit is set, the OUI is then
+  /// meaningless
+  pub const fn is_local(&self) -> bool {
+    self.0[0] & Self::LOCAL_BIT != 0
+  }
+
+  /// Return true for 01:00:5e:00:00:00 to 01:00:5e:7f:ff:ff, the addresses
+  /// IPv4 multicast groups are mapped to
+  /// <https://www.rfc-editor.org/rfc/rfc1112#section-6.4>
+  pub const fn is_ipv4_multicast(&self) -> bool {
+    matches!(self.0, [0x01, 0x00, 0x5E, d, ..] if d & 0x80 == 0)
+  }
+
+  /// Return true for 33:33:xx:xx:xx:xx, the addresses IPv6 multicast groups
+  /// are mapped to
+  /// <https://www.rfc-editor.org/rfc/rfc2464#section-7>
+  pub const fn is_ipv6_multicast(&self) -> bool {
+    matches!(self.0, [0x33, 0x33, ..])
+  }
+
+  /// Return the MAC address of an IPv4 multicast group, the low 23 bits of
+  /// the group are kept. None if addr is not multicast
+  pub const fn from_ipv4_multicast(addr: Ipv4Addr) -> Option<Self> {
+    if addr.is_multicast() {
+      let [_, b, c, d] = addr.octets();
+      Some(Self([0x01, 0x00, 0x5E, b & 0x7F, c, d]))
+    } else {
+      None
+    }
+  }
+
+  /// Return the MAC address of an IPv6 multicast group, the low 32 bits of
+  /// the group are kept. None if addr is not multicast
+  pub const fn from_ipv6_multicast(addr: Ipv6Addr) -> Option<Self> {
+    if addr.is_multicast() {
+      let [a, b, c, d] = (addr.to_bits() as u32).to_be_bytes();
+      Some(Self([0x33, 0x33, a, b, c, d]))
+    } else {
+      None
+    }
+  }
+
+  /// Return the modified EUI-64 interface identifier, `ff:fe` is inserted in
+  /// the middle and the universal/local bit is inverted
+  /// <https://www.rfc-editor.org/rfc/rfc4291#appendix-A>
+  pub const fn to_eui64(&self) -> [u8; 8] {
+    let [a, b, c, d, e, f] = self.0;
+    [a ^ Self::LOCAL_BIT, b, c, 0xFF, 0xFE, d, e, f]
+  }
+
+  /// Return the MAC address a modified EUI-64 interface identifier was
+  /// derived from, None if `ff:fe` is not in the middle
+  pub const fn from_eui64(eui64: [u8; 8]) -> Option<Self> {
+    match eui64 {
+      [a, b, c, 0xFF, 0xFE, d, e, f] => Some(Self([a ^ Self::LOCAL_BIT, b, c, d, e, f])),
+      _ => None,
+    }
+  }
+
+  /// Return the fe80::/64 link-local address with the modified EUI-64
+  /// interface identifier, as given by SLAAC
+  pub const fn to_link_local(&self) -> Ipv6Addr {
+    Ipv6Addr::from_bits(0xFE80 << 112 | u64::from_be_bytes(self.to_eui64()) as u128)
+  }
+
+  /// Return the MAC address the interface identifier of addr was derived
+  /// from, None if it's not a modified EUI-64, like privacy addresses
+  pub const fn from_ipv6(addr: Ipv6Addr) -> Option<Self> {
+    Self::from_eui64((addr.to_bits() as u64).to_be_bytes())
+  }
+
+  /// Return the vendor of the OUI from the built-in table, None for locally
+  /// administered addresses
+  #[cfg(feature = "oui")]
+  pub fn vendor(&self) -> Option<&'static str> {
+    if self.is_local() {
+      None
+    } else {
+      crate::oui_vendor(self.oui())
+    }
+  }
+}
+
+impl From<[u8; 6]> for MacAddr {
+  fn from(octets: [u8; 6]) -> Self {
+    Self(octets)
+  }
+}
+
+impl From<MacAddr> for [u8; 6] {
+  fn from(mac: MacAddr) -> Self {
+    mac.0
+  }
+}
+
+impl Display for MacAddr {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    let [a, b, c, d, e, g] = self.0;
+    write!(
+      f,
+      "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+      a, b, c, d, e, g
+    )
+  }
+}
+
+/// Link layer key of a frame, the same for both directions of a
+/// conversation, the lowest address comes first
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct L2Key {
+  /// VLAN Identifier of the outermost tag
+  pub vid: Option<u16>,
+  /// Lowest MAC address
+  pub low: MacAddr,
+  /// Highest MAC address
+  pub high: MacAddr,
+}
+
+/// IEEE 802.1Q or 802.1ad VLAN tag
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VlanTag {
+  /// Tag Protocol Identifier, the EtherType announcing the tag
+  pub tpid: EtherType,
+  /// Priority Code Point, 3 bits
+  pub pcp: u8,
+  /// Drop Eligible Indicator
+  pub dei: bool,
+  /// VLAN Identifier, 12 bits
+  pub vid: u16,
+}
+
+impl VlanTag {
+  /// Length of a tag, TPID included
+  pub const LEN: usize = 4;
+
+  /// Return VlanTag from its TPID and raw Tag Control Information
+  pub const fn new(tpid: EtherType, tci: u16) -> Self {
+    Self {
+      tpid,
+      pcp: (tci >> 13) as u8,
+      dei: tci & 1 << 12 != 0,
+      vid: tci & 0x0FFF,
+    }
+  }
+
+  /// Return the raw Tag Control Information, pcp and vid are truncated to
+  /// their width
+  pub const fn tci(&self) -> u16 {
+    (self.pcp as u16 & 0b111) << 13 | (self.dei as u16) << 12 | self.vid & 0x0FFF
+  }
+
+  /// Return true if ether_type announce a VLAN tag
+  pub const fn is_tpid(ether_type: EtherType) -> bool {
+    matches!(
+      ether_type,
+      EtherType::VLAN | EtherType::QINQ | EtherType::VLAN_DOUBLE
+    )
+  }
+}
+
 /// EthernetFrame
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EthernetFrame {
   /// MAC destination
   pub destination: [u8; 6],
   /// MAC source
   pub source: [u8; 6],
-  /// EtherType used
-  pub ether_type: EtherType,
-  /// TCI
-  pub tci: Option<u16>,
+  /// EtherType used or 802.3 length
+  pub ether_type: EtherTypeOrLength,
+  /// VLAN tags, outermost first
+  pub vlans: Vec<VlanTag>,
+  /// LLC header, present when ether_type is a length
+  pub llc: Option<LlcHeader>,
 }
 
-/// Parser that return a ethernet frame on success
+// A header is never empty
+#[allow(clippy::len_without_is_empty)]
+impl EthernetFrame {
+  /// Length of the trailing frame check sequence
+  pub const FCS_LEN: usize = 4;
+  /// Length of an ethernet header without VLAN tag
+  pub const MIN_LEN: usize = 14;
+
+  /// Return the length of the header including the VLAN tags and the LLC
+  /// header, in bytes
+  pub const fn len(&self) -> usize {
+    let llc = match self.llc {
+      Some(llc) => llc.len(),
+      None => 0,
+    };
+    Self::MIN_LEN + self.vlans.len() * VlanTag::LEN + llc
+  }
+
+  /// Return an untagged Ethernet II header from its wire representation,
+  /// without going through the combinator machinery. None if the EtherType
+  /// field is a VLAN tag or an 802.3 length as the header would be longer.
+  pub const fn from_bytes(bytes: &[u8; Self::MIN_LEN]) -> Option<Self> {
+    let [d0, d1, d2, d3, d4, d5, s0, s1, s2, s3, s4, s5, e0, e1] = *bytes;
+    let ether_type = u16::from_be_bytes([e0, e1]);
+    if VlanTag::is_tpid(EtherType::new(ether_type)) {
+      return None;
+    }
+    match EtherTypeOrLength::new(ether_type) {
+      Some(ether_type @ EtherTypeOrLength::EtherType(_)) => Some(Self {
+        destination: [d0, d1, d2, d3, d4, d5],
+        source: [s0, s1, s2, s3, s4, s5],
+        ether_type,
+        vlans: Vec::new(),
+        llc: None,
+      }),
+      _ => None,
+    }
+  }
+
+  /// Return the wire representation of an untagged Ethernet II header, None
+  /// if the header have a VLAN tag or an LLC header
+  pub const fn to_bytes(&self) -> Option<[u8; Self::MIN_LEN]> {
+    let (EtherTypeOrLength::EtherType(ether_type), true, None) =
+      (self.ether_type, self.vlans.is_empty(), self.llc)
+    else {
+      return None;
+    };
+    let [d0, d1, d2, d3, d4, d5] = self.destination;
+    let [s0, s1, s2, s3, s4, s5] = self.source;
+    let [e0, e1] = ether_type.ether_type().to_be_bytes();
+    Some([d0, d1, d2, d3, d4, d5, s0, s1, s2, s3, s4, s5, e0, e1])
+  }
+
+  /// Return the EtherType of the payload, from the EtherType field for
+  /// Ethernet II frames or from the SNAP header for 802.3 frames
+  pub const fn payload_ether_type(&self) -> Option<EtherType> {
+    match (self.ether_type, self.llc) {
+      (EtherTypeOrLength::EtherType(ether_type), _) => Some(ether_type),
+      (EtherTypeOrLength::Length(_), Some(llc)) => llc.ether_type(),
+      (EtherTypeOrLength::Length(_), None) => None,
+    }
+  }
+
+  /// Return the length of the payload following the LLC header for 802.3
+  /// frames, the bytes after it are padding. None for Ethernet II frames.
+  pub const fn payload_len(&self) -> Option<usize> {
+    match (self.ether_type, self.llc) {
+      (EtherTypeOrLength::Length(length), Some(llc)) => {
+        Some((length as usize).saturating_sub(llc.len()))
+      }
+      _ => None,
+    }
+  }
+
+  /// Return the destination address
+  pub const fn destination_mac(&self) -> MacAddr {
+    MacAddr(self.destination)
+  }
+
+  /// Return the source address
+  pub const fn source_mac(&self) -> MacAddr {
+    MacAddr(self.source)
+  }
+
+  /// Return true if the frame is sent to every station
+  pub const fn is_broadcast(&self) -> bool {
+    self.destination_mac().is_broadcast()
+  }
+
+  /// Return true if the frame is sent to a group, broadcast included
+  pub const fn is_multicast(&self) -> bool {
+    self.destination_mac().is_multicast()
+  }
+
+  /// Return true if the destination is the MAC address of an IPv4 multicast
+  /// group
+  pub const fn is_ipv4_multicast(&self) -> bool {
+    self.destination_mac().is_ipv4_multicast()
+  }
+
+  /// Return true if the destination is the MAC address of an IPv6 multicast
+  /// group
+  pub const fn is_ipv6_multicast(&self) -> bool {
+    self.destination_mac().is_ipv6_multicast()
+  }
+
+  /// Return the key of the conversation, see [L2Key]
+  pub fn l2_key(&self) -> L2Key {
+    let (source, destination) = (self.source_mac(), self.destination_mac());
+    L2Key {
+      vid: self.vlans.first().map(|vlan| vlan.vid),
+      low: source.min(destination),
+      high: source.max(destination),
+    }
+  }
+
+  /// Return payload without the padding added to reach the minimum frame
+  /// length, or any other trailer. The length is given by the 802.3 length
+  /// or by the IPv4 total length or IPv6 payload length, payload is returned
+  /// unchanged when it can't be known or is inconsistent, like for a
+  /// truncated header or a jumbogram.
+  pub fn trim_padding<'a>(&self, payload: &'a [u8]) -> &'a [u8] {
+    let len = match (self.payload_len(), self.payload_ether_type()) {
+      (Some(len), _) => Some(len),
+      (None, Some(EtherType::IPV4)) => match payload {
+        [_, _, a, b, ..] => {
+          Some(usize::from(u16::from_be_bytes([*a, *b]))).filter(|len| *len >= 20)
+        }
+        _ => None,
+      },
+      (None, Some(EtherType::IPV6)) => match payload {
+        [_, _, _, _, a, b, ..] => match u16::from_be_bytes([*a, *b]) {
+          0 => None,
+          len => Some(40 + usize::from(len)),
+        },
+        _ => None,
+      },
+      _ => None,
+    };
+    match len {
+      Some(len) if len <= payload.len() => &payload[..len],
+      _ => payload,
+    }
+  }
+}
+
+/// Parser that return a ethernet frame on success, when the EtherType field
+/// is an 802.3 length the LLC header and its SNAP header are parsed too and
+/// the returned stream is bounded by the length so padding and trailer are
+/// left out. A length bigger than the stream is ignored, like for a frame
+/// truncated by the capture.
 /// <https://en.wikipedia.org/wiki/Ethernet_frame>
 pub fn ethernet_frame<Stream, Context>(stream: Stream) -> Parsed<EthernetFrame, Stream, Context>
+where
+  Stream: Streaming<Span = Stream>,
+  Stream: Eq,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<EthernetAtom>,
+{
+  let Success { token, stream } = ethernet_header.parse(stream)?;
+
+  let stream = match token.payload_len() {
+    Some(len) => match take(len).parse(stream.clone()) {
+      Parsed::Success { token, .. } => token,
+      Parsed::Failure(_) => stream,
+      Parsed::Error(context) => return Parsed::Error(context),
+    },
+    None => stream,
+  };
+
+  Parsed::Success { token, stream }
+}
+
+fn ethernet_header<Stream, Context>(stream: Stream) -> Parsed<EthernetFrame, Stream, Context>
 where
   Stream: Clone,
   Stream: Eq,
@@ -43,7 +452,7 @@ where
   Stream::Item: Into<u8>,
   Context: Contexting<CoreAtom<Stream>>,
   Context: Contexting<UtilsAtom<Stream>>,
-  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<EthernetAtom>,
 {
   let Success {
     token: destination,
@@ -54,55 +463,228 @@ where
     stream,
   } = octet.fill().parse(stream)?;
   let Success {
-    token: (ether_type, tci),
-    stream,
-  } = ether_type
-    .and_then(|tmp_ether_type| {
-      move |stream: Stream| {
-        if tmp_ether_type == EtherType::VLAN {
-          let Success { token: tci, stream } =
-            octet.fill().map(u16::from_be_bytes).parse(stream)?;
-          let Success {
-            token: ether_type,
-            stream,
-          } = ether_type.parse(stream)?;
-
-          Parsed::Success {
-            token: (ether_type, Some(tci)),
-            stream,
-          }
-        } else {
-          Parsed::Success {
-            token: (tmp_ether_type, None),
-            stream,
-          }
-        }
+    token: mut ether_type,
+    mut stream,
+  } = ether_type_or_length.parse(stream)?;
+
+  // 802.1ad and older double tagging stack tags before the 802.1Q tag
+  let mut vlans = Vec::new();
+  while let EtherTypeOrLength::EtherType(tpid) = ether_type {
+    if !VlanTag::is_tpid(tpid) {
+      break;
+    }
+    let Success {
+      token: (tci, next),
+      stream: next_stream,
+    } = (octet.fill().map(u16::from_be_bytes), ether_type_or_length).parse(stream)?;
+    vlans.push(VlanTag::new(tpid, tci));
+    ether_type = next;
+    stream = next_stream;
+  }
+
+  let Success { token: llc, stream } = match ether_type {
+    EtherTypeOrLength::Length(_) => llc_header.map(Some).parse(stream),
+    EtherTypeOrLength::EtherType(_) => Parsed::Success {
+      token: None,
+      stream,
+    },
+  }?;
+
+  let token = EthernetFrame {
+    destination,
+    source,
+    ether_type,
+    vlans,
+    llc,
+  };
+
+  #[cfg(feature = "tracing")]
+  crate::trace_layer(&token);
+
+  Parsed::Success { token, stream }
+}
+
+/// Atom produced by ethernet parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EthernetAtom {
+  /// When the EtherType field is between 802.3 max length and the first
+  /// EtherType
+  EtherTypeOrLength(u16),
+  /// When the frame check sequence doesn't match the frame
+  Fcs {
+    /// CRC-32 computed over the frame
+    expected: u32,
+    /// Frame check sequence found at the end of the frame
+    found: u32,
+  },
+}
+
+impl Display for EthernetAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      EthernetAtom::EtherTypeOrLength(value) => write!(
+        f,
+        "EtherTypeOrLength: {:#06X} is neither a length nor an EtherType",
+        value
+      ),
+      EthernetAtom::Fcs { expected, found } => {
+        write!(f, "Fcs: expected {:#010X} found {:#010X}", expected, found)
+      }
+    }
+  }
+}
+
+/// Parse a whole ethernet frame ending with its frame check sequence, the FCS
+/// is verified then stripped. Return the header and the payload without the
+/// FCS, the padding of 802.3 frames is stripped too. This consume the whole
+/// stream.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ethernet_frame_with_fcs<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(EthernetFrame, <Stream as Streaming>::Span), Stream, Context>
+where
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<EndOfStreamAtom<Stream>>,
+  Context: Contexting<EthernetAtom>,
+{
+  let fcs = || octet.fill().and_drop(end_of_stream);
+
+  let Success { .. } = octet
+    .fold_until(fcs(), || CRC32_INIT, crc32_update)
+    .try_map(|(state, fcs)| {
+      let expected = !state;
+      let found = u32::from_le_bytes(fcs);
+      if expected == found {
+        Ok(())
+      } else {
+        Err(Context::new(EthernetAtom::Fcs { expected, found }))
       }
     })
-    .parse(stream)?;
+    .parse(stream.clone())?;
+
+  let Success {
+    token: header,
+    stream,
+  } = ethernet_header.parse(stream)?;
+
+  let until_fcs = || any.drop().fold_until(fcs().peek(), || (), Acc::acc);
+  let Success {
+    token: payload,
+    stream,
+  } = match header.payload_len() {
+    Some(len) => take(len).and_drop(until_fcs()).parse(stream)?,
+    None => until_fcs().span().map(Success::into_stream).parse(stream)?,
+  };
+
+  let Success { stream, .. } = fcs().parse(stream)?;
 
   Parsed::Success {
-    token: EthernetFrame {
-      destination,
-      source,
-      ether_type,
-      tci,
-    },
+    token: (header, payload),
     stream,
   }
 }
 
+/// Append the frame check sequence of frame to frame, frame must start with
+/// the destination address
+pub fn append_fcs(frame: &mut Vec<u8>) {
+  let fcs = crc32(frame);
+  frame.extend_from_slice(&fcs.to_le_bytes());
+}
+
 #[cfg(test)]
 mod tests {
+  use std::net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  };
+
   use binator::{
     context::Ignore,
     Parsed,
+    Success,
   };
 
   use super::{
+    append_fcs,
     EtherType,
+    EtherTypeOrLength,
     EthernetFrame,
+    LlcHeader,
+    MacAddr,
+    VlanTag,
   };
+  use crate::LlcControl;
+
+  #[test]
+  fn mac_addr() {
+    let mac = MacAddr::new(0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B);
+    assert_eq!(mac.to_string(), "00:1b:21:0f:91:9b");
+    assert_eq!(mac.oui(), [0x00, 0x1B, 0x21]);
+    assert!(!mac.is_multicast() && !mac.is_local() && !mac.is_broadcast());
+    assert!(MacAddr::BROADCAST.is_broadcast() && MacAddr::BROADCAST.is_multicast());
+
+    assert_eq!(
+      mac.to_eui64(),
+      [0x02, 0x1B, 0x21, 0xFF, 0xFE, 0x0F, 0x91, 0x9B]
+    );
+    let link_local = mac.to_link_local();
+    assert_eq!(
+      link_local,
+      "fe80::21b:21ff:fe0f:919b"
+        .parse::<std::net::Ipv6Addr>()
+        .unwrap()
+    );
+    assert_eq!(MacAddr::from_ipv6(link_local), Some(mac));
+    assert_eq!(
+      MacAddr::from_ipv6("2001:db8::5c3a:91e4:7b20:6d11".parse().unwrap()),
+      None
+    );
+    assert_eq!(<[u8; 6]>::from(mac), [0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B]);
+
+    let mdns = MacAddr::from_ipv4_multicast(Ipv4Addr::new(224, 0, 0, 251)).unwrap();
+    assert_eq!(mdns, MacAddr::new(0x01, 0x00, 0x5E, 0x00, 0x00, 0xFB));
+    assert!(mdns.is_ipv4_multicast() && mdns.is_multicast());
+    // 239.128.0.1 share the address of 224.0.0.1
+    assert_eq!(
+      MacAddr::from_ipv4_multicast(Ipv4Addr::new(239, 128, 0, 1)),
+      MacAddr::from_ipv4_multicast(Ipv4Addr::new(224, 0, 0, 1))
+    );
+    assert_eq!(
+      MacAddr::from_ipv4_multicast(Ipv4Addr::new(10, 0, 0, 1)),
+      None
+    );
+    assert!(!MacAddr::new(0x01, 0x00, 0x5E, 0x80, 0x00, 0x01).is_ipv4_multicast());
+
+    let all_nodes =
+      MacAddr::from_ipv6_multicast(Ipv6Addr::new(0xFF02, 0, 0, 0, 0, 1, 0xFF00, 0x0001)).unwrap();
+    assert_eq!(all_nodes, MacAddr::new(0x33, 0x33, 0xFF, 0x00, 0x00, 0x01));
+    assert!(all_nodes.is_ipv6_multicast() && !all_nodes.is_ipv4_multicast());
+    assert_eq!(MacAddr::from_ipv6_multicast(Ipv6Addr::LOCALHOST), None);
+
+    let frame = EthernetFrame {
+      destination: [0x33, 0x33, 0x00, 0x00, 0x00, 0x01],
+      source: mac.octets(),
+      ether_type: EtherType::IPV6.into(),
+      vlans: vec![VlanTag::new(EtherType::VLAN, 42)],
+      llc: None,
+    };
+    assert!(frame.is_multicast() && frame.is_ipv6_multicast() && !frame.is_broadcast());
+    let reply = EthernetFrame {
+      destination: frame.source,
+      source: frame.destination,
+      ..frame.clone()
+    };
+    assert_eq!(frame.l2_key(), reply.l2_key());
+    assert_eq!(frame.l2_key().vid, Some(42));
+    assert_eq!(frame.l2_key().low, mac);
+  }
 
   #[test]
   fn ethernet_frame() {
@@ -114,8 +696,9 @@ mod tests {
         EthernetFrame {
           destination: [0x00, 0x23, 0x54, 0x07, 0x93, 0x6C],
           source: [0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B],
-          ether_type: EtherType::IPV4,
-          tci: None,
+          ether_type: EtherType::IPV4.into(),
+          vlans: Vec::new(),
+          llc: None,
         },
       ),
       (
@@ -126,13 +709,46 @@ mod tests {
         EthernetFrame {
           destination: [0x00, 0x23, 0x54, 0x07, 0x93, 0x6C],
           source: [0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B],
-          ether_type: EtherType::IPV4,
-          tci: Some(1234),
+          ether_type: EtherType::IPV4.into(),
+          vlans: vec![VlanTag::new(EtherType::VLAN, 1234)],
+          llc: None,
+        },
+      ),
+      (
+        &[
+          0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x88, 0xA8, 0xB0,
+          0x64, 0x81, 0x00, 0x04, 0xD2, 0x86, 0xDD,
+        ][..],
+        EthernetFrame {
+          destination: [0x00, 0x23, 0x54, 0x07, 0x93, 0x6C],
+          source: [0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B],
+          ether_type: EtherType::IPV6.into(),
+          vlans: vec![
+            VlanTag {
+              tpid: EtherType::QINQ,
+              pcp: 5,
+              dei: true,
+              vid: 100,
+            },
+            VlanTag::new(EtherType::VLAN, 1234),
+          ],
+          llc: None,
         },
       ),
     ];
 
+    assert_eq!(tests[2].1.vlans[0].tci(), 0xB064);
+
+    let untagged: [u8; 14] = tests[0].0.try_into().unwrap();
+    assert_eq!(
+      EthernetFrame::from_bytes(&untagged),
+      Some(tests[0].1.clone())
+    );
+    assert_eq!(tests[0].1.to_bytes(), Some(untagged));
+    assert_eq!(tests[1].1.to_bytes(), None);
+
     for (stream, expected) in tests {
+      assert_eq!(expected.len(), stream.len());
       assert_eq!(
         super::ethernet_frame::<_, Ignore>(stream),
         Parsed::Success {
@@ -142,4 +758,83 @@ mod tests {
       );
     }
   }
+
+  #[test]
+  fn ethernet_frame_802_3() {
+    // 802.3 frame, 5 bytes of LLC and payload followed by padding
+    let ieee_802_3 = [
+      0x01, 0x80, 0xC2, 0x00, 0x00, 0x00, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x00, 0x05, 0x42,
+      0x42, 0x03, 0xAB, 0xCD, 0x00, 0x00, 0x00,
+    ];
+    let Success { token, stream } = super::ethernet_frame::<_, Ignore>(&ieee_802_3[..]).unwrap();
+    assert_eq!(token.ether_type, EtherTypeOrLength::Length(5));
+    assert_eq!(stream, [0xAB, 0xCD]);
+
+    // truncated by the capture
+    let Success { stream, .. } = super::ethernet_frame::<_, Ignore>(&ieee_802_3[..18]).unwrap();
+    assert_eq!(stream, [0xAB]);
+  }
+
+  #[test]
+  fn ethernet_frame_with_fcs() {
+    let mut frame = vec![
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0xCA,
+      0xFE,
+    ];
+    append_fcs(&mut frame);
+
+    let (header, payload) = super::ethernet_frame_with_fcs::<_, Ignore>(frame.as_slice())
+      .unwrap()
+      .token;
+    assert_eq!(header.ether_type, EtherType::IPV4.into());
+    assert_eq!(payload, [0xCA, 0xFE]);
+
+    // 802.3 frame, 5 bytes of LLC and payload followed by padding
+    let mut ieee_802_3 = vec![
+      0x01, 0x80, 0xC2, 0x00, 0x00, 0x00, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x00, 0x05, 0x42,
+      0x42, 0x03, 0xAB, 0xCD, 0x00, 0x00, 0x00,
+    ];
+    append_fcs(&mut ieee_802_3);
+
+    let (header, payload) = super::ethernet_frame_with_fcs::<_, Ignore>(ieee_802_3.as_slice())
+      .unwrap()
+      .token;
+    assert_eq!(header.ether_type, EtherTypeOrLength::Length(5));
+    assert_eq!(
+      header.llc,
+      Some(LlcHeader {
+        dsap: 0x42,
+        ssap: 0x42,
+        control: LlcControl::Unnumbered(0x03),
+        snap: None,
+      })
+    );
+    assert_eq!(header.len(), 17);
+    assert_eq!(header.payload_ether_type(), None);
+    assert_eq!(payload, [0xAB, 0xCD]);
+
+    // minimum size frame, a 28 bytes IPv4 packet followed by 18 bytes of
+    // padding
+    let mut short = vec![
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x1C,
+    ];
+    short.resize(60, 0);
+    append_fcs(&mut short);
+    let (header, payload) = super::ethernet_frame_with_fcs::<_, Ignore>(short.as_slice())
+      .unwrap()
+      .token;
+    assert_eq!(payload.len(), 46);
+    assert_eq!(header.trim_padding(payload), &short[14..42]);
+    // inconsistent total length
+    short[16] = 0xFF;
+    assert_eq!(header.trim_padding(&short[14..60]).len(), 46);
+
+    let len = frame.len();
+    frame[len - 1] ^= 0xFF;
+    assert!(matches!(
+      super::ethernet_frame_with_fcs::<_, Ignore>(frame.as_slice()),
+      Parsed::Failure(_)
+    ));
+  }
 }
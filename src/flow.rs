@@ -0,0 +1,227 @@
+//! Identify the flow of a packet by its addresses, protocol and ports
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    IpAddr,
+    SocketAddr,
+  },
+};
+
+use crate::{
+  IPProtocol,
+  Port,
+  PseudoHeader,
+  TcpHeader,
+  UdpHeader,
+};
+#[cfg(feature = "dissect")]
+use crate::{
+  Network,
+  Packet,
+  Transport,
+};
+
+/// Directional flow key, ports are None for protocols without ports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FiveTuple {
+  /// IP source address
+  pub source_addr: IpAddr,
+  /// IP destination address
+  pub dest_addr: IpAddr,
+  /// IPv4 protocol or IPv6 next header
+  pub protocol: IPProtocol,
+  /// TCP or UDP source port
+  pub source_port: Option<Port>,
+  /// TCP or UDP destination port
+  pub dest_port: Option<Port>,
+}
+
+impl FiveTuple {
+  /// Return the flow of a packet without ports, ip is the IPv4 or IPv6
+  /// header
+  pub fn from_ip<Ip: PseudoHeader>(ip: &Ip, protocol: IPProtocol) -> Self {
+    let (source_addr, dest_addr) = ip.pseudo_addrs();
+    Self {
+      source_addr,
+      dest_addr,
+      protocol,
+      source_port: None,
+      dest_port: None,
+    }
+  }
+
+  /// Return the flow of a TCP segment, ip is the IPv4 or IPv6 header
+  /// carrying tcp
+  pub fn from_tcp<Ip: PseudoHeader, Options>(ip: &Ip, tcp: &TcpHeader<Options>) -> Self {
+    Self {
+      source_port: Some(tcp.source_port),
+      dest_port: Some(tcp.dest_port),
+      ..Self::from_ip(ip, IPProtocol::TCP)
+    }
+  }
+
+  /// Return the flow of a UDP datagram, ip is the IPv4 or IPv6 header
+  /// carrying udp
+  pub fn from_udp<Ip: PseudoHeader>(ip: &Ip, udp: &UdpHeader) -> Self {
+    Self {
+      source_port: Some(udp.source_port),
+      dest_port: Some(udp.dest_port),
+      ..Self::from_ip(ip, IPProtocol::UDP)
+    }
+  }
+
+  /// Return the flow of the other direction
+  pub fn reverse(&self) -> Self {
+    Self {
+      source_addr: self.dest_addr,
+      dest_addr: self.source_addr,
+      protocol: self.protocol,
+      source_port: self.dest_port,
+      dest_port: self.source_port,
+    }
+  }
+
+  /// Return true if the source endpoint is not greater than the destination
+  /// endpoint
+  pub fn is_canonical(&self) -> bool {
+    (self.source_addr, self.source_port) <= (self.dest_addr, self.dest_port)
+  }
+
+  /// Return the same key for both directions of a flow, the lowest endpoint
+  /// is the source
+  pub fn canonical(&self) -> Self {
+    if self.is_canonical() {
+      *self
+    } else {
+      self.reverse()
+    }
+  }
+
+  /// Return a hash of the flow that doesn't depend on its direction nor on
+  /// the process, unlike [std::hash::Hash] with a randomly keyed hasher. It's
+  /// FNV-1a over the canonical flow.
+  pub fn flow_hash(&self) -> u64 {
+    const OFFSET_BASIS: u64 = 0xCBF2_9CE4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01B3;
+
+    fn addr(addr: IpAddr) -> [u8; 16] {
+      match addr {
+        IpAddr::V4(addr) => addr.to_ipv6_mapped().octets(),
+        IpAddr::V6(addr) => addr.octets(),
+      }
+    }
+    fn port(port: Option<Port>) -> [u8; 2] {
+      port.map_or(0, |port| port.get()).to_be_bytes()
+    }
+
+    let flow = self.canonical();
+    [
+      &addr(flow.source_addr)[..],
+      &addr(flow.dest_addr),
+      &[flow.protocol.protocol()],
+      &port(flow.source_port),
+      &port(flow.dest_port),
+    ]
+    .concat()
+    .iter()
+    .fold(OFFSET_BASIS, |hash, octet| {
+      (hash ^ u64::from(*octet)).wrapping_mul(PRIME)
+    })
+  }
+}
+
+impl Display for FiveTuple {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match (self.source_port, self.dest_port) {
+      (Some(source_port), Some(dest_port)) => write!(
+        f,
+        "{} -> {} {}",
+        SocketAddr::new(self.source_addr, source_port.get()),
+        SocketAddr::new(self.dest_addr, dest_port.get()),
+        self.protocol
+      ),
+      _ => write!(
+        f,
+        "{} -> {} {}",
+        self.source_addr, self.dest_addr, self.protocol
+      ),
+    }
+  }
+}
+
+#[cfg(feature = "dissect")]
+impl<Span> Packet<Span> {
+  /// Return the flow of the packet, None if it has no IP layer
+  pub fn five_tuple(&self) -> Option<FiveTuple> {
+    let (ip, protocol) = match &self.network {
+      Some(Network::Ipv4(ipv4)) => (ipv4.pseudo_addrs(), ipv4.protocol),
+      Some(Network::Ipv6(ipv6)) => (ipv6.pseudo_addrs(), ipv6.next_header),
+      Some(Network::Arp(_)) | None => return None,
+    };
+    let ports = match &self.transport {
+      Some(Transport::Tcp(tcp)) => Some((tcp.source_port, tcp.dest_port)),
+      Some(Transport::Udp(udp)) => Some((udp.source_port, udp.dest_port)),
+      Some(Transport::Icmp(_) | Transport::Icmpv6(_)) | None => None,
+    };
+
+    Some(FiveTuple {
+      source_addr: ip.0,
+      dest_addr: ip.1,
+      protocol,
+      source_port: ports.map(|(source, _)| source),
+      dest_port: ports.map(|(_, dest)| dest),
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    IpAddr,
+    Ipv4Addr,
+  };
+
+  use super::FiveTuple;
+  use crate::{
+    IPProtocol,
+    Port,
+    PseudoHeader,
+    UdpHeader,
+  };
+
+  struct Addrs;
+
+  impl PseudoHeader for Addrs {
+    fn pseudo_addrs(&self) -> (IpAddr, IpAddr) {
+      (
+        Ipv4Addr::new(10, 0, 0, 2).into(),
+        Ipv4Addr::new(10, 0, 0, 1).into(),
+      )
+    }
+  }
+
+  #[test]
+  fn five_tuple() {
+    let udp = UdpHeader {
+      source_port: Port(53),
+      dest_port: Port(40000),
+      length: 8,
+      checksum: 0,
+    };
+    let flow = FiveTuple::from_udp(&Addrs, &udp);
+    assert_eq!(flow.to_string(), "10.0.0.2:53 -> 10.0.0.1:40000 Udp: 17");
+    assert!(!flow.is_canonical());
+    assert_eq!(flow.canonical(), flow.reverse());
+    assert_eq!(flow.canonical().source_port, Some(Port(40000)));
+    assert_eq!(flow.flow_hash(), flow.reverse().flow_hash());
+    assert_ne!(
+      flow.flow_hash(),
+      FiveTuple::from_ip(&Addrs, IPProtocol::UDP).flow_hash()
+    );
+  }
+}
@@ -0,0 +1,104 @@
+//! Split a reassembled byte stream into messages prefixed by their length,
+//! like DNS over TCP <https://www.rfc-editor.org/rfc/rfc1035#section-4.2.2>
+
+use binator::{
+  base::{
+    primitive::u16_be,
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+};
+
+/// Parse a message prefixed by its length as a 16 bits big endian integer,
+/// return the message without its prefix
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn length_prefixed<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  Stream: Streaming,
+  Stream: Eq,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+{
+  u16_be
+    .and_then(|length| take(usize::from(length)))
+    .parse(stream)
+}
+
+/// Implementation of [split_length_prefixed]
+#[derive(Clone, Debug)]
+pub struct LengthPrefixed<'a> {
+  buffer: &'a [u8],
+}
+
+impl<'a> LengthPrefixed<'a> {
+  /// Return the bytes not yielded yet, once the iterator is exhausted it's the
+  /// start of an incomplete message that should be kept until more data is
+  /// received
+  pub fn remaining(&self) -> &'a [u8] {
+    self.buffer
+  }
+}
+
+impl<'a> Iterator for LengthPrefixed<'a> {
+  type Item = &'a [u8];
+
+  fn next(&mut self) -> Option<Self::Item> {
+    let [a, b, rest @ ..] = self.buffer else {
+      return None;
+    };
+    let length = usize::from(u16::from_be_bytes([*a, *b]));
+    let message = rest.get(..length)?;
+    self.buffer = &rest[length..];
+    Some(message)
+  }
+}
+
+/// Return an iterator over the complete length prefixed messages of buffer,
+/// the incomplete trailing message is available with
+/// [LengthPrefixed::remaining]
+pub fn split_length_prefixed(buffer: &[u8]) -> LengthPrefixed<'_> {
+  LengthPrefixed { buffer }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::split_length_prefixed;
+
+  #[test]
+  fn length_prefixed() {
+    let buffer = [0x00, 0x02, 0xAA, 0xBB, 0x00, 0x00, 0x00, 0x03, 0xCC];
+
+    let mut messages = split_length_prefixed(&buffer);
+    assert_eq!(messages.next(), Some(&[0xAA, 0xBB][..]));
+    assert_eq!(messages.next(), Some(&[][..]));
+    assert_eq!(messages.next(), None);
+    assert_eq!(messages.remaining(), [0x00, 0x03, 0xCC]);
+
+    assert_eq!(
+      super::length_prefixed::<_, Ignore>(&buffer[..]),
+      Parsed::Success {
+        token: &[0xAA, 0xBB][..],
+        stream: &buffer[4..],
+      }
+    );
+  }
+}
@@ -0,0 +1,448 @@
+//! Handles parsing of GTPv1-U and GTPv2-C headers
+//! <https://www.etsi.org/deliver/etsi_ts/129200_129299/129281/>
+//! <https://www.etsi.org/deliver/etsi_ts/129200_129299/129274/>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    all,
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  ipv4_header,
+  ipv6_header,
+  struct_variants,
+  IPv4Header,
+  IPv6Header,
+  Ipv4Atom,
+  Ipv6Atom,
+  Port,
+};
+
+/// UDP port of GTP-U
+pub const GTPU_PORT: Port = Port(2152);
+/// UDP port of GTP-C
+pub const GTPC_PORT: Port = Port(2123);
+
+/// Meta trait for gtp combinator
+pub trait GtpParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<GtpAtom>;
+
+/// Atom produced by gtp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GtpAtom {
+  /// When the version is not the one of the parser
+  Version(u8),
+  /// When the protocol type bit is not set, GTP' is not handled
+  ProtocolType,
+  /// When an extension header has a length of 0
+  ExtensionLength,
+}
+
+impl Display for GtpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      GtpAtom::Version(version) => write!(f, "Version: unexpected version {}", version),
+      GtpAtom::ProtocolType => write!(f, "ProtocolType: GTP' is not supported"),
+      GtpAtom::ExtensionLength => write!(f, "ExtensionLength: extension header length is 0"),
+    }
+  }
+}
+
+struct_variants! {
+  GtpV1MessageType, message_type, u8:
+    /// Echo Request
+    ECHO_REQUEST => 1,
+    /// Echo Response
+    ECHO_RESPONSE => 2,
+    /// Error Indication
+    ERROR_INDICATION => 26,
+    /// Supported Extension Headers Notification
+    SUPPORTED_EXTENSION_HEADERS_NOTIFICATION => 31,
+    /// End Marker
+    END_MARKER => 254,
+    /// User data, the payload is a user IP packet
+    G_PDU => 255,
+}
+
+/// Extension header of a GTPv1 header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GtpExtension<Span> {
+  /// Type of the extension, as announced by the previous next extension
+  /// header type field
+  pub extension_type: u8,
+  /// Content, without the length and next extension header type fields
+  pub content: Span,
+}
+
+impl<Span> GtpExtension<Span> {
+  /// PDU Session Container
+  pub const PDU_SESSION_CONTAINER: u8 = 0x85;
+  /// UDP Port
+  pub const UDP_PORT: u8 = 0x40;
+}
+
+/// GTPv1 header, the payload of a UDP datagram on [GTPU_PORT]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GtpV1Header<Span> {
+  /// Version, protocol type and E, S, PN flags
+  pub flags: u8,
+  /// Message type
+  pub message_type: GtpV1MessageType,
+  /// Length of the payload, optional fields and extension headers included
+  pub length: u16,
+  /// Tunnel Endpoint Identifier
+  pub teid: u32,
+  /// Sequence number, when the S flag is set
+  pub sequence: Option<u16>,
+  /// N-PDU number, when the PN flag is set
+  pub npdu: Option<u8>,
+  /// Extension headers, when the E flag is set
+  pub extensions: Vec<GtpExtension<Span>>,
+}
+
+impl<Span> GtpV1Header<Span> {
+  /// Extension header flag
+  pub const E_FLAG: u8 = 0x04;
+  /// Length of the mandatory part of a GTPv1 header
+  pub const MIN_LEN: usize = 8;
+  /// N-PDU number flag
+  pub const PN_FLAG: u8 = 0x01;
+  /// Protocol type flag, GTP and not GTP'
+  pub const PT_FLAG: u8 = 0x10;
+  /// Sequence number flag
+  pub const S_FLAG: u8 = 0x02;
+}
+
+/// Parse a GTPv1 header, the stream is left at the payload
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn gtpv1_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<GtpV1Header<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): GtpParse<Stream, Context>,
+{
+  let Success {
+    token: (flags, message_type, length, teid),
+    stream,
+  } = (
+    octet.try_map(|flags| match flags >> 5u8 {
+      1 if flags & GtpV1Header::<()>::PT_FLAG != 0 => Ok(flags),
+      1 => Err(Context::new(GtpAtom::ProtocolType)),
+      version => Err(Context::new(GtpAtom::Version(version))),
+    }),
+    octet.map(GtpV1MessageType::new),
+    u16_be,
+    u32_be,
+  )
+    .parse(stream)?;
+
+  let optional = GtpV1Header::<()>::E_FLAG | GtpV1Header::<()>::S_FLAG | GtpV1Header::<()>::PN_FLAG;
+  if flags & optional == 0 {
+    return Parsed::Success {
+      token: GtpV1Header {
+        flags,
+        message_type,
+        length,
+        teid,
+        sequence: None,
+        npdu: None,
+        extensions: Vec::new(),
+      },
+      stream,
+    };
+  }
+
+  // the optional fields are all present as soon as one flag is set
+  let Success {
+    token: (sequence, npdu, mut next),
+    mut stream,
+  } = (u16_be, octet, octet).parse(stream)?;
+
+  let mut extensions = Vec::new();
+  if flags & GtpV1Header::<()>::E_FLAG != 0 {
+    while next != 0 {
+      // length is in 4 bytes units, length and next type fields included
+      let Success {
+        token: len,
+        stream: rest,
+      } = octet
+        .try_map(|len| {
+          if len != 0 {
+            Ok(usize::from(len) * 4 - 2)
+          } else {
+            Err(Context::new(GtpAtom::ExtensionLength))
+          }
+        })
+        .parse(stream)?;
+      let Success {
+        token: (content, following),
+        stream: rest,
+      } = (take(len), octet).parse(rest)?;
+      extensions.push(GtpExtension {
+        extension_type: next,
+        content,
+      });
+      next = following;
+      stream = rest;
+    }
+  }
+
+  Parsed::Success {
+    token: GtpV1Header {
+      flags,
+      message_type,
+      length,
+      teid,
+      sequence: (flags & GtpV1Header::<()>::S_FLAG != 0).then_some(sequence),
+      npdu: (flags & GtpV1Header::<()>::PN_FLAG != 0).then_some(npdu),
+      extensions,
+    },
+    stream,
+  }
+}
+
+/// Payload of a GTP-U message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GtpPayload<Span> {
+  /// Inner IPv4 header of a G-PDU, the stream is left at its payload
+  Ipv4(IPv4Header<Span>),
+  /// Inner IPv6 header of a G-PDU, the stream is left at its payload
+  Ipv6(IPv6Header),
+  /// Payload of other messages or of a G-PDU not carrying IP
+  Other(Span),
+}
+
+/// GTP-U message with its decoded payload
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GtpUserPacket<Span> {
+  /// GTPv1 header
+  pub header: GtpV1Header<Span>,
+  /// Payload
+  pub payload: GtpPayload<Span>,
+}
+
+/// Parse a GTPv1-U header then, for a G-PDU, the inner IP header so the user
+/// packet can be dissected like any other
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn gtpu_decap<Stream, Context>(
+  stream: Stream,
+) -> Parsed<GtpUserPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): GtpParse<Stream, Context>,
+  Context: Contexting<Ipv4Atom>,
+  Context: Contexting<Ipv6Atom>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = gtpv1_header.parse(stream)?;
+
+  let version = if header.message_type == GtpV1MessageType::G_PDU {
+    match octet::<_, Context>.peek().parse(stream.clone()) {
+      Parsed::Success {
+        token: (octet, _), ..
+      } => Some(octet >> 4u8),
+      _ => None,
+    }
+  } else {
+    None
+  };
+
+  let Success {
+    token: payload,
+    stream,
+  } = match version {
+    Some(4) => ipv4_header.map(GtpPayload::Ipv4).parse(stream),
+    Some(6) => ipv6_header.map(GtpPayload::Ipv6).parse(stream),
+    _ => all.map(GtpPayload::Other).parse(stream),
+  }?;
+
+  Parsed::Success {
+    token: GtpUserPacket { header, payload },
+    stream,
+  }
+}
+
+struct_variants! {
+  GtpV2MessageType, message_type, u8:
+    /// Echo Request
+    ECHO_REQUEST => 1,
+    /// Echo Response
+    ECHO_RESPONSE => 2,
+    /// Create Session Request
+    CREATE_SESSION_REQUEST => 32,
+    /// Create Session Response
+    CREATE_SESSION_RESPONSE => 33,
+    /// Modify Bearer Request
+    MODIFY_BEARER_REQUEST => 34,
+    /// Modify Bearer Response
+    MODIFY_BEARER_RESPONSE => 35,
+    /// Delete Session Request
+    DELETE_SESSION_REQUEST => 36,
+    /// Delete Session Response
+    DELETE_SESSION_RESPONSE => 37,
+}
+
+/// GTPv2-C header, the payload of a UDP datagram on [GTPC_PORT]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GtpV2Header {
+  /// Version, piggybacking and TEID flags
+  pub flags: u8,
+  /// Message type
+  pub message_type: GtpV2MessageType,
+  /// Length of the message after the first 4 bytes
+  pub length: u16,
+  /// Tunnel Endpoint Identifier, when the T flag is set
+  pub teid: Option<u32>,
+  /// 24 bits sequence number
+  pub sequence: u32,
+}
+
+impl GtpV2Header {
+  /// Piggybacking flag, another message follow this one
+  pub const P_FLAG: u8 = 0x10;
+  /// TEID flag
+  pub const T_FLAG: u8 = 0x08;
+
+  /// Return true if another message is piggybacked after this one
+  pub const fn piggybacked(&self) -> bool {
+    self.flags & Self::P_FLAG != 0
+  }
+}
+
+/// Parse a GTPv2-C header, the stream is left at the information elements
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn gtpv2_header<Stream, Context>(stream: Stream) -> Parsed<GtpV2Header, Stream, Context>
+where
+  (): GtpParse<Stream, Context>,
+{
+  let Success {
+    token: (flags, message_type, length),
+    stream,
+  } = (
+    octet.try_map(|flags| match flags >> 5u8 {
+      2 => Ok(flags),
+      version => Err(Context::new(GtpAtom::Version(version))),
+    }),
+    octet.map(GtpV2MessageType::new),
+    u16_be,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: teid,
+    stream,
+  } = if flags & GtpV2Header::T_FLAG != 0 {
+    u32_be.map(Some).parse(stream)?
+  } else {
+    Success {
+      token: None,
+      stream,
+    }
+  };
+
+  // sequence number is followed by a spare byte
+  let Success {
+    token: sequence,
+    stream,
+  } = u32_be.map(|word| word >> 8u8).parse(stream)?;
+
+  Parsed::Success {
+    token: GtpV2Header {
+      flags,
+      message_type,
+      length,
+      teid,
+      sequence,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    GtpPayload,
+    GtpUserPacket,
+    GtpV1MessageType,
+    GtpV2MessageType,
+  };
+  use crate::IPProtocol;
+
+  #[test]
+  fn gtp() {
+    let gpdu = [
+      0x34, 0xFF, 0x00, 0x24, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x85, 0x01, 0x00, 0x09,
+      0x00, 0x45, 0x00, 0x00, 0x1C, 0x00, 0x00, 0x40, 0x00, 0x40, 0x11, 0x00, 0x00, 0x0A, 0x00,
+      0x00, 0x01, 0x0A, 0x00, 0x00, 0x02, 0xAA,
+    ];
+    let Success {
+      token: GtpUserPacket { header, payload },
+      stream,
+    } = super::gtpu_decap::<_, Ignore>(&gpdu[..]).unwrap();
+    assert_eq!(header.message_type, GtpV1MessageType::G_PDU);
+    assert_eq!(header.teid, 1);
+    assert_eq!(header.sequence, None);
+    assert_eq!(header.extensions.len(), 1);
+    assert_eq!(header.extensions[0].content, [0x00, 0x09]);
+    let GtpPayload::Ipv4(ipv4) = payload else {
+      panic!("expected an IPv4 packet");
+    };
+    assert_eq!(ipv4.protocol, IPProtocol::UDP);
+    assert_eq!(stream, [0xAA]);
+
+    let create = [
+      0x48, 0x20, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x2A, 0x00,
+    ];
+    let Success { token, stream } = super::gtpv2_header::<_, Ignore>(&create[..]).unwrap();
+    assert_eq!(token.message_type, GtpV2MessageType::CREATE_SESSION_REQUEST);
+    assert_eq!(token.teid, Some(0));
+    assert_eq!(token.sequence, 42);
+    assert_eq!(stream, b"");
+  }
+}
@@ -0,0 +1,526 @@
+//! Handles parsing of HTTP/1.x messages head and body framing
+//! <https://www.rfc-editor.org/rfc/rfc9112>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    is,
+    octet,
+    tag,
+    take,
+    to_digit,
+    BaseAtom,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+/// Meta trait for http combinator
+pub trait HttpParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  <Stream as Streaming>::Span: AsRef<[u8]>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<BaseAtom<u8>>,
+  Context: Contexting<HttpAtom>;
+
+/// Atom produced by http parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HttpAtom {
+  /// When Content-Length is not a number or several values disagree
+  ContentLength,
+  /// When a request use a Transfer-Encoding that doesn't end with chunked
+  TransferEncoding,
+  /// When a chunk size doesn't fit in 64 bits
+  ChunkSize,
+}
+
+impl Display for HttpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      HttpAtom::ContentLength => write!(f, "ContentLength: invalid Content-Length"),
+      HttpAtom::TransferEncoding => {
+        write!(f, "TransferEncoding: request body length is unknown")
+      }
+      HttpAtom::ChunkSize => write!(f, "ChunkSize: chunk size is too big"),
+    }
+  }
+}
+
+/// HTTP version
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpVersion {
+  /// Major version
+  pub major: u8,
+  /// Minor version
+  pub minor: u8,
+}
+
+/// Header field, name and value without surrounding whitespace
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpHeader<Span> {
+  /// Field name
+  pub name: Span,
+  /// Field value
+  pub value: Span,
+}
+
+/// How the body following a message head is delimited
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HttpBody {
+  /// No body
+  None,
+  /// Body of the given length
+  ContentLength(u64),
+  /// Body is a sequence of chunks, see [http_chunked_body]
+  Chunked,
+  /// Body end when the connection is closed, only for response
+  UntilClose,
+}
+
+/// HTTP request head
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpRequest<Span> {
+  /// Method
+  pub method: Span,
+  /// Request target
+  pub target: Span,
+  /// Version
+  pub version: HttpVersion,
+  /// Header fields in order
+  pub headers: Vec<HttpHeader<Span>>,
+  /// Body framing
+  pub body: HttpBody,
+}
+
+/// HTTP response head
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpResponse<Span> {
+  /// Version
+  pub version: HttpVersion,
+  /// Status code
+  pub status: u16,
+  /// Reason phrase
+  pub reason: Span,
+  /// Header fields in order
+  pub headers: Vec<HttpHeader<Span>>,
+  /// Body framing, a response to a HEAD request has no body whatever this
+  /// say
+  pub body: HttpBody,
+}
+
+/// Body of [HttpBody::Chunked]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HttpChunkedBody<Span> {
+  /// Data of each chunk
+  pub chunks: Vec<Span>,
+  /// Trailer fields
+  pub trailers: Vec<HttpHeader<Span>>,
+}
+
+pub(crate) fn find_header<'a, Span: AsRef<[u8]>>(
+  headers: &'a [HttpHeader<Span>], name: &str,
+) -> Option<&'a Span> {
+  headers
+    .iter()
+    .find(|header| header.name.as_ref().eq_ignore_ascii_case(name.as_bytes()))
+    .map(|header| &header.value)
+}
+
+impl<Span: AsRef<[u8]>> HttpRequest<Span> {
+  /// Return the value of the first header field named name, ignoring case
+  pub fn header(&self, name: &str) -> Option<&Span> {
+    find_header(&self.headers, name)
+  }
+}
+
+impl<Span: AsRef<[u8]>> HttpResponse<Span> {
+  /// Return the value of the first header field named name, ignoring case
+  pub fn header(&self, name: &str) -> Option<&Span> {
+    find_header(&self.headers, name)
+  }
+}
+
+pub(crate) fn is_tchar(octet: u8) -> bool {
+  octet.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&octet)
+}
+
+pub(crate) fn is_vchar(octet: u8) -> bool {
+  octet > b' ' && octet != 0x7F
+}
+
+pub(crate) fn is_ws(octet: u8) -> bool {
+  octet == b' ' || octet == b'\t'
+}
+
+// Span of at least min octets matching f
+pub(crate) fn http_run<Stream, Context>(
+  min: usize, f: fn(u8) -> bool,
+) -> impl Parse<Stream, Context, Token = <Stream as Streaming>::Span>
+where
+  (): HttpParse<Stream, Context>,
+{
+  octet
+    .filter(move |octet| f(*octet))
+    .fold_bounds(min.., || (), |(), _| ())
+    .span()
+    .map(Success::into_stream)
+}
+
+// Tolerate a bare LF as RFC 9112 allow
+pub(crate) fn http_crlf<Stream, Context>(stream: Stream) -> Parsed<(), Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  (is(b'\r').opt(), is(b'\n')).drop().parse(stream)
+}
+
+// name/major.minor, RTSP share the syntax of HTTP
+pub(crate) fn protocol_version<Stream, Context>(
+  name: &'static str,
+) -> impl Parse<Stream, Context, Token = HttpVersion>
+where
+  (): HttpParse<Stream, Context>,
+{
+  (tag(name), is(b'/'), to_digit, is(b'.'), to_digit)
+    .map(|(_, _, major, _, minor)| HttpVersion { major, minor })
+}
+
+fn http_version<Stream, Context>(stream: Stream) -> Parsed<HttpVersion, Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  protocol_version("HTTP").parse(stream)
+}
+
+fn http_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<HttpHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  let Success {
+    token: (name, ..),
+    stream,
+  } = (http_run(1, is_tchar), is(b':'), http_run(0, is_ws)).parse(stream)?;
+
+  let Success {
+    token: (value, ..),
+    stream,
+  } = (
+    (
+      http_run(1, is_vchar),
+      (http_run(1, is_ws), http_run(1, is_vchar))
+        .drop()
+        .fold_bounds(.., || (), |(), ()| ()),
+    )
+      .opt()
+      .span()
+      .map(Success::into_stream),
+    http_run(0, is_ws),
+    http_crlf,
+  )
+    .parse(stream)?;
+
+  Parsed::Success {
+    token: HttpHeader { name, value },
+    stream,
+  }
+}
+
+pub(crate) fn http_headers<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Vec<HttpHeader<<Stream as Streaming>::Span>>, Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  http_header
+    .fold_bounds(.., Vec::new, Acc::acc)
+    .and_drop(http_crlf)
+    .parse(stream)
+}
+
+pub(crate) fn content_length(value: &[u8]) -> Option<u64> {
+  if value.is_empty() || !value.iter().all(u8::is_ascii_digit) {
+    return None;
+  }
+  std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+// Transfer-Encoding take precedence over Content-Length, None when the
+// message has neither
+fn http_body<Span: AsRef<[u8]>>(
+  headers: &[HttpHeader<Span>], request: bool,
+) -> Result<Option<HttpBody>, HttpAtom> {
+  let mut body = None;
+  for header in headers {
+    let name = header.name.as_ref();
+    let value = header.value.as_ref();
+    if name.eq_ignore_ascii_case(b"transfer-encoding") {
+      let chunked = value
+        .rsplit(|octet| *octet == b',')
+        .next()
+        .map(|coding| coding.trim_ascii().eq_ignore_ascii_case(b"chunked"))
+        .unwrap_or(false);
+      return match (chunked, request) {
+        (true, _) => Ok(Some(HttpBody::Chunked)),
+        (false, true) => Err(HttpAtom::TransferEncoding),
+        (false, false) => Ok(Some(HttpBody::UntilClose)),
+      };
+    }
+    if name.eq_ignore_ascii_case(b"content-length") {
+      let length = content_length(value).ok_or(HttpAtom::ContentLength)?;
+      match body {
+        Some(HttpBody::ContentLength(previous)) if previous != length => {
+          return Err(HttpAtom::ContentLength);
+        }
+        _ => body = Some(HttpBody::ContentLength(length)),
+      }
+    }
+  }
+
+  // a later Transfer-Encoding field would have returned
+  Ok(body)
+}
+
+/// Parse a request line and header fields up to the empty line, the stream
+/// is left at the start of the body
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn http_request<Stream, Context>(
+  stream: Stream,
+) -> Parsed<HttpRequest<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  let Success {
+    token: (method, _, target, _, version, _, headers),
+    stream,
+  } = (
+    http_run(1, is_tchar),
+    is(b' '),
+    http_run(1, is_vchar),
+    is(b' '),
+    http_version,
+    http_crlf,
+    http_headers,
+  )
+    .parse(stream)?;
+
+  match http_body(&headers, true) {
+    Ok(body) => Parsed::Success {
+      token: HttpRequest {
+        method,
+        target,
+        version,
+        headers,
+        body: body.unwrap_or(HttpBody::None),
+      },
+      stream,
+    },
+    Err(atom) => Parsed::Failure(Context::new(atom)),
+  }
+}
+
+/// Parse a status line and header fields up to the empty line, the stream
+/// is left at the start of the body
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn http_response<Stream, Context>(
+  stream: Stream,
+) -> Parsed<HttpResponse<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  let Success {
+    token: (version, _, status, _, reason, _, headers),
+    stream,
+  } = (
+    http_version,
+    is(b' '),
+    (to_digit, to_digit, to_digit)
+      .map(|(a, b, c)| u16::from(a) * 100 + u16::from(b) * 10 + u16::from(c)),
+    is(b' '),
+    http_run(0, |octet| is_ws(octet) || is_vchar(octet)),
+    http_crlf,
+    http_headers,
+  )
+    .parse(stream)?;
+
+  let body = match status {
+    100..=199 | 204 | 304 => Ok(Some(HttpBody::None)),
+    _ => http_body(&headers, false),
+  };
+  match body {
+    Ok(body) => Parsed::Success {
+      token: HttpResponse {
+        version,
+        status,
+        reason,
+        headers,
+        body: body.unwrap_or(HttpBody::UntilClose),
+      },
+      stream,
+    },
+    Err(atom) => Parsed::Failure(Context::new(atom)),
+  }
+}
+
+/// Parse a chunk and return its data, chunk extensions are ignored. The last
+/// chunk has no data and is followed by the trailer fields.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn http_chunk<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  let Success {
+    token: (size, ..),
+    stream,
+  } = (
+    octet
+      .filter_map(|octet| char::from(octet).to_digit(16))
+      .fold_bounds(
+        1..=16,
+        || 0u64,
+        |size, digit| size << 4u8 | u64::from(digit),
+      ),
+    http_run(0, |octet| octet != b'\r' && octet != b'\n'),
+    http_crlf,
+  )
+    .parse(stream)?;
+
+  let Ok(size) = usize::try_from(size) else {
+    return Parsed::Failure(Context::new(HttpAtom::ChunkSize));
+  };
+  if size == 0 {
+    take(0).parse(stream)
+  } else {
+    take(size).and_drop(http_crlf).parse(stream)
+  }
+}
+
+/// Parse every chunk of a chunked body and the trailer fields
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn http_chunked_body<Stream, Context>(
+  mut stream: Stream,
+) -> Parsed<HttpChunkedBody<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): HttpParse<Stream, Context>,
+{
+  let mut chunks = Vec::new();
+  loop {
+    let Success {
+      token: chunk,
+      stream: next,
+    } = http_chunk.parse(stream)?;
+    stream = next;
+    if chunk.as_ref().is_empty() {
+      break;
+    }
+    chunks.push(chunk);
+  }
+
+  let Success {
+    token: trailers,
+    stream,
+  } = http_headers.parse(stream)?;
+
+  Parsed::Success {
+    token: HttpChunkedBody { chunks, trailers },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    HttpBody,
+    HttpVersion,
+  };
+
+  #[test]
+  fn http_message() {
+    let request = b"GET /index.html HTTP/1.1\r\nHost: example.com \r\nAccept:  text/html, */*\r\nX-Empty:\r\n\r\nrest";
+
+    let Success {
+      token: request,
+      stream,
+    } = super::http_request::<_, Ignore>(&request[..]).unwrap();
+    assert_eq!(stream, b"rest");
+    assert_eq!(request.method, b"GET");
+    assert_eq!(request.target, b"/index.html");
+    assert_eq!(request.version, HttpVersion { major: 1, minor: 1 });
+    assert_eq!(request.header("host"), Some(&&b"example.com"[..]));
+    assert_eq!(request.header("ACCEPT"), Some(&&b"text/html, */*"[..]));
+    assert_eq!(request.header("x-empty"), Some(&&b""[..]));
+    assert_eq!(request.body, HttpBody::None);
+
+    let response = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5;ext=1\r\nhello\r\n6\r\n world\r\n0\r\nExpires: never\r\n\r\n";
+
+    let Success {
+      token: head,
+      stream,
+    } = super::http_response::<_, Ignore>(&response[..]).unwrap();
+    assert_eq!(head.status, 200);
+    assert_eq!(head.reason, b"OK");
+    assert_eq!(head.body, HttpBody::Chunked);
+
+    let Success {
+      token: body,
+      stream,
+    } = super::http_chunked_body::<_, Ignore>(stream).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(body.chunks, [&b"hello"[..], &b" world"[..]]);
+    assert_eq!(body.trailers[0].value, b"never");
+
+    let response = b"HTTP/1.0 404 Not Found\r\nContent-Length: 3\r\ncontent-length: 3\r\n\r\n";
+    let head = super::http_response::<_, Ignore>(&response[..])
+      .unwrap()
+      .token;
+    assert_eq!(head.body, HttpBody::ContentLength(3));
+
+    let response = b"HTTP/1.1 200 OK\r\nContent-Length: 3\r\nContent-Length: 4\r\n\r\n";
+    assert!(matches!(
+      super::http_response::<_, Ignore>(&response[..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
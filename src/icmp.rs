@@ -0,0 +1,646 @@
+//! Handles parsing of ICMP and ICMPv6 messages
+//! <https://www.rfc-editor.org/rfc/rfc792>
+//! <https://www.rfc-editor.org/rfc/rfc4443>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::Ipv6Addr,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  ipv4_header,
+  ipv6_header,
+  struct_variants,
+  IPProtocol,
+  IPv4Header,
+  IPv6Header,
+  Ipv4Atom,
+  Ipv6Atom,
+  Port,
+};
+
+/// Meta trait for icmp combinator
+pub trait IcmpParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<IcmpAtom>;
+
+/// Atom produced by icmp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IcmpAtom {
+  /// When an error message was expected, contain the type found
+  NotError(u8),
+  /// When an MLD message was expected, contain the type found
+  NotMld(u8),
+}
+
+impl Display for IcmpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      IcmpAtom::NotError(icmp_type) => {
+        write!(f, "NotError: type {} is not an error message", icmp_type)
+      }
+      IcmpAtom::NotMld(icmpv6_type) => {
+        write!(f, "NotMld: type {} is not an MLD message", icmpv6_type)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  IcmpType, icmp_type, u8:
+    /// Echo Reply
+    ECHO_REPLY => 0,
+    /// Destination Unreachable
+    DEST_UNREACHABLE => 3,
+    /// Source Quench
+    SOURCE_QUENCH => 4,
+    /// Redirect
+    REDIRECT => 5,
+    /// Echo
+    ECHO => 8,
+    /// Router Advertisement
+    ROUTER_ADVERTISEMENT => 9,
+    /// Router Solicitation
+    ROUTER_SOLICITATION => 10,
+    /// Time Exceeded
+    TIME_EXCEEDED => 11,
+    /// Parameter Problem
+    PARAMETER_PROBLEM => 12,
+    /// Timestamp
+    TIMESTAMP => 13,
+    /// Timestamp Reply
+    TIMESTAMP_REPLY => 14,
+}
+
+impl IcmpType {
+  /// Return true if messages of this type quote the datagram that caused
+  /// them
+  pub const fn is_error(&self) -> bool {
+    matches!(self.icmp_type, 3 | 4 | 5 | 11 | 12)
+  }
+}
+
+struct_variants! {
+  Icmpv6Type, icmpv6_type, u8:
+    /// Destination Unreachable
+    DEST_UNREACHABLE => 1,
+    /// Packet Too Big
+    PACKET_TOO_BIG => 2,
+    /// Time Exceeded
+    TIME_EXCEEDED => 3,
+    /// Parameter Problem
+    PARAMETER_PROBLEM => 4,
+    /// Echo Request
+    ECHO_REQUEST => 128,
+    /// Echo Reply
+    ECHO_REPLY => 129,
+    /// Multicast Listener Query
+    MLD_QUERY => 130,
+    /// Multicast Listener Report
+    MLD_REPORT => 131,
+    /// Multicast Listener Done
+    MLD_DONE => 132,
+    /// Router Solicitation
+    ROUTER_SOLICITATION => 133,
+    /// Router Advertisement
+    ROUTER_ADVERTISEMENT => 134,
+    /// Neighbor Solicitation
+    NEIGHBOR_SOLICITATION => 135,
+    /// Neighbor Advertisement
+    NEIGHBOR_ADVERTISEMENT => 136,
+    /// Redirect
+    REDIRECT => 137,
+    /// Version 2 Multicast Listener Report
+    MLDV2_REPORT => 143,
+}
+
+impl Icmpv6Type {
+  /// Return true if messages of this type quote the packet that caused them
+  pub const fn is_error(&self) -> bool {
+    self.icmpv6_type < 128
+  }
+}
+
+/// ICMP header, the payload of an IPv4 packet with [IPProtocol::ICMP]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IcmpHeader {
+  /// Type of message
+  pub icmp_type: IcmpType,
+  /// Subtype of message
+  pub code: u8,
+  /// Internet checksum of the whole message
+  pub checksum: u16,
+  /// Rest of header, its meaning depend on type, identifier and sequence
+  /// number for echo, gateway address for redirect, unused for most errors
+  pub rest: u32,
+}
+
+impl IcmpHeader {
+  /// Length of an ICMP header
+  pub const LEN: usize = 8;
+}
+
+/// ICMPv6 header, the payload of an IPv6 packet with [IPProtocol::ICMP_6]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Icmpv6Header {
+  /// Type of message
+  pub icmpv6_type: Icmpv6Type,
+  /// Subtype of message
+  pub code: u8,
+  /// Internet checksum of the whole message and the IPv6 pseudo header
+  pub checksum: u16,
+  /// Rest of header, its meaning depend on type, MTU for packet too big,
+  /// pointer for parameter problem, unused for the other errors
+  pub rest: u32,
+}
+
+impl Icmpv6Header {
+  /// Length of an ICMPv6 header
+  pub const LEN: usize = 8;
+}
+
+/// Parse an ICMP header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn icmp_header<Stream, Context>(stream: Stream) -> Parsed<IcmpHeader, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+{
+  (octet.map(IcmpType::new), octet, u16_be, u32_be)
+    .map(|(icmp_type, code, checksum, rest)| IcmpHeader {
+      icmp_type,
+      code,
+      checksum,
+      rest,
+    })
+    .parse(stream)
+}
+
+/// Parse an ICMPv6 header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn icmpv6_header<Stream, Context>(stream: Stream) -> Parsed<Icmpv6Header, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+{
+  (octet.map(Icmpv6Type::new), octet, u16_be, u32_be)
+    .map(|(icmpv6_type, code, checksum, rest)| Icmpv6Header {
+      icmpv6_type,
+      code,
+      checksum,
+      rest,
+    })
+    .parse(stream)
+}
+
+/// Header of the datagram quoted by an ICMP error
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuotedIp<Span> {
+  /// Quoted by ICMP
+  Ipv4(IPv4Header<Span>),
+  /// Quoted by ICMPv6
+  Ipv6(IPv6Header),
+}
+
+/// Start of the datagram quoted by an ICMP error, enough to find the flow
+/// that caused it
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IcmpQuote<Span> {
+  /// IP header of the original datagram
+  pub ip: QuotedIp<Span>,
+  /// First 8 bytes of the original transport header
+  pub transport: [u8; 8],
+}
+
+impl<Span> IcmpQuote<Span> {
+  /// Return the transport protocol of the original datagram, IPv6 extension
+  /// headers are not followed
+  pub fn protocol(&self) -> IPProtocol {
+    match &self.ip {
+      QuotedIp::Ipv4(ipv4) => ipv4.protocol,
+      QuotedIp::Ipv6(ipv6) => ipv6.next_header,
+    }
+  }
+
+  /// Return the source and destination ports of the original datagram when
+  /// it's TCP, UDP or SCTP
+  pub fn ports(&self) -> Option<(Port, Port)> {
+    match self.protocol() {
+      IPProtocol::TCP | IPProtocol::UDP | IPProtocol::SCTP => {
+        let [s0, s1, d0, d1, ..] = self.transport;
+        Some((
+          Port(u16::from_be_bytes([s0, s1])),
+          Port(u16::from_be_bytes([d0, d1])),
+        ))
+      }
+      _ => None,
+    }
+  }
+}
+
+/// ICMP error message and the datagram it quote
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IcmpError<Header, Span> {
+  /// ICMP or ICMPv6 header
+  pub header: Header,
+  /// Quoted datagram
+  pub quote: IcmpQuote<Span>,
+}
+
+/// Parse an ICMP error message, Destination Unreachable, Source Quench,
+/// Redirect, Time Exceeded or Parameter Problem, and the IPv4 header plus the
+/// first 8 bytes of the transport header it quote. The rest of the quoted
+/// datagram is left in the stream.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn icmp_error<Stream, Context>(
+  stream: Stream,
+) -> Parsed<IcmpError<IcmpHeader, <Stream as Streaming>::Span>, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+  Context: Contexting<Ipv4Atom>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = icmp_header
+    .try_map(|header: IcmpHeader| {
+      if header.icmp_type.is_error() {
+        Ok(header)
+      } else {
+        Err(Context::new(IcmpAtom::NotError(
+          header.icmp_type.icmp_type(),
+        )))
+      }
+    })
+    .parse(stream)?;
+
+  (ipv4_header, octet.fill())
+    .map(|(ip, transport)| IcmpError {
+      header,
+      quote: IcmpQuote {
+        ip: QuotedIp::Ipv4(ip),
+        transport,
+      },
+    })
+    .parse(stream)
+}
+
+/// Parse an ICMPv6 error message, Destination Unreachable, Packet Too Big,
+/// Time Exceeded or Parameter Problem, and the IPv6 header plus the first 8
+/// bytes of the next header it quote. The rest of the quoted packet is left
+/// in the stream.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn icmpv6_error<Stream, Context>(
+  stream: Stream,
+) -> Parsed<IcmpError<Icmpv6Header, <Stream as Streaming>::Span>, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+  Context: Contexting<Ipv6Atom>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = icmpv6_header
+    .try_map(|header: Icmpv6Header| {
+      if header.icmpv6_type.is_error() {
+        Ok(header)
+      } else {
+        Err(Context::new(IcmpAtom::NotError(
+          header.icmpv6_type.icmpv6_type(),
+        )))
+      }
+    })
+    .parse(stream)?;
+
+  (ipv6_header, octet.fill())
+    .map(|(ip, transport)| IcmpError {
+      header,
+      quote: IcmpQuote {
+        ip: QuotedIp::Ipv6(ip),
+        transport,
+      },
+    })
+    .parse(stream)
+}
+
+struct_variants! {
+  MldRecordType, record_type, u8:
+    /// MODE_IS_INCLUDE
+    MODE_IS_INCLUDE => 1,
+    /// MODE_IS_EXCLUDE
+    MODE_IS_EXCLUDE => 2,
+    /// CHANGE_TO_INCLUDE_MODE
+    CHANGE_TO_INCLUDE => 3,
+    /// CHANGE_TO_EXCLUDE_MODE
+    CHANGE_TO_EXCLUDE => 4,
+    /// ALLOW_NEW_SOURCES
+    ALLOW_NEW_SOURCES => 5,
+    /// BLOCK_OLD_SOURCES
+    BLOCK_OLD_SOURCES => 6,
+}
+
+/// Fields an MLDv2 query add to an MLDv1 query
+/// <https://www.rfc-editor.org/rfc/rfc3810#section-5.1>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MldV2Query {
+  /// Suppress Router-Side Processing flag
+  pub suppress: bool,
+  /// Querier's Robustness Variable
+  pub qrv: u8,
+  /// Querier's Query Interval Code
+  pub qqic: u8,
+  /// Sources of a source specific query
+  pub sources: Vec<Ipv6Addr>,
+}
+
+/// Multicast address record of an MLDv2 report
+/// <https://www.rfc-editor.org/rfc/rfc3810#section-5.2>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MldRecord<Span> {
+  /// Type of record
+  pub record_type: MldRecordType,
+  /// Multicast address the record is about
+  pub address: Ipv6Addr,
+  /// Sources
+  pub sources: Vec<Ipv6Addr>,
+  /// Auxiliary data, unused by MLDv2
+  pub aux_data: Span,
+}
+
+/// Multicast Listener Discovery message
+/// <https://www.rfc-editor.org/rfc/rfc2710>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MldMessage<Span> {
+  /// Multicast Listener Query, general when address is unspecified
+  Query {
+    /// Maximum delay before sending a report, in milliseconds for MLDv1
+    max_response_code: u16,
+    /// Multicast address queried
+    address: Ipv6Addr,
+    /// Present for MLDv2 queries
+    v2: Option<MldV2Query>,
+  },
+  /// MLDv1 Multicast Listener Report
+  Report {
+    /// Multicast address listened
+    address: Ipv6Addr,
+  },
+  /// MLDv1 Multicast Listener Done
+  Done {
+    /// Multicast address no longer listened
+    address: Ipv6Addr,
+  },
+  /// MLDv2 Multicast Listener Report
+  V2Report {
+    /// Multicast address records
+    records: Vec<MldRecord<Span>>,
+  },
+}
+
+fn mld_addr<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+{
+  octet.fill().map(Ipv6Addr::from).parse(stream)
+}
+
+fn mld_sources<Stream, Context>(stream: Stream, len: u16) -> Parsed<Vec<Ipv6Addr>, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+{
+  mld_addr
+    .fold_bounds(usize::from(len), Vec::new, Acc::acc)
+    .parse(stream)
+}
+
+fn mld_v2_query<Stream, Context>(stream: Stream) -> Parsed<MldV2Query, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+{
+  let Success {
+    token: (flags, qqic, len),
+    stream,
+  } = (octet, octet, u16_be).parse(stream)?;
+
+  mld_sources(stream, len).map_token(|sources| MldV2Query {
+    suppress: flags & 0x08 != 0,
+    qrv: flags & 0x07,
+    qqic,
+    sources,
+  })
+}
+
+fn mld_record<Stream, Context>(
+  stream: Stream,
+) -> Parsed<MldRecord<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+{
+  let Success {
+    token: (record_type, aux_len, len, address),
+    stream,
+  } = (octet.map(MldRecordType::new), octet, u16_be, mld_addr).parse(stream)?;
+
+  let Success {
+    token: sources,
+    stream,
+  } = mld_sources(stream, len)?;
+
+  let Success {
+    token: aux_data,
+    stream,
+  } = take(usize::from(aux_len) * 4).parse(stream)?;
+
+  Parsed::Success {
+    token: MldRecord {
+      record_type,
+      address,
+      sources,
+      aux_data,
+    },
+    stream,
+  }
+}
+
+/// Parse an ICMPv6 Multicast Listener Discovery message, MLDv1 or MLDv2. A
+/// query is MLDv2 when bytes follow the multicast address.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn mld_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<MldMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): IcmpParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = icmpv6_header.parse(stream)?;
+  let [code_0, code_1, len_0, len_1] = header.rest.to_be_bytes();
+
+  match header.icmpv6_type {
+    Icmpv6Type::MLD_QUERY => (mld_addr, mld_v2_query.opt())
+      .map(|(address, v2)| MldMessage::Query {
+        max_response_code: u16::from_be_bytes([code_0, code_1]),
+        address,
+        v2,
+      })
+      .parse(stream),
+    Icmpv6Type::MLD_REPORT => mld_addr
+      .map(|address| MldMessage::Report { address })
+      .parse(stream),
+    Icmpv6Type::MLD_DONE => mld_addr
+      .map(|address| MldMessage::Done { address })
+      .parse(stream),
+    Icmpv6Type::MLDV2_REPORT => mld_record
+      .fold_bounds(
+        usize::from(u16::from_be_bytes([len_0, len_1])),
+        Vec::new,
+        Acc::acc,
+      )
+      .map(|records| MldMessage::V2Report { records })
+      .parse(stream),
+    icmpv6_type => Parsed::Failure(Context::new(IcmpAtom::NotMld(icmpv6_type.icmpv6_type()))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  };
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    IcmpType,
+    MldMessage,
+    MldRecord,
+    MldRecordType,
+    QuotedIp,
+  };
+  use crate::Port;
+
+  #[test]
+  fn icmp_error() {
+    // time exceeded in transit for a traceroute probe
+    let message = [
+      0x0B, 0x00, 0xF4, 0xFF, 0x00, 0x00, 0x00, 0x00, 0x45, 0x00, 0x00, 0x3C, 0x1C, 0x46, 0x00,
+      0x00, 0x01, 0x11, 0x00, 0x00, 0xC0, 0xA8, 0x00, 0x6C, 0x08, 0x08, 0x08, 0x08, 0x82, 0x9B,
+      0x82, 0x9A, 0x00, 0x28, 0x00, 0x00, 0x40,
+    ];
+
+    let Success { token, stream } = super::icmp_error::<_, Ignore>(&message[..]).unwrap();
+    assert_eq!(token.header.icmp_type, IcmpType::TIME_EXCEEDED);
+    let QuotedIp::Ipv4(ip) = &token.quote.ip else {
+      panic!("quoted ipv4 header");
+    };
+    assert_eq!(ip.dest_addr, Ipv4Addr::new(8, 8, 8, 8));
+    assert_eq!(ip.ttl, 1);
+    assert_eq!(token.quote.ports(), Some((Port(33435), Port(33434))));
+    assert_eq!(stream, [0x40]);
+
+    let echo = [0x08, 0x00, 0xF7, 0xFF, 0x00, 0x00, 0x00, 0x00];
+    assert!(matches!(
+      super::icmp_error::<_, Ignore>(&echo[..]),
+      Parsed::Failure(_)
+    ));
+    assert_eq!(
+      super::icmp_header::<_, Ignore>(&echo[..])
+        .unwrap()
+        .token
+        .icmp_type,
+      IcmpType::ECHO
+    );
+  }
+  #[test]
+  fn mld_message() {
+    let report = [
+      0x8F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x04, 0x00, 0x00, 0x01, 0xFF, 0x02, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xFF, 0x00, 0x00, 0x01, 0xFE, 0x80,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    ];
+
+    assert_eq!(
+      super::mld_message::<_, Ignore>(&report[..]),
+      Parsed::Success {
+        token: MldMessage::V2Report {
+          records: vec![MldRecord {
+            record_type: MldRecordType::CHANGE_TO_EXCLUDE,
+            address: "ff02::1:ff00:1".parse().unwrap(),
+            sources: vec!["fe80::1".parse().unwrap()],
+            aux_data: &[][..],
+          }],
+        },
+        stream: &[][..],
+      }
+    );
+
+    let query = [
+      0x82, 0x00, 0x00, 0x00, 0x27, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let Success { token, .. } = super::mld_message::<_, Ignore>(&query[..]).unwrap();
+    assert_eq!(
+      token,
+      MldMessage::Query {
+        max_response_code: 10000,
+        address: Ipv6Addr::UNSPECIFIED,
+        v2: None,
+      }
+    );
+  }
+}
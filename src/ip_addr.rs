@@ -4,13 +4,18 @@ use core::fmt::{
   Formatter,
 };
 use std::net::{
+  IpAddr,
   Ipv4Addr,
   Ipv6Addr,
+  SocketAddr,
+  SocketAddrV4,
+  SocketAddrV6,
 };
 
 use binator::{
   base::{
     is,
+    octet,
     to_digit,
     uint_radix,
     BaseAtom,
@@ -25,7 +30,15 @@ use binator::{
   CoreAtom,
   Parse,
   Parsed,
+  Split,
   Streaming,
+  Success,
+};
+
+use crate::{
+  IpNet,
+  Ipv4Net,
+  Ipv6Net,
 };
 
 /// Atom of ip_addr parser
@@ -35,6 +48,20 @@ pub enum IpAddrAtom {
   NotAnOctet,
   /// When value in IPv4 have leading zero
   LeadingZero,
+  /// When prefix length of a CIDR is greater than the address length
+  PrefixLen(u8),
+  /// When port of a socket address would overflow an u16
+  NotAPort(u32),
+  /// When IPv6 have more than 8 groups, or 7 with `::`
+  TooManyGroups,
+  /// When IPv6 without `::` have less than 8 groups
+  TooFewGroups(usize),
+  /// When IPv6 have more than one `::`
+  MultipleDoubleColon,
+  /// When strict IPv6 is followed by a colon
+  TrailingColon,
+  /// When strict IPv6 is followed by a hex digit or a dot
+  TrailingOctet(u8),
 }
 
 impl Display for IpAddrAtom {
@@ -42,6 +69,13 @@ impl Display for IpAddrAtom {
     match self {
       Self::NotAnOctet => write!(f, "IpAddr: NotAnOctet"),
       Self::LeadingZero => write!(f, "IpAddr: LeadingZero"),
+      Self::PrefixLen(len) => write!(f, "IpAddr: PrefixLen {}", len),
+      Self::NotAPort(port) => write!(f, "IpAddr: NotAPort {}", port),
+      Self::TooManyGroups => write!(f, "IpAddr: TooManyGroups"),
+      Self::TooFewGroups(len) => write!(f, "IpAddr: TooFewGroups {}", len),
+      Self::MultipleDoubleColon => write!(f, "IpAddr: MultipleDoubleColon"),
+      Self::TrailingColon => write!(f, "IpAddr: TrailingColon"),
+      Self::TrailingOctet(octet) => write!(f, "IpAddr: TrailingOctet {:#04x}", octet),
     }
   }
 }
@@ -180,280 +214,368 @@ where
     .parse(stream)
 }
 
-#[allow(rustdoc::private_intra_doc_links)]
-/// IPv6address =                            6( h16 ":" ) ls32
-///             /                       "::" 5( h16 ":" ) ls32
-///             / [               h16 ] "::" 4( h16 ":" ) ls32
-///             / [ *1( h16 ":" ) h16 ] "::" 3( h16 ":" ) ls32
-///             / [ *2( h16 ":" ) h16 ] "::" 2( h16 ":" ) ls32
-///             / [ *3( h16 ":" ) h16 ] "::"    h16 ":"   ls32
-///             / [ *4( h16 ":" ) h16 ] "::"              ls32
-///             / [ *5( h16 ":" ) h16 ] "::"    h16
-///             / [ *6( h16 ":" ) h16 ] "::"
-pub fn ipv6_address<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv6 address with an optional zone, the zone is kept as written, pct
+/// encoded in a reference
+#[derive(Clone, Copy, Eq, PartialEq, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ipv6Zoned<Span> {
+  /// ipv6
+  pub ipv6: Ipv6Addr,
+  /// Zone identifier without the `%` or `%25` delimiter
+  pub zone: Option<Span>,
+}
+
+impl<Span: AsRef<[u8]>> Ipv6Zoned<Span> {
+  /// Return the zone as scope id when it's numeric, an interface name need
+  /// to be resolved by the caller
+  pub fn scope_id(&self) -> Option<u32> {
+    let zone = self.zone.as_ref()?.as_ref();
+    if zone.iter().all(u8::is_ascii_digit) {
+      std::str::from_utf8(zone).ok()?.parse().ok()
+    } else {
+      None
+    }
+  }
+}
+
+// unreserved = ALPHA / DIGIT / "-" / "." / "_" / "~"
+fn unreserved<Stream, Context>(stream: Stream) -> Parsed<(), Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  [
-    ipv6_address_0,
-    ipv6_address_1,
-    ipv6_address_2,
-    ipv6_address_3,
-    ipv6_address_4,
-    ipv6_address_5,
-    ipv6_address_6,
-    ipv6_address_7,
-    ipv6_address_8,
-  ]
-  .parse(stream)
+  octet
+    .filter(|octet| octet.is_ascii_alphanumeric() || b"-._~".contains(octet))
+    .drop()
+    .parse(stream)
 }
 
-fn ipv6_address_0<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+// pct-encoded = "%" HEXDIG HEXDIG
+fn pct_encoded<Stream, Context>(stream: Stream) -> Parsed<(), Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    h16_colon, h16_colon, h16_colon, h16_colon, h16_colon, h16_colon, ls32,
-  )
-    .map(|(a, b, c, d, e, f, (g, h))| Ipv6Addr::new(a, b, c, d, e, f, g, h))
-    .parse(stream)
+  let hexdig = || octet.filter(u8::is_ascii_hexdigit);
+  (is(b'%'), hexdig(), hexdig()).drop().parse(stream)
 }
 
-fn ipv6_address_1<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv6address [ "%" 1*unreserved ], the raw form like `fe80::1%eth0`
+pub fn ipv6_address_zoned<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Ipv6Zoned<<Stream as Streaming>::Span>, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    double_colon,
-    h16_colon,
-    h16_colon,
-    h16_colon,
-    h16_colon,
-    h16_colon,
-    ls32,
-  )
-    .map(|(_, b, c, d, e, f, (g, h))| Ipv6Addr::new(0, b, c, d, e, f, g, h))
+  let zone = unreserved
+    .fold_bounds(1.., || (), |(), ()| ())
+    .span()
+    .map(Success::into_stream);
+  (ipv6_address, is(b'%').drop_and(zone).opt())
+    .map(|(ipv6, zone)| Ipv6Zoned { ipv6, zone })
     .parse(stream)
 }
 
-fn ipv6_address_2<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv6addrz reference = "[" IPv6address [ "%25" ZoneID ] "]"
+/// ZoneID = 1*( unreserved / pct-encoded )
+/// <https://www.rfc-editor.org/rfc/rfc6874>
+pub fn ipv6_reference_zoned<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Ipv6Zoned<<Stream as Streaming>::Span>, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
+  let zone = unreserved
+    .or(pct_encoded)
+    .fold_bounds(1.., || (), |(), ()| ())
+    .span()
+    .map(Success::into_stream);
   (
-    h16.opt(),
-    double_colon,
-    h16_colon,
-    h16_colon,
-    h16_colon,
-    h16_colon,
-    ls32,
+    is(b'['),
+    ipv6_address,
+    (is(b'%'), is(b'2'), is(b'5')).drop_and(zone).opt(),
+    is(b']'),
   )
-    .map(|(a, _, c, d, e, f, (g, h))| Ipv6Addr::new(a.unwrap_or(0), 0, c, d, e, f, g, h))
+    .map(|(_, ipv6, zone, _)| Ipv6Zoned { ipv6, zone })
     .parse(stream)
 }
 
-fn ipv6_address_3<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv6address / IPv4address, IPv6 is tried first
+pub fn ip_address<Stream, Context>(stream: Stream) -> Parsed<IpAddr, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    h16.opt(),
-    colon_h16.opt(),
-    double_colon,
-    h16_colon,
-    h16_colon,
-    h16_colon,
-    ls32,
-  )
-    .map(|(a, b, _, d, e, f, (g, h))| {
-      Ipv6Addr::new(a.unwrap_or(0), b.unwrap_or(0), 0, d, e, f, g, h)
-    })
+  ipv6_address
+    .map(IpAddr::V6)
+    .or(ipv4_address.map(IpAddr::V4))
     .parse(stream)
 }
 
-fn ipv6_address_4<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv6reference / IPv6address / IPv4address, like the host of an URI
+/// without reg-name
+pub fn ip_address_or_reference<Stream, Context>(stream: Stream) -> Parsed<IpAddr, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    double_colon,
-    h16_colon,
-    h16_colon,
-    ls32,
-  )
-    .map(|(a, b, c, _, e, f, (g, h))| {
-      Ipv6Addr::new(
-        a.unwrap_or(0),
-        b.unwrap_or(0),
-        c.unwrap_or(0),
-        0,
-        e,
-        f,
-        g,
-        h,
-      )
-    })
+  ipv6_reference
+    .map(|reference| IpAddr::V6(reference.ipv6))
+    .or(ip_address)
     .parse(stream)
 }
 
-fn ipv6_address_5<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+// prefix-length = 1*3DIGIT
+fn prefix_len<Stream, Context>(stream: Stream) -> Parsed<u8, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    double_colon,
-    h16_colon,
-    ls32,
-  )
-    .map(|(a, b, c, d, _, f, (g, h))| {
-      Ipv6Addr::new(
-        a.unwrap_or(0),
-        b.unwrap_or(0),
-        c.unwrap_or(0),
-        d.unwrap_or(0),
-        0,
-        f,
-        g,
-        h,
-      )
-    })
+  is(b'/')
+    .drop_and(uint_radix(1..3, Radix::DEC))
     .parse(stream)
 }
 
-fn ipv6_address_6<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv4address "/" prefix-length, prefix-length is at most 32
+pub fn ipv4_cidr<Stream, Context>(stream: Stream) -> Parsed<Ipv4Net, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    double_colon,
-    ls32,
-  )
-    .map(|(a, b, c, d, e, _, (g, h))| {
-      Ipv6Addr::new(
-        a.unwrap_or(0),
-        b.unwrap_or(0),
-        c.unwrap_or(0),
-        d.unwrap_or(0),
-        e.unwrap_or(0),
-        0,
-        g,
-        h,
-      )
+  (ipv4_address, prefix_len)
+    .try_map(|(addr, len)| {
+      Ipv4Net::new(addr, len).ok_or_else(|| Context::new(IpAddrAtom::PrefixLen(len)))
     })
     .parse(stream)
 }
 
-fn ipv6_address_7<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv6address "/" prefix-length, prefix-length is at most 128
+pub fn ipv6_cidr<Stream, Context>(stream: Stream) -> Parsed<Ipv6Net, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    double_colon,
-    h16,
-  )
-    .map(|(a, b, c, d, e, f, _, h)| {
-      Ipv6Addr::new(
-        a.unwrap_or(0),
-        b.unwrap_or(0),
-        c.unwrap_or(0),
-        d.unwrap_or(0),
-        e.unwrap_or(0),
-        f.unwrap_or(0),
-        0,
-        h,
-      )
+  (ipv6_address, prefix_len)
+    .try_map(|(addr, len)| {
+      Ipv6Net::new(addr, len).ok_or_else(|| Context::new(IpAddrAtom::PrefixLen(len)))
     })
     .parse(stream)
 }
 
-fn ipv6_address_8<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+/// IPv6 CIDR / IPv4 CIDR, IPv6 is tried first
+pub fn ip_cidr<Stream, Context>(stream: Stream) -> Parsed<IpNet, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  (
-    h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    colon_h16.opt(),
-    double_colon,
-  )
-    .map(|(a, b, c, d, e, f, g, _)| {
-      Ipv6Addr::new(
-        a.unwrap_or(0),
-        b.unwrap_or(0),
-        c.unwrap_or(0),
-        d.unwrap_or(0),
-        e.unwrap_or(0),
-        f.unwrap_or(0),
-        g.unwrap_or(0),
-        0,
-      )
-    })
+  ipv6_cidr
+    .map(IpNet::V6)
+    .or(ipv4_cidr.map(IpNet::V4))
     .parse(stream)
 }
 
-// custom rule
-// h16colon = h16 ":"
-fn h16_colon<Stream, Context>(stream: Stream) -> Parsed<u16, Stream, Context>
+// port = 1*5DIGIT
+fn port<Stream, Context>(stream: Stream) -> Parsed<u16, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  h16.and_drop(is(b':')).parse(stream)
+  is(b':')
+    .drop_and(to_digit.fold_bounds(1..5, || 0u32, |acc, digit| acc * 10 + u32::from(digit)))
+    .try_map(|port| u16::try_from(port).map_err(|_| Context::new(IpAddrAtom::NotAPort(port))))
+    .parse(stream)
 }
 
-fn colon_h16<Stream, Context>(stream: Stream) -> Parsed<u16, Stream, Context>
+/// IPv4address ":" port
+pub fn socket_addr_v4<Stream, Context>(stream: Stream) -> Parsed<SocketAddrV4, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  is(b':').drop_and(h16).parse(stream)
+  (ipv4_address, port)
+    .map(|(ip, port)| SocketAddrV4::new(ip, port))
+    .parse(stream)
 }
 
-fn double_colon<Stream, Context>(stream: Stream) -> Parsed<(), Stream, Context>
+/// IPv6reference ":" port, flow info and scope id are zero
+pub fn socket_addr_v6<Stream, Context>(stream: Stream) -> Parsed<SocketAddrV6, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  is(b':').and(is(b':')).drop().parse(stream)
+  (ipv6_reference, port)
+    .map(|(reference, port)| SocketAddrV6::new(reference.ipv6, port, 0, 0))
+    .parse(stream)
 }
 
-// // ls32 = ( h16 ":" h16 ) / IPv4address
-fn ls32<Stream, Context>(stream: Stream) -> Parsed<(u16, u16), Stream, Context>
+/// IPv6 socket address / IPv4 socket address
+pub fn socket_addr<Stream, Context>(stream: Stream) -> Parsed<SocketAddr, Stream, Context>
 where
   (): IpAddrParse<Stream, Context>,
 {
-  h16
-    .and(colon_h16)
-    .or(ipv4_address.map(|ipv4| {
-      let [a, b, c, d] = ipv4.octets();
-      (
-        (a as u16) << 8u16 | (b as u16),
-        (c as u16) << 8u16 | (d as u16),
-      )
-    }))
+  socket_addr_v6
+    .map(SocketAddr::V6)
+    .or(socket_addr_v4.map(SocketAddr::V4))
     .parse(stream)
 }
 
+/// IPv6address =                            6( h16 ":" ) ls32
+///             /                       "::" 5( h16 ":" ) ls32
+///             / [               h16 ] "::" 4( h16 ":" ) ls32
+///             / [ *1( h16 ":" ) h16 ] "::" 3( h16 ":" ) ls32
+///             / [ *2( h16 ":" ) h16 ] "::" 2( h16 ":" ) ls32
+///             / [ *3( h16 ":" ) h16 ] "::"    h16 ":"   ls32
+///             / [ *4( h16 ":" ) h16 ] "::"              ls32
+///             / [ *5( h16 ":" ) h16 ] "::"    h16
+///             / [ *6( h16 ":" ) h16 ] "::"
+///
+/// Parsed in a single pass, groups are collected until a separator is not
+/// followed by a group, the zeros of `::` are filled at the end
+pub fn ipv6_address<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+where
+  (): IpAddrParse<Stream, Context>,
+{
+  let mut groups = [0u16; 8];
+  let mut len = 0;
+  // index of the groups following `::`
+  let mut double = None;
+
+  let mut stream = match split_colon(stream.clone()).and_then(split_colon) {
+    Some(next) => {
+      double = Some(0);
+      next
+    }
+    None => stream,
+  };
+
+  let mut after_group = false;
+  loop {
+    let group = if after_group {
+      let Some(next) = split_colon(stream.clone()) else {
+        break;
+      };
+      if let Some(next) = split_colon(next.clone()) {
+        if double.is_some() {
+          return Parsed::Failure(Context::new(IpAddrAtom::MultipleDoubleColon));
+        }
+        if len == 8 {
+          return Parsed::Failure(Context::new(IpAddrAtom::TooManyGroups));
+        }
+        double = Some(len);
+        stream = next;
+        after_group = false;
+        continue;
+      }
+      match ipv6_group.parse(next) {
+        Parsed::Failure(_) => break,
+        parsed => parsed,
+      }
+    } else {
+      match ipv6_group.parse(stream.clone()) {
+        Parsed::Failure(_) if double.is_some() => break,
+        parsed => parsed,
+      }
+    };
+    let Success {
+      token: group,
+      stream: next,
+    } = group?;
+
+    // `::` stand for at least one group
+    let max = if double.is_some() { 7 } else { 8 };
+    let ls32 = match group {
+      Ipv6Group::H16(h16) => {
+        if len == max {
+          return Parsed::Failure(Context::new(IpAddrAtom::TooManyGroups));
+        }
+        groups[len] = h16;
+        len += 1;
+        false
+      }
+      Ipv6Group::Ipv4(ipv4) => {
+        if len + 2 > max {
+          return Parsed::Failure(Context::new(IpAddrAtom::TooManyGroups));
+        }
+        let [a, b, c, d] = ipv4.octets();
+        groups[len] = u16::from_be_bytes([a, b]);
+        groups[len + 1] = u16::from_be_bytes([c, d]);
+        len += 2;
+        true
+      }
+    };
+    stream = next;
+    after_group = true;
+    // an IPv4 address can only end the address
+    if ls32 {
+      break;
+    }
+  }
+
+  match double {
+    Some(index) => {
+      let tail = len - index;
+      groups.copy_within(index..len, 8 - tail);
+      groups[index..8 - tail].fill(0);
+    }
+    None if len < 8 => {
+      return Parsed::Failure(Context::new(IpAddrAtom::TooFewGroups(len)));
+    }
+    None => {}
+  }
+
+  Parsed::Success {
+    token: Ipv6Addr::from(groups),
+    stream,
+  }
+}
+
+/// IPv6address not followed by an octet `Ipv6Addr::from_str` would read as
+/// part of the address, like `::12345` or `1::2:`. On a whole input it
+/// accept exactly what std accept
+pub fn ipv6_address_strict<Stream, Context>(stream: Stream) -> Parsed<Ipv6Addr, Stream, Context>
+where
+  (): IpAddrParse<Stream, Context>,
+{
+  let Success { token, stream } = ipv6_address.parse(stream)?;
+  if let Split::Success { item, .. } = stream.clone().split_first() {
+    let octet = item.into();
+    if octet == b':' {
+      return Parsed::Failure(Context::new(IpAddrAtom::TrailingColon));
+    }
+    if octet == b'.' || octet.is_ascii_hexdigit() {
+      return Parsed::Failure(Context::new(IpAddrAtom::TrailingOctet(octet)));
+    }
+  }
+  Parsed::Success { token, stream }
+}
+
+enum Ipv6Group {
+  H16(u16),
+  Ipv4(Ipv4Addr),
+}
+
+// Stream after the next octet if it's a colon, peeking this way doesn't
+// build a context for every separator
+fn split_colon<Stream>(stream: Stream) -> Option<Stream>
+where
+  Stream: Streaming,
+  <Stream as Streaming>::Item: Into<u8> + Clone,
+{
+  match stream.split_first() {
+    Split::Success { item, stream } if item.clone().into() == b':' => Some(stream),
+    _ => None,
+  }
+}
+
+// h16 / ls32, an IPv4 address is only tried when h16 is followed by a dot
+fn ipv6_group<Stream, Context>(stream: Stream) -> Parsed<Ipv6Group, Stream, Context>
+where
+  (): IpAddrParse<Stream, Context>,
+{
+  let Success {
+    token,
+    stream: next,
+  } = h16.parse(stream.clone())?;
+  match next.clone().split_first() {
+    Split::Success { item, .. } if item.clone().into() == b'.' => {
+      ipv4_address.map(Ipv6Group::Ipv4).parse(stream)
+    }
+    _ => Parsed::Success {
+      token: Ipv6Group::H16(token),
+      stream: next,
+    },
+  }
+}
+
 // h16 = 1*4HEXDIG
 fn h16<Stream, Context>(stream: Stream) -> Parsed<u16, Stream, Context>
 where
@@ -590,6 +712,15 @@ mod tests {
       "2001:db8:aaaa:bbbb:cccc:dddd:eeee:aaaa",
       "2001:db8:aaaa:bbbb:cccc:dddd:eeee:AAAA",
       "2001:db8:aaaa:bbbb:cccc:dddd:eeee:AaAa",
+      "1:2:3:4:5:6:7::",
+      "::2:3:4:5:6:7:8",
+      "1::8",
+      "1:2:3:4:5:6:7",
+      "1::2::3",
+      "1:2:3:4:5:6:7:8:9",
+      "::1:2:3:4:5:6:7:8",
+      "1:2:3:4:5:6:7:1.2.3.4",
+      "",
     ];
 
     for &ipv6 in ipv6s.iter() {
@@ -610,5 +741,222 @@ mod tests {
         }
       }
     }
+
+    let atoms = [
+      ("1:2:3:4:5:6:7", IpAddrAtom::TooFewGroups(7)),
+      ("1::2::3", IpAddrAtom::MultipleDoubleColon),
+      ("1:2:3:4:5:6:7:8:9", IpAddrAtom::TooManyGroups),
+      ("1:2:3:4:5:6:7:8::", IpAddrAtom::TooManyGroups),
+      ("1:2:3::4:5:6:7:8", IpAddrAtom::TooManyGroups),
+    ];
+    for (ipv6, atom) in atoms {
+      let context = ipv6_address::<_, HandleAtom<_>>(ipv6.as_bytes()).unwrap_context();
+      assert_eq!(context.last(), &FromAtom::IpAddr(atom), "{}", ipv6);
+    }
+  }
+
+  #[test]
+  fn test_ip_address() {
+    let ips = [
+      "",
+      "127.0.0.1",
+      "::1",
+      "::FFFF:129.144.52.38",
+      "2001:db8::1",
+      "256.0.0.1",
+      "[::1]",
+    ];
+
+    for ip in ips.iter() {
+      println!("test: {}", ip);
+      match (
+        ip_address::<_, HandleAtom<_>>(ip.as_bytes()),
+        IpAddr::from_str(ip),
+      ) {
+        (Parsed::Success { token, stream }, Ok(expected)) => {
+          assert_eq!((token, stream), (expected, b"".as_slice()))
+        }
+        (Parsed::Failure(_) | Parsed::Error(_), Err(_)) => {} // no need compare error
+        (Parsed::Success { token, stream }, Err(e)) => {
+          panic!("We ok on {:?} {:?} but std error on {}", token, stream, e)
+        }
+        (Parsed::Failure(failure) | Parsed::Error(failure), Ok(expected)) => {
+          panic!("We error on {:?} but std ok on {}", failure, expected)
+        }
+      }
+    }
+
+    let references = [
+      ("[2001:db8::1]:80", "2001:db8::1", ":80"),
+      ("10.0.0.1:80", "10.0.0.1", ":80"),
+      ("::1", "::1", ""),
+    ];
+    for (reference, expected, rest) in references {
+      let Success { token, stream } =
+        ip_address_or_reference::<_, HandleAtom<_>>(reference.as_bytes()).unwrap();
+      assert_eq!(token, IpAddr::from_str(expected).unwrap());
+      assert_eq!(stream, rest.as_bytes());
+    }
+  }
+
+  #[test]
+  fn test_ip_cidr() {
+    let Success { token, stream } =
+      ipv4_cidr::<_, HandleAtom<_>>(&b"192.168.4.17/22 "[..]).unwrap();
+    assert_eq!(stream, b" ");
+    assert_eq!(token.to_string(), "192.168.4.17/22");
+    assert_eq!(token.network(), Ipv4Addr::new(192, 168, 4, 0));
+    assert_eq!(token.broadcast(), Ipv4Addr::new(192, 168, 7, 255));
+    assert!(token.contains(Ipv4Addr::new(192, 168, 6, 1)));
+    assert!(!token.contains(Ipv4Addr::new(192, 168, 8, 1)));
+
+    let Success { token, .. } = ip_cidr::<_, HandleAtom<_>>(&b"2001:db8::1/32"[..]).unwrap();
+    assert_eq!(token.to_string(), "2001:db8::1/32");
+    assert_eq!(token.network(), IpAddr::from_str("2001:db8::").unwrap());
+    assert_eq!(
+      token.broadcast(),
+      IpAddr::from_str("2001:db8:ffff:ffff:ffff:ffff:ffff:ffff").unwrap()
+    );
+    assert!(token.contains(IpAddr::from_str("2001:db8:1::1").unwrap()));
+    assert!(!token.contains(IpAddr::from_str("10.0.0.1").unwrap()));
+
+    let Success { token, .. } = ip_cidr::<_, HandleAtom<_>>(&b"0.0.0.0/0"[..]).unwrap();
+    assert_eq!(token.prefix_len(), 0);
+    assert_eq!(
+      token.broadcast(),
+      IpAddr::from_str("255.255.255.255").unwrap()
+    );
+
+    let context = ipv4_cidr::<_, HandleAtom<_>>(&b"10.0.0.0/33"[..]).unwrap_context();
+    assert_eq!(context.last(), &FromAtom::IpAddr(IpAddrAtom::PrefixLen(33)));
+    let context = ipv6_cidr::<_, HandleAtom<_>>(&b"::/129"[..]).unwrap_context();
+    assert_eq!(
+      context.last(),
+      &FromAtom::IpAddr(IpAddrAtom::PrefixLen(129))
+    );
+    assert!(!ip_cidr::<_, HandleAtom<_>>(&b"10.0.0.0"[..]).is_success());
+  }
+
+  #[test]
+  fn test_socket_addr() {
+    let addrs = [
+      "127.0.0.1:80",
+      "[::1]:65535",
+      "[2001:db8::1]:0",
+      "::1:80",
+      "127.0.0.1",
+      "127.0.0.1:",
+    ];
+
+    for addr in addrs {
+      match (
+        socket_addr::<_, HandleAtom<_>>(addr.as_bytes()),
+        SocketAddr::from_str(addr),
+      ) {
+        (Parsed::Success { token, stream }, Ok(expected)) => {
+          assert_eq!((token, stream), (expected, b"".as_slice()))
+        }
+        (Parsed::Failure(_) | Parsed::Error(_), Err(_)) => {}
+        (Parsed::Success { token, stream }, Err(e)) => {
+          panic!("We ok on {:?} {:?} but std error on {}", token, stream, e)
+        }
+        (Parsed::Failure(failure) | Parsed::Error(failure), Ok(expected)) => {
+          panic!("We error on {:?} but std ok on {}", failure, expected)
+        }
+      }
+    }
+
+    let Success { token, stream } =
+      socket_addr_v4::<_, HandleAtom<_>>(&b"10.0.0.1:8080 rest"[..]).unwrap();
+    assert_eq!(token, SocketAddrV4::new(Ipv4Addr::new(10, 0, 0, 1), 8080));
+    assert_eq!(stream, b" rest");
+
+    let context = socket_addr_v6::<_, HandleAtom<_>>(&b"[::1]:65536"[..]).unwrap_context();
+    assert_eq!(
+      context.last(),
+      &FromAtom::IpAddr(IpAddrAtom::NotAPort(65536))
+    );
+  }
+
+  #[test]
+  fn test_ipv6_zoned() {
+    let fe80 = Ipv6Addr::new(0xFE80, 0, 0, 0, 0, 0, 0, 1);
+
+    let Success { token, stream } =
+      ipv6_address_zoned::<_, HandleAtom<_>>(&b"fe80::1%eth0 up"[..]).unwrap();
+    assert_eq!(token.ipv6, fe80);
+    assert_eq!(token.zone, Some(&b"eth0"[..]));
+    assert_eq!(token.scope_id(), None);
+    assert_eq!(stream, b" up");
+
+    let Success { token, .. } = ipv6_address_zoned::<_, HandleAtom<_>>(&b"fe80::1%3"[..]).unwrap();
+    assert_eq!(token.scope_id(), Some(3));
+
+    let Success { token, stream } =
+      ipv6_address_zoned::<_, HandleAtom<_>>(&b"fe80::1"[..]).unwrap();
+    assert_eq!(token.zone, None);
+    assert_eq!(stream, b"");
+
+    let Success { token, stream } =
+      ipv6_reference_zoned::<_, HandleAtom<_>>(&b"[fe80::1%25en1]:80"[..]).unwrap();
+    assert_eq!(token.ipv6, fe80);
+    assert_eq!(token.zone, Some(&b"en1"[..]));
+    assert_eq!(stream, b":80");
+
+    let Success { token, .. } =
+      ipv6_reference_zoned::<_, HandleAtom<_>>(&b"[fe80::1%25%41b]"[..]).unwrap();
+    assert_eq!(token.zone, Some(&b"%41b"[..]));
+
+    let Success { token, .. } = ipv6_reference_zoned::<_, HandleAtom<_>>(&b"[::1]"[..]).unwrap();
+    assert_eq!(token.zone, None);
+
+    // the delimiter must be pct encoded in a reference
+    assert!(!ipv6_reference_zoned::<_, HandleAtom<_>>(&b"[fe80::1%eth0]"[..]).is_success());
+  }
+
+  #[test]
+  fn test_ipv6_address_strict() {
+    let tokens = [
+      "0", "1", "ff", "ABCD", "12345", "0000", "1.2.3.4", "01.2.3.4", "1.2.3.04", "1.2.3", "::",
+      ":", ":", ":", ".",
+    ];
+    // differential against std on random concatenations of tokens
+    let mut seed = 0x2545_F491_u64;
+    let mut xorshift = || {
+      seed ^= seed << 13u32;
+      seed ^= seed >> 7u32;
+      seed ^= seed << 17u32;
+      seed
+    };
+    for _ in 0..100_000u32 {
+      let ipv6: String = (0..xorshift() % 16)
+        .map(|_| tokens[(xorshift() % tokens.len() as u64) as usize])
+        .collect();
+
+      let ours = match ipv6_address_strict::<_, HandleAtom<_>>(ipv6.as_bytes()) {
+        Parsed::Success { token, stream: b"" } => Some(token),
+        _ => None,
+      };
+      assert_eq!(ours, Ipv6Addr::from_str(&ipv6).ok(), "{}", ipv6);
+    }
+
+    let atoms = [
+      ("::12345", IpAddrAtom::TrailingOctet(b'5')),
+      ("::1.2.3.04", IpAddrAtom::TrailingOctet(b'4')),
+      ("::1.2.3", IpAddrAtom::TrailingOctet(b'1')),
+      ("1::2:", IpAddrAtom::TrailingColon),
+      (":::", IpAddrAtom::TrailingColon),
+    ];
+    for (ipv6, atom) in atoms {
+      // the lax parser accept a prefix
+      assert!(ipv6_address::<_, HandleAtom<_>>(ipv6.as_bytes()).is_success());
+      let context = ipv6_address_strict::<_, HandleAtom<_>>(ipv6.as_bytes()).unwrap_context();
+      assert_eq!(context.last(), &FromAtom::IpAddr(atom), "{}", ipv6);
+    }
+
+    let Success { token, stream } =
+      ipv6_address_strict::<_, HandleAtom<_>>(&b"fe80::1]:80"[..]).unwrap();
+    assert_eq!(token, Ipv6Addr::new(0xFE80, 0, 0, 0, 0, 0, 0, 1));
+    assert_eq!(stream, b"]:80");
   }
 }
@@ -0,0 +1,480 @@
+//! Classify IP addresses and networks against a built-in table of special
+//! purpose prefixes
+//! <https://www.iana.org/assignments/iana-ipv4-special-registry>
+//! <https://www.iana.org/assignments/iana-ipv6-special-registry>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
+/// Class of an address
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IpScope {
+  /// 0.0.0.0/8 or ::
+  Unspecified,
+  /// 127.0.0.0/8 or ::1
+  Loopback,
+  /// RFC 1918 private networks or fc00::/7 unique local addresses
+  Private,
+  /// 100.64.0.0/10 shared address space of carrier grade NAT
+  Cgnat,
+  /// 169.254.0.0/16 or fe80::/10
+  LinkLocal,
+  /// 224.0.0.0/4 or ff00::/8
+  Multicast,
+  /// Networks reserved for documentation
+  Documentation,
+  /// Other special purpose networks that are not globally routable
+  Reserved,
+  /// Anything else
+  Global,
+}
+
+/// IPv4 network, an address and a prefix length
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ipv4Net {
+  addr: Ipv4Addr,
+  prefix_len: u8,
+}
+
+impl Ipv4Net {
+  /// Return Ipv4Net, None if prefix_len is greater than 32
+  pub const fn new(addr: Ipv4Addr, prefix_len: u8) -> Option<Self> {
+    if prefix_len <= 32 {
+      Some(Self { addr, prefix_len })
+    } else {
+      None
+    }
+  }
+
+  /// Return the address
+  pub const fn addr(&self) -> Ipv4Addr {
+    self.addr
+  }
+
+  /// Return the prefix length
+  pub const fn prefix_len(&self) -> u8 {
+    self.prefix_len
+  }
+
+  const fn mask(&self) -> u32 {
+    match self.prefix_len {
+      0 => 0,
+      len => u32::MAX << (32 - len),
+    }
+  }
+
+  /// Return true if addr is in the network
+  pub const fn contains(&self, addr: Ipv4Addr) -> bool {
+    (self.addr.to_bits() ^ addr.to_bits()) & self.mask() == 0
+  }
+
+  /// Return the first address of the network
+  pub const fn network(&self) -> Ipv4Addr {
+    Ipv4Addr::from_bits(self.addr.to_bits() & self.mask())
+  }
+
+  /// Return the last address of the network
+  pub const fn broadcast(&self) -> Ipv4Addr {
+    Ipv4Addr::from_bits(self.addr.to_bits() | !self.mask())
+  }
+
+  /// Return the scope of the special purpose network containing the whole
+  /// network, or [IpScope::Global]
+  pub fn scope(&self) -> IpScope {
+    IPV4_SPECIAL
+      .iter()
+      .find(|(net, _)| net.prefix_len <= self.prefix_len && net.contains(self.addr))
+      .map_or(IpScope::Global, |(_, scope)| *scope)
+  }
+}
+
+impl Display for Ipv4Net {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}/{}", self.addr, self.prefix_len)
+  }
+}
+
+/// IPv6 network, an address and a prefix length
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ipv6Net {
+  addr: Ipv6Addr,
+  prefix_len: u8,
+}
+
+impl Ipv6Net {
+  /// Return Ipv6Net, None if prefix_len is greater than 128
+  pub const fn new(addr: Ipv6Addr, prefix_len: u8) -> Option<Self> {
+    if prefix_len <= 128 {
+      Some(Self { addr, prefix_len })
+    } else {
+      None
+    }
+  }
+
+  /// Return the address
+  pub const fn addr(&self) -> Ipv6Addr {
+    self.addr
+  }
+
+  /// Return the prefix length
+  pub const fn prefix_len(&self) -> u8 {
+    self.prefix_len
+  }
+
+  const fn mask(&self) -> u128 {
+    match self.prefix_len {
+      0 => 0,
+      len => u128::MAX << (128 - len),
+    }
+  }
+
+  /// Return true if addr is in the network
+  pub const fn contains(&self, addr: Ipv6Addr) -> bool {
+    (self.addr.to_bits() ^ addr.to_bits()) & self.mask() == 0
+  }
+
+  /// Return the first address of the network
+  pub const fn network(&self) -> Ipv6Addr {
+    Ipv6Addr::from_bits(self.addr.to_bits() & self.mask())
+  }
+
+  /// Return the last address of the network, IPv6 has no broadcast but it's
+  /// computed the same way
+  pub const fn broadcast(&self) -> Ipv6Addr {
+    Ipv6Addr::from_bits(self.addr.to_bits() | !self.mask())
+  }
+
+  /// Return the scope of the special purpose network containing the whole
+  /// network, or [IpScope::Global]
+  pub fn scope(&self) -> IpScope {
+    IPV6_SPECIAL
+      .iter()
+      .find(|(net, _)| net.prefix_len <= self.prefix_len && net.contains(self.addr))
+      .map_or(IpScope::Global, |(_, scope)| *scope)
+  }
+}
+
+impl Display for Ipv6Net {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}/{}", self.addr, self.prefix_len)
+  }
+}
+
+/// IPv4 or IPv6 network
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IpNet {
+  /// IPv4 network
+  V4(Ipv4Net),
+  /// IPv6 network
+  V6(Ipv6Net),
+}
+
+impl IpNet {
+  /// Return the address
+  pub const fn addr(&self) -> IpAddr {
+    match self {
+      Self::V4(net) => IpAddr::V4(net.addr()),
+      Self::V6(net) => IpAddr::V6(net.addr()),
+    }
+  }
+
+  /// Return the prefix length
+  pub const fn prefix_len(&self) -> u8 {
+    match self {
+      Self::V4(net) => net.prefix_len(),
+      Self::V6(net) => net.prefix_len(),
+    }
+  }
+
+  /// Return true if addr is in the network, always false if the version
+  /// differ
+  pub const fn contains(&self, addr: IpAddr) -> bool {
+    match (self, addr) {
+      (Self::V4(net), IpAddr::V4(addr)) => net.contains(addr),
+      (Self::V6(net), IpAddr::V6(addr)) => net.contains(addr),
+      _ => false,
+    }
+  }
+
+  /// Return the first address of the network
+  pub const fn network(&self) -> IpAddr {
+    match self {
+      Self::V4(net) => IpAddr::V4(net.network()),
+      Self::V6(net) => IpAddr::V6(net.network()),
+    }
+  }
+
+  /// Return the last address of the network
+  pub const fn broadcast(&self) -> IpAddr {
+    match self {
+      Self::V4(net) => IpAddr::V4(net.broadcast()),
+      Self::V6(net) => IpAddr::V6(net.broadcast()),
+    }
+  }
+}
+
+impl From<Ipv4Net> for IpNet {
+  fn from(net: Ipv4Net) -> Self {
+    Self::V4(net)
+  }
+}
+
+impl From<Ipv6Net> for IpNet {
+  fn from(net: Ipv6Net) -> Self {
+    Self::V6(net)
+  }
+}
+
+impl Display for IpNet {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Self::V4(net) => net.fmt(f),
+      Self::V6(net) => net.fmt(f),
+    }
+  }
+}
+
+const fn v4(a: u8, b: u8, c: u8, d: u8, prefix_len: u8, scope: IpScope) -> (Ipv4Net, IpScope) {
+  (
+    Ipv4Net {
+      addr: Ipv4Addr::new(a, b, c, d),
+      prefix_len,
+    },
+    scope,
+  )
+}
+
+const fn v6(segment: u16, second: u16, prefix_len: u8, scope: IpScope) -> (Ipv6Net, IpScope) {
+  (
+    Ipv6Net {
+      addr: Ipv6Addr::new(segment, second, 0, 0, 0, 0, 0, 0),
+      prefix_len,
+    },
+    scope,
+  )
+}
+
+/// Special purpose IPv4 networks
+pub const IPV4_SPECIAL: &[(Ipv4Net, IpScope)] = &[
+  v4(0, 0, 0, 0, 8, IpScope::Unspecified),
+  v4(10, 0, 0, 0, 8, IpScope::Private),
+  v4(100, 64, 0, 0, 10, IpScope::Cgnat),
+  v4(127, 0, 0, 0, 8, IpScope::Loopback),
+  v4(169, 254, 0, 0, 16, IpScope::LinkLocal),
+  v4(172, 16, 0, 0, 12, IpScope::Private),
+  v4(192, 0, 0, 0, 24, IpScope::Reserved),
+  v4(192, 0, 2, 0, 24, IpScope::Documentation),
+  v4(192, 168, 0, 0, 16, IpScope::Private),
+  v4(198, 18, 0, 0, 15, IpScope::Reserved),
+  v4(198, 51, 100, 0, 24, IpScope::Documentation),
+  v4(203, 0, 113, 0, 24, IpScope::Documentation),
+  v4(224, 0, 0, 0, 4, IpScope::Multicast),
+  v4(240, 0, 0, 0, 4, IpScope::Reserved),
+];
+
+/// Special purpose IPv6 networks
+pub const IPV6_SPECIAL: &[(Ipv6Net, IpScope)] = &[
+  (
+    Ipv6Net {
+      addr: Ipv6Addr::UNSPECIFIED,
+      prefix_len: 128,
+    },
+    IpScope::Unspecified,
+  ),
+  (
+    Ipv6Net {
+      addr: Ipv6Addr::LOCALHOST,
+      prefix_len: 128,
+    },
+    IpScope::Loopback,
+  ),
+  (
+    Ipv6Net {
+      addr: Ipv6Addr::new(0, 0, 0, 0, 0, 0xFFFF, 0, 0),
+      prefix_len: 96,
+    },
+    IpScope::Reserved,
+  ),
+  v6(0x0100, 0, 64, IpScope::Reserved),
+  v6(0x2001, 0x0DB8, 32, IpScope::Documentation),
+  v6(0x3FFF, 0, 20, IpScope::Documentation),
+  v6(0xFC00, 0, 7, IpScope::Private),
+  v6(0xFE80, 0, 10, IpScope::LinkLocal),
+  v6(0xFEC0, 0, 10, IpScope::Reserved),
+  v6(0xFF00, 0, 8, IpScope::Multicast),
+];
+
+/// Classification of addresses and networks
+pub trait IpClassify {
+  /// Return the scope
+  fn ip_scope(&self) -> IpScope;
+
+  /// Return true for RFC 1918 private networks and IPv6 unique local
+  /// addresses
+  fn is_private_scope(&self) -> bool {
+    self.ip_scope() == IpScope::Private
+  }
+
+  /// Return true for link-local addresses
+  fn is_link_local_scope(&self) -> bool {
+    self.ip_scope() == IpScope::LinkLocal
+  }
+
+  /// Return true for loopback addresses
+  fn is_loopback_scope(&self) -> bool {
+    self.ip_scope() == IpScope::Loopback
+  }
+
+  /// Return true for multicast addresses
+  fn is_multicast_scope(&self) -> bool {
+    self.ip_scope() == IpScope::Multicast
+  }
+
+  /// Return true for documentation addresses
+  fn is_documentation_scope(&self) -> bool {
+    self.ip_scope() == IpScope::Documentation
+  }
+
+  /// Return true for the carrier grade NAT shared address space
+  fn is_cgnat(&self) -> bool {
+    self.ip_scope() == IpScope::Cgnat
+  }
+
+  /// Return true for addresses that should never be seen as source on the
+  /// public internet, every scope but [IpScope::Global]
+  fn is_martian(&self) -> bool {
+    self.ip_scope() != IpScope::Global
+  }
+}
+
+impl IpClassify for Ipv4Net {
+  fn ip_scope(&self) -> IpScope {
+    self.scope()
+  }
+}
+
+impl IpClassify for Ipv6Net {
+  fn ip_scope(&self) -> IpScope {
+    self.scope()
+  }
+}
+
+impl IpClassify for IpNet {
+  fn ip_scope(&self) -> IpScope {
+    match self {
+      Self::V4(net) => net.scope(),
+      Self::V6(net) => net.scope(),
+    }
+  }
+}
+
+impl IpClassify for Ipv4Addr {
+  fn ip_scope(&self) -> IpScope {
+    Ipv4Net {
+      addr: *self,
+      prefix_len: 32,
+    }
+    .scope()
+  }
+}
+
+impl IpClassify for Ipv6Addr {
+  fn ip_scope(&self) -> IpScope {
+    Ipv6Net {
+      addr: *self,
+      prefix_len: 128,
+    }
+    .scope()
+  }
+}
+
+impl IpClassify for IpAddr {
+  fn ip_scope(&self) -> IpScope {
+    match self {
+      IpAddr::V4(addr) => addr.ip_scope(),
+      IpAddr::V6(addr) => addr.ip_scope(),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+  };
+
+  use super::{
+    IpClassify,
+    IpScope,
+    Ipv4Net,
+    Ipv6Net,
+  };
+
+  #[test]
+  fn ip_scope() {
+    let tests = [
+      ("10.1.2.3", IpScope::Private),
+      ("172.31.255.255", IpScope::Private),
+      ("172.32.0.1", IpScope::Global),
+      ("100.100.0.1", IpScope::Cgnat),
+      ("127.0.0.1", IpScope::Loopback),
+      ("169.254.1.1", IpScope::LinkLocal),
+      ("239.255.255.250", IpScope::Multicast),
+      ("198.51.100.7", IpScope::Documentation),
+      ("255.255.255.255", IpScope::Reserved),
+      ("8.8.8.8", IpScope::Global),
+      ("::", IpScope::Unspecified),
+      ("::1", IpScope::Loopback),
+      ("fd12:3456::1", IpScope::Private),
+      ("fe80::1", IpScope::LinkLocal),
+      ("ff02::1", IpScope::Multicast),
+      ("2001:db8::1", IpScope::Documentation),
+      ("::ffff:10.0.0.1", IpScope::Reserved),
+      ("2606:4700::1111", IpScope::Global),
+    ];
+
+    for (addr, scope) in tests {
+      assert_eq!(
+        addr.parse::<IpAddr>().unwrap().ip_scope(),
+        scope,
+        "{}",
+        addr
+      );
+    }
+
+    assert!(Ipv4Addr::new(192, 168, 1, 1).is_private_scope());
+    assert!(!Ipv4Addr::new(1, 1, 1, 1).is_martian());
+
+    let net = Ipv4Net::new(Ipv4Addr::new(192, 168, 4, 0), 24).unwrap();
+    assert_eq!(net.to_string(), "192.168.4.0/24");
+    assert!(net.contains(Ipv4Addr::new(192, 168, 4, 200)));
+    assert!(!net.contains(Ipv4Addr::new(192, 168, 5, 1)));
+    assert_eq!(net.ip_scope(), IpScope::Private);
+    assert_eq!(
+      Ipv4Net::new(Ipv4Addr::new(192, 0, 0, 0), 8)
+        .unwrap()
+        .ip_scope(),
+      IpScope::Global
+    );
+    assert_eq!(Ipv4Net::new(Ipv4Addr::UNSPECIFIED, 33), None);
+
+    let net = Ipv6Net::new(Ipv6Addr::new(0xFE80, 0, 0, 0, 0, 0, 0, 0), 64).unwrap();
+    assert!(net.is_link_local_scope());
+    assert!(net.contains(Ipv6Addr::new(0xFE80, 0, 0, 0, 1, 2, 3, 4)));
+  }
+}
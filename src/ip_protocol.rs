@@ -300,8 +300,236 @@ struct_variants! {
     WESP        => 0x8D,
     ///  Robust Header Compression
     ROHC        => 0x8E,
+    /// Ethernet, used by SRv6
+    ETHERNET    => 0x8F,
+    /// Aggregation and Fragmentation mode of IP-TFS
+    AGGFRAG     => 0x90,
+    /// Network Service Header
+    NSH         => 0x91,
+    /// Use for experimentation and testing
+    EXPERIMENT_1 => 0xFD,
+    /// Use for experimentation and testing
+    EXPERIMENT_2 => 0xFE,
+    /// Reserved
+    RESERVED    => 0xFF,
 }
 
+impl IPProtocol {
+  /// Return true for protocols carrying application data between ports
+  pub const fn is_transport(&self) -> bool {
+    matches!(
+      *self,
+      Self::TCP | Self::UDP | Self::DCCP | Self::SCTP | Self::UDP_LITE
+    )
+  }
+
+  /// Return true for the IPv6 extension headers, they are followed by
+  /// another next header
+  /// <https://www.iana.org/assignments/ipv6-parameters/ipv6-parameters.xhtml#extension-header>
+  pub const fn is_ipv6_extension(&self) -> bool {
+    matches!(
+      *self,
+      Self::HOPOPT
+        | Self::IPV6_ROUTE
+        | Self::IPV6_FRAG
+        | Self::ESP
+        | Self::AH
+        | Self::OPTS_6
+        | Self::MOBILITY_6
+        | Self::HIP
+        | Self::SHIM_6
+        | Self::EXPERIMENT_1
+        | Self::EXPERIMENT_2
+    )
+  }
+
+  /// Return the IANA keyword, None for unassigned numbers and assignments
+  /// without keyword like [IPProtocol::AHIP]
+  /// <https://www.iana.org/assignments/protocol-numbers/protocol-numbers.xhtml>
+  pub const fn keyword(&self) -> Option<&'static str> {
+    let keyword = match self.protocol {
+      0x00 => "HOPOPT",
+      0x01 => "ICMP",
+      0x02 => "IGMP",
+      0x03 => "GGP",
+      0x04 => "IPv4",
+      0x05 => "ST",
+      0x06 => "TCP",
+      0x07 => "CBT",
+      0x08 => "EGP",
+      0x09 => "IGP",
+      0x0A => "BBN-RCC-MON",
+      0x0B => "NVP-II",
+      0x0C => "PUP",
+      0x0D => "ARGUS",
+      0x0E => "EMCON",
+      0x0F => "XNET",
+      0x10 => "CHAOS",
+      0x11 => "UDP",
+      0x12 => "MUX",
+      0x13 => "DCN-MEAS",
+      0x14 => "HMP",
+      0x15 => "PRM",
+      0x16 => "XNS-IDP",
+      0x17 => "TRUNK-1",
+      0x18 => "TRUNK-2",
+      0x19 => "LEAF-1",
+      0x1A => "LEAF-2",
+      0x1B => "RDP",
+      0x1C => "IRTP",
+      0x1D => "ISO-TP4",
+      0x1E => "NETBLT",
+      0x1F => "MFE-NSP",
+      0x20 => "MERIT-INP",
+      0x21 => "DCCP",
+      0x22 => "3PC",
+      0x23 => "IDPR",
+      0x24 => "XTP",
+      0x25 => "DDP",
+      0x26 => "IDPR-CMTP",
+      0x27 => "TP++",
+      0x28 => "IL",
+      0x29 => "IPv6",
+      0x2A => "SDRP",
+      0x2B => "IPv6-Route",
+      0x2C => "IPv6-Frag",
+      0x2D => "IDRP",
+      0x2E => "RSVP",
+      0x2F => "GRE",
+      0x30 => "DSR",
+      0x31 => "BNA",
+      0x32 => "ESP",
+      0x33 => "AH",
+      0x34 => "I-NLSP",
+      0x35 => "SWIPE",
+      0x36 => "NARP",
+      0x37 => "Min-IPv4",
+      0x38 => "TLSP",
+      0x39 => "SKIP",
+      0x3A => "IPv6-ICMP",
+      0x3B => "IPv6-NoNxt",
+      0x3C => "IPv6-Opts",
+      0x3E => "CFTP",
+      0x40 => "SAT-EXPAK",
+      0x41 => "KRYPTOLAN",
+      0x42 => "RVD",
+      0x43 => "IPPC",
+      0x45 => "SAT-MON",
+      0x46 => "VISA",
+      0x47 => "IPCU",
+      0x48 => "CPNX",
+      0x49 => "CPHB",
+      0x4A => "WSN",
+      0x4B => "PVP",
+      0x4C => "BR-SAT-MON",
+      0x4D => "SUN-ND",
+      0x4E => "WB-MON",
+      0x4F => "WB-EXPAK",
+      0x50 => "ISO-IP",
+      0x51 => "VMTP",
+      0x52 => "SECURE-VMTP",
+      0x53 => "VINES",
+      0x54 => "TTP",
+      0x55 => "NSFNET-IGP",
+      0x56 => "DGP",
+      0x57 => "TCF",
+      0x58 => "EIGRP",
+      0x59 => "OSPFIGP",
+      0x5A => "Sprite-RPC",
+      0x5B => "LARP",
+      0x5C => "MTP",
+      0x5D => "AX.25",
+      0x5E => "IPIP",
+      0x5F => "MICP",
+      0x60 => "SCC-SP",
+      0x61 => "ETHERIP",
+      0x62 => "ENCAP",
+      0x64 => "GMTP",
+      0x65 => "IFMP",
+      0x66 => "PNNI",
+      0x67 => "PIM",
+      0x68 => "ARIS",
+      0x69 => "SCPS",
+      0x6A => "QNX",
+      0x6B => "A/N",
+      0x6C => "IPComp",
+      0x6D => "SNP",
+      0x6E => "Compaq-Peer",
+      0x6F => "IPX-in-IP",
+      0x70 => "VRRP",
+      0x71 => "PGM",
+      0x73 => "L2TP",
+      0x74 => "DDX",
+      0x75 => "IATP",
+      0x76 => "STP",
+      0x77 => "SRP",
+      0x78 => "UTI",
+      0x79 => "SMP",
+      0x7A => "SM",
+      0x7B => "PTP",
+      0x7C => "ISIS over IPv4",
+      0x7D => "FIRE",
+      0x7E => "CRTP",
+      0x7F => "CRUDP",
+      0x80 => "SSCOPMCE",
+      0x81 => "IPLT",
+      0x82 => "SPS",
+      0x83 => "PIPE",
+      0x84 => "SCTP",
+      0x85 => "FC",
+      0x86 => "RSVP-E2E-IGNORE",
+      0x87 => "Mobility Header",
+      0x88 => "UDPLite",
+      0x89 => "MPLS-in-IP",
+      0x8A => "manet",
+      0x8B => "HIP",
+      0x8C => "Shim6",
+      0x8D => "WESP",
+      0x8E => "ROHC",
+      0x8F => "Ethernet",
+      0x90 => "AGGFRAG",
+      0x91 => "NSH",
+      _ => return None,
+    };
+    Some(keyword)
+  }
+}
+
+/// Explicit Congestion Notification codepoint, the 2 low bits of the IPv4
+/// ToS or of the IPv6 traffic class
+/// <https://www.rfc-editor.org/rfc/rfc3168>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ecn {
+  /// Not ECN-Capable Transport
+  NotEct = 0b00,
+  /// ECN Capable Transport, ECT(1)
+  Ect1 = 0b01,
+  /// ECN Capable Transport, ECT(0)
+  Ect0 = 0b10,
+  /// Congestion Experienced
+  Ce = 0b11,
+}
+
+/// Only the 2 low bits are used
+impl From<u8> for Ecn {
+  fn from(bits: u8) -> Self {
+    match bits & 0b11 {
+      0b00 => Self::NotEct,
+      0b01 => Self::Ect1,
+      0b10 => Self::Ect0,
+      _ => Self::Ce,
+    }
+  }
+}
+
+impl From<Ecn> for u8 {
+  fn from(ecn: Ecn) -> Self {
+    ecn as u8
+  }
+}
+
+#[cfg_attr(not(any(feature = "ipv4", feature = "ipv6")), allow(dead_code))]
 pub(crate) fn ip_protocol<Stream, Context>(stream: Stream) -> Parsed<IPProtocol, Stream, Context>
 where
   Stream: Streaming,
@@ -337,5 +565,20 @@ mod tests {
         }
       );
     }
+
+    assert!(IPProtocol::UDP_LITE.is_transport());
+    assert!(!IPProtocol::ICMP_6.is_transport());
+    assert!(IPProtocol::IPV6_FRAG.is_ipv6_extension());
+    assert!(IPProtocol::EXPERIMENT_2.is_ipv6_extension());
+    assert!(!IPProtocol::TCP.is_ipv6_extension());
+
+    assert_eq!(IPProtocol::ICMP_6.keyword(), Some("IPv6-ICMP"));
+    assert_eq!(IPProtocol::NSH.keyword(), Some("NSH"));
+    assert_eq!(IPProtocol::AHIP.keyword(), None);
+    assert_eq!(IPProtocol::new(200).keyword(), None);
+    assert_eq!(IPProtocol::RESERVED.keyword(), None);
+
+    assert_eq!(IPProtocol::ETHERNET.to_string(), "Ethernet: 143");
+    assert_eq!(IPProtocol::new(146).to_string(), "Unknown: 146");
   }
 }
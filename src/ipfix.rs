@@ -0,0 +1,619 @@
+//! Handles parsing of IPFIX messages and decoding of their data records
+//! <https://www.rfc-editor.org/rfc/rfc7011>
+
+use std::{
+  collections::HashMap,
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
+use binator::{
+  base::{
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// UDP port of IPFIX
+pub const IPFIX_PORT: Port = Port(4739);
+
+/// Meta trait for ipfix combinator
+pub trait IpfixParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<IpfixAtom>;
+
+/// Atom produced by ipfix parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpfixAtom {
+  /// When the version is not 10
+  Version(u16),
+  /// When a message or set length is shorter than its header or than its
+  /// content
+  Length(u16),
+}
+
+impl Display for IpfixAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      IpfixAtom::Version(version) => write!(f, "Version: expected 10 found {}", version),
+      IpfixAtom::Length(length) => write!(f, "Length: length {} doesn't match content", length),
+    }
+  }
+}
+
+/// IPFIX message header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IpfixHeader {
+  /// Version, always 10
+  pub version: u16,
+  /// Length of the message, header included
+  pub length: u16,
+  /// Export time in seconds since the UNIX epoch
+  pub export_time: u32,
+  /// Sequence number of the data records
+  pub sequence: u32,
+  /// Observation domain id, templates are scoped by it
+  pub observation_domain: u32,
+}
+
+impl IpfixHeader {
+  /// Length of an IPFIX message header
+  pub const LEN: usize = 16;
+  /// IPFIX version
+  pub const VERSION: u16 = 10;
+}
+
+/// Field specifier of a template
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IpfixFieldSpecifier {
+  /// Information element id without the enterprise bit
+  pub element_id: IpfixElementId,
+  /// Length of the field, [IpfixFieldSpecifier::VARIABLE] for variable length
+  pub length: u16,
+  /// Enterprise number of enterprise specific elements
+  pub enterprise: Option<u32>,
+}
+
+impl IpfixFieldSpecifier {
+  /// Length of variable length fields
+  pub const VARIABLE: u16 = 0xFFFF;
+}
+
+/// Template or options template, the first scope_count fields of an options
+/// template are scope fields
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IpfixTemplate {
+  /// Template id, data sets use it as set id
+  pub id: u16,
+  /// Number of scope fields, 0 for templates
+  pub scope_count: u16,
+  /// Fields, empty for a template withdrawal
+  pub fields: Vec<IpfixFieldSpecifier>,
+}
+
+/// Set of an IPFIX message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IpfixSet<Span> {
+  /// Template set
+  Template(Vec<IpfixTemplate>),
+  /// Options template set
+  OptionsTemplate(Vec<IpfixTemplate>),
+  /// Data set, see [IpfixTemplateCache::records]. Ids below 256 are reserved
+  /// and kept as data sets too.
+  Data {
+    /// Template id of the records
+    set_id: u16,
+    /// Records and padding
+    records: Span,
+  },
+}
+
+impl<Span> IpfixSet<Span> {
+  /// Length of a set header
+  pub const HEADER_LEN: usize = 4;
+  /// Set id of options template sets
+  pub const OPTIONS_TEMPLATE: u16 = 3;
+  /// Set id of template sets
+  pub const TEMPLATE: u16 = 2;
+}
+
+/// IPFIX message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IpfixMessage<Span> {
+  /// Message header
+  pub header: IpfixHeader,
+  /// Sets
+  pub sets: Vec<IpfixSet<Span>>,
+}
+
+/// Parse an IPFIX message header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipfix_header<Stream, Context>(stream: Stream) -> Parsed<IpfixHeader, Stream, Context>
+where
+  (): IpfixParse<Stream, Context>,
+{
+  let Success {
+    token: (version, length, export_time, sequence, observation_domain),
+    stream,
+  } = (
+    u16_be.try_map(|version| {
+      if version == IpfixHeader::VERSION {
+        Ok(version)
+      } else {
+        Err(Context::new(IpfixAtom::Version(version)))
+      }
+    }),
+    u16_be.try_map(|length| {
+      if usize::from(length) >= IpfixHeader::LEN {
+        Ok(length)
+      } else {
+        Err(Context::new(IpfixAtom::Length(length)))
+      }
+    }),
+    u32_be,
+    u32_be,
+    u32_be,
+  )
+    .parse(stream)?;
+
+  Parsed::Success {
+    token: IpfixHeader {
+      version,
+      length,
+      export_time,
+      sequence,
+      observation_domain,
+    },
+    stream,
+  }
+}
+
+fn ipfix_field_specifier<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(IpfixFieldSpecifier, usize), Stream, Context>
+where
+  (): IpfixParse<Stream, Context>,
+{
+  let Success {
+    token: (element_id, length),
+    stream,
+  } = (u16_be, u16_be).parse(stream)?;
+
+  // the enterprise bit announce an enterprise number
+  let (enterprise, stream, consumed) = if element_id & 0x8000 != 0 {
+    let Success {
+      token: enterprise,
+      stream,
+    } = u32_be.parse(stream)?;
+    (Some(enterprise), stream, 8)
+  } else {
+    (None, stream, 4)
+  };
+
+  Parsed::Success {
+    token: (
+      IpfixFieldSpecifier {
+        element_id: IpfixElementId::new(element_id & 0x7FFF),
+        length,
+        enterprise,
+      },
+      consumed,
+    ),
+    stream,
+  }
+}
+
+fn ipfix_templates<Stream, Context>(
+  mut stream: Stream, length: u16, options: bool,
+) -> Parsed<Vec<IpfixTemplate>, Stream, Context>
+where
+  (): IpfixParse<Stream, Context>,
+{
+  let mut len = usize::from(length) - IpfixSet::<()>::HEADER_LEN;
+  let header_len = if options { 6 } else { 4 };
+  let mut templates = Vec::new();
+  // less than a template header left is padding
+  while len >= header_len {
+    let Success {
+      token: (id, field_count),
+      stream: next,
+    } = (u16_be, u16_be).parse(stream)?;
+    let Success {
+      token: scope_count,
+      stream: next,
+    } = if options {
+      u16_be.parse(next)?
+    } else {
+      Success {
+        token: 0,
+        stream: next,
+      }
+    };
+    len -= header_len;
+    stream = next;
+
+    let mut fields = Vec::with_capacity(usize::from(field_count));
+    for _ in 0..field_count {
+      let Success {
+        token: (field, consumed),
+        stream: next,
+      } = ipfix_field_specifier.parse(stream)?;
+      let Some(rest) = len.checked_sub(consumed) else {
+        return Parsed::Failure(Context::new(IpfixAtom::Length(length)));
+      };
+      len = rest;
+      fields.push(field);
+      stream = next;
+    }
+
+    templates.push(IpfixTemplate {
+      id,
+      scope_count,
+      fields,
+    });
+  }
+
+  let Success { stream, .. } = take(len).parse(stream)?;
+
+  Parsed::Success {
+    token: templates,
+    stream,
+  }
+}
+
+fn ipfix_set<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(IpfixSet<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  (): IpfixParse<Stream, Context>,
+{
+  let Success {
+    token: (set_id, length),
+    stream,
+  } = (u16_be, u16_be).parse(stream)?;
+  let Some(len) = usize::from(length).checked_sub(IpfixSet::<()>::HEADER_LEN) else {
+    return Parsed::Failure(Context::new(IpfixAtom::Length(length)));
+  };
+  let consumed = usize::from(length);
+
+  match set_id {
+    IpfixSet::<()>::TEMPLATE => ipfix_templates(stream, length, false)
+      .map_token(|templates| (IpfixSet::Template(templates), consumed)),
+    IpfixSet::<()>::OPTIONS_TEMPLATE => ipfix_templates(stream, length, true)
+      .map_token(|templates| (IpfixSet::OptionsTemplate(templates), consumed)),
+    set_id => take(len)
+      .map(|records| (IpfixSet::Data { set_id, records }, consumed))
+      .parse(stream),
+  }
+}
+
+/// Parse an IPFIX message, data records are decoded by
+/// [IpfixTemplateCache::records]
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipfix_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<IpfixMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): IpfixParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    mut stream,
+  } = ipfix_header.parse(stream)?;
+
+  let mut len = usize::from(header.length) - IpfixHeader::LEN;
+  let mut sets = Vec::new();
+  while len > 0 {
+    let Success {
+      token: (set, consumed),
+      stream: next,
+    } = ipfix_set.parse(stream)?;
+    let Some(rest) = len.checked_sub(consumed) else {
+      return Parsed::Failure(Context::new(IpfixAtom::Length(header.length)));
+    };
+    len = rest;
+    sets.push(set);
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: IpfixMessage { header, sets },
+    stream,
+  }
+}
+
+struct_variants! {
+  IpfixElementId, element_id, u16:
+    /// Number of octets since the previous report
+    OCTET_DELTA_COUNT => 1,
+    /// Number of packets since the previous report
+    PACKET_DELTA_COUNT => 2,
+    /// IP protocol
+    PROTOCOL_IDENTIFIER => 4,
+    /// IPv4 TOS or IPv6 traffic class
+    IP_CLASS_OF_SERVICE => 5,
+    /// TCP flags
+    TCP_CONTROL_BITS => 6,
+    /// Source port
+    SOURCE_TRANSPORT_PORT => 7,
+    /// Source IPv4 address
+    SOURCE_IPV4_ADDRESS => 8,
+    /// Ingress interface index
+    INGRESS_INTERFACE => 10,
+    /// Destination port
+    DESTINATION_TRANSPORT_PORT => 11,
+    /// Destination IPv4 address
+    DESTINATION_IPV4_ADDRESS => 12,
+    /// Egress interface index
+    EGRESS_INTERFACE => 14,
+    /// Next hop IPv4 address
+    IP_NEXT_HOP_IPV4_ADDRESS => 15,
+    /// Source IPv6 address
+    SOURCE_IPV6_ADDRESS => 27,
+    /// Destination IPv6 address
+    DESTINATION_IPV6_ADDRESS => 28,
+    /// Source MAC address
+    SOURCE_MAC_ADDRESS => 56,
+    /// Destination MAC address
+    DESTINATION_MAC_ADDRESS => 80,
+    /// Flow start in seconds since the UNIX epoch
+    FLOW_START_SECONDS => 150,
+    /// Flow end in seconds since the UNIX epoch
+    FLOW_END_SECONDS => 151,
+    /// Flow start in milliseconds since the UNIX epoch
+    FLOW_START_MILLISECONDS => 152,
+    /// Flow end in milliseconds since the UNIX epoch
+    FLOW_END_MILLISECONDS => 153,
+}
+
+/// Value of an information element
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IpfixValue<'a> {
+  /// Unsigned integer, counters and timestamps included
+  Unsigned(u64),
+  /// IPv4 address
+  Ipv4(Ipv4Addr),
+  /// IPv6 address
+  Ipv6(Ipv6Addr),
+  /// MAC address
+  Mac([u8; 6]),
+  /// Unknown element or unexpected length
+  Bytes(&'a [u8]),
+}
+
+impl<'a> IpfixValue<'a> {
+  /// Decode the value of an IANA element, unsigned integers can use reduced
+  /// size encoding
+  pub fn decode(element_id: IpfixElementId, data: &'a [u8]) -> Self {
+    match (element_id, data.len()) {
+      (
+        IpfixElementId::SOURCE_IPV4_ADDRESS
+        | IpfixElementId::DESTINATION_IPV4_ADDRESS
+        | IpfixElementId::IP_NEXT_HOP_IPV4_ADDRESS,
+        4,
+      ) => <[u8; 4]>::try_from(data).map_or(Self::Bytes(data), |addr| Self::Ipv4(addr.into())),
+      (IpfixElementId::SOURCE_IPV6_ADDRESS | IpfixElementId::DESTINATION_IPV6_ADDRESS, 16) => {
+        <[u8; 16]>::try_from(data).map_or(Self::Bytes(data), |addr| Self::Ipv6(addr.into()))
+      }
+      (IpfixElementId::SOURCE_MAC_ADDRESS | IpfixElementId::DESTINATION_MAC_ADDRESS, 6) => {
+        <[u8; 6]>::try_from(data).map_or(Self::Bytes(data), Self::Mac)
+      }
+      (
+        IpfixElementId::OCTET_DELTA_COUNT
+        | IpfixElementId::PACKET_DELTA_COUNT
+        | IpfixElementId::PROTOCOL_IDENTIFIER
+        | IpfixElementId::IP_CLASS_OF_SERVICE
+        | IpfixElementId::TCP_CONTROL_BITS
+        | IpfixElementId::SOURCE_TRANSPORT_PORT
+        | IpfixElementId::INGRESS_INTERFACE
+        | IpfixElementId::DESTINATION_TRANSPORT_PORT
+        | IpfixElementId::EGRESS_INTERFACE
+        | IpfixElementId::FLOW_START_SECONDS
+        | IpfixElementId::FLOW_END_SECONDS
+        | IpfixElementId::FLOW_START_MILLISECONDS
+        | IpfixElementId::FLOW_END_MILLISECONDS,
+        1..=8,
+      ) => Self::Unsigned(
+        data
+          .iter()
+          .fold(0, |value, octet| value << 8u8 | u64::from(*octet)),
+      ),
+      _ => Self::Bytes(data),
+    }
+  }
+}
+
+/// Data record decoded with its template
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IpfixRecord<'a> {
+  /// Fields with their data, variable length prefix removed
+  pub fields: Vec<(IpfixFieldSpecifier, &'a [u8])>,
+}
+
+impl<'a> IpfixRecord<'a> {
+  /// Return the decoded value of the first IANA field with element_id
+  pub fn get(&self, element_id: IpfixElementId) -> Option<IpfixValue<'a>> {
+    self
+      .fields
+      .iter()
+      .find(|(field, _)| field.element_id == element_id && field.enterprise.is_none())
+      .map(|(_, data)| IpfixValue::decode(element_id, data))
+  }
+}
+
+/// Templates received from exporters, scoped by observation domain.
+///
+/// Templates live until withdrawn, UDP exporters resend them periodically.
+#[derive(Clone, Debug, Default)]
+pub struct IpfixTemplateCache {
+  templates: HashMap<(u32, u16), IpfixTemplate>,
+}
+
+impl IpfixTemplateCache {
+  /// Return an empty cache
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Learn the templates and withdrawals of a message
+  pub fn learn<Span>(&mut self, message: &IpfixMessage<Span>) {
+    let domain = message.header.observation_domain;
+    for set in &message.sets {
+      let (IpfixSet::Template(templates) | IpfixSet::OptionsTemplate(templates)) = set else {
+        continue;
+      };
+      for template in templates {
+        if template.fields.is_empty() {
+          self.templates.remove(&(domain, template.id));
+        } else {
+          self
+            .templates
+            .insert((domain, template.id), template.clone());
+        }
+      }
+    }
+  }
+
+  /// Return the template of a data set
+  pub fn template(&self, observation_domain: u32, set_id: u16) -> Option<&IpfixTemplate> {
+    self.templates.get(&(observation_domain, set_id))
+  }
+
+  /// Decode the records of a data set, None if the template is unknown or a
+  /// record is truncated
+  pub fn records<'a>(
+    &self, observation_domain: u32, set_id: u16, mut data: &'a [u8],
+  ) -> Option<Vec<IpfixRecord<'a>>> {
+    let template = self.template(observation_domain, set_id)?;
+    // a variable length field take at least its 1 byte prefix
+    let min_len = template
+      .fields
+      .iter()
+      .map(|field| match field.length {
+        IpfixFieldSpecifier::VARIABLE => 1,
+        length => usize::from(length),
+      })
+      .sum::<usize>()
+      .max(1);
+
+    let mut records = Vec::new();
+    // less than a record left is padding
+    while data.len() >= min_len {
+      let mut fields = Vec::with_capacity(template.fields.len());
+      for field in &template.fields {
+        let (len, rest) = match field.length {
+          IpfixFieldSpecifier::VARIABLE => match data {
+            [255, high, low, rest @ ..] => (usize::from(u16::from_be_bytes([*high, *low])), rest),
+            [len, rest @ ..] => (usize::from(*len), rest),
+            [] => return None,
+          },
+          length => (usize::from(length), data),
+        };
+        if rest.len() < len {
+          return None;
+        }
+        let (value, rest) = rest.split_at(len);
+        fields.push((*field, value));
+        data = rest;
+      }
+      records.push(IpfixRecord { fields });
+    }
+
+    Some(records)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    IpfixElementId,
+    IpfixSet,
+    IpfixTemplateCache,
+    IpfixValue,
+  };
+
+  #[test]
+  fn ipfix_message() {
+    let message = [
+      0x00, 0x0A, 0x00, 0x3E, 0x65, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00,
+      0x07, // template set
+      0x00, 0x02, 0x00, 0x1C, 0x01, 0x00, 0x00, 0x04, 0x00, 0x08, 0x00, 0x04, 0x00, 0x0C, 0x00,
+      0x04, 0x00, 0x02, 0x00, 0x02, 0x80, 0x01, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x09,
+      // data set, one record and padding
+      0x01, 0x00, 0x00, 0x12, 0xC0, 0xA8, 0x00, 0x01, 0x0A, 0x00, 0x00, 0x01, 0x00, 0x2A, 0x01,
+      0xAB, 0x00, 0x00,
+    ];
+    let Success { token, stream } = super::ipfix_message::<_, Ignore>(&message[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.header.observation_domain, 7);
+    assert_eq!(token.sets.len(), 2);
+    let IpfixSet::Template(templates) = &token.sets[0] else {
+      panic!("expected a template set");
+    };
+    assert_eq!(templates[0].fields[3].enterprise, Some(9));
+
+    let mut cache = IpfixTemplateCache::new();
+    cache.learn(&token);
+    let IpfixSet::Data { set_id, records } = token.sets[1] else {
+      panic!("expected a data set");
+    };
+    assert_eq!(cache.records(8, set_id, records), None);
+    let records = cache.records(7, set_id, records).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(
+      records[0].get(IpfixElementId::SOURCE_IPV4_ADDRESS),
+      Some(IpfixValue::Ipv4(Ipv4Addr::new(192, 168, 0, 1)))
+    );
+    assert_eq!(
+      records[0].get(IpfixElementId::PACKET_DELTA_COUNT),
+      Some(IpfixValue::Unsigned(42))
+    );
+    assert_eq!(records[0].fields[3].1, [0xAB]);
+  }
+}
@@ -0,0 +1,197 @@
+//! Handles parsing of IPsec Authentication Header and Encapsulating Security
+//! Payload
+//! <https://www.rfc-editor.org/rfc/rfc4302>
+//! <https://www.rfc-editor.org/rfc/rfc4303>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    all,
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::IPProtocol;
+
+/// Meta trait for ipsec combinator
+pub trait IpsecParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<IpsecAtom>;
+
+/// Atom produced by ipsec parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IpsecAtom {
+  /// When the AH payload length is shorter than the fixed fields
+  AhLength(u8),
+}
+
+impl Display for IpsecAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      IpsecAtom::AhLength(length) => {
+        write!(f, "AhLength: payload length {} is shorter than 1", length)
+      }
+    }
+  }
+}
+
+/// IPsec Authentication Header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AhHeader<Span> {
+  /// Protocol of the authenticated payload
+  pub next_header: IPProtocol,
+  /// Length of the header in 32 bits words, minus 2
+  pub payload_len: u8,
+  /// Security Parameters Index
+  pub spi: u32,
+  /// Sequence number
+  pub sequence: u32,
+  /// Integrity Check Value
+  pub icv: Span,
+}
+
+// A header is never empty
+#[allow(clippy::len_without_is_empty)]
+impl<Span> AhHeader<Span> {
+  /// Length of the fixed fields of an Authentication Header
+  pub const MIN_LEN: usize = 12;
+
+  /// Return the length of the header, in bytes
+  pub const fn len(&self) -> usize {
+    (self.payload_len as usize + 2) * 4
+  }
+}
+
+/// Parse an IPsec Authentication Header, the stream is left at the
+/// authenticated payload
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ah_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<AhHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): IpsecParse<Stream, Context>,
+{
+  let Success {
+    token: (next_header, payload_len, _reserved, spi, sequence),
+    stream,
+  } = (
+    octet.map(IPProtocol::new),
+    octet.try_map(|payload_len| {
+      if payload_len >= 1 {
+        Ok(payload_len)
+      } else {
+        Err(Context::new(IpsecAtom::AhLength(payload_len)))
+      }
+    }),
+    u16_be,
+    u32_be,
+    u32_be,
+  )
+    .parse(stream)?;
+
+  let icv_len = (usize::from(payload_len) + 2) * 4 - AhHeader::<()>::MIN_LEN;
+  take(icv_len)
+    .map(|icv| AhHeader {
+      next_header,
+      payload_len,
+      spi,
+      sequence,
+      icv,
+    })
+    .parse(stream)
+}
+
+/// IPsec Encapsulating Security Payload, everything after the sequence
+/// number is encrypted, next header and padding included
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EspPacket<Span> {
+  /// Security Parameters Index
+  pub spi: u32,
+  /// Sequence number
+  pub sequence: u32,
+  /// Encrypted payload, trailer and Integrity Check Value
+  pub encrypted: Span,
+}
+
+impl<Span> EspPacket<Span> {
+  /// Length of the clear fields of an ESP packet
+  pub const HEADER_LEN: usize = 8;
+}
+
+/// Parse an IPsec ESP packet, the rest of the stream is the encrypted part
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn esp_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<EspPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): IpsecParse<Stream, Context>,
+{
+  (u32_be, u32_be, all)
+    .map(|(spi, sequence, encrypted)| EspPacket {
+      spi,
+      sequence,
+      encrypted,
+    })
+    .parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use crate::IPProtocol;
+
+  #[test]
+  fn ipsec() {
+    let ah = [
+      0x06, 0x04, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x2A, 0x01, 0x02, 0x03,
+      0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0A, 0x0B, 0x0C, 0xB0,
+    ];
+    let Success { token, stream } = super::ah_header::<_, Ignore>(&ah[..]).unwrap();
+    assert_eq!(token.next_header, IPProtocol::TCP);
+    assert_eq!(token.spi, 0x100);
+    assert_eq!(token.sequence, 42);
+    assert_eq!(token.icv.len(), 12);
+    assert_eq!(token.len(), 24);
+    assert_eq!(stream, [0xB0]);
+
+    let Success { token, stream } = super::esp_packet::<_, Ignore>(&ah[4..]).unwrap();
+    assert_eq!(token.spi, 0x100);
+    assert_eq!(token.sequence, 42);
+    assert_eq!(token.encrypted, &ah[12..]);
+    assert_eq!(stream, b"");
+  }
+}
@@ -13,6 +13,11 @@ use binator::{
     any,
     nbit,
     octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
     NBit,
   },
   utils::{
@@ -24,13 +29,19 @@ use binator::{
   CoreAtom,
   Parse,
   Parsed,
+  Split,
   Streaming,
   Success,
 };
 
-use crate::ip_protocol::{
-  self,
-  IPProtocol,
+use crate::{
+  ip_protocol::{
+    self,
+    Ecn,
+    IPProtocol,
+  },
+  ChecksumBackend,
+  SoftwareChecksum,
 };
 
 /// <https://en.wikipedia.org/wiki/Internet_Protocol_version_4>
@@ -144,6 +155,151 @@ pub struct IPv4Header<Span> {
   pub options: Span,
 }
 
+impl<Span> IPv4Header<Span> {
+  /// Don't Fragment flag
+  pub const DONT_FRAGMENT: u8 = 0b010;
+  /// Length of an IPv4 header with the maximum of options
+  pub const MAX_LEN: usize = 60;
+  /// Length of an IPv4 header without options
+  pub const MIN_LEN: usize = 20;
+  /// More Fragments flag
+  pub const MORE_FRAGMENTS: u8 = 0b001;
+
+  /// Return the length of the header including options, in bytes
+  pub const fn header_len(&self) -> usize {
+    self.ihl as usize * 4
+  }
+
+  /// Return true if the Don't Fragment flag is set
+  pub const fn dont_fragment(&self) -> bool {
+    self.flags & Self::DONT_FRAGMENT != 0
+  }
+
+  /// Return true if the More Fragments flag is set
+  pub const fn more_fragments(&self) -> bool {
+    self.flags & Self::MORE_FRAGMENTS != 0
+  }
+
+  /// Return true if the packet is a fragment, more fragments follow or the
+  /// fragment offset is not 0
+  pub const fn is_fragment(&self) -> bool {
+    self.more_fragments() || self.fragment_offset != 0
+  }
+
+  /// Return the fragment offset in bytes
+  pub const fn fragment_offset_bytes(&self) -> usize {
+    self.fragment_offset as usize * 8
+  }
+
+  /// Return the Differentiated Services Code Point, the 6 high bits of tos
+  pub const fn dscp(&self) -> u8 {
+    self.tos >> 2
+  }
+
+  /// Return the Explicit Congestion Notification, the 2 low bits of tos
+  pub fn ecn(&self) -> Ecn {
+    Ecn::from(self.tos)
+  }
+
+  /// Return the length of the payload following the header, computed from
+  /// the total length, None if the total length is less than the header length
+  pub const fn payload_len(&self) -> Option<usize> {
+    (self.length as usize).checked_sub(self.header_len())
+  }
+
+  /// Return the header with options replaced by f(options)
+  pub fn map_options<Options, F>(self, f: F) -> IPv4Header<Options>
+  where
+    F: FnOnce(Span) -> Options,
+  {
+    IPv4Header {
+      version: self.version,
+      ihl: self.ihl,
+      tos: self.tos,
+      length: self.length,
+      id: self.id,
+      flags: self.flags,
+      fragment_offset: self.fragment_offset,
+      ttl: self.ttl,
+      protocol: self.protocol,
+      chksum: self.chksum,
+      source_addr: self.source_addr,
+      dest_addr: self.dest_addr,
+      options: f(self.options),
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<Span: AsRef<[u8]>> IPv4Header<Span> {
+  /// Return a copy of the header owning its options, it can outlive the
+  /// parsed stream
+  pub fn to_owned(&self) -> IPv4Header<Vec<u8>> {
+    IPv4Header {
+      version: self.version,
+      ihl: self.ihl,
+      tos: self.tos,
+      length: self.length,
+      id: self.id,
+      flags: self.flags,
+      fragment_offset: self.fragment_offset,
+      ttl: self.ttl,
+      protocol: self.protocol,
+      chksum: self.chksum,
+      source_addr: self.source_addr,
+      dest_addr: self.dest_addr,
+      options: self.options.as_ref().to_vec(),
+    }
+  }
+
+  /// Return the header owning its options, it can outlive the parsed stream
+  pub fn into_owned(self) -> IPv4Header<Vec<u8>> {
+    self.map_options(|options| options.as_ref().to_vec())
+  }
+}
+
+impl<Span> IPv4Header<Span>
+where
+  Span: AsRef<[u8]>,
+{
+  /// Return the header checksum computed over the fields of self, the
+  /// chksum field is ignored
+  pub fn compute_checksum(&self) -> u16 {
+    self.compute_checksum_with(&SoftwareChecksum)
+  }
+
+  /// Like [IPv4Header::compute_checksum] but computed by backend
+  pub fn compute_checksum_with<Backend>(&self, backend: &Backend) -> u16
+  where
+    Backend: ChecksumBackend,
+  {
+    let [length_0, length_1] = self.length.to_be_bytes();
+    let [id_0, id_1] = self.id.to_be_bytes();
+    let [offset_0, offset_1] = self.fragment_offset.to_be_bytes();
+    let header = [
+      self.version << 4u8 | self.ihl,
+      self.tos,
+      length_0,
+      length_1,
+      id_0,
+      id_1,
+      self.flags << 5u8 | offset_0,
+      offset_1,
+      self.ttl,
+      self.protocol.protocol(),
+      0,
+      0,
+    ];
+
+    backend.checksum(&[
+      &header,
+      &self.source_addr.octets(),
+      &self.dest_addr.octets(),
+      self.options.as_ref(),
+    ])
+  }
+}
+
 /// Ipv4 failure cause
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Ipv4Atom {
@@ -151,6 +307,14 @@ pub enum Ipv4Atom {
   Version(u8),
   /// When IHL is less than 5
   IHL(u8),
+  /// When header checksum is wrong, contain the computed checksum
+  BadChecksum(u16),
+  /// When total length is less than the header length, contain the total
+  /// length
+  LengthTooSmall(u16),
+  /// When the stream is shorter than the total length, contain the total
+  /// length
+  TruncatedPacket(u16),
 }
 
 impl Display for Ipv4Atom {
@@ -162,6 +326,27 @@ impl Display for Ipv4Atom {
       Ipv4Atom::IHL(ihl) => {
         write!(f, "Ipv4Context: IHL field is less than 5 found {}", ihl)
       }
+      Ipv4Atom::BadChecksum(chksum) => {
+        write!(
+          f,
+          "Ipv4Context: header checksum doesn't match expected {:#06X}",
+          chksum
+        )
+      }
+      Ipv4Atom::LengthTooSmall(length) => {
+        write!(
+          f,
+          "Ipv4Context: total length {} is less than the header length",
+          length
+        )
+      }
+      Ipv4Atom::TruncatedPacket(length) => {
+        write!(
+          f,
+          "Ipv4Context: packet is shorter than total length {}",
+          length
+        )
+      }
     }
   }
 }
@@ -250,40 +435,406 @@ where
     .span()
     .parse(stream)?;
 
+  let token = IPv4Header {
+    version,
+    ihl,
+    tos,
+    length,
+    id,
+    flags,
+    fragment_offset,
+    ttl,
+    protocol,
+    chksum,
+    source_addr,
+    dest_addr,
+    options,
+  };
+
+  #[cfg(feature = "tracing")]
+  crate::trace_layer(&token);
+
+  Parsed::Success { token, stream }
+}
+
+/// Parse ipv4 header like [ipv4_header] but fail with
+/// [Ipv4Atom::BadChecksum] if the header checksum is wrong.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipv4_header_checked<Stream, Context>(
+  stream: Stream,
+) -> Parsed<IPv4Header<Stream::Span>, Stream, Context>
+where
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Stream::Span: AsRef<[u8]>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv4Atom>,
+{
+  ipv4_header_checked_with(stream, &SoftwareChecksum)
+}
+
+/// Like [ipv4_header_checked] but the checksum is computed by backend
+pub fn ipv4_header_checked_with<Stream, Context, Backend>(
+  stream: Stream, backend: &Backend,
+) -> Parsed<IPv4Header<Stream::Span>, Stream, Context>
+where
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Stream::Span: AsRef<[u8]>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv4Atom>,
+  Backend: ChecksumBackend,
+{
+  ipv4_header
+    .try_map(|header: IPv4Header<Stream::Span>| {
+      let chksum = header.compute_checksum_with(backend);
+      if chksum == header.chksum {
+        Ok(header)
+      } else {
+        Err(Context::new(Ipv4Atom::BadChecksum(chksum)))
+      }
+    })
+    .parse(stream)
+}
+
+/// Parse ipv4 header like [ipv4_header] but fail with
+/// [Ipv4Atom::LengthTooSmall] if the total length is less than the header
+/// length or with [Ipv4Atom::TruncatedPacket] if the stream doesn't hold the
+/// whole payload. The stream is not trimmed, use [ipv4_payload].
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipv4_header_strict<Stream, Context>(
+  stream: Stream,
+) -> Parsed<IPv4Header<Stream::Span>, Stream, Context>
+where
+  Stream: Eq + Clone,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv4Atom>,
+{
+  let Success { token, stream } = ipv4_header.parse(stream)?;
+  let Success { .. } = ipv4_payload(stream.clone(), &token)?;
+  Parsed::Success { token, stream }
+}
+
+/// Parse the payload of header, exactly its total length minus the header
+/// length, the bytes after it like Ethernet padding are left in the stream
+pub fn ipv4_payload<Stream, Context, Span>(
+  stream: Stream, header: &IPv4Header<Span>,
+) -> Parsed<Stream::Span, Stream, Context>
+where
+  Stream: Streaming,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<Ipv4Atom>,
+{
+  let Some(len) = header.payload_len() else {
+    return Parsed::Failure(Context::new(Ipv4Atom::LengthTooSmall(header.length)));
+  };
+  match take(len).parse(stream) {
+    Parsed::Failure(_) => Parsed::Failure(Context::new(Ipv4Atom::TruncatedPacket(header.length))),
+    parsed => parsed,
+  }
+}
+
+/// Meta trait for ipv4 option combinator
+pub trait Ipv4OptionParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv4OptionAtom>;
+
+/// Atom produced by ipv4_options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ipv4OptionAtom {
+  /// When an option length is less than 2
+  Len(u8),
+  /// When a route option length is not 3 plus a multiple of 4
+  RouteLen(u8),
+  /// When a timestamp option length doesn't match its flag
+  TimestampLen(u8),
+  /// When router alert option length is not 4
+  RouterAlertLen(u8),
+}
+
+impl Display for Ipv4OptionAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      Ipv4OptionAtom::Len(len) => write!(f, "Len: option length {} is less than 2", len),
+      Ipv4OptionAtom::RouteLen(len) => {
+        write!(f, "RouteLen: route option length {} is invalid", len)
+      }
+      Ipv4OptionAtom::TimestampLen(len) => {
+        write!(
+          f,
+          "TimestampLen: timestamp option length {} is invalid",
+          len
+        )
+      }
+      Ipv4OptionAtom::RouterAlertLen(len) => {
+        write!(
+          f,
+          "RouterAlertLen: router alert length is not 4 found {}",
+          len
+        )
+      }
+    }
+  }
+}
+
+/// IPv4 option
+/// <https://www.iana.org/assignments/ip-parameters/ip-parameters.xhtml>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ipv4Option<Span> {
+  /// End of Options List
+  EndOfOptions,
+  /// No Operation
+  Noop,
+  /// Record Route
+  RecordRoute {
+    /// Offset of the next slot, from the start of the option
+    pointer: u8,
+    /// Route data
+    route: Vec<Ipv4Addr>,
+  },
+  /// Loose Source Route
+  LooseSourceRoute {
+    /// Offset of the next address, from the start of the option
+    pointer: u8,
+    /// Route data
+    route: Vec<Ipv4Addr>,
+  },
+  /// Strict Source Route
+  StrictSourceRoute {
+    /// Offset of the next address, from the start of the option
+    pointer: u8,
+    /// Route data
+    route: Vec<Ipv4Addr>,
+  },
+  /// Internet Timestamp
+  Timestamp {
+    /// Offset of the next slot, from the start of the option
+    pointer: u8,
+    /// Number of hosts that couldn't register a timestamp
+    overflow: u8,
+    /// 0 timestamps only, 1 each host register its address, 3 addresses are
+    /// prespecified
+    flag: u8,
+    /// Address, when flag is not 0, and timestamp of each slot
+    timestamps: Vec<(Option<Ipv4Addr>, u32)>,
+  },
+  /// Router Alert
+  RouterAlert(u16),
+  /// Any other option, type and data
+  Unknown(u8, Span),
+}
+
+fn ipv4_route<Stream, Context>(
+  stream: Stream, len: u8,
+) -> Parsed<(u8, Vec<Ipv4Addr>), Stream, Context>
+where
+  (): Ipv4OptionParse<Stream, Context>,
+{
+  if len < 3 || !(len - 3).is_multiple_of(4) {
+    return Parsed::Failure(Context::new(Ipv4OptionAtom::RouteLen(len)));
+  }
+
+  octet
+    .and(
+      u32_be
+        .map(Ipv4Addr::from)
+        .fold_bounds(usize::from(len - 3) / 4, Vec::new, Acc::acc),
+    )
+    .parse(stream)
+}
+
+fn ipv4_timestamp<Stream, Context>(
+  stream: Stream, len: u8,
+) -> Parsed<Ipv4Option<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): Ipv4OptionParse<Stream, Context>,
+{
+  let Success {
+    token: (pointer, overflow_flag),
+    stream,
+  } = (octet, octet).parse(stream)?;
+  let (overflow, flag) = (overflow_flag >> 4u8, overflow_flag & 0x0F);
+
+  let data = usize::from(len).checked_sub(4);
+  let Success {
+    token: timestamps,
+    stream,
+  } = match (flag, data) {
+    (0, Some(data)) if data.is_multiple_of(4) => u32_be
+      .map(|timestamp| (None, timestamp))
+      .fold_bounds(data / 4, Vec::new, Acc::acc)
+      .parse(stream),
+    (1 | 3, Some(data)) if data.is_multiple_of(8) => (u32_be.map(Ipv4Addr::from), u32_be)
+      .map(|(addr, timestamp)| (Some(addr), timestamp))
+      .fold_bounds(data / 8, Vec::new, Acc::acc)
+      .parse(stream),
+    _ => Parsed::Failure(Context::new(Ipv4OptionAtom::TimestampLen(len))),
+  }?;
+
   Parsed::Success {
-    token: IPv4Header {
-      version,
-      ihl,
-      tos,
-      length,
-      id,
-      flags,
-      fragment_offset,
-      ttl,
-      protocol,
-      chksum,
-      source_addr,
-      dest_addr,
-      options,
+    token: Ipv4Option::Timestamp {
+      pointer,
+      overflow,
+      flag,
+      timestamps,
     },
     stream,
   }
 }
 
+fn ipv4_option<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Ipv4Option<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): Ipv4OptionParse<Stream, Context>,
+{
+  let Success { token: op, stream } = octet.parse(stream)?;
+  match op {
+    0 => {
+      return Parsed::Success {
+        token: Ipv4Option::EndOfOptions,
+        stream,
+      }
+    }
+    1 => {
+      return Parsed::Success {
+        token: Ipv4Option::Noop,
+        stream,
+      }
+    }
+    _ => {}
+  }
+
+  let Success { token: len, stream } = octet
+    .try_map(|len| {
+      if len >= 2 {
+        Ok(len)
+      } else {
+        Err(Context::new(Ipv4OptionAtom::Len(len)))
+      }
+    })
+    .parse(stream)?;
+
+  match op {
+    7 => ipv4_route(stream, len)
+      .map_token(|(pointer, route)| Ipv4Option::RecordRoute { pointer, route }),
+    131 => ipv4_route(stream, len)
+      .map_token(|(pointer, route)| Ipv4Option::LooseSourceRoute { pointer, route }),
+    137 => ipv4_route(stream, len)
+      .map_token(|(pointer, route)| Ipv4Option::StrictSourceRoute { pointer, route }),
+    68 => ipv4_timestamp(stream, len),
+    148 if len == 4 => u16_be.map(Ipv4Option::RouterAlert).parse(stream),
+    148 => Parsed::Failure(Context::new(Ipv4OptionAtom::RouterAlertLen(len))),
+    op => take(usize::from(len - 2))
+      .map(|data| Ipv4Option::Unknown(op, data))
+      .parse(stream),
+  }
+}
+
+/// Parse ipv4 options, this can be used on the options Span of IPv4Header.
+/// Parsing stop after [Ipv4Option::EndOfOptions], the padding that follow is
+/// left in the stream. A malformed or truncated option is a failure.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipv4_options<Stream, Context>(
+  mut stream: Stream,
+) -> Parsed<Vec<Ipv4Option<<Stream as Streaming>::Span>>, Stream, Context>
+where
+  (): Ipv4OptionParse<Stream, Context>,
+{
+  let mut options = Vec::new();
+  loop {
+    match ipv4_option.parse(stream.clone()) {
+      Parsed::Success {
+        token: Ipv4Option::EndOfOptions,
+        stream,
+      } => {
+        options.push(Ipv4Option::EndOfOptions);
+        return Parsed::Success {
+          token: options,
+          stream,
+        };
+      }
+      Parsed::Success {
+        token,
+        stream: next,
+      } => {
+        options.push(token);
+        stream = next;
+      }
+      // only the end of the stream end the options without EndOfOptions
+      Parsed::Failure(context) => {
+        return match stream.clone().split_first() {
+          Split::NotEnoughItem(_) => Parsed::Success {
+            token: options,
+            stream,
+          },
+          _ => Parsed::Failure(context),
+        };
+      }
+      Parsed::Error(context) => return Parsed::Error(context),
+    }
+  }
+}
+
 #[cfg(test)]
 mod tests {
+  use core::fmt::Debug;
   use std::net::Ipv4Addr;
 
   use binator::{
-    context::Ignore,
+    context::{
+      Ignore,
+      Tree,
+    },
+    utils::UtilsAtom,
+    CoreAtom,
     Parsed,
+    ProvideElement,
+    Streaming,
+  };
+  use derive_more::{
+    Display,
+    From,
   };
 
   use super::{
+    ChecksumBackend,
+    Ecn,
     IPProtocol,
     IPv4Header,
+    Ipv4Option,
+    Ipv4OptionAtom,
   };
 
+  #[derive(Display, Debug, Clone, PartialEq, From)]
+  enum FromAtom<Stream: Streaming + Debug, Error = <Stream as Streaming>::Error> {
+    Core(CoreAtom<Stream, Error>),
+    Utils(UtilsAtom<Stream>),
+    Ipv4Option(Ipv4OptionAtom),
+  }
+
+  type HandleAtom<Stream> = Tree<FromAtom<Stream>>;
+
   #[test]
   fn ipv4_header() {
     let data = [
@@ -306,6 +857,24 @@ mod tests {
       dest_addr: Ipv4Addr::new(10, 10, 1, 180),
       options: "".as_bytes(),
     };
+    assert_eq!(expectation.header_len(), IPv4Header::<()>::MIN_LEN);
+    assert!(expectation.more_fragments() && !expectation.dont_fragment());
+    assert!(expectation.is_fragment());
+    assert_eq!(expectation.fragment_offset_bytes(), 0);
+    assert_eq!((expectation.dscp(), expectation.ecn()), (0, Ecn::NotEct));
+    assert_eq!(Ecn::from(0xBB), Ecn::Ce);
+    assert_eq!(expectation.compute_checksum(), 0x22ED);
+    // checksum precomputed by the NIC
+    struct Precomputed(u16);
+    impl ChecksumBackend for Precomputed {
+      fn checksum(&self, _parts: &[&[u8]]) -> u16 {
+        self.0
+      }
+    }
+    assert_eq!(
+      expectation.compute_checksum_with(&Precomputed(0x1234)),
+      0x1234
+    );
     assert_eq!(
       Parsed::Success {
         token: expectation,
@@ -313,5 +882,101 @@ mod tests {
       },
       super::ipv4_header::<_, Ignore>(data.as_slice())
     );
+    assert_eq!(
+      super::ipv4_header::<_, Ignore>(data.as_slice()),
+      super::ipv4_header_checked::<_, Ignore>(data.as_slice())
+    );
+
+    let mut data = data;
+    data[8] = 0x3F;
+    assert!(matches!(
+      super::ipv4_header_checked::<_, Ignore>(data.as_slice()),
+      Parsed::Failure(_)
+    ));
+    assert!(
+      super::ipv4_header_checked_with::<_, Ignore, _>(data.as_slice(), &Precomputed(0x22ED))
+        .is_success()
+    );
+  }
+
+  #[test]
+  fn ipv4_strict() {
+    let data = [
+      0x45, 0x00, 0x00, 0x18, 0x00, 0x00, 0x00, 0x00, 0x40, 0x11, 0x00, 0x00, 0x0A, 0x00, 0x00,
+      0x01, 0x0A, 0x00, 0x00, 0x02, 0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00,
+    ];
+
+    let Parsed::Success { token, stream } = super::ipv4_header_strict::<_, Ignore>(&data[..])
+    else {
+      panic!("expected success");
+    };
+    assert_eq!(token.payload_len(), Some(4));
+    assert_eq!(
+      super::ipv4_payload::<_, Ignore, _>(stream, &token),
+      Parsed::Success {
+        token: &[0xDE, 0xAD, 0xBE, 0xEF][..],
+        stream: &[0x00, 0x00][..],
+      }
+    );
+
+    let mut data = data;
+    data[3] = 0x10;
+    assert!(matches!(
+      super::ipv4_header_strict::<_, Ignore>(&data[..]),
+      Parsed::Failure(_)
+    ));
+    data[3] = 0x20;
+    assert!(matches!(
+      super::ipv4_header_strict::<_, Ignore>(&data[..]),
+      Parsed::Failure(_)
+    ));
+    assert!(super::ipv4_header::<_, Ignore>(&data[..]).is_success());
+  }
+
+  #[test]
+  fn ipv4_options() {
+    let options = [
+      0x94, 0x04, 0x00, 0x00, 0x01, 0x07, 0x07, 0x04, 0x0A, 0x00, 0x00, 0x01, 0x44, 0x0C, 0x0D,
+      0x01, 0xC0, 0xA8, 0x00, 0x01, 0x00, 0x00, 0x00, 0x2A, 0x00, 0x00, 0x00,
+    ];
+
+    assert_eq!(
+      super::ipv4_options::<_, Ignore>(&options[..]),
+      Parsed::Success {
+        token: vec![
+          Ipv4Option::RouterAlert(0),
+          Ipv4Option::Noop,
+          Ipv4Option::RecordRoute {
+            pointer: 4,
+            route: vec![Ipv4Addr::new(10, 0, 0, 1)],
+          },
+          Ipv4Option::Timestamp {
+            pointer: 13,
+            overflow: 0,
+            flag: 1,
+            timestamps: vec![(Some(Ipv4Addr::new(192, 168, 0, 1)), 42)],
+          },
+          Ipv4Option::EndOfOptions,
+        ],
+        stream: &[0x00, 0x00][..],
+      }
+    );
+    assert_eq!(
+      super::ipv4_options::<_, Ignore>(&[0x01][..]),
+      Parsed::Success {
+        token: vec![Ipv4Option::Noop],
+        stream: &[][..],
+      }
+    );
+
+    let Parsed::Failure(context) =
+      super::ipv4_options::<_, HandleAtom<_>>(&[0x07, 0x05, 0x04, 0x00, 0x00][..])
+    else {
+      panic!("expected failure");
+    };
+    assert_eq!(
+      context.last(),
+      &FromAtom::Ipv4Option(Ipv4OptionAtom::RouteLen(5))
+    );
   }
 }
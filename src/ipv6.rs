@@ -12,9 +12,15 @@ use binator::{
   base::{
     nbit,
     octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
     NBit,
   },
   utils::{
+    Acc,
     Utils,
     UtilsAtom,
   },
@@ -74,17 +80,279 @@ pub struct IPv6Header {
   pub dest_addr: Ipv6Addr,
 }
 
-/// Aom produced by ipv6_header parser
+impl IPv6Header {
+  /// Length of an IPv6 header, extension headers are not part of it
+  pub const LEN: usize = 40;
+
+  /// Return IPv6Header from its wire representation, without going through
+  /// the combinator machinery. None if version is not 6.
+  pub const fn from_bytes(bytes: &[u8; Self::LEN]) -> Option<Self> {
+    const fn addr(bytes: &[u8; IPv6Header::LEN], at: usize) -> Ipv6Addr {
+      let mut segments = [0u16; 8];
+      let mut i = 0usize;
+      while i < 8 {
+        segments[i] = u16::from_be_bytes([bytes[at + i * 2], bytes[at + i * 2 + 1]]);
+        i += 1;
+      }
+      let [a, b, c, d, e, f, g, h] = segments;
+      Ipv6Addr::new(a, b, c, d, e, f, g, h)
+    }
+
+    let version = bytes[0] >> 4u8;
+    if version != 6 {
+      return None;
+    }
+    let tc = bytes[0] << 4u8 | bytes[1] >> 4u8;
+
+    Some(Self {
+      version,
+      ds: tc >> 2u8,
+      ecn: tc & 0b11,
+      flow_label: u32::from_be_bytes([0, bytes[1] & 0x0F, bytes[2], bytes[3]]),
+      length: u16::from_be_bytes([bytes[4], bytes[5]]),
+      next_header: IPProtocol::new(bytes[6]),
+      hop_limit: bytes[7],
+      source_addr: addr(bytes, 8),
+      dest_addr: addr(bytes, 24),
+    })
+  }
+
+  /// Return the size of the payload in octets, extension headers included.
+  /// hop_by_hop is the Hop-by-Hop header following this header, its Jumbo
+  /// Payload option is used when length is 0. Fail with [Ipv6Atom::BadJumbo]
+  /// if the option is present while length is not 0 or if the jumbo length
+  /// is not greater than 65535
+  pub fn payload_length<Span>(
+    &self, hop_by_hop: Option<&Ipv6HopByHopHeader<Span>>,
+  ) -> Result<u32, Ipv6Atom> {
+    match (self.length, hop_by_hop.and_then(Ipv6HopByHopHeader::jumbo)) {
+      (0, Some(jumbo)) if jumbo > u32::from(u16::MAX) => Ok(jumbo),
+      (_, Some(jumbo)) => Err(Ipv6Atom::BadJumbo(jumbo)),
+      (length, None) => Ok(u32::from(length)),
+    }
+  }
+
+  /// Return the wire representation of the header
+  pub const fn to_bytes(&self) -> [u8; Self::LEN] {
+    let tc = self.ds << 2u8 | self.ecn & 0b11;
+    let [_, f0, f1, f2] = self.flow_label.to_be_bytes();
+    let [l0, l1] = self.length.to_be_bytes();
+
+    let mut bytes = [0u8; Self::LEN];
+    bytes[0] = self.version << 4u8 | tc >> 4u8;
+    bytes[1] = tc << 4u8 | f0 & 0x0F;
+    bytes[2] = f1;
+    bytes[3] = f2;
+    bytes[4] = l0;
+    bytes[5] = l1;
+    bytes[6] = self.next_header.protocol();
+    bytes[7] = self.hop_limit;
+
+    let source = self.source_addr.octets();
+    let dest = self.dest_addr.octets();
+    let mut i = 0usize;
+    while i < 16 {
+      bytes[8 + i] = source[i];
+      bytes[24 + i] = dest[i];
+      i += 1;
+    }
+    bytes
+  }
+}
+
+/// IPv6 fragment extension header, found after the headers with
+/// [IPProtocol::IPV6_FRAG] as next header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ipv6FragmentHeader {
+  /// Type of the header following the fragmentable part
+  pub next_header: IPProtocol,
+  /// Offset of the fragment in 8 bytes units
+  pub fragment_offset: u16,
+  /// More fragments follow
+  pub more_fragments: bool,
+  /// Identification shared by the fragments of a packet
+  pub id: u32,
+}
+
+impl Ipv6FragmentHeader {
+  /// Length of a fragment header
+  pub const LEN: usize = 8;
+}
+
+/// IPv6 option, carried by the Hop-by-Hop and Destination Options headers
+/// <https://www.iana.org/assignments/ipv6-parameters/ipv6-parameters.xhtml>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ipv6Option<Span> {
+  /// Single octet of padding
+  Pad1,
+  /// Padding, contain the length of the data
+  PadN(u8),
+  /// Router Alert
+  RouterAlert(u16),
+  /// Jumbo Payload, the length of the payload when it's more than 65535
+  Jumbo(u32),
+  /// Any other option, type and data
+  Unknown(u8, Span),
+}
+
+/// IPv6 Hop-by-Hop Options extension header, found after the header with
+/// [IPProtocol::HOPOPT] as next header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ipv6HopByHopHeader<Span> {
+  /// Type of the header following this one
+  pub next_header: IPProtocol,
+  /// Options, padding included
+  pub options: Vec<Ipv6Option<Span>>,
+}
+
+impl<Span> Ipv6HopByHopHeader<Span> {
+  /// Return the length of the Jumbo Payload option if any
+  pub fn jumbo(&self) -> Option<u32> {
+    self.options.iter().find_map(|option| match option {
+      Ipv6Option::Jumbo(length) => Some(*length),
+      _ => None,
+    })
+  }
+}
+
+/// TLV of a Segment Routing Header
+/// <https://www.rfc-editor.org/rfc/rfc8754#section-2.1>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SrhTlv<Span> {
+  /// Single octet of padding
+  Pad1,
+  /// Padding, contain the length of the data
+  PadN(u8),
+  /// HMAC
+  Hmac {
+    /// Destination address verification flag
+    d_flag: bool,
+    /// Key identifier, 0 means no HMAC
+    key_id: u32,
+    /// HMAC value
+    hmac: Span,
+  },
+  /// Any other TLV, type and value
+  Unknown(u8, Span),
+}
+
+/// Segment Routing Header, routing type 4
+/// <https://www.rfc-editor.org/rfc/rfc8754>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ipv6SegmentRoutingHeader<Span> {
+  /// Type of the header following this one
+  pub next_header: IPProtocol,
+  /// Index in segments of the active segment
+  pub segments_left: u8,
+  /// Index in segments of the first segment of the path
+  pub last_entry: u8,
+  /// Flags, none are defined
+  pub flags: u8,
+  /// Tag a packet as part of a class or group of packets
+  pub tag: u16,
+  /// Segment list in reverse order, the last segment of the path first
+  pub segments: Vec<Ipv6Addr>,
+  /// TLVs following the segment list, padding included
+  pub tlvs: Vec<SrhTlv<Span>>,
+}
+
+impl<Span> Ipv6SegmentRoutingHeader<Span> {
+  /// Routing type of a Segment Routing Header
+  pub const ROUTING_TYPE: u8 = 4;
+
+  /// Return the active segment, the one of the destination address
+  pub fn active_segment(&self) -> Option<Ipv6Addr> {
+    self.segments.get(usize::from(self.segments_left)).copied()
+  }
+
+  /// Return the last segment of the path
+  pub fn final_segment(&self) -> Option<Ipv6Addr> {
+    self.segments.first().copied()
+  }
+}
+
+/// IPv6 Routing extension header, found after the headers with
+/// [IPProtocol::IPV6_ROUTE] as next header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Ipv6RoutingHeader<Span> {
+  /// Segment Routing Header
+  SegmentRouting(Ipv6SegmentRoutingHeader<Span>),
+  /// Any other routing type
+  Other {
+    /// Type of the header following this one
+    next_header: IPProtocol,
+    /// Routing type
+    routing_type: u8,
+    /// Number of route segments remaining
+    segments_left: u8,
+    /// Type specific data
+    data: Span,
+  },
+}
+
+impl<Span> Ipv6RoutingHeader<Span> {
+  /// Return the type of the header following this one
+  pub const fn next_header(&self) -> IPProtocol {
+    match self {
+      Self::SegmentRouting(srh) => srh.next_header,
+      Self::Other { next_header, .. } => *next_header,
+    }
+  }
+}
+
+/// Atom produced by ipv6 parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Ipv6Atom {
   /// When version is not 6
   Version(u8),
+  /// When the stream is shorter than the header, contain the header length
+  Truncated(usize),
+  /// When an option is longer than what's left of its extension header or
+  /// when a Jumbo Payload option length is not 4
+  OptionLen(u8),
+  /// When a Jumbo Payload option is inconsistent with the payload length,
+  /// contain the jumbo length
+  BadJumbo(u32),
+  /// When the segment list doesn't fit in the Segment Routing Header,
+  /// contain the last entry
+  SegmentList(u8),
 }
 
 impl Display for Ipv6Atom {
   fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
     match self {
       Self::Version(version) => {
-        write!(f, "Ipv4Context: Version field is not 6 found {}", version)
+        write!(f, "Ipv6Context: Version field is not 6 found {}", version)
+      }
+      Self::Truncated(len) => {
+        write!(
+          f,
+          "Ipv6Context: stream is shorter than header length {}",
+          len
+        )
+      }
+      Self::OptionLen(len) => {
+        write!(f, "Ipv6Context: option length {} is invalid", len)
+      }
+      Self::BadJumbo(length) => {
+        write!(
+          f,
+          "Ipv6Context: jumbo payload length {} is inconsistent",
+          length
+        )
+      }
+      Self::SegmentList(last_entry) => {
+        write!(
+          f,
+          "Ipv6Context: segment list of last entry {} exceed the header",
+          last_entry
+        )
       }
     }
   }
@@ -106,6 +374,11 @@ where
   Context: Contexting<UtilsAtom<Stream>>,
   Context: Contexting<Ipv6Atom>,
 {
+  let Success { stream, .. } = take(IPv6Header::LEN)
+    .peek()
+    .add_atom(|| Ipv6Atom::Truncated(IPv6Header::LEN))
+    .parse(stream)?;
+
   let Success {
     token: (version, tc_0),
     stream,
@@ -158,37 +431,349 @@ where
     stream,
   } = octet.fill().map(Ipv6Addr::from).parse(stream)?;
 
+  let token = IPv6Header {
+    version,
+    ds: (tc_0 << 2) + (tc_1 >> 2),
+    ecn: tc_1 & 0b11,
+    flow_label,
+    length,
+    next_header,
+    hop_limit,
+    source_addr,
+    dest_addr,
+  };
+
+  #[cfg(feature = "tracing")]
+  crate::trace_layer(&token);
+
+  Parsed::Success { token, stream }
+}
+
+/// Parse IPv6 fragment extension header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipv6_fragment_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Ipv6FragmentHeader, Stream, Context>
+where
+  Stream: Clone,
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+{
+  let Success {
+    token: (next_header, _reserved),
+    stream,
+  } = ip_protocol::ip_protocol.and(octet).parse(stream)?;
+
+  let Success {
+    token: offset,
+    stream,
+  } = octet.fill().map(u16::from_be_bytes).parse(stream)?;
+
+  let Success { token: id, stream } = octet.fill().map(u32::from_be_bytes).parse(stream)?;
+
   Parsed::Success {
-    token: IPv6Header {
-      version,
-      ds: (tc_0 << 2) + (tc_1 >> 2),
-      ecn: tc_1 & 0b11,
-      flow_label,
-      length,
+    token: Ipv6FragmentHeader {
       next_header,
-      hop_limit,
-      source_addr,
-      dest_addr,
+      fragment_offset: offset >> 3u8,
+      more_fragments: offset & 1 != 0,
+      id,
     },
     stream,
   }
 }
 
+// option and its size, remaining is what's left of the extension header
+fn ipv6_option<Stream, Context>(
+  stream: Stream, remaining: usize,
+) -> Parsed<(Ipv6Option<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  Stream: Clone,
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv6Atom>,
+{
+  let Success {
+    token: kind,
+    stream,
+  } = octet.parse(stream)?;
+  if kind == 0 {
+    return Parsed::Success {
+      token: (Ipv6Option::Pad1, 1),
+      stream,
+    };
+  }
+
+  let Success { token: len, stream } = octet
+    .try_map(|len| {
+      if usize::from(len) + 2 <= remaining {
+        Ok(len)
+      } else {
+        Err(Context::new(Ipv6Atom::OptionLen(len)))
+      }
+    })
+    .parse(stream)?;
+
+  let size = usize::from(len) + 2;
+  match (kind, len) {
+    (1, len) => take(usize::from(len))
+      .map(|_| (Ipv6Option::PadN(len), size))
+      .parse(stream),
+    (5, 2) => u16_be
+      .map(|value| (Ipv6Option::RouterAlert(value), size))
+      .parse(stream),
+    (0xC2, 4) => u32_be
+      .map(|length| (Ipv6Option::Jumbo(length), size))
+      .parse(stream),
+    (5 | 0xC2, len) => Parsed::Failure(Context::new(Ipv6Atom::OptionLen(len))),
+    (kind, len) => take(usize::from(len))
+      .map(|data| (Ipv6Option::Unknown(kind, data), size))
+      .parse(stream),
+  }
+}
+
+/// Parse IPv6 Hop-by-Hop Options extension header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipv6_hop_by_hop_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Ipv6HopByHopHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  Stream: Clone,
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv6Atom>,
+{
+  let Success {
+    token: (next_header, len),
+    stream,
+  } = ip_protocol::ip_protocol.and(octet).parse(stream)?;
+
+  // length is in 8 octets units, not including the first 8 octets
+  let header_len = (usize::from(len) + 1) * 8;
+  let Success { mut stream, .. } = take(header_len - 2)
+    .peek()
+    .add_atom(|| Ipv6Atom::Truncated(header_len))
+    .parse(stream)?;
+
+  let mut remaining = header_len - 2;
+  let mut options = Vec::new();
+  while remaining > 0 {
+    let Success {
+      token: (option, size),
+      stream: next,
+    } = ipv6_option(stream, remaining)?;
+    options.push(option);
+    remaining -= size;
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: Ipv6HopByHopHeader {
+      next_header,
+      options,
+    },
+    stream,
+  }
+}
+
+// TLV and its size, remaining is what's left of the header
+fn srh_tlv<Stream, Context>(
+  stream: Stream, remaining: usize,
+) -> Parsed<(SrhTlv<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  Stream: Clone,
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv6Atom>,
+{
+  let Success {
+    token: kind,
+    stream,
+  } = octet.parse(stream)?;
+  if kind == 0 {
+    return Parsed::Success {
+      token: (SrhTlv::Pad1, 1),
+      stream,
+    };
+  }
+
+  let Success { token: len, stream } = octet
+    .try_map(|len| {
+      if usize::from(len) + 2 <= remaining {
+        Ok(len)
+      } else {
+        Err(Context::new(Ipv6Atom::OptionLen(len)))
+      }
+    })
+    .parse(stream)?;
+
+  let size = usize::from(len) + 2;
+  match (kind, len) {
+    (4, len) => take(usize::from(len))
+      .map(|_| (SrhTlv::PadN(len), size))
+      .parse(stream),
+    (5, 6..) => (u16_be, u32_be, take(usize::from(len - 6)))
+      .map(|(d_flag, key_id, hmac)| {
+        let d_flag = d_flag & 0x8000 != 0;
+        (
+          SrhTlv::Hmac {
+            d_flag,
+            key_id,
+            hmac,
+          },
+          size,
+        )
+      })
+      .parse(stream),
+    (5, len) => Parsed::Failure(Context::new(Ipv6Atom::OptionLen(len))),
+    (kind, len) => take(usize::from(len))
+      .map(|value| (SrhTlv::Unknown(kind, value), size))
+      .parse(stream),
+  }
+}
+
+/// Parse IPv6 Routing extension header, Segment Routing Header are decoded
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ipv6_routing_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Ipv6RoutingHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  Stream: Clone,
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv6Atom>,
+{
+  let Success {
+    token: (next_header, len, routing_type, segments_left),
+    stream,
+  } = (ip_protocol::ip_protocol, octet, octet, octet).parse(stream)?;
+
+  // length is in 8 octets units, not including the first 8 octets
+  let header_len = (usize::from(len) + 1) * 8;
+  let Success { stream, .. } = take(header_len - 4)
+    .peek()
+    .add_atom(|| Ipv6Atom::Truncated(header_len))
+    .parse(stream)?;
+
+  if routing_type != Ipv6SegmentRoutingHeader::<()>::ROUTING_TYPE {
+    return take(header_len - 4)
+      .map(|data| Ipv6RoutingHeader::Other {
+        next_header,
+        routing_type,
+        segments_left,
+        data,
+      })
+      .parse(stream);
+  }
+
+  let Success {
+    token: (last_entry, flags, tag),
+    stream,
+  } = (octet, octet, u16_be).parse(stream)?;
+
+  let segments_len = (usize::from(last_entry) + 1) * 16;
+  let Some(mut remaining) = (header_len - 8).checked_sub(segments_len) else {
+    return Parsed::Failure(Context::new(Ipv6Atom::SegmentList(last_entry)));
+  };
+  let Success {
+    token: segments,
+    mut stream,
+  } = octet
+    .fill()
+    .map(Ipv6Addr::from)
+    .fold_bounds(usize::from(last_entry) + 1, Vec::new, Acc::acc)
+    .parse(stream)?;
+
+  let mut tlvs = Vec::new();
+  while remaining > 0 {
+    let Success {
+      token: (tlv, size),
+      stream: next,
+    } = srh_tlv(stream, remaining)?;
+    tlvs.push(tlv);
+    remaining -= size;
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: Ipv6RoutingHeader::SegmentRouting(Ipv6SegmentRoutingHeader {
+      next_header,
+      segments_left,
+      last_entry,
+      flags,
+      tag,
+      segments,
+      tlvs,
+    }),
+    stream,
+  }
+}
+
 #[cfg(test)]
 mod tests {
+  use core::fmt::Debug;
   use std::net::Ipv6Addr;
 
   use binator::{
-    context::Ignore,
+    context::{
+      Ignore,
+      Tree,
+    },
+    utils::UtilsAtom,
+    CoreAtom,
     Parsed,
+    ProvideElement,
+    Streaming,
+  };
+  use derive_more::{
+    Display,
+    From,
   };
   use pretty_assertions::assert_eq;
 
   use super::{
     IPProtocol,
     IPv6Header,
+    Ipv6Atom,
+    Ipv6FragmentHeader,
+    Ipv6HopByHopHeader,
+    Ipv6Option,
+    Ipv6RoutingHeader,
+    SrhTlv,
   };
 
+  #[derive(Display, Debug, Clone, PartialEq, From)]
+  enum FromAtom<Stream: Streaming + Debug, Error = <Stream as Streaming>::Error> {
+    Core(CoreAtom<Stream, Error>),
+    Utils(UtilsAtom<Stream>),
+    Ipv6(Ipv6Atom),
+  }
+
+  type HandleAtom<Stream> = Tree<FromAtom<Stream>>;
+
   #[test]
   fn ipv6_header() {
     let bytes = [
@@ -210,6 +795,8 @@ mod tests {
         0x2001, 0xDB8, 0x7890, 0x2AE9, 0x908F, 0xA9F4, 0x2F4A, 0x9B80,
       ),
     };
+    assert_eq!(IPv6Header::from_bytes(&bytes), Some(expectation));
+    assert_eq!(expectation.to_bytes(), bytes);
     assert_eq!(
       super::ipv6_header::<_, Ignore>(&bytes[..]),
       Parsed::Success {
@@ -217,5 +804,132 @@ mod tests {
         stream: "".as_bytes(),
       }
     );
+
+    let context = super::ipv6_header::<_, HandleAtom<_>>(&bytes[..20]).unwrap_context();
+    assert_eq!(context.last(), &FromAtom::Ipv6(Ipv6Atom::Truncated(40)));
+    let mut bytes = bytes;
+    bytes[0] = 0x40;
+    let context = super::ipv6_header::<_, HandleAtom<_>>(&bytes[..]).unwrap_context();
+    assert_eq!(context.last(), &FromAtom::Ipv6(Ipv6Atom::Version(4)));
+  }
+
+  #[test]
+  fn ipv6_fragment_header() {
+    let bytes = [0x11, 0x00, 0x05, 0x39, 0x12, 0x34, 0x56, 0x78, 0xAA];
+
+    assert_eq!(
+      super::ipv6_fragment_header::<_, Ignore>(&bytes[..]),
+      Parsed::Success {
+        token: Ipv6FragmentHeader {
+          next_header: IPProtocol::UDP,
+          fragment_offset: 167,
+          more_fragments: true,
+          id: 0x12345678,
+        },
+        stream: &[0xAA][..],
+      }
+    );
+  }
+
+  #[test]
+  fn ipv6_jumbogram() {
+    let bytes = [
+      0x3A, 0x00, 0xC2, 0x04, 0x00, 0x01, 0x86, 0xA0, 0x05, 0x02, 0x00, 0x00, 0x1E, 0x02, 0x00,
+      0x00, 0xAA,
+    ];
+
+    let Parsed::Success { token, stream } = super::ipv6_hop_by_hop_header::<_, Ignore>(&bytes[..8])
+    else {
+      panic!("expected success");
+    };
+    assert_eq!(
+      token,
+      Ipv6HopByHopHeader {
+        next_header: IPProtocol::ICMP_6,
+        options: vec![Ipv6Option::Jumbo(100_000)],
+      }
+    );
+    assert_eq!(stream, b"");
+
+    let mut header = IPv6Header::from_bytes(&[0x60; IPv6Header::LEN]).unwrap();
+    header.length = 0;
+    assert_eq!(header.payload_length(Some(&token)), Ok(100_000));
+    header.length = 8;
+    assert_eq!(
+      header.payload_length(Some(&token)),
+      Err(Ipv6Atom::BadJumbo(100_000))
+    );
+    assert_eq!(header.payload_length::<&[u8]>(None), Ok(8));
+
+    let mut bytes = bytes;
+    bytes[1] = 1;
+    let Parsed::Success { token, stream } = super::ipv6_hop_by_hop_header::<_, Ignore>(&bytes[..])
+    else {
+      panic!("expected success");
+    };
+    assert_eq!(
+      token.options,
+      [
+        Ipv6Option::Jumbo(100_000),
+        Ipv6Option::RouterAlert(0),
+        Ipv6Option::Unknown(0x1E, &[0x00, 0x00][..]),
+      ]
+    );
+    assert_eq!(stream, [0xAA]);
+
+    bytes[13] = 0x03;
+    assert!(matches!(
+      super::ipv6_hop_by_hop_header::<_, Ignore>(&bytes[..]),
+      Parsed::Failure(_)
+    ));
+  }
+
+  #[test]
+  fn ipv6_segment_routing_header() {
+    let mut bytes = vec![0x3A, 0x05, 0x04, 0x01, 0x01, 0x00, 0x00, 0x2A];
+    bytes.extend_from_slice(&"2001:db8::2".parse::<Ipv6Addr>().unwrap().octets());
+    bytes.extend_from_slice(&"2001:db8::1".parse::<Ipv6Addr>().unwrap().octets());
+    bytes.extend_from_slice(&[0x05, 0x06, 0x80, 0x00, 0x00, 0x00, 0x00, 0x07]);
+    bytes.push(0xAA);
+
+    let Parsed::Success { token, stream } =
+      super::ipv6_routing_header::<_, HandleAtom<_>>(&bytes[..])
+    else {
+      panic!("expected success");
+    };
+    assert_eq!(stream, [0xAA]);
+    assert_eq!(token.next_header(), IPProtocol::ICMP_6);
+    let Ipv6RoutingHeader::SegmentRouting(srh) = token else {
+      panic!("expected a segment routing header");
+    };
+    assert_eq!((srh.segments_left, srh.last_entry, srh.tag), (1, 1, 42));
+    assert_eq!(srh.active_segment(), Some("2001:db8::1".parse().unwrap()));
+    assert_eq!(srh.final_segment(), Some("2001:db8::2".parse().unwrap()));
+    assert_eq!(
+      srh.tlvs,
+      [SrhTlv::Hmac {
+        d_flag: true,
+        key_id: 7,
+        hmac: &[][..],
+      }]
+    );
+
+    bytes[4] = 2;
+    let context = super::ipv6_routing_header::<_, HandleAtom<_>>(&bytes[..]).unwrap_context();
+    assert_eq!(context.last(), &FromAtom::Ipv6(Ipv6Atom::SegmentList(2)));
+
+    let legacy = [0x06, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00];
+    assert_eq!(
+      super::ipv6_routing_header::<_, Ignore>(&legacy[..]),
+      Parsed::Success {
+        token: Ipv6RoutingHeader::Other {
+          next_header: IPProtocol::TCP,
+          routing_type: 2,
+          segments_left: 0,
+          data: &[0x00, 0x00, 0x00, 0x00][..],
+        },
+        stream: &[][..],
+      }
+    );
   }
 }
@@ -0,0 +1,772 @@
+//! Handles parsing of Kerberos V5 messages, encrypted parts are left as is.
+//! Over TCP each message is prefixed by its length on 4 bytes.
+//! <https://www.rfc-editor.org/rfc/rfc4120>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::take,
+  utils::Utils,
+  Contexting,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  ber_expect,
+  ber_header,
+  ber_integer,
+  struct_variants,
+  BerParse,
+  BerTag,
+  BerTlv,
+  Port,
+};
+
+/// UDP and TCP port of the KDC
+pub const KERBEROS_PORT: Port = Port(88);
+
+/// Meta trait for kerberos combinator
+pub trait KerberosParse<Stream, Context> = where
+  (): BerParse<Stream, Context>,
+  Context: Contexting<KerberosAtom>;
+
+/// Atom produced by kerberos parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KerberosAtom {
+  /// When the message is not tagged with an application tag
+  Message(BerTag),
+  /// When the protocol version is not 5
+  Version(i64),
+  /// When a mandatory field is absent
+  Missing(&'static str),
+  /// When the fields overflow their sequence
+  Length(usize),
+}
+
+impl Display for KerberosAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      KerberosAtom::Message(tag) => write!(f, "Message: {} is not a message", tag),
+      KerberosAtom::Version(version) => write!(f, "Version: expected 5 found {}", version),
+      KerberosAtom::Missing(field) => write!(f, "Missing: field {} is mandatory", field),
+      KerberosAtom::Length(length) => {
+        write!(f, "Length: length {} doesn't match its content", length)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  KerberosMessageType, message_type, u8:
+    /// KRB_AS_REQ
+    AS_REQ => 10,
+    /// KRB_AS_REP
+    AS_REP => 11,
+    /// KRB_TGS_REQ
+    TGS_REQ => 12,
+    /// KRB_TGS_REP
+    TGS_REP => 13,
+    /// KRB_AP_REQ
+    AP_REQ => 14,
+    /// KRB_AP_REP
+    AP_REP => 15,
+    /// KRB_SAFE
+    SAFE => 20,
+    /// KRB_PRIV
+    PRIV => 21,
+    /// KRB_CRED
+    CRED => 22,
+    /// KRB_ERROR
+    ERROR => 30,
+}
+
+struct_variants! {
+  KerberosNameType, name_type, i32:
+    /// NT-UNKNOWN
+    UNKNOWN => 0,
+    /// NT-PRINCIPAL, users
+    PRINCIPAL => 1,
+    /// NT-SRV-INST, services like krbtgt
+    SRV_INST => 2,
+    /// NT-SRV-HST, services with a host name
+    SRV_HST => 3,
+    /// NT-SRV-XHST
+    SRV_XHST => 4,
+    /// NT-UID
+    UID => 5,
+    /// NT-X500-PRINCIPAL
+    X500_PRINCIPAL => 6,
+    /// NT-SMTP-NAME
+    SMTP_NAME => 7,
+    /// NT-ENTERPRISE
+    ENTERPRISE => 10,
+}
+
+struct_variants! {
+  KerberosEncryptionType, etype, i32:
+    /// des-cbc-crc
+    DES_CBC_CRC => 1,
+    /// des-cbc-md4
+    DES_CBC_MD4 => 2,
+    /// des-cbc-md5
+    DES_CBC_MD5 => 3,
+    /// des3-cbc-sha1-kd
+    DES3_CBC_SHA1_KD => 16,
+    /// aes128-cts-hmac-sha1-96
+    AES128_CTS_HMAC_SHA1_96 => 17,
+    /// aes256-cts-hmac-sha1-96
+    AES256_CTS_HMAC_SHA1_96 => 18,
+    /// aes128-cts-hmac-sha256-128
+    AES128_CTS_HMAC_SHA256_128 => 19,
+    /// aes256-cts-hmac-sha384-192
+    AES256_CTS_HMAC_SHA384_192 => 20,
+    /// rc4-hmac, the target of Kerberoasting
+    RC4_HMAC => 23,
+    /// rc4-hmac-exp
+    RC4_HMAC_EXP => 24,
+}
+
+/// Name of a principal, the realm is carried aside
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosPrincipalName<Span> {
+  /// Name type
+  pub name_type: KerberosNameType,
+  /// Components of the name, for example `HTTP` and `host.example.com`
+  pub name: Vec<Span>,
+}
+
+/// Encrypted data, the cipher is not decrypted
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosEncryptedData<Span> {
+  /// Encryption type
+  pub etype: KerberosEncryptionType,
+  /// Version of the key
+  pub kvno: Option<u32>,
+  /// Cipher text
+  pub cipher: Span,
+}
+
+/// Ticket
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosTicket<Span> {
+  /// Realm of the service
+  pub realm: Span,
+  /// Name of the service
+  pub sname: KerberosPrincipalName<Span>,
+  /// Encrypted part
+  pub enc_part: KerberosEncryptedData<Span>,
+}
+
+/// Pre-authentication data
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosPaData<Span> {
+  /// Type
+  pub padata_type: i32,
+  /// Value
+  pub value: Span,
+}
+
+/// Body of KRB_AS_REQ and KRB_TGS_REQ
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosKdcReqBody<Span> {
+  /// KDC options as a bit string, its first byte is the number of unused
+  /// bits
+  pub kdc_options: Span,
+  /// Name of the client, only in KRB_AS_REQ
+  pub cname: Option<KerberosPrincipalName<Span>>,
+  /// Realm of the server
+  pub realm: Span,
+  /// Name of the server
+  pub sname: Option<KerberosPrincipalName<Span>>,
+  /// Random number
+  pub nonce: u32,
+  /// Encryption types supported by the client, in order of preference
+  pub etypes: Vec<KerberosEncryptionType>,
+}
+
+/// KRB_AS_REQ and KRB_TGS_REQ
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosKdcReq<Span> {
+  /// Pre-authentication data, the absence of it in KRB_AS_REQ allow
+  /// AS-REP roasting
+  pub padata: Vec<KerberosPaData<Span>>,
+  /// Body
+  pub body: KerberosKdcReqBody<Span>,
+}
+
+/// KRB_AS_REP and KRB_TGS_REP
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosKdcRep<Span> {
+  /// Pre-authentication data
+  pub padata: Vec<KerberosPaData<Span>>,
+  /// Realm of the client
+  pub crealm: Span,
+  /// Name of the client
+  pub cname: KerberosPrincipalName<Span>,
+  /// Ticket
+  pub ticket: KerberosTicket<Span>,
+  /// Encrypted part, for the client
+  pub enc_part: KerberosEncryptedData<Span>,
+}
+
+/// KRB_AP_REQ
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct KerberosApReq<Span> {
+  /// AP options as a bit string, its first byte is the number of unused bits
+  pub ap_options: Span,
+  /// Ticket
+  pub ticket: KerberosTicket<Span>,
+  /// Encrypted authenticator
+  pub authenticator: KerberosEncryptedData<Span>,
+}
+
+/// Kerberos message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum KerberosMessage<Span> {
+  /// KRB_AS_REQ
+  AsReq(KerberosKdcReq<Span>),
+  /// KRB_AS_REP
+  AsRep(KerberosKdcRep<Span>),
+  /// KRB_TGS_REQ
+  TgsReq(KerberosKdcReq<Span>),
+  /// KRB_TGS_REP
+  TgsRep(KerberosKdcRep<Span>),
+  /// KRB_AP_REQ
+  ApReq(KerberosApReq<Span>),
+  /// Any other message, undecoded
+  Other(BerTlv<Span>),
+}
+
+impl<Span> KerberosMessage<Span> {
+  /// Return the message type
+  pub fn message_type(&self) -> KerberosMessageType {
+    match self {
+      Self::AsReq(_) => KerberosMessageType::AS_REQ,
+      Self::AsRep(_) => KerberosMessageType::AS_REP,
+      Self::TgsReq(_) => KerberosMessageType::TGS_REQ,
+      Self::TgsRep(_) => KerberosMessageType::TGS_REP,
+      Self::ApReq(_) => KerberosMessageType::AP_REQ,
+      Self::Other(tlv) => KerberosMessageType::new(tlv.tag.number as u8),
+    }
+  }
+}
+
+fn missing<Token, Stream, Context>(field: &'static str) -> Parsed<Token, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  Parsed::Failure(Context::new(KerberosAtom::Missing(field)))
+}
+
+// Walk the explicitly tagged fields of a sequence, field is given the tag
+// number and the content of a field and return None for fields to skip
+fn krb_fields<Stream, Context, Field>(
+  stream: Stream, tag: BerTag, mut field: Field,
+) -> Parsed<(), Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+  Field: FnMut(u32, Stream) -> Option<Parsed<(), Stream, Context>>,
+{
+  let Success {
+    token: sequence,
+    mut stream,
+  } = ber_expect(tag).parse(stream)?;
+
+  let mut len = sequence.length;
+  while len > 0 {
+    let Success {
+      token: header,
+      stream: next,
+    } = ber_header.parse(stream)?;
+    let Some(rest) = len.checked_sub(header.total_len()) else {
+      return Parsed::Failure(Context::new(KerberosAtom::Length(sequence.length)));
+    };
+    let parsed = if header.tag.class == BerTag::CONTEXT {
+      field(header.tag.number, next.clone())
+    } else {
+      None
+    };
+    let Success { stream: next, .. } = match parsed {
+      Some(parsed) => parsed?,
+      None => take(header.length).map(|_| ()).parse(next)?,
+    };
+    len = rest;
+    stream = next;
+  }
+
+  Parsed::Success { token: (), stream }
+}
+
+// Parse a SEQUENCE OF, element parse a whole value
+fn krb_list<Stream, Context, Element, Token>(
+  stream: Stream, mut element: Element,
+) -> Parsed<Vec<Token>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+  Element: Parse<Stream, Context, Token = Token>,
+{
+  let Success {
+    token: sequence,
+    mut stream,
+  } = ber_expect(BerTag::SEQUENCE).parse(stream)?;
+
+  let mut len = sequence.length;
+  let mut list = Vec::new();
+  while len > 0 {
+    let Success { token: header, .. } = ber_header.parse(stream.clone())?;
+    let Some(rest) = len.checked_sub(header.total_len()) else {
+      return Parsed::Failure(Context::new(KerberosAtom::Length(sequence.length)));
+    };
+    let Success {
+      token,
+      stream: next,
+    } = element.parse(stream)?;
+    list.push(token);
+    len = rest;
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: list,
+    stream,
+  }
+}
+
+fn krb_int32<Stream, Context>(stream: Stream) -> Parsed<i32, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  ber_integer.map(|value| value as i32).parse(stream)
+}
+
+fn krb_string<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_expect(BerTag::GENERAL_STRING).parse(stream)?;
+  take(header.length).parse(stream)
+}
+
+fn krb_bit_string<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_expect(BerTag::BIT_STRING).parse(stream)?;
+  take(header.length).parse(stream)
+}
+
+fn krb_octet_string<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_expect(BerTag::OCTET_STRING).parse(stream)?;
+  take(header.length).parse(stream)
+}
+
+fn krb_principal_name<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosPrincipalName<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let mut name_type = None;
+  let mut name = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      0 => krb_int32(stream).map_token(|value| name_type = Some(KerberosNameType::new(value))),
+      1 => krb_list(stream, krb_string).map_token(|value| name = Some(value)),
+      _ => return None,
+    })
+  })?;
+
+  let (Some(name_type), Some(name)) = (name_type, name) else {
+    return missing("PrincipalName");
+  };
+  Parsed::Success {
+    token: KerberosPrincipalName { name_type, name },
+    stream,
+  }
+}
+
+fn krb_encrypted_data<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosEncryptedData<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let mut etype = None;
+  let mut kvno = None;
+  let mut cipher = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      0 => krb_int32(stream).map_token(|value| etype = Some(KerberosEncryptionType::new(value))),
+      1 => ber_integer
+        .parse(stream)
+        .map_token(|value| kvno = Some(value as u32)),
+      2 => krb_octet_string(stream).map_token(|value| cipher = Some(value)),
+      _ => return None,
+    })
+  })?;
+
+  let (Some(etype), Some(cipher)) = (etype, cipher) else {
+    return missing("EncryptedData");
+  };
+  Parsed::Success {
+    token: KerberosEncryptedData {
+      etype,
+      kvno,
+      cipher,
+    },
+    stream,
+  }
+}
+
+fn krb_ticket<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosTicket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let Success { stream, .. } = ber_expect(BerTag::application(1)).parse(stream)?;
+
+  let mut realm = None;
+  let mut sname = None;
+  let mut enc_part = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      1 => krb_string(stream).map_token(|value| realm = Some(value)),
+      2 => krb_principal_name(stream).map_token(|value| sname = Some(value)),
+      3 => krb_encrypted_data(stream).map_token(|value| enc_part = Some(value)),
+      _ => return None,
+    })
+  })?;
+
+  let (Some(realm), Some(sname), Some(enc_part)) = (realm, sname, enc_part) else {
+    return missing("Ticket");
+  };
+  Parsed::Success {
+    token: KerberosTicket {
+      realm,
+      sname,
+      enc_part,
+    },
+    stream,
+  }
+}
+
+fn krb_pa_data<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosPaData<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let mut padata_type = None;
+  let mut value = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      1 => krb_int32(stream).map_token(|token| padata_type = Some(token)),
+      2 => krb_octet_string(stream).map_token(|token| value = Some(token)),
+      _ => return None,
+    })
+  })?;
+
+  let (Some(padata_type), Some(value)) = (padata_type, value) else {
+    return missing("PA-DATA");
+  };
+  Parsed::Success {
+    token: KerberosPaData { padata_type, value },
+    stream,
+  }
+}
+
+fn krb_version<Stream, Context>(stream: Stream) -> Parsed<(), Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  ber_integer
+    .try_map(|version| {
+      if version == 5 {
+        Ok(())
+      } else {
+        Err(Context::new(KerberosAtom::Version(version)))
+      }
+    })
+    .parse(stream)
+}
+
+fn krb_kdc_req_body<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosKdcReqBody<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let mut kdc_options = None;
+  let mut cname = None;
+  let mut realm = None;
+  let mut sname = None;
+  let mut nonce = None;
+  let mut etypes = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      0 => krb_bit_string(stream).map_token(|value| kdc_options = Some(value)),
+      1 => krb_principal_name(stream).map_token(|value| cname = Some(value)),
+      2 => krb_string(stream).map_token(|value| realm = Some(value)),
+      3 => krb_principal_name(stream).map_token(|value| sname = Some(value)),
+      7 => ber_integer
+        .parse(stream)
+        .map_token(|value| nonce = Some(value as u32)),
+      8 => krb_list(stream, krb_int32.map(KerberosEncryptionType::new))
+        .map_token(|value| etypes = Some(value)),
+      _ => return None,
+    })
+  })?;
+
+  let (Some(kdc_options), Some(realm), Some(nonce), Some(etypes)) =
+    (kdc_options, realm, nonce, etypes)
+  else {
+    return missing("KDC-REQ-BODY");
+  };
+  Parsed::Success {
+    token: KerberosKdcReqBody {
+      kdc_options,
+      cname,
+      realm,
+      sname,
+      nonce,
+      etypes,
+    },
+    stream,
+  }
+}
+
+fn krb_kdc_req<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosKdcReq<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let mut padata = Vec::new();
+  let mut body = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      1 => krb_version(stream),
+      3 => krb_list(stream, krb_pa_data).map_token(|value| padata = value),
+      4 => krb_kdc_req_body(stream).map_token(|value| body = Some(value)),
+      _ => return None,
+    })
+  })?;
+
+  let Some(body) = body else {
+    return missing("KDC-REQ");
+  };
+  Parsed::Success {
+    token: KerberosKdcReq { padata, body },
+    stream,
+  }
+}
+
+fn krb_kdc_rep<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosKdcRep<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let mut padata = Vec::new();
+  let mut crealm = None;
+  let mut cname = None;
+  let mut ticket = None;
+  let mut enc_part = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      0 => krb_version(stream),
+      2 => krb_list(stream, krb_pa_data).map_token(|value| padata = value),
+      3 => krb_string(stream).map_token(|value| crealm = Some(value)),
+      4 => krb_principal_name(stream).map_token(|value| cname = Some(value)),
+      5 => krb_ticket(stream).map_token(|value| ticket = Some(value)),
+      6 => krb_encrypted_data(stream).map_token(|value| enc_part = Some(value)),
+      _ => return None,
+    })
+  })?;
+
+  let (Some(crealm), Some(cname), Some(ticket), Some(enc_part)) = (crealm, cname, ticket, enc_part)
+  else {
+    return missing("KDC-REP");
+  };
+  Parsed::Success {
+    token: KerberosKdcRep {
+      padata,
+      crealm,
+      cname,
+      ticket,
+      enc_part,
+    },
+    stream,
+  }
+}
+
+fn krb_ap_req<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosApReq<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let mut ap_options = None;
+  let mut ticket = None;
+  let mut authenticator = None;
+  let Success { stream, .. } = krb_fields(stream, BerTag::SEQUENCE, |number, stream| {
+    Some(match number {
+      0 => krb_version(stream),
+      2 => krb_bit_string(stream).map_token(|value| ap_options = Some(value)),
+      3 => krb_ticket(stream).map_token(|value| ticket = Some(value)),
+      4 => krb_encrypted_data(stream).map_token(|value| authenticator = Some(value)),
+      _ => return None,
+    })
+  })?;
+
+  let (Some(ap_options), Some(ticket), Some(authenticator)) = (ap_options, ticket, authenticator)
+  else {
+    return missing("AP-REQ");
+  };
+  Parsed::Success {
+    token: KerberosApReq {
+      ap_options,
+      ticket,
+      authenticator,
+    },
+    stream,
+  }
+}
+
+/// Parse a Kerberos message, KRB_AS_REQ, KRB_AS_REP, KRB_TGS_REQ, KRB_TGS_REP
+/// and KRB_AP_REQ are decoded
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn kerberos_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<KerberosMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): KerberosParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream: content,
+  } = ber_header
+    .try_map(|header| {
+      if header.tag.class == BerTag::APPLICATION && header.tag.constructed {
+        Ok(header)
+      } else {
+        Err(Context::new(KerberosAtom::Message(header.tag)))
+      }
+    })
+    .parse(stream)?;
+
+  let message_type =
+    u8::try_from(header.tag.number).map_or(KerberosMessageType::ERROR, KerberosMessageType::new);
+  match message_type {
+    KerberosMessageType::AS_REQ => krb_kdc_req(content).map_token(KerberosMessage::AsReq),
+    KerberosMessageType::AS_REP => krb_kdc_rep(content).map_token(KerberosMessage::AsRep),
+    KerberosMessageType::TGS_REQ => krb_kdc_req(content).map_token(KerberosMessage::TgsReq),
+    KerberosMessageType::TGS_REP => krb_kdc_rep(content).map_token(KerberosMessage::TgsRep),
+    KerberosMessageType::AP_REQ => krb_ap_req(content).map_token(KerberosMessage::ApReq),
+    _ => take(header.length)
+      .map(|value| {
+        KerberosMessage::Other(BerTlv {
+          tag: header.tag,
+          value,
+        })
+      })
+      .parse(content),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    KerberosEncryptionType,
+    KerberosMessage,
+    KerberosMessageType,
+    KerberosNameType,
+  };
+
+  #[test]
+  fn kerberos() {
+    let tgs_req = [
+      0x6C, 0x6E, 0x30, 0x6C, 0xA1, 0x03, 0x02, 0x01, 0x05, 0xA2, 0x03, 0x02, 0x01, 0x0C, 0xA3,
+      0x0E, 0x30, 0x0C, 0x30, 0x0A, 0xA1, 0x03, 0x02, 0x01, 0x01, 0xA2, 0x03, 0x04, 0x01, 0xAA,
+      0xA4, 0x50, 0x30, 0x4E, 0xA0, 0x07, 0x03, 0x05, 0x00, 0x40, 0x81, 0x00, 0x10, 0xA2, 0x09,
+      0x1B, 0x07, 0x45, 0x58, 0x41, 0x4D, 0x50, 0x4C, 0x45, 0xA3, 0x16, 0x30, 0x14, 0xA0, 0x03,
+      0x02, 0x01, 0x02, 0xA1, 0x0D, 0x30, 0x0B, 0x1B, 0x04, 0x48, 0x54, 0x54, 0x50, 0x1B, 0x03,
+      0x77, 0x65, 0x62, 0xA5, 0x11, 0x18, 0x0F, 0x32, 0x30, 0x33, 0x37, 0x30, 0x39, 0x31, 0x33,
+      0x30, 0x32, 0x34, 0x38, 0x30, 0x35, 0x5A, 0xA7, 0x03, 0x02, 0x01, 0x2A, 0xA8, 0x08, 0x30,
+      0x06, 0x02, 0x01, 0x17, 0x02, 0x01, 0x12,
+    ];
+    let Success { token, stream } = super::kerberos_message::<_, Ignore>(&tgs_req[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.message_type(), KerberosMessageType::TGS_REQ);
+    let KerberosMessage::TgsReq(request) = token else {
+      panic!("expected a TGS-REQ");
+    };
+    assert_eq!(request.padata.len(), 1);
+    assert_eq!(request.padata[0].padata_type, 1i32);
+    assert_eq!(request.body.realm, b"EXAMPLE");
+    assert_eq!(request.body.cname, None);
+    let sname = request.body.sname.unwrap();
+    assert_eq!(sname.name_type, KerberosNameType::SRV_INST);
+    assert_eq!(sname.name, [&b"HTTP"[..], &b"web"[..]]);
+    assert_eq!(request.body.nonce, 42u32);
+    assert_eq!(
+      request.body.etypes,
+      [
+        KerberosEncryptionType::RC4_HMAC,
+        KerberosEncryptionType::AES256_CTS_HMAC_SHA1_96
+      ]
+    );
+
+    let error = [0x7E, 0x02, 0x30, 0x00];
+    let Success { token, .. } = super::kerberos_message::<_, Ignore>(&error[..]).unwrap();
+    assert_eq!(token.message_type(), KerberosMessageType::ERROR);
+
+    assert!(matches!(
+      super::kerberos_message::<_, Ignore>(&[0x30, 0x00][..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
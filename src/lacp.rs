@@ -0,0 +1,330 @@
+//! Handles parsing of Slow Protocols frames and LACPDU of link aggregation
+//! <https://standards.ieee.org/ieee/802.1AX/6768/>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    all,
+    octet,
+    primitive::u16_be,
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+/// Destination MAC of Slow Protocols frames
+pub const SLOW_PROTOCOLS_MULTICAST: [u8; 6] = [0x01, 0x80, 0xC2, 0x00, 0x00, 0x02];
+
+/// Meta trait for lacp combinator
+pub trait LacpParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<LacpAtom>;
+
+/// Atom produced by lacp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LacpAtom {
+  /// When a TLV is not the one expected at its place
+  Tlv {
+    /// Expected type
+    expected: u8,
+    /// Found type
+    tlv_type: u8,
+    /// Found length
+    length: u8,
+  },
+}
+
+impl Display for LacpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LacpAtom::Tlv {
+        expected,
+        tlv_type,
+        length,
+      } => write!(
+        f,
+        "Tlv: expected TLV {} found TLV {} of length {}",
+        expected, tlv_type, length
+      ),
+    }
+  }
+}
+
+struct_variants! {
+  SlowProtocolSubtype, subtype, u8:
+    /// Link Aggregation Control Protocol
+    LACP => 1,
+    /// Link Aggregation Marker Protocol
+    MARKER => 2,
+    /// Ethernet OAM
+    OAM => 3,
+    /// Organization Specific Slow Protocol
+    OSSP => 10,
+}
+
+/// State of an actor or partner port
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LacpState {
+  /// Active LACP, passive otherwise
+  pub activity: bool,
+  /// Short timeout, long otherwise
+  pub timeout: bool,
+  /// The link can be aggregated, individual otherwise
+  pub aggregation: bool,
+  /// The link is in the right aggregation
+  pub synchronization: bool,
+  /// Collection of incoming frames is enabled
+  pub collecting: bool,
+  /// Distribution of outgoing frames is enabled
+  pub distributing: bool,
+  /// The partner information are administrative defaults
+  pub defaulted: bool,
+  /// The receive machine is in the expired state
+  pub expired: bool,
+}
+
+impl From<u8> for LacpState {
+  fn from(state: u8) -> Self {
+    Self {
+      activity: state & 0x01 != 0,
+      timeout: state & 0x02 != 0,
+      aggregation: state & 0x04 != 0,
+      synchronization: state & 0x08 != 0,
+      collecting: state & 0x10 != 0,
+      distributing: state & 0x20 != 0,
+      defaulted: state & 0x40 != 0,
+      expired: state & 0x80 != 0,
+    }
+  }
+}
+
+impl From<LacpState> for u8 {
+  fn from(state: LacpState) -> Self {
+    [
+      state.activity,
+      state.timeout,
+      state.aggregation,
+      state.synchronization,
+      state.collecting,
+      state.distributing,
+      state.defaulted,
+      state.expired,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0, |acc, (bit, set)| acc | u8::from(*set) << bit)
+  }
+}
+
+/// Actor or partner information
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LacpPortInfo {
+  /// System priority
+  pub system_priority: u16,
+  /// System id, a MAC address
+  pub system: [u8; 6],
+  /// Operational key
+  pub key: u16,
+  /// Port priority
+  pub port_priority: u16,
+  /// Port number
+  pub port: u16,
+  /// State
+  pub state: LacpState,
+}
+
+/// LACPDU
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lacpdu {
+  /// Version
+  pub version: u8,
+  /// Information of the sender
+  pub actor: LacpPortInfo,
+  /// What the sender know of its partner
+  pub partner: LacpPortInfo,
+  /// Maximum delay of the frame collector, in tens of microseconds
+  pub collector_max_delay: u16,
+}
+
+/// Slow Protocols frame, the payload of an ethernet frame with
+/// [crate::EtherType::SLOW_PROTOCOLS]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SlowProtocol<Span> {
+  /// LACPDU
+  Lacp(Lacpdu),
+  /// Any other subtype, the data following the subtype
+  Other(SlowProtocolSubtype, Span),
+}
+
+fn lacp_tlv_header<Stream, Context>(
+  expected: u8, expected_length: u8,
+) -> impl Parse<Stream, Context, Token = ()>
+where
+  (): LacpParse<Stream, Context>,
+{
+  (octet, octet).try_map(move |(tlv_type, length)| {
+    if tlv_type == expected && length == expected_length {
+      Ok(())
+    } else {
+      Err(Context::new(LacpAtom::Tlv {
+        expected,
+        tlv_type,
+        length,
+      }))
+    }
+  })
+}
+
+fn lacp_port_info<Stream, Context>(
+  stream: Stream, tlv_type: u8,
+) -> Parsed<LacpPortInfo, Stream, Context>
+where
+  (): LacpParse<Stream, Context>,
+{
+  (
+    lacp_tlv_header(tlv_type, 20),
+    u16_be,
+    octet.fill(),
+    u16_be,
+    u16_be,
+    u16_be,
+    octet.map(LacpState::from),
+    take(3),
+  )
+    .map(
+      |(_, system_priority, system, key, port_priority, port, state, _)| LacpPortInfo {
+        system_priority,
+        system,
+        key,
+        port_priority,
+        port,
+        state,
+      },
+    )
+    .parse(stream)
+}
+
+/// Parse a LACPDU following its subtype, up to the collector information,
+/// the stream is left at the terminator TLV
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn lacpdu<Stream, Context>(stream: Stream) -> Parsed<Lacpdu, Stream, Context>
+where
+  (): LacpParse<Stream, Context>,
+{
+  let Success {
+    token: version,
+    stream,
+  } = octet.parse(stream)?;
+  let Success {
+    token: actor,
+    stream,
+  } = lacp_port_info(stream, 1)?;
+  let Success {
+    token: partner,
+    stream,
+  } = lacp_port_info(stream, 2)?;
+
+  (lacp_tlv_header(3, 16), u16_be, take(12))
+    .map(|(_, collector_max_delay, _)| Lacpdu {
+      version,
+      actor,
+      partner,
+      collector_max_delay,
+    })
+    .parse(stream)
+}
+
+/// Parse a Slow Protocols frame
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn slow_protocol<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SlowProtocol<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): LacpParse<Stream, Context>,
+{
+  let Success {
+    token: subtype,
+    stream,
+  } = octet.map(SlowProtocolSubtype::new).parse(stream)?;
+
+  match subtype {
+    SlowProtocolSubtype::LACP => lacpdu.map(SlowProtocol::Lacp).parse(stream),
+    _ => all
+      .map(|data| SlowProtocol::Other(subtype, data))
+      .parse(stream),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    LacpState,
+    SlowProtocol,
+  };
+
+  #[test]
+  fn lacp() {
+    let frame = [
+      0x01, 0x01, 0x01, 0x14, 0x80, 0x00, 0x00, 0x1B, 0x21, 0x3C, 0x4D, 0x5E, 0x00, 0x0A, 0x80,
+      0x00, 0x00, 0x03, 0x3D, 0x00, 0x00, 0x00, 0x02, 0x14, 0xFF, 0xFF, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x00, 0x01, 0xFF, 0xFF, 0x00, 0x01, 0x42, 0x00, 0x00, 0x00, 0x03, 0x10, 0x00,
+      0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    let Success { token, stream } = super::slow_protocol::<_, Ignore>(&frame[..]).unwrap();
+    assert_eq!(stream, [0x00, 0x00]);
+    let SlowProtocol::Lacp(lacpdu) = token else {
+      panic!("expected a LACPDU");
+    };
+    assert_eq!(lacpdu.actor.system, [0x00, 0x1B, 0x21, 0x3C, 0x4D, 0x5E]);
+    assert_eq!(lacpdu.actor.key, 10);
+    assert_eq!(lacpdu.actor.port, 3);
+    let state = lacpdu.actor.state;
+    assert!(state.activity && state.aggregation && state.synchronization);
+    assert!(state.collecting && state.distributing && !state.timeout);
+    assert_eq!(u8::from(state), 0x3D);
+    assert!(lacpdu.partner.state.defaulted);
+    assert_eq!(lacpdu.partner.state, LacpState::from(0x42));
+    assert_eq!(lacpdu.collector_max_delay, 5);
+
+    let mut truncated = frame;
+    truncated[2] = 0x02;
+    assert!(matches!(
+      super::slow_protocol::<_, Ignore>(&truncated[..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
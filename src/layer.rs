@@ -0,0 +1,270 @@
+//! Per layer events for tools that want to observe a dissection
+
+use binator::{
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+#[cfg(feature = "ipv4")]
+use crate::IPv4Header;
+#[cfg(feature = "ipv6")]
+use crate::IPv6Header;
+#[cfg(feature = "tcp")]
+use crate::TcpHeader;
+#[cfg(feature = "udp")]
+use crate::UdpHeader;
+#[cfg(feature = "ethernet")]
+use crate::{
+  EthernetFrame,
+  LlcHeader,
+};
+
+/// A layer decoded by a parser of this crate, variants depend on the enabled
+/// features. Headers are copied, IPv4 and TCP ones without their options.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum LayerEvent {
+  /// Ethernet header
+  #[cfg(feature = "ethernet")]
+  Ethernet(EthernetFrame),
+  /// LLC header
+  #[cfg(feature = "ethernet")]
+  Llc(LlcHeader),
+  /// IPv4 header
+  #[cfg(feature = "ipv4")]
+  Ipv4(IPv4Header<()>),
+  /// IPv6 header
+  #[cfg(feature = "ipv6")]
+  Ipv6(IPv6Header),
+  /// TCP header
+  #[cfg(feature = "tcp")]
+  Tcp(TcpHeader<()>),
+  /// UDP header
+  #[cfg(feature = "udp")]
+  Udp(UdpHeader),
+}
+
+impl LayerEvent {
+  /// Emit a trace event with the main fields of the layer
+  #[cfg(feature = "tracing")]
+  pub fn trace(&self) {
+    match self {
+      #[cfg(feature = "ethernet")]
+      LayerEvent::Ethernet(ethernet) => tracing::trace!(
+        layer = "ethernet",
+        destination = ?ethernet.destination,
+        source = ?ethernet.source,
+        ether_type = ?ethernet.ether_type,
+        vlans = ?ethernet.vlans,
+      ),
+      #[cfg(feature = "ethernet")]
+      LayerEvent::Llc(llc) => tracing::trace!(
+        layer = "llc",
+        dsap = llc.dsap,
+        ssap = llc.ssap,
+        control = ?llc.control,
+      ),
+      #[cfg(feature = "ipv4")]
+      LayerEvent::Ipv4(ipv4) => tracing::trace!(
+        layer = "ipv4",
+        source = %ipv4.source_addr,
+        destination = %ipv4.dest_addr,
+        protocol = %ipv4.protocol,
+        length = ipv4.length,
+        ttl = ipv4.ttl,
+      ),
+      #[cfg(feature = "ipv6")]
+      LayerEvent::Ipv6(ipv6) => tracing::trace!(
+        layer = "ipv6",
+        source = %ipv6.source_addr,
+        destination = %ipv6.dest_addr,
+        next_header = %ipv6.next_header,
+        length = ipv6.length,
+        hop_limit = ipv6.hop_limit,
+      ),
+      #[cfg(feature = "tcp")]
+      LayerEvent::Tcp(tcp) => tracing::trace!(
+        layer = "tcp",
+        source_port = %tcp.source_port,
+        dest_port = %tcp.dest_port,
+        data_offset = tcp.data_offset,
+        flags = ?tcp.flags,
+        sequence_no = tcp.sequence_no,
+        ack_no = tcp.ack_no,
+        window = tcp.window,
+      ),
+      #[cfg(feature = "udp")]
+      LayerEvent::Udp(udp) => tracing::trace!(
+        layer = "udp",
+        source_port = %udp.source_port,
+        dest_port = %udp.dest_port,
+        length = udp.length,
+      ),
+    }
+  }
+}
+
+#[cfg(feature = "tracing")]
+pub(crate) fn trace_layer<Token>(token: &Token)
+where
+  Token: AsLayerEvent,
+{
+  token.as_layer_event().trace()
+}
+
+/// Token that can be reported as a [LayerEvent]
+pub trait AsLayerEvent {
+  /// Return the event describing self
+  fn as_layer_event(&self) -> LayerEvent;
+}
+
+macro_rules! as_layer_event {
+  ($($(#[$meta:meta])* $variant:ident($header:ty),)*) => {
+    $($(#[$meta])* impl AsLayerEvent for $header {
+      fn as_layer_event(&self) -> LayerEvent {
+        LayerEvent::$variant(self.clone())
+      }
+    })*
+  };
+}
+
+as_layer_event! {
+  #[cfg(feature = "ethernet")]
+  Ethernet(EthernetFrame),
+  #[cfg(feature = "ethernet")]
+  Llc(LlcHeader),
+  #[cfg(feature = "ipv6")]
+  Ipv6(IPv6Header),
+  #[cfg(feature = "udp")]
+  Udp(UdpHeader),
+}
+
+#[cfg(feature = "ipv4")]
+impl<Span> AsLayerEvent for IPv4Header<Span> {
+  fn as_layer_event(&self) -> LayerEvent {
+    LayerEvent::Ipv4(IPv4Header {
+      version: self.version,
+      ihl: self.ihl,
+      tos: self.tos,
+      length: self.length,
+      id: self.id,
+      flags: self.flags,
+      fragment_offset: self.fragment_offset,
+      ttl: self.ttl,
+      protocol: self.protocol,
+      chksum: self.chksum,
+      source_addr: self.source_addr,
+      dest_addr: self.dest_addr,
+      options: (),
+    })
+  }
+}
+
+#[cfg(feature = "tcp")]
+impl<Span> AsLayerEvent for TcpHeader<Span> {
+  fn as_layer_event(&self) -> LayerEvent {
+    LayerEvent::Tcp(TcpHeader {
+      source_port: self.source_port,
+      dest_port: self.dest_port,
+      sequence_no: self.sequence_no,
+      ack_no: self.ack_no,
+      data_offset: self.data_offset,
+      flags: self.flags.clone(),
+      window: self.window,
+      checksum: self.checksum,
+      urgent_pointer: self.urgent_pointer,
+      options: (),
+    })
+  }
+}
+
+/// Receive an event for every layer parsed through [with_sink]
+pub trait DissectionSink {
+  /// Called after a layer have been successfully parsed
+  fn on_layer(&mut self, event: LayerEvent);
+}
+
+impl<F> DissectionSink for F
+where
+  F: FnMut(LayerEvent),
+{
+  fn on_layer(&mut self, event: LayerEvent) {
+    self(event)
+  }
+}
+
+/// Implementation of [with_sink]
+pub struct WithSink<'s, Parser, Sink> {
+  parser: Parser,
+  sink: &'s mut Sink,
+}
+
+impl<'s, Stream, Context, Parser, Sink> Parse<Stream, Context> for WithSink<'s, Parser, Sink>
+where
+  Stream: Streaming,
+  Parser: Parse<Stream, Context>,
+  Parser::Token: AsLayerEvent,
+  Sink: DissectionSink,
+{
+  type Token = Parser::Token;
+
+  fn parse(&mut self, stream: Stream) -> Parsed<Self::Token, Stream, Context> {
+    let Success { token, stream } = self.parser.parse(stream)?;
+    self.sink.on_layer(token.as_layer_event());
+    Parsed::Success { token, stream }
+  }
+}
+
+/// Report the token of parser to sink on success
+pub fn with_sink<Parser, Sink>(parser: Parser, sink: &mut Sink) -> WithSink<'_, Parser, Sink> {
+  WithSink { parser, sink }
+}
+
+#[cfg(all(test, feature = "ipv4", feature = "tcp"))]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parse,
+    Success,
+  };
+
+  use super::{
+    with_sink,
+    LayerEvent,
+  };
+  use crate::{
+    ipv4_header,
+    tcp_header,
+  };
+
+  #[test]
+  fn with_sink_reports_layers() {
+    let bytes = [
+      0x45, 0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00,
+      0x6C, 0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39,
+      0xAE, 0xE6, 0x50, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00,
+    ];
+
+    let mut layers = Vec::new();
+    let mut sink = |event: LayerEvent| {
+      layers.push(match event {
+        #[cfg(feature = "ipv4")]
+        LayerEvent::Ipv4(ipv4) => format!("ipv4 {}", ipv4.source_addr),
+        #[cfg(feature = "tcp")]
+        LayerEvent::Tcp(tcp) => format!("tcp {}", tcp.dest_port),
+        _ => String::from("other"),
+      })
+    };
+
+    let Success { stream, .. } = with_sink(ipv4_header::<_, Ignore>, &mut sink)
+      .parse(&bytes[..])
+      .unwrap();
+    let Success { stream, .. } = with_sink(tcp_header::<_, Ignore>, &mut sink)
+      .parse(stream)
+      .unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(layers, ["ipv4 192.168.0.108", "tcp 80"]);
+  }
+}
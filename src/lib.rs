@@ -1,26 +1,287 @@
 #![doc = include_str!("../readme.md")]
 // #![cfg_attr(not(test), no_std)]
-#![feature(trait_alias)]
+// trait aliases are only defined by the parser modules
+#![cfg_attr(
+  any(
+    feature = "arp",
+    feature = "ber",
+    feature = "bgp",
+    feature = "bittorrent",
+    feature = "cdp",
+    feature = "dhcp",
+    feature = "dissect",
+    feature = "dnp3",
+    feature = "eapol",
+    feature = "gtp",
+    feature = "http",
+    feature = "icmp",
+    feature = "ip_addr",
+    feature = "ipfix",
+    feature = "ipsec",
+    feature = "ipv4",
+    feature = "kerberos",
+    feature = "lacp",
+    feature = "link_type",
+    feature = "lisp",
+    feature = "ppp",
+    feature = "pppoe",
+    feature = "quic",
+    feature = "rtp",
+    feature = "rtsp",
+    feature = "sixlowpan",
+    feature = "sll",
+    feature = "smb",
+    feature = "snmp",
+    feature = "socks",
+    feature = "ssdp",
+    feature = "tcp",
+    feature = "textproto",
+    feature = "tls",
+    feature = "tunnel",
+    feature = "vxlan",
+    feature = "wifi"
+  ),
+  feature(trait_alias)
+)]
 // #![feature(generic_const_exprs)]
 #![warn(missing_docs)]
 #![deny(clippy::default_numeric_fallback)]
 
+#[cfg(feature = "anonymize")]
+mod anonymize;
+#[cfg(feature = "anonymize")]
+pub use anonymize::*;
+#[cfg(feature = "batch")]
+mod batch;
+#[cfg(feature = "batch")]
+pub use batch::*;
+#[cfg(feature = "builder")]
+mod builder;
+#[cfg(feature = "builder")]
+pub use builder::*;
+#[cfg(feature = "arp")]
+mod arp;
+#[cfg(feature = "arp")]
+pub use arp::*;
+#[cfg(feature = "avtp")]
+mod avtp;
+#[cfg(feature = "avtp")]
+pub use avtp::*;
+#[cfg(feature = "ber")]
+mod ber;
+#[cfg(feature = "ber")]
+pub use ber::*;
+#[cfg(feature = "bgp")]
+mod bgp;
+#[cfg(feature = "bgp")]
+pub use bgp::*;
+#[cfg(feature = "bittorrent")]
+mod bittorrent;
+#[cfg(feature = "bittorrent")]
+pub use bittorrent::*;
+#[cfg(feature = "cdp")]
+mod cdp;
+#[cfg(feature = "cdp")]
+pub use cdp::*;
+mod checksum;
+pub use checksum::*;
+pub mod concrete;
+#[cfg(feature = "dhcp")]
+mod dhcp;
+#[cfg(feature = "dhcp")]
+pub use dhcp::*;
+#[cfg(feature = "dissect")]
+mod dissect;
+#[cfg(feature = "dissect")]
+pub use dissect::*;
+#[cfg(feature = "dnp3")]
+mod dnp3;
+#[cfg(feature = "dnp3")]
+pub use dnp3::*;
+#[cfg(feature = "eapol")]
+mod eapol;
+#[cfg(feature = "eapol")]
+pub use eapol::*;
+#[cfg(feature = "ethernet")]
 mod ether_type;
+#[cfg(feature = "ethernet")]
 pub use ether_type::*;
+#[cfg(feature = "ethernet")]
 mod ethernet;
+#[cfg(feature = "ethernet")]
 pub use ethernet::*;
+#[cfg(feature = "flow")]
+mod flow;
+#[cfg(feature = "flow")]
+pub use flow::*;
+#[cfg(feature = "framing")]
+mod framing;
+#[cfg(feature = "framing")]
+pub use framing::*;
+#[cfg(feature = "gtp")]
+mod gtp;
+#[cfg(feature = "gtp")]
+pub use gtp::*;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "http")]
+pub use http::*;
+#[cfg(feature = "icmp")]
+mod icmp;
+#[cfg(feature = "icmp")]
+pub use icmp::*;
+#[cfg(feature = "ip_addr")]
 mod ip_addr;
+#[cfg(feature = "ip_addr")]
 pub use ip_addr::*;
+#[cfg(feature = "ip_class")]
+mod ip_class;
+#[cfg(feature = "ip_class")]
+pub use ip_class::*;
 mod ip_protocol;
 pub use ip_protocol::*;
+#[cfg(feature = "ipfix")]
+mod ipfix;
+#[cfg(feature = "ipfix")]
+pub use ipfix::*;
+#[cfg(feature = "ipsec")]
+mod ipsec;
+#[cfg(feature = "ipsec")]
+pub use ipsec::*;
+#[cfg(feature = "ipv4")]
 mod ipv4;
+#[cfg(feature = "ipv4")]
 pub use ipv4::*;
+#[cfg(feature = "ipv6")]
 mod ipv6;
+#[cfg(feature = "ipv6")]
 pub use ipv6::*;
+#[cfg(feature = "kerberos")]
+mod kerberos;
+#[cfg(feature = "kerberos")]
+pub use kerberos::*;
+#[cfg(feature = "lacp")]
+mod lacp;
+#[cfg(feature = "lacp")]
+pub use lacp::*;
+#[cfg(any(
+  feature = "ethernet",
+  feature = "ipv4",
+  feature = "ipv6",
+  feature = "tcp",
+  feature = "udp"
+))]
+mod layer;
+#[cfg(any(
+  feature = "ethernet",
+  feature = "ipv4",
+  feature = "ipv6",
+  feature = "tcp",
+  feature = "udp"
+))]
+pub use layer::*;
+#[cfg(feature = "link_type")]
+mod link_type;
+#[cfg(feature = "link_type")]
+pub use link_type::*;
+#[cfg(feature = "lisp")]
+mod lisp;
+#[cfg(feature = "lisp")]
+pub use lisp::*;
+#[cfg(feature = "ethernet")]
+mod llc;
+#[cfg(feature = "ethernet")]
+pub use llc::*;
+#[cfg(feature = "nat")]
+mod nat;
+#[cfg(feature = "nat")]
+pub use nat::*;
+#[cfg(feature = "oui")]
+mod oui;
+#[cfg(feature = "oui")]
+pub use oui::*;
+mod port;
+pub use port::*;
+#[cfg(feature = "ppp")]
+mod ppp;
+#[cfg(feature = "ppp")]
+pub use ppp::*;
+#[cfg(feature = "pppoe")]
+mod pppoe;
+#[cfg(feature = "pppoe")]
+pub use pppoe::*;
+#[cfg(feature = "quic")]
+mod quic;
+#[cfg(feature = "quic")]
+pub use quic::*;
+#[cfg(feature = "reassembly")]
+pub mod reassembly;
+#[cfg(feature = "rtp")]
+mod rtp;
+#[cfg(feature = "rtp")]
+pub use rtp::*;
+#[cfg(feature = "rtsp")]
+mod rtsp;
+#[cfg(feature = "rtsp")]
+pub use rtsp::*;
+#[cfg(feature = "sixlowpan")]
+mod sixlowpan;
+#[cfg(feature = "sixlowpan")]
+pub use sixlowpan::*;
+#[cfg(feature = "smb")]
+mod smb;
+#[cfg(feature = "smb")]
+pub use smb::*;
+#[cfg(feature = "snmp")]
+mod snmp;
+#[cfg(feature = "snmp")]
+pub use snmp::*;
+#[cfg(feature = "sll")]
+mod sll;
+#[cfg(feature = "sll")]
+pub use sll::*;
+mod snaplen;
+pub use snaplen::*;
+#[cfg(feature = "stats")]
+mod stats;
+#[cfg(feature = "stats")]
+pub use stats::*;
+#[cfg(feature = "socks")]
+mod socks;
+#[cfg(feature = "socks")]
+pub use socks::*;
+#[cfg(feature = "ssdp")]
+mod ssdp;
+#[cfg(feature = "ssdp")]
+pub use ssdp::*;
+#[cfg(feature = "tcp")]
 mod tcp;
+#[cfg(feature = "tcp")]
 pub use tcp::*;
+#[cfg(feature = "textproto")]
+mod textproto;
+#[cfg(feature = "textproto")]
+pub use textproto::*;
+#[cfg(feature = "tls")]
+mod tls;
+#[cfg(feature = "tls")]
+pub use tls::*;
+#[cfg(feature = "tunnel")]
+mod tunnel;
+#[cfg(feature = "tunnel")]
+pub use tunnel::*;
+#[cfg(feature = "udp")]
 mod udp;
+#[cfg(feature = "udp")]
 pub use udp::*;
+#[cfg(feature = "vxlan")]
+mod vxlan;
+#[cfg(feature = "vxlan")]
+pub use vxlan::*;
+#[cfg(feature = "wifi")]
+mod wifi;
+#[cfg(feature = "wifi")]
+pub use wifi::*;
 
 macro_rules! pascal_name {
   ($name:ident) => {
@@ -116,7 +377,7 @@ pub(crate) use display_variants;
 pub(crate) use pascal_name;
 pub(crate) use struct_variants;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "ipv4", feature = "tcp"))]
 mod tests {
   use core::fmt::Debug;
 
@@ -145,6 +406,7 @@ mod tests {
     tcp_header,
     tcp_options,
     Ipv4Atom,
+    Port,
     TcpAtom,
     TcpOption,
   };
@@ -182,8 +444,8 @@ mod tests {
       .parse(bytes.as_slice())
       .unwrap();
 
-    assert_eq!(tcp_header.source_port, 45250);
-    assert_eq!(tcp_header.dest_port, 80);
+    assert_eq!(tcp_header.source_port, Port(45250));
+    assert_eq!(tcp_header.dest_port, Port(80));
     assert_eq!(data, b"GET /index.html\x0a");
   }
 
@@ -203,8 +465,8 @@ mod tests {
       .parse(bytes.as_slice())
       .unwrap();
 
-    assert_eq!(tcp_header.source_port, 80);
-    assert_eq!(tcp_header.dest_port, 49703);
+    assert_eq!(tcp_header.source_port, Port(80));
+    assert_eq!(tcp_header.dest_port, Port(49703));
 
     assert_eq!(stream, b"");
 
@@ -224,9 +486,8 @@ mod tests {
     assert_eq!(options[2], TcpOption::WindowScale(4));
     assert_eq!(options[3], TcpOption::SackPermitted);
     assert_eq!(options[4], TcpOption::EndOfOption);
-    assert_eq!(options[5], TcpOption::EndOfOption);
 
-    assert_eq!(options.len(), 6);
-    assert_eq!(stream, b"");
+    assert_eq!(options.len(), 5);
+    assert_eq!(stream, [0x00]);
   }
 }
@@ -0,0 +1,270 @@
+//! Start the dissection of a captured packet according to the link-layer
+//! header type of the capture
+//! <https://www.tcpdump.org/linktypes.html>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::u32_be,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+};
+
+use crate::{
+  ethernet_frame,
+  radiotap_header,
+  sll2_header,
+  sll_header,
+  struct_variants,
+  wifi_header,
+  EtherType,
+  EthernetAtom,
+  EthernetFrame,
+  RadiotapHeader,
+  SllHeader,
+  WifiAtom,
+  WifiHeader,
+};
+
+/// Meta trait for link type combinator
+pub trait LinkParse<Stream, Context> = where
+  Stream: Streaming<Span = Stream> + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  <Stream as Streaming>::Span: AsRef<[u8]>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<EthernetAtom>,
+  Context: Contexting<WifiAtom>,
+  Context: Contexting<LinkAtom>;
+
+/// Atom produced by link type parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkAtom {
+  /// When the link type has no parser
+  Unsupported(LinkType),
+  /// When a raw IP packet version is neither 4 nor 6
+  IpVersion(u8),
+}
+
+impl Display for LinkAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LinkAtom::Unsupported(link_type) => {
+        write!(f, "Unsupported: link type {} is not supported", link_type)
+      }
+      LinkAtom::IpVersion(version) => {
+        write!(f, "IpVersion: raw IP version {} is not 4 or 6", version)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  LinkType, link_type, u32:
+    /// BSD loopback, protocol family in host byte order
+    NULL => 0,
+    /// IEEE 802.3 Ethernet
+    ETHERNET => 1,
+    /// Raw IPv4 or IPv6
+    RAW => 101,
+    /// IEEE 802.11 wireless LAN
+    IEEE802_11 => 105,
+    /// OpenBSD loopback, protocol family in network byte order
+    LOOP => 108,
+    /// Linux cooked capture v1
+    LINUX_SLL => 113,
+    /// Radiotap header followed by an 802.11 frame
+    IEEE802_11_RADIOTAP => 127,
+    /// Raw IPv4
+    IPV4 => 228,
+    /// Raw IPv6
+    IPV6 => 229,
+    /// Linux cooked capture v2
+    LINUX_SLL2 => 276,
+}
+
+/// Loopback header of [LinkType::NULL] and [LinkType::LOOP] captures
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoopbackHeader {
+  /// Protocol family, AF_INET or one of the AF_INET6 values
+  pub family: u32,
+}
+
+impl LoopbackHeader {
+  /// AF_INET on every system
+  pub const AF_INET: u32 = 2;
+  /// AF_INET6 of Linux, NetBSD and OpenBSD, FreeBSD and Darwin
+  pub const AF_INET6: [u32; 4] = [10, 24, 28, 30];
+  /// Length of a loopback header
+  pub const LEN: usize = 4;
+
+  /// Return the EtherType matching the family, None if it's not IP
+  pub fn ether_type(&self) -> Option<EtherType> {
+    match self.family {
+      Self::AF_INET => Some(EtherType::IPV4),
+      family if Self::AF_INET6.contains(&family) => Some(EtherType::IPV6),
+      _ => None,
+    }
+  }
+}
+
+/// Link-layer header returned by [link_layer]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LinkFrame {
+  /// Ethernet header
+  Ethernet(EthernetFrame),
+  /// Loopback header
+  Loopback(LoopbackHeader),
+  /// Linux cooked capture header
+  Sll(SllHeader),
+  /// No header, the packet start with an IPv4 or IPv6 header
+  Raw(EtherType),
+  /// 802.11 MAC header
+  Wifi(WifiHeader),
+  /// Radiotap header followed by an 802.11 MAC header
+  Radiotap(RadiotapHeader, WifiHeader),
+}
+
+impl LinkFrame {
+  /// Return the EtherType of the payload, None if it's not known
+  pub fn ether_type(&self) -> Option<EtherType> {
+    match self {
+      LinkFrame::Ethernet(ethernet) => ethernet.payload_ether_type(),
+      LinkFrame::Loopback(loopback) => loopback.ether_type(),
+      LinkFrame::Sll(sll) => (sll.protocol > EtherType::LANMAX).then_some(sll.protocol),
+      LinkFrame::Raw(ether_type) => Some(*ether_type),
+      // the 802.11 body start with an LLC header
+      LinkFrame::Wifi(_) | LinkFrame::Radiotap(..) => None,
+    }
+  }
+}
+
+/// Parse the loopback header of [LinkType::NULL] captures, written in the
+/// byte order of the capturing host. Family values are small so the order is
+/// guessed from the zero bytes.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn loopback_header<Stream, Context>(stream: Stream) -> Parsed<LoopbackHeader, Stream, Context>
+where
+  (): LinkParse<Stream, Context>,
+{
+  octet
+    .fill()
+    .map(|bytes: [u8; 4]| {
+      let family = match bytes {
+        [0, 0, ..] => u32::from_be_bytes(bytes),
+        _ => u32::from_le_bytes(bytes),
+      };
+      LoopbackHeader { family }
+    })
+    .parse(stream)
+}
+
+fn raw_ip<Stream, Context>(stream: Stream) -> Parsed<EtherType, Stream, Context>
+where
+  (): LinkParse<Stream, Context>,
+{
+  octet
+    .peek()
+    .try_map(|(octet, _)| match octet >> 4u8 {
+      4 => Ok(EtherType::IPV4),
+      6 => Ok(EtherType::IPV6),
+      version => Err(Context::new(LinkAtom::IpVersion(version))),
+    })
+    .parse(stream)
+}
+
+/// Return a parser of the link-layer header of link_type, the stream is left
+/// at the start of the network layer
+pub fn link_layer<Stream, Context>(
+  link_type: LinkType,
+) -> impl Parse<Stream, Context, Token = LinkFrame>
+where
+  (): LinkParse<Stream, Context>,
+{
+  move |stream: Stream| -> Parsed<LinkFrame, Stream, Context> {
+    match link_type {
+      LinkType::ETHERNET => ethernet_frame.map(LinkFrame::Ethernet).parse(stream),
+      LinkType::NULL => loopback_header.map(LinkFrame::Loopback).parse(stream),
+      LinkType::LOOP => u32_be
+        .map(|family| LinkFrame::Loopback(LoopbackHeader { family }))
+        .parse(stream),
+      LinkType::LINUX_SLL => sll_header.map(LinkFrame::Sll).parse(stream),
+      LinkType::LINUX_SLL2 => sll2_header.map(LinkFrame::Sll).parse(stream),
+      LinkType::IEEE802_11 => wifi_header.map(LinkFrame::Wifi).parse(stream),
+      LinkType::IEEE802_11_RADIOTAP => (radiotap_header, wifi_header)
+        .map(|(radiotap, wifi)| LinkFrame::Radiotap(radiotap, wifi))
+        .parse(stream),
+      LinkType::RAW => raw_ip.map(LinkFrame::Raw).parse(stream),
+      LinkType::IPV4 => Parsed::Success {
+        token: LinkFrame::Raw(EtherType::IPV4),
+        stream,
+      },
+      LinkType::IPV6 => Parsed::Success {
+        token: LinkFrame::Raw(EtherType::IPV6),
+        stream,
+      },
+      link_type => Parsed::Failure(Context::new(LinkAtom::Unsupported(link_type))),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parse,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    LinkFrame,
+    LinkType,
+    LoopbackHeader,
+  };
+  use crate::EtherType;
+
+  #[test]
+  fn link_layer() {
+    let packet = [0x1E, 0x00, 0x00, 0x00, 0x60];
+    let Success { token, stream } = super::link_layer::<_, Ignore>(LinkType::NULL)
+      .parse(&packet[..])
+      .unwrap();
+    assert_eq!(token, LinkFrame::Loopback(LoopbackHeader { family: 30 }));
+    assert_eq!(token.ether_type(), Some(EtherType::IPV6));
+    assert_eq!(stream, [0x60]);
+
+    let Success { token, stream } = super::link_layer::<_, Ignore>(LinkType::RAW)
+      .parse(&packet[4..])
+      .unwrap();
+    assert_eq!(token, LinkFrame::Raw(EtherType::IPV6));
+    assert_eq!(stream, [0x60]);
+
+    assert!(matches!(
+      super::link_layer::<_, Ignore>(LinkType::RAW).parse(&packet[..]),
+      Parsed::Failure(_)
+    ));
+    assert!(matches!(
+      super::link_layer::<_, Ignore>(LinkType::new(147)).parse(&packet[..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
@@ -0,0 +1,271 @@
+//! Handles parsing of LISP data and control headers
+//! <https://www.rfc-editor.org/rfc/rfc9300>
+//! <https://www.rfc-editor.org/rfc/rfc9301>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    all,
+    primitive::{
+      u32_be,
+      u64_be,
+    },
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// UDP port of LISP encapsulated data packets
+pub const LISP_DATA_PORT: Port = Port(4341);
+/// UDP port of LISP control messages
+pub const LISP_CONTROL_PORT: Port = Port(4342);
+
+/// Meta trait for lisp combinator
+pub trait LispParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<LispAtom>;
+
+/// Atom produced by lisp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LispAtom {
+  /// When both the N and V bits are set
+  NonceAndMapVersion,
+}
+
+impl Display for LispAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      LispAtom::NonceAndMapVersion => {
+        write!(f, "NonceAndMapVersion: N and V bits can't be both set")
+      }
+    }
+  }
+}
+
+/// LISP data header, the inner IP packet follows it
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LispDataHeader<Span> {
+  /// E bit, the sender request the nonce to be echoed
+  pub echo_nonce: bool,
+  /// 24 bits nonce, when the N bit is set
+  pub nonce: Option<u32>,
+  /// Source and destination 12 bits map-version, when the V bit is set
+  pub map_version: Option<(u16, u16)>,
+  /// 24 bits instance ID, when the I bit is set
+  pub instance_id: Option<u32>,
+  /// Locator-Status-Bits, when the L bit is set. Only 8 bits long when an
+  /// instance ID is present
+  pub locator_status_bits: Option<u32>,
+  /// Inner IP packet
+  pub packet: Span,
+}
+
+/// Parse a LISP data header, sent to [LISP_DATA_PORT]
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn lisp_data<Stream, Context>(
+  stream: Stream,
+) -> Parsed<LispDataHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): LispParse<Stream, Context>,
+{
+  let Success {
+    token: (first, second),
+    stream,
+  } = (
+    u32_be.try_map(|first| {
+      if first & 0xD000_0000 == 0xD000_0000 {
+        Err(Context::new(LispAtom::NonceAndMapVersion))
+      } else {
+        Ok(first)
+      }
+    }),
+    u32_be,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: packet,
+    stream,
+  } = all.parse(stream)?;
+
+  let [flags, ..] = first.to_be_bytes();
+  let field = first & 0x00FF_FFFF;
+  let instance = flags & 0x08 != 0;
+
+  Parsed::Success {
+    token: LispDataHeader {
+      echo_nonce: flags & 0x20 != 0,
+      nonce: (flags & 0x80 != 0).then_some(field),
+      map_version: (flags & 0x10 != 0).then_some(((field >> 12u8) as u16, (field & 0x0FFF) as u16)),
+      instance_id: instance.then_some(second >> 8u8),
+      locator_status_bits: (flags & 0x40 != 0).then_some(if instance {
+        second & 0xFF
+      } else {
+        second
+      }),
+      packet,
+    },
+    stream,
+  }
+}
+
+struct_variants! {
+  LispMessageType, message_type, u8:
+    /// Map-Request
+    MAP_REQUEST => 1,
+    /// Map-Reply
+    MAP_REPLY => 2,
+    /// Map-Register
+    MAP_REGISTER => 3,
+    /// Map-Notify
+    MAP_NOTIFY => 4,
+    /// Map-Notify-Ack
+    MAP_NOTIFY_ACK => 5,
+    /// Map-Referral
+    MAP_REFERRAL => 6,
+    /// Encapsulated Control Message
+    ENCAPSULATED_CONTROL_MESSAGE => 8,
+}
+
+/// LISP control message, sent to [LISP_CONTROL_PORT]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LispControl<Span> {
+  /// Encapsulated Control Message, the inner IP packet carry a control message
+  Encapsulated {
+    /// S and D bits and reserved bits following the type
+    flags: u32,
+    /// Inner IP packet
+    packet: Span,
+  },
+  /// Map-Request, Map-Reply, Map-Register, Map-Notify and other messages
+  /// sharing their header
+  Message {
+    /// Type of the message
+    message_type: LispMessageType,
+    /// Bits between the type and the record count, their meaning depend on
+    /// message_type
+    flags: u32,
+    /// Number of records
+    record_count: u8,
+    /// Nonce of the message
+    nonce: u64,
+    /// Remaining of the message, the records are left undecoded
+    body: Span,
+  },
+}
+
+/// Parse a LISP control message header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn lisp_control<Stream, Context>(
+  stream: Stream,
+) -> Parsed<LispControl<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): LispParse<Stream, Context>,
+{
+  let Success {
+    token: first,
+    stream,
+  } = u32_be.parse(stream)?;
+  let message_type = LispMessageType::new((first >> 28u8) as u8);
+
+  if message_type == LispMessageType::ENCAPSULATED_CONTROL_MESSAGE {
+    all
+      .map(|packet| LispControl::Encapsulated {
+        flags: first & 0x0FFF_FFFF,
+        packet,
+      })
+      .parse(stream)
+  } else {
+    (u64_be, all)
+      .map(|(nonce, body)| LispControl::Message {
+        message_type,
+        flags: first >> 8u8 & 0x000F_FFFF,
+        record_count: first.to_be_bytes()[3],
+        nonce,
+        body,
+      })
+      .parse(stream)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::{
+    LispControl,
+    LispDataHeader,
+    LispMessageType,
+  };
+
+  #[test]
+  fn lisp() {
+    let data = [0xC8, 0x12, 0x34, 0x56, 0x00, 0x00, 0x2A, 0x01, 0x45];
+    assert_eq!(
+      super::lisp_data::<_, Ignore>(&data[..]),
+      Parsed::Success {
+        token: LispDataHeader {
+          echo_nonce: false,
+          nonce: Some(0x12_3456),
+          map_version: None,
+          instance_id: Some(42),
+          locator_status_bits: Some(1),
+          packet: &[0x45][..],
+        },
+        stream: &[][..],
+      }
+    );
+    assert!(matches!(
+      super::lisp_data::<_, Ignore>(&[0xD0, 0, 0, 0, 0, 0, 0, 0][..]),
+      Parsed::Failure(_)
+    ));
+
+    let control = [
+      0x20, 0x00, 0x00, 0x01, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0xAA,
+    ];
+    assert_eq!(
+      super::lisp_control::<_, Ignore>(&control[..]),
+      Parsed::Success {
+        token: LispControl::Message {
+          message_type: LispMessageType::MAP_REPLY,
+          flags: 0,
+          record_count: 1,
+          nonce: 0x0102_0304_0506_0708,
+          body: &[0xAA][..],
+        },
+        stream: &[][..],
+      }
+    );
+  }
+}
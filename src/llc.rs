@@ -0,0 +1,241 @@
+//! Handles parsing of IEEE 802.2 LLC headers and their SNAP extension
+
+use binator::{
+  base::{
+    octet,
+    primitive::u16_be,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::EtherType;
+
+/// Control field of an LLC header, its format is given by the low bits of
+/// the first byte
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LlcControl {
+  /// Information format, 2 bytes
+  Information(u16),
+  /// Supervisory format, 2 bytes
+  Supervisory(u16),
+  /// Unnumbered format, 1 byte
+  Unnumbered(u8),
+}
+
+/// Subnetwork Access Protocol extension of an LLC header
+/// <https://en.wikipedia.org/wiki/Subnetwork_Access_Protocol>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnapHeader {
+  /// Organizationally Unique Identifier
+  pub oui: [u8; 3],
+  /// Protocol identifier, an EtherType for the encapsulation OUIs
+  pub protocol_id: u16,
+}
+
+impl SnapHeader {
+  /// Length of a SNAP header
+  pub const LEN: usize = 5;
+  /// OUI of IEEE 802.1H bridge tunnel encapsulation
+  pub const OUI_BRIDGE_TUNNEL: [u8; 3] = [0x00, 0x00, 0xF8];
+  /// OUI of Cisco, carrying CDP among others
+  pub const OUI_CISCO: [u8; 3] = [0x00, 0x00, 0x0C];
+  /// OUI of RFC 1042 encapsulation
+  pub const OUI_RFC1042: [u8; 3] = [0x00, 0x00, 0x00];
+
+  /// Return the protocol id as EtherType, None if the OUI is not an
+  /// encapsulation OUI
+  pub const fn ether_type(&self) -> Option<EtherType> {
+    match self.oui {
+      Self::OUI_RFC1042 | Self::OUI_BRIDGE_TUNNEL => Some(EtherType::new(self.protocol_id)),
+      _ => None,
+    }
+  }
+}
+
+/// IEEE 802.2 Logical Link Control header
+/// <https://en.wikipedia.org/wiki/IEEE_802.2>
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LlcHeader {
+  /// Destination Service Access Point
+  pub dsap: u8,
+  /// Source Service Access Point
+  pub ssap: u8,
+  /// Control field
+  pub control: LlcControl,
+  /// SNAP header, present when both SAP are [LlcHeader::SAP_SNAP] and the
+  /// control is unnumbered information
+  pub snap: Option<SnapHeader>,
+}
+
+// A header is never empty
+#[allow(clippy::len_without_is_empty)]
+impl LlcHeader {
+  /// Service Access Point of SNAP
+  pub const SAP_SNAP: u8 = 0xAA;
+  /// Unnumbered information control
+  pub const UI: u8 = 0x03;
+
+  /// Return the length of the header including the SNAP header, in bytes
+  pub const fn len(&self) -> usize {
+    let llc = match self.control {
+      LlcControl::Unnumbered(_) => 3,
+      LlcControl::Information(_) | LlcControl::Supervisory(_) => 4,
+    };
+    match self.snap {
+      Some(_) => llc + SnapHeader::LEN,
+      None => llc,
+    }
+  }
+
+  /// Return the EtherType of the payload carried by SNAP, None without SNAP
+  /// or with a vendor OUI
+  pub const fn ether_type(&self) -> Option<EtherType> {
+    match self.snap {
+      Some(snap) => snap.ether_type(),
+      None => None,
+    }
+  }
+}
+
+/// Parse an LLC header followed by its SNAP header if any
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn llc_header<Stream, Context>(stream: Stream) -> Parsed<LlcHeader, Stream, Context>
+where
+  Stream: Eq,
+  Stream: Streaming,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+{
+  let Success {
+    token: (dsap, ssap, first),
+    stream,
+  } = (octet, octet, octet).parse(stream)?;
+
+  let Success {
+    token: control,
+    stream,
+  } = match first & 0b11 {
+    0b11 => Parsed::Success {
+      token: LlcControl::Unnumbered(first),
+      stream,
+    },
+    format => octet
+      .map(|second| {
+        let control = u16::from_be_bytes([first, second]);
+        if format == 0b01 {
+          LlcControl::Supervisory(control)
+        } else {
+          LlcControl::Information(control)
+        }
+      })
+      .parse(stream),
+  }?;
+
+  let Success {
+    token: snap,
+    stream,
+  } = if dsap == LlcHeader::SAP_SNAP
+    && ssap == LlcHeader::SAP_SNAP
+    && control == LlcControl::Unnumbered(LlcHeader::UI)
+  {
+    (octet.fill(), u16_be)
+      .map(|(oui, protocol_id)| Some(SnapHeader { oui, protocol_id }))
+      .parse(stream)
+  } else {
+    Parsed::Success {
+      token: None,
+      stream,
+    }
+  }?;
+
+  let token = LlcHeader {
+    dsap,
+    ssap,
+    control,
+    snap,
+  };
+
+  #[cfg(feature = "tracing")]
+  crate::trace_layer(&token);
+
+  Parsed::Success { token, stream }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::{
+    LlcControl,
+    LlcHeader,
+    SnapHeader,
+  };
+  use crate::EtherType;
+
+  #[test]
+  fn llc_header() {
+    let tests = [
+      (
+        &[0x42, 0x42, 0x03][..],
+        LlcHeader {
+          dsap: 0x42,
+          ssap: 0x42,
+          control: LlcControl::Unnumbered(0x03),
+          snap: None,
+        },
+      ),
+      (
+        &[0xF0, 0xF0, 0x01, 0x02][..],
+        LlcHeader {
+          dsap: 0xF0,
+          ssap: 0xF0,
+          control: LlcControl::Supervisory(0x0102),
+          snap: None,
+        },
+      ),
+      (
+        &[0xAA, 0xAA, 0x03, 0x00, 0x00, 0x00, 0x08, 0x06][..],
+        LlcHeader {
+          dsap: 0xAA,
+          ssap: 0xAA,
+          control: LlcControl::Unnumbered(0x03),
+          snap: Some(SnapHeader {
+            oui: SnapHeader::OUI_RFC1042,
+            protocol_id: 0x0806,
+          }),
+        },
+      ),
+    ];
+
+    assert_eq!(tests[2].1.ether_type(), Some(EtherType::ARP));
+    for (stream, expected) in tests {
+      assert_eq!(expected.len(), stream.len());
+      assert_eq!(
+        super::llc_header::<_, Ignore>(stream),
+        Parsed::Success {
+          token: expected,
+          stream: &[][..],
+        }
+      );
+    }
+  }
+}
@@ -0,0 +1,237 @@
+//! Rewrite addresses and ports of a raw IP packet in place
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::IpAddr,
+};
+
+use binator::{
+  context::Ignore,
+  Parse,
+  Parsed,
+};
+
+use crate::{
+  checksum_update,
+  ipv4_header,
+  ipv6_header,
+  IPProtocol,
+  Port,
+  TcpHeader,
+  UdpHeader,
+};
+
+/// Which side of the packet a NAT rewrite apply to
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum NatSide {
+  /// Source address and source port
+  Source,
+  /// Destination address and destination port
+  Destination,
+}
+
+/// Reason a NAT rewrite was refused, the packet is left untouched
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NatError {
+  /// The packet doesn't start with a valid IPv4 or IPv6 header
+  Header,
+  /// The new address is not of the same family than the packet
+  AddressFamily,
+  /// A port was given but the packet doesn't carry a complete TCP or UDP
+  /// header
+  Transport,
+}
+
+impl Display for NatError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      NatError::Header => write!(f, "Header: packet doesn't start with an IP header"),
+      NatError::AddressFamily => write!(f, "AddressFamily: address family mismatch"),
+      NatError::Transport => write!(f, "Transport: packet doesn't carry a TCP or UDP header"),
+    }
+  }
+}
+
+/// Rewrite the address of side of packet to addr and, if port is Some, the
+/// TCP or UDP port of side. packet must start with an IPv4 or IPv6 header.
+///
+/// The IPv4 header checksum and the TCP, UDP or ICMPv6 checksum are patched
+/// with incremental updates so a valid packet stay valid. The transport
+/// header of non first IPv4 fragments is not touched.
+pub fn nat_rewrite(
+  packet: &mut [u8], side: NatSide, addr: IpAddr, port: Option<Port>,
+) -> Result<(), NatError> {
+  let (offset, new, ip_checksum, l4, protocol) = match (packet.first().map(|b| b >> 4u8), addr) {
+    (Some(4), IpAddr::V4(addr)) => {
+      let Parsed::Success { token, stream } = ipv4_header::<_, Ignore>.parse(&*packet) else {
+        return Err(NatError::Header);
+      };
+      let offset = match side {
+        NatSide::Source => 12,
+        NatSide::Destination => 16,
+      };
+      let protocol = (token.fragment_offset == 0).then_some(token.protocol);
+      (
+        offset,
+        addr.octets().to_vec(),
+        Some(10),
+        packet.len() - stream.len(),
+        protocol,
+      )
+    }
+    (Some(6), IpAddr::V6(addr)) => {
+      let Parsed::Success { token, stream } = ipv6_header::<_, Ignore>.parse(&*packet) else {
+        return Err(NatError::Header);
+      };
+      let offset = match side {
+        NatSide::Source => 8,
+        NatSide::Destination => 24,
+      };
+      (
+        offset,
+        addr.octets().to_vec(),
+        None,
+        packet.len() - stream.len(),
+        Some(token.next_header),
+      )
+    }
+    (Some(4 | 6), _) => return Err(NatError::AddressFamily),
+    _ => return Err(NatError::Header),
+  };
+
+  let (checksum_offset, header_len) = match protocol {
+    Some(IPProtocol::TCP) => (Some(16), TcpHeader::<()>::MIN_LEN),
+    Some(IPProtocol::UDP) => (Some(6), UdpHeader::LEN),
+    Some(IPProtocol::ICMP_6) => (Some(2), 4),
+    _ => (None, 0),
+  };
+  let transport = packet.len() >= l4 + header_len && header_len != 0;
+  if port.is_some() && !(transport && protocol != Some(IPProtocol::ICMP_6)) {
+    return Err(NatError::Transport);
+  }
+
+  let range = offset..offset + new.len();
+  let old = packet[range.clone()].to_vec();
+  packet[range].copy_from_slice(&new);
+  if let Some(at) = ip_checksum {
+    let checksum = u16::from_be_bytes([packet[at], packet[at + 1]]);
+    packet[at..at + 2].copy_from_slice(&checksum_update(checksum, &old, &new).to_be_bytes());
+  }
+
+  let (Some(checksum_offset), true) = (checksum_offset, transport) else {
+    return Ok(());
+  };
+  let at = l4 + checksum_offset;
+  let checksum = u16::from_be_bytes([packet[at], packet[at + 1]]);
+  let udp = protocol == Some(IPProtocol::UDP);
+  let mut checksum = checksum_update(checksum, &old, &new);
+
+  if let Some(port) = port {
+    let at = match side {
+      NatSide::Source => l4,
+      NatSide::Destination => l4 + 2,
+    };
+    let old = [packet[at], packet[at + 1]];
+    let new = port.get().to_be_bytes();
+    packet[at..at + 2].copy_from_slice(&new);
+    checksum = checksum_update(checksum, &old, &new);
+  }
+
+  // A zero UDP checksum means no checksum
+  if !(udp && packet[at..at + 2] == [0, 0]) {
+    let checksum = match checksum {
+      0 if udp => 0xFFFF,
+      checksum => checksum,
+    };
+    packet[at..at + 2].copy_from_slice(&checksum.to_be_bytes());
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+  };
+
+  use super::{
+    nat_rewrite,
+    NatError,
+    NatSide,
+  };
+  use crate::{
+    internet_checksum,
+    Port,
+  };
+
+  #[test]
+  fn nat() {
+    let mut packet = [
+      0x45, 0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00,
+      0x6C, 0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39,
+      0xAE, 0xE6, 0x50, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00, 0x47, 0x45, 0x54, 0x20, 0x2F,
+      0x69, 0x6E, 0x64, 0x65, 0x78, 0x2E, 0x68, 0x74, 0x6D, 0x6C, 0x0A,
+    ];
+    let tcp_checksum = |packet: &[u8]| {
+      let mut pseudo = Vec::new();
+      pseudo.extend_from_slice(&packet[12..20]);
+      pseudo.extend_from_slice(&[0, 6, 0, 36]);
+      pseudo.extend_from_slice(&packet[20..]);
+      internet_checksum(&pseudo)
+    };
+
+    assert_eq!(
+      nat_rewrite(
+        &mut packet,
+        NatSide::Source,
+        IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)),
+        Some(Port(61000)),
+      ),
+      Ok(())
+    );
+    assert_eq!(packet[12..16], [203, 0, 113, 7]);
+    assert_eq!(packet[20..22], 61000u16.to_be_bytes());
+    assert_eq!(internet_checksum(&packet[..20]), 0);
+    assert_eq!(tcp_checksum(&packet), 0);
+
+    assert_eq!(
+      nat_rewrite(
+        &mut packet,
+        NatSide::Destination,
+        IpAddr::V4(Ipv4Addr::new(10, 0, 0, 80)),
+        Some(Port(8080)),
+      ),
+      Ok(())
+    );
+    assert_eq!(packet[16..20], [10, 0, 0, 80]);
+    assert_eq!(packet[22..24], 8080u16.to_be_bytes());
+    assert_eq!(internet_checksum(&packet[..20]), 0);
+    assert_eq!(tcp_checksum(&packet), 0);
+
+    assert_eq!(
+      nat_rewrite(
+        &mut packet,
+        NatSide::Source,
+        IpAddr::V6(Ipv6Addr::LOCALHOST),
+        None,
+      ),
+      Err(NatError::AddressFamily)
+    );
+    assert_eq!(
+      nat_rewrite(
+        &mut packet[..30],
+        NatSide::Source,
+        IpAddr::V4(Ipv4Addr::LOCALHOST),
+        Some(Port(1)),
+      ),
+      Err(NatError::Transport)
+    );
+  }
+}
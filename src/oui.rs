@@ -0,0 +1,64 @@
+//! Built-in table of Organizationally Unique Identifiers, a small subset of
+//! the IEEE registry covering common network equipment and hypervisors
+//! <https://standards-oui.ieee.org/>
+
+/// OUI and vendor name, sorted by OUI
+pub const OUI_VENDORS: &[([u8; 3], &str)] = &[
+  ([0x00, 0x00, 0x0C], "Cisco Systems"),
+  ([0x00, 0x00, 0x5E], "IANA"),
+  ([0x00, 0x00, 0xF0], "Samsung Electronics"),
+  ([0x00, 0x03, 0x93], "Apple"),
+  ([0x00, 0x03, 0xFF], "Microsoft"),
+  ([0x00, 0x04, 0x96], "Extreme Networks"),
+  ([0x00, 0x04, 0xF2], "Polycom"),
+  ([0x00, 0x05, 0x69], "VMware"),
+  ([0x00, 0x05, 0x85], "Juniper Networks"),
+  ([0x00, 0x0B, 0x82], "Grandstream Networks"),
+  ([0x00, 0x0C, 0x29], "VMware"),
+  ([0x00, 0x0D, 0x3A], "Microsoft"),
+  ([0x00, 0x0E, 0xC6], "ASIX Electronics"),
+  ([0x00, 0x10, 0x18], "Broadcom"),
+  ([0x00, 0x14, 0x22], "Dell"),
+  ([0x00, 0x15, 0x5D], "Microsoft"),
+  ([0x00, 0x16, 0x3E], "Xensource"),
+  ([0x00, 0x17, 0x88], "Philips Lighting"),
+  ([0x00, 0x1A, 0x11], "Google"),
+  ([0x00, 0x1B, 0x21], "Intel"),
+  ([0x00, 0x1C, 0x14], "VMware"),
+  ([0x00, 0x1C, 0x42], "Parallels"),
+  ([0x00, 0x25, 0x90], "Super Micro Computer"),
+  ([0x00, 0x50, 0x56], "VMware"),
+  ([0x00, 0xA0, 0xC9], "Intel"),
+  ([0x00, 0xE0, 0x4C], "Realtek"),
+  ([0x08, 0x00, 0x27], "PCS Systemtechnik (VirtualBox)"),
+  ([0x24, 0xA4, 0x3C], "Ubiquiti"),
+  ([0x3C, 0x5A, 0xB4], "Google"),
+  ([0xB8, 0x27, 0xEB], "Raspberry Pi Foundation"),
+  ([0xDC, 0xA6, 0x32], "Raspberry Pi Trading"),
+  ([0xF0, 0x9F, 0xC2], "Ubiquiti"),
+];
+
+/// Return the vendor of oui from [OUI_VENDORS]
+pub fn oui_vendor(oui: [u8; 3]) -> Option<&'static str> {
+  OUI_VENDORS
+    .binary_search_by_key(&oui, |(oui, _)| *oui)
+    .ok()
+    .map(|i| OUI_VENDORS[i].1)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::OUI_VENDORS;
+  use crate::MacAddr;
+
+  #[test]
+  fn oui() {
+    assert!(OUI_VENDORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+    let mac = MacAddr::new(0x00, 0x0C, 0x29, 0x12, 0x34, 0x56);
+    assert_eq!(mac.vendor(), Some("VMware"));
+    assert_eq!(MacAddr::new(0x00, 0x00, 0x00, 0, 0, 1).vendor(), None);
+    // locally administered, like the QEMU 52:54:00 prefix
+    assert_eq!(MacAddr::new(0x02, 0x0C, 0x29, 0, 0, 1).vendor(), None);
+  }
+}
@@ -0,0 +1,300 @@
+//! Transport layer port number
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  num::ParseIntError,
+  str::FromStr,
+};
+
+use crate::{
+  struct_variants,
+  IPProtocol,
+};
+
+/// TCP or UDP port number
+/// <https://www.rfc-editor.org/rfc/rfc6335#section-6>
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+#[repr(transparent)]
+pub struct Port(pub u16);
+
+impl Port {
+  /// Return Port from u16
+  pub const fn new(port: u16) -> Self {
+    Self(port)
+  }
+
+  /// Return u16
+  pub const fn get(&self) -> u16 {
+    self.0
+  }
+
+  /// Return true for System Ports, 0 to 1023
+  pub const fn is_well_known(&self) -> bool {
+    self.0 < 1024
+  }
+
+  /// Return true for Dynamic and/or Private Ports, 49152 to 65535
+  pub const fn is_ephemeral(&self) -> bool {
+    self.0 >= 49152
+  }
+}
+
+impl From<u16> for Port {
+  fn from(port: u16) -> Self {
+    Self(port)
+  }
+}
+
+impl From<Port> for u16 {
+  fn from(port: Port) -> Self {
+    port.0
+  }
+}
+
+impl Display for Port {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    Display::fmt(&self.0, f)
+  }
+}
+
+impl FromStr for Port {
+  type Err = ParseIntError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    s.parse().map(Self)
+  }
+}
+
+struct_variants! {
+  ServicePort, port, u16:
+    /// File Transfer Protocol data
+    FTP_DATA         => 20,
+    /// File Transfer Protocol control
+    FTP              => 21,
+    /// Secure Shell
+    SSH              => 22,
+    /// Telnet
+    TELNET           => 23,
+    /// Simple Mail Transfer Protocol
+    SMTP             => 25,
+    /// Domain Name System
+    DNS              => 53,
+    /// DHCP server
+    DHCP_SERVER      => 67,
+    /// DHCP client
+    DHCP_CLIENT      => 68,
+    /// Trivial File Transfer Protocol
+    TFTP             => 69,
+    /// Hypertext Transfer Protocol
+    HTTP             => 80,
+    /// Kerberos
+    KERBEROS         => 88,
+    /// Post Office Protocol 3
+    POP3             => 110,
+    /// Network Time Protocol
+    NTP              => 123,
+    /// NetBIOS Name Service
+    NBNS             => 137,
+    /// NetBIOS Session Service
+    NETBIOS_SESSION  => 139,
+    /// Internet Message Access Protocol
+    IMAP             => 143,
+    /// Simple Network Management Protocol
+    SNMP             => 161,
+    /// SNMP trap
+    SNMP_TRAP        => 162,
+    /// Border Gateway Protocol
+    BGP              => 179,
+    /// Lightweight Directory Access Protocol
+    LDAP             => 389,
+    /// HTTP over TLS
+    HTTPS            => 443,
+    /// Server Message Block
+    SMB              => 445,
+    /// Syslog
+    SYSLOG           => 514,
+    /// Real Time Streaming Protocol
+    RTSP             => 554,
+    /// SMTP message submission
+    SMTP_SUBMISSION  => 587,
+    /// LDAP over TLS
+    LDAPS            => 636,
+    /// IMAP over TLS
+    IMAPS            => 993,
+    /// POP3 over TLS
+    POP3S            => 995,
+    /// SOCKS proxy
+    SOCKS            => 1080,
+    /// Message Queuing Telemetry Transport
+    MQTT             => 1883,
+    /// Simple Service Discovery Protocol
+    SSDP             => 1900,
+    /// GTP control plane
+    GTPC             => 2123,
+    /// GTP user plane
+    GTPU             => 2152,
+    /// Teredo
+    TEREDO           => 3544,
+    /// LISP data plane
+    LISP_DATA        => 4341,
+    /// LISP control plane
+    LISP_CONTROL     => 4342,
+    /// IP Flow Information Export
+    IPFIX            => 4739,
+    /// Virtual eXtensible LAN
+    VXLAN            => 4789,
+    /// BitTorrent
+    BITTORRENT       => 6881,
+    /// Distributed Network Protocol 3
+    DNP3             => 20000,
+}
+
+impl From<Port> for ServicePort {
+  fn from(port: Port) -> Self {
+    Self::new(port.0)
+  }
+}
+
+impl From<ServicePort> for Port {
+  fn from(port: ServicePort) -> Self {
+    Self(port.port())
+  }
+}
+
+/// Application protocol a payload is likely to carry, each one has a parser
+/// in this crate
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Application {
+  /// dhcp_message
+  Dhcp,
+  /// http_request or http_response
+  Http,
+  /// tls_record
+  Tls,
+  /// quic_long_packet
+  Quic,
+  /// kerberos_message
+  Kerberos,
+  /// snmp_message
+  Snmp,
+  /// bgp_message
+  Bgp,
+  /// nbns_packet
+  Nbns,
+  /// smb2_header
+  Smb,
+  /// rtsp_message
+  Rtsp,
+  /// text_command or text_reply of FTP
+  Ftp,
+  /// text_command or text_reply of SMTP
+  Smtp,
+  /// socks4_request or socks5_greeting
+  Socks,
+  /// ssdp_message
+  Ssdp,
+  /// gtpv1_header
+  GtpU,
+  /// gtpv2_header
+  GtpC,
+  /// teredo_packet
+  Teredo,
+  /// lisp_data or lisp_control
+  Lisp,
+  /// ipfix_message
+  Ipfix,
+  /// vxlan_header
+  Vxlan,
+  /// bittorrent_message
+  BitTorrent,
+  /// dnp3_frame
+  Dnp3,
+}
+
+impl Application {
+  /// Return the application usually found on port for protocol, TCP or UDP
+  pub fn from_port(protocol: IPProtocol, port: Port) -> Option<Self> {
+    let tcp = protocol == IPProtocol::TCP;
+    let udp = protocol == IPProtocol::UDP;
+    if !tcp && !udp {
+      return None;
+    }
+
+    Some(match ServicePort::from(port) {
+      ServicePort::DHCP_SERVER | ServicePort::DHCP_CLIENT if udp => Self::Dhcp,
+      ServicePort::HTTP if tcp => Self::Http,
+      ServicePort::HTTPS | ServicePort::LDAPS | ServicePort::IMAPS | ServicePort::POP3S if tcp => {
+        Self::Tls
+      }
+      ServicePort::HTTPS if udp => Self::Quic,
+      ServicePort::KERBEROS => Self::Kerberos,
+      ServicePort::SNMP | ServicePort::SNMP_TRAP if udp => Self::Snmp,
+      ServicePort::BGP if tcp => Self::Bgp,
+      ServicePort::NBNS if udp => Self::Nbns,
+      ServicePort::SMB | ServicePort::NETBIOS_SESSION if tcp => Self::Smb,
+      ServicePort::RTSP if tcp => Self::Rtsp,
+      ServicePort::FTP if tcp => Self::Ftp,
+      ServicePort::SMTP | ServicePort::SMTP_SUBMISSION if tcp => Self::Smtp,
+      ServicePort::SOCKS if tcp => Self::Socks,
+      ServicePort::SSDP if udp => Self::Ssdp,
+      ServicePort::GTPU if udp => Self::GtpU,
+      ServicePort::GTPC if udp => Self::GtpC,
+      ServicePort::TEREDO if udp => Self::Teredo,
+      ServicePort::LISP_DATA | ServicePort::LISP_CONTROL if udp => Self::Lisp,
+      ServicePort::IPFIX => Self::Ipfix,
+      ServicePort::VXLAN if udp => Self::Vxlan,
+      ServicePort::BITTORRENT if tcp => Self::BitTorrent,
+      ServicePort::DNP3 => Self::Dnp3,
+      _ => return None,
+    })
+  }
+}
+
+/// Guess the application of a TCP or UDP payload from its ports, the
+/// destination port is tried first then the source port
+pub fn guess_payload(protocol: IPProtocol, source: Port, dest: Port) -> Option<Application> {
+  Application::from_port(protocol, dest).or_else(|| Application::from_port(protocol, source))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{
+    Application,
+    Port,
+    ServicePort,
+  };
+  use crate::IPProtocol;
+
+  #[test]
+  fn port() {
+    assert_eq!("443".parse(), Ok(Port(443)));
+    assert!("65536".parse::<Port>().is_err());
+    assert_eq!(Port(8080).to_string(), "8080");
+    assert!(Port(443).is_well_known());
+    assert!(!Port(443).is_ephemeral());
+    assert!(Port(50000).is_ephemeral());
+    assert!(Port(22) < Port(80));
+    assert_eq!(u16::from(Port::from(53)), 53);
+  }
+
+  #[test]
+  fn service_port() {
+    assert_eq!(ServicePort::from(Port(443)), ServicePort::HTTPS);
+    assert_eq!(ServicePort::HTTPS.to_string(), "Https: 443");
+    assert_eq!(Port::from(ServicePort::DNS), Port(53));
+
+    let guess = |protocol, source, dest| super::guess_payload(protocol, Port(source), Port(dest));
+    assert_eq!(guess(IPProtocol::TCP, 50000, 443), Some(Application::Tls));
+    assert_eq!(guess(IPProtocol::UDP, 50000, 443), Some(Application::Quic));
+    assert_eq!(guess(IPProtocol::UDP, 67, 68), Some(Application::Dhcp));
+    assert_eq!(guess(IPProtocol::TCP, 80, 50000), Some(Application::Http));
+    assert_eq!(guess(IPProtocol::UDP, 80, 50000), None);
+    assert_eq!(guess(IPProtocol::ICMP, 0, 443), None);
+  }
+}
@@ -0,0 +1,274 @@
+//! Handles parsing of PPP frames as carried by PPPoE sessions
+//! <https://www.rfc-editor.org/rfc/rfc1661>
+
+use binator::{
+  base::{
+    all,
+    octet,
+    primitive::u16_be,
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  ipv4_header,
+  ipv6_header,
+  struct_variants,
+  IPv4Header,
+  IPv6Header,
+  Ipv4Atom,
+  Ipv6Atom,
+};
+
+/// Meta trait for ppp combinator
+pub trait PppParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv4Atom>,
+  Context: Contexting<Ipv6Atom>;
+
+struct_variants! {
+  PppProtocol, protocol, u16:
+    /// Internet Protocol version 4
+    IPV4 => 0x0021,
+    /// Internet Protocol version 6
+    IPV6 => 0x0057,
+    /// IP Control Protocol
+    IPCP => 0x8021,
+    /// IPv6 Control Protocol
+    IPV6CP => 0x8057,
+    /// Link Control Protocol
+    LCP => 0xC021,
+    /// Password Authentication Protocol
+    PAP => 0xC023,
+    /// Challenge Handshake Authentication Protocol
+    CHAP => 0xC223,
+}
+
+/// Parse the PPP protocol field, a single byte when compressed
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ppp_protocol<Stream, Context>(stream: Stream) -> Parsed<PppProtocol, Stream, Context>
+where
+  (): PppParse<Stream, Context>,
+{
+  let Success {
+    token: first,
+    stream,
+  } = octet.parse(stream)?;
+
+  // the low bit is only set in the last byte of the field
+  if first & 1 != 0 {
+    Parsed::Success {
+      token: PppProtocol::new(u16::from(first)),
+      stream,
+    }
+  } else {
+    octet
+      .map(|second| PppProtocol::new(u16::from_be_bytes([first, second])))
+      .parse(stream)
+  }
+}
+
+/// Option of a configure packet of a control protocol
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PppOption<Span> {
+  /// Option type, meaning depend on the control protocol
+  pub option_type: u8,
+  /// Option data
+  pub data: Span,
+}
+
+/// Content of a control protocol packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PppControlData<Span> {
+  /// Options of Configure-Request, Ack, Nak and Reject
+  Options(Vec<PppOption<Span>>),
+  /// Data of other codes
+  Data(Span),
+}
+
+/// Packet of LCP, IPCP and other control protocols sharing the LCP format
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PppControl<Span> {
+  /// Code
+  pub code: u8,
+  /// Identifier matching requests and replies
+  pub identifier: u8,
+  /// Content
+  pub data: PppControlData<Span>,
+}
+
+impl<Span> PppControl<Span> {
+  /// Code of Configure-Ack
+  pub const CONFIGURE_ACK: u8 = 2;
+  /// Code of Configure-Nak
+  pub const CONFIGURE_NAK: u8 = 3;
+  /// Code of Configure-Reject
+  pub const CONFIGURE_REJECT: u8 = 4;
+  /// Code of Configure-Request
+  pub const CONFIGURE_REQUEST: u8 = 1;
+  /// Code of Echo-Reply
+  pub const ECHO_REPLY: u8 = 10;
+  /// Code of Echo-Request
+  pub const ECHO_REQUEST: u8 = 9;
+  /// Length of a control packet header
+  pub const HEADER_LEN: usize = 4;
+  /// Code of Terminate-Ack
+  pub const TERMINATE_ACK: u8 = 6;
+  /// Code of Terminate-Request
+  pub const TERMINATE_REQUEST: u8 = 5;
+}
+
+/// Parse a packet of LCP, IPCP or another control protocol with the LCP
+/// format
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ppp_control<Stream, Context>(
+  stream: Stream,
+) -> Parsed<PppControl<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): PppParse<Stream, Context>,
+{
+  let Success {
+    token: (code, identifier, length),
+    stream,
+  } = (octet, octet, u16_be).parse(stream)?;
+  let len = usize::from(length).saturating_sub(PppControl::<()>::HEADER_LEN);
+
+  if !(PppControl::<()>::CONFIGURE_REQUEST..=PppControl::<()>::CONFIGURE_REJECT).contains(&code) {
+    return take(len)
+      .map(|data| PppControl {
+        code,
+        identifier,
+        data: PppControlData::Data(data),
+      })
+      .parse(stream);
+  }
+
+  let mut stream = stream;
+  let mut len = len;
+  let mut options = Vec::new();
+  // option length include its type and length bytes
+  while len >= 2 {
+    let Success {
+      token: (option_type, option_len),
+      stream: next,
+    } = (octet, octet).parse(stream)?;
+    let option_len = usize::from(option_len).clamp(2, len);
+    let Success {
+      token: data,
+      stream: next,
+    } = take(option_len - 2).parse(next)?;
+    options.push(PppOption { option_type, data });
+    len -= option_len;
+    stream = next;
+  }
+  let Success { stream, .. } = take(len).parse(stream)?;
+
+  Parsed::Success {
+    token: PppControl {
+      code,
+      identifier,
+      data: PppControlData::Options(options),
+    },
+    stream,
+  }
+}
+
+/// PPP frame dispatched on its protocol
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PppFrame<Span> {
+  /// IPv4 header, the stream is left at its payload
+  Ipv4(IPv4Header<Span>),
+  /// IPv6 header, the stream is left at its payload
+  Ipv6(IPv6Header),
+  /// Link Control Protocol packet
+  Lcp(PppControl<Span>),
+  /// IP Control Protocol packet
+  Ipcp(PppControl<Span>),
+  /// Other protocol and its payload
+  Other(PppProtocol, Span),
+}
+
+/// Parse a PPP frame without HDLC framing, as found in PPPoE sessions
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ppp_frame<Stream, Context>(
+  stream: Stream,
+) -> Parsed<PppFrame<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): PppParse<Stream, Context>,
+{
+  let Success {
+    token: protocol,
+    stream,
+  } = ppp_protocol.parse(stream)?;
+
+  match protocol {
+    PppProtocol::IPV4 => ipv4_header.map(PppFrame::Ipv4).parse(stream),
+    PppProtocol::IPV6 => ipv6_header.map(PppFrame::Ipv6).parse(stream),
+    PppProtocol::LCP => ppp_control.map(PppFrame::Lcp).parse(stream),
+    PppProtocol::IPCP => ppp_control.map(PppFrame::Ipcp).parse(stream),
+    protocol => all
+      .map(|payload| PppFrame::Other(protocol, payload))
+      .parse(stream),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    PppControlData,
+    PppFrame,
+    PppProtocol,
+  };
+
+  #[test]
+  fn ppp_frame() {
+    let lcp = [
+      0xC0, 0x21, 0x01, 0x01, 0x00, 0x0E, 0x01, 0x04, 0x05, 0xD4, 0x05, 0x06, 0x12, 0x34, 0x56,
+      0x78,
+    ];
+    let Success { token, stream } = super::ppp_frame::<_, Ignore>(&lcp[..]).unwrap();
+    assert_eq!(stream, b"");
+    let PppFrame::Lcp(lcp) = token else {
+      panic!("expected an LCP packet");
+    };
+    let PppControlData::Options(options) = lcp.data else {
+      panic!("expected options");
+    };
+    assert_eq!(options.len(), 2);
+    assert_eq!(options[0].data, [0x05, 0xD4]);
+
+    let Success { token, .. } = super::ppp_protocol::<_, Ignore>(&[0x21][..]).unwrap();
+    assert_eq!(token, PppProtocol::IPV4);
+  }
+}
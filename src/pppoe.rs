@@ -0,0 +1,276 @@
+//! Handles parsing of PPPoE Discovery and Session stages
+//! <https://www.rfc-editor.org/rfc/rfc2516>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::u16_be,
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+/// Meta trait for pppoe combinator
+pub trait PppoeParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<PppoeAtom>;
+
+/// Atom produced by pppoe parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PppoeAtom {
+  /// When the version and type byte is not 0x11
+  VersionType(u8),
+  /// When a session stage header doesn't have the session code
+  Code(PppoeCode),
+  /// When a tag is longer than the payload length
+  Length(u16),
+}
+
+impl Display for PppoeAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      PppoeAtom::VersionType(version_type) => {
+        write!(f, "VersionType: expected 0x11 found {:#04X}", version_type)
+      }
+      PppoeAtom::Code(code) => write!(f, "Code: expected Session found {}", code),
+      PppoeAtom::Length(length) => {
+        write!(f, "Length: tags overflow the payload length {}", length)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  PppoeCode, code, u8:
+    /// Session stage
+    SESSION => 0x00,
+    /// Active Discovery Offer
+    PADO => 0x07,
+    /// Active Discovery Initiation
+    PADI => 0x09,
+    /// Active Discovery Request
+    PADR => 0x19,
+    /// Active Discovery Session-confirmation
+    PADS => 0x65,
+    /// Active Discovery Terminate
+    PADT => 0xA7,
+}
+
+/// PPPoE header, shared by both stages
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PppoeHeader {
+  /// Packet code
+  pub code: PppoeCode,
+  /// Session id, 0 before the session is confirmed
+  pub session_id: u16,
+  /// Length of the payload
+  pub length: u16,
+}
+
+impl PppoeHeader {
+  /// Length of a PPPoE header
+  pub const LEN: usize = 6;
+  /// Version 1 and type 1
+  pub const VERSION_TYPE: u8 = 0x11;
+}
+
+/// Parse a PPPoE header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn pppoe_header<Stream, Context>(stream: Stream) -> Parsed<PppoeHeader, Stream, Context>
+where
+  (): PppoeParse<Stream, Context>,
+{
+  (
+    octet.try_map(|version_type| {
+      if version_type == PppoeHeader::VERSION_TYPE {
+        Ok(version_type)
+      } else {
+        Err(Context::new(PppoeAtom::VersionType(version_type)))
+      }
+    }),
+    octet.map(PppoeCode::new),
+    u16_be,
+    u16_be,
+  )
+    .map(|(_, code, session_id, length)| PppoeHeader {
+      code,
+      session_id,
+      length,
+    })
+    .parse(stream)
+}
+
+/// Parse a PPPoE Session stage header, the stream is left at the PPP frame,
+/// see [crate::ppp_frame]
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn pppoe_session<Stream, Context>(stream: Stream) -> Parsed<PppoeHeader, Stream, Context>
+where
+  (): PppoeParse<Stream, Context>,
+{
+  pppoe_header
+    .try_map(|header| {
+      if header.code == PppoeCode::SESSION {
+        Ok(header)
+      } else {
+        Err(Context::new(PppoeAtom::Code(header.code)))
+      }
+    })
+    .parse(stream)
+}
+
+/// Tag of a Discovery stage packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PppoeTag<Span> {
+  /// Tag type
+  pub tag_type: u16,
+  /// Tag value
+  pub value: Span,
+}
+
+impl<Span> PppoeTag<Span> {
+  /// Access concentrator cookie
+  pub const AC_COOKIE: u16 = 0x0104;
+  /// Access concentrator name
+  pub const AC_NAME: u16 = 0x0102;
+  /// Last tag, optional
+  pub const END_OF_LIST: u16 = 0x0000;
+  /// Value the host match replies with
+  pub const HOST_UNIQ: u16 = 0x0103;
+  /// Relay session id
+  pub const RELAY_SESSION_ID: u16 = 0x0110;
+  /// Service name
+  pub const SERVICE_NAME: u16 = 0x0101;
+}
+
+/// PPPoE Discovery stage packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PppoeDiscovery<Span> {
+  /// Header, code is PADI, PADO, PADR, PADS or PADT
+  pub header: PppoeHeader,
+  /// Tags, up to the end of list tag
+  pub tags: Vec<PppoeTag<Span>>,
+}
+
+impl<Span> PppoeDiscovery<Span> {
+  /// Return the value of the first tag of tag_type
+  pub fn tag(&self, tag_type: u16) -> Option<&Span> {
+    self
+      .tags
+      .iter()
+      .find(|tag| tag.tag_type == tag_type)
+      .map(|tag| &tag.value)
+  }
+}
+
+/// Parse a PPPoE Discovery stage packet, Ethernet padding is left in the
+/// stream
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn pppoe_discovery<Stream, Context>(
+  stream: Stream,
+) -> Parsed<PppoeDiscovery<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): PppoeParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    mut stream,
+  } = pppoe_header.parse(stream)?;
+
+  let mut len = usize::from(header.length);
+  let mut tags = Vec::new();
+  while len > 0 {
+    let Success {
+      token: (tag_type, tag_len),
+      stream: next,
+    } = (u16_be, u16_be).parse(stream)?;
+    let Some(rest) = len.checked_sub(4 + usize::from(tag_len)) else {
+      return Parsed::Failure(Context::new(PppoeAtom::Length(header.length)));
+    };
+    let Success {
+      token: value,
+      stream: next,
+    } = take(usize::from(tag_len)).parse(next)?;
+    len = rest;
+    stream = next;
+    if tag_type == PppoeTag::<()>::END_OF_LIST {
+      break;
+    }
+    tags.push(PppoeTag { tag_type, value });
+  }
+  let Success { stream, .. } = take(len).parse(stream)?;
+
+  Parsed::Success {
+    token: PppoeDiscovery { header, tags },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    PppoeCode,
+    PppoeTag,
+  };
+
+  #[test]
+  fn pppoe() {
+    let padi = [
+      0x11, 0x09, 0x00, 0x00, 0x00, 0x0C, 0x01, 0x01, 0x00, 0x00, 0x01, 0x03, 0x00, 0x04, 0xDE,
+      0xAD, 0xBE, 0xEF, 0x00,
+    ];
+    let Success { token, stream } = super::pppoe_discovery::<_, Ignore>(&padi[..]).unwrap();
+    assert_eq!(token.header.code, PppoeCode::PADI);
+    assert_eq!(token.tags.len(), 2);
+    assert_eq!(
+      token.tag(PppoeTag::<()>::HOST_UNIQ),
+      Some(&&[0xDE, 0xAD, 0xBE, 0xEF][..])
+    );
+    assert_eq!(stream, [0x00]);
+    assert!(matches!(
+      super::pppoe_session::<_, Ignore>(&padi[..]),
+      Parsed::Failure(_)
+    ));
+
+    let session = [0x11, 0x00, 0x12, 0x34, 0x00, 0x02, 0x00, 0x21];
+    let Success { token, stream } = super::pppoe_session::<_, Ignore>(&session[..]).unwrap();
+    assert_eq!(token.session_id, 0x1234);
+    assert_eq!(stream, [0x00, 0x21]);
+  }
+}
@@ -0,0 +1,458 @@
+//! Handles parsing of QUIC primitives and long header packets
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    all,
+    any,
+    end_of_stream,
+    octet,
+    primitive::u32_be,
+    take,
+    EndOfStreamAtom,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+/// Meta trait for quic combinator
+pub trait QuicParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<EndOfStreamAtom<Stream>>,
+  Context: Contexting<QuicAtom>;
+
+/// Biggest value a QUIC variable-length integer can hold, 2^62 - 1
+pub const QUIC_VARINT_MAX: u64 = (1 << 62) - 1;
+
+/// Parse a QUIC variable-length integer, the two most significant bits of the
+/// first byte encode the length of the integer (1, 2, 4 or 8 bytes).
+/// <https://www.rfc-editor.org/rfc/rfc9000#section-16>
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn quic_varint<Stream, Context>(stream: Stream) -> Parsed<u64, Stream, Context>
+where
+  Stream: Streaming,
+  Stream: Eq,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+{
+  octet
+    .and_then(|first: u8| {
+      octet.fold_bounds(
+        (1usize << (first >> 6u8)) - 1,
+        move || u64::from(first & 0x3F),
+        |acc, octet| acc << 8u8 | u64::from(octet),
+      )
+    })
+    .parse(stream)
+}
+
+/// Return the number of bytes needed to encode value, None if value is
+/// greater than [QUIC_VARINT_MAX]
+pub const fn quic_varint_len(value: u64) -> Option<usize> {
+  if value < 1 << 6 {
+    Some(1)
+  } else if value < 1 << 14 {
+    Some(2)
+  } else if value < 1 << 30 {
+    Some(4)
+  } else if value <= QUIC_VARINT_MAX {
+    Some(8)
+  } else {
+    None
+  }
+}
+
+/// Append value encoded as a QUIC variable-length integer to out using the
+/// shortest encoding, return the number of bytes written or value if it's
+/// greater than [QUIC_VARINT_MAX]
+pub fn emit_quic_varint(value: u64, out: &mut Vec<u8>) -> Result<usize, u64> {
+  let len = quic_varint_len(value).ok_or(value)?;
+  let prefix = match len {
+    1 => 0b00,
+    2 => 0b01,
+    4 => 0b10,
+    _ => 0b11,
+  };
+  let bytes = (value | prefix << (len * 8 - 2)).to_be_bytes();
+  out.extend_from_slice(&bytes[bytes.len() - len..]);
+  Ok(len)
+}
+
+/// QUIC version 1 <https://www.rfc-editor.org/rfc/rfc9000>
+pub const QUIC_VERSION_1: u32 = 0x0000_0001;
+
+/// QUIC version 2 <https://www.rfc-editor.org/rfc/rfc9369>
+pub const QUIC_VERSION_2: u32 = 0x6B33_43CF;
+
+struct_variants! {
+  QuicLongPacketType, packet_type, u8:
+    /// Initial packet, carry the first CRYPTO frames
+    INITIAL => 0x00,
+    /// 0-RTT packet, carry early data
+    ZERO_RTT => 0x01,
+    /// Handshake packet, carry handshake CRYPTO frames
+    HANDSHAKE => 0x02,
+    /// Retry packet, carry an address validation token
+    RETRY => 0x03,
+}
+
+/// Atom produced by quic parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QuicAtom {
+  /// When the header form bit is not set
+  ShortHeader,
+}
+
+impl Display for QuicAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      QuicAtom::ShortHeader => write!(f, "ShortHeader: header form bit is not set"),
+    }
+  }
+}
+
+/// Version independent part of a QUIC long header
+/// <https://www.rfc-editor.org/rfc/rfc8999#section-5.1>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuicLongHeader<Span> {
+  /// Header form, fixed bit, long packet type and type-specific bits
+  pub first_byte: u8,
+  /// QUIC version, 0 is reserved for version negotiation
+  pub version: u32,
+  /// Destination connection ID
+  pub dest_cid: Span,
+  /// Source connection ID
+  pub source_cid: Span,
+}
+
+impl<Span> QuicLongHeader<Span> {
+  /// Return the long packet type, the encoding of the type bits depend of the
+  /// version, None if the version is unknown
+  /// <https://www.rfc-editor.org/rfc/rfc9369#section-3.2>
+  pub const fn packet_type(&self) -> Option<QuicLongPacketType> {
+    let bits = (self.first_byte >> 4u8) & 0b11;
+    match self.version {
+      QUIC_VERSION_1 => Some(QuicLongPacketType::new(bits)),
+      QUIC_VERSION_2 => Some(match bits {
+        0b01 => QuicLongPacketType::INITIAL,
+        0b10 => QuicLongPacketType::ZERO_RTT,
+        0b11 => QuicLongPacketType::HANDSHAKE,
+        _ => QuicLongPacketType::RETRY,
+      }),
+      _ => None,
+    }
+  }
+}
+
+/// QUIC long header packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuicLongPacket<Span> {
+  /// Version negotiation packet, sent by a server that doesn't support the
+  /// version of the client
+  VersionNegotiation {
+    /// Long header, version is 0
+    header: QuicLongHeader<Span>,
+    /// Versions supported by the server
+    supported_versions: Vec<u32>,
+  },
+  /// Retry packet, sent by a server to validate the address of the client
+  Retry {
+    /// Long header
+    header: QuicLongHeader<Span>,
+    /// Opaque token the client must echo in its next Initial packet
+    retry_token: Span,
+    /// AEAD tag computed over the retry pseudo-packet
+    integrity_tag: [u8; 16],
+  },
+  /// Initial, 0-RTT or Handshake packet, or any packet of an unknown version,
+  /// the type-specific fields and the protected payload are left undecoded
+  Protected {
+    /// Long header
+    header: QuicLongHeader<Span>,
+    /// Remaining of the packet
+    payload: Span,
+  },
+}
+
+fn connection_id<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): QuicParse<Stream, Context>,
+{
+  octet.and_then(|len| take(usize::from(len))).parse(stream)
+}
+
+/// Parse the version independent part of a QUIC long header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn quic_long_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<QuicLongHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): QuicParse<Stream, Context>,
+{
+  let Success {
+    token: first_byte,
+    stream,
+  } = octet
+    .try_map(|first_byte| {
+      if first_byte & 0x80 != 0 {
+        Ok(first_byte)
+      } else {
+        Err(Context::new(QuicAtom::ShortHeader))
+      }
+    })
+    .parse(stream)?;
+
+  let Success {
+    token: (version, dest_cid, source_cid),
+    stream,
+  } = (u32_be, connection_id, connection_id).parse(stream)?;
+
+  Parsed::Success {
+    token: QuicLongHeader {
+      first_byte,
+      version,
+      dest_cid,
+      source_cid,
+    },
+    stream,
+  }
+}
+
+/// Parse a QUIC long header packet, this consume the whole stream as a QUIC
+/// long header packet can't be coalesced without decoding its length field.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn quic_long_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<QuicLongPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): QuicParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = quic_long_header.parse(stream)?;
+
+  if header.version == 0 {
+    let Success {
+      token: supported_versions,
+      stream,
+    } = u32_be.fold_bounds(.., Vec::new, Acc::acc).parse(stream)?;
+
+    Parsed::Success {
+      token: QuicLongPacket::VersionNegotiation {
+        header,
+        supported_versions,
+      },
+      stream,
+    }
+  } else if header.packet_type() == Some(QuicLongPacketType::RETRY) {
+    let Success {
+      token: Success {
+        stream: retry_token,
+        ..
+      },
+      stream,
+    } = any
+      .drop()
+      .fold_until(
+        octet.fill::<16>().and(end_of_stream).peek(),
+        || (),
+        Acc::acc,
+      )
+      .span()
+      .parse(stream)?;
+    let Success {
+      token: integrity_tag,
+      stream,
+    } = octet.fill().parse(stream)?;
+
+    Parsed::Success {
+      token: QuicLongPacket::Retry {
+        header,
+        retry_token,
+        integrity_tag,
+      },
+      stream,
+    }
+  } else {
+    let Success {
+      token: payload,
+      stream,
+    } = all.parse(stream)?;
+
+    Parsed::Success {
+      token: QuicLongPacket::Protected { header, payload },
+      stream,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::{
+    emit_quic_varint,
+    quic_varint_len,
+    QuicLongHeader,
+    QuicLongPacket,
+    QuicLongPacketType,
+    QUIC_VARINT_MAX,
+    QUIC_VERSION_2,
+  };
+
+  #[test]
+  fn quic_varint() {
+    // https://www.rfc-editor.org/rfc/rfc9000#appendix-A.1
+    let tests = [
+      (
+        &[0xC2, 0x19, 0x7C, 0x5E, 0xFF, 0x14, 0xE8, 0x8C][..],
+        151_288_809_941_952_652,
+      ),
+      (&[0x9D, 0x7F, 0x3E, 0x7D][..], 494_878_333),
+      (&[0x7B, 0xBD][..], 15_293),
+      (&[0x25][..], 37),
+    ];
+
+    for (stream, expected) in tests {
+      assert_eq!(
+        super::quic_varint::<_, Ignore>(stream),
+        Parsed::Success {
+          token: expected,
+          stream: &[][..],
+        }
+      );
+
+      let mut out = Vec::new();
+      assert_eq!(emit_quic_varint(expected, &mut out), Ok(stream.len()));
+      assert_eq!(out, stream);
+    }
+
+    // non shortest encoding is valid
+    assert_eq!(
+      super::quic_varint::<_, Ignore>(&[0x40, 0x25][..]),
+      Parsed::Success {
+        token: 37,
+        stream: &[][..],
+      }
+    );
+    assert_eq!(quic_varint_len(QUIC_VARINT_MAX + 1), None);
+  }
+
+  #[test]
+  fn quic_long_packet() {
+    // https://www.rfc-editor.org/rfc/rfc9001#appendix-A.4
+    let retry = [
+      0xFF, 0x00, 0x00, 0x00, 0x01, 0x00, 0x08, 0xF0, 0x67, 0xA5, 0x50, 0x2A, 0x42, 0x62, 0xB5,
+      0x74, 0x6F, 0x6B, 0x65, 0x6E, 0x04, 0xA2, 0x65, 0xBA, 0x2E, 0xFF, 0x4D, 0x82, 0x90, 0x58,
+      0xFB, 0x3F, 0x0F, 0x24, 0x96, 0xBA,
+    ];
+    let version_negotiation = [
+      0x80, 0x00, 0x00, 0x00, 0x00, 0x01, 0xAA, 0x02, 0xBB, 0xCC, 0x00, 0x00, 0x00, 0x01, 0x6B,
+      0x33, 0x43, 0xCF,
+    ];
+
+    let tests = [
+      (
+        &retry[..],
+        QuicLongPacket::Retry {
+          header: QuicLongHeader {
+            first_byte: 0xFF,
+            version: 1,
+            dest_cid: &[][..],
+            source_cid: &retry[7..15],
+          },
+          retry_token: b"token".as_slice(),
+          integrity_tag: retry[20..].try_into().unwrap(),
+        },
+      ),
+      (
+        &version_negotiation[..],
+        QuicLongPacket::VersionNegotiation {
+          header: QuicLongHeader {
+            first_byte: 0x80,
+            version: 0,
+            dest_cid: &[0xAA][..],
+            source_cid: &[0xBB, 0xCC][..],
+          },
+          supported_versions: vec![0x0000_0001, 0x6B33_43CF],
+        },
+      ),
+    ];
+
+    for (stream, expected) in tests {
+      assert_eq!(
+        super::quic_long_packet::<_, Ignore>(stream),
+        Parsed::Success {
+          token: expected,
+          stream: &[][..],
+        }
+      );
+    }
+
+    // type bits 0b11 are Handshake in QUIC version 2, and Retry for an unknown
+    // version is not assumed
+    for (version, expected) in [
+      (QUIC_VERSION_2, Some(QuicLongPacketType::HANDSHAKE)),
+      (0x1A2A_3A4A, None),
+    ] {
+      let mut packet = retry;
+      packet[1..5].copy_from_slice(&version.to_be_bytes());
+      let Parsed::Success {
+        token: QuicLongPacket::Protected { header, payload },
+        ..
+      } = super::quic_long_packet::<_, Ignore>(&packet[..])
+      else {
+        panic!("protected packet");
+      };
+      assert_eq!(header.packet_type(), expected);
+      assert_eq!(payload, &retry[15..]);
+    }
+
+    let mut packet = retry;
+    packet[0] = 0xCF;
+    packet[1..5].copy_from_slice(&QUIC_VERSION_2.to_be_bytes());
+    assert!(matches!(
+      super::quic_long_packet::<_, Ignore>(&packet[..]),
+      Parsed::Success {
+        token: QuicLongPacket::Retry { .. },
+        ..
+      }
+    ));
+  }
+}
@@ -0,0 +1,5 @@
+//! Rebuild data split over several packets, the byte streams of TCP
+//! connections and fragmented IP datagrams
+
+pub mod ip;
+pub mod tcp;
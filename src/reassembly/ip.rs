@@ -0,0 +1,407 @@
+//! Rebuild IPv4 and IPv6 datagrams from their fragments
+
+use std::{
+  collections::HashMap,
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::IpAddr,
+  time::Duration,
+};
+
+use crate::{
+  IPProtocol,
+  IPv4Header,
+  IPv6Header,
+  Ipv6FragmentHeader,
+};
+
+/// Fragments of the same datagram share this key
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct IpFragmentKey {
+  /// IP source address
+  pub source_addr: IpAddr,
+  /// IP destination address
+  pub dest_addr: IpAddr,
+  /// IPv4 identification or IPv6 fragment header identification
+  pub id: u32,
+  /// IPv4 protocol or IPv6 fragment header next header
+  pub protocol: IPProtocol,
+}
+
+/// One fragment of a datagram
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IpFragment<'a> {
+  /// Datagram of the fragment
+  pub key: IpFragmentKey,
+  /// Offset of payload in the datagram, in bytes
+  pub offset: usize,
+  /// More fragments follow
+  pub more_fragments: bool,
+  /// Fragment data
+  pub payload: &'a [u8],
+}
+
+impl<'a> IpFragment<'a> {
+  /// Return the fragment carried by an IPv4 packet, payload is the data
+  /// following the header. None if the packet is not fragmented.
+  pub fn ipv4<Span>(header: &IPv4Header<Span>, payload: &'a [u8]) -> Option<Self> {
+    if !header.is_fragment() {
+      return None;
+    }
+
+    Some(Self {
+      key: IpFragmentKey {
+        source_addr: header.source_addr.into(),
+        dest_addr: header.dest_addr.into(),
+        id: u32::from(header.id),
+        protocol: header.protocol,
+      },
+      offset: header.fragment_offset_bytes(),
+      more_fragments: header.more_fragments(),
+      payload,
+    })
+  }
+
+  /// Return the fragment carried by an IPv6 packet, payload is the data
+  /// following the fragment header
+  pub fn ipv6(header: &IPv6Header, fragment: &Ipv6FragmentHeader, payload: &'a [u8]) -> Self {
+    Self {
+      key: IpFragmentKey {
+        source_addr: header.source_addr.into(),
+        dest_addr: header.dest_addr.into(),
+        id: fragment.id,
+        protocol: fragment.next_header,
+      },
+      offset: usize::from(fragment.fragment_offset) * 8,
+      more_fragments: fragment.more_fragments,
+      payload,
+    }
+  }
+}
+
+/// Reason a fragment was refused by [IpDefragmenter::push]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefragError {
+  /// The datagram would be longer than the limit
+  TooLong(usize),
+  /// The fragment overlap a fragment with different data, the whole datagram
+  /// is dropped
+  Overlap,
+  /// Too many datagrams are being reassembled
+  TooManyDatagrams,
+  /// The datagram has too many fragments, it is dropped
+  TooManyFragments,
+  /// The fragment end past the length given by the last fragment, or it is a
+  /// last fragment with a different length, the datagram is dropped
+  Length(usize),
+}
+
+impl Display for DefragError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      DefragError::TooLong(len) => write!(f, "TooLong: datagram of {} bytes is too long", len),
+      DefragError::Overlap => write!(f, "Overlap: fragments overlap with different data"),
+      DefragError::TooManyDatagrams => {
+        write!(f, "TooManyDatagrams: too many datagrams in reassembly")
+      }
+      DefragError::TooManyFragments => {
+        write!(f, "TooManyFragments: too many fragments in datagram")
+      }
+      DefragError::Length(end) => write!(
+        f,
+        "Length: fragment ending at {} is inconsistent with datagram length",
+        end
+      ),
+    }
+  }
+}
+
+#[derive(Clone, Debug)]
+struct Datagram {
+  first_seen: Duration,
+  len: Option<usize>,
+  fragments: Vec<(usize, Vec<u8>)>,
+}
+
+impl Datagram {
+  // A fragment overlapping with the same data is a retransmission, it is only
+  // kept if it brings new data
+  fn insert(&mut self, fragment: &IpFragment<'_>, max_fragments: usize) -> Result<(), DefragError> {
+    let IpFragment {
+      offset, payload, ..
+    } = *fragment;
+    let end = offset + payload.len();
+    let last = !fragment.more_fragments;
+    let inconsistent = match self.len {
+      Some(len) => end > len || (last && end != len),
+      None => {
+        last
+          && self
+            .fragments
+            .iter()
+            .any(|(other, data)| other + data.len() > end)
+      }
+    };
+    if inconsistent {
+      return Err(DefragError::Length(end));
+    }
+
+    let mut covered = false;
+    for (other, data) in &self.fragments {
+      let other_end = other + data.len();
+      if offset < other_end && *other < end {
+        let start = offset.max(*other);
+        let stop = end.min(other_end);
+        if payload[start - offset..stop - offset] != data[start - other..stop - other] {
+          return Err(DefragError::Overlap);
+        }
+      }
+      covered |= *other <= offset && end <= other_end;
+    }
+    if last {
+      self.len = Some(end);
+    }
+    if covered {
+      return Ok(());
+    }
+    if self.fragments.len() >= max_fragments {
+      return Err(DefragError::TooManyFragments);
+    }
+    self.fragments.push((offset, payload.to_vec()));
+
+    Ok(())
+  }
+
+  fn assemble(&mut self) -> Option<Vec<u8>> {
+    let len = self.len?;
+    self.fragments.sort_unstable_by_key(|(offset, _)| *offset);
+
+    let mut datagram = Vec::with_capacity(len);
+    for (offset, data) in &self.fragments {
+      if *offset > datagram.len() {
+        return None;
+      }
+      if let Some(data) = data.get(datagram.len() - offset..) {
+        datagram.extend_from_slice(data);
+      }
+    }
+
+    (datagram.len() == len).then_some(datagram)
+  }
+}
+
+/// Rebuild datagrams from [IpFragment] received in any order.
+///
+/// Timestamps are given by the caller, usually the capture time of the
+/// packets, a datagram not complete after the timeout is dropped.
+#[derive(Clone, Debug)]
+pub struct IpDefragmenter {
+  datagrams: HashMap<IpFragmentKey, Datagram>,
+  timeout: Duration,
+  max_datagrams: usize,
+  max_fragments: usize,
+  max_len: usize,
+}
+
+impl Default for IpDefragmenter {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl IpDefragmenter {
+  /// Default number of datagrams reassembled at the same time
+  pub const MAX_DATAGRAMS: usize = 1024;
+  /// Default maximum number of fragments of a datagram
+  pub const MAX_FRAGMENTS: usize = 64;
+  /// Default maximum length of a reassembled datagram
+  pub const MAX_LEN: usize = 65535;
+  /// Default time allowed to receive every fragment of a datagram
+  pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+  /// Return an IpDefragmenter with the default limits
+  pub fn new() -> Self {
+    Self::with_limits(
+      Self::TIMEOUT,
+      Self::MAX_DATAGRAMS,
+      Self::MAX_FRAGMENTS,
+      Self::MAX_LEN,
+    )
+  }
+
+  /// Return an IpDefragmenter with custom limits
+  pub fn with_limits(
+    timeout: Duration, max_datagrams: usize, max_fragments: usize, max_len: usize,
+  ) -> Self {
+    Self {
+      datagrams: HashMap::new(),
+      timeout,
+      max_datagrams,
+      max_fragments,
+      max_len,
+    }
+  }
+
+  /// Add a fragment received at now, return the payload of its datagram once
+  /// every fragment has been received
+  pub fn push(
+    &mut self, fragment: &IpFragment<'_>, now: Duration,
+  ) -> Result<Option<Vec<u8>>, DefragError> {
+    self.expire(now);
+
+    let end = fragment.offset + fragment.payload.len();
+    if end > self.max_len {
+      self.datagrams.remove(&fragment.key);
+      return Err(DefragError::TooLong(end));
+    }
+    if !self.datagrams.contains_key(&fragment.key) && self.datagrams.len() >= self.max_datagrams {
+      return Err(DefragError::TooManyDatagrams);
+    }
+
+    let datagram = self
+      .datagrams
+      .entry(fragment.key)
+      .or_insert_with(|| Datagram {
+        first_seen: now,
+        len: None,
+        fragments: Vec::new(),
+      });
+    if let Err(error) = datagram.insert(fragment, self.max_fragments) {
+      self.datagrams.remove(&fragment.key);
+      return Err(error);
+    }
+
+    let payload = datagram.assemble();
+    if payload.is_some() {
+      self.datagrams.remove(&fragment.key);
+    }
+    Ok(payload)
+  }
+
+  /// Drop the datagrams first seen more than the timeout before now
+  pub fn expire(&mut self, now: Duration) {
+    let timeout = self.timeout;
+    self
+      .datagrams
+      .retain(|_, datagram| now.saturating_sub(datagram.first_seen) <= timeout);
+  }
+
+  /// Return the number of datagrams waiting for fragments
+  pub fn len(&self) -> usize {
+    self.datagrams.len()
+  }
+
+  /// Return true if no datagram is waiting for fragments
+  pub fn is_empty(&self) -> bool {
+    self.datagrams.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    net::Ipv4Addr,
+    time::Duration,
+  };
+
+  use super::{
+    DefragError,
+    IpDefragmenter,
+    IpFragment,
+    IpFragmentKey,
+  };
+  use crate::IPProtocol;
+
+  #[test]
+  fn ip_defragmenter() {
+    let key = IpFragmentKey {
+      source_addr: Ipv4Addr::new(10, 0, 0, 1).into(),
+      dest_addr: Ipv4Addr::new(10, 0, 0, 2).into(),
+      id: 42,
+      protocol: IPProtocol::UDP,
+    };
+    let fragment = |offset, more_fragments, payload| IpFragment {
+      key,
+      offset,
+      more_fragments,
+      payload,
+    };
+    let mut defragmenter = IpDefragmenter::new();
+    let now = Duration::from_secs(1);
+
+    assert_eq!(
+      defragmenter.push(&fragment(16, false, b"end"), now),
+      Ok(None)
+    );
+    assert_eq!(
+      defragmenter.push(&fragment(0, true, b"01234567"), now),
+      Ok(None)
+    );
+    // retransmission
+    assert_eq!(
+      defragmenter.push(&fragment(0, true, b"01234567"), now),
+      Ok(None)
+    );
+    assert_eq!(
+      defragmenter.push(&fragment(8, true, b"89abcdef"), now),
+      Ok(Some(b"0123456789abcdefend".to_vec()))
+    );
+    assert!(defragmenter.is_empty());
+
+    defragmenter
+      .push(&fragment(0, true, b"01234567"), now)
+      .unwrap();
+    assert_eq!(
+      defragmenter.push(&fragment(4, true, b"XXXXXXXX"), now),
+      Err(DefragError::Overlap)
+    );
+    assert!(defragmenter.is_empty());
+
+    // past the end or a second different last fragment
+    defragmenter.push(&fragment(8, false, b"end"), now).unwrap();
+    assert_eq!(
+      defragmenter.push(&fragment(8, true, b"89abcdef"), now),
+      Err(DefragError::Length(16))
+    );
+    defragmenter.push(&fragment(8, false, b"end"), now).unwrap();
+    assert_eq!(
+      defragmenter.push(&fragment(16, false, b"end"), now),
+      Err(DefragError::Length(19))
+    );
+    defragmenter
+      .push(&fragment(8, true, b"89abcdef"), now)
+      .unwrap();
+    assert_eq!(
+      defragmenter.push(&fragment(0, false, b"0123"), now),
+      Err(DefragError::Length(4))
+    );
+    assert!(defragmenter.is_empty());
+
+    // repeated fragments are not stored again
+    let mut defragmenter = IpDefragmenter::with_limits(IpDefragmenter::TIMEOUT, 1, 2, 100);
+    for _ in 0..10u8 {
+      assert_eq!(
+        defragmenter.push(&fragment(0, true, b"01234567"), now),
+        Ok(None)
+      );
+      assert_eq!(defragmenter.push(&fragment(2, true, b"23"), now), Ok(None));
+    }
+    defragmenter
+      .push(&fragment(16, true, b"01234567"), now)
+      .unwrap();
+    assert_eq!(
+      defragmenter.push(&fragment(32, true, b"01234567"), now),
+      Err(DefragError::TooManyFragments)
+    );
+    assert!(defragmenter.is_empty());
+
+    defragmenter
+      .push(&fragment(0, true, b"01234567"), now)
+      .unwrap();
+    defragmenter.expire(now + IpDefragmenter::TIMEOUT * 2);
+    assert!(defragmenter.is_empty());
+  }
+}
@@ -0,0 +1,340 @@
+//! Reorder TCP segments into the byte stream of each direction of a
+//! connection
+
+use std::{
+  collections::HashMap,
+  net::IpAddr,
+  time::Duration,
+};
+
+use crate::{
+  Port,
+  PseudoHeader,
+  TcpHeader,
+};
+
+/// One direction of a TCP connection
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TcpFlow {
+  /// IP source address
+  pub source_addr: IpAddr,
+  /// TCP source port
+  pub source_port: Port,
+  /// IP destination address
+  pub dest_addr: IpAddr,
+  /// TCP destination port
+  pub dest_port: Port,
+}
+
+impl TcpFlow {
+  /// Return the other direction of the connection
+  pub fn reverse(&self) -> Self {
+    Self {
+      source_addr: self.dest_addr,
+      source_port: self.dest_port,
+      dest_addr: self.source_addr,
+      dest_port: self.source_port,
+    }
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+struct TcpStream {
+  next: Option<u32>,
+  fin: Option<u32>,
+  closed: bool,
+  last_seen: Duration,
+  pending: Vec<(u32, Vec<u8>)>,
+  pending_len: usize,
+  ready: Vec<u8>,
+}
+
+// Signed distance from next to seq, valid while the window is smaller than
+// 2^31 bytes
+fn seq_offset(seq: u32, next: u32) -> i32 {
+  seq.wrapping_sub(next) as i32
+}
+
+impl TcpStream {
+  fn segment(&mut self, seq: u32, data: &[u8], max_pending: usize) {
+    let next = *self.next.get_or_insert(seq);
+    if self.closed || data.is_empty() {
+      return;
+    }
+
+    if seq_offset(seq, next) <= 0i32 {
+      self.append(seq, data);
+      self.drain();
+    } else if self.pending_len + data.len() <= max_pending {
+      self.pending.push((seq, data.to_vec()));
+      self.pending_len += data.len();
+    }
+  }
+
+  // Append the part of data after next, a retransmission add nothing
+  fn append(&mut self, seq: u32, data: &[u8]) {
+    let Some(next) = self.next else {
+      return;
+    };
+    let skip = next.wrapping_sub(seq) as usize;
+    if let Some(data) = data.get(skip..) {
+      self.ready.extend_from_slice(data);
+      self.next = Some(next.wrapping_add(data.len() as u32));
+    }
+  }
+
+  // Segments are taken in reception order so data received first win over
+  // overlapping data received later
+  fn drain(&mut self) {
+    while let Some(next) = self.next {
+      let Some(i) = self
+        .pending
+        .iter()
+        .position(|(seq, _)| seq_offset(*seq, next) <= 0i32)
+      else {
+        break;
+      };
+      let (seq, data) = self.pending.remove(i);
+      self.pending_len -= data.len();
+      self.append(seq, &data);
+    }
+
+    if self.fin.is_some() && self.fin == self.next {
+      self.close();
+    }
+  }
+
+  fn close(&mut self) {
+    self.closed = true;
+    self.pending.clear();
+    self.pending_len = 0;
+  }
+}
+
+/// Rebuild the ordered byte stream of each [TcpFlow] from segments received
+/// in any order.
+///
+/// The stream start at the SYN or, for a connection already open, at the
+/// first segment received. Retransmitted data is ignored, overlapping data
+/// keep the bytes received first. Segments ahead of the stream wait until the
+/// gap is filled, up to a limit of bytes per flow after which they are
+/// dropped.
+///
+/// Timestamps are given by the caller, usually the capture time of the
+/// packets, a flow without segment for longer than the timeout is dropped, a
+/// closed flow is dropped once its bytes have been read.
+#[derive(Clone, Debug)]
+pub struct TcpReassembler {
+  streams: HashMap<TcpFlow, TcpStream>,
+  timeout: Duration,
+  max_pending: usize,
+}
+
+impl Default for TcpReassembler {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl TcpReassembler {
+  /// Default limit of out of order bytes kept per flow
+  pub const MAX_PENDING: usize = 1 << 20;
+  /// Default time a flow is kept without receiving a segment
+  pub const TIMEOUT: Duration = Duration::from_secs(300);
+
+  /// Return a TcpReassembler keeping up to [Self::MAX_PENDING] out of order
+  /// bytes per flow
+  pub fn new() -> Self {
+    Self::with_max_pending(Self::MAX_PENDING)
+  }
+
+  /// Return a TcpReassembler keeping up to max_pending out of order bytes per
+  /// flow
+  pub fn with_max_pending(max_pending: usize) -> Self {
+    Self::with_limits(Self::TIMEOUT, max_pending)
+  }
+
+  /// Return a TcpReassembler with custom limits
+  pub fn with_limits(timeout: Duration, max_pending: usize) -> Self {
+    Self {
+      streams: HashMap::new(),
+      timeout,
+      max_pending,
+    }
+  }
+
+  /// Add a segment received at now, ip is the IPv4 or IPv6 header carrying
+  /// tcp and payload the TCP payload. Return the flow of the segment.
+  pub fn push<Ip, Options>(
+    &mut self, ip: &Ip, tcp: &TcpHeader<Options>, payload: &[u8], now: Duration,
+  ) -> TcpFlow
+  where
+    Ip: PseudoHeader,
+  {
+    self.expire(now);
+
+    let (source_addr, dest_addr) = ip.pseudo_addrs();
+    let flow = TcpFlow {
+      source_addr,
+      source_port: tcp.source_port,
+      dest_addr,
+      dest_port: tcp.dest_port,
+    };
+    let stream = self.streams.entry(flow).or_default();
+    stream.last_seen = now;
+
+    if tcp.is_rst() {
+      stream.close();
+      return flow;
+    }
+
+    // SYN use one sequence number before the data
+    let mut seq = tcp.sequence_no;
+    if tcp.is_syn() {
+      seq = seq.wrapping_add(1);
+      stream.next.get_or_insert(seq);
+    }
+    if tcp.is_fin() {
+      stream.fin = Some(seq.wrapping_add(payload.len() as u32));
+    }
+    stream.segment(seq, payload, self.max_pending);
+    stream.drain();
+
+    flow
+  }
+
+  /// Take the bytes of flow received in order since the last read
+  pub fn read(&mut self, flow: &TcpFlow) -> Vec<u8> {
+    self
+      .streams
+      .get_mut(flow)
+      .map(|stream| std::mem::take(&mut stream.ready))
+      .unwrap_or_default()
+  }
+
+  /// Return true once every byte up to the FIN of flow has been received or
+  /// after a RST
+  pub fn is_closed(&self, flow: &TcpFlow) -> bool {
+    self.streams.get(flow).is_some_and(|stream| stream.closed)
+  }
+
+  /// Return the number of out of order bytes of flow waiting for a gap to be
+  /// filled
+  pub fn pending(&self, flow: &TcpFlow) -> usize {
+    self
+      .streams
+      .get(flow)
+      .map_or(0, |stream| stream.pending_len)
+  }
+
+  /// Return every flow seen
+  pub fn flows(&self) -> impl Iterator<Item = &TcpFlow> {
+    self.streams.keys()
+  }
+
+  /// Forget flow and return the bytes not read yet
+  pub fn remove(&mut self, flow: &TcpFlow) -> Option<Vec<u8>> {
+    self.streams.remove(flow).map(|stream| stream.ready)
+  }
+
+  /// Drop the flows without segment for more than the timeout before now and
+  /// the closed flows whose bytes have all been read
+  pub fn expire(&mut self, now: Duration) {
+    let timeout = self.timeout;
+    self.streams.retain(|_, stream| {
+      !(stream.closed && stream.ready.is_empty()) && now.saturating_sub(stream.last_seen) <= timeout
+    });
+  }
+
+  /// Return the number of flows tracked
+  pub fn len(&self) -> usize {
+    self.streams.len()
+  }
+
+  /// Return true if no flow is tracked
+  pub fn is_empty(&self) -> bool {
+    self.streams.is_empty()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{
+    net::{
+      IpAddr,
+      Ipv4Addr,
+    },
+    time::Duration,
+  };
+
+  use super::TcpReassembler;
+  use crate::{
+    Port,
+    PseudoHeader,
+    TcpFlags,
+    TcpHeader,
+  };
+
+  struct Addrs;
+
+  impl PseudoHeader for Addrs {
+    fn pseudo_addrs(&self) -> (IpAddr, IpAddr) {
+      (
+        Ipv4Addr::new(10, 0, 0, 1).into(),
+        Ipv4Addr::new(10, 0, 0, 2).into(),
+      )
+    }
+  }
+
+  fn segment(sequence_no: u32, flags: u16) -> TcpHeader<()> {
+    TcpHeader {
+      source_port: Port(1234),
+      dest_port: Port(80),
+      sequence_no,
+      ack_no: 0,
+      data_offset: 5,
+      flags: TcpFlags::from(flags),
+      window: 0,
+      checksum: 0,
+      urgent_pointer: 0,
+      options: (),
+    }
+  }
+
+  #[test]
+  fn tcp_reassembler() {
+    let mut reassembler = TcpReassembler::new();
+    let now = Duration::from_secs(1);
+    let flow = reassembler.push(&Addrs, &segment(u32::MAX - 1, 0x02), b"", now);
+
+    // out of order, crossing sequence number wrap
+    reassembler.push(&Addrs, &segment(4, 0x00), b"world", now);
+    assert_eq!(reassembler.read(&flow), b"");
+    assert_eq!(reassembler.pending(&flow), 5);
+
+    reassembler.push(&Addrs, &segment(u32::MAX, 0x00), b"hello", now);
+    assert_eq!(reassembler.read(&flow), b"helloworld");
+    assert_eq!(reassembler.pending(&flow), 0);
+
+    // retransmission then overlap
+    reassembler.push(&Addrs, &segment(4, 0x00), b"world", now);
+    reassembler.push(&Addrs, &segment(7, 0x01), b"LD!", now);
+    assert_eq!(reassembler.read(&flow), b"!");
+    assert!(reassembler.is_closed(&flow));
+
+    assert_eq!(reassembler.read(&flow.reverse()), b"");
+    assert!(!reassembler.is_closed(&flow.reverse()));
+
+    // closed and read
+    reassembler.expire(now);
+    assert!(reassembler.is_empty());
+
+    // idle
+    reassembler.push(&Addrs, &segment(0, 0x00), b"idle", now);
+    reassembler.expire(now + TcpReassembler::TIMEOUT);
+    assert_eq!(reassembler.len(), 1);
+    reassembler.expire(now + TcpReassembler::TIMEOUT * 2);
+    assert!(reassembler.is_empty());
+  }
+}
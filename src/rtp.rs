@@ -0,0 +1,553 @@
+//! Handles parsing of RTP headers and RTCP compound packets
+//! <https://www.rfc-editor.org/rfc/rfc3550>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+      u64_be,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+/// Meta trait for rtp combinator
+pub trait RtpParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<RtpAtom>;
+
+/// Atom produced by rtp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RtpAtom {
+  /// When the version is not 2
+  Version(u8),
+  /// When the RTCP length is shorter than the packet content
+  Length(u16),
+}
+
+impl Display for RtpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      RtpAtom::Version(version) => write!(f, "Version: expected 2 found {}", version),
+      RtpAtom::Length(length) => {
+        write!(f, "Length: {} words is too short for the packet", length)
+      }
+    }
+  }
+}
+
+/// Header extension of an RTP header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtpExtension<Span> {
+  /// Profile defined identifier
+  pub profile: u16,
+  /// Extension data, a multiple of 4 bytes
+  pub data: Span,
+}
+
+/// RTP header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtpHeader<Span> {
+  /// The last byte of the payload is the number of padding bytes
+  pub padding: bool,
+  /// Profile defined marker, usually the start of a talkspurt or the end of
+  /// a video frame
+  pub marker: bool,
+  /// Payload type, 7 bits
+  pub payload_type: u8,
+  /// Sequence number
+  pub sequence: u16,
+  /// Sampling instant of the first payload byte
+  pub timestamp: u32,
+  /// Synchronization source
+  pub ssrc: u32,
+  /// Contributing sources
+  pub csrc: Vec<u32>,
+  /// Header extension
+  pub extension: Option<RtpExtension<Span>>,
+}
+
+impl<Span> RtpHeader<Span> {
+  /// Length of an RTP header without CSRC nor extension
+  pub const MIN_LEN: usize = 12;
+  /// RTP version
+  pub const VERSION: u8 = 2;
+}
+
+fn rtp_version<Stream, Context>(stream: Stream) -> Parsed<u8, Stream, Context>
+where
+  (): RtpParse<Stream, Context>,
+{
+  octet
+    .try_map(|first| {
+      let version = first >> 6u8;
+      if version == RtpHeader::<()>::VERSION {
+        Ok(first)
+      } else {
+        Err(Context::new(RtpAtom::Version(version)))
+      }
+    })
+    .parse(stream)
+}
+
+/// Parse an RTP header, the stream is left at the payload
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn rtp_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<RtpHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): RtpParse<Stream, Context>,
+{
+  let Success {
+    token: (first, second, sequence, timestamp, ssrc),
+    stream,
+  } = (rtp_version, octet, u16_be, u32_be, u32_be).parse(stream)?;
+
+  let csrc_count = usize::from(first & 0x0F);
+  let Success {
+    token: csrc,
+    stream,
+  } = u32_be
+    .fold_bounds(csrc_count, Vec::new, Acc::acc)
+    .parse(stream)?;
+
+  let Success {
+    token: extension,
+    stream,
+  } = if first & 0x10 != 0 {
+    let Success {
+      token: (profile, words),
+      stream,
+    } = (u16_be, u16_be).parse(stream)?;
+    take(usize::from(words) * 4)
+      .map(|data| Some(RtpExtension { profile, data }))
+      .parse(stream)?
+  } else {
+    Success {
+      token: None,
+      stream,
+    }
+  };
+
+  Parsed::Success {
+    token: RtpHeader {
+      padding: first & 0x20 != 0,
+      marker: second & 0x80 != 0,
+      payload_type: second & 0x7F,
+      sequence,
+      timestamp,
+      ssrc,
+      csrc,
+      extension,
+    },
+    stream,
+  }
+}
+
+/// Reception report block of sender and receiver reports
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtcpReportBlock {
+  /// Source of the report
+  pub ssrc: u32,
+  /// Fraction of packets lost since the previous report, over 256
+  pub fraction_lost: u8,
+  /// Packets lost since the start of reception, 24 bits signed
+  pub cumulative_lost: i32,
+  /// Extended highest sequence number received
+  pub highest_sequence: u32,
+  /// Interarrival jitter in timestamp units
+  pub jitter: u32,
+  /// Middle 32 bits of the NTP timestamp of the last sender report
+  pub last_sr: u32,
+  /// Delay since the last sender report in 1/65536 seconds
+  pub delay_since_last_sr: u32,
+}
+
+impl RtcpReportBlock {
+  /// Length of a report block
+  pub const LEN: usize = 24;
+}
+
+fn rtcp_report_block<Stream, Context>(stream: Stream) -> Parsed<RtcpReportBlock, Stream, Context>
+where
+  (): RtpParse<Stream, Context>,
+{
+  (u32_be, u32_be, u32_be, u32_be, u32_be, u32_be)
+    .map(
+      |(ssrc, lost, highest_sequence, jitter, last_sr, delay_since_last_sr)| RtcpReportBlock {
+        ssrc,
+        fraction_lost: (lost >> 24u8) as u8,
+        // sign extend the 24 bits
+        cumulative_lost: ((lost << 8u8) as i32) >> 8u8,
+        highest_sequence,
+        jitter,
+        last_sr,
+        delay_since_last_sr,
+      },
+    )
+    .parse(stream)
+}
+
+/// Item of an SDES chunk
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtcpSdesItem<Span> {
+  /// Item type, 1 for CNAME
+  pub item_type: u8,
+  /// Item text
+  pub data: Span,
+}
+
+impl<Span> RtcpSdesItem<Span> {
+  /// Canonical name item type
+  pub const CNAME: u8 = 1;
+}
+
+/// Chunk of a source description
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtcpSdesChunk<Span> {
+  /// Described source
+  pub ssrc: u32,
+  /// Items
+  pub items: Vec<RtcpSdesItem<Span>>,
+}
+
+/// RTCP packet, profile specific extensions and padding are skipped
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RtcpPacket<Span> {
+  /// Sender report
+  SenderReport {
+    /// Sender source
+    ssrc: u32,
+    /// Wallclock time of the report
+    ntp_timestamp: u64,
+    /// Same time in RTP timestamp units
+    rtp_timestamp: u32,
+    /// Packets sent
+    packet_count: u32,
+    /// Payload octets sent
+    octet_count: u32,
+    /// Reception reports
+    reports: Vec<RtcpReportBlock>,
+  },
+  /// Receiver report
+  ReceiverReport {
+    /// Receiver source
+    ssrc: u32,
+    /// Reception reports
+    reports: Vec<RtcpReportBlock>,
+  },
+  /// Source description
+  SourceDescription(Vec<RtcpSdesChunk<Span>>),
+  /// Goodbye
+  Goodbye {
+    /// Sources leaving
+    sources: Vec<u32>,
+    /// Reason for leaving
+    reason: Option<Span>,
+  },
+  /// Other packet type
+  Other {
+    /// Packet type
+    packet_type: u8,
+    /// Count or subtype field, 5 bits
+    count: u8,
+    /// Packet content after the header
+    data: Span,
+  },
+}
+
+impl<Span> RtcpPacket<Span> {
+  /// Packet type of goodbyes
+  pub const BYE: u8 = 203;
+  /// Packet type of receiver reports
+  pub const RR: u8 = 201;
+  /// Packet type of source descriptions
+  pub const SDES: u8 = 202;
+  /// Packet type of sender reports
+  pub const SR: u8 = 200;
+}
+
+fn rtcp_sdes_chunk<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(RtcpSdesChunk<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  (): RtpParse<Stream, Context>,
+{
+  let Success {
+    token: ssrc,
+    mut stream,
+  } = u32_be.parse(stream)?;
+  let mut consumed = 4;
+
+  // items end with a null item padded to a 32 bits boundary
+  let mut items = Vec::new();
+  loop {
+    let Success {
+      token: item_type,
+      stream: next,
+    } = octet.parse(stream)?;
+    consumed += 1;
+    if item_type == 0 {
+      let pad = (4 - consumed % 4) % 4;
+      let Success { stream, .. } = take(pad).parse(next)?;
+      return Parsed::Success {
+        token: (RtcpSdesChunk { ssrc, items }, consumed + pad),
+        stream,
+      };
+    }
+
+    let Success {
+      token: len,
+      stream: next,
+    } = octet.parse(next)?;
+    let Success {
+      token: data,
+      stream: next,
+    } = take(usize::from(len)).parse(next)?;
+    consumed += 1 + usize::from(len);
+    items.push(RtcpSdesItem { item_type, data });
+    stream = next;
+  }
+}
+
+/// Parse one RTCP packet of a compound packet
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn rtcp_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<RtcpPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): RtpParse<Stream, Context>,
+{
+  let Success {
+    token: (first, packet_type, length),
+    stream,
+  } = (rtp_version, octet, u16_be).parse(stream)?;
+  let count = first & 0x1F;
+  let len = usize::from(length) * 4;
+  let reports = usize::from(count);
+
+  let Success {
+    token: (packet, consumed),
+    stream,
+  } = match packet_type {
+    RtcpPacket::<()>::SR => (
+      u32_be,
+      u64_be,
+      u32_be,
+      u32_be,
+      u32_be,
+      rtcp_report_block.fold_bounds(reports, Vec::new, Acc::acc),
+    )
+      .map(
+        |(ssrc, ntp_timestamp, rtp_timestamp, packet_count, octet_count, reports)| {
+          let consumed = 24 + reports.len() * RtcpReportBlock::LEN;
+          (
+            RtcpPacket::SenderReport {
+              ssrc,
+              ntp_timestamp,
+              rtp_timestamp,
+              packet_count,
+              octet_count,
+              reports,
+            },
+            consumed,
+          )
+        },
+      )
+      .parse(stream)?,
+    RtcpPacket::<()>::RR => (
+      u32_be,
+      rtcp_report_block.fold_bounds(reports, Vec::new, Acc::acc),
+    )
+      .map(|(ssrc, reports)| {
+        let consumed = 4 + reports.len() * RtcpReportBlock::LEN;
+        (RtcpPacket::ReceiverReport { ssrc, reports }, consumed)
+      })
+      .parse(stream)?,
+    RtcpPacket::<()>::SDES => {
+      let mut stream = stream;
+      let mut chunks = Vec::with_capacity(reports);
+      let mut consumed = 0;
+      for _ in 0..reports {
+        let Success {
+          token: (chunk, chunk_len),
+          stream: next,
+        } = rtcp_sdes_chunk.parse(stream)?;
+        chunks.push(chunk);
+        consumed += chunk_len;
+        stream = next;
+      }
+      Success {
+        token: (RtcpPacket::SourceDescription(chunks), consumed),
+        stream,
+      }
+    }
+    RtcpPacket::<()>::BYE => {
+      let Success {
+        token: sources,
+        stream,
+      } = u32_be
+        .fold_bounds(reports, Vec::new, Acc::acc)
+        .parse(stream)?;
+      let consumed = reports * 4;
+      // the reason is present when the length leave room for it
+      if len > consumed {
+        let Success {
+          token: reason_len,
+          stream,
+        } = octet.parse(stream)?;
+        let Success {
+          token: reason,
+          stream,
+        } = take(usize::from(reason_len)).parse(stream)?;
+        Success {
+          token: (
+            RtcpPacket::Goodbye {
+              sources,
+              reason: Some(reason),
+            },
+            consumed + 1 + usize::from(reason_len),
+          ),
+          stream,
+        }
+      } else {
+        Success {
+          token: (
+            RtcpPacket::Goodbye {
+              sources,
+              reason: None,
+            },
+            consumed,
+          ),
+          stream,
+        }
+      }
+    }
+    packet_type => take(len)
+      .map(|data| {
+        (
+          RtcpPacket::Other {
+            packet_type,
+            count,
+            data,
+          },
+          len,
+        )
+      })
+      .parse(stream)?,
+  };
+
+  let Some(rest) = len.checked_sub(consumed) else {
+    return Parsed::Failure(Context::new(RtpAtom::Length(length)));
+  };
+  let Success { stream, .. } = take(rest).parse(stream)?;
+
+  Parsed::Success {
+    token: packet,
+    stream,
+  }
+}
+
+/// Parse an RTCP compound packet, the payload of a UDP datagram
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn rtcp_compound<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Vec<RtcpPacket<<Stream as Streaming>::Span>>, Stream, Context>
+where
+  (): RtpParse<Stream, Context>,
+{
+  rtcp_packet
+    .fold_bounds(1.., Vec::new, Acc::acc)
+    .parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::RtcpPacket;
+
+  #[test]
+  fn rtp_rtcp() {
+    let rtp = [
+      0x91, 0x80, 0x12, 0x34, 0x00, 0x00, 0x03, 0x20, 0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x00, 0x00,
+      0x01, 0xBE, 0xDE, 0x00, 0x01, 0x10, 0xAA, 0x00, 0x00, 0xFF,
+    ];
+    let Success { token, stream } = super::rtp_header::<_, Ignore>(&rtp[..]).unwrap();
+    assert!(token.marker);
+    assert_eq!(token.payload_type, 0);
+    assert_eq!(token.sequence, 0x1234);
+    assert_eq!(token.timestamp, 800);
+    assert_eq!(token.csrc, [1]);
+    assert_eq!(token.extension.unwrap().profile, 0xBEDE);
+    assert_eq!(stream, [0xFF]);
+
+    let rtcp = [
+      // receiver report with one block
+      0x81, 0xC9, 0x00, 0x07, 0x00, 0x00, 0x00, 0x01, 0xDE, 0xAD, 0xBE, 0xEF, 0x40, 0xFF, 0xFF,
+      0xFE, 0x00, 0x00, 0x12, 0x34, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, // source description with a CNAME
+      0x81, 0xCA, 0x00, 0x03, 0x00, 0x00, 0x00, 0x01, 0x01, 0x03, b'a', b'b', b'c', 0x00, 0x00,
+      0x00, // goodbye with a reason
+      0x81, 0xCB, 0x00, 0x02, 0x00, 0x00, 0x00, 0x01, 0x02, b'o', b'k', 0x00,
+    ];
+    let Success { token, stream } = super::rtcp_compound::<_, Ignore>(&rtcp[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.len(), 3);
+    let RtcpPacket::ReceiverReport { reports, .. } = &token[0] else {
+      panic!("expected a receiver report");
+    };
+    assert_eq!(reports[0].fraction_lost, 0x40);
+    assert_eq!(reports[0].cumulative_lost, -2i32);
+    let RtcpPacket::SourceDescription(chunks) = &token[1] else {
+      panic!("expected a source description");
+    };
+    assert_eq!(chunks[0].items[0].data, b"abc");
+    assert_eq!(
+      token[2],
+      RtcpPacket::Goodbye {
+        sources: vec![1],
+        reason: Some(&b"ok"[..]),
+      }
+    );
+  }
+}
@@ -0,0 +1,307 @@
+//! Handles parsing of RTSP messages and of the interleaved binary frames
+//! carrying RTP and RTCP over the RTSP connection
+//! <https://www.rfc-editor.org/rfc/rfc2326>
+
+use binator::{
+  base::{
+    is,
+    octet,
+    primitive::u16_be,
+    take,
+    to_digit,
+  },
+  utils::Utils,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  content_length,
+  find_header,
+  http_crlf,
+  http_headers,
+  http_run,
+  is_tchar,
+  is_vchar,
+  is_ws,
+  protocol_version,
+  HttpAtom,
+  HttpHeader,
+  HttpParse,
+  HttpVersion,
+  Port,
+};
+
+/// TCP port of RTSP
+pub const RTSP_PORT: Port = Port(554);
+
+/// Meta trait for rtsp combinator
+pub trait RtspParse<Stream, Context> = where (): HttpParse<Stream, Context>;
+
+/// RTSP request
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtspRequest<Span> {
+  /// Method, like `DESCRIBE` or `SETUP`
+  pub method: Span,
+  /// Request URI
+  pub target: Span,
+  /// Version
+  pub version: HttpVersion,
+  /// Header fields in order
+  pub headers: Vec<HttpHeader<Span>>,
+  /// Body, empty without Content-Length
+  pub body: Span,
+}
+
+/// RTSP response
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtspResponse<Span> {
+  /// Version
+  pub version: HttpVersion,
+  /// Status code
+  pub status: u16,
+  /// Reason phrase
+  pub reason: Span,
+  /// Header fields in order
+  pub headers: Vec<HttpHeader<Span>>,
+  /// Body, empty without Content-Length
+  pub body: Span,
+}
+
+/// Interleaved binary frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RtspInterleaved<Span> {
+  /// Channel, given by the interleaved parameter of the Transport header
+  pub channel: u8,
+  /// RTP or RTCP packet
+  pub data: Span,
+}
+
+/// Anything sent on a RTSP connection
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RtspMessage<Span> {
+  /// Request
+  Request(RtspRequest<Span>),
+  /// Response
+  Response(RtspResponse<Span>),
+  /// Interleaved binary frame
+  Interleaved(RtspInterleaved<Span>),
+}
+
+fn cseq<Span: AsRef<[u8]>>(headers: &[HttpHeader<Span>]) -> Option<u32> {
+  let value = find_header(headers, "CSeq")?.as_ref();
+  std::str::from_utf8(value).ok()?.parse().ok()
+}
+
+impl<Span: AsRef<[u8]>> RtspRequest<Span> {
+  /// Return the value of the first header field named name, ignoring case
+  pub fn header(&self, name: &str) -> Option<&Span> {
+    find_header(&self.headers, name)
+  }
+
+  /// Return the sequence number matching requests and responses
+  pub fn cseq(&self) -> Option<u32> {
+    cseq(&self.headers)
+  }
+}
+
+impl<Span: AsRef<[u8]>> RtspResponse<Span> {
+  /// Return the value of the first header field named name, ignoring case
+  pub fn header(&self, name: &str) -> Option<&Span> {
+    find_header(&self.headers, name)
+  }
+
+  /// Return the sequence number matching requests and responses
+  pub fn cseq(&self) -> Option<u32> {
+    cseq(&self.headers)
+  }
+}
+
+fn rtsp_body<Stream, Context, Span>(
+  stream: Stream, headers: &[HttpHeader<Span>],
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): RtspParse<Stream, Context>,
+  Span: AsRef<[u8]>,
+{
+  let length = match find_header(headers, "Content-Length") {
+    Some(value) => {
+      match content_length(value.as_ref()).and_then(|length| usize::try_from(length).ok()) {
+        Some(length) => length,
+        None => return Parsed::Failure(Context::new(HttpAtom::ContentLength)),
+      }
+    }
+    None => 0,
+  };
+  take(length).parse(stream)
+}
+
+/// Parse a request line, header fields and body
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn rtsp_request<Stream, Context>(
+  stream: Stream,
+) -> Parsed<RtspRequest<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): RtspParse<Stream, Context>,
+{
+  let Success {
+    token: (method, _, target, _, version, _, headers),
+    stream,
+  } = (
+    http_run(1, is_tchar),
+    is(b' '),
+    http_run(1, is_vchar),
+    is(b' '),
+    protocol_version("RTSP"),
+    http_crlf,
+    http_headers,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: body,
+    stream,
+  } = rtsp_body(stream, &headers)?;
+
+  Parsed::Success {
+    token: RtspRequest {
+      method,
+      target,
+      version,
+      headers,
+      body,
+    },
+    stream,
+  }
+}
+
+/// Parse a status line, header fields and body
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn rtsp_response<Stream, Context>(
+  stream: Stream,
+) -> Parsed<RtspResponse<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): RtspParse<Stream, Context>,
+{
+  let Success {
+    token: (version, _, status, _, reason, _, headers),
+    stream,
+  } = (
+    protocol_version("RTSP"),
+    is(b' '),
+    (to_digit, to_digit, to_digit)
+      .map(|(a, b, c)| u16::from(a) * 100 + u16::from(b) * 10 + u16::from(c)),
+    is(b' '),
+    http_run(0, |octet| is_ws(octet) || is_vchar(octet)),
+    http_crlf,
+    http_headers,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: body,
+    stream,
+  } = rtsp_body(stream, &headers)?;
+
+  Parsed::Success {
+    token: RtspResponse {
+      version,
+      status,
+      reason,
+      headers,
+      body,
+    },
+    stream,
+  }
+}
+
+/// Parse an interleaved binary frame, `$`, channel and length
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn rtsp_interleaved<Stream, Context>(
+  stream: Stream,
+) -> Parsed<RtspInterleaved<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): RtspParse<Stream, Context>,
+{
+  let Success {
+    token: (_, channel, length),
+    stream,
+  } = (is(b'$'), octet, u16_be).parse(stream)?;
+  take(usize::from(length))
+    .map(|data| RtspInterleaved { channel, data })
+    .parse(stream)
+}
+
+/// Parse the next message of a RTSP connection
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn rtsp_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<RtspMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): RtspParse<Stream, Context>,
+{
+  rtsp_interleaved
+    .map(RtspMessage::Interleaved)
+    .or(rtsp_response.map(RtspMessage::Response))
+    .or(rtsp_request.map(RtspMessage::Request))
+    .parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::RtspMessage;
+  use crate::HttpVersion;
+
+  #[test]
+  fn rtsp() {
+    let session = b"DESCRIBE rtsp://camera/stream RTSP/1.0\r\nCSeq: 2\r\n\r\nRTSP/1.0 200 OK\r\nCSeq: 2\r\nContent-Length: 4\r\n\r\nv=0\n$\x00\x00\x02\x80\x60";
+
+    let Success { token, stream } = super::rtsp_message::<_, Ignore>(&session[..]).unwrap();
+    let RtspMessage::Request(request) = token else {
+      panic!("expected a request");
+    };
+    assert_eq!(request.method, b"DESCRIBE");
+    assert_eq!(request.target, b"rtsp://camera/stream");
+    assert_eq!(request.version, HttpVersion { major: 1, minor: 0 });
+    assert_eq!(request.cseq(), Some(2));
+    assert_eq!(request.body, b"");
+
+    let Success { token, stream } = super::rtsp_message::<_, Ignore>(stream).unwrap();
+    let RtspMessage::Response(response) = token else {
+      panic!("expected a response");
+    };
+    assert_eq!(response.status, 200);
+    assert_eq!(response.body, b"v=0\n");
+
+    let Success { token, stream } = super::rtsp_message::<_, Ignore>(stream).unwrap();
+    let RtspMessage::Interleaved(frame) = token else {
+      panic!("expected an interleaved frame");
+    };
+    assert_eq!(frame.channel, 0);
+    assert_eq!(frame.data, [0x80, 0x60]);
+    assert_eq!(stream, b"");
+  }
+}
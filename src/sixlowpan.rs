@@ -0,0 +1,619 @@
+//! Handles parsing of 6LoWPAN fragmentation and compressed headers, IPHC and
+//! UDP NHC headers are decoded back to [IPv6Header] and [UdpHeader]
+//! <https://www.rfc-editor.org/rfc/rfc4944>
+//! <https://www.rfc-editor.org/rfc/rfc6282>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::Ipv6Addr,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::u16_be,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  IPProtocol,
+  IPv6Header,
+  Port,
+  UdpHeader,
+};
+
+/// Meta trait for 6LoWPAN combinator
+pub trait SixLowpanParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<SixLowpanAtom>;
+
+/// Atom produced by 6LoWPAN parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SixLowpanAtom {
+  /// When the dispatch byte is not the one expected by the parser
+  Dispatch(u8),
+  /// When a context identifier has no prefix in [SixLowpanLink]
+  Context(u8),
+  /// When an address mode is reserved
+  AddressMode,
+  /// When the next header compression is not supported
+  Nhc(u8),
+}
+
+impl Display for SixLowpanAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SixLowpanAtom::Dispatch(dispatch) => {
+        write!(f, "Dispatch: unexpected dispatch {:#04x}", dispatch)
+      }
+      SixLowpanAtom::Context(id) => write!(f, "Context: no prefix for context {}", id),
+      SixLowpanAtom::AddressMode => write!(f, "AddressMode: reserved address mode"),
+      SixLowpanAtom::Nhc(nhc) => {
+        write!(f, "Nhc: unsupported next header compression {:#04x}", nhc)
+      }
+    }
+  }
+}
+
+/// 6LoWPAN fragmentation header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SixLowpanFragment {
+  /// Size of the whole IPv6 datagram, before compression
+  pub size: u16,
+  /// Identify the fragments of a datagram
+  pub tag: u16,
+  /// Offset of the fragment in the datagram, in bytes, None for the first
+  /// fragment
+  pub offset: Option<u16>,
+}
+
+/// Parse a 6LoWPAN first (FRAG1) or subsequent (FRAGN) fragmentation header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn sixlowpan_fragment<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SixLowpanFragment, Stream, Context>
+where
+  (): SixLowpanParse<Stream, Context>,
+{
+  let Success {
+    token: (dispatch, size, tag),
+    stream,
+  } = (
+    octet.try_map(|dispatch| match dispatch >> 3u8 {
+      0b11000 | 0b11100 => Ok(dispatch),
+      _ => Err(Context::new(SixLowpanAtom::Dispatch(dispatch))),
+    }),
+    octet,
+    u16_be,
+  )
+    .parse(stream)?;
+  let size = u16::from_be_bytes([dispatch & 0x07, size]);
+
+  if dispatch >> 3u8 == 0b11100 {
+    octet
+      .map(|offset| SixLowpanFragment {
+        size,
+        tag,
+        offset: Some(u16::from(offset) * 8),
+      })
+      .parse(stream)
+  } else {
+    Parsed::Success {
+      token: SixLowpanFragment {
+        size,
+        tag,
+        offset: None,
+      },
+      stream,
+    }
+  }
+}
+
+/// IEEE 802.15.4 address of a node
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SixLowpanLinkAddr {
+  /// 16 bits short address
+  Short(u16),
+  /// 64 bits extended address
+  Extended([u8; 8]),
+}
+
+impl SixLowpanLinkAddr {
+  /// Return the IPv6 interface identifier derived from the link address
+  pub const fn interface_id(&self) -> [u8; 8] {
+    match *self {
+      SixLowpanLinkAddr::Short(addr) => {
+        let [a, b] = addr.to_be_bytes();
+        [0, 0, 0, 0xFF, 0xFE, 0, a, b]
+      }
+      SixLowpanLinkAddr::Extended(mut addr) => {
+        addr[0] ^= 0x02;
+        addr
+      }
+    }
+  }
+}
+
+/// Link layer information needed to decompress an IPHC header, elided
+/// addresses are derived from the link addresses or the context prefixes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SixLowpanLink {
+  /// Link source address of the frame
+  pub source: SixLowpanLinkAddr,
+  /// Link destination address of the frame
+  pub dest: SixLowpanLinkAddr,
+  /// 64 bits prefix of each context identifier
+  pub contexts: [Option<[u8; 8]>; 16],
+}
+
+impl SixLowpanLink {
+  /// Return SixLowpanLink without any context
+  pub const fn new(source: SixLowpanLinkAddr, dest: SixLowpanLinkAddr) -> Self {
+    Self {
+      source,
+      dest,
+      contexts: [None; 16],
+    }
+  }
+}
+
+/// Headers decompressed from an IPHC header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SixLowpanHeader {
+  /// IPv6 header, length is deduced from the remaining of the frame so it's
+  /// only right for an unfragmented datagram
+  pub ipv6: IPv6Header,
+  /// UDP header when compressed with NHC, length is deduced like the IPv6
+  /// length and checksum is 0 if elided
+  pub udp: Option<UdpHeader>,
+}
+
+/// Implementation of [sixlowpan_iphc]
+pub struct SixLowpanIphc {
+  link: SixLowpanLink,
+}
+
+fn inline<Stream, Context>(stream: Stream, len: usize) -> Parsed<[u8; 16], Stream, Context>
+where
+  (): SixLowpanParse<Stream, Context>,
+{
+  octet
+    .fold_bounds(
+      len,
+      || ([0u8; 16], 0usize),
+      |(mut bytes, i), octet| {
+        bytes[i] = octet;
+        (bytes, i + 1)
+      },
+    )
+    .map(|(bytes, _)| bytes)
+    .parse(stream)
+}
+
+impl SixLowpanIphc {
+  fn prefix<Context>(&self, context: Option<u8>) -> Result<[u8; 8], Context>
+  where
+    Context: Contexting<SixLowpanAtom>,
+  {
+    match context {
+      None => Ok([0xFE, 0x80, 0, 0, 0, 0, 0, 0]),
+      Some(id) => {
+        self.link.contexts[usize::from(id)].ok_or_else(|| Context::new(SixLowpanAtom::Context(id)))
+      }
+    }
+  }
+
+  fn unicast<Stream, Context>(
+    &self, stream: Stream, context: Option<u8>, mode: u8, link: SixLowpanLinkAddr,
+  ) -> Parsed<Ipv6Addr, Stream, Context>
+  where
+    (): SixLowpanParse<Stream, Context>,
+  {
+    let prefix = match (context, mode) {
+      (Some(_), 0) => Ok([0u8; 8]),
+      _ => self.prefix(context),
+    };
+    let prefix = match prefix {
+      Ok(prefix) => prefix,
+      Err(context) => return Parsed::Failure(context),
+    };
+    let len = match (context, mode) {
+      (None, 0) => 16,
+      (_, 1) => 8,
+      (_, 2) => 2,
+      _ => 0,
+    };
+    let Success {
+      token: bytes,
+      stream,
+    } = inline(stream, len)?;
+
+    let mut addr = [0u8; 16];
+    match (context, mode) {
+      (None, 0) => addr = bytes,
+      // unspecified address
+      (Some(_), 0) => {}
+      (_, 1) => {
+        addr[..8].copy_from_slice(&prefix);
+        addr[8..].copy_from_slice(&bytes[..8]);
+      }
+      (_, 2) => {
+        addr[..8].copy_from_slice(&prefix);
+        addr[8..14].copy_from_slice(&[0, 0, 0, 0xFF, 0xFE, 0]);
+        addr[14..].copy_from_slice(&bytes[..2]);
+      }
+      _ => {
+        addr[..8].copy_from_slice(&prefix);
+        addr[8..].copy_from_slice(&link.interface_id());
+      }
+    }
+
+    Parsed::Success {
+      token: Ipv6Addr::from(addr),
+      stream,
+    }
+  }
+
+  fn multicast<Stream, Context>(
+    &self, stream: Stream, context: Option<u8>, mode: u8,
+  ) -> Parsed<Ipv6Addr, Stream, Context>
+  where
+    (): SixLowpanParse<Stream, Context>,
+  {
+    let len = match (context, mode) {
+      (None, 0) => 16,
+      (_, 0 | 1) => 6,
+      (None, 2) => 4,
+      (None, _) => 1,
+      (Some(_), _) => return Parsed::Failure(Context::new(SixLowpanAtom::AddressMode)),
+    };
+    let Success {
+      token: bytes,
+      stream,
+    } = inline(stream, len)?;
+
+    let mut addr = [0u8; 16];
+    addr[0] = 0xFF;
+    match (context, mode) {
+      (None, 0) => addr = bytes,
+      // unicast prefix based, ffXX:XXLL:PPPP:PPPP:PPPP:PPPP:XXXX:XXXX
+      (Some(_), _) => {
+        let prefix = match self.prefix(context) {
+          Ok(prefix) => prefix,
+          Err(context) => return Parsed::Failure(context),
+        };
+        addr[1..3].copy_from_slice(&bytes[..2]);
+        addr[3] = 64;
+        addr[4..12].copy_from_slice(&prefix);
+        addr[12..].copy_from_slice(&bytes[2..6]);
+      }
+      (None, 1) => {
+        addr[1] = bytes[0];
+        addr[11..].copy_from_slice(&bytes[1..6]);
+      }
+      (None, 2) => {
+        addr[1] = bytes[0];
+        addr[13..].copy_from_slice(&bytes[1..4]);
+      }
+      (None, _) => {
+        addr[1] = 0x02;
+        addr[15] = bytes[0];
+      }
+    }
+
+    Parsed::Success {
+      token: Ipv6Addr::from(addr),
+      stream,
+    }
+  }
+}
+
+impl<Stream, Context> Parse<Stream, Context> for SixLowpanIphc
+where
+  (): SixLowpanParse<Stream, Context>,
+  Stream: AsRef<[u8]>,
+{
+  type Token = SixLowpanHeader;
+
+  fn parse(&mut self, stream: Stream) -> Parsed<Self::Token, Stream, Context> {
+    let Success {
+      token: (iphc_0, iphc_1),
+      stream,
+    } = octet
+      .try_map(|iphc_0| {
+        if iphc_0 >> 5u8 == 0b011 {
+          Ok(iphc_0)
+        } else {
+          Err(Context::new(SixLowpanAtom::Dispatch(iphc_0)))
+        }
+      })
+      .and(octet)
+      .parse(stream)?;
+
+    let Success { token: cid, stream } = inline(stream, usize::from(iphc_1 >> 7u8))?;
+    let (sci, dci) = (cid[0] >> 4u8, cid[0] & 0x0F);
+
+    let tf = iphc_0 >> 3u8 & 0b11;
+    let Success {
+      token: bytes,
+      stream,
+    } = match tf {
+      0b00 => inline(stream, 4),
+      0b01 => inline(stream, 3),
+      0b10 => inline(stream, 1),
+      _ => inline(stream, 0),
+    }?;
+    let (ecn, ds, flow_label) = match tf {
+      0b00 => (
+        bytes[0] >> 6u8,
+        bytes[0] & 0x3F,
+        u32::from_be_bytes([0, bytes[1] & 0x0F, bytes[2], bytes[3]]),
+      ),
+      0b01 => (
+        bytes[0] >> 6u8,
+        0,
+        u32::from_be_bytes([0, bytes[0] & 0x0F, bytes[1], bytes[2]]),
+      ),
+      0b10 => (bytes[0] >> 6u8, bytes[0] & 0x3F, 0),
+      _ => (0, 0, 0),
+    };
+
+    let nhc = iphc_0 & 0x04 != 0;
+    let Success {
+      token: next_header,
+      stream,
+    } = inline(stream, usize::from(!nhc))?;
+
+    let Success {
+      token: hop_limit,
+      stream,
+    } = match iphc_0 & 0b11 {
+      0b00 => octet.parse(stream),
+      0b01 => Parsed::Success { token: 1, stream },
+      0b10 => Parsed::Success { token: 64, stream },
+      _ => Parsed::Success { token: 255, stream },
+    }?;
+
+    let sac = (iphc_1 & 0x40 != 0).then_some(sci);
+    let Success {
+      token: source_addr,
+      stream,
+    } = self.unicast(stream, sac, iphc_1 >> 4u8 & 0b11, self.link.source)?;
+
+    let dac = (iphc_1 & 0x04 != 0).then_some(dci);
+    let dam = iphc_1 & 0b11;
+    let Success {
+      token: dest_addr,
+      stream,
+    } = match (iphc_1 & 0x08 != 0, dac, dam) {
+      (true, ..) => self.multicast(stream, dac, dam),
+      (false, Some(_), 0) => Parsed::Failure(Context::new(SixLowpanAtom::AddressMode)),
+      (false, ..) => self.unicast(stream, dac, dam, self.link.dest),
+    }?;
+
+    let Success { token: udp, stream } = if nhc {
+      let Success { token: nhc, stream } = octet
+        .try_map(|nhc| {
+          if nhc & 0xF8 == 0xF0 {
+            Ok(nhc)
+          } else {
+            Err(Context::new(SixLowpanAtom::Nhc(nhc)))
+          }
+        })
+        .parse(stream)?;
+
+      let Success {
+        token: ports,
+        stream,
+      } = match nhc & 0b11 {
+        0b00 => inline(stream, 4),
+        0b01 | 0b10 => inline(stream, 3),
+        _ => inline(stream, 1),
+      }?;
+      let (source_port, dest_port) = match nhc & 0b11 {
+        0b00 => (
+          u16::from_be_bytes([ports[0], ports[1]]),
+          u16::from_be_bytes([ports[2], ports[3]]),
+        ),
+        0b01 => (
+          u16::from_be_bytes([ports[0], ports[1]]),
+          0xF000 | u16::from(ports[2]),
+        ),
+        0b10 => (
+          0xF000 | u16::from(ports[0]),
+          u16::from_be_bytes([ports[1], ports[2]]),
+        ),
+        _ => (
+          0xF0B0 | u16::from(ports[0] >> 4u8),
+          0xF0B0 | u16::from(ports[0] & 0x0F),
+        ),
+      };
+
+      let Success {
+        token: checksum,
+        stream,
+      } = if nhc & 0x04 == 0 {
+        u16_be.parse(stream)
+      } else {
+        Parsed::Success { token: 0, stream }
+      }?;
+
+      Parsed::Success {
+        token: Some((Port(source_port), Port(dest_port), checksum)),
+        stream,
+      }
+    } else {
+      Parsed::Success {
+        token: None,
+        stream,
+      }
+    }?;
+
+    let udp_len = if udp.is_some() { UdpHeader::LEN } else { 0 };
+    let length = u16::try_from(stream.as_ref().len() + udp_len).unwrap_or(u16::MAX);
+    let udp = udp.map(|(source_port, dest_port, checksum)| UdpHeader {
+      source_port,
+      dest_port,
+      length,
+      checksum,
+    });
+
+    let token = SixLowpanHeader {
+      ipv6: IPv6Header {
+        version: 6,
+        ds,
+        ecn,
+        flow_label,
+        length,
+        next_header: match udp {
+          Some(_) => IPProtocol::UDP,
+          None => IPProtocol::new(next_header[0]),
+        },
+        hop_limit,
+        source_addr,
+        dest_addr,
+      },
+      udp,
+    };
+
+    #[cfg(feature = "tracing")]
+    {
+      crate::trace_layer(&token.ipv6);
+      if let Some(udp) = &token.udp {
+        crate::trace_layer(udp);
+      }
+    }
+
+    Parsed::Success { token, stream }
+  }
+}
+
+/// Parse a 6LoWPAN IPHC header and the UDP NHC header that may follow it,
+/// link give the addresses and context prefixes used for elided addresses
+pub fn sixlowpan_iphc(link: SixLowpanLink) -> SixLowpanIphc {
+  SixLowpanIphc { link }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv6Addr;
+
+  use binator::{
+    context::Ignore,
+    Parse,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    sixlowpan_iphc,
+    SixLowpanFragment,
+    SixLowpanLink,
+    SixLowpanLinkAddr,
+  };
+  use crate::{
+    IPProtocol,
+    Port,
+  };
+
+  #[test]
+  fn sixlowpan() {
+    assert_eq!(
+      super::sixlowpan_fragment::<_, Ignore>(&[0xC0, 0x50, 0x12, 0x34][..]),
+      Parsed::Success {
+        token: SixLowpanFragment {
+          size: 80,
+          tag: 0x1234,
+          offset: None,
+        },
+        stream: &[][..],
+      }
+    );
+    assert_eq!(
+      super::sixlowpan_fragment::<_, Ignore>(&[0xE0, 0x50, 0x12, 0x34, 0x02][..]),
+      Parsed::Success {
+        token: SixLowpanFragment {
+          size: 80,
+          tag: 0x1234,
+          offset: Some(16),
+        },
+        stream: &[][..],
+      }
+    );
+
+    let mut link = SixLowpanLink::new(
+      SixLowpanLinkAddr::Extended([0x00, 0x12, 0x4B, 0, 0, 0, 0, 1]),
+      SixLowpanLinkAddr::Short(2),
+    );
+
+    // addresses derived from the link, ICMPv6 inline
+    let parsed: Parsed<_, _, Ignore> =
+      sixlowpan_iphc(link).parse(&[0x7A, 0x33, 0x3A, 0x80, 0x00, 0x00, 0x00][..]);
+    let Success { token, stream } = parsed.unwrap();
+    assert_eq!(stream, [0x80, 0x00, 0x00, 0x00]);
+    assert_eq!(token.udp, None);
+    assert_eq!(token.ipv6.next_header, IPProtocol::ICMP_6);
+    assert_eq!(token.ipv6.hop_limit, 64);
+    assert_eq!(token.ipv6.length, 4);
+    assert_eq!(
+      token.ipv6.source_addr,
+      "fe80::212:4b00:0:1".parse::<Ipv6Addr>().unwrap()
+    );
+    assert_eq!(
+      token.ipv6.dest_addr,
+      "fe80::ff:fe00:2".parse::<Ipv6Addr>().unwrap()
+    );
+
+    // context 0 source, 16 bits destination and UDP NHC
+    let parsed: Parsed<_, _, Ignore> = sixlowpan_iphc(link).parse(&[0x7F, 0x52][..]);
+    assert!(matches!(parsed, Parsed::Failure(_)));
+    link.contexts[0] = Some([0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0]);
+    let parsed: Parsed<_, _, Ignore> = sixlowpan_iphc(link).parse(
+      &[
+        0x7F, 0x52, 0, 0, 0, 0, 0, 0, 0, 1, 0x00, 0x05, 0xF3, 0x12, 0xAB, 0xCD, 1, 2, 3,
+      ][..],
+    );
+    let Success { token, stream } = parsed.unwrap();
+    assert_eq!(stream, [1, 2, 3]);
+    assert_eq!(token.ipv6.next_header, IPProtocol::UDP);
+    assert_eq!(token.ipv6.hop_limit, 255);
+    assert_eq!(token.ipv6.length, 11);
+    assert_eq!(
+      token.ipv6.source_addr,
+      "2001:db8::1".parse::<Ipv6Addr>().unwrap()
+    );
+    assert_eq!(
+      token.ipv6.dest_addr,
+      "fe80::ff:fe00:5".parse::<Ipv6Addr>().unwrap()
+    );
+    let udp = token.udp.unwrap();
+    assert_eq!(
+      (udp.source_port, udp.dest_port),
+      (Port(0xF0B1), Port(0xF0B2))
+    );
+    assert_eq!((udp.length, udp.checksum), (11, 0xABCD));
+  }
+}
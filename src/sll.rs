@@ -0,0 +1,172 @@
+//! Handles parsing of Linux cooked capture headers, written by `tcpdump -i
+//! any` instead of the link-layer header of each interface
+//! <https://www.tcpdump.org/linktypes/LINKTYPE_LINUX_SLL.html>
+//! <https://www.tcpdump.org/linktypes/LINKTYPE_LINUX_SLL2.html>
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  EtherType,
+};
+
+/// Meta trait for sll combinator
+pub trait SllParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>;
+
+struct_variants! {
+  SllPacketType, packet_type, u8:
+    /// Sent to this host
+    HOST => 0,
+    /// Broadcast by another host
+    BROADCAST => 1,
+    /// Multicast by another host
+    MULTICAST => 2,
+    /// Sent by another host to another host
+    OTHER_HOST => 3,
+    /// Sent by this host
+    OUTGOING => 4,
+}
+
+/// Linux cooked capture header, version 1 or 2
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SllHeader {
+  /// Direction of the packet
+  pub packet_type: SllPacketType,
+  /// ARPHRD type of the interface, 1 for Ethernet
+  pub arphrd: u16,
+  /// Length of the link-layer address, can exceed the 8 bytes kept
+  pub address_len: u8,
+  /// Link-layer source address, padded with zero
+  pub address: [u8; 8],
+  /// Protocol of the payload, an EtherType for most ARPHRD types. 0x0001
+  /// means Novell 802.3 and 0x0004 an 802.2 LLC header.
+  pub protocol: EtherType,
+  /// Index of the capture interface, only in version 2
+  pub interface_index: Option<u32>,
+}
+
+impl SllHeader {
+  /// Length of a version 1 header
+  pub const LEN: usize = 16;
+  /// Length of a version 2 header
+  pub const LEN_2: usize = 20;
+
+  /// Return the link-layer address without padding
+  pub fn address(&self) -> &[u8] {
+    &self.address[..usize::from(self.address_len).min(self.address.len())]
+  }
+}
+
+/// Parse a Linux cooked capture version 1 header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn sll_header<Stream, Context>(stream: Stream) -> Parsed<SllHeader, Stream, Context>
+where
+  (): SllParse<Stream, Context>,
+{
+  let Success {
+    token: (packet_type, arphrd, address_len, address, protocol),
+    stream,
+  } = (u16_be, u16_be, u16_be, octet.fill(), u16_be).parse(stream)?;
+
+  Parsed::Success {
+    token: SllHeader {
+      packet_type: SllPacketType::new(packet_type as u8),
+      arphrd,
+      address_len: address_len.min(u16::from(u8::MAX)) as u8,
+      address,
+      protocol: EtherType::new(protocol),
+      interface_index: None,
+    },
+    stream,
+  }
+}
+
+/// Parse a Linux cooked capture version 2 header
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn sll2_header<Stream, Context>(stream: Stream) -> Parsed<SllHeader, Stream, Context>
+where
+  (): SllParse<Stream, Context>,
+{
+  let Success {
+    token: (protocol, _reserved, interface_index, arphrd, packet_type, address_len, address),
+    stream,
+  } = (u16_be, u16_be, u32_be, u16_be, octet, octet, octet.fill()).parse(stream)?;
+
+  Parsed::Success {
+    token: SllHeader {
+      packet_type: SllPacketType::new(packet_type),
+      arphrd,
+      address_len,
+      address,
+      protocol: EtherType::new(protocol),
+      interface_index: Some(interface_index),
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::SllPacketType;
+  use crate::EtherType;
+
+  #[test]
+  fn sll_header() {
+    let header = [
+      0x00, 0x04, 0x00, 0x01, 0x00, 0x06, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x00, 0x00, 0x08,
+      0x00, 0x45,
+    ];
+    let Success { token, stream } = super::sll_header::<_, Ignore>(&header[..]).unwrap();
+    assert_eq!(token.packet_type, SllPacketType::OUTGOING);
+    assert_eq!(token.address(), [0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B]);
+    assert_eq!(token.protocol, EtherType::IPV4);
+    assert_eq!(token.interface_index, None);
+    assert_eq!(stream, [0x45]);
+
+    let header = [
+      0x86, 0xDD, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01, 0x00, 0x06, 0x00, 0x1B, 0x21,
+      0x0F, 0x91, 0x9B, 0x00, 0x00, 0x60,
+    ];
+    let Success { token, stream } = super::sll2_header::<_, Ignore>(&header[..]).unwrap();
+    assert_eq!(token.packet_type, SllPacketType::HOST);
+    assert_eq!(token.arphrd, 1);
+    assert_eq!(token.address(), [0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B]);
+    assert_eq!(token.protocol, EtherType::IPV6);
+    assert_eq!(token.interface_index, Some(2));
+    assert_eq!(stream, [0x60]);
+  }
+}
@@ -0,0 +1,515 @@
+//! Handles parsing of NetBIOS session framing, NBNS name queries and SMB2/3
+//! headers
+//! <https://www.rfc-editor.org/rfc/rfc1002>
+//! <https://learn.microsoft.com/en-us/openspecs/windows_protocols/ms-smb2>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u16_le,
+      u32_le,
+      u64_le,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// UDP port of NBNS
+pub const NBNS_PORT: Port = Port(137);
+/// TCP port of the NetBIOS session service
+pub const NETBIOS_SESSION_PORT: Port = Port(139);
+/// TCP port of SMB over TCP, using the NetBIOS session framing
+pub const SMB_PORT: Port = Port(445);
+
+/// Meta trait for smb combinator
+pub trait SmbParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<SmbAtom>;
+
+/// Atom produced by smb parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SmbAtom {
+  /// When the protocol id is not the one of SMB2, SMB1 and encrypted messages
+  /// included
+  ProtocolId([u8; 4]),
+  /// When the SMB2 header structure size is not 64
+  StructureSize(u16),
+  /// When an encoded NetBIOS name is not 32 bytes long
+  NameLength(u8),
+  /// When a label of a NetBIOS name is longer than 63 bytes, compression
+  /// pointers included
+  LabelLength(u8),
+}
+
+impl Display for SmbAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SmbAtom::ProtocolId(id) => write!(f, "ProtocolId: expected FE534D42 found {:02X?}", id),
+      SmbAtom::StructureSize(size) => {
+        write!(f, "StructureSize: expected 64 found {}", size)
+      }
+      SmbAtom::NameLength(length) => write!(f, "NameLength: expected 32 found {}", length),
+      SmbAtom::LabelLength(length) => {
+        write!(f, "LabelLength: label length {} is too long", length)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  NetbiosMessageType, message_type, u8:
+    /// Session message, the payload is a SMB message
+    SESSION_MESSAGE => 0x00,
+    /// Session request
+    SESSION_REQUEST => 0x81,
+    /// Positive session response
+    POSITIVE_SESSION_RESPONSE => 0x82,
+    /// Negative session response
+    NEGATIVE_SESSION_RESPONSE => 0x83,
+    /// Retarget session response
+    RETARGET_SESSION_RESPONSE => 0x84,
+    /// Session keep alive
+    SESSION_KEEP_ALIVE => 0x85,
+}
+
+/// NetBIOS session service packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetbiosSession<Span> {
+  /// Packet type
+  pub message_type: NetbiosMessageType,
+  /// Length of the payload, 17 bits
+  pub length: u32,
+  /// Payload
+  pub payload: Span,
+}
+
+/// Parse a NetBIOS session service packet, this framing is also used by SMB
+/// directly over TCP
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn netbios_session<Stream, Context>(
+  stream: Stream,
+) -> Parsed<NetbiosSession<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SmbParse<Stream, Context>,
+{
+  let Success {
+    token: (message_type, flags, length),
+    stream,
+  } = (octet.map(NetbiosMessageType::new), octet, u16_be).parse(stream)?;
+  // the low bit of flags extend the length
+  let length = u32::from(flags & 1) << 16u8 | u32::from(length);
+
+  take(length as usize)
+    .map(|payload| NetbiosSession {
+      message_type,
+      length,
+      payload,
+    })
+    .parse(stream)
+}
+
+/// NetBIOS name, decoded from its first-level encoding
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NetbiosName<Span> {
+  /// Name without its trailing spaces
+  pub name: Vec<u8>,
+  /// Suffix, the 16th byte, telling the service
+  pub suffix: u8,
+  /// Labels of the scope
+  pub scope: Vec<Span>,
+}
+
+impl<Span> NetbiosName<Span> {
+  /// Suffix of domain master browsers
+  pub const DOMAIN_MASTER_BROWSER: u8 = 0x1B;
+  /// Suffix of file servers
+  pub const FILE_SERVER: u8 = 0x20;
+  /// Suffix of workstations
+  pub const WORKSTATION: u8 = 0x00;
+}
+
+fn netbios_name<Stream, Context>(
+  stream: Stream,
+) -> Parsed<NetbiosName<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SmbParse<Stream, Context>,
+{
+  let Success {
+    token: decoded,
+    mut stream,
+  } = (
+    octet.try_map(|length| {
+      if length == 32 {
+        Ok(length)
+      } else {
+        Err(Context::new(SmbAtom::NameLength(length)))
+      }
+    }),
+    // each byte is split in two nibbles, each added to 'A'
+    (octet, octet)
+      .map(|(high, low)| high.wrapping_sub(b'A') << 4u8 | low.wrapping_sub(b'A') & 0x0F)
+      .fold_bounds(16, Vec::new, Acc::acc),
+  )
+    .map(|(_, decoded): (u8, Vec<u8>)| decoded)
+    .parse(stream)?;
+
+  let mut scope = Vec::new();
+  loop {
+    let Success {
+      token: length,
+      stream: next,
+    } = octet
+      .try_map(|length| {
+        if length <= 63 {
+          Ok(length)
+        } else {
+          Err(Context::new(SmbAtom::LabelLength(length)))
+        }
+      })
+      .parse(stream)?;
+    if length == 0 {
+      stream = next;
+      break;
+    }
+    let Success {
+      token: label,
+      stream: next,
+    } = take(usize::from(length)).parse(next)?;
+    scope.push(label);
+    stream = next;
+  }
+
+  let (name, suffix) = decoded.split_at(15);
+  let len = name
+    .iter()
+    .rposition(|&byte| byte != b' ')
+    .map_or(0, |i| i + 1);
+  Parsed::Success {
+    token: NetbiosName {
+      name: name[..len].to_vec(),
+      suffix: suffix[0],
+      scope,
+    },
+    stream,
+  }
+}
+
+/// Question of a NBNS packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NbnsQuestion<Span> {
+  /// Queried name
+  pub name: NetbiosName<Span>,
+  /// Question type, 0x20 NB or 0x21 NBSTAT
+  pub question_type: u16,
+  /// Question class, 1 IN
+  pub question_class: u16,
+}
+
+/// NBNS packet, only the questions are decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NbnsPacket<Span> {
+  /// Transaction id
+  pub transaction_id: u16,
+  /// Response flag, opcode, flags and response code
+  pub flags: u16,
+  /// Number of answer records
+  pub answer_count: u16,
+  /// Number of authority records
+  pub authority_count: u16,
+  /// Number of additional records
+  pub additional_count: u16,
+  /// Questions
+  pub questions: Vec<NbnsQuestion<Span>>,
+}
+
+impl<Span> NbnsPacket<Span> {
+  /// Length of a NBNS header
+  pub const HEADER_LEN: usize = 12;
+  /// Response flag
+  pub const RESPONSE: u16 = 0x8000;
+
+  /// Return the opcode, 0 query, 5 registration, 6 release
+  pub const fn opcode(&self) -> u8 {
+    (self.flags >> 11u8 & 0x0F) as u8
+  }
+}
+
+/// Parse the header and questions of a NBNS packet, the stream is left at
+/// the resource records
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn nbns_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<NbnsPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SmbParse<Stream, Context>,
+{
+  let Success {
+    token: (transaction_id, flags, question_count, answer_count, authority_count, additional_count),
+    stream,
+  } = (u16_be, u16_be, u16_be, u16_be, u16_be, u16_be).parse(stream)?;
+
+  (netbios_name, u16_be, u16_be)
+    .map(|(name, question_type, question_class)| NbnsQuestion {
+      name,
+      question_type,
+      question_class,
+    })
+    .fold_bounds(usize::from(question_count), Vec::new, Acc::acc)
+    .map(|questions| NbnsPacket {
+      transaction_id,
+      flags,
+      answer_count,
+      authority_count,
+      additional_count,
+      questions,
+    })
+    .parse(stream)
+}
+
+struct_variants! {
+  Smb2Command, command, u16:
+    /// SMB2 NEGOTIATE
+    NEGOTIATE => 0x0000,
+    /// SMB2 SESSION_SETUP
+    SESSION_SETUP => 0x0001,
+    /// SMB2 LOGOFF
+    LOGOFF => 0x0002,
+    /// SMB2 TREE_CONNECT
+    TREE_CONNECT => 0x0003,
+    /// SMB2 TREE_DISCONNECT
+    TREE_DISCONNECT => 0x0004,
+    /// SMB2 CREATE
+    CREATE => 0x0005,
+    /// SMB2 CLOSE
+    CLOSE => 0x0006,
+    /// SMB2 FLUSH
+    FLUSH => 0x0007,
+    /// SMB2 READ
+    READ => 0x0008,
+    /// SMB2 WRITE
+    WRITE => 0x0009,
+    /// SMB2 LOCK
+    LOCK => 0x000A,
+    /// SMB2 IOCTL
+    IOCTL => 0x000B,
+    /// SMB2 CANCEL
+    CANCEL => 0x000C,
+    /// SMB2 ECHO
+    ECHO => 0x000D,
+    /// SMB2 QUERY_DIRECTORY
+    QUERY_DIRECTORY => 0x000E,
+    /// SMB2 CHANGE_NOTIFY
+    CHANGE_NOTIFY => 0x000F,
+    /// SMB2 QUERY_INFO
+    QUERY_INFO => 0x0010,
+    /// SMB2 SET_INFO
+    SET_INFO => 0x0011,
+    /// SMB2 OPLOCK_BREAK
+    OPLOCK_BREAK => 0x0012,
+}
+
+/// SMB2 and SMB3 header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Smb2Header {
+  /// Number of credits charged by the request
+  pub credit_charge: u16,
+  /// Status of a response, channel sequence of a request
+  pub status: u32,
+  /// Command
+  pub command: Smb2Command,
+  /// Credits requested or granted
+  pub credits: u16,
+  /// Flags
+  pub flags: u32,
+  /// Offset of the next command of a compound message, 0 for the last one
+  pub next_command: u32,
+  /// Message id
+  pub message_id: u64,
+  /// Async id, only for async messages
+  pub async_id: Option<u64>,
+  /// Tree id, only for sync messages
+  pub tree_id: Option<u32>,
+  /// Session id
+  pub session_id: u64,
+  /// Signature
+  pub signature: [u8; 16],
+}
+
+impl Smb2Header {
+  /// Async message flag
+  pub const ASYNC_COMMAND: u32 = 0x0000_0002;
+  /// Length of a SMB2 header
+  pub const LEN: usize = 64;
+  /// Protocol id of SMB2 and SMB3
+  pub const PROTOCOL_ID: [u8; 4] = [0xFE, b'S', b'M', b'B'];
+  /// Related operations flag, the message is part of a compound message
+  pub const RELATED_OPERATIONS: u32 = 0x0000_0004;
+  /// Response flag
+  pub const SERVER_TO_REDIR: u32 = 0x0000_0001;
+  /// Signed message flag
+  pub const SIGNED: u32 = 0x0000_0008;
+
+  /// Return true if the message is a response
+  pub const fn is_response(&self) -> bool {
+    self.flags & Self::SERVER_TO_REDIR != 0
+  }
+}
+
+/// Parse a SMB2 or SMB3 header, the stream is left at the command body.
+/// Encrypted and compressed messages are not parsed.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn smb2_header<Stream, Context>(stream: Stream) -> Parsed<Smb2Header, Stream, Context>
+where
+  (): SmbParse<Stream, Context>,
+{
+  let Success {
+    token: (_protocol_id, _structure_size, credit_charge, status, command, credits, flags),
+    stream,
+  } = (
+    octet.fill().try_map(|protocol_id| {
+      if protocol_id == Smb2Header::PROTOCOL_ID {
+        Ok(protocol_id)
+      } else {
+        Err(Context::new(SmbAtom::ProtocolId(protocol_id)))
+      }
+    }),
+    u16_le.try_map(|size| {
+      if usize::from(size) == Smb2Header::LEN {
+        Ok(size)
+      } else {
+        Err(Context::new(SmbAtom::StructureSize(size)))
+      }
+    }),
+    u16_le,
+    u32_le,
+    u16_le.map(Smb2Command::new),
+    u16_le,
+    u32_le,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: (next_command, message_id, id, session_id, signature),
+    stream,
+  } = (u32_le, u64_le, u64_le, u64_le, octet.fill()).parse(stream)?;
+  let (async_id, tree_id) = if flags & Smb2Header::ASYNC_COMMAND != 0 {
+    (Some(id), None)
+  } else {
+    // a reserved field precede the tree id
+    (None, Some((id >> 32u8) as u32))
+  };
+
+  Parsed::Success {
+    token: Smb2Header {
+      credit_charge,
+      status,
+      command,
+      credits,
+      flags,
+      next_command,
+      message_id,
+      async_id,
+      tree_id,
+      session_id,
+      signature,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    NetbiosMessageType,
+    NetbiosName,
+    Smb2Command,
+  };
+
+  #[test]
+  fn smb() {
+    let mut smb2 = vec![
+      0x00, 0x00, 0x00, 0x40, 0xFE, b'S', b'M', b'B', 0x40, 0x00, 0x01, 0x00,
+    ];
+    smb2.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x1F, 0x00]);
+    smb2.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    smb2.extend_from_slice(&[0x07, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    smb2.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00]);
+    smb2.extend_from_slice(&[0x11, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    smb2.extend_from_slice(&[0x00; 16]);
+
+    let Success { token, stream } = super::netbios_session::<_, Ignore>(smb2.as_slice()).unwrap();
+    assert_eq!(token.message_type, NetbiosMessageType::SESSION_MESSAGE);
+    assert_eq!(stream, b"");
+    let Success { token, stream } = super::smb2_header::<_, Ignore>(token.payload).unwrap();
+    assert_eq!(token.command, Smb2Command::TREE_CONNECT);
+    assert_eq!(token.message_id, 7);
+    assert_eq!(token.tree_id, Some(5));
+    assert_eq!(token.session_id, 0x11);
+    assert!(!token.is_response());
+    assert_eq!(stream, b"");
+
+    let mut query = vec![
+      0x12, 0x34, 0x01, 0x10, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20,
+    ];
+    for byte in b"WORKGROUP      \x1B" {
+      query.push(b'A' + (byte >> 4u8));
+      query.push(b'A' + (byte & 0x0F));
+    }
+    query.extend_from_slice(&[0x00, 0x00, 0x20, 0x00, 0x01]);
+    let Success { token, stream } = super::nbns_packet::<_, Ignore>(query.as_slice()).unwrap();
+    assert_eq!(token.transaction_id, 0x1234);
+    assert_eq!(token.opcode(), 0);
+    assert_eq!(token.questions[0].name.name, b"WORKGROUP");
+    assert_eq!(
+      token.questions[0].name.suffix,
+      NetbiosName::<()>::DOMAIN_MASTER_BROWSER
+    );
+    assert_eq!(token.questions[0].question_type, 0x20);
+    assert_eq!(stream, b"");
+  }
+}
@@ -0,0 +1,689 @@
+//! Handles headers cut by the snapshot length of a capture
+
+#[cfg(feature = "ipv4")]
+use std::net::Ipv4Addr;
+#[cfg(feature = "ipv6")]
+use std::net::Ipv6Addr;
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  marker::PhantomData,
+};
+
+#[cfg(feature = "ethernet")]
+use binator::context::Ignore;
+use binator::{
+  Contexting,
+  Parse,
+  Parsed,
+};
+
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+use crate::IPProtocol;
+#[cfg(feature = "ipv4")]
+use crate::IPv4Header;
+#[cfg(feature = "ipv6")]
+use crate::IPv6Header;
+#[cfg(any(feature = "tcp", feature = "udp"))]
+use crate::Port;
+#[cfg(feature = "udp")]
+use crate::UdpHeader;
+#[cfg(feature = "ethernet")]
+use crate::{
+  llc_header,
+  EtherType,
+  EtherTypeOrLength,
+  EthernetFrame,
+  LlcHeader,
+  SnapHeader,
+  VlanTag,
+};
+#[cfg(feature = "tcp")]
+use crate::{
+  TcpFlags,
+  TcpHeader,
+};
+
+/// Atom produced by [snapped] when the stream is shorter than the header,
+/// this is distinct from a malformed header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruncatedAtom {
+  /// Number of bytes the header need
+  pub needed: usize,
+  /// Number of bytes available
+  pub available: usize,
+}
+
+impl Display for TruncatedAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "Truncated: needed {} bytes found {}",
+      self.needed, self.available
+    )
+  }
+}
+
+/// Position of a field in a header, in bytes, fields sharing a byte overlap
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HeaderField {
+  /// Name of the field in the header struct
+  pub name: &'static str,
+  /// Offset from the start of the header
+  pub offset: usize,
+  /// Length of the field
+  pub len: usize,
+}
+
+#[cfg(any(
+  feature = "ethernet",
+  feature = "ipv4",
+  feature = "ipv6",
+  feature = "tcp",
+  feature = "udp"
+))]
+const fn field(name: &'static str, offset: usize, len: usize) -> HeaderField {
+  HeaderField { name, offset, len }
+}
+
+#[cfg(any(
+  feature = "ethernet",
+  feature = "ipv4",
+  feature = "ipv6",
+  feature = "tcp",
+  feature = "udp"
+))]
+fn array<const N: usize>(bytes: &[u8], offset: usize) -> Option<[u8; N]> {
+  bytes.get(offset..offset + N)?.try_into().ok()
+}
+
+#[cfg(any(
+  feature = "ethernet",
+  feature = "ipv4",
+  feature = "ipv6",
+  feature = "tcp",
+  feature = "udp"
+))]
+fn u16_at(bytes: &[u8], offset: usize) -> Option<u16> {
+  array(bytes, offset).map(u16::from_be_bytes)
+}
+
+#[cfg(any(feature = "tcp", feature = "ipv6"))]
+fn u32_at(bytes: &[u8], offset: usize) -> Option<u32> {
+  array(bytes, offset).map(u32::from_be_bytes)
+}
+
+/// Byte layout of a header
+pub trait HeaderLayout {
+  /// Fixed fields of the header
+  const FIELDS: &'static [HeaderField];
+
+  /// Header with every field optional
+  type Partial;
+
+  /// Return the length the header starting bytes need, bytes can be
+  /// truncated
+  fn needed_len(bytes: &[u8]) -> usize;
+
+  /// Return the fields fully present in the first bytes of a truncated
+  /// header, fields are decoded but not validated
+  fn partial(bytes: &[u8]) -> Self::Partial;
+
+  /// Return the fields fully present in the available first bytes of a
+  /// header
+  fn present_fields(available: usize) -> impl Iterator<Item = &'static HeaderField> {
+    Self::FIELDS
+      .iter()
+      .filter(move |field| field.offset + field.len <= available)
+  }
+}
+
+#[cfg(feature = "ethernet")]
+impl HeaderLayout for EthernetFrame {
+  type Partial = PartialEthernetFrame;
+
+  const FIELDS: &'static [HeaderField] = &[
+    field("destination", 0, 6),
+    field("source", 6, 6),
+    field("ether_type", 12, 2),
+  ];
+
+  // an 802.3 length is followed by the LLC header and its SNAP header
+  fn needed_len(bytes: &[u8]) -> usize {
+    let mut len = Self::MIN_LEN;
+    while let Some(value) = u16_at(bytes, len - 2) {
+      if value <= EtherType::LANMAX.ether_type() {
+        return len + llc_len(bytes.get(len..).unwrap_or_default());
+      }
+      if !VlanTag::is_tpid(EtherType::new(value)) {
+        break;
+      }
+      len += VlanTag::LEN;
+    }
+    len
+  }
+
+  fn partial(bytes: &[u8]) -> Self::Partial {
+    let mut partial = PartialEthernetFrame {
+      destination: array(bytes, 0),
+      source: array(bytes, 6),
+      ..PartialEthernetFrame::default()
+    };
+    let mut len = Self::MIN_LEN;
+    while let Some(value) = u16_at(bytes, len - 2) {
+      partial.ether_type = EtherTypeOrLength::new(value);
+      match partial.ether_type {
+        Some(EtherTypeOrLength::EtherType(tpid)) if VlanTag::is_tpid(tpid) => {
+          let Some(tci) = u16_at(bytes, len) else {
+            partial.ether_type = None;
+            break;
+          };
+          partial.vlans.push(VlanTag::new(tpid, tci));
+          len += VlanTag::LEN;
+        }
+        Some(EtherTypeOrLength::Length(_)) => {
+          partial.llc = match llc_header::<_, Ignore>(&bytes[len..]) {
+            Parsed::Success { token, .. } => Some(token),
+            _ => None,
+          };
+          break;
+        }
+        _ => break,
+      }
+    }
+    partial
+  }
+}
+
+#[cfg(feature = "ethernet")]
+fn llc_len(llc: &[u8]) -> usize {
+  match *llc {
+    [LlcHeader::SAP_SNAP, LlcHeader::SAP_SNAP, LlcHeader::UI, ..] => 3 + SnapHeader::LEN,
+    [_, _, control, ..] if control & 0b11 != 0b11 => 4,
+    _ => 3,
+  }
+}
+
+/// Fields of an ethernet header present in a truncated frame, see
+/// [EthernetFrame]
+#[cfg(feature = "ethernet")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartialEthernetFrame {
+  /// Destination MAC address
+  pub destination: Option<[u8; 6]>,
+  /// Source MAC address
+  pub source: Option<[u8; 6]>,
+  /// Complete VLAN tags
+  pub vlans: Vec<VlanTag>,
+  /// EtherType or 802.3 length following the VLAN tags
+  pub ether_type: Option<EtherTypeOrLength>,
+  /// LLC header of an 802.3 frame
+  pub llc: Option<LlcHeader>,
+}
+
+#[cfg(feature = "ipv4")]
+impl<Span> HeaderLayout for IPv4Header<Span> {
+  type Partial = PartialIpv4Header;
+
+  const FIELDS: &'static [HeaderField] = &[
+    field("version", 0, 1),
+    field("ihl", 0, 1),
+    field("tos", 1, 1),
+    field("length", 2, 2),
+    field("id", 4, 2),
+    field("flags", 6, 1),
+    field("fragment_offset", 6, 2),
+    field("ttl", 8, 1),
+    field("protocol", 9, 1),
+    field("chksum", 10, 2),
+    field("source_addr", 12, 4),
+    field("dest_addr", 16, 4),
+  ];
+
+  fn needed_len(bytes: &[u8]) -> usize {
+    match bytes.first() {
+      Some(first) => usize::from(first & 0x0F).max(5) * 4,
+      None => Self::MIN_LEN,
+    }
+  }
+
+  fn partial(bytes: &[u8]) -> Self::Partial {
+    let byte = |offset| bytes.get(offset).copied();
+    PartialIpv4Header {
+      version: byte(0).map(|byte| byte >> 4u8),
+      ihl: byte(0).map(|byte| byte & 0x0F),
+      tos: byte(1),
+      length: u16_at(bytes, 2),
+      id: u16_at(bytes, 4),
+      flags: byte(6).map(|byte| byte >> 5u8),
+      fragment_offset: u16_at(bytes, 6).map(|value| value & 0x1FFF),
+      ttl: byte(8),
+      protocol: byte(9).map(IPProtocol::new),
+      chksum: u16_at(bytes, 10),
+      source_addr: array(bytes, 12).map(Ipv4Addr::from),
+      dest_addr: array(bytes, 16).map(Ipv4Addr::from),
+    }
+  }
+}
+
+/// Fields of an IPv4 header present in a truncated packet, see [IPv4Header]
+#[cfg(feature = "ipv4")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartialIpv4Header {
+  /// Version, 4 for a valid header
+  pub version: Option<u8>,
+  /// Header length in 32 bits words
+  pub ihl: Option<u8>,
+  /// Type of service
+  pub tos: Option<u8>,
+  /// Total length
+  pub length: Option<u16>,
+  /// Identification
+  pub id: Option<u16>,
+  /// Fragmentation flags
+  pub flags: Option<u8>,
+  /// Fragment offset in 8 bytes units
+  pub fragment_offset: Option<u16>,
+  /// Time to live
+  pub ttl: Option<u8>,
+  /// Protocol of the payload
+  pub protocol: Option<IPProtocol>,
+  /// Header checksum
+  pub chksum: Option<u16>,
+  /// Source address
+  pub source_addr: Option<Ipv4Addr>,
+  /// Destination address
+  pub dest_addr: Option<Ipv4Addr>,
+}
+
+#[cfg(feature = "ipv6")]
+impl HeaderLayout for IPv6Header {
+  type Partial = PartialIpv6Header;
+
+  const FIELDS: &'static [HeaderField] = &[
+    field("version", 0, 1),
+    field("ds", 0, 2),
+    field("ecn", 1, 1),
+    field("flow_label", 1, 3),
+    field("length", 4, 2),
+    field("next_header", 6, 1),
+    field("hop_limit", 7, 1),
+    field("source_addr", 8, 16),
+    field("dest_addr", 24, 16),
+  ];
+
+  fn needed_len(_bytes: &[u8]) -> usize {
+    Self::LEN
+  }
+
+  fn partial(bytes: &[u8]) -> Self::Partial {
+    let byte = |offset| bytes.get(offset).copied();
+    PartialIpv6Header {
+      version: byte(0).map(|byte| byte >> 4u8),
+      ds: u16_at(bytes, 0).map(|value| (value >> 6u8) as u8 & 0x3F),
+      ecn: byte(1).map(|byte| (byte >> 4u8) & 0b11),
+      flow_label: u32_at(bytes, 0).map(|value| value & 0x000F_FFFF),
+      length: u16_at(bytes, 4),
+      next_header: byte(6).map(IPProtocol::new),
+      hop_limit: byte(7),
+      source_addr: array(bytes, 8).map(Ipv6Addr::from),
+      dest_addr: array(bytes, 24).map(Ipv6Addr::from),
+    }
+  }
+}
+
+/// Fields of an IPv6 header present in a truncated packet, see [IPv6Header]
+#[cfg(feature = "ipv6")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartialIpv6Header {
+  /// Version, 6 for a valid header
+  pub version: Option<u8>,
+  /// Differentiated services
+  pub ds: Option<u8>,
+  /// Explicit congestion notification
+  pub ecn: Option<u8>,
+  /// Flow label
+  pub flow_label: Option<u32>,
+  /// Payload length
+  pub length: Option<u16>,
+  /// Protocol of the next header
+  pub next_header: Option<IPProtocol>,
+  /// Hop limit
+  pub hop_limit: Option<u8>,
+  /// Source address
+  pub source_addr: Option<Ipv6Addr>,
+  /// Destination address
+  pub dest_addr: Option<Ipv6Addr>,
+}
+
+#[cfg(feature = "tcp")]
+impl<Span> HeaderLayout for TcpHeader<Span> {
+  type Partial = PartialTcpHeader;
+
+  const FIELDS: &'static [HeaderField] = &[
+    field("source_port", 0, 2),
+    field("dest_port", 2, 2),
+    field("sequence_no", 4, 4),
+    field("ack_no", 8, 4),
+    field("flags", 12, 2),
+    field("window", 14, 2),
+    field("checksum", 16, 2),
+    field("urgent_pointer", 18, 2),
+  ];
+
+  fn needed_len(bytes: &[u8]) -> usize {
+    match bytes.get(12) {
+      Some(data_offset) => usize::from(data_offset >> 4u8).max(5) * 4,
+      None => Self::MIN_LEN,
+    }
+  }
+
+  fn partial(bytes: &[u8]) -> Self::Partial {
+    let wire = u16_at(bytes, 12).map(TcpFlags::from_wire);
+    PartialTcpHeader {
+      source_port: u16_at(bytes, 0).map(Port),
+      dest_port: u16_at(bytes, 2).map(Port),
+      sequence_no: u32_at(bytes, 4),
+      ack_no: u32_at(bytes, 8),
+      data_offset: bytes.get(12).map(|byte| byte >> 4u8),
+      flags: wire.map(|(_, flags)| flags),
+      window: u16_at(bytes, 14),
+      checksum: u16_at(bytes, 16),
+      urgent_pointer: u16_at(bytes, 18),
+    }
+  }
+}
+
+/// Fields of a TCP header present in a truncated segment, see [TcpHeader]
+#[cfg(feature = "tcp")]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PartialTcpHeader {
+  /// Source port
+  pub source_port: Option<Port>,
+  /// Destination port
+  pub dest_port: Option<Port>,
+  /// Sequence number
+  pub sequence_no: Option<u32>,
+  /// Acknowledgment number
+  pub ack_no: Option<u32>,
+  /// Header length in 32 bits words
+  pub data_offset: Option<u8>,
+  /// Control bits
+  pub flags: Option<TcpFlags>,
+  /// Receive window
+  pub window: Option<u16>,
+  /// Checksum
+  pub checksum: Option<u16>,
+  /// Urgent pointer
+  pub urgent_pointer: Option<u16>,
+}
+
+#[cfg(feature = "udp")]
+impl HeaderLayout for UdpHeader {
+  type Partial = PartialUdpHeader;
+
+  const FIELDS: &'static [HeaderField] = &[
+    field("source_port", 0, 2),
+    field("dest_port", 2, 2),
+    field("length", 4, 2),
+    field("checksum", 6, 2),
+  ];
+
+  fn needed_len(_bytes: &[u8]) -> usize {
+    Self::LEN
+  }
+
+  fn partial(bytes: &[u8]) -> Self::Partial {
+    PartialUdpHeader {
+      source_port: u16_at(bytes, 0).map(Port),
+      dest_port: u16_at(bytes, 2).map(Port),
+      length: u16_at(bytes, 4),
+      checksum: u16_at(bytes, 6),
+    }
+  }
+}
+
+/// Fields of a UDP header present in a truncated datagram, see [UdpHeader]
+#[cfg(feature = "udp")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PartialUdpHeader {
+  /// Source port
+  pub source_port: Option<Port>,
+  /// Destination port
+  pub dest_port: Option<Port>,
+  /// Length of header and payload
+  pub length: Option<u16>,
+  /// Checksum
+  pub checksum: Option<u16>,
+}
+
+/// Implementation of [snapped]
+pub struct Snapped<Parser, Header> {
+  parser: Parser,
+  header: PhantomData<fn() -> Header>,
+}
+
+impl<Stream, Context, Parser, Header> Parse<Stream, Context> for Snapped<Parser, Header>
+where
+  Stream: AsRef<[u8]>,
+  Parser: Parse<Stream, Context>,
+  Header: HeaderLayout,
+  Context: Contexting<TruncatedAtom>,
+{
+  type Token = Parser::Token;
+
+  fn parse(&mut self, stream: Stream) -> Parsed<Self::Token, Stream, Context> {
+    let bytes = stream.as_ref();
+    let needed = Header::needed_len(bytes);
+    if bytes.len() < needed {
+      Parsed::Failure(Context::new(TruncatedAtom {
+        needed,
+        available: bytes.len(),
+      }))
+    } else {
+      self.parser.parse(stream)
+    }
+  }
+}
+
+/// Fail with [TruncatedAtom] instead of running parser when the stream is
+/// shorter than the header parser would read, use
+/// [HeaderLayout::present_fields] to know what fields are still readable or
+/// [snapped_partial] to get their values.
+pub fn snapped<Header, Parser>(parser: Parser) -> Snapped<Parser, Header>
+where
+  Header: HeaderLayout,
+{
+  Snapped {
+    parser,
+    header: PhantomData,
+  }
+}
+
+/// Token of [snapped_partial]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Snap<Token, Partial> {
+  /// The header was complete, token is the result of the parser
+  Complete(Token),
+  /// The stream is shorter than the header
+  Truncated {
+    /// Fields present in the stream
+    partial: Partial,
+    /// Number of bytes needed and available
+    truncated: TruncatedAtom,
+  },
+}
+
+/// Implementation of [snapped_partial]
+pub struct SnappedPartial<Parser, Header> {
+  parser: Parser,
+  header: PhantomData<fn() -> Header>,
+}
+
+impl<Stream, Context, Parser, Header> Parse<Stream, Context> for SnappedPartial<Parser, Header>
+where
+  Stream: AsRef<[u8]>,
+  Parser: Parse<Stream, Context>,
+  Header: HeaderLayout,
+{
+  type Token = Snap<Parser::Token, Header::Partial>;
+
+  fn parse(&mut self, stream: Stream) -> Parsed<Self::Token, Stream, Context> {
+    let bytes = stream.as_ref();
+    let needed = Header::needed_len(bytes);
+    if bytes.len() < needed {
+      Parsed::Success {
+        token: Snap::Truncated {
+          partial: Header::partial(bytes),
+          truncated: TruncatedAtom {
+            needed,
+            available: bytes.len(),
+          },
+        },
+        stream,
+      }
+    } else {
+      self.parser.parse(stream).map_token(Snap::Complete)
+    }
+  }
+}
+
+/// Like [snapped] but a stream shorter than the header is a success returning
+/// the fields present, the stream is left untouched.
+pub fn snapped_partial<Header, Parser>(parser: Parser) -> SnappedPartial<Parser, Header>
+where
+  Header: HeaderLayout,
+{
+  SnappedPartial {
+    parser,
+    header: PhantomData,
+  }
+}
+
+#[cfg(all(test, feature = "ipv4", feature = "tcp"))]
+mod tests {
+  use binator::{
+    context::Tree,
+    Parse,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    snapped,
+    snapped_partial,
+    HeaderLayout,
+    PartialTcpHeader,
+    Snap,
+    TruncatedAtom,
+  };
+  use crate::{
+    concrete::NetworkAtom,
+    ipv4_header,
+    tcp_header,
+    IPProtocol,
+    IPv4Header,
+    Port,
+    TcpHeader,
+  };
+  #[cfg(feature = "ethernet")]
+  use crate::{
+    EtherTypeOrLength,
+    EthernetFrame,
+  };
+
+  #[test]
+  fn snapped_tcp() {
+    // 30 bytes snaplen of an IPv4 TCP packet
+    let bytes = [
+      0x45, 0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00,
+      0x6C, 0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39,
+    ];
+
+    let Success { stream, .. } =
+      snapped::<IPv4Header<&[u8]>, _>(ipv4_header::<_, Tree<NetworkAtom>>)
+        .parse(&bytes[..])
+        .unwrap();
+
+    let parsed = snapped::<TcpHeader<&[u8]>, _>(tcp_header::<_, Tree<NetworkAtom>>).parse(stream);
+    let Parsed::Failure(context) = parsed else {
+      panic!("expected failure");
+    };
+    assert!(context.to_string().contains(
+      &TruncatedAtom {
+        needed: 20,
+        available: 10,
+      }
+      .to_string()
+    ));
+
+    let present: Vec<_> = TcpHeader::<()>::present_fields(stream.len())
+      .map(|field| field.name)
+      .collect();
+    assert_eq!(present, ["source_port", "dest_port", "sequence_no"]);
+
+    let Success { token, .. } =
+      snapped_partial::<TcpHeader<&[u8]>, _>(tcp_header::<_, Tree<NetworkAtom>>)
+        .parse(stream)
+        .unwrap();
+    assert_eq!(
+      token,
+      Snap::Truncated {
+        partial: PartialTcpHeader {
+          source_port: Some(Port(45250)),
+          dest_port: Some(Port(80)),
+          sequence_no: Some(0xB0EE_32A6),
+          ..PartialTcpHeader::default()
+        },
+        truncated: TruncatedAtom {
+          needed: 20,
+          available: 10,
+        },
+      }
+    );
+
+    let partial = IPv4Header::<()>::partial(&bytes[..14]);
+    assert_eq!(partial.version, Some(4));
+    assert_eq!(partial.protocol, Some(IPProtocol::TCP));
+    assert_eq!(partial.fragment_offset, Some(0));
+    assert_eq!(partial.flags, Some(0b010));
+    assert_eq!(partial.source_addr, None);
+    assert!(matches!(
+      snapped_partial::<IPv4Header<&[u8]>, _>(ipv4_header::<_, Tree<NetworkAtom>>)
+        .parse(&bytes[..]),
+      Parsed::Success {
+        token: Snap::Complete(_),
+        ..
+      }
+    ));
+  }
+
+  #[cfg(feature = "ethernet")]
+  #[test]
+  fn snapped_ethernet_llc() {
+    // 802.3 frame with a SNAP header
+    let frame = [
+      0x01, 0x00, 0x0C, 0xCC, 0xCC, 0xCC, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x00, 0x40, 0xAA,
+      0xAA, 0x03, 0x00, 0x00, 0x0C, 0x20, 0x00,
+    ];
+    assert_eq!(EthernetFrame::needed_len(&frame), 22);
+    assert_eq!(EthernetFrame::needed_len(&frame[..14]), 17);
+
+    let partial = EthernetFrame::partial(&frame[..20]);
+    assert_eq!(partial.source, Some([0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B]));
+    assert_eq!(partial.ether_type, Some(EtherTypeOrLength::Length(0x40)));
+    assert_eq!(partial.llc, None);
+    assert!(EthernetFrame::partial(&frame).llc.is_some());
+
+    // VLAN tag cut before its TCI
+    let tagged = [
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x81, 0x00, 0x04,
+    ];
+    assert_eq!(EthernetFrame::needed_len(&tagged), 18);
+    let partial = EthernetFrame::partial(&tagged);
+    assert_eq!(partial.ether_type, None);
+    assert!(partial.vlans.is_empty());
+  }
+}
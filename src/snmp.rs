@@ -0,0 +1,441 @@
+//! Handles parsing of SNMPv1 and SNMPv2c messages
+//! <https://www.rfc-editor.org/rfc/rfc1157>
+//! <https://www.rfc-editor.org/rfc/rfc3416>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::Ipv4Addr,
+};
+
+use binator::{
+  base::{
+    primitive::u32_be,
+    take,
+  },
+  utils::Utils,
+  Contexting,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  ber_expect,
+  ber_header,
+  ber_integer,
+  ber_integer_content,
+  ber_octet_string,
+  ber_oid,
+  ber_oid_content,
+  ber_unsigned_content,
+  struct_variants,
+  BerOid,
+  BerParse,
+  BerTag,
+  BerTlv,
+  Port,
+};
+
+/// UDP port of SNMP agents
+pub const SNMP_PORT: Port = Port(161);
+/// UDP port of SNMP trap receivers
+pub const SNMP_TRAP_PORT: Port = Port(162);
+
+/// Meta trait for snmp combinator
+pub trait SnmpParse<Stream, Context> = where
+  (): BerParse<Stream, Context>,
+  Context: Contexting<SnmpAtom>;
+
+/// Atom produced by snmp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnmpAtom {
+  /// When the version is not v1 or v2c
+  Version(i64),
+  /// When the PDU tag is not a context-specific constructed tag
+  Pdu(BerTag),
+  /// When the variable bindings overflow their list or an address is not 4
+  /// bytes long
+  Length(usize),
+}
+
+impl Display for SnmpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SnmpAtom::Version(version) => {
+        write!(f, "Version: expected v1 or v2c found {}", version)
+      }
+      SnmpAtom::Pdu(tag) => write!(f, "Pdu: {} is not a PDU", tag),
+      SnmpAtom::Length(length) => {
+        write!(f, "Length: length {} doesn't match its content", length)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  SnmpVersion, version, u8:
+    /// SNMPv1
+    V1 => 0,
+    /// SNMPv2c
+    V2C => 1,
+}
+
+struct_variants! {
+  SnmpPduType, pdu_type, u8:
+    /// GetRequest-PDU
+    GET_REQUEST => 0,
+    /// GetNextRequest-PDU
+    GET_NEXT_REQUEST => 1,
+    /// Response-PDU
+    RESPONSE => 2,
+    /// SetRequest-PDU
+    SET_REQUEST => 3,
+    /// SNMPv1 Trap-PDU
+    TRAP => 4,
+    /// GetBulkRequest-PDU
+    GET_BULK_REQUEST => 5,
+    /// InformRequest-PDU
+    INFORM_REQUEST => 6,
+    /// SNMPv2-Trap-PDU
+    SNMPV2_TRAP => 7,
+    /// Report-PDU
+    REPORT => 8,
+}
+
+/// Value of a variable binding
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnmpValue<Span> {
+  /// INTEGER
+  Integer(i64),
+  /// OCTET STRING
+  OctetString(Span),
+  /// NULL, the value of requests
+  Null,
+  /// OBJECT IDENTIFIER
+  Oid(BerOid),
+  /// IpAddress
+  IpAddress(Ipv4Addr),
+  /// Counter32
+  Counter32(u32),
+  /// Gauge32 and Unsigned32
+  Gauge32(u32),
+  /// TimeTicks, in hundredths of a second
+  TimeTicks(u32),
+  /// Counter64
+  Counter64(u64),
+  /// noSuchObject exception
+  NoSuchObject,
+  /// noSuchInstance exception
+  NoSuchInstance,
+  /// endOfMibView exception
+  EndOfMibView,
+  /// Any other value
+  Other(BerTlv<Span>),
+}
+
+/// Variable binding, an object and its value
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnmpVarBind<Span> {
+  /// Object identifier
+  pub oid: BerOid,
+  /// Value
+  pub value: SnmpValue<Span>,
+}
+
+/// Every PDU but the SNMPv1 Trap-PDU
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnmpRequest<Span> {
+  /// PDU type
+  pub pdu_type: SnmpPduType,
+  /// Identifier matching requests and responses
+  pub request_id: i64,
+  /// Error status, non-repeaters for GetBulkRequest
+  pub error_status: i64,
+  /// Error index, max-repetitions for GetBulkRequest
+  pub error_index: i64,
+  /// Variable bindings
+  pub varbinds: Vec<SnmpVarBind<Span>>,
+}
+
+/// SNMPv1 Trap-PDU
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnmpTrap<Span> {
+  /// Type of object generating the trap
+  pub enterprise: BerOid,
+  /// Address of the agent
+  pub agent_addr: Ipv4Addr,
+  /// Generic trap type
+  pub generic_trap: i64,
+  /// Enterprise specific trap code
+  pub specific_trap: i64,
+  /// Time since the agent started
+  pub timestamp: u32,
+  /// Variable bindings
+  pub varbinds: Vec<SnmpVarBind<Span>>,
+}
+
+/// PDU of a message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SnmpPdu<Span> {
+  /// Request, response, SNMPv2 trap and report
+  Request(SnmpRequest<Span>),
+  /// SNMPv1 trap
+  Trap(SnmpTrap<Span>),
+}
+
+/// SNMP message, community based
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SnmpMessage<Span> {
+  /// Version
+  pub version: SnmpVersion,
+  /// Community string
+  pub community: Span,
+  /// PDU
+  pub pdu: SnmpPdu<Span>,
+}
+
+// application tags of the SMI types
+const IP_ADDRESS: u32 = 0;
+const COUNTER32: u32 = 1;
+const GAUGE32: u32 = 2;
+const TIME_TICKS: u32 = 3;
+const COUNTER64: u32 = 6;
+
+fn snmp_value<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SnmpValue<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SnmpParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = ber_header.parse(stream)?;
+  let len = header.length;
+
+  match (header.tag.class, header.tag.number) {
+    (BerTag::UNIVERSAL, 2) => ber_integer_content(stream, len).map_token(SnmpValue::Integer),
+    (BerTag::UNIVERSAL, 4) => take(len).map(SnmpValue::OctetString).parse(stream),
+    (BerTag::UNIVERSAL, 5) => take(len).map(|_| SnmpValue::Null).parse(stream),
+    (BerTag::UNIVERSAL, 6) => ber_oid_content(stream, len).map_token(SnmpValue::Oid),
+    (BerTag::APPLICATION, IP_ADDRESS) if len == 4 => u32_be
+      .map(|addr| SnmpValue::IpAddress(Ipv4Addr::from(addr)))
+      .parse(stream),
+    (BerTag::APPLICATION, COUNTER32) => {
+      ber_unsigned_content(stream, len).map_token(|value| SnmpValue::Counter32(value as u32))
+    }
+    (BerTag::APPLICATION, GAUGE32) => {
+      ber_unsigned_content(stream, len).map_token(|value| SnmpValue::Gauge32(value as u32))
+    }
+    (BerTag::APPLICATION, TIME_TICKS) => {
+      ber_unsigned_content(stream, len).map_token(|value| SnmpValue::TimeTicks(value as u32))
+    }
+    (BerTag::APPLICATION, COUNTER64) => {
+      ber_unsigned_content(stream, len).map_token(SnmpValue::Counter64)
+    }
+    (BerTag::CONTEXT, 0) => take(len).map(|_| SnmpValue::NoSuchObject).parse(stream),
+    (BerTag::CONTEXT, 1) => take(len).map(|_| SnmpValue::NoSuchInstance).parse(stream),
+    (BerTag::CONTEXT, 2) => take(len).map(|_| SnmpValue::EndOfMibView).parse(stream),
+    _ => take(len)
+      .map(|value| {
+        SnmpValue::Other(BerTlv {
+          tag: header.tag,
+          value,
+        })
+      })
+      .parse(stream),
+  }
+}
+
+fn snmp_varbinds<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Vec<SnmpVarBind<<Stream as Streaming>::Span>>, Stream, Context>
+where
+  (): SnmpParse<Stream, Context>,
+{
+  let Success {
+    token: list,
+    mut stream,
+  } = ber_expect(BerTag::SEQUENCE).parse(stream)?;
+
+  let mut len = list.length;
+  let mut varbinds = Vec::new();
+  while len > 0 {
+    let Success {
+      token: varbind,
+      stream: next,
+    } = ber_expect(BerTag::SEQUENCE).parse(stream)?;
+    let Some(rest) = len.checked_sub(varbind.total_len()) else {
+      return Parsed::Failure(Context::new(SnmpAtom::Length(list.length)));
+    };
+    let Success {
+      token: (oid, value),
+      stream: next,
+    } = (ber_oid, snmp_value).parse(next)?;
+    varbinds.push(SnmpVarBind { oid, value });
+    len = rest;
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: varbinds,
+    stream,
+  }
+}
+
+fn snmp_trap<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SnmpTrap<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SnmpParse<Stream, Context>,
+{
+  let Success {
+    token: (enterprise, agent_addr, generic_trap, specific_trap, timestamp, varbinds),
+    stream,
+  } = (
+    ber_oid,
+    |stream| {
+      let Success {
+        token: header,
+        stream,
+      } = ber_expect(BerTag {
+        class: BerTag::APPLICATION,
+        constructed: false,
+        number: IP_ADDRESS,
+      })
+      .parse(stream)?;
+      if header.length != 4 {
+        return Parsed::Failure(Context::new(SnmpAtom::Length(header.length)));
+      }
+      u32_be.map(Ipv4Addr::from).parse(stream)
+    },
+    ber_integer,
+    ber_integer,
+    |stream| {
+      let Success {
+        token: header,
+        stream,
+      } = ber_header.parse(stream)?;
+      ber_unsigned_content(stream, header.length)
+    },
+    snmp_varbinds,
+  )
+    .parse(stream)?;
+
+  Parsed::Success {
+    token: SnmpTrap {
+      enterprise,
+      agent_addr,
+      generic_trap,
+      specific_trap,
+      timestamp: timestamp as u32,
+      varbinds,
+    },
+    stream,
+  }
+}
+
+/// Parse a SNMPv1 or SNMPv2c message
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn snmp_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SnmpMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SnmpParse<Stream, Context>,
+{
+  let Success {
+    token: (_message, version, community, pdu),
+    stream,
+  } = (
+    ber_expect(BerTag::SEQUENCE),
+    ber_integer.try_map(|version| match version {
+      0 | 1 => Ok(SnmpVersion::new(version as u8)),
+      version => Err(Context::new(SnmpAtom::Version(version))),
+    }),
+    ber_octet_string,
+    ber_header.try_map(|header| {
+      if header.tag.class == BerTag::CONTEXT && header.tag.constructed {
+        Ok(header)
+      } else {
+        Err(Context::new(SnmpAtom::Pdu(header.tag)))
+      }
+    }),
+  )
+    .parse(stream)?;
+
+  let pdu_type = SnmpPduType::new(pdu.tag.number as u8);
+  let Success { token: pdu, stream } = if pdu_type == SnmpPduType::TRAP {
+    snmp_trap(stream).map_token(SnmpPdu::Trap)?
+  } else {
+    (ber_integer, ber_integer, ber_integer, snmp_varbinds)
+      .map(|(request_id, error_status, error_index, varbinds)| {
+        SnmpPdu::Request(SnmpRequest {
+          pdu_type,
+          request_id,
+          error_status,
+          error_index,
+          varbinds,
+        })
+      })
+      .parse(stream)?
+  };
+
+  Parsed::Success {
+    token: SnmpMessage {
+      version,
+      community,
+      pdu,
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    SnmpPdu,
+    SnmpPduType,
+    SnmpValue,
+    SnmpVersion,
+  };
+  use crate::BerOid;
+
+  #[test]
+  fn snmp_message() {
+    let response = [
+      0x30, 0x2B, 0x02, 0x01, 0x01, 0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c', 0xA2, 0x1E,
+      0x02, 0x02, 0x30, 0x39, 0x02, 0x01, 0x00, 0x02, 0x01, 0x00, 0x30, 0x12, 0x30, 0x10, 0x06,
+      0x08, 0x2B, 0x06, 0x01, 0x02, 0x01, 0x01, 0x03, 0x00, 0x43, 0x04, 0x00, 0x01, 0xE2, 0x40,
+    ];
+    let Success { token, stream } = super::snmp_message::<_, Ignore>(&response[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.version, SnmpVersion::V2C);
+    assert_eq!(token.community, b"public");
+    let SnmpPdu::Request(pdu) = token.pdu else {
+      panic!("expected a response");
+    };
+    assert_eq!(pdu.pdu_type, SnmpPduType::RESPONSE);
+    assert_eq!(pdu.request_id, 12345);
+    assert_eq!(pdu.varbinds.len(), 1);
+    assert_eq!(pdu.varbinds[0].oid, BerOid(vec![1, 3, 6, 1, 2, 1, 1, 3, 0]));
+    assert_eq!(pdu.varbinds[0].value, SnmpValue::TimeTicks(123456));
+  }
+}
@@ -0,0 +1,565 @@
+//! Handles parsing of the SOCKS4, SOCKS4a and SOCKS5 handshakes
+//! <https://www.rfc-editor.org/rfc/rfc1928>
+//! <https://www.rfc-editor.org/rfc/rfc1929>
+//! <https://www.openssh.com/txt/socks4.protocol>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  struct_variants,
+  Port,
+};
+
+/// TCP port of SOCKS servers
+pub const SOCKS_PORT: Port = Port(1080);
+
+/// Meta trait for socks combinator
+pub trait SocksParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<SocksAtom>;
+
+/// Atom produced by socks parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SocksAtom {
+  /// When the version is not the one expected
+  Version {
+    /// Expected version
+    expected: u8,
+    /// Found version
+    found: u8,
+  },
+  /// When the address type is unknown
+  AddressType(u8),
+}
+
+impl Display for SocksAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SocksAtom::Version { expected, found } => {
+        write!(f, "Version: expected {} found {}", expected, found)
+      }
+      SocksAtom::AddressType(address_type) => {
+        write!(f, "AddressType: unknown address type {}", address_type)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  SocksCommand, command, u8:
+    /// CONNECT
+    CONNECT => 1,
+    /// BIND
+    BIND => 2,
+    /// UDP ASSOCIATE, SOCKS5 only
+    UDP_ASSOCIATE => 3,
+}
+
+struct_variants! {
+  Socks5Method, method, u8:
+    /// No authentication required
+    NO_AUTHENTICATION => 0x00,
+    /// GSSAPI
+    GSSAPI => 0x01,
+    /// Username and password
+    USERNAME_PASSWORD => 0x02,
+    /// No acceptable methods, only in a method selection
+    NO_ACCEPTABLE => 0xFF,
+}
+
+struct_variants! {
+  Socks5ReplyCode, reply_code, u8:
+    /// Succeeded
+    SUCCEEDED => 0,
+    /// General SOCKS server failure
+    GENERAL_FAILURE => 1,
+    /// Connection not allowed by ruleset
+    NOT_ALLOWED => 2,
+    /// Network unreachable
+    NETWORK_UNREACHABLE => 3,
+    /// Host unreachable
+    HOST_UNREACHABLE => 4,
+    /// Connection refused
+    CONNECTION_REFUSED => 5,
+    /// TTL expired
+    TTL_EXPIRED => 6,
+    /// Command not supported
+    COMMAND_NOT_SUPPORTED => 7,
+    /// Address type not supported
+    ADDRESS_TYPE_NOT_SUPPORTED => 8,
+}
+
+struct_variants! {
+  Socks4Status, status, u8:
+    /// Request granted
+    GRANTED => 90,
+    /// Request rejected or failed
+    REJECTED => 91,
+    /// Rejected because the identd of the client is unreachable
+    IDENTD_UNREACHABLE => 92,
+    /// Rejected because the identd of the client report another user id
+    IDENTD_MISMATCH => 93,
+}
+
+/// Destination or bound address
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SocksAddress<Span> {
+  /// IPv4 address
+  Ipv4(Ipv4Addr),
+  /// Domain name, resolved by the server
+  Domain(Span),
+  /// IPv6 address, SOCKS5 only
+  Ipv6(Ipv6Addr),
+}
+
+/// SOCKS5 request or reply, and UDP ASSOCIATE datagram header
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SocksEndpoint<Span> {
+  /// Address
+  pub address: SocksAddress<Span>,
+  /// Port
+  pub port: Port,
+}
+
+/// SOCKS5 username and password authentication request
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Socks5Auth<Span> {
+  /// Username
+  pub username: Span,
+  /// Password, in clear
+  pub password: Span,
+}
+
+/// SOCKS5 request
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Socks5Request<Span> {
+  /// Command
+  pub command: SocksCommand,
+  /// Destination
+  pub destination: SocksEndpoint<Span>,
+}
+
+/// SOCKS5 reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Socks5Reply<Span> {
+  /// Reply code
+  pub reply_code: Socks5ReplyCode,
+  /// Address bound by the server
+  pub bound: SocksEndpoint<Span>,
+}
+
+/// Header of the datagrams relayed by UDP ASSOCIATE
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Socks5UdpHeader<Span> {
+  /// Fragment number, 0 for standalone datagrams
+  pub fragment: u8,
+  /// Destination
+  pub destination: SocksEndpoint<Span>,
+}
+
+/// SOCKS4 and SOCKS4a request
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Socks4Request<Span> {
+  /// Command, CONNECT or BIND
+  pub command: SocksCommand,
+  /// Destination, a domain for SOCKS4a
+  pub destination: SocksEndpoint<Span>,
+  /// User id
+  pub user_id: Span,
+}
+
+/// SOCKS4 reply
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Socks4Reply {
+  /// Status
+  pub status: Socks4Status,
+  /// Port, for BIND
+  pub port: Port,
+  /// Address, for BIND
+  pub address: Ipv4Addr,
+}
+
+fn socks_version<Stream, Context>(expected: u8) -> impl Parse<Stream, Context, Token = u8>
+where
+  (): SocksParse<Stream, Context>,
+{
+  octet.try_map(move |found| {
+    if found == expected {
+      Ok(found)
+    } else {
+      Err(Context::new(SocksAtom::Version { expected, found }))
+    }
+  })
+}
+
+fn socks_port<Stream, Context>(stream: Stream) -> Parsed<Port, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  u16_be.map(Port).parse(stream)
+}
+
+// span of a string prefixed by its length on one byte
+fn socks_string<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  octet.and_then(|len| take(usize::from(len))).parse(stream)
+}
+
+// span of a null terminated string, without its terminator
+fn socks_null_terminated<Stream, Context>(
+  stream: Stream,
+) -> Parsed<<Stream as Streaming>::Span, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  octet
+    .filter(|octet| *octet != 0)
+    .fold_bounds(.., || (), |(), _| ())
+    .span()
+    .map(Success::into_stream)
+    .and_drop(octet)
+    .parse(stream)
+}
+
+fn socks5_endpoint<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SocksEndpoint<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  let Success {
+    token: address_type,
+    stream,
+  } = octet.parse(stream)?;
+  let Success {
+    token: address,
+    stream,
+  } = match address_type {
+    1 => u32_be
+      .map(|addr| SocksAddress::Ipv4(Ipv4Addr::from(addr)))
+      .parse(stream)?,
+    3 => socks_string.map(SocksAddress::Domain).parse(stream)?,
+    4 => octet
+      .fill()
+      .map(|addr: [u8; 16]| SocksAddress::Ipv6(Ipv6Addr::from(addr)))
+      .parse(stream)?,
+    _ => return Parsed::Failure(Context::new(SocksAtom::AddressType(address_type))),
+  };
+
+  socks_port
+    .parse(stream)
+    .map_token(|port| SocksEndpoint { address, port })
+}
+
+/// Parse the SOCKS5 greeting of the client, the authentication methods it
+/// support
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks5_greeting<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Vec<Socks5Method>, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  let Success {
+    token: (_, count),
+    stream,
+  } = (socks_version(5), octet).parse(stream)?;
+  octet
+    .map(Socks5Method::new)
+    .fold_bounds(usize::from(count), Vec::new, Acc::acc)
+    .parse(stream)
+}
+
+/// Parse the SOCKS5 method selected by the server
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks5_method_selection<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Socks5Method, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  socks_version(5)
+    .drop_and(octet.map(Socks5Method::new))
+    .parse(stream)
+}
+
+/// Parse a SOCKS5 username and password authentication request
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks5_auth<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Socks5Auth<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  (socks_version(1), socks_string, socks_string)
+    .map(|(_, username, password)| Socks5Auth { username, password })
+    .parse(stream)
+}
+
+/// Parse a SOCKS5 request
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks5_request<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Socks5Request<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  (
+    socks_version(5),
+    octet.map(SocksCommand::new),
+    octet,
+    socks5_endpoint,
+  )
+    .map(|(_, command, _, destination)| Socks5Request {
+      command,
+      destination,
+    })
+    .parse(stream)
+}
+
+/// Parse a SOCKS5 reply
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks5_reply<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Socks5Reply<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  (
+    socks_version(5),
+    octet.map(Socks5ReplyCode::new),
+    octet,
+    socks5_endpoint,
+  )
+    .map(|(_, reply_code, _, bound)| Socks5Reply { reply_code, bound })
+    .parse(stream)
+}
+
+/// Parse the header of a datagram relayed by UDP ASSOCIATE, the stream is
+/// left at the data
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks5_udp_header<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Socks5UdpHeader<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  (u16_be, octet, socks5_endpoint)
+    .map(|(_, fragment, destination)| Socks5UdpHeader {
+      fragment,
+      destination,
+    })
+    .parse(stream)
+}
+
+/// Parse a SOCKS4 request, or a SOCKS4a one when the address is 0.0.0.x with
+/// x not zero, the domain follow the user id
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks4_request<Stream, Context>(
+  stream: Stream,
+) -> Parsed<Socks4Request<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  let Success {
+    token: (_, command, port, address, user_id),
+    stream,
+  } = (
+    socks_version(4),
+    octet.map(SocksCommand::new),
+    socks_port,
+    u32_be.map(Ipv4Addr::from),
+    socks_null_terminated,
+  )
+    .parse(stream)?;
+
+  let Success {
+    token: address,
+    stream,
+  } = match address.octets() {
+    [0, 0, 0, x] if x != 0 => socks_null_terminated
+      .map(SocksAddress::Domain)
+      .parse(stream)?,
+    _ => Success {
+      token: SocksAddress::Ipv4(address),
+      stream,
+    },
+  };
+
+  Parsed::Success {
+    token: Socks4Request {
+      command,
+      destination: SocksEndpoint { address, port },
+      user_id,
+    },
+    stream,
+  }
+}
+
+/// Parse a SOCKS4 reply
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn socks4_reply<Stream, Context>(stream: Stream) -> Parsed<Socks4Reply, Stream, Context>
+where
+  (): SocksParse<Stream, Context>,
+{
+  (
+    socks_version(0),
+    octet.map(Socks4Status::new),
+    socks_port,
+    u32_be.map(Ipv4Addr::from),
+  )
+    .map(|(_, status, port, address)| Socks4Reply {
+      status,
+      port,
+      address,
+    })
+    .parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv6Addr;
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    Socks5Method,
+    SocksAddress,
+    SocksCommand,
+  };
+  use crate::Port;
+
+  #[test]
+  fn socks() {
+    let greeting = [0x05, 0x02, 0x00, 0x02];
+    let Success { token, .. } = super::socks5_greeting::<_, Ignore>(&greeting[..]).unwrap();
+    assert_eq!(
+      token,
+      [
+        Socks5Method::NO_AUTHENTICATION,
+        Socks5Method::USERNAME_PASSWORD
+      ]
+    );
+
+    let auth = [0x01, 0x03, b'b', b'o', b'b', 0x02, b'p', b'w'];
+    let Success { token, .. } = super::socks5_auth::<_, Ignore>(&auth[..]).unwrap();
+    assert_eq!(token.username, b"bob");
+    assert_eq!(token.password, b"pw");
+
+    let request = [
+      0x05, 0x01, 0x00, 0x03, 0x0B, b'e', b'x', b'a', b'm', b'p', b'l', b'e', b'.', b'c', b'o',
+      b'm', 0x01, 0xBB,
+    ];
+    let Success { token, stream } = super::socks5_request::<_, Ignore>(&request[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.command, SocksCommand::CONNECT);
+    assert_eq!(
+      token.destination.address,
+      SocksAddress::Domain(&b"example.com"[..])
+    );
+    assert_eq!(token.destination.port, Port(443));
+
+    let udp = [
+      0x00, 0x00, 0x00, 0x04, 0x20, 0x01, 0x0D, 0xB8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00,
+      0x35, 0xAA,
+    ];
+    let Success { token, stream } = super::socks5_udp_header::<_, Ignore>(&udp[..]).unwrap();
+    assert_eq!(stream, [0xAA]);
+    assert_eq!(
+      token.destination.address,
+      SocksAddress::Ipv6(Ipv6Addr::new(0x2001, 0xDB8, 0, 0, 0, 0, 0, 1))
+    );
+
+    let socks4a = [
+      0x04, 0x01, 0x00, 0x50, 0x00, 0x00, 0x00, 0x01, b'i', b'd', 0x00, b'h', b'o', b's', b't',
+      0x00,
+    ];
+    let Success { token, stream } = super::socks4_request::<_, Ignore>(&socks4a[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.user_id, b"id");
+    assert_eq!(
+      token.destination.address,
+      SocksAddress::Domain(&b"host"[..])
+    );
+    assert_eq!(token.destination.port, Port(80));
+
+    assert!(matches!(
+      super::socks5_request::<_, Ignore>(&[0x04, 0x01, 0x00, 0x01][..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
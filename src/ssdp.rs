@@ -0,0 +1,211 @@
+//! Handles parsing of SSDP messages, the discovery protocol of UPnP, they are
+//! HTTP/1.1 message heads sent over UDP
+//! <https://openconnectivity.org/upnp-specs/UPnP-arch-DeviceArchitecture-v2.0-20200417.pdf>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
+use binator::{
+  utils::Utils,
+  Contexting,
+  Parse,
+  Parsed,
+  Streaming,
+};
+
+use crate::{
+  find_header,
+  http_request,
+  http_response,
+  HttpHeader,
+  HttpParse,
+  Port,
+};
+
+/// UDP port of SSDP
+pub const SSDP_PORT: Port = Port(1900);
+/// IPv4 multicast group of SSDP
+pub const SSDP_MULTICAST_V4: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+/// IPv6 link-local multicast group of SSDP
+pub const SSDP_MULTICAST_V6: Ipv6Addr = Ipv6Addr::new(0xFF02, 0, 0, 0, 0, 0, 0, 0xC);
+
+/// Meta trait for ssdp combinator
+pub trait SsdpParse<Stream, Context> = where
+  (): HttpParse<Stream, Context>,
+  Context: Contexting<SsdpAtom>;
+
+/// Atom produced by ssdp parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SsdpAtom {
+  /// When the method of a request is not NOTIFY or M-SEARCH
+  Method,
+}
+
+impl Display for SsdpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      SsdpAtom::Method => write!(f, "Method: expected NOTIFY or M-SEARCH"),
+    }
+  }
+}
+
+/// Kind of SSDP message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SsdpKind {
+  /// NOTIFY, advertisement of a device or service
+  Notify,
+  /// M-SEARCH, search request
+  Search,
+  /// Response to a search, with its status code
+  Response(u16),
+}
+
+/// SSDP message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SsdpMessage<Span> {
+  /// Kind
+  pub kind: SsdpKind,
+  /// Header fields in order
+  pub headers: Vec<HttpHeader<Span>>,
+}
+
+impl<Span: AsRef<[u8]>> SsdpMessage<Span> {
+  /// Return the value of the first header field named name, ignoring case
+  pub fn header(&self, name: &str) -> Option<&Span> {
+    find_header(&self.headers, name)
+  }
+
+  /// Return the search target of a search or a response
+  pub fn st(&self) -> Option<&Span> {
+    self.header("ST")
+  }
+
+  /// Return the notification type of an advertisement
+  pub fn nt(&self) -> Option<&Span> {
+    self.header("NT")
+  }
+
+  /// Return the notification sub type of an advertisement, `ssdp:alive`,
+  /// `ssdp:byebye` or `ssdp:update`
+  pub fn nts(&self) -> Option<&Span> {
+    self.header("NTS")
+  }
+
+  /// Return the unique service name
+  pub fn usn(&self) -> Option<&Span> {
+    self.header("USN")
+  }
+
+  /// Return the URL of the description of the device
+  pub fn location(&self) -> Option<&Span> {
+    self.header("LOCATION")
+  }
+
+  /// Return the maximum wait in seconds of a search
+  pub fn mx(&self) -> Option<u8> {
+    parse_number(self.header("MX")?.as_ref())
+  }
+
+  /// Return the max-age directive of CACHE-CONTROL, in seconds
+  pub fn max_age(&self) -> Option<u32> {
+    self
+      .header("CACHE-CONTROL")?
+      .as_ref()
+      .split(|octet| *octet == b',')
+      .find_map(|directive| {
+        let (name, value) = directive.split_at(directive.iter().position(|octet| *octet == b'=')?);
+        if name.trim_ascii().eq_ignore_ascii_case(b"max-age") {
+          parse_number(&value[1..])
+        } else {
+          None
+        }
+      })
+  }
+}
+
+fn parse_number<Number: std::str::FromStr>(value: &[u8]) -> Option<Number> {
+  std::str::from_utf8(value.trim_ascii()).ok()?.parse().ok()
+}
+
+/// Parse a NOTIFY or M-SEARCH request or a response to a search
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn ssdp_message<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SsdpMessage<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): SsdpParse<Stream, Context>,
+{
+  http_response
+    .map(|response| SsdpMessage {
+      kind: SsdpKind::Response(response.status),
+      headers: response.headers,
+    })
+    .or(http_request.try_map(|request| {
+      let kind = match request.method.as_ref() {
+        b"NOTIFY" => SsdpKind::Notify,
+        b"M-SEARCH" => SsdpKind::Search,
+        _ => return Err(Context::new(SsdpAtom::Method)),
+      };
+      Ok(SsdpMessage {
+        kind,
+        headers: request.headers,
+      })
+    }))
+    .parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::SsdpKind;
+
+  #[test]
+  fn ssdp() {
+    let search = b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: ssdp:all\r\n\r\n";
+    let Success { token, stream } = super::ssdp_message::<_, Ignore>(&search[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.kind, SsdpKind::Search);
+    assert_eq!(token.mx(), Some(2));
+    assert_eq!(token.st(), Some(&&b"ssdp:all"[..]));
+
+    let notify = b"NOTIFY * HTTP/1.1\r\nCache-Control: no-cache, max-age=1800\r\nLocation: http://192.168.1.1:80/desc.xml\r\nNT: upnp:rootdevice\r\nNTS: ssdp:alive\r\nUSN: uuid:1234::upnp:rootdevice\r\n\r\n";
+    let Success { token, .. } = super::ssdp_message::<_, Ignore>(&notify[..]).unwrap();
+    assert_eq!(token.kind, SsdpKind::Notify);
+    assert_eq!(token.max_age(), Some(1800));
+    assert_eq!(token.nts(), Some(&&b"ssdp:alive"[..]));
+    assert_eq!(
+      token.location(),
+      Some(&&b"http://192.168.1.1:80/desc.xml"[..])
+    );
+
+    let response =
+      b"HTTP/1.1 200 OK\r\nST: upnp:rootdevice\r\nUSN: uuid:1234::upnp:rootdevice\r\n\r\n";
+    let Success { token, .. } = super::ssdp_message::<_, Ignore>(&response[..]).unwrap();
+    assert_eq!(token.kind, SsdpKind::Response(200));
+    assert_eq!(token.usn(), Some(&&b"uuid:1234::upnp:rootdevice"[..]));
+
+    let get = b"GET / HTTP/1.1\r\n\r\n";
+    assert!(matches!(
+      super::ssdp_message::<_, Ignore>(&get[..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
@@ -0,0 +1,323 @@
+//! Count packets and bytes per EtherType, IP protocol, port and flow
+
+use std::{
+  collections::HashMap,
+  hash::Hash,
+  net::IpAddr,
+};
+
+use crate::{
+  batch::Row,
+  DissectionSink,
+  EtherType,
+  IPProtocol,
+  LayerEvent,
+  Port,
+};
+
+/// Number of packets and bytes
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Counter {
+  /// Number of packets
+  pub packets: u64,
+  /// Number of bytes
+  pub bytes: u64,
+}
+
+impl Counter {
+  fn add(&mut self, bytes: u64) {
+    self.packets += 1;
+    self.bytes += bytes;
+  }
+}
+
+/// Directional flow key, ports are None for protocols without ports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatsFlow {
+  /// IP source address
+  pub source_addr: IpAddr,
+  /// IP destination address
+  pub dest_addr: IpAddr,
+  /// IPv4 protocol or IPv6 next header
+  pub protocol: IPProtocol,
+  /// TCP or UDP source port
+  pub source_port: Option<Port>,
+  /// TCP or UDP destination port
+  pub dest_port: Option<Port>,
+}
+
+/// Counters of a [Stats] at a point in time, sorted by key
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatsSnapshot {
+  /// Every packet
+  pub total: Counter,
+  /// Packets per EtherType
+  pub ether_types: Vec<(EtherType, Counter)>,
+  /// Packets per IPv4 protocol or IPv6 next header
+  pub ip_protocols: Vec<(IPProtocol, Counter)>,
+  /// Packets per TCP or UDP port, a packet count for its source and its
+  /// destination port
+  pub ports: Vec<(Port, Counter)>,
+  /// Packets per flow
+  pub flows: Vec<(StatsFlow, Counter)>,
+}
+
+/// Aggregate counters from raw ethernet frames with [Stats::record_frame]
+/// or from parsed layers as a [DissectionSink]
+#[derive(Clone, Debug, Default)]
+pub struct Stats {
+  total: Counter,
+  ether_types: HashMap<EtherType, Counter>,
+  ip_protocols: HashMap<IPProtocol, Counter>,
+  ports: HashMap<Port, Counter>,
+  flows: HashMap<StatsFlow, Counter>,
+  pending: Option<Packet>,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Packet {
+  ether_type: Option<EtherType>,
+  addrs: Option<(IpAddr, IpAddr, IPProtocol)>,
+  ports: Option<(Port, Port)>,
+  bytes: u64,
+}
+
+fn sorted<Key: Ord + Copy>(map: &HashMap<Key, Counter>) -> Vec<(Key, Counter)> {
+  let mut entries: Vec<_> = map.iter().map(|(key, counter)| (*key, *counter)).collect();
+  entries.sort_unstable_by_key(|(key, _)| *key);
+  entries
+}
+
+impl Stats {
+  /// Return empty Stats
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Parse frame and count it
+  pub fn record_frame(&mut self, frame: &[u8]) {
+    self.flush();
+    let row = Row::new(frame);
+    self.account(Packet {
+      ether_type: row.ether_type,
+      addrs: row
+        .source_addr
+        .zip(row.dest_addr)
+        .zip(row.protocol)
+        .map(|((source, dest), protocol)| (source, dest, protocol)),
+      ports: row.source_port.zip(row.dest_port),
+      bytes: frame.len() as u64,
+    });
+  }
+
+  /// Count the packet being assembled from layer events, this is done
+  /// automatically when a new packet start or a snapshot is taken
+  pub fn flush(&mut self) {
+    if let Some(packet) = self.pending.take() {
+      self.account(packet);
+    }
+  }
+
+  fn account(&mut self, packet: Packet) {
+    fn add<Key: Eq + Hash>(map: &mut HashMap<Key, Counter>, key: Key, bytes: u64) {
+      map.entry(key).or_default().add(bytes);
+    }
+
+    self.total.add(packet.bytes);
+    if let Some(ether_type) = packet.ether_type {
+      add(&mut self.ether_types, ether_type, packet.bytes);
+    }
+    if let Some((source, dest)) = packet.ports {
+      add(&mut self.ports, source, packet.bytes);
+      if dest != source {
+        add(&mut self.ports, dest, packet.bytes);
+      }
+    }
+    if let Some((source_addr, dest_addr, protocol)) = packet.addrs {
+      add(&mut self.ip_protocols, protocol, packet.bytes);
+      let flow = StatsFlow {
+        source_addr,
+        dest_addr,
+        protocol,
+        source_port: packet.ports.map(|(source, _)| source),
+        dest_port: packet.ports.map(|(_, dest)| dest),
+      };
+      add(&mut self.flows, flow, packet.bytes);
+    }
+  }
+
+  /// Return the current counters
+  pub fn snapshot(&mut self) -> StatsSnapshot {
+    self.flush();
+    StatsSnapshot {
+      total: self.total,
+      ether_types: sorted(&self.ether_types),
+      ip_protocols: sorted(&self.ip_protocols),
+      ports: sorted(&self.ports),
+      flows: sorted(&self.flows),
+    }
+  }
+
+  /// Return the current counters and reset them, to export periodic
+  /// snapshots
+  pub fn take_snapshot(&mut self) -> StatsSnapshot {
+    let snapshot = self.snapshot();
+    *self = Self::default();
+    snapshot
+  }
+}
+
+/// Layers are assembled into packets, an Ethernet layer or an IP layer
+/// following an IP layer start a new packet. Bytes are the ethernet header
+/// length plus the IP total length.
+impl DissectionSink for Stats {
+  fn on_layer(&mut self, event: LayerEvent) {
+    match event {
+      LayerEvent::Ethernet(ethernet) => {
+        self.flush();
+        self.pending = Some(Packet {
+          ether_type: ethernet.payload_ether_type(),
+          bytes: ethernet.len() as u64,
+          ..Packet::default()
+        });
+      }
+      LayerEvent::Ipv4(ipv4) => {
+        self.ip_layer(
+          ipv4.source_addr.into(),
+          ipv4.dest_addr.into(),
+          ipv4.protocol,
+          u64::from(ipv4.length),
+        );
+      }
+      LayerEvent::Ipv6(ipv6) => {
+        self.ip_layer(
+          ipv6.source_addr.into(),
+          ipv6.dest_addr.into(),
+          ipv6.next_header,
+          u64::from(ipv6.length) + 40,
+        );
+      }
+      LayerEvent::Tcp(tcp) => self.transport_layer(tcp.source_port, tcp.dest_port),
+      LayerEvent::Udp(udp) => self.transport_layer(udp.source_port, udp.dest_port),
+      _ => {}
+    }
+  }
+}
+
+impl Stats {
+  fn ip_layer(&mut self, source: IpAddr, dest: IpAddr, protocol: IPProtocol, bytes: u64) {
+    if self
+      .pending
+      .as_ref()
+      .is_none_or(|pending| pending.addrs.is_some())
+    {
+      self.flush();
+      self.pending = Some(Packet::default());
+    }
+    if let Some(pending) = &mut self.pending {
+      pending.addrs = Some((source, dest, protocol));
+      pending.bytes += bytes;
+    }
+  }
+
+  fn transport_layer(&mut self, source: Port, dest: Port) {
+    if let Some(pending) = &mut self.pending {
+      pending.ports.get_or_insert((source, dest));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parse,
+    Success,
+  };
+
+  use super::{
+    Counter,
+    Stats,
+  };
+  use crate::{
+    ipv4_header,
+    tcp_header,
+    with_sink,
+    EtherType,
+    IPProtocol,
+    Port,
+  };
+
+  #[test]
+  fn stats() {
+    let tcp = [
+      0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+      0x00, 0x00, 0x38, 0x76, 0xF4, 0x40, 0x00, 0x40, 0x06, 0x80, 0xD9, 0xC0, 0xA8, 0x00, 0x6C,
+      0xD0, 0x61, 0xB1, 0x7C, 0xB0, 0xC2, 0x00, 0x50, 0xB0, 0xEE, 0x32, 0xA6, 0x04, 0x39, 0xAE,
+      0xE6, 0x50, 0x18, 0x00, 0xE5, 0x76, 0x92, 0x00, 0x00, 0x47, 0x45, 0x54, 0x20, 0x2F, 0x69,
+      0x6E, 0x64, 0x65, 0x78, 0x2E, 0x68, 0x74, 0x6D, 0x6C, 0x0A,
+    ];
+    let arp = [
+      0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x06,
+    ];
+
+    let mut stats = Stats::new();
+    stats.record_frame(&tcp);
+    stats.record_frame(&arp);
+
+    // the same TCP packet without its ethernet header, through layer events
+    let Success { stream, .. } = with_sink(ipv4_header::<_, Ignore>, &mut stats)
+      .parse(&tcp[14..])
+      .unwrap();
+    with_sink(tcp_header::<_, Ignore>, &mut stats)
+      .parse(stream)
+      .unwrap();
+
+    let snapshot = stats.take_snapshot();
+    let tcp_bytes = tcp.len() as u64 + 56;
+    assert_eq!(
+      snapshot.total,
+      Counter {
+        packets: 3,
+        bytes: tcp_bytes + arp.len() as u64,
+      }
+    );
+    assert_eq!(
+      snapshot.ether_types,
+      [
+        (
+          EtherType::IPV4,
+          Counter {
+            packets: 1,
+            bytes: tcp.len() as u64,
+          }
+        ),
+        (
+          EtherType::ARP,
+          Counter {
+            packets: 1,
+            bytes: arp.len() as u64,
+          }
+        ),
+      ]
+    );
+    assert_eq!(
+      snapshot.ip_protocols,
+      [(
+        IPProtocol::TCP,
+        Counter {
+          packets: 2,
+          bytes: tcp_bytes,
+        }
+      )]
+    );
+    assert_eq!(snapshot.ports.len(), 2);
+    assert_eq!(snapshot.ports[0].0, Port(80));
+    assert_eq!(snapshot.flows.len(), 1);
+    assert_eq!(snapshot.flows[0].1.packets, 2);
+    assert_eq!(stats.snapshot().total, Counter::default());
+  }
+}
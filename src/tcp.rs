@@ -1,23 +1,30 @@
 //! Handles parsing of TCP headers
 
-use std::fmt::{
-  Debug,
-  Display,
-  Formatter,
+use std::{
+  fmt::{
+    Debug,
+    Display,
+    Formatter,
+  },
+  str::FromStr,
 };
 
 use binator::{
   base::{
+    all,
     any,
     is,
     octet,
     primitive::{
       u16_be,
       u32_be,
+      u64_be,
     },
+    take,
     BaseAtom,
     IntRadixAtom,
   },
+  context::Ignore,
   utils::{
     Acc,
     Utils,
@@ -27,10 +34,17 @@ use binator::{
   CoreAtom,
   Parse,
   Parsed,
+  Split,
   Streaming,
   Success,
 };
 
+#[cfg(feature = "ipv4")]
+use crate::IPv4Header;
+#[cfg(feature = "ipv6")]
+use crate::IPv6Header;
+use crate::Port;
+
 /// Meta trait for tcp combinator
 pub trait TcpParse<Stream, Context> = where
   Stream: Streaming + Clone + Eq,
@@ -72,29 +86,12 @@ macro_rules! tcp_flags {
         }
       })*
 
-      /// Return data offset
-      pub const fn get_data_offset(&self) -> u8 {
-        (self.raw >> 12) as u8
-      }
-
-      /// Set data offset value, error if 4 < N < 16 is not true
-      pub fn set_data_offset(&mut self, n: usize) -> Result<usize, usize> {
-        if n > 4 && n < 16 {
-          self.raw &= u16::MAX >> 4; // reset to 0
-          self.raw |= (n as u16) << 12; // set to n
-          Ok(n)
-        }
-        else {
-          Err(n)
-        }
-      }
     }
 
     paste::paste! {
       impl Debug for TcpFlags {
         fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
           f.debug_struct("TcpFlags")
-            .field("data_offset", &self.get_data_offset())
             $(.field(stringify!($name), &self.[<get_ $name>]()))*
             .finish()
         }
@@ -118,9 +115,153 @@ tcp_flags! {
   fin => 0u16,
 }
 
+// name, tcpdump letter and position of the control flags in tcpdump order
+const TCP_FLAGS: [(&str, char, u16); 9] = [
+  ("FIN", 'F', 0),
+  ("SYN", 'S', 1),
+  ("RST", 'R', 2),
+  ("PSH", 'P', 3),
+  ("ACK", '.', 4),
+  ("URG", 'U', 5),
+  ("ECE", 'E', 6),
+  ("CWR", 'W', 7),
+  ("NS", 'N', 8),
+];
+
+impl TcpFlags {
+  /// Mask of the control bits, including the reserved ones, in the 16 bits
+  /// word following the acknowledgment number
+  pub const MASK: u16 = 0x0FFF;
+
+  const fn with(bits: u16) -> Self {
+    Self { raw: bits }
+  }
+
+  /// Split the 16 bits word following the acknowledgment number into the
+  /// data offset and the control bits
+  pub const fn from_wire(word: u16) -> (u8, Self) {
+    ((word >> 12) as u8, Self::with(word & Self::MASK))
+  }
+
+  /// Return the 16 bits word following the acknowledgment number, only the 4
+  /// low bits of data_offset are used
+  pub const fn to_wire(&self, data_offset: u8) -> u16 {
+    (data_offset as u16 & 0x0F) << 12 | self.raw
+  }
+
+  /// Return flags with only SYN set
+  pub const fn syn() -> Self {
+    Self::with(0x0002)
+  }
+
+  /// Return flags with SYN and ACK set
+  pub const fn syn_ack() -> Self {
+    Self::with(0x0012)
+  }
+
+  /// Return flags with only ACK set
+  pub const fn ack() -> Self {
+    Self::with(0x0010)
+  }
+
+  /// Return flags with PSH and ACK set
+  pub const fn psh_ack() -> Self {
+    Self::with(0x0018)
+  }
+
+  /// Return flags with FIN and ACK set
+  pub const fn fin_ack() -> Self {
+    Self::with(0x0011)
+  }
+
+  /// Return flags with only RST set
+  pub const fn rst() -> Self {
+    Self::with(0x0004)
+  }
+
+  /// Return an iterator over the names of the control flags set, like `SYN`
+  pub fn flags_set(&self) -> impl Iterator<Item = &'static str> + '_ {
+    TCP_FLAGS
+      .iter()
+      .filter(|(_, _, pos)| self.raw & 1 << pos != 0)
+      .map(|(name, ..)| *name)
+  }
+}
+
+/// Names joined by `|` like `SYN|ACK`, the alternate form `{:#}` is the
+/// tcpdump notation like `S.`, `none` when no control flag is set
+impl Display for TcpFlags {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    if self.flags_set().next().is_none() {
+      return write!(f, "none");
+    }
+    for (i, (name, letter, _)) in TCP_FLAGS
+      .iter()
+      .filter(|(_, _, pos)| self.raw & 1 << pos != 0)
+      .enumerate()
+    {
+      if f.alternate() {
+        write!(f, "{}", letter)?;
+      } else if i == 0 {
+        write!(f, "{}", name)?;
+      } else {
+        write!(f, "|{}", name)?;
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Parse names joined by `|` like `SYN|ACK`, ignoring case, or letters like
+/// tcpdump `S.` or `SA`, `A` being ACK.
+impl FromStr for TcpFlags {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.trim();
+    let mut flags = Self::default();
+    if s.is_empty() || s.eq_ignore_ascii_case("none") {
+      return Ok(flags);
+    }
+
+    let by_name = |name: &str| {
+      TCP_FLAGS
+        .iter()
+        .find(|(flag, ..)| flag.eq_ignore_ascii_case(name.trim()))
+        .map(|(_, _, pos)| *pos)
+    };
+    if s.contains('|') || by_name(s).is_some() {
+      for name in s.split('|') {
+        flags.raw |= 1 << by_name(name).ok_or(())?;
+      }
+    } else {
+      for letter in s.chars() {
+        let letter = match letter.to_ascii_uppercase() {
+          'A' => '.',
+          letter => letter,
+        };
+        let (_, _, pos) = TCP_FLAGS
+          .iter()
+          .find(|(_, flag, _)| *flag == letter)
+          .ok_or(())?;
+        flags.raw |= 1 << pos;
+      }
+    }
+    Ok(flags)
+  }
+}
+
+/// Bits outside of [TcpFlags::MASK] are ignored, use [TcpFlags::from_wire]
+/// to keep the data offset
 impl From<u16> for TcpFlags {
   fn from(raw: u16) -> Self {
-    Self { raw }
+    Self::with(raw & Self::MASK)
+  }
+}
+
+impl From<&TcpFlags> for u16 {
+  fn from(flags: &TcpFlags) -> Self {
+    flags.raw
   }
 }
 
@@ -129,9 +270,9 @@ impl From<u16> for TcpFlags {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct TcpHeader<Span> {
   /// Identifies the sending port.
-  pub source_port: u16,
+  pub source_port: Port,
   /// Identifies the receiving port.
-  pub dest_port: u16,
+  pub dest_port: Port,
   /// Has a dual role:
   /// * If the SYN flag is set (1), then this is the initial sequence number.
   ///   The sequence number of the actual first data byte and the acknowledged
@@ -144,7 +285,9 @@ pub struct TcpHeader<Span> {
   /// of all prior bytes (if any). The first ACK sent by each end acknowledges
   /// the other end's initial sequence number itself, but no data.
   pub ack_no: u32,
-  /// Contains 8 1-bit flags (control bits)
+  /// Specifies the size of the TCP header in 32-bit words, from 5 to 15
+  pub data_offset: u8,
+  /// Contains 9 1-bit flags (control bits) and the reserved bits
   pub flags: TcpFlags,
   /// The size of the receive window, which specifies the number of window size
   /// units that the sender of this segment is currently willing to
@@ -158,12 +301,111 @@ pub struct TcpHeader<Span> {
   /// If the URG flag is set, then this 16-bit field is an offset from the
   /// sequence number indicating the last urgent data byte.
   pub urgent_pointer: u16,
-  /// Options use tcp_options with the Span to parse Options to a Vec
-  // TODO could be custom type that impl iterator
+  /// Options use tcp_options with the Span to parse Options to a Vec, or
+  /// parse the header with tcp_header_full
   pub options: Span,
 }
 
-impl<Span> TcpHeader<Span> {}
+impl<Span> TcpHeader<Span> {
+  /// Length of a TCP header with the maximum of options
+  pub const MAX_LEN: usize = 60;
+  /// Length of a TCP header without options
+  pub const MIN_LEN: usize = 20;
+
+  /// Return the length of the header including options, in bytes
+  pub const fn header_len(&self) -> usize {
+    self.data_offset as usize * 4
+  }
+
+  /// Return the length of the payload following the header, computed from
+  /// the total length of ip, None if ip is too short to hold the headers
+  #[cfg(feature = "ipv4")]
+  pub const fn payload_len<Options>(&self, ip: &IPv4Header<Options>) -> Option<usize> {
+    (ip.length as usize).checked_sub(ip.header_len() + self.header_len())
+  }
+
+  /// Return the length of the payload following the header, computed from
+  /// the payload length of ip, assuming no extension header precede TCP. None
+  /// if ip is too short to hold the header
+  #[cfg(feature = "ipv6")]
+  pub const fn payload_len_v6(&self, ip: &IPv6Header) -> Option<usize> {
+    (ip.length as usize).checked_sub(self.header_len())
+  }
+
+  /// Return true if SYN is set
+  pub const fn is_syn(&self) -> bool {
+    self.flags.get_syn()
+  }
+
+  /// Return true if ACK is set
+  pub const fn is_ack(&self) -> bool {
+    self.flags.get_ack()
+  }
+
+  /// Return true if FIN is set
+  pub const fn is_fin(&self) -> bool {
+    self.flags.get_fin()
+  }
+
+  /// Return true if RST is set
+  pub const fn is_rst(&self) -> bool {
+    self.flags.get_rst()
+  }
+
+  /// Return true if PSH is set
+  pub const fn is_psh(&self) -> bool {
+    self.flags.get_psh()
+  }
+
+  /// Return true if URG is set
+  pub const fn is_urg(&self) -> bool {
+    self.flags.get_urg()
+  }
+
+  /// Return the header with options replaced by f(options)
+  pub fn map_options<Options, F>(self, f: F) -> TcpHeader<Options>
+  where
+    F: FnOnce(Span) -> Options,
+  {
+    TcpHeader {
+      source_port: self.source_port,
+      dest_port: self.dest_port,
+      sequence_no: self.sequence_no,
+      ack_no: self.ack_no,
+      data_offset: self.data_offset,
+      flags: self.flags,
+      window: self.window,
+      checksum: self.checksum,
+      urgent_pointer: self.urgent_pointer,
+      options: f(self.options),
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<Span: AsRef<[u8]>> TcpHeader<Span> {
+  /// Return a copy of the header owning its options, it can outlive the
+  /// parsed stream
+  pub fn to_owned(&self) -> TcpHeader<Vec<u8>> {
+    TcpHeader {
+      source_port: self.source_port,
+      dest_port: self.dest_port,
+      sequence_no: self.sequence_no,
+      ack_no: self.ack_no,
+      data_offset: self.data_offset,
+      flags: self.flags.clone(),
+      window: self.window,
+      checksum: self.checksum,
+      urgent_pointer: self.urgent_pointer,
+      options: self.options.as_ref().to_vec(),
+    }
+  }
+
+  /// Return the header owning its options, it can outlive the parsed stream
+  pub fn into_owned(self) -> TcpHeader<Vec<u8>> {
+    self.map_options(|options| options.as_ref().to_vec())
+  }
+}
 
 /// Atom produced by TCP
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -180,6 +422,20 @@ pub enum TcpAtom {
   SackLen(u8),
   /// When Maximum len option size not 10
   TimestampsLen,
+  /// When an option overflow the options
+  OptionTruncated,
+  /// When an option length is less than 2, kind and length included
+  OptionLen(u8),
+  /// When Fast Open cookie length is invalid
+  FastOpenLen(u8),
+  /// When User Timeout option size not 4
+  UserTimeoutLen,
+  /// When TCP-AO option size is less than 4
+  AuthenticationLen(u8),
+  /// When MPTCP option length is invalid for its subtype
+  MptcpLen(u8),
+  /// When experimental option size is less than 4
+  ExperimentalLen(u8),
 }
 
 impl Display for TcpAtom {
@@ -202,6 +458,35 @@ impl Display for TcpAtom {
       TcpAtom::TimestampsLen => {
         write!(f, "TimestampsLen: Maximun len size is not 10")
       }
+      TcpAtom::OptionTruncated => {
+        write!(f, "OptionTruncated: option overflow the options")
+      }
+      TcpAtom::OptionLen(len) => {
+        write!(f, "OptionLen: option length is less than 2 found {}", len)
+      }
+      TcpAtom::FastOpenLen(len) => {
+        write!(f, "FastOpenLen: fast open length is invalid found {}", len)
+      }
+      TcpAtom::UserTimeoutLen => {
+        write!(f, "UserTimeoutLen: Maximun len size is not 4")
+      }
+      TcpAtom::AuthenticationLen(len) => {
+        write!(
+          f,
+          "AuthenticationLen: TCP-AO length is less than 4 found {}",
+          len
+        )
+      }
+      TcpAtom::MptcpLen(len) => {
+        write!(f, "MptcpLen: MPTCP length is invalid found {}", len)
+      }
+      TcpAtom::ExperimentalLen(len) => {
+        write!(
+          f,
+          "ExperimentalLen: experimental length is less than 4 found {}",
+          len
+        )
+      }
     }
   }
 }
@@ -210,15 +495,15 @@ impl Display for TcpAtom {
   feature = "tracing",
   tracing::instrument(level = "trace", skip_all, ret(Display))
 )]
-fn tcp_flags<Stream, Context>(stream: Stream) -> Parsed<TcpFlags, Stream, Context>
+fn tcp_flags<Stream, Context>(stream: Stream) -> Parsed<(u8, TcpFlags), Stream, Context>
 where
   (): TcpParse<Stream, Context>,
 {
   u16_be
-    .map(TcpFlags::from)
-    .try_map(|flags| {
-      if flags.get_data_offset() >= 5 {
-        Ok(flags)
+    .map(TcpFlags::from_wire)
+    .try_map(|(data_offset, flags)| {
+      if data_offset >= 5 {
+        Ok((data_offset, flags))
       } else {
         Err(Contexting::new(TcpAtom::DataOffSet))
       }
@@ -238,10 +523,27 @@ where
   (): TcpParse<Stream, Context>,
 {
   let Success {
-    token: (source_port, dest_port, sequence_no, ack_no, flags, window, checksum, urgent_pointer),
+    token:
+      (
+        source_port,
+        dest_port,
+        sequence_no,
+        ack_no,
+        (data_offset, flags),
+        window,
+        checksum,
+        urgent_pointer,
+      ),
     stream,
   } = (
-    u16_be, u16_be, u32_be, u32_be, tcp_flags, u16_be, u16_be, u16_be,
+    u16_be.map(Port),
+    u16_be.map(Port),
+    u32_be,
+    u32_be,
+    tcp_flags,
+    u16_be,
+    u16_be,
+    u16_be,
   )
     .parse(stream)?;
 
@@ -250,43 +552,237 @@ where
     stream,
   } = any
     .drop()
-    .fold_bounds(
-      (usize::from(flags.get_data_offset()) - 5) * 4,
-      || (),
-      Acc::acc,
-    )
+    .fold_bounds((usize::from(data_offset) - 5) * 4, || (), Acc::acc)
     .span()
     .map(Success::into_stream)
     .parse(stream)?;
 
-  Parsed::Success {
-    stream,
-    token: TcpHeader {
-      source_port,
-      dest_port,
-      sequence_no,
-      ack_no,
-      flags,
-      window,
-      checksum,
-      urgent_pointer,
-      options,
-    },
+  let token = TcpHeader {
+    source_port,
+    dest_port,
+    sequence_no,
+    ack_no,
+    data_offset,
+    flags,
+    window,
+    checksum,
+    urgent_pointer,
+    options,
+  };
+
+  #[cfg(feature = "tracing")]
+  crate::trace_layer(&token);
+
+  Parsed::Success { token, stream }
+}
+
+/// SACK blocks, pairs of left and right edges of the received blocks, up to
+/// [SackBlocks::MAX]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SackBlocks {
+  blocks: [(u32, u32); SackBlocks::MAX],
+  len: u8,
+}
+
+impl SackBlocks {
+  /// Kind of the SACK option
+  pub const KIND: u8 = 5;
+  /// Maximum number of blocks of a SACK option
+  pub const MAX: usize = 4;
+
+  /// Return SackBlocks, None if blocks is empty or has more than
+  /// [SackBlocks::MAX] blocks
+  pub fn new(blocks: &[(u32, u32)]) -> Option<Self> {
+    if blocks.is_empty() || blocks.len() > Self::MAX {
+      return None;
+    }
+    let mut sack = Self {
+      blocks: [(0, 0); Self::MAX],
+      len: 0,
+    };
+    for block in blocks {
+      sack = sack.push(*block);
+    }
+    Some(sack)
+  }
+
+  fn push(mut self, block: (u32, u32)) -> Self {
+    self.blocks[usize::from(self.len)] = block;
+    self.len += 1;
+    self
+  }
+
+  /// Return the number of blocks
+  pub const fn len(&self) -> usize {
+    self.len as usize
+  }
+
+  /// Return true if there is no block, never the case of a parsed option
+  pub const fn is_empty(&self) -> bool {
+    self.len == 0
+  }
+
+  /// Return the blocks as (left edge, right edge)
+  pub fn as_slice(&self) -> &[(u32, u32)] {
+    &self.blocks[..self.len()]
+  }
+
+  /// Return an iterator over the blocks as (left edge, right edge)
+  pub fn iter(&self) -> std::iter::Copied<std::slice::Iter<'_, (u32, u32)>> {
+    self.as_slice().iter().copied()
+  }
+
+  /// Return the length field of the option, kind and length included
+  pub const fn option_len(&self) -> u8 {
+    2 + 8 * self.len
+  }
+
+  /// Append the wire representation of the option to buf, kind and length
+  /// included
+  pub fn write_option(&self, buf: &mut Vec<u8>) {
+    buf.extend_from_slice(&[Self::KIND, self.option_len()]);
+    for (left, right) in self.iter() {
+      buf.extend_from_slice(&left.to_be_bytes());
+      buf.extend_from_slice(&right.to_be_bytes());
+    }
   }
 }
 
-/// Sack
+impl<'a> IntoIterator for &'a SackBlocks {
+  type IntoIter = std::iter::Copied<std::slice::Iter<'a, (u32, u32)>>;
+  type Item = (u32, u32);
+
+  fn into_iter(self) -> Self::IntoIter {
+    self.iter()
+  }
+}
+
+/// User Timeout option
+/// <https://www.rfc-editor.org/rfc/rfc5482>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UserTimeout {
+  /// True if timeout is in minutes, in seconds otherwise
+  pub minutes: bool,
+  /// 15 bits timeout
+  pub timeout: u16,
+}
+
+/// TCP Authentication Option
+/// <https://www.rfc-editor.org/rfc/rfc5925>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TcpAuthentication<Span> {
+  /// Key used to compute mac
+  pub key_id: u8,
+  /// Key the sender is ready to receive
+  pub rnext_key_id: u8,
+  /// Message Authentication Code
+  pub mac: Span,
+}
+
+/// Experimental option, kind 253 or 254, identified by its ExID
+/// <https://www.rfc-editor.org/rfc/rfc6994>
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TcpExperimental<Span> {
+  /// 253 or 254
+  pub kind: u8,
+  /// 16 bits Experiment Identifier
+  pub exid: u16,
+  /// Data following the ExID
+  pub data: Span,
+}
+
+/// MP_CAPABLE MPTCP option
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MptcpCapable {
+  /// MPTCP version
+  pub version: u8,
+  /// Flags
+  pub flags: u8,
+  /// Key of the sender, absent on a version 1 SYN
+  pub sender_key: Option<u64>,
+  /// Key of the receiver, only on the third ACK
+  pub receiver_key: Option<u64>,
+  /// Data-level length of the first data
+  pub data_len: Option<u16>,
+  /// Checksum of the first data
+  pub checksum: Option<u16>,
+}
+
+/// MP_JOIN MPTCP option
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MptcpJoin<Span> {
+  /// The subflow is a backup path
+  pub backup: bool,
+  /// Address identifier
+  pub address_id: u8,
+  /// Token and random number on SYN, truncated HMAC and random number on
+  /// SYN/ACK, HMAC on ACK
+  pub data: Span,
+}
+
+/// Mapping of a DSS MPTCP option
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DssMapping {
+  /// Data Sequence Number
+  pub dsn: u64,
+  /// Subflow sequence number, relative to the initial sequence number
+  pub subflow_seq: u32,
+  /// Data-level length
+  pub data_len: u16,
+  /// Checksum, absent if checksums are not negotiated
+  pub checksum: Option<u16>,
+}
+
+/// DSS MPTCP option
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MptcpDss {
+  /// Flags
+  pub flags: u8,
+  /// Data ACK
+  pub data_ack: Option<u64>,
+  /// Mapping
+  pub mapping: Option<DssMapping>,
+}
+
+impl MptcpDss {
+  /// Data ACK is present
+  pub const A_FLAG: u8 = 0x01;
+  /// DATA_FIN
+  pub const F_FLAG: u8 = 0x10;
+  /// Data ACK is 8 octets
+  pub const LOWER_A_FLAG: u8 = 0x02;
+  /// Data Sequence Number is 8 octets
+  pub const LOWER_M_FLAG: u8 = 0x08;
+  /// Mapping is present
+  pub const M_FLAG: u8 = 0x04;
+}
+
+/// MPTCP option
+/// <https://www.rfc-editor.org/rfc/rfc8684>
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Debug, PartialEq, Eq)]
-pub enum Sack {
-  /// Sack with 2 u32
-  A([u32; 2]),
-  /// Sack with 4 u32
-  B([u32; 4]),
-  /// Sack with 6 u32
-  C([u32; 6]),
-  /// Sack with 8 u32
-  D([u32; 8]),
+pub enum MptcpOption<Span> {
+  /// MP_CAPABLE, subtype 0
+  Capable(MptcpCapable),
+  /// MP_JOIN, subtype 1
+  Join(MptcpJoin<Span>),
+  /// DSS, subtype 2
+  Dss(MptcpDss),
+  /// Other subtype
+  Other {
+    /// Subtype
+    subtype: u8,
+    /// Data following the subtype
+    data: Span,
+  },
 }
 
 /// Tcp Option
@@ -305,14 +801,85 @@ pub enum TcpOption<Span> {
   WindowScale(u8),
   /// Selective acknowledgments is permited or not.
   SackPermitted,
-  /// Sack data
-  Sack(Sack),
+  /// Sack blocks
+  Sack(SackBlocks),
   /// Timestamps of paquet
   Timestamps((u32, u32)),
+  /// User Timeout
+  UserTimeout(UserTimeout),
+  /// TCP Authentication Option
+  Authentication(TcpAuthentication<Span>),
+  /// Multipath TCP
+  Mptcp(MptcpOption<Span>),
+  /// TCP Fast Open cookie, None for a cookie request
+  FastOpen(Option<Span>),
+  /// Experimental option
+  Experimental(TcpExperimental<Span>),
   /// Unknown option
   Unknown((u8, Span)),
 }
 
+impl<Span> TcpOption<Span> {
+  /// Return the option with its spans replaced by f(span)
+  pub fn map_span<T, F>(self, mut f: F) -> TcpOption<T>
+  where
+    F: FnMut(Span) -> T,
+  {
+    match self {
+      TcpOption::EndOfOption => TcpOption::EndOfOption,
+      TcpOption::Noop => TcpOption::Noop,
+      TcpOption::MaximumSegmentSize(mss) => TcpOption::MaximumSegmentSize(mss),
+      TcpOption::WindowScale(scale) => TcpOption::WindowScale(scale),
+      TcpOption::SackPermitted => TcpOption::SackPermitted,
+      TcpOption::Sack(sack) => TcpOption::Sack(sack),
+      TcpOption::Timestamps(timestamps) => TcpOption::Timestamps(timestamps),
+      TcpOption::UserTimeout(timeout) => TcpOption::UserTimeout(timeout),
+      TcpOption::Authentication(ao) => TcpOption::Authentication(TcpAuthentication {
+        key_id: ao.key_id,
+        rnext_key_id: ao.rnext_key_id,
+        mac: f(ao.mac),
+      }),
+      TcpOption::Mptcp(mptcp) => TcpOption::Mptcp(match mptcp {
+        MptcpOption::Capable(capable) => MptcpOption::Capable(capable),
+        MptcpOption::Join(join) => MptcpOption::Join(MptcpJoin {
+          backup: join.backup,
+          address_id: join.address_id,
+          data: f(join.data),
+        }),
+        MptcpOption::Dss(dss) => MptcpOption::Dss(dss),
+        MptcpOption::Other { subtype, data } => MptcpOption::Other {
+          subtype,
+          data: f(data),
+        },
+      }),
+      TcpOption::FastOpen(cookie) => TcpOption::FastOpen(cookie.map(f)),
+      TcpOption::Experimental(experimental) => TcpOption::Experimental(TcpExperimental {
+        kind: experimental.kind,
+        exid: experimental.exid,
+        data: f(experimental.data),
+      }),
+      TcpOption::Unknown((kind, data)) => TcpOption::Unknown((kind, f(data))),
+    }
+  }
+}
+
+#[cfg(feature = "alloc")]
+impl<Span: AsRef<[u8]>> TcpOption<Span> {
+  /// Return a copy of the option owning its data, it can outlive the parsed
+  /// stream
+  pub fn to_owned(&self) -> TcpOption<Vec<u8>>
+  where
+    Span: Clone,
+  {
+    self.clone().into_owned()
+  }
+
+  /// Return the option owning its data, it can outlive the parsed stream
+  pub fn into_owned(self) -> TcpOption<Vec<u8>> {
+    self.map_span(|span| span.as_ref().to_vec())
+  }
+}
+
 #[cfg_attr(
   feature = "tracing",
   tracing::instrument(level = "trace", skip_all, ret(Display))
@@ -392,10 +959,16 @@ where
   octet
     .and_then(|len| {
       move |stream: Stream| match len {
-        10 => u32_be.fill().map(Sack::A).parse(stream),
-        18 => u32_be.fill().map(Sack::B).parse(stream),
-        26 => u32_be.fill().map(Sack::C).parse(stream),
-        34 => u32_be.fill().map(Sack::D).parse(stream),
+        10 | 18 | 26 | 34 => (u32_be, u32_be)
+          .fold_bounds(
+            usize::from(len / 8),
+            || SackBlocks {
+              blocks: [(0, 0); SackBlocks::MAX],
+              len: 0,
+            },
+            SackBlocks::push,
+          )
+          .parse(stream),
         len => Parsed::Failure(Context::new(TcpAtom::SackLen(len))),
       }
     })
@@ -420,6 +993,282 @@ where
     .parse(stream)
 }
 
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+fn user_timeout<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TcpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  is(4)
+    .add_atom(|| TcpAtom::UserTimeoutLen)
+    .drop_and(u16_be)
+    .map(|value| {
+      TcpOption::UserTimeout(UserTimeout {
+        minutes: value & 0x8000 != 0,
+        timeout: value & 0x7FFF,
+      })
+    })
+    .parse(stream)
+}
+
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+fn authentication<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TcpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  octet
+    .and_then(|len| {
+      move |stream: Stream| match len.checked_sub(4) {
+        Some(mac_len) => (octet, octet, take(usize::from(mac_len)))
+          .map(|(key_id, rnext_key_id, mac)| TcpAuthentication {
+            key_id,
+            rnext_key_id,
+            mac,
+          })
+          .parse(stream),
+        None => Parsed::Failure(Context::new(TcpAtom::AuthenticationLen(len))),
+      }
+    })
+    .map(TcpOption::Authentication)
+    .parse(stream)
+}
+
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+fn fast_open<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TcpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  // cookie is 4 to 16 octets, a multiple of 2
+  octet
+    .and_then(|len| {
+      move |stream: Stream| match len {
+        2 => Parsed::Success {
+          token: None,
+          stream,
+        },
+        6..=18 if len % 2 == 0 => take(usize::from(len - 2)).map(Some).parse(stream),
+        len => Parsed::Failure(Context::new(TcpAtom::FastOpenLen(len))),
+      }
+    })
+    .map(TcpOption::FastOpen)
+    .parse(stream)
+}
+
+fn experimental<Stream, Context>(
+  kind: u8, stream: Stream,
+) -> Parsed<TcpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  let Success { token: len, stream } = octet.parse(stream)?;
+  let Some(data_len) = len.checked_sub(4) else {
+    return Parsed::Failure(Context::new(TcpAtom::ExperimentalLen(len)));
+  };
+  (u16_be, take(usize::from(data_len)))
+    .map(|(exid, data)| TcpOption::Experimental(TcpExperimental { kind, exid, data }))
+    .parse(stream)
+}
+
+fn mptcp_capable<Stream, Context>(
+  stream: Stream, len: u8, version: u8,
+) -> Parsed<MptcpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  let Success {
+    token: flags,
+    stream,
+  } = octet.parse(stream)?;
+  let Success {
+    token: (sender_key, receiver_key, data_len, checksum),
+    stream,
+  } = match len {
+    4 => Parsed::Success {
+      token: (None, None, None, None),
+      stream,
+    },
+    12 => u64_be
+      .map(|sender| (Some(sender), None, None, None))
+      .parse(stream),
+    20 => (u64_be, u64_be)
+      .map(|(sender, receiver)| (Some(sender), Some(receiver), None, None))
+      .parse(stream),
+    22 => (u64_be, u64_be, u16_be)
+      .map(|(sender, receiver, data_len)| (Some(sender), Some(receiver), Some(data_len), None))
+      .parse(stream),
+    24 => (u64_be, u64_be, u16_be, u16_be)
+      .map(|(sender, receiver, data_len, checksum)| {
+        (Some(sender), Some(receiver), Some(data_len), Some(checksum))
+      })
+      .parse(stream),
+    len => Parsed::Failure(Context::new(TcpAtom::MptcpLen(len))),
+  }?;
+
+  Parsed::Success {
+    token: MptcpOption::Capable(MptcpCapable {
+      version,
+      flags,
+      sender_key,
+      receiver_key,
+      data_len,
+      checksum,
+    }),
+    stream,
+  }
+}
+
+// 4 or 8 octets according to flag
+fn mptcp_number<Stream, Context>(
+  stream: Stream, present: bool, long: bool,
+) -> Parsed<Option<u64>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  match (present, long) {
+    (false, _) => Parsed::Success {
+      token: None,
+      stream,
+    },
+    (true, false) => u32_be.map(|n| Some(u64::from(n))).parse(stream),
+    (true, true) => u64_be.map(Some).parse(stream),
+  }
+}
+
+fn mptcp_dss<Stream, Context>(
+  stream: Stream, len: u8,
+) -> Parsed<MptcpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  let Success {
+    token: flags,
+    stream,
+  } = octet.parse(stream)?;
+  let ack = flags & MptcpDss::A_FLAG != 0;
+  let long_ack = flags & MptcpDss::LOWER_A_FLAG != 0;
+  let mapping = flags & MptcpDss::M_FLAG != 0;
+  let long_dsn = flags & MptcpDss::LOWER_M_FLAG != 0;
+
+  let ack_len = match (ack, long_ack) {
+    (false, _) => 0,
+    (true, false) => 4,
+    (true, true) => 8,
+  };
+  let mapping_len = match (mapping, long_dsn) {
+    (false, _) => 0,
+    (true, false) => 10,
+    (true, true) => 14,
+  };
+  let checksum = match len.checked_sub(4 + ack_len + mapping_len) {
+    Some(0) => false,
+    Some(2) if mapping => true,
+    _ => return Parsed::Failure(Context::new(TcpAtom::MptcpLen(len))),
+  };
+
+  let Success {
+    token: data_ack,
+    stream,
+  } = mptcp_number(stream, ack, long_ack)?;
+  let Success { token: dsn, stream } = mptcp_number(stream, mapping, long_dsn)?;
+  let Success {
+    token: mapping,
+    stream,
+  } = match dsn {
+    Some(dsn) => {
+      let Success {
+        token: (subflow_seq, data_len),
+        stream,
+      } = (u32_be, u16_be).parse(stream)?;
+      let Success {
+        token: checksum,
+        stream,
+      } = if checksum {
+        u16_be.map(Some).parse(stream)?
+      } else {
+        Success {
+          token: None,
+          stream,
+        }
+      };
+      Success {
+        token: Some(DssMapping {
+          dsn,
+          subflow_seq,
+          data_len,
+          checksum,
+        }),
+        stream,
+      }
+    }
+    None => Success {
+      token: None,
+      stream,
+    },
+  };
+
+  Parsed::Success {
+    token: MptcpOption::Dss(MptcpDss {
+      flags,
+      data_ack,
+      mapping,
+    }),
+    stream,
+  }
+}
+
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+fn mptcp<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TcpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  let Success {
+    token: (len, subtype),
+    stream,
+  } = (octet, octet).parse(stream)?;
+  if len < 3 {
+    return Parsed::Failure(Context::new(TcpAtom::MptcpLen(len)));
+  }
+  let (subtype, low) = (subtype >> 4u8, subtype & 0x0F);
+
+  match subtype {
+    0 => mptcp_capable(stream, len, low),
+    1 if len >= 4 => (octet, take(usize::from(len - 4)))
+      .map(|(address_id, data)| {
+        MptcpOption::Join(MptcpJoin {
+          backup: low & 0x01 != 0,
+          address_id,
+          data,
+        })
+      })
+      .parse(stream),
+    1 => Parsed::Failure(Context::new(TcpAtom::MptcpLen(len))),
+    2 => mptcp_dss(stream, len),
+    subtype => take(usize::from(len - 3))
+      .map(|data| MptcpOption::Other { subtype, data })
+      .parse(stream),
+  }
+  .map_token(TcpOption::Mptcp)
+}
+
 struct Unknown {
   op: u8,
 }
@@ -446,7 +1295,13 @@ where
   fn parse(
     &mut self, stream: Stream,
   ) -> Parsed<TcpOption<<Stream as Streaming>::Span>, Stream, Context> {
+    // the length includes the kind and length octets
     octet
+      .try_map(|len| {
+        len
+          .checked_sub(2)
+          .ok_or_else(|| Context::new(TcpAtom::OptionLen(len)))
+      })
       .and_then(|len| {
         any
           .drop()
@@ -481,18 +1336,92 @@ where
         4 => sack_permitted.parse(stream),
         5 => sack.parse(stream),
         8 => tipestamps.parse(stream),
+        28 => user_timeout.parse(stream),
+        29 => authentication.parse(stream),
+        30 => mptcp.parse(stream),
+        34 => fast_open.parse(stream),
+        253 | 254 => experimental(op, stream),
         op => unknown(op).parse(stream),
       }
     })
     .parse(stream)
 }
 
-/// Parse tcp option this can be used on the Stream Span.
+/// Parse tcp option this can be used on the Stream Span. Parsing stop after
+/// [TcpOption::EndOfOption], the padding that follow is left in the stream.
+/// A malformed or truncated option is a failure, use [tcp_options_lenient] to
+/// keep the options before it.
 #[cfg_attr(
   feature = "tracing",
   tracing::instrument(level = "trace", skip_all, ret(Display))
 )]
 pub fn tcp_options<Stream, Context>(
+  mut stream: Stream,
+) -> Parsed<Vec<TcpOption<<Stream as Streaming>::Span>>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  let mut options = Vec::new();
+  loop {
+    match tcp_option.parse(stream.clone()) {
+      Parsed::Success {
+        token: TcpOption::EndOfOption,
+        stream,
+      } => {
+        options.push(TcpOption::EndOfOption);
+        return Parsed::Success {
+          token: options,
+          stream,
+        };
+      }
+      Parsed::Success {
+        token,
+        stream: next,
+      } => {
+        options.push(token);
+        stream = next;
+      }
+      // only the end of the stream end the options without EndOfOption
+      Parsed::Failure(context) => {
+        return match stream.clone().split_first() {
+          Split::NotEnoughItem(_) => Parsed::Success {
+            token: options,
+            stream,
+          },
+          _ => Parsed::Failure(context),
+        };
+      }
+      Parsed::Error(context) => return Parsed::Error(context),
+    }
+  }
+}
+
+/// Tcp options and the padding following them
+pub type TcpOptionsPadding<Span> = (Vec<TcpOption<Span>>, Span);
+
+/// Parse tcp options like [tcp_options] then return the padding following
+/// [TcpOption::EndOfOption] as a span
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn tcp_options_padding<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TcpOptionsPadding<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+{
+  (tcp_options, all).parse(stream)
+}
+
+/// Parse tcp options without stopping at [TcpOption::EndOfOption], padding
+/// is parsed as more [TcpOption::EndOfOption]. This is the lenient behavior
+/// of older versions.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn tcp_options_lenient<Stream, Context>(
   stream: Stream,
 ) -> Parsed<Vec<TcpOption<<Stream as Streaming>::Span>>, Stream, Context>
 where
@@ -501,6 +1430,130 @@ where
   tcp_option.fold_bounds(.., Vec::new, Acc::acc).parse(stream)
 }
 
+/// Iterator over the options of a TCP header, options are decoded lazily
+/// without allocation, it stops after [TcpOption::EndOfOption] or the first
+/// error
+#[derive(Clone, Debug)]
+pub struct TcpOptionsIter<'a> {
+  options: &'a [u8],
+  end: bool,
+}
+
+impl<'a> TcpOptionsIter<'a> {
+  /// Return an iterator over options, the options span of a [TcpHeader]
+  pub const fn new(options: &'a [u8]) -> Self {
+    Self {
+      options,
+      end: false,
+    }
+  }
+
+  /// Return the bytes not decoded yet, the padding once
+  /// [TcpOption::EndOfOption] is yielded
+  pub const fn remaining(&self) -> &'a [u8] {
+    self.options
+  }
+
+  // the option is bounded by its length then decoded by tcp_option, a
+  // failure can only come from a length the kind doesn't accept
+  fn option(&mut self, kind: u8) -> Result<TcpOption<&'a [u8]>, TcpAtom> {
+    let len = *self.options.get(1).ok_or(TcpAtom::OptionTruncated)?;
+    if len < 2 {
+      return Err(TcpAtom::OptionLen(len));
+    }
+    let atom = match kind {
+      2 => TcpAtom::MssLen,
+      3 => TcpAtom::WindowScaleLen,
+      4 => TcpAtom::SackPermittedLen,
+      5 => TcpAtom::SackLen(len),
+      8 => TcpAtom::TimestampsLen,
+      28 => TcpAtom::UserTimeoutLen,
+      29 => TcpAtom::AuthenticationLen(len),
+      30 => TcpAtom::MptcpLen(len),
+      34 => TcpAtom::FastOpenLen(len),
+      253 | 254 => TcpAtom::ExperimentalLen(len),
+      _ => TcpAtom::OptionLen(len),
+    };
+
+    let option = self
+      .options
+      .get(..usize::from(len))
+      .ok_or(TcpAtom::OptionTruncated)?;
+    match tcp_option::<_, Ignore>(option) {
+      Parsed::Success { token, stream: [] } => {
+        self.options = &self.options[option.len()..];
+        Ok(token)
+      }
+      _ => Err(atom),
+    }
+  }
+}
+
+impl<'a> Iterator for TcpOptionsIter<'a> {
+  type Item = Result<TcpOption<&'a [u8]>, TcpAtom>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.end {
+      return None;
+    }
+    let (&kind, rest) = self.options.split_first()?;
+    let option = match kind {
+      0 => {
+        self.options = rest;
+        self.end = true;
+        Ok(TcpOption::EndOfOption)
+      }
+      1 => {
+        self.options = rest;
+        Ok(TcpOption::Noop)
+      }
+      kind => self.option(kind),
+    };
+    // the malformed option is left in remaining
+    if option.is_err() {
+      self.end = true;
+    }
+    Some(option)
+  }
+}
+
+impl<Span: AsRef<[u8]>> TcpHeader<Span> {
+  /// Return an iterator over the options, see [TcpOptionsIter]
+  pub fn options_iter(&self) -> TcpOptionsIter<'_> {
+    TcpOptionsIter::new(self.options.as_ref())
+  }
+}
+
+/// TcpHeader with its options parsed
+pub type TcpHeaderFull<Span> = TcpHeader<Vec<TcpOption<Span>>>;
+
+/// Parse tcp header like tcp_header and its options with tcp_options
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn tcp_header_full<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TcpHeaderFull<<<Stream as Streaming>::Span as Streaming>::Span>, Stream, Context>
+where
+  (): TcpParse<Stream, Context>,
+  (): TcpParse<<Stream as Streaming>::Span, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = tcp_header.parse(stream)?;
+
+  match tcp_options.parse(header.options.clone()) {
+    Parsed::Success { token: options, .. } => Parsed::Success {
+      token: header.map_options(|_| options),
+      stream,
+    },
+    Parsed::Failure(context) => Parsed::Failure(context),
+    Parsed::Error(context) => Parsed::Error(context),
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use core::fmt::Debug;
@@ -515,6 +1568,7 @@ mod tests {
     CoreAtom,
     Parse,
     Parsed,
+    ProvideElement,
     Streaming,
   };
   use derive_more::{
@@ -526,9 +1580,25 @@ mod tests {
 
   use crate::{
     tcp_header,
+    tcp_header_full,
+    tcp_options,
+    tcp_options_lenient,
+    tcp_options_padding,
+    DssMapping,
+    MptcpCapable,
+    MptcpDss,
+    MptcpJoin,
+    MptcpOption,
+    Port,
+    SackBlocks,
     TcpAtom,
+    TcpAuthentication,
+    TcpExperimental,
     TcpFlags,
     TcpHeader,
+    TcpOption,
+    TcpOptionsIter,
+    UserTimeout,
   };
 
   //  use super::*;
@@ -559,12 +1629,12 @@ mod tests {
     let mut flags = TcpFlags::default();
     flags.set_ack(true);
     flags.set_psh(true);
-    flags.set_data_offset(5).unwrap();
     let expect = TcpHeader {
-      source_port: 49695,
-      dest_port: 80,
+      source_port: Port(49695),
+      dest_port: Port(80),
       sequence_no: 0x0FD87F4C,
       ack_no: 0xEB2F05C8,
+      data_offset: 5,
       flags,
       window: 256,
       checksum: 0x7C29,
@@ -572,6 +1642,9 @@ mod tests {
       options: "".as_bytes(),
     };
 
+    assert_eq!(TcpFlags::from_wire(0x5018), (5, expect.flags.clone()));
+    assert_eq!(expect.flags.to_wire(expect.data_offset), 0x5018);
+
     let result: Parsed<_, _, HandleAtom<_>> = tcp_header.parse(stream.as_slice());
     let expected = Parsed::Success {
       token: expect,
@@ -580,4 +1653,241 @@ mod tests {
 
     assert_eq!(result, expected);
   }
+  #[test]
+  fn test_tcp_parse_full() {
+    let stream = [
+      0xC2, 0x1F, 0x00, 0x50, 0x0F, 0xD8, 0x7F, 0x4C, 0xEB, 0x2F, 0x05, 0xC8, 0x60, 0x02, 0x01,
+      0x00, 0x7C, 0x29, 0x00, 0x00, 0x02, 0x04, 0x05, 0xB4, 0xAA,
+    ];
+
+    let result: Parsed<_, _, HandleAtom<_>> = tcp_header_full.parse(stream.as_slice());
+    let Parsed::Success { token, stream } = result else {
+      panic!("expected success");
+    };
+    assert_eq!(token.options, [TcpOption::MaximumSegmentSize(1460)]);
+    assert_eq!(stream, [0xAA]);
+  }
+
+  #[test]
+  fn test_tcp_flags_str() {
+    let syn_ack = TcpFlags::syn_ack();
+    assert_eq!(syn_ack.to_string(), "SYN|ACK");
+    assert_eq!(format!("{:#}", syn_ack), "S.");
+    assert_eq!(syn_ack.flags_set().collect::<Vec<_>>(), ["SYN", "ACK"]);
+    assert_eq!(TcpFlags::default().to_string(), "none");
+
+    assert_eq!("SYN|ACK".parse(), Ok(syn_ack.clone()));
+    assert_eq!("syn | ack".parse(), Ok(syn_ack.clone()));
+    assert_eq!("S.".parse(), Ok(syn_ack.clone()));
+    assert_eq!("SA".parse(), Ok(syn_ack));
+    assert_eq!("FP.".parse(), Ok(TcpFlags::from(0x0019)));
+    assert_eq!("none".parse(), Ok(TcpFlags::default()));
+    assert_eq!("SYN|BOGUS".parse::<TcpFlags>(), Err(()));
+    assert_eq!("SX".parse::<TcpFlags>(), Err(()));
+  }
+
+  #[test]
+  fn test_tcp_options_iter() {
+    let options = [
+      0x02, 0x04, 0x05, 0xB4, 0x01, 0x03, 0x03, 0x07, 0x04, 0x02, 0x08, 0x0A, 0x00, 0x00, 0x00,
+      0x01, 0x00, 0x00, 0x00, 0x02, 0x05, 0x0A, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x04,
+      0x50, 0x04, 0xAA, 0xBB, 0x00, 0x00,
+    ];
+
+    let result: Parsed<_, _, HandleAtom<_>> = tcp_options_padding.parse(&options[..]);
+    let Parsed::Success {
+      token: (token, padding),
+      ..
+    } = result
+    else {
+      panic!("expected success");
+    };
+    assert_eq!(token.last(), Some(&TcpOption::EndOfOption));
+    assert_eq!(padding, [0x00]);
+    let result: Parsed<_, _, HandleAtom<_>> = tcp_options_lenient.parse(&options[..]);
+    let Parsed::Success { token, .. } = result else {
+      panic!("expected success");
+    };
+    assert_eq!(token.len(), 9);
+
+    let mut iter = TcpOptionsIter::new(&options);
+    assert_eq!(
+      iter.by_ref().collect::<Result<Vec<_>, _>>(),
+      Ok(vec![
+        TcpOption::MaximumSegmentSize(1460),
+        TcpOption::Noop,
+        TcpOption::WindowScale(7),
+        TcpOption::SackPermitted,
+        TcpOption::Timestamps((1, 2)),
+        TcpOption::Sack(SackBlocks::new(&[(3, 4)]).unwrap()),
+        TcpOption::Unknown((0x50, &[0xAA, 0xBB][..])),
+        TcpOption::EndOfOption,
+      ])
+    );
+    assert_eq!(iter.remaining(), [0x00]);
+
+    let sack = [
+      0x05, 0x12, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x05, 0x00,
+      0x00, 0x00, 0x08,
+    ];
+    let result: Parsed<_, _, HandleAtom<_>> = tcp_options.parse(&sack[..]);
+    let Parsed::Success { token, .. } = result else {
+      panic!("expected success");
+    };
+    let [TcpOption::Sack(blocks)] = token.as_slice() else {
+      panic!("expected sack");
+    };
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks.iter().collect::<Vec<_>>(), [(1, 2), (5, 8)]);
+    let mut wire = Vec::new();
+    blocks.write_option(&mut wire);
+    assert_eq!(wire, sack);
+
+    let mut iter = TcpOptionsIter::new(&[0x02, 0x04, 0x05]);
+    assert_eq!(iter.next(), Some(Err(TcpAtom::OptionTruncated)));
+    assert_eq!(iter.next(), None);
+  }
+
+  #[test]
+  fn test_tcp_options_modern() {
+    let options = [
+      0x1C, 0x04, 0x80, 0x05, 0x22, 0x02, 0x22, 0x06, 0x01, 0x02, 0x03, 0x04, 0x1D, 0x06, 0x01,
+      0x02, 0xAA, 0xBB, 0x1E, 0x0C, 0x01, 0x81, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07,
+      0x1E, 0x0C, 0x11, 0x03, 0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x0A, 0x1E, 0x14, 0x20,
+      0x05, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x01, 0x05, 0xB4,
+      0x12, 0x34, 0xFE, 0x06, 0xF9, 0x89, 0xDE, 0xAD,
+    ];
+    let expected = vec![
+      TcpOption::UserTimeout(UserTimeout {
+        minutes: true,
+        timeout: 5,
+      }),
+      TcpOption::FastOpen(None),
+      TcpOption::FastOpen(Some(&[0x01, 0x02, 0x03, 0x04][..])),
+      TcpOption::Authentication(TcpAuthentication {
+        key_id: 1,
+        rnext_key_id: 2,
+        mac: &[0xAA, 0xBB][..],
+      }),
+      TcpOption::Mptcp(MptcpOption::Capable(MptcpCapable {
+        version: 1,
+        flags: 0x81,
+        sender_key: Some(7),
+        receiver_key: None,
+        data_len: None,
+        checksum: None,
+      })),
+      TcpOption::Mptcp(MptcpOption::Join(MptcpJoin {
+        backup: true,
+        address_id: 3,
+        data: &[0x00, 0x00, 0x00, 0x09, 0x00, 0x00, 0x00, 0x0A][..],
+      })),
+      TcpOption::Mptcp(MptcpOption::Dss(MptcpDss {
+        flags: MptcpDss::A_FLAG | MptcpDss::M_FLAG,
+        data_ack: Some(256),
+        mapping: Some(DssMapping {
+          dsn: 512,
+          subflow_seq: 1,
+          data_len: 1460,
+          checksum: Some(0x1234),
+        }),
+      })),
+      TcpOption::Experimental(TcpExperimental {
+        kind: 254,
+        exid: 0xF989,
+        data: &[0xDE, 0xAD][..],
+      }),
+    ];
+
+    let result: Parsed<_, _, HandleAtom<_>> = tcp_options.parse(&options[..]);
+    let Parsed::Success { token, stream } = result else {
+      panic!("expected success");
+    };
+    assert_eq!(token, expected);
+    assert_eq!(stream, b"");
+    assert_eq!(
+      TcpOptionsIter::new(&options).collect::<Result<Vec<_>, _>>(),
+      Ok(expected)
+    );
+
+    let mut iter = TcpOptionsIter::new(&[0x1E, 0x05, 0x20, 0x01, 0x00]);
+    assert_eq!(iter.next(), Some(Err(TcpAtom::MptcpLen(5))));
+  }
+
+  #[test]
+  fn test_tcp_options_malformed() {
+    // a length of 0 or 1 can't hold kind and length
+    for malformed in [
+      &[0x50, 0x00, 0x01, 0x01][..],
+      &[0x01, 0x50, 0x01, 0x02, 0x04, 0x05, 0xB4][..],
+    ] {
+      let result: Parsed<_, _, HandleAtom<_>> = tcp_options.parse(malformed);
+      let Parsed::Failure(context) = result else {
+        panic!("expected failure");
+      };
+      assert!(matches!(
+        context.last(),
+        FromAtom::Tcp(TcpAtom::OptionLen(_))
+      ));
+
+      let result: Parsed<_, _, HandleAtom<_>> = tcp_options_lenient.parse(malformed);
+      let Parsed::Success { token, stream } = result else {
+        panic!("expected success");
+      };
+      assert!(!token
+        .iter()
+        .any(|option| matches!(option, TcpOption::Unknown(_))));
+      assert!(!stream.is_empty());
+
+      assert!(TcpOptionsIter::new(malformed).any(|option| option.is_err()));
+    }
+
+    let mut iter = TcpOptionsIter::new(&[0x50, 0x01, 0x02, 0x04, 0x05, 0xB4]);
+    assert_eq!(iter.next(), Some(Err(TcpAtom::OptionLen(1))));
+    assert_eq!(iter.next(), None);
+
+    // the payload is length minus kind and length, next option stays aligned
+    let aligned = [0x50, 0x03, 0xAA, 0x02, 0x04, 0x05, 0xB4];
+    let result: Parsed<_, _, HandleAtom<_>> = tcp_options.parse(&aligned[..]);
+    assert_eq!(
+      result,
+      Parsed::Success {
+        token: vec![
+          TcpOption::Unknown((0x50, &[0xAA][..])),
+          TcpOption::MaximumSegmentSize(1460),
+        ],
+        stream: &[][..],
+      }
+    );
+  }
+
+  #[cfg(feature = "alloc")]
+  #[test]
+  fn test_tcp_owned() {
+    let stream = [
+      0xC2, 0x1F, 0x00, 0x50, 0x0F, 0xD8, 0x7F, 0x4C, 0xEB, 0x2F, 0x05, 0xC8, 0x60, 0x02, 0x01,
+      0x00, 0x7C, 0x29, 0x00, 0x00, 0x1E, 0x04, 0x01, 0x02,
+    ];
+
+    let owned = {
+      let copy = stream.to_vec();
+      let result: Parsed<_, _, HandleAtom<_>> = tcp_header.parse(copy.as_slice());
+      let Parsed::Success { token, .. } = result else {
+        panic!("expected success");
+      };
+      assert_eq!(token.to_owned().options, token.options);
+      token.into_owned()
+    };
+    assert_eq!(owned.options, [0x1E, 0x04, 0x01, 0x02]);
+
+    let option = TcpOption::Unknown((0x1E, &stream[22..]));
+    assert_eq!(
+      option.to_owned(),
+      TcpOption::Unknown((0x1E, vec![0x01, 0x02]))
+    );
+    assert_eq!(
+      option.into_owned(),
+      TcpOption::Unknown((0x1E, vec![0x01, 0x02]))
+    );
+  }
 }
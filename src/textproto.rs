@@ -0,0 +1,338 @@
+//! Handles parsing of the command and reply lines of FTP and SMTP
+//! <https://www.rfc-editor.org/rfc/rfc959>
+//! <https://www.rfc-editor.org/rfc/rfc2428>
+//! <https://www.rfc-editor.org/rfc/rfc5321>
+
+use std::{
+  net::{
+    IpAddr,
+    Ipv4Addr,
+  },
+  str::FromStr,
+};
+
+use binator::{
+  base::{
+    is,
+    octet,
+    to_digit,
+    BaseAtom,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::Port;
+
+/// TCP port of the FTP control connection
+pub const FTP_PORT: Port = Port(21);
+/// TCP port of the FTP data connection in active mode
+pub const FTP_DATA_PORT: Port = Port(20);
+/// TCP port of SMTP relays
+pub const SMTP_PORT: Port = Port(25);
+/// TCP port of SMTP message submission
+pub const SMTP_SUBMISSION_PORT: Port = Port(587);
+
+/// Meta trait for textproto combinator
+pub trait TextParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  <Stream as Streaming>::Span: AsRef<[u8]>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<BaseAtom<u8>>;
+
+/// Command line, a verb and its argument
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextCommand<Span> {
+  /// Verb, like `USER` or `MAIL`
+  pub verb: Span,
+  /// Everything after the space following the verb
+  pub argument: Option<Span>,
+}
+
+/// Reply, a code and one or more lines of text
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TextReply<Span> {
+  /// Three digits code
+  pub code: u16,
+  /// Text of every line, without the code
+  pub lines: Vec<Span>,
+}
+
+impl<Span: AsRef<[u8]>> TextCommand<Span> {
+  /// Return true if the verb is verb, ignoring case
+  pub fn is(&self, verb: &str) -> bool {
+    self.verb.as_ref().eq_ignore_ascii_case(verb.as_bytes())
+  }
+
+  /// Return the address the server must connect to of a FTP `PORT` or
+  /// `EPRT` command
+  pub fn ftp_data_address(&self) -> Option<(IpAddr, Port)> {
+    let argument = self.argument.as_ref()?.as_ref();
+    if self.is("PORT") {
+      ftp_host_port(argument).map(|(addr, port)| (IpAddr::V4(addr), port))
+    } else if self.is("EPRT") {
+      match ftp_extended(argument)? {
+        (Some(addr), port) => Some((addr, port)),
+        (None, _) => None,
+      }
+    } else {
+      None
+    }
+  }
+
+  /// Return the path between angle brackets of a SMTP `MAIL` or `RCPT`
+  /// command, empty for the null reverse path
+  pub fn smtp_path(&self) -> Option<&[u8]> {
+    if !self.is("MAIL") && !self.is("RCPT") {
+      return None;
+    }
+    let argument = self.argument.as_ref()?.as_ref();
+    let start = argument.iter().position(|octet| *octet == b'<')? + 1;
+    let end = start + argument[start..].iter().position(|octet| *octet == b'>')?;
+    Some(&argument[start..end])
+  }
+}
+
+impl<Span: AsRef<[u8]>> TextReply<Span> {
+  /// Return true for 2xx codes
+  pub const fn is_positive(&self) -> bool {
+    self.code / 100 == 2
+  }
+
+  /// Return true for 4xx and 5xx codes
+  pub const fn is_negative(&self) -> bool {
+    self.code / 100 == 4 || self.code / 100 == 5
+  }
+
+  /// Return the address the client must connect to of a FTP `227` or `229`
+  /// reply, `229` doesn't give an address, the one of the server is used
+  pub fn ftp_passive_address(&self) -> Option<(Option<IpAddr>, Port)> {
+    let text = self.lines.first()?.as_ref();
+    match self.code {
+      // the format is not specified, the numbers are searched
+      227 => {
+        let start = text.iter().position(u8::is_ascii_digit)?;
+        let end = text.iter().rposition(u8::is_ascii_digit)? + 1;
+        ftp_host_port(&text[start..end]).map(|(addr, port)| (Some(IpAddr::V4(addr)), port))
+      }
+      229 => {
+        let start = text.iter().position(|octet| *octet == b'(')? + 1;
+        let end = text.iter().rposition(|octet| *octet == b')')?;
+        ftp_extended(text.get(start..end)?)
+      }
+      _ => None,
+    }
+  }
+}
+
+fn parse_number<Number: FromStr>(text: &[u8]) -> Option<Number> {
+  std::str::from_utf8(text.trim_ascii()).ok()?.parse().ok()
+}
+
+// h1,h2,h3,h4,p1,p2
+fn ftp_host_port(text: &[u8]) -> Option<(Ipv4Addr, Port)> {
+  let mut numbers = [0u8; 6];
+  let mut fields = text.split(|octet| *octet == b',');
+  for number in &mut numbers {
+    *number = parse_number(fields.next()?)?;
+  }
+  if fields.next().is_some() {
+    return None;
+  }
+  let [a, b, c, d, p1, p2] = numbers;
+  Some((
+    Ipv4Addr::new(a, b, c, d),
+    Port(u16::from_be_bytes([p1, p2])),
+  ))
+}
+
+// <d><net-prt><d><net-addr><d><tcp-port><d>, the address can be empty
+fn ftp_extended(text: &[u8]) -> Option<(Option<IpAddr>, Port)> {
+  let (&delimiter, text) = text.split_first()?;
+  let mut fields = text.split(|octet| *octet == delimiter);
+  let (_protocol, addr, port) = (fields.next()?, fields.next()?, fields.next()?);
+  let port = Port(parse_number(port)?);
+  if addr.is_empty() {
+    Some((None, port))
+  } else {
+    Some((Some(parse_number(addr)?), port))
+  }
+}
+
+fn is_text(octet: u8) -> bool {
+  octet != b'\r' && octet != b'\n'
+}
+
+// Tolerate a bare LF
+fn text_crlf<Stream, Context>(stream: Stream) -> Parsed<(), Stream, Context>
+where
+  (): TextParse<Stream, Context>,
+{
+  (is(b'\r').opt(), is(b'\n')).drop().parse(stream)
+}
+
+// Span of octets matching f
+fn text_run<Stream, Context>(
+  min: usize, f: fn(u8) -> bool,
+) -> impl Parse<Stream, Context, Token = <Stream as Streaming>::Span>
+where
+  (): TextParse<Stream, Context>,
+{
+  octet
+    .filter(move |octet| f(*octet))
+    .fold_bounds(min.., || (), |(), _| ())
+    .span()
+    .map(Success::into_stream)
+}
+
+// code, true if it's the last line, text
+fn text_reply_line<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(u16, bool, <Stream as Streaming>::Span), Stream, Context>
+where
+  (): TextParse<Stream, Context>,
+{
+  (
+    (to_digit, to_digit, to_digit)
+      .map(|(a, b, c)| u16::from(a) * 100 + u16::from(b) * 10 + u16::from(c)),
+    octet.filter(|octet| *octet == b' ' || *octet == b'-').opt(),
+    text_run(0, is_text),
+    text_crlf,
+  )
+    .map(|(code, separator, text, _)| (code, separator != Some(b'-'), text))
+    .parse(stream)
+}
+
+/// Parse a command line
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn text_command<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TextCommand<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TextParse<Stream, Context>,
+{
+  (
+    text_run(1, |octet| octet.is_ascii_alphabetic()),
+    is(b' ').drop_and(text_run(0, is_text)).opt(),
+    text_crlf,
+  )
+    .map(|(verb, argument, _)| TextCommand { verb, argument })
+    .parse(stream)
+}
+
+/// Parse a reply, a multi-line reply end with the line starting with its code
+/// followed by a space, the lines in between can have no code
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn text_reply<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TextReply<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TextParse<Stream, Context>,
+{
+  let Success {
+    token: (code, mut last, text),
+    mut stream,
+  } = text_reply_line.parse(stream)?;
+
+  let mut lines = vec![text];
+  while !last {
+    let Success {
+      token: (line_code, line_last, text),
+      stream: next,
+    } = text_reply_line
+      .or((text_run(0, is_text), text_crlf).map(|(text, _)| (0, false, text)))
+      .parse(stream)?;
+    last = line_last && line_code == code;
+    lines.push(text);
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: TextReply { code, lines },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::{
+    IpAddr,
+    Ipv4Addr,
+  };
+
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use crate::Port;
+
+  #[test]
+  fn textproto() {
+    let port = b"PORT 192,168,1,2,19,137\r\n";
+    let Success { token, stream } = super::text_command::<_, Ignore>(&port[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert!(token.is("port"));
+    assert_eq!(
+      token.ftp_data_address(),
+      Some((IpAddr::V4(Ipv4Addr::new(192, 168, 1, 2)), Port(5001)))
+    );
+
+    let eprt = b"EPRT |2|2001:db8::1|6275|\r\n";
+    let Success { token, .. } = super::text_command::<_, Ignore>(&eprt[..]).unwrap();
+    assert_eq!(
+      token.ftp_data_address(),
+      Some((IpAddr::V6("2001:db8::1".parse().unwrap()), Port(6275)))
+    );
+
+    let pasv = b"227 Entering Passive Mode (10,0,0,1,4,1).\r\n";
+    let Success { token, .. } = super::text_reply::<_, Ignore>(&pasv[..]).unwrap();
+    assert_eq!(
+      token.ftp_passive_address(),
+      Some((Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))), Port(1025)))
+    );
+
+    let epsv = b"229 Entering Extended Passive Mode (|||6446|)\r\n";
+    let Success { token, .. } = super::text_reply::<_, Ignore>(&epsv[..]).unwrap();
+    assert_eq!(token.ftp_passive_address(), Some((None, Port(6446))));
+
+    let ehlo = b"250-mail.example.com\r\n250-SIZE 1000000\r\n250 8BITMIME\r\nrest";
+    let Success { token, stream } = super::text_reply::<_, Ignore>(&ehlo[..]).unwrap();
+    assert_eq!(stream, b"rest");
+    assert!(token.is_positive());
+    assert_eq!(
+      token.lines,
+      [
+        &b"mail.example.com"[..],
+        &b"SIZE 1000000"[..],
+        &b"8BITMIME"[..]
+      ]
+    );
+
+    let help = b"214-Commands:\r\n USER PASS\r\n214 End\r\n";
+    let Success { token, .. } = super::text_reply::<_, Ignore>(&help[..]).unwrap();
+    assert_eq!(token.lines.len(), 3);
+
+    let mail = b"MAIL FROM:<alice@example.com> SIZE=100\r\n";
+    let Success { token, .. } = super::text_command::<_, Ignore>(&mail[..]).unwrap();
+    assert_eq!(token.smtp_path(), Some(&b"alice@example.com"[..]));
+  }
+}
@@ -0,0 +1,618 @@
+//! Handles parsing of TLS records and of the hello handshake messages
+//! <https://www.rfc-editor.org/rfc/rfc8446>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::u16_be,
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+/// Meta trait for tls combinator
+pub trait TlsParse<Stream, Context> = where
+  Stream: Streaming + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<TlsAtom>;
+
+/// Atom produced by tls parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsAtom {
+  /// When a record is longer than 2^14 + 2048 bytes
+  RecordLength(u16),
+  /// When a length field doesn't match the fields it cover
+  Length(u32),
+}
+
+impl Display for TlsAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TlsAtom::RecordLength(length) => {
+        write!(f, "RecordLength: record length {} is too long", length)
+      }
+      TlsAtom::Length(length) => {
+        write!(f, "Length: length {} doesn't match its content", length)
+      }
+    }
+  }
+}
+
+struct_variants! {
+  TlsContentType, content_type, u8:
+    /// change_cipher_spec
+    CHANGE_CIPHER_SPEC => 20,
+    /// alert
+    ALERT => 21,
+    /// handshake
+    HANDSHAKE => 22,
+    /// application_data
+    APPLICATION_DATA => 23,
+    /// heartbeat
+    HEARTBEAT => 24,
+}
+
+struct_variants! {
+  TlsVersion, version, u16:
+    /// SSL 3.0
+    SSL_3_0 => 0x0300,
+    /// TLS 1.0
+    TLS_1_0 => 0x0301,
+    /// TLS 1.1
+    TLS_1_1 => 0x0302,
+    /// TLS 1.2
+    TLS_1_2 => 0x0303,
+    /// TLS 1.3
+    TLS_1_3 => 0x0304,
+}
+
+struct_variants! {
+  TlsHandshakeType, handshake_type, u8:
+    /// hello_request
+    HELLO_REQUEST => 0,
+    /// client_hello
+    CLIENT_HELLO => 1,
+    /// server_hello
+    SERVER_HELLO => 2,
+    /// new_session_ticket
+    NEW_SESSION_TICKET => 4,
+    /// encrypted_extensions
+    ENCRYPTED_EXTENSIONS => 8,
+    /// certificate
+    CERTIFICATE => 11,
+    /// server_key_exchange
+    SERVER_KEY_EXCHANGE => 12,
+    /// certificate_request
+    CERTIFICATE_REQUEST => 13,
+    /// server_hello_done
+    SERVER_HELLO_DONE => 14,
+    /// certificate_verify
+    CERTIFICATE_VERIFY => 15,
+    /// client_key_exchange
+    CLIENT_KEY_EXCHANGE => 16,
+    /// finished
+    FINISHED => 20,
+}
+
+/// TLS record, the fragment of an handshake record can be given to
+/// [tls_handshake] when it's not encrypted
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TlsRecord<Span> {
+  /// Type of fragment
+  pub content_type: TlsContentType,
+  /// Legacy record version
+  pub version: TlsVersion,
+  /// Length of fragment
+  pub length: u16,
+  /// Fragment
+  pub fragment: Span,
+}
+
+impl<Span> TlsRecord<Span> {
+  /// Maximum length of a record fragment
+  pub const MAX_LEN: u16 = (1 << 14) + 2048;
+}
+
+/// Hello extension, only the ones needed to identify a connection are typed
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TlsExtension<Span> {
+  /// Server Name Indication, empty when sent by a server
+  ServerName(Vec<Span>),
+  /// Application-Layer Protocol Negotiation protocols
+  Alpn(Vec<Span>),
+  /// Versions supported by the client
+  SupportedVersions(Vec<TlsVersion>),
+  /// Version selected by the server
+  SelectedVersion(TlsVersion),
+  /// Any other extension, type and data
+  Other(u16, Span),
+}
+
+/// ClientHello message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ClientHello<Span> {
+  /// Legacy version, TLS 1.3 use [TlsExtension::SupportedVersions]
+  pub version: TlsVersion,
+  /// Random
+  pub random: [u8; 32],
+  /// Legacy session id
+  pub session_id: Span,
+  /// Cipher suites in order of preference
+  pub cipher_suites: Vec<u16>,
+  /// Legacy compression methods
+  pub compression_methods: Span,
+  /// Extensions
+  pub extensions: Vec<TlsExtension<Span>>,
+}
+
+impl<Span> ClientHello<Span> {
+  /// Return the first host name of the Server Name Indication extension
+  pub fn server_name(&self) -> Option<&Span> {
+    self
+      .extensions
+      .iter()
+      .find_map(|extension| match extension {
+        TlsExtension::ServerName(names) => names.first(),
+        _ => None,
+      })
+  }
+
+  /// Return the protocols of the ALPN extension
+  pub fn alpn(&self) -> Option<&[Span]> {
+    self
+      .extensions
+      .iter()
+      .find_map(|extension| match extension {
+        TlsExtension::Alpn(protocols) => Some(protocols.as_slice()),
+        _ => None,
+      })
+  }
+
+  /// Return the versions of the supported versions extension
+  pub fn supported_versions(&self) -> Option<&[TlsVersion]> {
+    self
+      .extensions
+      .iter()
+      .find_map(|extension| match extension {
+        TlsExtension::SupportedVersions(versions) => Some(versions.as_slice()),
+        _ => None,
+      })
+  }
+}
+
+/// ServerHello message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ServerHello<Span> {
+  /// Legacy version, TLS 1.3 use [TlsExtension::SelectedVersion]
+  pub version: TlsVersion,
+  /// Random
+  pub random: [u8; 32],
+  /// Legacy session id echo
+  pub session_id: Span,
+  /// Selected cipher suite
+  pub cipher_suite: u16,
+  /// Legacy compression method
+  pub compression_method: u8,
+  /// Extensions
+  pub extensions: Vec<TlsExtension<Span>>,
+}
+
+impl<Span> ServerHello<Span> {
+  /// Return the negotiated version, from the supported versions extension
+  /// when present
+  pub fn selected_version(&self) -> TlsVersion {
+    self
+      .extensions
+      .iter()
+      .find_map(|extension| match extension {
+        TlsExtension::SelectedVersion(version) => Some(*version),
+        _ => None,
+      })
+      .unwrap_or(self.version)
+  }
+}
+
+/// Handshake message
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TlsHandshake<Span> {
+  /// ClientHello
+  ClientHello(ClientHello<Span>),
+  /// ServerHello
+  ServerHello(ServerHello<Span>),
+  /// Any other message, type and body
+  Other(TlsHandshakeType, Span),
+}
+
+/// Parse a TLS record
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn tls_record<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TlsRecord<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  let Success {
+    token: (content_type, version, length),
+    stream,
+  } = (
+    octet.map(TlsContentType::new),
+    u16_be.map(TlsVersion::new),
+    u16_be.try_map(|length| {
+      if length <= TlsRecord::<()>::MAX_LEN {
+        Ok(length)
+      } else {
+        Err(Context::new(TlsAtom::RecordLength(length)))
+      }
+    }),
+  )
+    .parse(stream)?;
+
+  take(usize::from(length))
+    .map(|fragment| TlsRecord {
+      content_type,
+      version,
+      length,
+      fragment,
+    })
+    .parse(stream)
+}
+
+fn length_failure<Token, Stream, Context>(length: usize) -> Parsed<Token, Stream, Context>
+where
+  Context: Contexting<TlsAtom>,
+{
+  Parsed::Failure(Context::new(TlsAtom::Length(length as u32)))
+}
+
+// Parse items until exactly len bytes are consumed, the parser return the
+// number of bytes it consumed with its token
+fn tls_list<Stream, Context, Parser, Token>(
+  mut stream: Stream, mut len: usize, mut parser: Parser,
+) -> Parsed<Vec<Token>, Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+  Parser: Parse<Stream, Context, Token = (Token, usize)>,
+{
+  let total = len;
+  let mut list = Vec::new();
+  while len > 0 {
+    let Success {
+      token: (token, consumed),
+      stream: next,
+    } = parser.parse(stream)?;
+    let Some(rest) = len.checked_sub(consumed) else {
+      return length_failure(total);
+    };
+    len = rest;
+    list.push(token);
+    stream = next;
+  }
+
+  Parsed::Success {
+    token: list,
+    stream,
+  }
+}
+
+fn tls_opaque8<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(<Stream as Streaming>::Span, usize), Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  let Success { token: len, stream } = octet.parse(stream)?;
+  let len = usize::from(len);
+  take(len).map(|data| (data, 1 + len)).parse(stream)
+}
+
+fn tls_server_name<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(<Stream as Streaming>::Span, usize), Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  let Success {
+    token: (_name_type, len),
+    stream,
+  } = (octet, u16_be).parse(stream)?;
+  let len = usize::from(len);
+  take(len).map(|name| (name, 3 + len)).parse(stream)
+}
+
+fn tls_extension<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(TlsExtension<<Stream as Streaming>::Span>, usize), Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  let Success {
+    token: (extension_type, len),
+    stream,
+  } = (u16_be, u16_be).parse(stream)?;
+  let len = usize::from(len);
+
+  let Success {
+    token: (extension, consumed),
+    stream,
+  } = match extension_type {
+    0 if len == 0 => Parsed::Success {
+      token: (TlsExtension::ServerName(Vec::new()), 0),
+      stream,
+    },
+    0 | 16 => {
+      let Success {
+        token: list_len,
+        stream,
+      } = u16_be.parse(stream)?;
+      let list_len = usize::from(list_len);
+      if extension_type == 0 {
+        tls_list(stream, list_len, tls_server_name)
+          .map_token(|names| (TlsExtension::ServerName(names), 2 + list_len))
+      } else {
+        tls_list(stream, list_len, tls_opaque8)
+          .map_token(|protocols| (TlsExtension::Alpn(protocols), 2 + list_len))
+      }
+    }
+    43 if len == 2 => u16_be
+      .map(|version| (TlsExtension::SelectedVersion(TlsVersion::new(version)), 2))
+      .parse(stream),
+    43 => {
+      let Success {
+        token: list_len,
+        stream,
+      } = octet.parse(stream)?;
+      if list_len % 2 != 0 {
+        return length_failure(len);
+      }
+      u16_be
+        .map(TlsVersion::new)
+        .fold_bounds(usize::from(list_len / 2), Vec::new, Acc::acc)
+        .map(|versions| {
+          (
+            TlsExtension::SupportedVersions(versions),
+            1 + usize::from(list_len),
+          )
+        })
+        .parse(stream)
+    }
+    _ => take(len)
+      .map(|data| (TlsExtension::Other(extension_type, data), len))
+      .parse(stream),
+  }?;
+
+  if consumed != len {
+    return length_failure(len);
+  }
+  Parsed::Success {
+    token: (extension, 4 + len),
+    stream,
+  }
+}
+
+// extensions are optional, they are absent when the hello body end before
+fn tls_extensions<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<Vec<TlsExtension<<Stream as Streaming>::Span>>, Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  if len == 0 {
+    return Parsed::Success {
+      token: Vec::new(),
+      stream,
+    };
+  }
+
+  let Success {
+    token: extensions_len,
+    stream,
+  } = u16_be.parse(stream)?;
+  if 2 + usize::from(extensions_len) != len {
+    return length_failure(len);
+  }
+  tls_list(stream, usize::from(extensions_len), tls_extension)
+}
+
+fn client_hello<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<ClientHello<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  let Success {
+    token: (version, random, (session_id, session_id_len)),
+    stream,
+  } = (u16_be.map(TlsVersion::new), octet.fill(), tls_opaque8).parse(stream)?;
+
+  let Success {
+    token: cipher_suites_len,
+    stream,
+  } = u16_be.parse(stream)?;
+  if cipher_suites_len % 2 != 0 {
+    return length_failure(usize::from(cipher_suites_len));
+  }
+  let Success {
+    token: (cipher_suites, (compression_methods, compression_methods_len)),
+    stream,
+  } = (
+    u16_be.fold_bounds(usize::from(cipher_suites_len / 2), Vec::new, Acc::acc),
+    tls_opaque8,
+  )
+    .parse(stream)?;
+
+  let consumed =
+    2 + 32 + session_id_len + 2 + usize::from(cipher_suites_len) + compression_methods_len;
+  let Some(extensions_len) = len.checked_sub(consumed) else {
+    return length_failure(len);
+  };
+  tls_extensions(stream, extensions_len).map_token(|extensions| ClientHello {
+    version,
+    random,
+    session_id,
+    cipher_suites,
+    compression_methods,
+    extensions,
+  })
+}
+
+fn server_hello<Stream, Context>(
+  stream: Stream, len: usize,
+) -> Parsed<ServerHello<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  let Success {
+    token: (version, random, (session_id, session_id_len), cipher_suite, compression_method),
+    stream,
+  } = (
+    u16_be.map(TlsVersion::new),
+    octet.fill(),
+    tls_opaque8,
+    u16_be,
+    octet,
+  )
+    .parse(stream)?;
+
+  let consumed = 2 + 32 + session_id_len + 2 + 1;
+  let Some(extensions_len) = len.checked_sub(consumed) else {
+    return length_failure(len);
+  };
+  tls_extensions(stream, extensions_len).map_token(|extensions| ServerHello {
+    version,
+    random,
+    session_id,
+    cipher_suite,
+    compression_method,
+    extensions,
+  })
+}
+
+/// Parse a plaintext handshake message, ClientHello and ServerHello are
+/// decoded. A message must not span several records.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn tls_handshake<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TlsHandshake<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TlsParse<Stream, Context>,
+{
+  let Success {
+    token: (handshake_type, len_0, len_1),
+    stream,
+  } = (octet.map(TlsHandshakeType::new), octet, u16_be).parse(stream)?;
+  let len = usize::from(len_0) << 16u8 | usize::from(len_1);
+
+  match handshake_type {
+    TlsHandshakeType::CLIENT_HELLO => {
+      client_hello(stream, len).map_token(TlsHandshake::ClientHello)
+    }
+    TlsHandshakeType::SERVER_HELLO => {
+      server_hello(stream, len).map_token(TlsHandshake::ServerHello)
+    }
+    _ => take(len)
+      .map(|body| TlsHandshake::Other(handshake_type, body))
+      .parse(stream),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    TlsContentType,
+    TlsHandshake,
+    TlsVersion,
+  };
+
+  #[test]
+  fn client_hello() {
+    let mut hello = vec![0x03, 0x03];
+    hello.extend_from_slice(&[0x42; 32]);
+    hello.extend_from_slice(&[0x00, 0x00, 0x04, 0x13, 0x01, 0x13, 0x02, 0x01, 0x00]);
+    let extensions = [
+      // server_name example.com
+      0x00, 0x00, 0x00, 0x10, 0x00, 0x0E, 0x00, 0x00, 0x0B, b'e', b'x', b'a', b'm', b'p', b'l',
+      b'e', b'.', b'c', b'o', b'm', // alpn h2 http/1.1
+      0x00, 0x10, 0x00, 0x0E, 0x00, 0x0C, 0x02, b'h', b'2', 0x08, b'h', b't', b't', b'p', b'/',
+      b'1', b'.', b'1', // supported_versions
+      0x00, 0x2B, 0x00, 0x05, 0x04, 0x03, 0x04, 0x03, 0x03, // padding
+      0x00, 0x15, 0x00, 0x01, 0x00,
+    ];
+    hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+    hello.extend_from_slice(&extensions);
+
+    let mut handshake = vec![0x01, 0x00];
+    handshake.extend_from_slice(&(hello.len() as u16).to_be_bytes());
+    handshake.extend_from_slice(&hello);
+    let mut record = vec![0x16, 0x03, 0x01];
+    record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+    record.extend_from_slice(&handshake);
+
+    let Success {
+      token: record,
+      stream,
+    } = super::tls_record::<_, Ignore>(record.as_slice()).unwrap();
+    assert_eq!(record.content_type, TlsContentType::HANDSHAKE);
+    assert_eq!(stream, b"");
+
+    let Success {
+      token: TlsHandshake::ClientHello(hello),
+      stream,
+    } = super::tls_handshake::<_, Ignore>(record.fragment).unwrap()
+    else {
+      panic!("client hello");
+    };
+    assert_eq!(stream, b"");
+    assert_eq!(hello.version, TlsVersion::TLS_1_2);
+    assert_eq!(hello.cipher_suites, [0x1301, 0x1302]);
+    assert_eq!(hello.server_name(), Some(&&b"example.com"[..]));
+    assert_eq!(hello.alpn(), Some(&[&b"h2"[..], &b"http/1.1"[..]][..]));
+    assert_eq!(
+      hello.supported_versions(),
+      Some(&[TlsVersion::TLS_1_3, TlsVersion::TLS_1_2][..])
+    );
+    assert_eq!(hello.extensions.len(), 4);
+
+    handshake[3] += 1;
+    handshake.push(0);
+    assert!(matches!(
+      super::tls_handshake::<_, Ignore>(handshake.as_slice()),
+      Parsed::Failure(_)
+    ));
+  }
+}
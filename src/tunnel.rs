@@ -0,0 +1,388 @@
+//! Handles parsing of IPv6 transition tunnels, 6in4 and Teredo encapsulation,
+//! and recognition of the 6to4, Teredo and ISATAP address formats
+//! <https://www.rfc-editor.org/rfc/rfc4213>
+//! <https://www.rfc-editor.org/rfc/rfc4380>
+//! <https://www.rfc-editor.org/rfc/rfc3056>
+//! <https://www.rfc-editor.org/rfc/rfc5214>
+
+use std::{
+  fmt::{
+    Display,
+    Formatter,
+  },
+  net::{
+    Ipv4Addr,
+    Ipv6Addr,
+  },
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  ipv4_header,
+  ipv6_header,
+  IPProtocol,
+  IPv4Header,
+  IPv6Header,
+  Ipv4Atom,
+  Ipv6Atom,
+  Port,
+};
+
+/// UDP port of Teredo servers
+pub const TEREDO_PORT: Port = Port(3544);
+
+/// Meta trait for tunnel combinator
+pub trait TunnelParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<Ipv4Atom>,
+  Context: Contexting<Ipv6Atom>,
+  Context: Contexting<TunnelAtom>;
+
+/// Atom produced by tunnel parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TunnelAtom {
+  /// When the IPv4 packet doesn't carry IPv6
+  Protocol(IPProtocol),
+}
+
+impl Display for TunnelAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      TunnelAtom::Protocol(protocol) => write!(f, "Protocol: expected IPv6 found {}", protocol),
+    }
+  }
+}
+
+/// IPv6 packet carried by IPv4, [IPProtocol::IPV6]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SixInFourPacket<Span> {
+  /// Outer IPv4 header
+  pub ipv4: IPv4Header<Span>,
+  /// Inner IPv6 header
+  pub ipv6: IPv6Header,
+  /// Inner IPv6 packet, header included
+  pub packet: Span,
+}
+
+/// Authentication indicator of a Teredo packet
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TeredoAuthentication<Span> {
+  /// Client identifier
+  pub client_id: Span,
+  /// Authentication value
+  pub auth_value: Span,
+  /// Nonce
+  pub nonce: [u8; 8],
+  /// Confirmation byte
+  pub confirmation: u8,
+}
+
+/// Origin indicator of a Teredo packet, the mapped address of the client
+/// seen by the server
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TeredoOrigin {
+  /// Mapped port, already deobfuscated
+  pub port: Port,
+  /// Mapped address, already deobfuscated
+  pub addr: Ipv4Addr,
+}
+
+/// Teredo packet, the payload of a UDP datagram from or to [TEREDO_PORT] or a
+/// Teredo client
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TeredoPacket<Span> {
+  /// Authentication indicator
+  pub authentication: Option<TeredoAuthentication<Span>>,
+  /// Origin indicator
+  pub origin: Option<TeredoOrigin>,
+  /// Inner IPv6 header
+  pub ipv6: IPv6Header,
+  /// Inner IPv6 packet, header included
+  pub packet: Span,
+}
+
+/// Fields embedded in a Teredo address, 2001::/32
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TeredoAddress {
+  /// Address of the Teredo server
+  pub server: Ipv4Addr,
+  /// Flags
+  pub flags: u16,
+  /// Mapped port of the client, already deobfuscated
+  pub port: Port,
+  /// Mapped address of the client, already deobfuscated
+  pub client: Ipv4Addr,
+}
+
+impl TeredoAddress {
+  /// Flag telling the client is behind a cone NAT
+  pub const CONE_FLAG: u16 = 0x8000;
+}
+
+/// IPv6 address embedding an IPv4 address of a transition mechanism
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TransitionAddress {
+  /// 6to4 address, 2002::/16 followed by the IPv4 address of the site
+  SixToFour(Ipv4Addr),
+  /// Teredo address
+  Teredo(TeredoAddress),
+  /// ISATAP address, interface identifier `0000:5EFE` or `0200:5EFE` followed
+  /// by the IPv4 address of the node
+  Isatap(Ipv4Addr),
+}
+
+impl TransitionAddress {
+  /// Return the transition mechanism addr belongs to, None for a native
+  /// address
+  pub const fn from_ipv6(addr: Ipv6Addr) -> Option<Self> {
+    let bits = addr.to_bits();
+    let high = (bits >> 96u8) as u32;
+    let low = bits as u32;
+
+    if high >> 16u8 == 0x2002 {
+      let site = (high << 16u8) | (bits >> 80u8) as u16 as u32;
+      Some(Self::SixToFour(Ipv4Addr::from_bits(site)))
+    } else if high == 0x2001_0000 {
+      Some(Self::Teredo(TeredoAddress {
+        server: Ipv4Addr::from_bits((bits >> 64u8) as u32),
+        flags: (bits >> 48u8) as u16,
+        port: Port(!((bits >> 32u8) as u16)),
+        client: Ipv4Addr::from_bits(!low),
+      }))
+    } else if (bits >> 32u8) as u32 & 0xFDFF_FFFF == 0x0000_5EFE {
+      Some(Self::Isatap(Ipv4Addr::from_bits(low)))
+    } else {
+      None
+    }
+  }
+}
+
+// Peek the IPv6 header then take the whole packet
+fn tunnel_ipv6<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(IPv6Header, <Stream as Streaming>::Span), Stream, Context>
+where
+  (): TunnelParse<Stream, Context>,
+{
+  let Success { token: ipv6, .. } = ipv6_header.parse(stream.clone())?;
+  take(IPv6Header::LEN + usize::from(ipv6.length))
+    .map(|packet| (ipv6, packet))
+    .parse(stream)
+}
+
+fn teredo_authentication<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TeredoAuthentication<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TunnelParse<Stream, Context>,
+{
+  let Success {
+    token: (_, client_id_len, auth_value_len),
+    stream,
+  } = (
+    u16_be.filter(|indicator| *indicator == 0x0001),
+    octet,
+    octet,
+  )
+    .parse(stream)?;
+
+  (
+    take(usize::from(client_id_len)),
+    take(usize::from(auth_value_len)),
+    octet.fill(),
+    octet,
+  )
+    .map(
+      |(client_id, auth_value, nonce, confirmation)| TeredoAuthentication {
+        client_id,
+        auth_value,
+        nonce,
+        confirmation,
+      },
+    )
+    .parse(stream)
+}
+
+fn teredo_origin<Stream, Context>(stream: Stream) -> Parsed<TeredoOrigin, Stream, Context>
+where
+  (): TunnelParse<Stream, Context>,
+{
+  (
+    u16_be.filter(|indicator| *indicator == 0x0000),
+    u16_be,
+    u32_be,
+  )
+    .map(|(_, port, addr)| TeredoOrigin {
+      port: Port(!port),
+      addr: Ipv4Addr::from(!addr),
+    })
+    .parse(stream)
+}
+
+/// Parse an IPv4 header carrying IPv6 then the inner IPv6 header, the stream
+/// is left after the inner packet
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn six_in_four<Stream, Context>(
+  stream: Stream,
+) -> Parsed<SixInFourPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TunnelParse<Stream, Context>,
+{
+  let Success {
+    token: ipv4,
+    stream,
+  } = ipv4_header.parse(stream)?;
+  if ipv4.protocol != IPProtocol::IPV6 {
+    return Parsed::Failure(Context::new(TunnelAtom::Protocol(ipv4.protocol)));
+  }
+
+  let Success {
+    token: (ipv6, packet),
+    stream,
+  } = tunnel_ipv6(stream)?;
+
+  Parsed::Success {
+    token: SixInFourPacket { ipv4, ipv6, packet },
+    stream,
+  }
+}
+
+/// Parse the optional authentication and origin indicators of a Teredo packet
+/// then the inner IPv6 header, the stream is left after the inner packet
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn teredo_packet<Stream, Context>(
+  stream: Stream,
+) -> Parsed<TeredoPacket<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): TunnelParse<Stream, Context>,
+{
+  (
+    teredo_authentication.opt(),
+    teredo_origin.opt(),
+    tunnel_ipv6,
+  )
+    .map(|(authentication, origin, (ipv6, packet))| TeredoPacket {
+      authentication,
+      origin,
+      ipv6,
+      packet,
+    })
+    .parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::Ipv4Addr;
+
+  use binator::{
+    context::Ignore,
+    Parsed,
+    Success,
+  };
+
+  use super::{
+    TeredoAddress,
+    TeredoOrigin,
+    TransitionAddress,
+  };
+  use crate::{
+    IPProtocol,
+    Port,
+  };
+
+  #[test]
+  fn tunnel() {
+    let ipv6 = [
+      0x60, 0x00, 0x00, 0x00, 0x00, 0x00, 0x3B, 0x15, 0x20, 0x01, 0x00, 0x00, 0x41, 0x36, 0xE3,
+      0x78, 0x80, 0x00, 0x63, 0xBF, 0x3F, 0xFF, 0xFD, 0xD2, 0xFE, 0x80, 0x00, 0x00, 0x00, 0x00,
+      0x00, 0x00, 0x80, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE,
+    ];
+
+    let mut teredo = vec![0x00, 0x00, 0xEC, 0x62, 0x3F, 0xFF, 0xFD, 0xD2];
+    teredo.extend_from_slice(&ipv6);
+    let Success { token, stream } = super::teredo_packet::<_, Ignore>(&teredo[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.authentication, None);
+    assert_eq!(
+      token.origin,
+      Some(TeredoOrigin {
+        port: Port(5021),
+        addr: Ipv4Addr::new(192, 0, 2, 45),
+      })
+    );
+    assert_eq!(token.ipv6.next_header, IPProtocol::NO_NXT_6);
+    assert_eq!(token.packet, ipv6);
+    assert_eq!(
+      TransitionAddress::from_ipv6(token.ipv6.source_addr),
+      Some(TransitionAddress::Teredo(TeredoAddress {
+        server: Ipv4Addr::new(65, 54, 227, 120),
+        flags: TeredoAddress::CONE_FLAG,
+        port: Port(40000),
+        client: Ipv4Addr::new(192, 0, 2, 45),
+      }))
+    );
+    assert_eq!(
+      TransitionAddress::from_ipv6("2002:c000:0201::1".parse().unwrap()),
+      Some(TransitionAddress::SixToFour(Ipv4Addr::new(192, 0, 2, 1)))
+    );
+    assert_eq!(
+      TransitionAddress::from_ipv6("fe80::5efe:c000:201".parse().unwrap()),
+      Some(TransitionAddress::Isatap(Ipv4Addr::new(192, 0, 2, 1)))
+    );
+    assert_eq!(
+      TransitionAddress::from_ipv6("2001:db8::1".parse().unwrap()),
+      None
+    );
+
+    let mut six_in_four = vec![
+      0x45, 0x00, 0x00, 0x3C, 0x00, 0x00, 0x40, 0x00, 0x40, 0x29, 0x00, 0x00, 0xC0, 0x00, 0x02,
+      0x01, 0xC0, 0x00, 0x02, 0x02,
+    ];
+    six_in_four.extend_from_slice(&ipv6);
+    let Success { token, stream } = super::six_in_four::<_, Ignore>(&six_in_four[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(token.ipv4.source_addr, Ipv4Addr::new(192, 0, 2, 1));
+    assert_eq!(token.packet, ipv6);
+
+    six_in_four[9] = 0x11;
+    assert!(matches!(
+      super::six_in_four::<_, Ignore>(&six_in_four[..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
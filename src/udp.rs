@@ -1,16 +1,45 @@
 //! Handles parsing of UDP header
 
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
 use binator::{
-  base::primitive::u16_be,
-  utils::UtilsAtom,
+  base::{
+    octet,
+    primitive::{
+      u16_be,
+      u32_be,
+    },
+    take,
+  },
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
   Contexting,
   CoreAtom,
   Parse,
   Parsed,
+  Split,
   Streaming,
   Success,
 };
 
+#[cfg(feature = "ipv4")]
+use crate::IPv4Header;
+#[cfg(feature = "ipv6")]
+use crate::IPv6Header;
+#[cfg(any(feature = "ipv4", feature = "ipv6"))]
+use crate::SoftwareChecksum;
+use crate::{
+  verify_udp_checksum,
+  ChecksumBackend,
+  Port,
+  PseudoHeader,
+};
+
 /// Data of a UDP Header
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -20,13 +49,13 @@ pub struct UdpHeader {
   /// the source host is the client, the port number is likely to be an
   /// ephemeral port. If the source host is the server, the port number is
   /// likely to be a well-known port number from 0 to 1023.
-  pub source_port: u16,
+  pub source_port: Port,
   /// This field identifies the receiver's port and is required. Similar to
   /// source port number, if the client is the destination host then the port
   /// number will likely be an ephemeral port number and if the destination host
   /// is the server then the port number will likely be a well-known port
   /// number.
-  pub dest_port: u16,
+  pub dest_port: Port,
   /// This field specifies the length in bytes of the UDP header and UDP data.
   pub length: u16,
   /// The checksum field may be used for error-checking of the header and data.
@@ -35,55 +64,645 @@ pub struct UdpHeader {
   pub checksum: u16,
 }
 
-/// UDP header parser
+impl UdpHeader {
+  /// Length of a UDP header
+  pub const LEN: usize = 8;
+
+  /// Return UdpHeader from its wire representation, without going through
+  /// the combinator machinery
+  pub const fn from_bytes(bytes: &[u8; Self::LEN]) -> Self {
+    let [s0, s1, d0, d1, l0, l1, c0, c1] = *bytes;
+    Self {
+      source_port: Port(u16::from_be_bytes([s0, s1])),
+      dest_port: Port(u16::from_be_bytes([d0, d1])),
+      length: u16::from_be_bytes([l0, l1]),
+      checksum: u16::from_be_bytes([c0, c1]),
+    }
+  }
+
+  /// Return the wire representation of the header
+  pub const fn to_bytes(&self) -> [u8; Self::LEN] {
+    let [s0, s1] = self.source_port.get().to_be_bytes();
+    let [d0, d1] = self.dest_port.get().to_be_bytes();
+    let [l0, l1] = self.length.to_be_bytes();
+    let [c0, c1] = self.checksum.to_be_bytes();
+    [s0, s1, d0, d1, l0, l1, c0, c1]
+  }
+
+  /// Return the payload of the datagram from the bytes following the header,
+  /// None if rest is shorter than length say or length is less than 8
+  pub fn payload<'a>(&self, rest: &'a [u8]) -> Option<&'a [u8]> {
+    rest.get(..usize::from(self.length).checked_sub(Self::LEN)?)
+  }
+
+  /// Return the surplus area, the bytes following the payload up to the end
+  /// of the IP payload, rest is the bytes following the header up to the end
+  /// of the IP payload. The octet aligning the surplus area on 2 bytes is
+  /// skipped. None if rest is shorter than length say or length is less than 8
+  pub fn surplus<'a>(&self, rest: &'a [u8]) -> Option<&'a [u8]> {
+    let payload_len = usize::from(self.length).checked_sub(Self::LEN)?;
+    let surplus = rest.get(payload_len..)?;
+    Some(surplus.get(payload_len % 2..).unwrap_or_default())
+  }
+
+  /// Check length and checksum of the datagram carried by ip, payload is the
+  /// bytes following the header
+  #[cfg(feature = "ipv4")]
+  pub fn validate<Span>(&self, ip: &IPv4Header<Span>, payload: &[u8]) -> UdpValidation {
+    self.validate_with(&SoftwareChecksum, ip, payload)
+  }
+
+  /// Like [UdpHeader::validate] but the checksum is computed by backend
+  #[cfg(feature = "ipv4")]
+  pub fn validate_with<Span, Backend>(
+    &self, backend: &Backend, ip: &IPv4Header<Span>, payload: &[u8],
+  ) -> UdpValidation
+  where
+    Backend: ChecksumBackend,
+  {
+    self.validate_ip(backend, ip, ip.payload_len(), payload)
+  }
+
+  /// Check length and checksum of the datagram carried by ip, payload is the
+  /// bytes following the header. Extension headers are assumed absent when
+  /// comparing length with the IPv6 payload length.
+  #[cfg(feature = "ipv6")]
+  pub fn validate_v6(&self, ip: &IPv6Header, payload: &[u8]) -> UdpValidation {
+    self.validate_v6_with(&SoftwareChecksum, ip, payload)
+  }
+
+  /// Like [UdpHeader::validate_v6] but the checksum is computed by backend
+  #[cfg(feature = "ipv6")]
+  pub fn validate_v6_with<Backend>(
+    &self, backend: &Backend, ip: &IPv6Header, payload: &[u8],
+  ) -> UdpValidation
+  where
+    Backend: ChecksumBackend,
+  {
+    self.validate_ip(backend, ip, Some(usize::from(ip.length)), payload)
+  }
+
+  #[cfg_attr(not(any(feature = "ipv4", feature = "ipv6")), allow(dead_code))]
+  fn validate_ip<Backend: ChecksumBackend, Ip: PseudoHeader>(
+    &self, backend: &Backend, ip: &Ip, ip_len: Option<usize>, payload: &[u8],
+  ) -> UdpValidation {
+    let length = usize::from(self.length);
+    let length_status = match ip_len {
+      _ if length < Self::LEN => UdpLengthStatus::TooSmall,
+      Some(ip_len) if length > ip_len => UdpLengthStatus::Exceeds,
+      Some(ip_len) if length < ip_len => UdpLengthStatus::Surplus(ip_len - length),
+      Some(_) => UdpLengthStatus::Valid,
+      None => UdpLengthStatus::Exceeds,
+    };
+
+    let checksum_status = match self.payload(payload) {
+      None => UdpChecksumStatus::Unchecked,
+      Some(_) if self.checksum == 0 && ip.pseudo_addrs().0.is_ipv4() => UdpChecksumStatus::Absent,
+      Some(_) if self.checksum == 0 => UdpChecksumStatus::Zero,
+      Some(payload) if verify_udp_checksum(backend, ip, self, payload) => UdpChecksumStatus::Valid,
+      Some(_) => UdpChecksumStatus::Invalid,
+    };
+
+    UdpValidation {
+      length: length_status,
+      checksum: checksum_status,
+    }
+  }
+}
+
+/// Outcome of the length check of [UdpHeader::validate]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UdpLengthStatus {
+  /// Length match the IP payload
+  Valid,
+  /// Length is less than the header length
+  TooSmall,
+  /// Length exceed the IP payload
+  Exceeds,
+  /// IP payload is longer than length, contain the number of surplus bytes
+  Surplus(usize),
+}
+
+/// Outcome of the checksum check of [UdpHeader::validate]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UdpChecksumStatus {
+  /// Checksum match
+  Valid,
+  /// Checksum doesn't match
+  Invalid,
+  /// Checksum is zero over IPv4, the sender didn't compute it
+  Absent,
+  /// Checksum is zero over IPv6, this is forbidden
+  Zero,
+  /// Payload is shorter than length, the checksum can't be computed
+  Unchecked,
+}
+
+/// Report of [UdpHeader::validate]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UdpValidation {
+  /// Length check
+  pub length: UdpLengthStatus,
+  /// Checksum check
+  pub checksum: UdpChecksumStatus,
+}
+
+impl UdpValidation {
+  /// Return true if length doesn't exceed the IP payload and checksum is
+  /// valid or absent
+  pub const fn is_valid(&self) -> bool {
+    matches!(
+      self.length,
+      UdpLengthStatus::Valid | UdpLengthStatus::Surplus(_)
+    ) && matches!(
+      self.checksum,
+      UdpChecksumStatus::Valid | UdpChecksumStatus::Absent
+    )
+  }
+}
+
+/// Atom produced by udp_header parser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UdpAtom {
+  /// When length is less than the header length
+  Length(u16),
+  /// When length exceed the remaining of the stream
+  Truncated(u16),
+  /// When an option length is less than its header or doesn't match its kind
+  OptionLen(u16),
+}
+
+impl Display for UdpAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      UdpAtom::Length(length) => write!(f, "Length: {} is less than 8", length),
+      UdpAtom::Truncated(length) => {
+        write!(
+          f,
+          "Truncated: length {} exceed the remaining stream",
+          length
+        )
+      }
+      UdpAtom::OptionLen(len) => write!(f, "OptionLen: option length {} is invalid", len),
+    }
+  }
+}
+
+/// UDP header parser, the stream must hold the whole datagram as length is
+/// checked against it. The payload is not consumed.
 pub fn udp_header<Stream, Context>(stream: Stream) -> Parsed<UdpHeader, Stream, Context>
 where
+  Stream: Clone,
   Stream: Streaming,
   Stream: Eq,
   Stream::Item: Into<u8>,
   Context: Contexting<CoreAtom<Stream>>,
   Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<UdpAtom>,
+{
+  let Success { token, stream } = udp_header_unchecked.parse(stream)?;
+
+  let Success { stream, .. } = take(usize::from(token.length) - UdpHeader::LEN)
+    .peek()
+    .add_atom(|| UdpAtom::Truncated(token.length))
+    .parse(stream)?;
+
+  Parsed::Success { token, stream }
+}
+
+/// UDP header parser that doesn't check length against the stream, for the
+/// first fragment of a datagram or a frame truncated by the capture snaplen.
+/// The payload is not consumed.
+pub fn udp_header_unchecked<Stream, Context>(stream: Stream) -> Parsed<UdpHeader, Stream, Context>
+where
+  Stream: Clone,
+  Stream: Streaming,
+  Stream: Eq,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<UdpAtom>,
 {
   let Success {
     token: (source_port, dest_port, length, checksum),
     stream,
-  } = (u16_be, u16_be, u16_be, u16_be).parse(stream)?;
-
-  Parsed::Success {
-    token: UdpHeader {
-      source_port,
-      dest_port,
-      length,
-      checksum,
-    },
+  } = (
+    u16_be.map(Port),
+    u16_be.map(Port),
+    u16_be.try_map(|length| {
+      if usize::from(length) >= UdpHeader::LEN {
+        Ok(length)
+      } else {
+        Err(Context::new(UdpAtom::Length(length)))
+      }
+    }),
+    u16_be,
+  )
+    .parse(stream)?;
+
+  let token = UdpHeader {
+    source_port,
+    dest_port,
+    length,
+    checksum,
+  };
+
+  #[cfg(feature = "tracing")]
+  crate::trace_layer(&token);
+
+  Parsed::Success { token, stream }
+}
+
+/// UDP fragmentation option
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UdpFragment {
+  /// Offset of the options of this fragment, from the start of the fragment
+  /// data
+  pub start: u16,
+  /// Identification shared by the fragments of a datagram
+  pub id: u32,
+  /// Offset of this fragment in the reassembled datagram
+  pub offset: u16,
+  /// Offset of the end of the fragment, only present in the long form
+  pub end: Option<u16>,
+}
+
+/// UDP option, found in the surplus area
+/// <https://datatracker.ietf.org/doc/draft-ietf-tsvwg-udp-options/>
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UdpOption<Span> {
+  /// End of Options List, the rest of the surplus area is zero
+  EndOfOptions,
+  /// No Operation
+  Noop,
+  /// Fragmentation
+  Fragment(UdpFragment),
+  /// Maximum datagram size the sender can receive
+  Mss(u16),
+  /// Timestamps
+  Timestamps {
+    /// Timestamp value
+    tsval: u32,
+    /// Timestamp echo reply
+    tsecr: u32,
+  },
+  /// Any other option, kind and data
+  Unknown(u8, Span),
+}
+
+/// Options of the surplus area
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UdpOptions<Span> {
+  /// Option Checksum, the first 2 bytes of the surplus area
+  pub checksum: Option<u16>,
+  /// Options
+  pub options: Vec<UdpOption<Span>>,
+}
+
+fn udp_option<Stream, Context>(
+  stream: Stream,
+) -> Parsed<UdpOption<<Stream as Streaming>::Span>, Stream, Context>
+where
+  Stream: Clone,
+  Stream: Streaming,
+  Stream: Eq,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<UdpAtom>,
+{
+  let Success {
+    token: kind,
     stream,
+  } = octet.parse(stream)?;
+  match kind {
+    0 => {
+      return Parsed::Success {
+        token: UdpOption::EndOfOptions,
+        stream,
+      }
+    }
+    1 => {
+      return Parsed::Success {
+        token: UdpOption::Noop,
+        stream,
+      }
+    }
+    _ => {}
+  }
+
+  // a length of 255 announce a 16 bits length
+  let Success { token: len, stream } = octet.parse(stream)?;
+  let Success {
+    token: (len, header),
+    stream,
+  } = match len {
+    255 => u16_be.map(|len| (len, 4)).parse(stream)?,
+    len => Success {
+      token: (u16::from(len), 2),
+      stream,
+    },
+  };
+  let Some(data) = len.checked_sub(header) else {
+    return Parsed::Failure(Context::new(UdpAtom::OptionLen(len)));
+  };
+
+  match (kind, data) {
+    (3, 8 | 10) => (u16_be, u32_be, u16_be, move |stream: Stream| match data {
+      10 => u16_be.map(Some).parse(stream),
+      _ => Parsed::Success {
+        token: None,
+        stream,
+      },
+    })
+      .map(|(start, id, offset, end)| {
+        UdpOption::Fragment(UdpFragment {
+          start,
+          id,
+          offset,
+          end,
+        })
+      })
+      .parse(stream),
+    (4, 2) => u16_be.map(UdpOption::Mss).parse(stream),
+    (8, 8) => (u32_be, u32_be)
+      .map(|(tsval, tsecr)| UdpOption::Timestamps { tsval, tsecr })
+      .parse(stream),
+    (3 | 4 | 8, _) => Parsed::Failure(Context::new(UdpAtom::OptionLen(len))),
+    (kind, data) => take(usize::from(data))
+      .map(|data| UdpOption::Unknown(kind, data))
+      .parse(stream),
+  }
+}
+
+/// Parse the options of the surplus area given by [UdpHeader::surplus].
+/// Parsing stop after [UdpOption::EndOfOptions], leaving the padding in the
+/// stream. A malformed or truncated option is a failure.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn udp_options<Stream, Context>(
+  stream: Stream,
+) -> Parsed<UdpOptions<<Stream as Streaming>::Span>, Stream, Context>
+where
+  Stream: Clone,
+  Stream: Streaming,
+  Stream: Eq,
+  Stream::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<UdpAtom>,
+{
+  let Success {
+    token: checksum,
+    mut stream,
+  } = u16_be.opt().parse(stream)?;
+
+  let mut options = Vec::new();
+  loop {
+    match udp_option.parse(stream.clone()) {
+      Parsed::Success {
+        token: UdpOption::EndOfOptions,
+        stream,
+      } => {
+        options.push(UdpOption::EndOfOptions);
+        return Parsed::Success {
+          token: UdpOptions { checksum, options },
+          stream,
+        };
+      }
+      Parsed::Success {
+        token,
+        stream: next,
+      } => {
+        options.push(token);
+        stream = next;
+      }
+      // only the end of the stream end the options without EndOfOptions
+      Parsed::Failure(context) => {
+        return match stream.clone().split_first() {
+          Split::NotEnoughItem(_) => Parsed::Success {
+            token: UdpOptions { checksum, options },
+            stream,
+          },
+          _ => Parsed::Failure(context),
+        };
+      }
+      Parsed::Error(context) => return Parsed::Error(context),
+    }
   }
 }
 
 #[cfg(test)]
 mod tests {
+  use core::fmt::Debug;
+
   use binator::{
-    context::Ignore,
+    context::{
+      Ignore,
+      Tree,
+    },
+    utils::UtilsAtom,
+    CoreAtom,
     Parsed,
+    ProvideElement,
+    Streaming,
+  };
+  use derive_more::{
+    Display,
+    From,
   };
 
-  use super::UdpHeader;
+  use super::{
+    UdpAtom,
+    UdpFragment,
+    UdpHeader,
+    UdpOption,
+  };
+  use crate::Port;
+
+  #[derive(Display, Debug, Clone, PartialEq, From)]
+  enum FromAtom<Stream: Streaming + Debug, Error = <Stream as Streaming>::Error> {
+    Core(CoreAtom<Stream, Error>),
+    Utils(UtilsAtom<Stream>),
+    Udp(UdpAtom),
+  }
+
+  type HandleAtom<Stream> = Tree<FromAtom<Stream>>;
 
   #[test]
   fn udp_header_works() {
-    let bytes = [0x00, 0x12, 0x11, 0x11, 0x00, 0x1B, 0x21, 0x0F];
+    let bytes = [0x00, 0x12, 0x11, 0x11, 0x00, 0x0B, 0x21, 0x0F];
     let expectation = UdpHeader {
-      source_port: 0x12,
-      dest_port: 0x1111,
-      length: 0x1B,
+      source_port: Port(0x12),
+      dest_port: Port(0x1111),
+      length: 0x0B,
       checksum: 0x210F,
     };
+    assert_eq!(UdpHeader::from_bytes(&bytes), expectation);
+    assert_eq!(expectation.to_bytes(), bytes);
     assert_eq!(
-      super::udp_header::<_, Ignore>(&bytes[..]),
+      super::udp_header::<_, Ignore>(&[&bytes[..], &[1, 2, 3]].concat()[..]),
       Parsed::Success {
         token: expectation,
-        stream: &[][..]
+        stream: &[1, 2, 3][..]
       }
     );
+    assert_eq!(expectation.payload(&[1, 2, 3, 4]), Some(&[1, 2, 3][..]));
+    assert_eq!(expectation.payload(&[1, 2]), None);
+    assert!(matches!(
+      super::udp_header::<_, Ignore>(&bytes[..]),
+      Parsed::Failure(_)
+    ));
+  }
+
+  #[test]
+  fn udp_options() {
+    let udp = UdpHeader {
+      source_port: Port(1234),
+      dest_port: Port(53),
+      length: 11,
+      checksum: 0,
+    };
+    let rest = [
+      1, 2, 3, 0x00, 0xAB, 0xCD, 0x01, 0x04, 0x04, 0x05, 0xDC, 0x08, 0x0A, 0x00, 0x00, 0x00, 0x01,
+      0x00, 0x00, 0x00, 0x02, 0x03, 0x0A, 0x00, 0x00, 0x12, 0x34, 0x56, 0x78, 0x00, 0x08, 0x1E,
+      0xFF, 0x00, 0x05, 0x2A, 0x00, 0x00, 0x00,
+    ];
+    let surplus = udp.surplus(&rest).unwrap();
+    assert_eq!(surplus[..2], [0xAB, 0xCD]);
+    assert_eq!(udp.surplus(&rest[..3]), Some(&[][..]));
+    assert_eq!(udp.surplus(&rest[..2]), None);
+
+    let Parsed::Success { token, stream } = super::udp_options::<_, Ignore>(surplus) else {
+      panic!("expected success");
+    };
+    assert_eq!(token.checksum, Some(0xABCD));
+    assert_eq!(
+      token.options,
+      [
+        UdpOption::Noop,
+        UdpOption::Mss(1500),
+        UdpOption::Timestamps { tsval: 1, tsecr: 2 },
+        UdpOption::Fragment(UdpFragment {
+          start: 0,
+          id: 0x12345678,
+          offset: 8,
+          end: None,
+        }),
+        UdpOption::Unknown(0x1E, &[0x2A][..]),
+        UdpOption::EndOfOptions,
+      ]
+    );
+    assert_eq!(stream, [0x00, 0x00]);
+
+    let Parsed::Success { token, stream } =
+      super::udp_options::<_, Ignore>(&[0x00, 0x00, 0x01][..])
+    else {
+      panic!("expected success");
+    };
+    assert_eq!(token.options, [UdpOption::Noop]);
+    assert_eq!(stream, []);
+
+    for (malformed, len) in [
+      (&[0x00, 0x00, 0x04, 0x03, 0x05][..], 3),
+      (&[0x00, 0x00, 0x03, 0x06, 0x00, 0x00, 0x00, 0x00][..], 6),
+      (&[0x00, 0x00, 0x01, 0x08, 0x01][..], 1),
+    ] {
+      let Parsed::Failure(context) = super::udp_options::<_, HandleAtom<_>>(malformed) else {
+        panic!("expected failure");
+      };
+      assert_eq!(context.last(), &FromAtom::Udp(UdpAtom::OptionLen(len)));
+    }
+  }
+
+  #[test]
+  #[cfg(all(feature = "ipv4", feature = "ipv6"))]
+  fn udp_validate() {
+    use std::net::Ipv4Addr;
+
+    use crate::{
+      transport_checksum,
+      ChecksumBackend,
+      IPProtocol,
+      IPv4Header,
+      IPv6Header,
+      SoftwareChecksum,
+      UdpChecksumStatus,
+      UdpLengthStatus,
+    };
+
+    let ip = IPv4Header {
+      version: 4,
+      ihl: 5,
+      tos: 0,
+      length: 31,
+      id: 0,
+      flags: 0,
+      fragment_offset: 0,
+      ttl: 64,
+      protocol: IPProtocol::UDP,
+      chksum: 0,
+      source_addr: Ipv4Addr::new(10, 0, 0, 1),
+      dest_addr: Ipv4Addr::new(10, 0, 0, 2),
+      options: (),
+    };
+    let mut datagram = [0x04, 0xD2, 0x00, 0x35, 0x00, 0x0B, 0x00, 0x00, 1, 2, 3];
+    let checksum = transport_checksum(
+      &SoftwareChecksum,
+      ip.source_addr.into(),
+      ip.dest_addr.into(),
+      IPProtocol::UDP,
+      &datagram,
+    );
+    datagram[6..8].copy_from_slice(&checksum.to_be_bytes());
+    let udp = UdpHeader::from_bytes(datagram[..8].try_into().unwrap());
+
+    let report = udp.validate(&ip, &datagram[8..]);
+    assert_eq!(report.length, UdpLengthStatus::Valid);
+    assert_eq!(report.checksum, UdpChecksumStatus::Valid);
+    assert!(report.is_valid());
+
+    let surplus = [&datagram[8..], &[0x00, 0x00]].concat();
+    let ip_surplus = IPv4Header { length: 33, ..ip };
+    let report = udp.validate(&ip_surplus, &surplus);
+    assert_eq!(report.length, UdpLengthStatus::Surplus(2));
+    assert!(report.is_valid());
+
+    let report = udp.validate(&ip, &[1, 2, 4]);
+    assert_eq!(report.checksum, UdpChecksumStatus::Invalid);
+    let report = udp.validate(&ip, &[1, 2]);
+    assert_eq!(report.checksum, UdpChecksumStatus::Unchecked);
+
+    // offloaded checksum already verified by the NIC
+    struct Offloaded;
+    impl ChecksumBackend for Offloaded {
+      fn checksum(&self, _parts: &[&[u8]]) -> u16 {
+        0
+      }
+    }
+    let report = udp.validate_with(&Offloaded, &ip, &[1, 2, 4]);
+    assert_eq!(report.checksum, UdpChecksumStatus::Valid);
+
+    let no_checksum = UdpHeader { checksum: 0, ..udp };
+    let report = no_checksum.validate(&IPv4Header { length: 30, ..ip }, &[1, 2, 3]);
+    assert_eq!(report.length, UdpLengthStatus::Exceeds);
+    assert_eq!(report.checksum, UdpChecksumStatus::Absent);
+    assert!(!report.is_valid());
+
+    let mut ipv6 = IPv6Header::from_bytes(&[0x60; IPv6Header::LEN]).unwrap();
+    ipv6.length = 11;
+    let report = no_checksum.validate_v6(&ipv6, &[1, 2, 3]);
+    assert_eq!(report.length, UdpLengthStatus::Valid);
+    assert_eq!(report.checksum, UdpChecksumStatus::Zero);
+    let report = udp.validate_v6_with(&Offloaded, &ipv6, &[1, 2, 3]);
+    assert_eq!(report.checksum, UdpChecksumStatus::Valid);
   }
 }
@@ -0,0 +1,159 @@
+//! Handles parsing of VXLAN headers
+//! <https://www.rfc-editor.org/rfc/rfc7348>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::primitive::u32_be,
+  utils::{
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::{
+  ethernet_frame,
+  EthernetAtom,
+  EthernetFrame,
+  Port,
+};
+
+/// UDP port of VXLAN
+pub const VXLAN_PORT: Port = Port(4789);
+
+/// Meta trait for vxlan combinator
+pub trait VxlanParse<Stream, Context> = where
+  Stream: Streaming<Span = Stream> + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<VxlanAtom>;
+
+/// Atom produced by vxlan parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VxlanAtom {
+  /// When the I flag, telling the VNI is valid, is not set
+  Flags(u8),
+}
+
+impl Display for VxlanAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      VxlanAtom::Flags(flags) => write!(f, "Flags: I flag is not set found {:#04X}", flags),
+    }
+  }
+}
+
+/// VXLAN header, the payload of a UDP datagram on [VXLAN_PORT]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VxlanHeader {
+  /// Flags, the I flag 0x08 is always set
+  pub flags: u8,
+  /// 24 bits VXLAN Network Identifier
+  pub vni: u32,
+}
+
+impl VxlanHeader {
+  /// Flag telling the VNI is valid
+  pub const I_FLAG: u8 = 0x08;
+  /// Length of a VXLAN header
+  pub const LEN: usize = 8;
+}
+
+/// Parse a VXLAN header, reserved fields are ignored
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn vxlan_header<Stream, Context>(stream: Stream) -> Parsed<VxlanHeader, Stream, Context>
+where
+  (): VxlanParse<Stream, Context>,
+{
+  let Success {
+    token: flags,
+    stream,
+  } = u32_be
+    .map(|word| (word >> 24u8) as u8)
+    .try_map(|flags| {
+      if flags & VxlanHeader::I_FLAG != 0 {
+        Ok(flags)
+      } else {
+        Err(Context::new(VxlanAtom::Flags(flags)))
+      }
+    })
+    .parse(stream)?;
+
+  let Success { token: vni, stream } = u32_be.map(|word| word >> 8u8).parse(stream)?;
+
+  Parsed::Success {
+    token: VxlanHeader { flags, vni },
+    stream,
+  }
+}
+
+/// Parse a VXLAN header then the inner ethernet frame it encapsulate, the
+/// stream is left at the inner frame payload
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn vxlan_decap<Stream, Context>(
+  stream: Stream,
+) -> Parsed<(VxlanHeader, EthernetFrame), Stream, Context>
+where
+  (): VxlanParse<Stream, Context>,
+  Context: Contexting<EthernetAtom>,
+{
+  (vxlan_header, ethernet_frame).parse(stream)
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Parsed,
+  };
+
+  use super::VxlanHeader;
+  use crate::EtherType;
+
+  #[test]
+  fn vxlan_decap() {
+    let packet = [
+      0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x7B, 0x00, 0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x00,
+      0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x08, 0x00, 0x45,
+    ];
+
+    let Parsed::Success {
+      token: (vxlan, ethernet),
+      stream,
+    } = super::vxlan_decap::<_, Ignore>(&packet[..])
+    else {
+      panic!("vxlan decap");
+    };
+    assert_eq!(
+      vxlan,
+      VxlanHeader {
+        flags: VxlanHeader::I_FLAG,
+        vni: 123,
+      }
+    );
+    assert_eq!(ethernet.ether_type.ether_type(), Some(EtherType::IPV4));
+    assert_eq!(stream, [0x45]);
+
+    assert!(matches!(
+      super::vxlan_header::<_, Ignore>(&[0u8; VxlanHeader::LEN][..]),
+      Parsed::Failure(_)
+    ));
+  }
+}
@@ -0,0 +1,769 @@
+//! Handles parsing of Radiotap headers and IEEE 802.11 MAC frames
+//! <https://www.radiotap.org>
+//! <https://en.wikipedia.org/wiki/802.11_frame_types>
+
+use std::fmt::{
+  Display,
+  Formatter,
+};
+
+use binator::{
+  base::{
+    octet,
+    primitive::{
+      u16_le,
+      u32_le,
+      u64_le,
+    },
+    take,
+  },
+  utils::{
+    Acc,
+    Utils,
+    UtilsAtom,
+  },
+  Contexting,
+  CoreAtom,
+  Parse,
+  Parsed,
+  Streaming,
+  Success,
+};
+
+use crate::struct_variants;
+
+/// Meta trait for wifi combinator
+pub trait WifiParse<Stream, Context> = where
+  Stream: Streaming + Clone + Eq,
+  <Stream as Streaming>::Item: Into<u8>,
+  <Stream as Streaming>::Span: AsRef<[u8]>,
+  Context: Contexting<CoreAtom<Stream>>,
+  Context: Contexting<UtilsAtom<Stream>>,
+  Context: Contexting<WifiAtom>;
+
+/// Atom produced by wifi parsers
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WifiAtom {
+  /// When the Radiotap version is not 0
+  RadiotapVersion(u8),
+  /// When the Radiotap length is shorter than its present words
+  RadiotapLength(u16),
+  /// When the 802.11 protocol version is not 0
+  FrameVersion(u8),
+}
+
+impl Display for WifiAtom {
+  fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    match self {
+      WifiAtom::RadiotapVersion(version) => {
+        write!(f, "RadiotapVersion: version is not 0 found {}", version)
+      }
+      WifiAtom::RadiotapLength(length) => {
+        write!(f, "RadiotapLength: length {} is too short", length)
+      }
+      WifiAtom::FrameVersion(version) => {
+        write!(
+          f,
+          "FrameVersion: protocol version is not 0 found {}",
+          version
+        )
+      }
+    }
+  }
+}
+
+/// Channel field of a Radiotap header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RadiotapChannel {
+  /// Frequency in MHz
+  pub frequency: u16,
+  /// Channel flags
+  pub flags: u16,
+}
+
+/// Radiotap header, only the fields of the first present word up to RX
+/// flags are decoded
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RadiotapHeader {
+  /// Version, always 0
+  pub version: u8,
+  /// Length of the whole header
+  pub length: u16,
+  /// First present word
+  pub present: u32,
+  /// Timer of the MAC when the first bit arrived, in microseconds
+  pub tsft: Option<u64>,
+  /// Flags, see [RadiotapHeader::FLAG_FCS]
+  pub flags: Option<u8>,
+  /// Rate in 500 kb/s units
+  pub rate: Option<u8>,
+  /// Channel
+  pub channel: Option<RadiotapChannel>,
+  /// Signal power in dBm
+  pub antenna_signal: Option<i8>,
+  /// Noise power in dBm
+  pub antenna_noise: Option<i8>,
+  /// Antenna index
+  pub antenna: Option<u8>,
+}
+
+impl RadiotapHeader {
+  // Fields are aligned to their natural size from the start of the header,
+  // (alignment, size) by present bit
+  const FIELDS: [(usize, usize); 15] = [
+    (8, 8),
+    (1, 1),
+    (1, 1),
+    (2, 4),
+    (2, 2),
+    (1, 1),
+    (1, 1),
+    (2, 2),
+    (2, 2),
+    (2, 2),
+    (1, 1),
+    (1, 1),
+    (1, 1),
+    (1, 1),
+    (2, 2),
+  ];
+  /// Flag telling the 802.11 frame end with its FCS
+  pub const FLAG_FCS: u8 = 0x10;
+  /// Length of a Radiotap header with one present word and no field
+  pub const MIN_LEN: usize = 8;
+
+  /// Return true if the 802.11 frame end with its 4 bytes FCS
+  pub fn has_fcs(&self) -> bool {
+    self.flags.is_some_and(|flags| flags & Self::FLAG_FCS != 0)
+  }
+
+  fn decode_fields(&mut self, fields: &[u8], mut offset: usize) {
+    let start = offset;
+    for (bit, (align, size)) in Self::FIELDS.iter().enumerate() {
+      if self.present & 1 << bit == 0 {
+        continue;
+      }
+      offset = offset.next_multiple_of(*align);
+      let Some(field) = fields.get(offset - start..offset - start + size) else {
+        return;
+      };
+      match (bit, field) {
+        (0, &[a, b, c, d, e, f, g, h]) => {
+          self.tsft = Some(u64::from_le_bytes([a, b, c, d, e, f, g, h]))
+        }
+        (1, &[flags]) => self.flags = Some(flags),
+        (2, &[rate]) => self.rate = Some(rate),
+        (3, &[a, b, c, d]) => {
+          self.channel = Some(RadiotapChannel {
+            frequency: u16::from_le_bytes([a, b]),
+            flags: u16::from_le_bytes([c, d]),
+          })
+        }
+        (5, &[signal]) => self.antenna_signal = Some(signal as i8),
+        (6, &[noise]) => self.antenna_noise = Some(noise as i8),
+        (11, &[antenna]) => self.antenna = Some(antenna),
+        _ => {}
+      }
+      offset += size;
+    }
+  }
+}
+
+/// Parse a Radiotap header, the stream is left at the 802.11 frame
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn radiotap_header<Stream, Context>(stream: Stream) -> Parsed<RadiotapHeader, Stream, Context>
+where
+  (): WifiParse<Stream, Context>,
+{
+  let Success {
+    token: (version, _pad, length, present),
+    stream,
+  } = (
+    octet.try_map(|version| {
+      if version == 0 {
+        Ok(version)
+      } else {
+        Err(Context::new(WifiAtom::RadiotapVersion(version)))
+      }
+    }),
+    octet,
+    u16_le,
+    u32_le,
+  )
+    .parse(stream)?;
+
+  // bit 31 tell another present word follow
+  let mut offset = RadiotapHeader::MIN_LEN;
+  let mut extended = present & 1 << 31u8 != 0;
+  let mut stream = stream;
+  while extended {
+    let Success {
+      token: word,
+      stream: next,
+    } = u32_le.parse(stream)?;
+    extended = word & 1 << 31u8 != 0;
+    offset += 4;
+    stream = next;
+  }
+
+  let Some(fields_len) = usize::from(length).checked_sub(offset) else {
+    return Parsed::Failure(Context::new(WifiAtom::RadiotapLength(length)));
+  };
+  let Success {
+    token: fields,
+    stream,
+  } = take(fields_len).parse(stream)?;
+
+  let mut radiotap = RadiotapHeader {
+    version,
+    length,
+    present,
+    tsft: None,
+    flags: None,
+    rate: None,
+    channel: None,
+    antenna_signal: None,
+    antenna_noise: None,
+    antenna: None,
+  };
+  radiotap.decode_fields(fields.as_ref(), offset);
+
+  Parsed::Success {
+    token: radiotap,
+    stream,
+  }
+}
+
+struct_variants! {
+  WifiFrameType, frame_type, u8:
+    /// Management frame
+    MANAGEMENT => 0,
+    /// Control frame
+    CONTROL => 1,
+    /// Data frame
+    DATA => 2,
+    /// Extension frame
+    EXTENSION => 3,
+}
+
+/// Frame control field of an 802.11 frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WifiFrameControl {
+  raw: u16,
+}
+
+macro_rules! wifi_flags {
+  ($($(#[$docs:meta])* $name:ident => $bit:expr,)*) => {
+    impl WifiFrameControl {
+      $($(#[$docs])*
+      pub const fn $name(&self) -> bool {
+        self.raw & 1 << $bit != 0
+      })*
+    }
+  };
+}
+
+wifi_flags! {
+  /// Frame is going to the distribution system
+  to_ds => 8u16,
+  /// Frame is coming from the distribution system
+  from_ds => 9u16,
+  /// More fragments follow
+  more_fragments => 10u16,
+  /// Frame is a retransmission
+  retry => 11u16,
+  /// Sender will enter power save mode
+  power_management => 12u16,
+  /// More frames are buffered for the receiver
+  more_data => 13u16,
+  /// Body is encrypted
+  protected => 14u16,
+  /// HT control field is present in QoS data and management frames
+  order => 15u16,
+}
+
+impl WifiFrameControl {
+  /// Return WifiFrameControl from its little endian value
+  pub const fn new(raw: u16) -> Self {
+    Self { raw }
+  }
+
+  /// Return protocol version
+  pub const fn version(&self) -> u8 {
+    (self.raw & 0b11) as u8
+  }
+
+  /// Return frame type
+  pub const fn frame_type(&self) -> WifiFrameType {
+    WifiFrameType::new((self.raw >> 2u8 & 0b11) as u8)
+  }
+
+  /// Return frame subtype
+  pub const fn subtype(&self) -> u8 {
+    (self.raw >> 4u8 & 0b1111) as u8
+  }
+}
+
+/// 802.11 MAC header, addresses after the first are absent from some control
+/// frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WifiHeader {
+  /// Frame control
+  pub frame_control: WifiFrameControl,
+  /// Duration or association id
+  pub duration: u16,
+  /// Receiver address
+  pub addr1: [u8; 6],
+  /// Transmitter address
+  pub addr2: Option<[u8; 6]>,
+  /// BSSID, source or destination address depending on the DS bits
+  pub addr3: Option<[u8; 6]>,
+  /// Fragment and sequence number
+  pub sequence_control: Option<u16>,
+  /// Source address of frames inside the distribution system
+  pub addr4: Option<[u8; 6]>,
+  /// QoS control of QoS data frames
+  pub qos_control: Option<u16>,
+  /// HT control
+  pub ht_control: Option<u32>,
+}
+
+impl WifiHeader {
+  /// Management subtype of association requests
+  pub const ASSOCIATION_REQUEST: u8 = 0;
+  /// Management subtype of association responses
+  pub const ASSOCIATION_RESPONSE: u8 = 1;
+  /// Management subtype of authentications
+  pub const AUTHENTICATION: u8 = 11;
+  /// Management subtype of beacons
+  pub const BEACON: u8 = 8;
+  /// Management subtype of deauthentications
+  pub const DEAUTHENTICATION: u8 = 12;
+  /// Management subtype of disassociations
+  pub const DISASSOCIATION: u8 = 10;
+  /// Management subtype of probe requests
+  pub const PROBE_REQUEST: u8 = 4;
+  /// Management subtype of probe responses
+  pub const PROBE_RESPONSE: u8 = 5;
+  /// Management subtype of reassociation requests
+  pub const REASSOCIATION_REQUEST: u8 = 2;
+  /// Management subtype of reassociation responses
+  pub const REASSOCIATION_RESPONSE: u8 = 3;
+}
+
+/// Fixed parameters of a management frame body
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WifiFixedParameters {
+  /// Beacon or probe response
+  Beacon {
+    /// TSF timer of the sender
+    timestamp: u64,
+    /// Beacon interval in time units
+    interval: u16,
+    /// Capability information
+    capability: u16,
+  },
+  /// Association or reassociation request
+  AssociationRequest {
+    /// Capability information
+    capability: u16,
+    /// Listen interval
+    listen_interval: u16,
+    /// Current AP of a reassociation
+    current_ap: Option<[u8; 6]>,
+  },
+  /// Association or reassociation response
+  AssociationResponse {
+    /// Capability information
+    capability: u16,
+    /// Status code
+    status: u16,
+    /// Association id
+    aid: u16,
+  },
+  /// Authentication
+  Authentication {
+    /// Authentication algorithm
+    algorithm: u16,
+    /// Transaction sequence number
+    sequence: u16,
+    /// Status code
+    status: u16,
+  },
+  /// Deauthentication or disassociation reason code
+  Reason(u16),
+  /// Subtype without fixed parameters
+  None,
+}
+
+/// Information element of a management frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WifiElement<Span> {
+  /// Element id
+  pub id: u8,
+  /// Element data
+  pub data: Span,
+}
+
+impl<Span> WifiElement<Span> {
+  /// Element id of the SSID
+  pub const SSID: u8 = 0;
+}
+
+/// Body of a management frame
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WifiManagement<Span> {
+  /// Fixed parameters
+  pub fixed: WifiFixedParameters,
+  /// Information elements
+  pub elements: Vec<WifiElement<Span>>,
+}
+
+impl<Span> WifiManagement<Span> {
+  /// Return the data of the SSID element
+  pub fn ssid(&self) -> Option<&Span> {
+    self
+      .elements
+      .iter()
+      .find(|element| element.id == WifiElement::<Span>::SSID)
+      .map(|element| &element.data)
+  }
+}
+
+/// 802.11 frame, the body of unprotected management frames is decoded
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WifiFrame<Span> {
+  /// MAC header
+  pub header: WifiHeader,
+  /// Management frame body
+  pub management: Option<WifiManagement<Span>>,
+}
+
+fn wifi_addr<Stream, Context>(stream: Stream) -> Parsed<[u8; 6], Stream, Context>
+where
+  (): WifiParse<Stream, Context>,
+{
+  octet.fill().parse(stream)
+}
+
+// Fields present according to the frame control
+fn when<Stream, Context, Parser>(
+  present: bool, mut parser: Parser,
+) -> impl Parse<Stream, Context, Token = Option<Parser::Token>>
+where
+  Stream: Streaming,
+  Parser: Parse<Stream, Context>,
+{
+  move |stream: Stream| -> Parsed<Option<Parser::Token>, Stream, Context> {
+    if present {
+      parser.parse(stream).map_token(Some)
+    } else {
+      Parsed::Success {
+        token: None,
+        stream,
+      }
+    }
+  }
+}
+
+/// Parse an 802.11 MAC header, the stream is left at the frame body
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn wifi_header<Stream, Context>(stream: Stream) -> Parsed<WifiHeader, Stream, Context>
+where
+  (): WifiParse<Stream, Context>,
+{
+  let Success {
+    token: (frame_control, duration, addr1),
+    stream,
+  } = (
+    u16_le.map(WifiFrameControl::new).try_map(|frame_control| {
+      if frame_control.version() == 0 {
+        Ok(frame_control)
+      } else {
+        Err(Context::new(WifiAtom::FrameVersion(
+          frame_control.version(),
+        )))
+      }
+    }),
+    u16_le,
+    wifi_addr,
+  )
+    .parse(stream)?;
+
+  let mut header = WifiHeader {
+    frame_control,
+    duration,
+    addr1,
+    addr2: None,
+    addr3: None,
+    sequence_control: None,
+    addr4: None,
+    qos_control: None,
+    ht_control: None,
+  };
+
+  let frame_type = frame_control.frame_type();
+  let subtype = frame_control.subtype();
+  // CTS and ACK only have the receiver address
+  if frame_type == WifiFrameType::CONTROL {
+    if matches!(subtype, 12 | 13) {
+      return Parsed::Success {
+        token: header,
+        stream,
+      };
+    }
+    return wifi_addr
+      .map(|addr2| WifiHeader {
+        addr2: Some(addr2),
+        ..header
+      })
+      .parse(stream);
+  }
+
+  let Success {
+    token: (addr2, addr3, sequence_control),
+    stream,
+  } = (wifi_addr, wifi_addr, u16_le).parse(stream)?;
+  header.addr2 = Some(addr2);
+  header.addr3 = Some(addr3);
+  header.sequence_control = Some(sequence_control);
+
+  let data = frame_type == WifiFrameType::DATA;
+  let qos = data && subtype & 0b1000 != 0;
+  let ht = frame_control.order() && (qos || frame_type == WifiFrameType::MANAGEMENT);
+  let Success {
+    token: (addr4, qos_control, ht_control),
+    stream,
+  } = (
+    when(
+      data && frame_control.to_ds() && frame_control.from_ds(),
+      wifi_addr,
+    ),
+    when(qos, u16_le),
+    when(ht, u32_le),
+  )
+    .parse(stream)?;
+  header.addr4 = addr4;
+  header.qos_control = qos_control;
+  header.ht_control = ht_control;
+
+  Parsed::Success {
+    token: header,
+    stream,
+  }
+}
+
+fn wifi_fixed_parameters<Stream, Context>(
+  subtype: u8,
+) -> impl Parse<Stream, Context, Token = WifiFixedParameters>
+where
+  (): WifiParse<Stream, Context>,
+{
+  move |stream: Stream| -> Parsed<WifiFixedParameters, Stream, Context> {
+    match subtype {
+      WifiHeader::BEACON | WifiHeader::PROBE_RESPONSE => (u64_le, u16_le, u16_le)
+        .map(
+          |(timestamp, interval, capability)| WifiFixedParameters::Beacon {
+            timestamp,
+            interval,
+            capability,
+          },
+        )
+        .parse(stream),
+      WifiHeader::ASSOCIATION_REQUEST | WifiHeader::REASSOCIATION_REQUEST => (
+        u16_le,
+        u16_le,
+        when(subtype == WifiHeader::REASSOCIATION_REQUEST, wifi_addr),
+      )
+        .map(
+          |(capability, listen_interval, current_ap)| WifiFixedParameters::AssociationRequest {
+            capability,
+            listen_interval,
+            current_ap,
+          },
+        )
+        .parse(stream),
+      WifiHeader::ASSOCIATION_RESPONSE | WifiHeader::REASSOCIATION_RESPONSE => {
+        (u16_le, u16_le, u16_le)
+          .map(
+            |(capability, status, aid)| WifiFixedParameters::AssociationResponse {
+              capability,
+              status,
+              aid: aid & 0x3FFF,
+            },
+          )
+          .parse(stream)
+      }
+      WifiHeader::AUTHENTICATION => (u16_le, u16_le, u16_le)
+        .map(
+          |(algorithm, sequence, status)| WifiFixedParameters::Authentication {
+            algorithm,
+            sequence,
+            status,
+          },
+        )
+        .parse(stream),
+      WifiHeader::DEAUTHENTICATION | WifiHeader::DISASSOCIATION => {
+        u16_le.map(WifiFixedParameters::Reason).parse(stream)
+      }
+      _ => Parsed::Success {
+        token: WifiFixedParameters::None,
+        stream,
+      },
+    }
+  }
+}
+
+fn wifi_element<Stream, Context>(
+  stream: Stream,
+) -> Parsed<WifiElement<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): WifiParse<Stream, Context>,
+{
+  let Success {
+    token: (id, len),
+    stream,
+  } = (octet, octet).parse(stream)?;
+
+  take(usize::from(len))
+    .map(|data| WifiElement { id, data })
+    .parse(stream)
+}
+
+/// Parse an 802.11 frame without FCS. The body of management frames, except
+/// action frames, is parsed up to the end of the stream, for other frames
+/// the stream is left at the frame body.
+#[cfg_attr(
+  feature = "tracing",
+  tracing::instrument(level = "trace", skip_all, ret(Display))
+)]
+pub fn wifi_frame<Stream, Context>(
+  stream: Stream,
+) -> Parsed<WifiFrame<<Stream as Streaming>::Span>, Stream, Context>
+where
+  (): WifiParse<Stream, Context>,
+{
+  let Success {
+    token: header,
+    stream,
+  } = wifi_header.parse(stream)?;
+
+  let frame_control = header.frame_control;
+  // action frames carry no element
+  if frame_control.frame_type() != WifiFrameType::MANAGEMENT
+    || frame_control.protected()
+    || matches!(frame_control.subtype(), 13 | 14)
+  {
+    return Parsed::Success {
+      token: WifiFrame {
+        header,
+        management: None,
+      },
+      stream,
+    };
+  }
+
+  let Success {
+    token: (fixed, elements),
+    stream,
+  } = (
+    wifi_fixed_parameters(frame_control.subtype()),
+    wifi_element.fold_bounds(.., Vec::new, Acc::acc),
+  )
+    .parse(stream)?;
+
+  Parsed::Success {
+    token: WifiFrame {
+      header,
+      management: Some(WifiManagement { fixed, elements }),
+    },
+    stream,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use binator::{
+    context::Ignore,
+    Success,
+  };
+
+  use super::{
+    RadiotapChannel,
+    WifiFixedParameters,
+    WifiFrameType,
+  };
+
+  #[test]
+  fn radiotap_wifi_frame() {
+    let radiotap = [
+      0x00, 0x00, 0x12, 0x00, 0x2E, 0x08, 0x00, 0x00, 0x10, 0x02, 0x6C, 0x09, 0xA0, 0x00, 0xC4,
+      0x00, 0x00, 0x00,
+    ];
+    let Success { token, stream } = super::radiotap_header::<_, Ignore>(&radiotap[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert!(token.has_fcs());
+    assert_eq!(token.rate, Some(2));
+    assert_eq!(
+      token.channel,
+      Some(RadiotapChannel {
+        frequency: 2412,
+        flags: 0x00A0,
+      })
+    );
+    assert_eq!(token.antenna_signal, Some(-60));
+    assert_eq!(token.antenna, Some(0));
+
+    let beacon = [
+      0x80, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00, 0x1B, 0x21, 0x0F, 0x91,
+      0x9B, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x10, 0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06,
+      0x07, 0x08, 0x64, 0x00, 0x11, 0x04, 0x00, 0x04, b't', b'e', b's', b't', 0x01, 0x01, 0x82,
+    ];
+    let Success { token, stream } = super::wifi_frame::<_, Ignore>(&beacon[..]).unwrap();
+    assert_eq!(stream, b"");
+    assert_eq!(
+      token.header.frame_control.frame_type(),
+      WifiFrameType::MANAGEMENT
+    );
+    assert_eq!(
+      token.header.addr2,
+      Some([0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B])
+    );
+    assert_eq!(token.header.sequence_control, Some(0x0010));
+    let management = token.management.unwrap();
+    assert_eq!(
+      management.fixed,
+      WifiFixedParameters::Beacon {
+        timestamp: 0x0807060504030201,
+        interval: 100,
+        capability: 0x0411,
+      }
+    );
+    assert_eq!(management.ssid(), Some(&&b"test"[..]));
+    assert_eq!(management.elements.len(), 2);
+
+    let qos_data = [
+      0x88, 0x02, 0x2C, 0x00, 0x00, 0x1B, 0x21, 0x0F, 0x91, 0x9B, 0x00, 0x23, 0x54, 0x07, 0x93,
+      0x6C, 0x00, 0x23, 0x54, 0x07, 0x93, 0x6C, 0x20, 0x00, 0x05, 0x00, 0xAA,
+    ];
+    let Success { token, stream } = super::wifi_frame::<_, Ignore>(&qos_data[..]).unwrap();
+    assert_eq!(token.header.qos_control, Some(5));
+    assert_eq!(token.header.addr4, None);
+    assert_eq!(token.management, None);
+    assert_eq!(stream, [0xAA]);
+  }
+}